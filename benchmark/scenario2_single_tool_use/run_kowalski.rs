@@ -1,12 +1,12 @@
+use async_trait::async_trait;
 use kowalski_core::agent::{Agent, BaseAgent};
 use kowalski_core::config::Config;
 use kowalski_core::error::KowalskiError;
 use kowalski_core::tools::ToolOutput;
-use tokio::time::Instant;
-use std::fs::File;
-use std::io::{BufReader, BufRead};
-use async_trait::async_trait;
 use serde_json::json;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use tokio::time::Instant;
 
 // Custom Agent trait implementation for this benchmark to include a simplified fs_tool
 pub struct BenchmarkAgent {
@@ -17,7 +17,9 @@ pub struct BenchmarkAgent {
 impl Agent for BenchmarkAgent {
     async fn new(config: Config) -> Result<Self, KowalskiError> {
         let (wm, em, sm) = kowalski_core::memory::helpers::create_memory_providers(&config).await?;
-        Ok(Self { base_agent: BaseAgent::new(config, "BenchmarkAgent", "", wm, em, sm).await? })
+        Ok(Self {
+            base_agent: BaseAgent::new(config, "BenchmarkAgent", "", wm, em, sm).await?,
+        })
     }
 
     fn start_conversation(&mut self, model: &str) -> String {
@@ -42,7 +44,9 @@ impl Agent for BenchmarkAgent {
         content: &str,
         role: Option<kowalski_core::role::Role>,
     ) -> Result<reqwest::Response, KowalskiError> {
-        self.base_agent.chat_with_history(conversation_id, content, role).await
+        self.base_agent
+            .chat_with_history(conversation_id, content, role)
+            .await
     }
 
     async fn process_stream_response(
@@ -50,11 +54,15 @@ impl Agent for BenchmarkAgent {
         conversation_id: &str,
         chunk: &[u8],
     ) -> Result<Option<kowalski_core::conversation::Message>, KowalskiError> {
-        self.base_agent.process_stream_response(conversation_id, chunk).await
+        self.base_agent
+            .process_stream_response(conversation_id, chunk)
+            .await
     }
 
     async fn add_message(&mut self, conversation_id: &str, role: &str, content: &str) {
-        self.base_agent.add_message(conversation_id, role, content).await
+        self.base_agent
+            .add_message(conversation_id, role, content)
+            .await
     }
 
     async fn execute_tool(
@@ -63,21 +71,38 @@ impl Agent for BenchmarkAgent {
         tool_input: &serde_json::Value,
     ) -> Result<ToolOutput, KowalskiError> {
         if tool_name == "fs_tool" {
-            let task = tool_input["task"].as_str().ok_or_else(|| KowalskiError::ToolExecution("Missing 'task' in fs_tool input".to_string()))?;
-            let path = tool_input["path"].as_str().ok_or_else(|| KowalskiError::ToolExecution("Missing 'path' in fs_tool input".to_string()))?;
+            let task = tool_input["task"].as_str().ok_or_else(|| {
+                KowalskiError::ToolExecution("Missing 'task' in fs_tool input".to_string())
+            })?;
+            let path = tool_input["path"].as_str().ok_or_else(|| {
+                KowalskiError::ToolExecution("Missing 'path' in fs_tool input".to_string())
+            })?;
 
             match task {
                 "get_first_lines" => {
                     let num_lines = tool_input["num_lines"].as_u64().unwrap_or(10) as usize;
-                    let file = File::open(path).map_err(|e| KowalskiError::ToolExecution(format!("Failed to open file {}: {}", path, e)))?;
+                    let file = File::open(path).map_err(|e| {
+                        KowalskiError::ToolExecution(format!("Failed to open file {}: {}", path, e))
+                    })?;
                     let reader = BufReader::new(file);
-                    let lines: Vec<String> = reader.lines().take(num_lines).filter_map(|l| l.ok()).collect();
-                    Ok(ToolOutput { result: json!({ "lines": lines.join("\n") }), metadata: None })
-                },
-                _ => Err(KowalskiError::ToolExecution(format!("Unknown fs_tool task: {}", task))),
+                    let lines: Vec<String> = reader
+                        .lines()
+                        .take(num_lines)
+                        .filter_map(|l| l.ok())
+                        .collect();
+                    Ok(ToolOutput::new(json!({ "lines": lines.join("\n") }), None)
+                        .with_source(path))
+                }
+                _ => Err(KowalskiError::ToolExecution(format!(
+                    "Unknown fs_tool task: {}",
+                    task
+                ))),
             }
         } else {
-            Err(KowalskiError::ToolExecution(format!("Unknown tool: {}", tool_name)))
+            Err(KowalskiError::ToolExecution(format!(
+                "Unknown tool: {}",
+                tool_name
+            )))
         }
     }
 
@@ -93,11 +118,15 @@ impl Agent for BenchmarkAgent {
                 parameters: json!({ "task": "get_first_lines", "path": "./example.txt", "num_lines": 10 }),
                 reasoning: Some("User asked for first 10 lines of example.txt".to_string()),
             };
-            let tool_result = self.execute_tool(&tool_call.name, &tool_call.parameters).await?;
+            let tool_result = self
+                .execute_tool(&tool_call.name, &tool_call.parameters)
+                .await?;
             Ok(tool_result.result.to_string())
         } else {
             // Fallback to base agent chat_with_tools if not a direct tool call
-            self.base_agent.chat_with_tools(conversation_id, user_input).await
+            self.base_agent
+                .chat_with_tools(conversation_id, user_input)
+                .await
         }
     }
 
@@ -123,11 +152,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let conversation_id = agent.start_conversation("llama3.2");
 
     let start_time = Instant::now();
-    let response = agent.chat_with_tools(&conversation_id, "Get the first 10 lines of example.txt").await?;
+    let response = agent
+        .chat_with_tools(&conversation_id, "Get the first 10 lines of example.txt")
+        .await?;
     let elapsed = start_time.elapsed();
 
     println!("Kowalski (FS Tool Use) - Response: {}", response);
     println!("Kowalski (FS Tool Use) - Time: {:?}", elapsed);
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -70,12 +70,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         timestamp: 1678886400, // Example timestamp
         content: content1.to_string(),
         embedding: Some(embedding1),
+        metadata: Default::default(),
     };
     let memory_unit2 = MemoryUnit {
         id: "rust_benefits".to_string(),
         timestamp: 1678886500,
         content: content2.to_string(),
         embedding: Some(embedding2),
+        metadata: Default::default(),
     };
     agent
         .semantic_memory
@@ -0,0 +1,1497 @@
+//! `kowalski-cli academic analyze <file>`: summarize a paper section by section.
+//!
+//! [`AcademicAgent::summarize_paper`] extracts the text (PDF via `pdf-extract`; `.txt` / `.md`
+//! are read as is), cleans it (re-joins hyphenated line breaks, drops page numbers), splits it at
+//! section headings, asks the model for a summary of each requested section (long sections are
+//! read in parts and the notes merged), then for an overall summary, the research questions, the
+//! key claims and the methodology, and lists the references. The [`PaperSummary`] renders as
+//! text, JSON or Markdown.
+
+use crate::error::KowalskiCliError;
+use kowalski_core::conversation::Message;
+use kowalski_core::llm::{ChatOptions, LLMProvider};
+use kowalski_core::progress::{NoProgress, Progress, ProgressReporter};
+use kowalski_core::text::chunk::chunk_by_tokens;
+use kowalski_core::utils::json::strip_markdown_code_fences;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// Canonical section names and the headings that map to them.
+const SECTION_ALIASES: &[(&str, &[&str])] = &[
+    ("abstract", &["abstract"]),
+    ("introduction", &["introduction"]),
+    ("background", &["background", "related work"]),
+    (
+        "methods",
+        &[
+            "methods",
+            "method",
+            "methodology",
+            "materials and methods",
+            "approach",
+        ],
+    ),
+    (
+        "results",
+        &["results", "experiments", "evaluation", "findings"],
+    ),
+    ("discussion", &["discussion"]),
+    ("limitations", &["limitations", "threats to validity"]),
+    ("conclusion", &["conclusion", "conclusions"]),
+    ("references", &["references", "bibliography", "works cited"]),
+];
+
+/// Name of the single section of a paper without recognizable headings.
+pub const WHOLE_PAPER: &str = "paper";
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnalysisFormat {
+    /// Plain text
+    #[default]
+    Text,
+    /// [`PaperSummary`] as one JSON object
+    Json,
+    /// Metadata header, the overview, one heading per section, then the references
+    Markdown,
+}
+
+/// Result of `academic analyze` (the `--format json` schema).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaperSummary {
+    pub source: String,
+    pub title: Option<String>,
+    pub model: String,
+    pub word_count: usize,
+    /// Overview of the whole paper (empty when no section was summarized).
+    pub summary: String,
+    /// What the paper sets out to answer.
+    pub research_questions: Vec<String>,
+    /// What the paper claims to show, one claim per item.
+    pub key_claims: Vec<String>,
+    /// How the work was done, when the model could tell.
+    pub methodology: Option<String>,
+    /// Summaries in the order the sections appear in the paper.
+    pub sections: Vec<SectionSummary>,
+    /// Requested sections the paper does not have; without a selection, the
+    /// [`STANDARD_SECTIONS`] it does not have.
+    pub missing_sections: Vec<String>,
+    /// Entries of the references section.
+    pub citations: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionSummary {
+    /// Canonical name (`abstract`, `methods`, …, or `paper`).
+    pub name: String,
+    pub word_count: usize,
+    /// Parts the section was read in; more than one when it exceeded the chunk budget.
+    pub chunks: usize,
+    pub summary: String,
+}
+
+/// A section of the cleaned paper text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub name: String,
+    pub text: String,
+}
+
+/// Maps a section name or heading (`Methodology`, `conclusions`) to its canonical name.
+pub fn canonical_section(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    SECTION_ALIASES
+        .iter()
+        .find(|(_, aliases)| aliases.contains(&name.as_str()))
+        .map_or(name, |(canonical, _)| canonical.to_string())
+}
+
+/// Text of the paper at `path`.
+pub fn extract_text(path: &Path) -> Result<String, KowalskiCliError> {
+    let is_pdf = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+    if is_pdf {
+        let bytes = std::fs::read(path)?;
+        pdf_extract::extract_text_from_mem(&bytes).map_err(|e| {
+            KowalskiCliError::Agent(format!("Failed to read PDF {}: {}", path.display(), e))
+        })
+    } else {
+        Ok(std::fs::read_to_string(path)?)
+    }
+}
+
+/// Re-joins words hyphenated across line breaks, drops page-number lines and trims lines.
+pub fn clean_text(raw: &str) -> String {
+    static HYPHENATED: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(\p{L})-[ \t]*\n[ \t]*(\p{Ll})").expect("valid regex"));
+    let text = raw.replace("\r\n", "\n").replace('\x0c', "\n");
+    let text = HYPHENATED.replace_all(&text, "$1$2");
+    text.lines()
+        .map(str::trim)
+        .filter(|line| line.is_empty() || !line.chars().all(|c| c.is_ascii_digit()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A heading line such as `2. Methods` or `Abstract: We study…`: the canonical name and any text
+/// after it on the same line.
+fn heading(line: &str) -> Option<(&'static str, &str)> {
+    static ROMAN_NUMBER: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^[IVX]+\.\s+").expect("valid regex"));
+    let unnumbered = line.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ' ');
+    let unnumbered = match ROMAN_NUMBER.find(unnumbered) {
+        Some(number) => &unnumbered[number.end()..],
+        None => unnumbered,
+    };
+    for (canonical, aliases) in SECTION_ALIASES {
+        for alias in *aliases {
+            let starts_with_alias = unnumbered
+                .get(..alias.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(alias));
+            if !starts_with_alias {
+                continue;
+            }
+            let rest = &unnumbered[alias.len()..];
+            if rest.trim().is_empty() {
+                return Some((canonical, ""));
+            }
+            if let Some(body) = rest.strip_prefix([':', '.', '—']) {
+                return Some((canonical, body.trim_start()));
+            }
+        }
+    }
+    None
+}
+
+/// Splits cleaned text at section headings. Returns the title (the first line before any
+/// heading) and the sections; text without headings is one [`WHOLE_PAPER`] section.
+pub fn split_sections(text: &str) -> (Option<String>, Vec<Section>) {
+    let mut preamble: Vec<&str> = Vec::new();
+    let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in text.lines() {
+        match heading(line) {
+            Some((name, rest)) => {
+                let mut lines = Vec::new();
+                if !rest.is_empty() {
+                    lines.push(rest);
+                }
+                sections.push((name.to_string(), lines));
+            }
+            None => match sections.last_mut() {
+                Some((_, lines)) => lines.push(line),
+                None => preamble.push(line),
+            },
+        }
+    }
+    if sections.is_empty() {
+        let text = reflow(&preamble.join("\n"));
+        return (
+            None,
+            vec![Section {
+                name: WHOLE_PAPER.to_string(),
+                text,
+            }],
+        );
+    }
+    let title = preamble
+        .iter()
+        .find(|line| !line.is_empty())
+        .map(|line| line.to_string());
+    let sections = sections
+        .into_iter()
+        .map(|(name, lines)| Section {
+            text: if name == "references" {
+                lines.join("\n")
+            } else {
+                reflow(&lines.join("\n"))
+            },
+            name,
+        })
+        .collect();
+    (title, sections)
+}
+
+/// Joins the lines of each paragraph; paragraphs stay separated by a blank line.
+fn reflow(text: &str) -> String {
+    text.split("\n\n")
+        .map(|paragraph| paragraph.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Entries of a references section: each starts with `[n]` or `n.`, or is one line when the
+/// list is unnumbered.
+pub fn extract_citations(references: &str) -> Vec<String> {
+    static ENTRY_START: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^(\[\d+\]|\d+\.)\s").expect("valid regex"));
+    let lines: Vec<&str> = references.lines().filter(|l| !l.is_empty()).collect();
+    if !lines.iter().any(|line| ENTRY_START.is_match(line)) {
+        return lines.into_iter().map(str::to_string).collect();
+    }
+    let mut entries: Vec<String> = Vec::new();
+    for line in lines {
+        match entries.last_mut() {
+            Some(entry) if !ENTRY_START.is_match(line) => {
+                entry.push(' ');
+                entry.push_str(line);
+            }
+            _ => entries.push(line.to_string()),
+        }
+    }
+    entries
+}
+
+/// Sections every paper is expected to have; without `--sections`, the ones a paper lacks are
+/// reported as missing.
+pub const STANDARD_SECTIONS: &[&str] = &[
+    "abstract",
+    "introduction",
+    "methods",
+    "results",
+    "discussion",
+    "limitations",
+];
+
+/// Words of paper text per model call (words approximate tokens); longer sections are
+/// summarized part by part and the notes merged (map-reduce).
+pub const DEFAULT_CHUNK_TOKENS: usize = 2_000;
+
+/// Words shared by consecutive parts of a long section, so no sentence is lost at a cut.
+const CHUNK_OVERLAP: usize = 50;
+
+/// Summary files kept in the cache directory; the least recently written are removed first.
+pub const MAX_CACHED_SUMMARIES: usize = 256;
+
+/// Rounds of merging a long section's notes before it is summarized from them anyway.
+const MAX_REDUCE_ROUNDS: usize = 3;
+
+/// What the summary of each section should cover.
+fn section_focus(name: &str) -> &'static str {
+    match name {
+        "abstract" => "the problem, the approach and the headline result",
+        "introduction" => "the problem, why it matters and the research questions or contributions",
+        "background" => "the prior work it builds on and the gap it addresses",
+        "methods" => "the data, the procedure and how it was evaluated",
+        "results" => "the main results, with numbers where the text gives them",
+        "discussion" => "how the authors interpret the results",
+        "limitations" => "the limitations and threats to validity the authors acknowledge",
+        "conclusion" => "the conclusions and the proposed future work",
+        _ => "the problem, the approach and the main results",
+    }
+}
+
+/// Summarizes papers with an LLM: each section with a prompt aimed at what that section should
+/// say, then the whole paper (overview, research questions, key claims, methodology) from the
+/// section summaries. Sections longer than the chunk budget are summarized part by part and
+/// merged, never cut off.
+pub struct AcademicAgent {
+    llm: Arc<dyn LLMProvider>,
+    model: String,
+    options: ChatOptions,
+    chunk_tokens: usize,
+    /// Summaries by [`AcademicAgent::cache_key`], so comparing papers does not summarize a
+    /// paper twice.
+    summaries: Mutex<HashMap<String, PaperSummary>>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl AcademicAgent {
+    pub fn new(llm: Arc<dyn LLMProvider>, model: impl Into<String>) -> Self {
+        Self {
+            llm,
+            model: model.into(),
+            options: ChatOptions::default(),
+            chunk_tokens: DEFAULT_CHUNK_TOKENS,
+            summaries: Mutex::new(HashMap::new()),
+            cache_dir: None,
+        }
+    }
+
+    pub fn with_options(mut self, options: ChatOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Words of paper text per model call (default [`DEFAULT_CHUNK_TOKENS`]).
+    pub fn with_chunk_tokens(mut self, chunk_tokens: usize) -> Self {
+        self.chunk_tokens = chunk_tokens.max(CHUNK_OVERLAP * 2);
+        self
+    }
+
+    /// Also keeps summaries as JSON files in `dir`, so later runs reuse them. A summary is
+    /// reused for the same text, model, chat options, chunk budget and sections. At most
+    /// [`MAX_CACHED_SUMMARIES`] files are kept.
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Summarizes the paper at `path`. `sections` selects what to summarize (canonical names or
+    /// aliases); empty means every section except the references.
+    pub async fn summarize_paper(
+        &self,
+        path: &Path,
+        sections: &[String],
+    ) -> Result<PaperSummary, KowalskiCliError> {
+        self.summarize_paper_with_progress(path, sections, &NoProgress)
+            .await
+    }
+
+    /// [`summarize_paper`](Self::summarize_paper), reporting each section and the overview.
+    pub async fn summarize_paper_with_progress(
+        &self,
+        path: &Path,
+        sections: &[String],
+        progress: &dyn ProgressReporter,
+    ) -> Result<PaperSummary, KowalskiCliError> {
+        let raw = extract_text(path)?;
+        self.summarize_text(&path.display().to_string(), &raw, sections, progress)
+            .await
+    }
+
+    /// Summarizes already extracted paper text; `source` names it in the summary. A cached
+    /// summary of the same text is returned without asking the model.
+    pub async fn summarize_text(
+        &self,
+        source: &str,
+        raw: &str,
+        sections: &[String],
+        progress: &dyn ProgressReporter,
+    ) -> Result<PaperSummary, KowalskiCliError> {
+        let key = self.cache_key(raw, sections);
+        if let Some(mut summary) = self.cached(&key) {
+            summary.source = source.to_string();
+            return Ok(summary);
+        }
+        let summary = self
+            .summarize_uncached(source, raw, sections, progress)
+            .await?;
+        self.store(&key, &summary);
+        Ok(summary)
+    }
+
+    /// SHA-256 of everything a summary depends on.
+    fn cache_key(&self, raw: &str, sections: &[String]) -> String {
+        let request = serde_json::json!([
+            self.model,
+            self.options.temperature,
+            self.options.max_tokens,
+            self.options.ollama,
+            self.chunk_tokens,
+            sections,
+            raw
+        ]);
+        let digest = Sha256::digest(request.to_string());
+        digest.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn cached(&self, key: &str) -> Option<PaperSummary> {
+        let mut summaries = self.summaries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(summary) = summaries.get(key) {
+            return Some(summary.clone());
+        }
+        let path = self.cache_dir.as_ref()?.join(format!("{key}.json"));
+        let summary: PaperSummary =
+            serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+        summaries.insert(key.to_string(), summary.clone());
+        Some(summary)
+    }
+
+    /// Remembers `summary`; a cache file that cannot be written only costs a later run time.
+    fn store(&self, key: &str, summary: &PaperSummary) {
+        if let Some(dir) = &self.cache_dir {
+            let written = std::fs::create_dir_all(dir).and_then(|()| {
+                let json = serde_json::to_string_pretty(summary).map_err(std::io::Error::other)?;
+                std::fs::write(dir.join(format!("{key}.json")), json)
+            });
+            if let Err(e) = written.and_then(|()| prune_cache(dir, MAX_CACHED_SUMMARIES)) {
+                log::warn!(
+                    "Could not cache the paper summary in {}: {}",
+                    dir.display(),
+                    e
+                );
+            }
+        }
+        self.summaries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.to_string(), summary.clone());
+    }
+
+    async fn summarize_uncached(
+        &self,
+        source: &str,
+        raw: &str,
+        sections: &[String],
+        progress: &dyn ProgressReporter,
+    ) -> Result<PaperSummary, KowalskiCliError> {
+        let text = clean_text(raw);
+        if text.trim().is_empty() {
+            return Err(KowalskiCliError::Agent(format!(
+                "No text found in {}",
+                source
+            )));
+        }
+        let (title, found) = split_sections(&text);
+        let requested: Vec<String> = sections.iter().map(|s| canonical_section(s)).collect();
+        let wanted = |name: &str| {
+            if requested.is_empty() {
+                name != "references"
+            } else {
+                requested.iter().any(|r| r == name)
+            }
+        };
+
+        let selected: Vec<&Section> = found.iter().filter(|s| wanted(&s.name)).collect();
+        let mut state = Progress {
+            total: Some(selected.len() + usize::from(!selected.is_empty())),
+            bytes: raw.len() as u64,
+            ..Progress::default()
+        };
+        let mut summaries = Vec::new();
+        for section in selected {
+            state.current = Some(section.name.clone());
+            progress.report(&state);
+            let (summary, chunks) = self.summarize_section(title.as_deref(), section).await?;
+            summaries.push(SectionSummary {
+                name: section.name.clone(),
+                word_count: section.text.split_whitespace().count(),
+                chunks,
+                summary,
+            });
+            state.done += 1;
+        }
+        // Without a selection, a paper is checked against the standard sections; a whole-paper
+        // fallback has no headings to check.
+        let expected: Vec<String> = if !requested.is_empty() {
+            requested.clone()
+        } else if found.iter().any(|s| s.name == WHOLE_PAPER) {
+            Vec::new()
+        } else {
+            STANDARD_SECTIONS.iter().map(|s| s.to_string()).collect()
+        };
+        let missing_sections = expected
+            .into_iter()
+            .filter(|r| !found.iter().any(|s| &s.name == r))
+            .collect();
+        let citations = found
+            .iter()
+            .find(|s| s.name == "references")
+            .map(|s| extract_citations(&s.text))
+            .unwrap_or_default();
+        let overview = if summaries.is_empty() {
+            Overview::default()
+        } else {
+            state.current = Some("overview".to_string());
+            progress.report(&state);
+            let overview = self.synthesize(title.as_deref(), &summaries).await?;
+            state.done += 1;
+            progress.report(&state);
+            overview
+        };
+
+        Ok(PaperSummary {
+            source: source.to_string(),
+            title,
+            model: self.model.clone(),
+            word_count: text.split_whitespace().count(),
+            summary: overview.summary,
+            research_questions: overview.research_questions,
+            key_claims: overview.key_claims,
+            methodology: overview.methodology,
+            sections: summaries,
+            missing_sections,
+            citations,
+        })
+    }
+
+    /// Compares papers on `dimensions` (default [`DEFAULT_DIMENSIONS`]): each paper is
+    /// summarized (or its cached summary reused), then the model is asked for every paper and
+    /// dimension with the summary and the sections that usually answer it, and quotes the
+    /// sentence it relied on.
+    pub async fn compare_papers(
+        &self,
+        paths: &[PathBuf],
+        dimensions: &[String],
+    ) -> Result<ComparisonMatrix, KowalskiCliError> {
+        self.compare_papers_with_progress(paths, dimensions, &NoProgress)
+            .await
+    }
+
+    /// [`compare_papers`](Self::compare_papers), reporting each summary and matrix cell.
+    pub async fn compare_papers_with_progress(
+        &self,
+        paths: &[PathBuf],
+        dimensions: &[String],
+        progress: &dyn ProgressReporter,
+    ) -> Result<ComparisonMatrix, KowalskiCliError> {
+        let dimensions: Vec<String> = if dimensions.is_empty() {
+            DEFAULT_DIMENSIONS.iter().map(|d| d.to_string()).collect()
+        } else {
+            dimensions.iter().map(|d| d.trim().to_lowercase()).collect()
+        };
+        let mut state = Progress {
+            total: Some(paths.len() * (dimensions.len() + 1)),
+            ..Progress::default()
+        };
+        let mut rows = Vec::new();
+        for path in paths {
+            let source = path.display().to_string();
+            state.current = Some(source.clone());
+            progress.report(&state);
+            let raw = extract_text(path)?;
+            state.bytes += raw.len() as u64;
+            let summary = self.summarize_text(&source, &raw, &[], &NoProgress).await?;
+            state.done += 1;
+            let (_, sections) = split_sections(&clean_text(&raw));
+            let mut cells = Vec::new();
+            for dimension in &dimensions {
+                state.current = Some(format!("{} – {}", source, dimension));
+                progress.report(&state);
+                cells.push(self.compare_cell(&summary, &sections, dimension).await?);
+                state.done += 1;
+            }
+            rows.push(ComparisonRow {
+                source,
+                title: summary.title,
+                cells,
+            });
+        }
+        progress.report(&state);
+        Ok(ComparisonMatrix { dimensions, rows })
+    }
+
+    /// One cell: the model's answer for `dimension`, with its quote kept only when the quote is
+    /// in this paper's text.
+    async fn compare_cell(
+        &self,
+        summary: &PaperSummary,
+        sections: &[Section],
+        dimension: &str,
+    ) -> Result<ComparisonCell, KowalskiCliError> {
+        let wanted = dimension_sections(dimension);
+        let excerpts = sections
+            .iter()
+            .filter(|s| s.name != "references")
+            .filter(|s| wanted.is_empty() || wanted.contains(&s.name.as_str()))
+            .filter_map(|s| {
+                // Sections past the budget are cut here: the summary covers the rest.
+                let first = chunk_by_tokens(&s.text, self.chunk_tokens, 0)
+                    .into_iter()
+                    .next()?;
+                Some(format!("[{}] {}", s.name, first.text.trim()))
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let subject = match &summary.title {
+            Some(title) => format!("the paper \"{}\"", title),
+            None => "this paper".to_string(),
+        };
+        let prompt = format!(
+            "Describe the {dimension} of {subject} in 1-2 sentences, from its summary and \
+             excerpts below, and copy one sentence from the excerpts word for word as evidence. \
+             Reply with only a JSON object: {{\"value\": \"the {dimension}, or 'not reported'\", \
+             \"quote\": \"the copied sentence, or null\"}}\n\nSummary:\n{}\n\nExcerpts:\n{}",
+            summary.summary,
+            if excerpts.is_empty() {
+                "(none)"
+            } else {
+                &excerpts
+            }
+        );
+        let reply = self.ask(prompt).await?;
+        let answer =
+            serde_json::from_str::<CellReply>(&json_object(&reply)).unwrap_or_else(|_| CellReply {
+                value: reply.trim().to_string(),
+                quote: None,
+            });
+        let paper_text = sections
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let quote = answer
+            .quote
+            .map(|q| q.trim().trim_matches(['"', '“', '”']).trim().to_string())
+            .filter(|q| !q.is_empty() && contains_quote(&paper_text, q));
+        Ok(ComparisonCell {
+            dimension: dimension.to_string(),
+            value: answer.value.trim().to_string(),
+            quote,
+        })
+    }
+
+    async fn ask(&self, prompt: String) -> Result<String, KowalskiCliError> {
+        let message = |role: &str, content: String| Message {
+            role: role.to_string(),
+            content,
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        };
+        let messages = [
+            message("system", SYSTEM_PROMPT.to_string()),
+            message("user", prompt),
+        ];
+        let reply = self
+            .llm
+            .chat_with_options(&self.model, &messages, &self.options)
+            .await
+            .map_err(|e| KowalskiCliError::Agent(e.to_string()))?;
+        Ok(reply.trim().to_string())
+    }
+
+    /// The summary of `section` and the number of parts it was read in.
+    async fn summarize_section(
+        &self,
+        title: Option<&str>,
+        section: &Section,
+    ) -> Result<(String, usize), KowalskiCliError> {
+        let subject = match (section.name.as_str(), title) {
+            (WHOLE_PAPER, Some(title)) => format!("the paper \"{}\"", title),
+            (WHOLE_PAPER, None) => "this paper".to_string(),
+            (name, Some(title)) => format!("the {} section of the paper \"{}\"", name, title),
+            (name, None) => format!("the {} section of this paper", name),
+        };
+        let focus = section_focus(&section.name);
+        let chunks = chunk_by_tokens(&section.text, self.chunk_tokens, CHUNK_OVERLAP);
+        if chunks.len() <= 1 {
+            let summary = self
+                .ask(format!(
+                    "Summarize {} in 2-4 sentences, covering {}.\n\n{}",
+                    subject, focus, section.text
+                ))
+                .await?;
+            return Ok((summary, 1));
+        }
+
+        // Map: notes on each part. Reduce: merge the notes, in rounds while they exceed the budget,
+        // at most MAX_REDUCE_ROUNDS times and only while merging leaves fewer notes.
+        let mut notes = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            notes.push(
+                self.ask(format!(
+                    "Take notes on part {} of {} of {}: {}. Use at most 5 short bullet points.\n\n{}",
+                    i + 1,
+                    chunks.len(),
+                    subject,
+                    focus,
+                    chunk.text.trim()
+                ))
+                .await?,
+            );
+        }
+        let mut rounds = 0;
+        loop {
+            let joined = notes.join("\n\n");
+            if joined.split_whitespace().count() <= self.chunk_tokens
+                || notes.len() == 1
+                || rounds == MAX_REDUCE_ROUNDS
+            {
+                let summary = self
+                    .ask(format!(
+                        "These are notes on consecutive parts of {}:\n\n{}\n\nSummarize {} in 2-4 \
+                         sentences, covering {}.",
+                        subject, joined, subject, focus
+                    ))
+                    .await?;
+                return Ok((summary, chunks.len()));
+            }
+            let mut merged = Vec::new();
+            for group in chunk_by_tokens(&joined, self.chunk_tokens, 0) {
+                merged.push(
+                    self.ask(format!(
+                        "Merge these notes on {} into at most 8 short bullet points about {}.\n\n{}",
+                        subject,
+                        focus,
+                        group.text.trim()
+                    ))
+                    .await?,
+                );
+            }
+            rounds += 1;
+            if merged.len() >= notes.len() {
+                // The notes stopped shrinking: another round would not fit them either.
+                rounds = MAX_REDUCE_ROUNDS;
+            }
+            notes = merged;
+        }
+    }
+
+    /// Asks for the overview of the paper from its section summaries. Without a methodology
+    /// from the model, the summary of the methods section stands in.
+    async fn synthesize(
+        &self,
+        title: Option<&str>,
+        summaries: &[SectionSummary],
+    ) -> Result<Overview, KowalskiCliError> {
+        let subject = match title {
+            Some(title) => format!("the paper \"{}\"", title),
+            None => "a paper".to_string(),
+        };
+        let sections = summaries
+            .iter()
+            .map(|s| format!("[{}] {}", s.name, s.summary))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let prompt = format!(
+            "These are section summaries of {}:\n\n{}\n\nReply with only a JSON object: \
+             {{\"summary\": \"2-3 sentence overview\", \"research_questions\": [\"each question \
+             the paper sets out to answer\"], \"key_claims\": [\"one claim the paper makes per \
+             item\"], \"methodology\": \"how the work was done, or null if not described\"}}",
+            subject, sections
+        );
+        let mut overview = Overview::parse(&self.ask(prompt).await?);
+        if overview.methodology.is_none() {
+            overview.methodology = summaries
+                .iter()
+                .find(|s| s.name == "methods")
+                .map(|s| s.summary.clone());
+        }
+        Ok(overview)
+    }
+}
+
+/// Removes the oldest summary files in `dir` until at most `keep` are left.
+fn prune_cache(dir: &Path, keep: usize) -> std::io::Result<()> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            let written = std::fs::metadata(&path)?.modified()?;
+            files.push((written, path));
+        }
+    }
+    if files.len() <= keep {
+        return Ok(());
+    }
+    files.sort();
+    for (_, path) in &files[..files.len() - keep] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+const SYSTEM_PROMPT: &str = "You summarize academic papers accurately and concisely, without \
+                             adding claims the text does not make.";
+
+/// The parts of a [`PaperSummary`] drawn from all section summaries together.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+struct Overview {
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    research_questions: Vec<String>,
+    #[serde(default)]
+    key_claims: Vec<String>,
+    #[serde(default)]
+    methodology: Option<String>,
+}
+
+impl Overview {
+    /// Reads the `{"summary", "research_questions", "key_claims", "methodology"}` object of a
+    /// reply, fenced or surrounded by prose. A reply without one becomes the summary as is.
+    fn parse(reply: &str) -> Self {
+        let mut overview =
+            serde_json::from_str::<Self>(&json_object(reply)).unwrap_or_else(|_| Self {
+                summary: reply.trim().to_string(),
+                ..Self::default()
+            });
+        overview.summary = overview.summary.trim().to_string();
+        overview.research_questions.retain(|q| !q.trim().is_empty());
+        overview.key_claims.retain(|c| !c.trim().is_empty());
+        overview.methodology = overview
+            .methodology
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty());
+        overview
+    }
+}
+
+/// The JSON object in a model reply, fenced or surrounded by prose; empty when there is none.
+fn json_object(reply: &str) -> String {
+    let text = strip_markdown_code_fences(reply);
+    match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => text[start..=end].to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Dimensions of `academic compare` without `--dimensions`.
+pub const DEFAULT_DIMENSIONS: &[&str] =
+    &["method", "dataset", "metrics", "findings", "limitations"];
+
+/// Sections that usually answer `dimension`; empty (all sections) for dimensions of the user's own.
+fn dimension_sections(dimension: &str) -> &'static [&'static str] {
+    match dimension {
+        "method" | "methods" | "methodology" | "approach" => &["abstract", "methods"],
+        "dataset" | "datasets" | "data" => &["methods", "results"],
+        "metrics" | "evaluation" => &["methods", "results"],
+        "findings" | "results" => &["abstract", "results", "discussion", "conclusion"],
+        "limitations" => &["limitations", "discussion", "conclusion"],
+        _ => &[],
+    }
+}
+
+/// Whether `quote` occurs in `text`, ignoring case and whitespace.
+fn contains_quote(text: &str, quote: &str) -> bool {
+    let normalize = |s: &str| {
+        s.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    normalize(text).contains(&normalize(quote))
+}
+
+#[derive(Debug, Deserialize)]
+struct CellReply {
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    quote: Option<String>,
+}
+
+/// Result of `academic compare` (the `--format json` schema): one row per paper, one cell per
+/// dimension in each row.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComparisonMatrix {
+    pub dimensions: Vec<String>,
+    pub rows: Vec<ComparisonRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComparisonRow {
+    pub source: String,
+    pub title: Option<String>,
+    /// In the order of [`ComparisonMatrix::dimensions`].
+    pub cells: Vec<ComparisonCell>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComparisonCell {
+    pub dimension: String,
+    pub value: String,
+    /// A sentence of this paper supporting the value; `None` when the model quoted nothing or
+    /// something the paper does not say.
+    pub quote: Option<String>,
+}
+
+impl ComparisonRow {
+    fn label(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.source)
+    }
+}
+
+impl ComparisonMatrix {
+    pub fn render(&self, format: AnalysisFormat) -> Result<String, KowalskiCliError> {
+        Ok(match format {
+            AnalysisFormat::Text => self.to_text(),
+            AnalysisFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| KowalskiCliError::Serialization(e.to_string()))?,
+            AnalysisFormat::Markdown => self.to_markdown(),
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        for row in &self.rows {
+            out.push_str(&format!("{}\n", row.label()));
+            for cell in &row.cells {
+                out.push_str(&format!(
+                    "  {}: {}\n",
+                    title_case(&cell.dimension),
+                    cell.value
+                ));
+                if let Some(quote) = &cell.quote {
+                    out.push_str(&format!("    \"{}\"\n", quote));
+                }
+            }
+            out.push('\n');
+        }
+        out.trim_end().to_string()
+    }
+
+    /// A table of papers × dimensions, then the quotes per paper.
+    pub fn to_markdown(&self) -> String {
+        let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+        let mut out = String::from("| Paper |");
+        for dimension in &self.dimensions {
+            out.push_str(&format!(" {} |", title_case(dimension)));
+        }
+        out.push_str("\n|---|");
+        out.push_str(&"---|".repeat(self.dimensions.len()));
+        out.push('\n');
+        for row in &self.rows {
+            out.push_str(&format!("| {} |", cell(row.label())));
+            for c in &row.cells {
+                out.push_str(&format!(" {} |", cell(&c.value)));
+            }
+            out.push('\n');
+        }
+        for row in &self.rows {
+            let quotes: Vec<&ComparisonCell> =
+                row.cells.iter().filter(|c| c.quote.is_some()).collect();
+            if quotes.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n### {}\n\n", row.label()));
+            for c in quotes {
+                out.push_str(&format!(
+                    "- {}: > {}\n",
+                    title_case(&c.dimension),
+                    c.quote.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+        out.trim_end().to_string()
+    }
+}
+
+fn title_case(name: &str) -> String {
+    let mut name = name.to_string();
+    if let Some(first) = name.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    name
+}
+
+impl PaperSummary {
+    pub fn render(&self, format: AnalysisFormat) -> Result<String, KowalskiCliError> {
+        Ok(match format {
+            AnalysisFormat::Text => self.to_text(),
+            AnalysisFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| KowalskiCliError::Serialization(e.to_string()))?,
+            AnalysisFormat::Markdown => self.to_markdown(),
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        if let Some(title) = &self.title {
+            out.push_str(&format!("{}\n\n", title));
+        }
+        if !self.summary.is_empty() {
+            out.push_str(&format!("Summary:\n{}\n\n", self.summary));
+        }
+        for (heading, items) in [
+            ("Research questions", &self.research_questions),
+            ("Key claims", &self.key_claims),
+        ] {
+            if !items.is_empty() {
+                out.push_str(&format!("{}:\n", heading));
+                for item in items {
+                    out.push_str(&format!("- {}\n", item));
+                }
+                out.push('\n');
+            }
+        }
+        if let Some(methodology) = &self.methodology {
+            out.push_str(&format!("Methodology:\n{}\n\n", methodology));
+        }
+        for section in &self.sections {
+            out.push_str(&format!(
+                "{}:\n{}\n\n",
+                title_case(&section.name),
+                section.summary
+            ));
+        }
+        if !self.missing_sections.is_empty() {
+            out.push_str(&format!(
+                "Not found: {}\n\n",
+                self.missing_sections.join(", ")
+            ));
+        }
+        if !self.citations.is_empty() {
+            out.push_str(&format!("References ({}):\n", self.citations.len()));
+            for citation in &self.citations {
+                out.push_str(&format!("- {}\n", citation));
+            }
+        }
+        out.trim_end().to_string()
+    }
+
+    fn to_markdown(&self) -> String {
+        let title = self.title.as_deref().unwrap_or(&self.source);
+        let mut out = format!(
+            "# {}\n\n- Source: {}\n- Model: {}\n- Words: {}\n",
+            title, self.source, self.model, self.word_count
+        );
+        if !self.missing_sections.is_empty() {
+            out.push_str(&format!(
+                "- Not found: {}\n",
+                self.missing_sections.join(", ")
+            ));
+        }
+        if !self.summary.is_empty() {
+            out.push_str(&format!("\n## Summary\n\n{}\n", self.summary));
+        }
+        for (heading, items) in [
+            ("Research questions", &self.research_questions),
+            ("Key claims", &self.key_claims),
+        ] {
+            if !items.is_empty() {
+                out.push_str(&format!("\n## {}\n\n", heading));
+                for item in items {
+                    out.push_str(&format!("- {}\n", item));
+                }
+            }
+        }
+        if let Some(methodology) = &self.methodology {
+            out.push_str(&format!("\n## Methodology\n\n{}\n", methodology));
+        }
+        for section in &self.sections {
+            out.push_str(&format!(
+                "\n## {}\n\n{}\n",
+                title_case(&section.name),
+                section.summary
+            ));
+        }
+        if !self.citations.is_empty() {
+            out.push_str("\n## References\n\n");
+            for (i, citation) in self.citations.iter().enumerate() {
+                out.push_str(&format!("{}. {}\n", i + 1, citation));
+            }
+        }
+        out.trim_end().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kowalski_core::testing::{MockBackend, MockReply};
+
+    const PAPER: &str = "Sparse Attention for Tiny Models\n\nAbstract: We show that sparse atten-\ntion helps.\n\n1. Introduction\nSmall models matter.\n3\n2 Methodology\nWe prune heads.\n\nThen we retrain.\nReferences\n[1] A. Author. Attention. 2017.\n[2] B. Author. Pruning\nheads. 2019.\n";
+
+    #[test]
+    fn papers_split_into_canonical_sections() {
+        let (title, sections) = split_sections(&clean_text(PAPER));
+        assert_eq!(title.as_deref(), Some("Sparse Attention for Tiny Models"));
+        let names: Vec<&str> = sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["abstract", "introduction", "methods", "references"]);
+        assert_eq!(sections[0].text, "We show that sparse attention helps.");
+        // The page number "3" is gone; paragraphs survive.
+        assert_eq!(sections[1].text, "Small models matter.");
+        assert_eq!(sections[2].text, "We prune heads.\n\nThen we retrain.");
+        assert_eq!(
+            extract_citations(&sections[3].text),
+            [
+                "[1] A. Author. Attention. 2017.",
+                "[2] B. Author. Pruning heads. 2019."
+            ]
+        );
+    }
+
+    #[test]
+    fn text_without_headings_is_one_section() {
+        let (title, sections) = split_sections("Just some notes\nabout a paper.");
+        assert_eq!(title, None);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].name, WHOLE_PAPER);
+        assert_eq!(sections[0].text, "Just some notes about a paper.");
+    }
+
+    fn report() -> PaperSummary {
+        PaperSummary {
+            source: "paper.pdf".to_string(),
+            title: Some("Sparse Attention for Tiny Models".to_string()),
+            model: "tiny-model".to_string(),
+            word_count: 42,
+            summary: "Pruning heads keeps accuracy.".to_string(),
+            research_questions: vec!["Do tiny models need every head?".to_string()],
+            key_claims: vec!["Half the heads suffice.".to_string()],
+            methodology: Some("Heads are pruned, then retrained.".to_string()),
+            sections: vec![SectionSummary {
+                name: "abstract".to_string(),
+                word_count: 6,
+                chunks: 1,
+                summary: "Sparse attention helps.".to_string(),
+            }],
+            missing_sections: vec!["discussion".to_string()],
+            citations: vec!["[1] A. Author. Attention. 2017.".to_string()],
+        }
+    }
+
+    #[test]
+    fn reports_render_in_every_format() {
+        let report = report();
+        assert_eq!(
+            report.render(AnalysisFormat::Text).unwrap(),
+            "Sparse Attention for Tiny Models\n\n\
+             Summary:\nPruning heads keeps accuracy.\n\n\
+             Research questions:\n- Do tiny models need every head?\n\n\
+             Key claims:\n- Half the heads suffice.\n\n\
+             Methodology:\nHeads are pruned, then retrained.\n\n\
+             Abstract:\nSparse attention helps.\n\n\
+             Not found: discussion\n\n\
+             References (1):\n- [1] A. Author. Attention. 2017."
+        );
+        assert_eq!(
+            report.render(AnalysisFormat::Markdown).unwrap(),
+            "# Sparse Attention for Tiny Models\n\n\
+             - Source: paper.pdf\n- Model: tiny-model\n- Words: 42\n- Not found: discussion\n\n\
+             ## Summary\n\nPruning heads keeps accuracy.\n\n\
+             ## Research questions\n\n- Do tiny models need every head?\n\n\
+             ## Key claims\n\n- Half the heads suffice.\n\n\
+             ## Methodology\n\nHeads are pruned, then retrained.\n\n\
+             ## Abstract\n\nSparse attention helps.\n\n\
+             ## References\n\n1. [1] A. Author. Attention. 2017."
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&report.render(AnalysisFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["summary"], "Pruning heads keeps accuracy.");
+        assert_eq!(
+            json["key_claims"],
+            serde_json::json!(["Half the heads suffice."])
+        );
+        assert_eq!(
+            json["research_questions"],
+            serde_json::json!(["Do tiny models need every head?"])
+        );
+        assert_eq!(json["methodology"], "Heads are pruned, then retrained.");
+        assert_eq!(json["sections"][0]["name"], "abstract");
+        assert_eq!(json["citations"][0], "[1] A. Author. Attention. 2017.");
+
+        // Without an overview only the sections are shown.
+        let bare = PaperSummary {
+            summary: String::new(),
+            research_questions: Vec::new(),
+            key_claims: Vec::new(),
+            methodology: None,
+            ..report
+        };
+        let text = bare.render(AnalysisFormat::Text).unwrap();
+        assert!(
+            text.starts_with("Sparse Attention for Tiny Models\n\nAbstract:"),
+            "{text}"
+        );
+        let markdown = bare.render(AnalysisFormat::Markdown).unwrap();
+        assert!(!markdown.contains("## Summary"), "{markdown}");
+    }
+
+    #[test]
+    fn overviews_are_read_from_fenced_or_plain_replies() {
+        let fenced = "Here you go:\n```json\n{\"summary\": \" Heads can go. \", \"research_questions\": [\"q\"], \"key_claims\": [\"a\", \" \"], \"methodology\": null}\n```";
+        assert_eq!(
+            Overview::parse(fenced),
+            Overview {
+                summary: "Heads can go.".to_string(),
+                research_questions: vec!["q".to_string()],
+                key_claims: vec!["a".to_string()],
+                methodology: None,
+            }
+        );
+        assert_eq!(
+            Overview::parse(" Just prose. "),
+            Overview {
+                summary: "Just prose.".to_string(),
+                ..Overview::default()
+            }
+        );
+    }
+
+    #[test]
+    fn section_aliases_are_canonical() {
+        assert_eq!(canonical_section("Methodology"), "methods");
+        assert_eq!(canonical_section(" conclusions "), "conclusion");
+        assert_eq!(canonical_section("appendix"), "appendix");
+        assert_eq!(canonical_section("Threats to validity"), "limitations");
+        assert_eq!(heading("IV. Limitations"), Some(("limitations", "")));
+    }
+
+    /// Replies by kind of prompt. With `wordy_notes`, notes and merged notes are 200 words
+    /// each, so merging never shrinks them.
+    fn scripted_llm(wordy_notes: bool) -> Arc<MockBackend> {
+        let backend = MockBackend::script().otherwise(move |request| {
+            let prompt = request.last_message();
+            if let Some(rest) = prompt.strip_prefix("Describe the ") {
+                // Quotes the first excerpt sentence, except for datasets, where it makes one up.
+                let dimension = &rest[..rest.find(" of ").unwrap()];
+                let excerpts = &prompt[prompt.find("Excerpts:\n[").unwrap()..];
+                let first = &excerpts[excerpts.find("] ").unwrap() + 2..];
+                let quote = if dimension == "dataset" {
+                    "We use ImageNet."
+                } else {
+                    &first[..=first.find('.').unwrap()]
+                };
+                return MockReply::text(
+                    serde_json::json!({"value": format!("The {dimension}."), "quote": quote})
+                        .to_string(),
+                );
+            }
+            MockReply::text(if prompt.contains("Reply with only a JSON object") {
+                r#"{"summary": "Pruning works.", "research_questions": ["Can heads be pruned?"], "key_claims": ["Half the heads suffice."], "methodology": null}"#.to_string()
+            } else if wordy_notes
+                && (prompt.starts_with("Take notes on part") || prompt.starts_with("Merge these"))
+            {
+                format!("- {}", vec!["note"; 200].join(" "))
+            } else if prompt.starts_with("Take notes on part") {
+                "- a note".to_string()
+            } else {
+                "A summary.".to_string()
+            })
+        });
+        Arc::new(backend.build())
+    }
+
+    /// The prompt of every request, in order.
+    fn prompts_of(llm: &MockBackend) -> Vec<String> {
+        llm.requests()
+            .iter()
+            .map(|r| r.last_message().to_string())
+            .collect()
+    }
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[tokio::test]
+    async fn papers_are_summarized_section_by_section() {
+        let llm = scripted_llm(false);
+        let agent = AcademicAgent::new(llm.clone(), "tiny-model");
+        let summary = agent
+            .summarize_text(
+                "paper_full.txt",
+                &fixture("paper_full.txt"),
+                &[],
+                &NoProgress,
+            )
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = summary.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "abstract",
+                "introduction",
+                "methods",
+                "results",
+                "discussion",
+                "limitations"
+            ]
+        );
+        assert!(summary.missing_sections.is_empty());
+        assert_eq!(summary.summary, "Pruning works.");
+        assert_eq!(summary.research_questions, ["Can heads be pruned?"]);
+        assert_eq!(summary.key_claims, ["Half the heads suffice."]);
+        // The model gave no methodology: the methods summary stands in.
+        assert_eq!(summary.methodology.as_deref(), Some("A summary."));
+        assert_eq!(summary.citations.len(), 2);
+
+        let prompts = prompts_of(&llm);
+        assert_eq!(prompts.len(), 7);
+        assert!(prompts[2].contains("methods section"), "{}", prompts[2]);
+        assert!(
+            prompts[2].contains("the data, the procedure"),
+            "{}",
+            prompts[2]
+        );
+        assert!(prompts[5].contains("threats to validity"), "{}", prompts[5]);
+        assert!(
+            prompts[6].contains("[limitations] A summary."),
+            "{}",
+            prompts[6]
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_standard_sections_are_reported() {
+        let llm = scripted_llm(false);
+        let agent = AcademicAgent::new(llm.clone(), "tiny-model");
+        let summary = agent
+            .summarize_text(
+                "paper_no_methods.txt",
+                &fixture("paper_no_methods.txt"),
+                &[],
+                &NoProgress,
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.missing_sections, ["methods", "limitations"]);
+        assert!(summary.sections.iter().all(|s| s.name != "methods"));
+        assert_eq!(summary.methodology, None);
+        assert!(
+            prompts_of(&llm)
+                .iter()
+                .all(|p| !p.contains("methods section"))
+        );
+    }
+
+    #[tokio::test]
+    async fn papers_are_compared_with_quotes_from_each_paper() {
+        let cache = std::env::temp_dir().join(format!("kowalski-papers-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache);
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let papers = [
+            fixtures.join("paper_full.txt"),
+            fixtures.join("paper_no_methods.txt"),
+        ];
+        let dimensions = ["method".to_string(), "dataset".to_string()];
+        let llm = scripted_llm(false);
+        let agent = AcademicAgent::new(llm.clone(), "tiny-model").with_cache_dir(&cache);
+
+        agent.summarize_paper(&papers[0], &[]).await.unwrap();
+        assert_eq!(prompts_of(&llm).len(), 7);
+        let matrix = agent.compare_papers(&papers, &dimensions).await.unwrap();
+        // The first paper's summary is reused: 6 calls summarize the second, 4 fill the cells.
+        assert_eq!(prompts_of(&llm).len(), 7 + 6 + 4);
+
+        assert_eq!(matrix.dimensions, dimensions);
+        assert_eq!(matrix.rows.len(), 2);
+        for row in &matrix.rows {
+            let cells: Vec<&str> = row.cells.iter().map(|c| c.dimension.as_str()).collect();
+            assert_eq!(cells, ["method", "dataset"]);
+            assert_eq!(row.cells[0].value, "The method.");
+            // The made-up quote is in neither paper.
+            assert_eq!(row.cells[1].quote, None);
+        }
+        assert_eq!(
+            matrix.rows[0].cells[0].quote.as_deref(),
+            Some("We ask whether tiny language models need all of their attention heads.")
+        );
+        assert_eq!(
+            matrix.rows[1].cells[0].quote.as_deref(),
+            Some("Small models deserve more research attention than they get.")
+        );
+        let prompts = prompts_of(&llm);
+        // Each cell sees only its own paper's text.
+        let method_prompt = &prompts[7 + 2 + 6];
+        assert!(
+            method_prompt.starts_with(
+                "Describe the method of the paper \"A Position Paper on Small Models\""
+            ),
+            "{method_prompt}"
+        );
+        assert!(method_prompt.contains("[abstract] Small models deserve"));
+        assert!(!method_prompt.contains("attention heads"));
+
+        let markdown = matrix.to_markdown();
+        assert!(
+            markdown.starts_with(
+                "| Paper | Method | Dataset |\n|---|---|---|\n\
+                 | Pruning Attention Heads in Tiny Language Models | The method. | The dataset. |\n\
+                 | A Position Paper on Small Models | The method. | The dataset. |\n"
+            ),
+            "{markdown}"
+        );
+        assert!(
+            markdown.contains("### A Position Paper on Small Models\n\n- Method: > Small models")
+        );
+
+        // A new agent finds both summaries on disk.
+        let llm = scripted_llm(false);
+        let agent = AcademicAgent::new(llm.clone(), "tiny-model").with_cache_dir(&cache);
+        let again = agent.compare_papers(&papers, &dimensions).await.unwrap();
+        assert_eq!(prompts_of(&llm).len(), 4);
+        assert_eq!(again, matrix);
+        std::fs::remove_dir_all(cache).unwrap();
+    }
+
+    #[test]
+    fn summaries_are_cached_per_chat_options_in_a_bounded_directory() {
+        let llm = scripted_llm(false);
+        let cool = AcademicAgent::new(llm.clone(), "tiny-model").with_options(ChatOptions {
+            temperature: Some(0.1),
+            ..Default::default()
+        });
+        let warm = AcademicAgent::new(llm, "tiny-model").with_options(ChatOptions {
+            temperature: Some(0.9),
+            ..Default::default()
+        });
+        assert_ne!(cool.cache_key(PAPER, &[]), warm.cache_key(PAPER, &[]));
+        assert_eq!(cool.cache_key(PAPER, &[]), cool.cache_key(PAPER, &[]));
+
+        let dir = std::env::temp_dir().join(format!("kowalski-prune-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["a", "b", "c"] {
+            std::fs::write(dir.join(format!("{name}.json")), "{}").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        std::fs::write(dir.join("notes.txt"), "kept").unwrap();
+        prune_cache(&dir, 2).unwrap();
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["b.json", "c.json", "notes.txt"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn long_sections_are_read_in_parts_and_merged() {
+        let llm = scripted_llm(false);
+        let agent = AcademicAgent::new(llm.clone(), "tiny-model").with_chunk_tokens(125);
+        let results = (1..=250)
+            .map(|i| format!("w{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let paper = format!(
+            "Title
+
+Results
+{results}
+"
+        );
+        let summary = agent
+            .summarize_text("long.txt", &paper, &["results".to_string()], &NoProgress)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.sections[0].chunks, 3);
+        assert_eq!(summary.sections[0].summary, "A summary.");
+        let prompts = prompts_of(&llm);
+        assert_eq!(prompts.len(), 5);
+        assert!(
+            prompts[0].starts_with("Take notes on part 1 of 3"),
+            "{}",
+            prompts[0]
+        );
+        assert!(prompts[0].contains("w1 ") && !prompts[0].contains("w150"));
+        // Nothing is cut off: the last words reach the model.
+        assert!(prompts[2].contains("w250"), "{}", prompts[2]);
+        assert!(prompts[3].starts_with("These are notes on consecutive parts"));
+        assert_eq!(prompts[3].matches("- a note").count(), 3);
+    }
+
+    #[tokio::test]
+    async fn notes_that_stop_shrinking_are_summarized_as_they_are() {
+        let llm = scripted_llm(true);
+        let agent = AcademicAgent::new(llm.clone(), "tiny-model").with_chunk_tokens(125);
+        let results = (1..=250)
+            .map(|i| format!("w{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let paper = format!("Title\n\nResults\n{results}\n");
+        let summary = agent
+            .summarize_text("long.txt", &paper, &["results".to_string()], &NoProgress)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.sections[0].summary, "A summary.");
+        let prompts = prompts_of(&llm);
+        // 3 parts, one round merging 600 words of notes in 5 groups (more notes than before),
+        // then the section summary instead of another round, and the paper overview.
+        let merges = prompts
+            .iter()
+            .filter(|p| p.starts_with("Merge these"))
+            .count();
+        assert_eq!(merges, 5);
+        assert_eq!(prompts.len(), 3 + 5 + 1 + 1);
+        assert!(prompts[8].starts_with("These are notes on consecutive parts"));
+    }
+}
@@ -0,0 +1,447 @@
+//! Agents for `create` / `chat` / `agents` / `delete`: definitions live in an [`AgentStore`], and
+//! agents are built from them the first time they are used in a process.
+
+use crate::agent_store::{AgentDefinition, AgentStore};
+use futures::future::BoxFuture;
+use kowalski_core::agent::Agent;
+use kowalski_core::config::Config;
+use kowalski_core::error::KowalskiError;
+use kowalski_core::memory::profile::{DEFAULT_PROFILE, ProfileStore};
+use kowalski_core::template::agent::TemplateAgent;
+use kowalski_core::tools::{
+    CargoGraphTool, CsvTool, DatasetProfiler, ImageTool, PatchTool, RepoMapper,
+    SchemaInferenceTool, SqlTool, StatsTool,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::io::IsTerminal;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub type BoxedAgent = Box<dyn Agent + Send + Sync>;
+
+/// Builds an agent from its saved definition and resolved config.
+pub type AgentFactory = Arc<
+    dyn Fn(AgentDefinition, Config) -> BoxFuture<'static, Result<BoxedAgent, KowalskiError>>
+        + Send
+        + Sync,
+>;
+
+/// `chat --prompt/--temperature/--model`: settings for one session that leave the saved agent
+/// unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct SessionOverrides {
+    pub system_prompt: Option<String>,
+    pub temperature: Option<f32>,
+    pub model: Option<String>,
+}
+
+impl SessionOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.system_prompt.is_none() && self.temperature.is_none() && self.model.is_none()
+    }
+
+    fn apply(&self, definition: &mut AgentDefinition) {
+        if let Some(prompt) = &self.system_prompt {
+            definition.system_prompt = Some(prompt.clone());
+        }
+        if let Some(temperature) = self.temperature {
+            definition.temperature = Some(temperature);
+        }
+        if let Some(model) = &self.model {
+            definition.model = Some(model.clone());
+        }
+    }
+}
+
+pub struct AgentManager {
+    store: AgentStore,
+    factory: AgentFactory,
+    agents: Arc<RwLock<HashMap<String, BoxedAgent>>>,
+    /// Definition (with any session overrides) and resolved config of each loaded agent.
+    loaded: Arc<RwLock<HashMap<String, (AgentDefinition, Config)>>>,
+}
+
+impl AgentManager {
+    /// Manager over `store` that builds [`TemplateAgent`]s.
+    pub fn new(store: AgentStore) -> Self {
+        Self {
+            store,
+            factory: Arc::new(|definition, config| {
+                Box::pin(build_template_agent(definition, config))
+            }),
+            agents: Arc::new(RwLock::new(HashMap::new())),
+            loaded: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Replaces how agents are built (tests, embedders with their own LLM provider).
+    pub fn with_factory(mut self, factory: AgentFactory) -> Self {
+        self.factory = factory;
+        self
+    }
+
+    pub fn store(&self) -> &AgentStore {
+        &self.store
+    }
+
+    pub async fn create_agent_from_config(
+        &self,
+        config_path: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        use crate::config::AgentConfig;
+        use std::path::Path;
+
+        let agent_config = AgentConfig::load_from_file(Path::new(config_path))
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+
+        println!(
+            "Loading agent '{}' of type '{}'...",
+            agent_config.name, agent_config.agent_type
+        );
+
+        let mut definition = AgentDefinition::new(&agent_config.agent_type);
+        definition.system_prompt = agent_config.system_prompt;
+        definition.temperature = agent_config.temperature;
+        definition.model = agent_config.model;
+        if let Some(llm) = agent_config.llm {
+            let mut table = toml::Table::new();
+            table.insert("provider".into(), llm.provider.into());
+            table.insert("model".into(), llm.model.into());
+            if let Some(api_key) = llm.api_key {
+                table.insert("api_key".into(), api_key.into());
+            }
+            definition.config.insert("llm".into(), table.into());
+        }
+
+        // If tools are specified, we might need a way to register them after creation
+        // but for now create_agent uses default tools for each type.
+        // In the future, we'll use tool_manager directly.
+
+        self.create_agent(agent_config.name.clone(), definition)
+            .await?;
+        Ok(agent_config.name)
+    }
+
+    /// Builds the agent and saves its definition, replacing any agent with the same name.
+    pub async fn create_agent(
+        &self,
+        name: String,
+        definition: AgentDefinition,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let config = definition.resolve_config()?;
+        let agent = (self.factory)(definition.clone(), config.clone()).await?;
+        self.store.save(&name, definition.clone())?;
+        self.agents.write().await.insert(name.clone(), agent);
+        self.loaded.write().await.insert(name, (definition, config));
+        Ok(())
+    }
+
+    /// The agent map, with `name` in it (rebuilt from the store if needed), or `None` when no
+    /// agent of that name was created.
+    pub async fn get_agent_mut(
+        &self,
+        name: &str,
+    ) -> Result<
+        Option<tokio::sync::RwLockWriteGuard<'_, HashMap<String, BoxedAgent>>>,
+        Box<dyn std::error::Error>,
+    > {
+        self.get_agent_for_session(name, &SessionOverrides::default())
+            .await
+    }
+
+    /// Like [`get_agent_mut`](Self::get_agent_mut), but with `overrides` applied: the agent is
+    /// rebuilt from its saved definition for this process only.
+    pub async fn get_agent_for_session(
+        &self,
+        name: &str,
+        overrides: &SessionOverrides,
+    ) -> Result<
+        Option<tokio::sync::RwLockWriteGuard<'_, HashMap<String, BoxedAgent>>>,
+        Box<dyn std::error::Error>,
+    > {
+        let mut guard = self.agents.write().await;
+        if guard.contains_key(name) && overrides.is_empty() {
+            return Ok(Some(guard));
+        }
+        let Some(mut definition) = self.store.get(name)? else {
+            return Ok(None);
+        };
+        overrides.apply(&mut definition);
+        let config = definition.resolve_config()?;
+        let agent = (self.factory)(definition.clone(), config.clone()).await?;
+        guard.insert(name.to_string(), agent);
+        self.loaded
+            .write()
+            .await
+            .insert(name.to_string(), (definition, config));
+        Ok(Some(guard))
+    }
+
+    /// A standalone agent for one-off use: the saved agent `name`, or else a new agent of that
+    /// type when `name` is one of `agent_types`. Nothing is stored or cached.
+    pub async fn build_agent(
+        &self,
+        name: &str,
+        agent_types: &[&str],
+        overrides: &SessionOverrides,
+    ) -> Result<(BoxedAgent, Config), Box<dyn std::error::Error>> {
+        let definition = self.definition(name, agent_types, overrides)?;
+        let config = definition.resolve_config()?;
+        let agent = (self.factory)(definition, config.clone()).await?;
+        Ok((agent, config))
+    }
+
+    /// Settings [`Self::build_agent`] would use, for commands that only need the model.
+    pub fn resolve_config(
+        &self,
+        name: &str,
+        agent_types: &[&str],
+        overrides: &SessionOverrides,
+    ) -> Result<Config, Box<dyn std::error::Error>> {
+        Ok(self
+            .definition(name, agent_types, overrides)?
+            .resolve_config()?)
+    }
+
+    fn definition(
+        &self,
+        name: &str,
+        agent_types: &[&str],
+        overrides: &SessionOverrides,
+    ) -> Result<AgentDefinition, Box<dyn std::error::Error>> {
+        let mut definition = match self.store.get(name)? {
+            Some(definition) => definition,
+            None if agent_types.contains(&name) => AgentDefinition::new(name),
+            None => return Err(format!("Agent '{}' not found", name).into()),
+        };
+        overrides.apply(&mut definition);
+        Ok(definition)
+    }
+
+    pub async fn get_config(&self, name: &str) -> Option<Config> {
+        self.loaded.read().await.get(name).map(|(_, c)| c.clone())
+    }
+
+    /// Settings of the loaded agent `name`, including session overrides.
+    pub async fn get_definition(&self, name: &str) -> Option<AgentDefinition> {
+        self.loaded.read().await.get(name).map(|(d, _)| d.clone())
+    }
+
+    /// Saved agents by name.
+    pub fn saved_agents(
+        &self,
+    ) -> Result<BTreeMap<String, AgentDefinition>, Box<dyn std::error::Error>> {
+        Ok(self.store.load()?)
+    }
+
+    pub async fn list_agents(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let agents = self.saved_agents()?;
+        if agents.is_empty() {
+            println!("No agents yet. Create one with: create <type> --name <name>");
+            return Ok(());
+        }
+        match self.store.path() {
+            Some(path) => println!("Agents ({}):", path.display()),
+            None => println!("Agents:"),
+        }
+        for (name, definition) in agents {
+            match definition.model {
+                Some(model) => println!("- {} ({}, {})", name, definition.agent_type, model),
+                None => println!("- {} ({})", name, definition.agent_type),
+            }
+        }
+        Ok(())
+    }
+
+    /// Forgets `name` in this process and in the store; `false` if it did not exist.
+    pub async fn delete_agent(&self, name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let loaded = self.agents.write().await.remove(name).is_some();
+        self.loaded.write().await.remove(name);
+        Ok(self.store.remove(name)? || loaded)
+    }
+}
+
+async fn build_template_agent(
+    definition: AgentDefinition,
+    config: Config,
+) -> Result<BoxedAgent, KowalskiError> {
+    #[cfg(feature = "charts")]
+    let charts = config.charts.clone();
+    let images = ImageTool::from_config(&config);
+    // Facts from `/remember` are shared by every agent in this memory store.
+    let profile = ProfileStore::open(&config.memory, DEFAULT_PROFILE).await?;
+    let mut agent = TemplateAgent::new(config).await?;
+    agent.base_mut().set_profile(Arc::new(profile));
+    let prompt = definition.system_prompt.unwrap_or_else(|| {
+        format!(
+            "Starting generic agent (was requested type: {})",
+            definition.agent_type
+        )
+    });
+    agent.base_mut().set_system_prompt(&prompt);
+    // Lets the model look up earlier conversations ("what did we say about X last week").
+    let history = agent.base().history_search_tool();
+    agent.register_tool(Box::new(history)).await?;
+    // Screenshots and diagrams, described by the `[vision]` model.
+    agent.register_tool(Box::new(images)).await?;
+    if definition.agent_type == "data" {
+        agent.register_tool(Box::new(CsvTool::new())).await?;
+        agent.register_tool(Box::new(StatsTool::new())).await?;
+        agent
+            .register_tool(Box::new(SqlTool::new().with_root(".")))
+            .await?;
+        agent
+            .register_tool(Box::new(SchemaInferenceTool::new().with_root(".")))
+            .await?;
+        // Files named in the conversation are profiled once and shown to the model.
+        agent
+            .base_mut()
+            .add_middleware(Box::new(DatasetProfiler::new()));
+        #[cfg(feature = "charts")]
+        agent
+            .register_tool(Box::new(kowalski_core::tools::ChartTool::new(charts)))
+            .await?;
+    }
+    if definition.agent_type == "code" {
+        // Project directories named in the conversation are mapped (files and their symbols);
+        // `repo_map` shows more of a subtree. Both share one outline cache.
+        let repo_map = RepoMapper::new();
+        agent.register_tool(Box::new(repo_map.tool())).await?;
+        agent.base_mut().add_middleware(Box::new(repo_map));
+        // `patch apply` and `revert_last_patch` only run through the approver set below.
+        agent.register_tool(Box::new(PatchTool::new("."))).await?;
+        agent
+            .register_tool(Box::new(CargoGraphTool::new(".")))
+            .await?;
+    }
+    if !crate::output::is_quiet() {
+        agent
+            .base_mut()
+            .add_observer(Box::new(crate::output::ToolSummaryObserver));
+    }
+    // Ask before running tools when someone is there to answer.
+    if std::io::stdin().is_terminal() {
+        agent
+            .base_mut()
+            .set_tool_approver(Box::new(crate::tool_approval::ConsoleApprover));
+    }
+    Ok(Box::new(agent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kowalski_core::agent::BaseAgent;
+    use kowalski_core::testing::{MockBackend, memory};
+    use kowalski_core::tools::manager::ToolManager;
+
+    /// Test agents answer `<saved system prompt>: <last message>`, so a reply shows what was
+    /// rebuilt.
+    fn echo_factory() -> AgentFactory {
+        Arc::new(|definition: AgentDefinition, config: Config| {
+            Box::pin(async move {
+                let persona = definition.system_prompt.unwrap_or_default();
+                let backend = MockBackend::script().echoes(format!("{persona}: ")).build();
+                let agent = BaseAgent::new(
+                    config,
+                    "echo",
+                    "echo",
+                    Arc::new(backend),
+                    memory(),
+                    memory(),
+                    memory(),
+                    ToolManager::new(),
+                )
+                .await?;
+                Ok(Box::new(agent) as BoxedAgent)
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn agents_survive_a_new_manager() {
+        let xdg = std::env::temp_dir().join(format!("kowalski-agents-{}", std::process::id()));
+        let store = AgentStore::new(xdg.join("kowalski/agents.toml"));
+
+        let first = AgentManager::new(store.clone()).with_factory(echo_factory());
+        let mut definition = AgentDefinition::new("data");
+        definition.system_prompt = Some("You are d1".to_string());
+        definition.model = Some("qwen3:8b".to_string());
+        first
+            .create_agent("d1".to_string(), definition)
+            .await
+            .unwrap();
+        drop(first);
+
+        let second = AgentManager::new(store).with_factory(echo_factory());
+        assert!(second.get_agent_mut("missing").await.unwrap().is_none());
+        let mut agents = second.get_agent_mut("d1").await.unwrap().unwrap();
+        let agent = agents.get_mut("d1").unwrap();
+        let conv_id = agent.start_conversation("qwen3:8b");
+        let reply = agent
+            .chat_with_history(&conv_id, "hello", None)
+            .await
+            .unwrap();
+        assert_eq!(reply, "You are d1: hello");
+        drop(agents);
+        assert_eq!(
+            second.get_config("d1").await.unwrap().ollama.model,
+            "qwen3:8b"
+        );
+
+        let overrides = SessionOverrides {
+            system_prompt: Some("You are d1 today".to_string()),
+            model: Some("llama3.2".to_string()),
+            ..SessionOverrides::default()
+        };
+        let mut agents = second
+            .get_agent_for_session("d1", &overrides)
+            .await
+            .unwrap()
+            .unwrap();
+        let agent = agents.get_mut("d1").unwrap();
+        let conv_id = agent.start_conversation("llama3.2");
+        let reply = agent
+            .chat_with_history(&conv_id, "hello", None)
+            .await
+            .unwrap();
+        assert_eq!(reply, "You are d1 today: hello");
+        drop(agents);
+        let effective = second.get_definition("d1").await.unwrap();
+        assert_eq!(effective.model.as_deref(), Some("llama3.2"));
+        // Session overrides are not saved.
+        let saved = second.store().get("d1").unwrap().unwrap();
+        assert_eq!(saved.system_prompt.as_deref(), Some("You are d1"));
+
+        assert!(second.delete_agent("d1").await.unwrap());
+        let third = AgentManager::new(second.store().clone()).with_factory(echo_factory());
+        assert!(third.get_agent_mut("d1").await.unwrap().is_none());
+        std::fs::remove_dir_all(xdg).unwrap();
+    }
+
+    #[tokio::test]
+    async fn data_agents_get_the_data_tools() {
+        let data_dir =
+            std::env::temp_dir().join(format!("kowalski-data-agent-{}", std::process::id()));
+        let mut config = Config {
+            data_dir: Some(data_dir.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        config.resolve_data_paths();
+
+        let agent = build_template_agent(AgentDefinition::new("data"), config)
+            .await
+            .unwrap();
+        let names: Vec<String> = agent
+            .list_tools()
+            .await
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        for tool in ["csv_tool", "stats", "sql_query", "infer_schema"] {
+            assert!(names.iter().any(|n| n == tool), "{tool} in {names:?}");
+        }
+        drop(agent);
+        std::fs::remove_dir_all(data_dir).unwrap();
+    }
+}
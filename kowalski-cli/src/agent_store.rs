@@ -0,0 +1,262 @@
+//! Agent definitions saved by `create`, so `chat`, `agents` and `delete` (one-shot or in the REPL)
+//! find them in later invocations. Stored as TOML in `agents.toml` of the data directory
+//! ([`data_dir`], e.g. `~/.local/share/kowalski/agents.toml`).
+
+use crate::error::KowalskiCliError;
+use kowalski_core::config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Everything needed to rebuild an agent created by `kowalski create`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentDefinition {
+    /// web, academic, code, data
+    pub agent_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Partial core `Config` (same layout as `config.toml`) merged over the defaults.
+    #[serde(default, skip_serializing_if = "toml::Table::is_empty")]
+    pub config: toml::Table,
+}
+
+impl AgentDefinition {
+    pub fn new(agent_type: impl Into<String>) -> Self {
+        Self {
+            agent_type: agent_type.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Core `Config` for this agent: defaults, then `config` overrides, then `KOWALSKI_*`
+    /// environment variables, then `model` and `temperature`, with store paths under the data
+    /// directory.
+    pub fn resolve_config(&self) -> Result<Config, KowalskiCliError> {
+        let mut config = if self.config.is_empty() {
+            Config::default()
+        } else {
+            let mut merged = toml::Table::try_from(Config::default())
+                .map_err(|e| KowalskiCliError::Serialization(e.to_string()))?;
+            merge_tables(&mut merged, self.config.clone());
+            merged
+                .try_into()
+                .map_err(|e| KowalskiCliError::Config(format!("Invalid agent config: {}", e)))?
+        };
+        config
+            .apply_env()
+            .map_err(|e| KowalskiCliError::Config(e.to_string()))?;
+        if let Some(model) = &self.model {
+            config.ollama.model = model.clone();
+        }
+        if let Some(temperature) = self.temperature {
+            config.chat.temperature = temperature;
+        }
+        config.resolve_data_paths();
+        Ok(config)
+    }
+}
+
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => {
+                merge_tables(base, value)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AgentsFile {
+    #[serde(default)]
+    agents: BTreeMap<String, AgentDefinition>,
+}
+
+/// `agents.toml` on disk. Every call re-reads the file, and changes are made under a lock
+/// (`agents.toml.lock`), so concurrent CLI processes see each other's changes and never lose them.
+#[derive(Debug, Clone)]
+pub struct AgentStore {
+    /// `None` without a data directory: nothing is saved yet, and saving fails.
+    path: Option<PathBuf>,
+}
+
+impl AgentStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+        }
+    }
+
+    /// Store at the default location (see [`data_dir`]). Without a data directory the store is
+    /// empty and only commands that save or delete agents fail.
+    pub fn open_default() -> Self {
+        Self {
+            path: data_dir().map(|dir| dir.join("agents.toml")),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// All saved agents by name; empty when the file does not exist yet.
+    pub fn load(&self) -> Result<BTreeMap<String, AgentDefinition>, KowalskiCliError> {
+        let Some(path) = &self.path else {
+            return Ok(BTreeMap::new());
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let file: AgentsFile = toml::from_str(&content).map_err(|e| {
+            KowalskiCliError::Config(format!("Failed to parse {}: {}", path.display(), e))
+        })?;
+        Ok(file.agents)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<AgentDefinition>, KowalskiCliError> {
+        Ok(self.load()?.remove(name))
+    }
+
+    /// Adds or replaces `name`.
+    pub fn save(&self, name: &str, definition: AgentDefinition) -> Result<(), KowalskiCliError> {
+        self.update(|agents| {
+            agents.insert(name.to_string(), definition);
+            true
+        })?;
+        Ok(())
+    }
+
+    /// Removes `name`; `false` if it was not saved.
+    pub fn remove(&self, name: &str) -> Result<bool, KowalskiCliError> {
+        self.update(|agents| agents.remove(name).is_some())
+    }
+
+    /// Read-modify-write under the store's lock; `change` returns whether to write the result.
+    fn update(
+        &self,
+        change: impl FnOnce(&mut BTreeMap<String, AgentDefinition>) -> bool,
+    ) -> Result<bool, KowalskiCliError> {
+        let path = self.path.as_deref().ok_or_else(no_data_dir)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let lock = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("toml.lock"))?;
+        // Released when `lock` is dropped.
+        lock.lock()?;
+        let mut agents = self.load()?;
+        if !change(&mut agents) {
+            return Ok(false);
+        }
+        let content = toml::to_string_pretty(&AgentsFile { agents })
+            .map_err(|e| KowalskiCliError::Serialization(e.to_string()))?;
+        write_atomic(path, &content)?;
+        Ok(true)
+    }
+}
+
+/// Writes `content` to a temporary file next to `path`, unique to this process and call, then
+/// renames it over `path`, so an interrupted save never truncates the file.
+pub(crate) fn write_atomic(path: &Path, content: &str) -> Result<(), KowalskiCliError> {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })?;
+    Ok(())
+}
+
+/// Error for stores used without a data directory.
+pub(crate) fn no_data_dir() -> KowalskiCliError {
+    KowalskiCliError::Config("No data directory: set KOWALSKI_DATA_DIR or HOME".to_string())
+}
+
+/// Kowalski's data directory: `$KOWALSKI_DATA_DIR`, else the platform data directory
+/// (`$XDG_DATA_HOME/kowalski` or `~/.local/share/kowalski` on Linux); see
+/// [`kowalski_core::config::default_data_dir`].
+pub fn data_dir() -> Option<PathBuf> {
+    kowalski_core::config::default_data_dir()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definitions_round_trip_and_apply_overrides() {
+        let dir = std::env::temp_dir().join(format!("kowalski-store-{}", std::process::id()));
+        let store = AgentStore::new(dir.join("kowalski/agents.toml"));
+        assert!(store.load().unwrap().is_empty());
+
+        let mut definition = AgentDefinition::new("data");
+        definition.system_prompt = Some("You analyse CSV files.".to_string());
+        definition.temperature = Some(0.2);
+        definition.model = Some("qwen3:8b".to_string());
+        definition.config = toml::from_str("[chat]\nmax_history = 3").unwrap();
+        store.save("d1", definition.clone()).unwrap();
+        store.save("d2", AgentDefinition::new("web")).unwrap();
+
+        let reopened = AgentStore::new(store.path().unwrap());
+        assert_eq!(reopened.get("d1").unwrap(), Some(definition.clone()));
+        let config = definition.resolve_config().unwrap();
+        assert_eq!(config.ollama.model, "qwen3:8b");
+        assert_eq!(config.chat.max_history, 3);
+        assert_eq!(config.chat.temperature, 0.2);
+        assert_eq!(config.ollama.host, Config::default().ollama.host);
+
+        assert!(reopened.remove("d1").unwrap());
+        assert!(!reopened.remove("d1").unwrap());
+        assert_eq!(store.load().unwrap().keys().collect::<Vec<_>>(), ["d2"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_saves_keep_every_agent() {
+        let dir = std::env::temp_dir().join(format!("kowalski-store-race-{}", std::process::id()));
+        let store = AgentStore::new(dir.join("agents.toml"));
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let store = store.clone();
+                scope.spawn(move || store.save(&format!("a{i}"), AgentDefinition::new("web")));
+            }
+        });
+        assert_eq!(store.load().unwrap().len(), 8);
+        let leftovers: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn without_a_data_directory_only_writes_fail() {
+        let store = AgentStore { path: None };
+        assert!(store.load().unwrap().is_empty());
+        assert_eq!(store.get("a").unwrap(), None);
+        assert!(store.save("a", AgentDefinition::new("web")).is_err());
+        assert!(store.remove("a").is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! `kowalski-cli ask [agent] "prompt"`: one tool-calling turn, printed as text, JSON or
+//! Markdown, for scripts. The prompt may come from stdin (`echo "…" | kowalski-cli ask data`).
+//! Text answers are written as they stream in.
+
+use crate::agent_manager::{AgentManager, SessionOverrides};
+use kowalski_core::agent::Agent;
+use kowalski_core::agent::tool_loop::{
+    ToolLoopEvent, ToolLoopOptions, ToolLoopOutcome, ToolTraceEntry, run_tool_loop_streaming,
+    run_tool_loop_with_options,
+};
+use serde::Serialize;
+use std::io::{IsTerminal, Read, Write};
+use std::time::Duration;
+
+/// Agent types `ask` can run without a saved agent.
+pub const AGENT_TYPES: &[&str] = &["web", "academic", "code", "data"];
+
+/// Agent used when `ask` is given none.
+pub const DEFAULT_AGENT: &str = "web";
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The answer only
+    #[default]
+    Text,
+    /// [`AskResult`] as one JSON object
+    Json,
+    /// The answer followed by a list of tool calls
+    Markdown,
+}
+
+/// What `ask --output json` prints.
+#[derive(Debug, Serialize)]
+pub struct AskResult {
+    pub agent: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub outcome: ToolLoopOutcome,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AskOptions {
+    pub overrides: SessionOverrides,
+    /// Plain chat turn: tool calls in the reply are not executed.
+    pub no_tools: bool,
+    pub timeout: Option<Duration>,
+    pub output: OutputFormat,
+}
+
+/// Splits `ask`'s positionals: with two, the first is the agent; a single one is the agent
+/// when it names a saved agent or an agent type, otherwise the prompt.
+pub fn split_args(
+    first: Option<String>,
+    second: Option<String>,
+    is_agent: impl Fn(&str) -> bool,
+) -> (Option<String>, Option<String>) {
+    match (first, second) {
+        (Some(agent), Some(prompt)) => (Some(agent), Some(prompt)),
+        (Some(only), None) if is_agent(&only) => (Some(only), None),
+        (only, _) => (None, only),
+    }
+}
+
+/// Runs one turn and prints it to stdout: text as the answer streams in, JSON and Markdown once
+/// the turn is complete.
+pub async fn ask(
+    manager: &AgentManager,
+    agent: Option<String>,
+    prompt: Option<String>,
+    options: &AskOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let prompt = match prompt {
+        Some(prompt) => prompt,
+        None if !std::io::stdin().is_terminal() => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        None => return Err("No prompt: pass one as an argument or pipe it to stdin".into()),
+    };
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Err("Empty prompt".into());
+    }
+    let name = agent.unwrap_or_else(|| DEFAULT_AGENT.to_string());
+
+    let turn = async {
+        let (mut agent, config) = manager
+            .build_agent(&name, AGENT_TYPES, &options.overrides)
+            .await?;
+        let model = config.ollama.model.clone();
+        let conv_id = agent.start_conversation(&model);
+        let loop_options = ToolLoopOptions::default();
+        if options.output == OutputFormat::Text && !options.no_tools {
+            stream_answer(agent.as_mut(), &conv_id, prompt, &loop_options).await?;
+            return Ok(None);
+        }
+        let outcome = if options.no_tools {
+            let answer = agent.chat_with_history(&conv_id, prompt, None).await?;
+            ToolLoopOutcome {
+                answer,
+                llm_calls: 1,
+                ..ToolLoopOutcome::default()
+            }
+        } else {
+            run_tool_loop_with_options(agent.as_mut(), &conv_id, prompt, &loop_options).await?
+        };
+        Ok::<_, Box<dyn std::error::Error>>(Some(AskResult {
+            agent: name.clone(),
+            model,
+            outcome,
+        }))
+    };
+    let result = match options.timeout {
+        Some(limit) => tokio::time::timeout(limit, turn)
+            .await
+            .map_err(|_| format!("No answer within {}s", limit.as_secs_f32()))??,
+        None => turn.await?,
+    };
+    if let Some(result) = result {
+        println!("{}", format_result(&result, options.output)?);
+    }
+    Ok(())
+}
+
+/// Runs the turn on the shared streaming tool loop ([`run_tool_loop_streaming`]), writing the
+/// answer to stdout as it arrives.
+async fn stream_answer(
+    agent: &mut (dyn Agent + Send + Sync),
+    conversation_id: &str,
+    prompt: &str,
+    options: &ToolLoopOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (event_tx, mut event_rx) = tokio::sync::mpsc::channel::<ToolLoopEvent>(256);
+    let print = tokio::spawn(async move {
+        let mut stdout = std::io::stdout();
+        let mut streamed = false;
+        while let Some(event) = event_rx.recv().await {
+            if let ToolLoopEvent::Token(delta) = event {
+                streamed = true;
+                let _ = stdout.write_all(delta.as_bytes());
+                let _ = stdout.flush();
+            }
+        }
+        streamed
+    });
+    let outcome = run_tool_loop_streaming(agent, conversation_id, prompt, options, &event_tx).await;
+    drop(event_tx);
+    let streamed = print.await.unwrap_or(true);
+    outcome?;
+    if streamed {
+        println!();
+    }
+    Ok(())
+}
+
+pub fn format_result(
+    result: &AskResult,
+    output: OutputFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(match output {
+        OutputFormat::Text => result.outcome.answer.clone(),
+        OutputFormat::Json => serde_json::to_string_pretty(result)?,
+        OutputFormat::Markdown => {
+            let mut out = result.outcome.answer.trim_end().to_string();
+            if !result.outcome.tool_trace.is_empty() {
+                out.push_str("\n\n## Tool calls\n\n");
+                for ToolTraceEntry {
+                    name,
+                    parameters,
+                    success,
+                    source,
+                    ..
+                } in &result.outcome.tool_trace
+                {
+                    let status = match (success, source) {
+                        (true, Some(source)) => format!("ok, source: {source}"),
+                        (true, None) => "ok".to_string(),
+                        (false, _) => "failed".to_string(),
+                    };
+                    out.push_str(&format!("- `{}` `{}` ({})\n", name, parameters, status));
+                }
+            }
+            out
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_positional_is_an_agent_only_when_it_names_one() {
+        let is_agent = |s: &str| s == "data" || s == "mine";
+        assert_eq!(
+            split_args(Some("data".into()), None, is_agent),
+            (Some("data".into()), None)
+        );
+        assert_eq!(
+            split_args(Some("what is 2+2?".into()), None, is_agent),
+            (None, Some("what is 2+2?".into()))
+        );
+        assert_eq!(
+            split_args(Some("mine".into()), Some("hi".into()), is_agent),
+            (Some("mine".into()), Some("hi".into()))
+        );
+        assert_eq!(split_args(None, None, is_agent), (None, None));
+    }
+}
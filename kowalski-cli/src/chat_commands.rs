@@ -0,0 +1,196 @@
+//! Slash commands of the interactive chat (`chat <agent>` without a message).
+
+/// Every command, for tab completion and help.
+pub const CHAT_COMMANDS: &[&str] = &[
+    "/bye",
+    "/clear",
+    "/facts",
+    "/forget",
+    "/handoff",
+    "/help",
+    "/load",
+    "/model",
+    "/regenerate",
+    "/remember",
+    "/role",
+    "/save",
+    "/search",
+    "/tools",
+];
+
+/// `(usage, description)` lines of `/help`.
+pub const CHAT_HELP: &[(&str, &str)] = &[
+    ("/help", "Show this help"),
+    ("/tools", "List the agent's tools"),
+    (
+        "/save <name> | /load <name>",
+        "Save or restore the conversation (sessions/<name>.json)",
+    ),
+    (
+        "/clear",
+        "Start a new conversation with the same agent and model",
+    ),
+    (
+        "/model [name]",
+        "Show or switch the model of this conversation",
+    ),
+    (
+        "/role [key|off]",
+        "List roles, or act in one from now on (prompt and tool restrictions)",
+    ),
+    ("/regenerate", "Ask again for the last reply, replacing it"),
+    (
+        "/search <query>",
+        "Find past messages about <query> in the agent's memory",
+    ),
+    (
+        "/remember <key> <value> | /forget <key>",
+        "Keep or drop a fact about you for every new conversation",
+    ),
+    ("/facts", "List the facts the agent keeps about you"),
+    (
+        "/handoff <agent> [reason]",
+        "Continue the conversation with another agent",
+    ),
+    ("/bye", "End the chat (or Ctrl-D)"),
+];
+
+/// One parsed slash command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatCommand {
+    Bye,
+    Help,
+    Tools,
+    /// `/save <name>`; `None` when the name is missing
+    Save(Option<String>),
+    /// `/load <name>`; `None` when the name is missing
+    Load(Option<String>),
+    Clear,
+    /// `/model [name]`: show (`None`) or switch the model
+    Model(Option<String>),
+    /// `/role [key]`: list (`None`) or select a role; `off` drops it
+    Role(Option<String>),
+    Regenerate,
+    /// `/search <query>`; `None` when the query is missing
+    Search(Option<String>),
+    /// `/remember <key> <value>`; an empty key or value when missing
+    Remember {
+        key: String,
+        value: String,
+    },
+    /// `/forget <key>`; `None` when the key is missing
+    Forget(Option<String>),
+    Facts,
+    /// `/handoff <agent> [reason]`; an empty target when it is missing
+    Handoff {
+        target: String,
+        reason: String,
+    },
+    /// Anything else starting with `/`
+    Unknown(String),
+}
+
+impl ChatCommand {
+    /// Parses `line` when it starts with `/`; other lines are chat messages (`None`). Command
+    /// names are case-insensitive.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if !line.starts_with('/') {
+            return None;
+        }
+        let (name, rest) = match line.split_once(char::is_whitespace) {
+            Some((name, rest)) => (name, rest.trim()),
+            None => (line, ""),
+        };
+        let arg = (!rest.is_empty()).then(|| rest.to_string());
+        Some(match name.to_ascii_lowercase().as_str() {
+            "/bye" | "/exit" | "/quit" => Self::Bye,
+            "/help" | "/?" => Self::Help,
+            "/tools" => Self::Tools,
+            "/save" => Self::Save(arg),
+            "/load" => Self::Load(arg),
+            "/clear" | "/new" => Self::Clear,
+            "/model" => Self::Model(arg),
+            "/role" => Self::Role(arg),
+            "/regenerate" | "/retry" => Self::Regenerate,
+            "/search" => Self::Search(arg),
+            "/remember" => {
+                let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                Self::Remember {
+                    key: key.to_string(),
+                    value: value.trim().to_string(),
+                }
+            }
+            "/forget" => Self::Forget(arg),
+            "/facts" => Self::Facts,
+            "/handoff" => {
+                let (target, reason) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                Self::Handoff {
+                    target: target.to_string(),
+                    reason: reason.trim().to_string(),
+                }
+            }
+            _ => Self::Unknown(name.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_their_arguments() {
+        assert_eq!(ChatCommand::parse("hello /bye"), None);
+        assert_eq!(ChatCommand::parse("  /BYE "), Some(ChatCommand::Bye));
+        assert_eq!(
+            ChatCommand::parse("/model  qwen2.5:7b "),
+            Some(ChatCommand::Model(Some("qwen2.5:7b".to_string())))
+        );
+        assert_eq!(ChatCommand::parse("/model"), Some(ChatCommand::Model(None)));
+        assert_eq!(ChatCommand::parse("/save"), Some(ChatCommand::Save(None)));
+        assert_eq!(
+            ChatCommand::parse("/search borrow checker"),
+            Some(ChatCommand::Search(Some("borrow checker".to_string())))
+        );
+        assert_eq!(
+            ChatCommand::parse("/handoff coder needs a refactor"),
+            Some(ChatCommand::Handoff {
+                target: "coder".to_string(),
+                reason: "needs a refactor".to_string(),
+            })
+        );
+        assert_eq!(
+            ChatCommand::parse("/handoff"),
+            Some(ChatCommand::Handoff {
+                target: String::new(),
+                reason: String::new(),
+            })
+        );
+        assert_eq!(
+            ChatCommand::parse("/remember editor  helix with vim keys"),
+            Some(ChatCommand::Remember {
+                key: "editor".to_string(),
+                value: "helix with vim keys".to_string(),
+            })
+        );
+        assert_eq!(
+            ChatCommand::parse("/forget editor"),
+            Some(ChatCommand::Forget(Some("editor".to_string())))
+        );
+        assert_eq!(
+            ChatCommand::parse("/frobnicate now"),
+            Some(ChatCommand::Unknown("/frobnicate".to_string()))
+        );
+        for command in CHAT_COMMANDS {
+            assert!(
+                !matches!(ChatCommand::parse(command), Some(ChatCommand::Unknown(_))),
+                "{command}"
+            );
+            assert!(
+                CHAT_HELP.iter().any(|(usage, _)| usage.contains(command)),
+                "{command} is missing from the help"
+            );
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Conversations from `chat`, saved after every turn so `conversation list/show/export/delete/
+//! resume` can reach them later. One JSON file per conversation in
+//! `conversations/` of the data directory (see [`crate::agent_store::data_dir`]).
+
+use crate::error::KowalskiCliError;
+use chrono::{Local, TimeZone};
+use kowalski_core::conversation::Conversation;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A saved conversation: the core [`Conversation`] (so the file can be imported as is) plus
+/// which agent it belongs to and when it last changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredConversation {
+    /// Name of the agent (as created with `create`).
+    pub agent: String,
+    /// Unix seconds.
+    pub updated_at: i64,
+    #[serde(flatten)]
+    pub conversation: Conversation,
+}
+
+impl StoredConversation {
+    pub fn summary(&self) -> ConversationSummary {
+        ConversationSummary {
+            id: self.conversation.id.clone(),
+            agent: self.agent.clone(),
+            model: self.conversation.model.clone(),
+            messages: self.conversation.messages.len(),
+            last_activity: format_time(self.updated_at),
+        }
+    }
+}
+
+/// One row of `conversation list` (also its `--json` output).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub agent: String,
+    pub model: String,
+    pub messages: usize,
+    pub last_activity: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConversationStore {
+    /// `None` without a data directory: nothing is listed, and saving fails.
+    dir: Option<PathBuf>,
+}
+
+impl ConversationStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+        }
+    }
+
+    /// Store at the default location (see [`crate::agent_store::data_dir`]). Without a data
+    /// directory the store is empty and only commands that read or write a conversation fail.
+    pub fn open_default() -> Self {
+        Self {
+            dir: crate::agent_store::data_dir().map(|dir| dir.join("conversations")),
+        }
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Adds or replaces `conversation`, stamped with the current time.
+    pub fn save(&self, agent: &str, conversation: &Conversation) -> Result<(), KowalskiCliError> {
+        let path = self.path(&conversation.id)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let stored = StoredConversation {
+            agent: agent.to_string(),
+            updated_at: chrono::Utc::now().timestamp(),
+            conversation: conversation.clone(),
+        };
+        let content = serde_json::to_string_pretty(&stored)
+            .map_err(|e| KowalskiCliError::Serialization(e.to_string()))?;
+        crate::agent_store::write_atomic(&path, &content)
+    }
+
+    /// Every saved conversation, most recent first; unreadable files are skipped with a warning.
+    pub fn list(&self) -> Result<Vec<StoredConversation>, KowalskiCliError> {
+        let Some(dir) = &self.dir else {
+            return Ok(Vec::new());
+        };
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut conversations = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match read(&path) {
+                Ok(stored) => conversations.push(stored),
+                Err(e) => log::warn!("Skipping {}: {}", path.display(), e),
+            }
+        }
+        conversations.sort_by_key(|c| std::cmp::Reverse(c.updated_at));
+        Ok(conversations)
+    }
+
+    /// The conversation whose id is `id` or, failing that, the only one starting with `id`.
+    pub fn get(&self, id: &str) -> Result<StoredConversation, KowalskiCliError> {
+        let path = self.path(id)?;
+        if path.exists() {
+            return read(&path);
+        }
+        let mut matches: Vec<_> = self
+            .list()?
+            .into_iter()
+            .filter(|c| c.conversation.id.starts_with(id))
+            .collect();
+        match matches.len() {
+            0 => Err(KowalskiCliError::Config(format!(
+                "No conversation '{}' (see `conversation list`)",
+                id
+            ))),
+            1 => Ok(matches.remove(0)),
+            n => Err(KowalskiCliError::Config(format!(
+                "'{}' matches {} conversations; use more of the id",
+                id, n
+            ))),
+        }
+    }
+
+    /// Removes conversation `id` (or unique prefix); returns its full id.
+    pub fn remove(&self, id: &str) -> Result<String, KowalskiCliError> {
+        let id = self.get(id)?.conversation.id;
+        fs::remove_file(self.path(&id)?)?;
+        Ok(id)
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf, KowalskiCliError> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(KowalskiCliError::Config(format!(
+                "Invalid conversation id '{}'",
+                id
+            )));
+        }
+        let dir = self
+            .dir
+            .as_deref()
+            .ok_or_else(crate::agent_store::no_data_dir)?;
+        Ok(dir.join(format!("{}.json", id)))
+    }
+}
+
+fn read(path: &Path) -> Result<StoredConversation, KowalskiCliError> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| KowalskiCliError::Config(format!("Failed to parse {}: {}", path.display(), e)))
+}
+
+/// Local `YYYY-MM-DD HH:MM` for a Unix timestamp.
+pub fn format_time(unix_secs: i64) -> String {
+    Local
+        .timestamp_opt(unix_secs, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// `conversation show`: one block per message, system prompts included.
+pub fn transcript(stored: &StoredConversation) -> String {
+    let mut out = format!(
+        "Conversation {} · agent {} · model {} · {}\n",
+        stored.conversation.id,
+        stored.agent,
+        stored.conversation.model,
+        format_time(stored.updated_at)
+    );
+    for message in &stored.conversation.messages {
+        out.push_str(&format!(
+            "\n[{}]\n{}\n",
+            message.role,
+            message.content.trim_end()
+        ));
+    }
+    out
+}
+
+/// `conversation export --format md`.
+pub fn to_markdown(stored: &StoredConversation) -> String {
+    let mut out = format!(
+        "# Conversation {}\n\n- Agent: {}\n- Model: {}\n- Last activity: {}\n",
+        stored.conversation.id,
+        stored.agent,
+        stored.conversation.model,
+        format_time(stored.updated_at)
+    );
+    for message in &stored.conversation.messages {
+        let mut role = message.role.clone();
+        if let Some(first) = role.get_mut(..1) {
+            first.make_ascii_uppercase();
+        }
+        out.push_str(&format!(
+            "\n## {}\n\n{}\n",
+            role,
+            message.content.trim_end()
+        ));
+    }
+    out
+}
+
+/// `conversation export --format jsonl`: one message object per line.
+pub fn to_jsonl(stored: &StoredConversation) -> Result<String, KowalskiCliError> {
+    let mut out = String::new();
+    for message in &stored.conversation.messages {
+        let line = serde_json::to_string(message)
+            .map_err(|e| KowalskiCliError::Serialization(e.to_string()))?;
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversations_round_trip_by_id_or_prefix() {
+        let dir = std::env::temp_dir().join(format!("kowalski-convs-{}", std::process::id()));
+        let store = ConversationStore::new(&dir);
+        assert!(store.list().unwrap().is_empty());
+
+        let mut conversation = Conversation::new("llama3.2");
+        conversation.add_message("user", "hello");
+        conversation.add_message("assistant", "hi");
+        store.save("w1", &conversation).unwrap();
+
+        let stored = store.get(&conversation.id[..8]).unwrap();
+        assert_eq!(stored.conversation, conversation);
+        assert_eq!(stored.summary().messages, 2);
+        assert_eq!(
+            to_jsonl(&stored).unwrap().lines().count(),
+            conversation.messages.len()
+        );
+        assert!(to_markdown(&stored).contains("## Assistant\n\nhi\n"));
+        // The file is a plain conversation to `Agent::import_conversation`.
+        let raw = fs::read_to_string(dir.join(format!("{}.json", conversation.id))).unwrap();
+        let imported: Conversation = serde_json::from_str(&raw).unwrap();
+        assert_eq!(imported, conversation);
+
+        assert!(store.get("../agents").is_err());
+        assert_eq!(store.remove(&conversation.id).unwrap(), conversation.id);
+        assert!(store.get(&conversation.id).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
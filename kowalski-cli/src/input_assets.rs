@@ -1,8 +1,14 @@
 use chrono::Utc;
+use log::warn;
 use reqwest::blocking as reqwest_blocking;
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default number of URLs fetched at once by [`ingest_assets_markdown`].
+pub const DEFAULT_FETCH_CONCURRENCY: usize = 4;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InputAsset {
@@ -70,11 +76,68 @@ fn md_cell(input: &str) -> String {
     input.replace('|', "\\|").replace('\n', " ")
 }
 
+/// Fetches `urls` with at most `concurrency` requests in flight. Results keep input order; a
+/// failed URL is logged and returned as `Err` without stopping the others.
+pub fn fetch_urls(urls: &[&str], concurrency: usize) -> Vec<Result<String, String>> {
+    let slots: Vec<Mutex<Option<Result<String, String>>>> =
+        urls.iter().map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+    let client = reqwest_blocking::Client::new();
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, urls.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(url) = urls.get(i) else { break };
+                    let result = client
+                        .get(*url)
+                        .send()
+                        .and_then(|r| r.error_for_status())
+                        .map(|r| {
+                            r.text()
+                                .unwrap_or_else(|_| "(unable to decode body)".to_string())
+                        })
+                        .map_err(|e| {
+                            warn!("fetch {url} failed: {e}");
+                            e.to_string()
+                        });
+                    *slots[i].lock().expect("fetch slot") = Some(result);
+                }
+            });
+        }
+    });
+    slots
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .expect("fetch slot")
+                .unwrap_or_else(|| Err("not fetched".to_string()))
+        })
+        .collect()
+}
+
 pub fn ingest_assets_markdown(
     root: &Path,
     source_input: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    ingest_assets_markdown_with_concurrency(root, source_input, DEFAULT_FETCH_CONCURRENCY)
+}
+
+/// Like [`ingest_assets_markdown`], fetching up to `concurrency` URLs in parallel.
+pub fn ingest_assets_markdown_with_concurrency(
+    root: &Path,
+    source_input: &str,
+    concurrency: usize,
 ) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let assets = parse_input_assets(source_input);
+    let urls: Vec<&str> = assets
+        .iter()
+        .filter_map(|a| match a {
+            InputAsset::Url(url) => Some(url.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut fetched = fetch_urls(&urls, concurrency).into_iter();
     let stamp = Utc::now().format("%Y%m%d-%H%M%S");
     let out = root
         .join("raw/sources")
@@ -96,11 +159,11 @@ pub fn ingest_assets_markdown(
     for (idx, asset) in assets.iter().enumerate() {
         match asset {
             InputAsset::Url(url) => {
-                let section = match reqwest_blocking::get(url) {
-                    Ok(resp) => {
-                        let text = resp
-                            .text()
-                            .unwrap_or_else(|_| "(unable to decode body)".to_string());
+                let fetch = fetched
+                    .next()
+                    .unwrap_or_else(|| Err("not fetched".to_string()));
+                let section = match fetch {
+                    Ok(text) => {
                         let clipped = text.chars().take(24000).collect::<String>();
                         doc.push_str(&format!(
                             "| {} | url | {} | ok | {} | fetched |\n",
@@ -181,3 +244,70 @@ pub fn ingest_assets_markdown(
     fs::write(&out, doc)?;
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serves `/page{n}` as `page {n}` (after a short delay) and 404 for anything else.
+    fn spawn_pages_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap() > 2 {
+                        line.clear();
+                    }
+                    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                    let (status, body) = match path.strip_prefix("/page") {
+                        Some(n) => ("200 OK", format!("page {n}")),
+                        None => ("404 Not Found", "missing".to_string()),
+                    };
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    let mut stream = stream;
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                });
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn fetches_all_pages_in_input_order_and_keeps_failures() {
+        let base = spawn_pages_server();
+        let urls: Vec<String> = (1..=6)
+            .map(|n| format!("{base}/page{n}"))
+            .chain([format!("{base}/nope")])
+            .collect();
+        let refs: Vec<&str> = urls.iter().map(String::as_str).collect();
+
+        let results = fetch_urls(&refs, 3);
+        assert_eq!(results.len(), 7);
+        for (n, result) in results.iter().take(6).enumerate() {
+            assert_eq!(result.as_deref(), Ok(format!("page {}", n + 1).as_str()));
+        }
+        assert!(results[6].as_ref().unwrap_err().contains("404"));
+
+        let root = std::env::temp_dir().join(format!("kowalski-ingest-{}", std::process::id()));
+        fs::create_dir_all(root.join("raw/sources")).unwrap();
+        let out = ingest_assets_markdown_with_concurrency(&root, &urls.join(" "), 4).unwrap();
+        let doc = fs::read_to_string(out).unwrap();
+        let first = doc.find("page 1").unwrap();
+        let last = doc.find("page 6").unwrap();
+        assert!(first < last);
+        assert!(doc.contains("| 7 | url |"));
+        assert!(doc.contains("| error |"));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,9 +1,23 @@
+pub mod academic;
 pub mod agent_app_ops;
+pub mod agent_manager;
+pub mod agent_store;
+pub mod ask;
+pub mod chat_commands;
 pub mod config;
+pub mod conversation_store;
 pub mod error;
 pub mod extension_ops;
 pub mod federation_ops;
 pub mod input_assets;
 pub mod interactive;
+pub mod line_editor;
 pub mod ops;
+pub mod output;
 pub mod run_ops;
+pub mod tool_approval;
+pub mod tool_ops;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "server")]
+pub mod ws_server;
@@ -0,0 +1,250 @@
+//! Line editing for the chat loop and REPL: Emacs keys, Ctrl-R search, persistent history in
+//! `<data dir>/history`, tab completion of slash commands, and multi-line messages.
+//!
+//! A message spans several lines when it is pasted (bracketed paste keeps the newlines), when it
+//! is wrapped in `"""` fences, or when a line ends with `\`; the latter continues until an
+//! empty line.
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, EditMode, Editor, Helper};
+use std::path::PathBuf;
+
+const FENCE: &str = "\"\"\"";
+
+/// Collects input lines into whole messages (see the module docs for the rules).
+#[derive(Debug, Default)]
+pub struct MultilineBuffer {
+    lines: Vec<String>,
+    mode: Option<Block>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    /// Opened by `"""`, closed by `"""`.
+    Fenced,
+    /// Opened by a trailing `\`, closed by an empty line.
+    Continued,
+}
+
+impl MultilineBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `true` while a message is still open.
+    pub fn is_pending(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    /// Feeds one line; returns the message once it is complete.
+    pub fn push(&mut self, line: &str) -> Option<String> {
+        match self.mode {
+            None => {
+                if line.trim() == FENCE {
+                    self.mode = Some(Block::Fenced);
+                    return None;
+                }
+                if let Some(head) = line.strip_suffix('\\') {
+                    self.mode = Some(Block::Continued);
+                    self.lines.push(head.to_string());
+                    return None;
+                }
+                Some(line.to_string())
+            }
+            Some(Block::Fenced) => {
+                if line.trim() == FENCE {
+                    return Some(self.finish());
+                }
+                self.lines.push(line.to_string());
+                None
+            }
+            Some(Block::Continued) => {
+                if line.trim().is_empty() {
+                    return Some(self.finish());
+                }
+                self.lines
+                    .push(line.strip_suffix('\\').unwrap_or(line).to_string());
+                None
+            }
+        }
+    }
+
+    /// Drops a half-entered message (Ctrl-C).
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.mode = None;
+    }
+
+    fn finish(&mut self) -> String {
+        self.mode = None;
+        std::mem::take(&mut self.lines).join("\n")
+    }
+}
+
+/// Completes a `/command` typed at the start of the line. Returns the replacement start and the
+/// matching commands.
+pub fn complete_command(line: &str, pos: usize, commands: &[&str]) -> (usize, Vec<String>) {
+    let typed = &line[..pos];
+    if !typed.starts_with('/') || typed.contains(char::is_whitespace) {
+        return (pos, Vec::new());
+    }
+    let matches = commands
+        .iter()
+        .filter(|c| c.starts_with(typed))
+        .map(|c| c.to_string())
+        .collect();
+    (0, matches)
+}
+
+/// rustyline helper that only completes slash commands.
+pub struct CommandHelper {
+    commands: Vec<&'static str>,
+}
+
+impl Completer for CommandHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_command(line, pos, &self.commands))
+    }
+}
+
+impl Hinter for CommandHelper {
+    type Hint = String;
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
+
+/// Readline front end shared by `chat` and the REPL.
+pub struct LineEditor {
+    editor: Editor<CommandHelper, FileHistory>,
+    history: Option<PathBuf>,
+    buffer: MultilineBuffer,
+}
+
+impl LineEditor {
+    /// Editor completing `commands`, with history in `<data dir>/history` when the data
+    /// directory is known.
+    pub fn new(commands: &[&'static str]) -> rustyline::Result<Self> {
+        let config = rustyline::Config::builder()
+            .edit_mode(EditMode::Emacs)
+            .history_ignore_dups(true)?
+            .auto_add_history(false)
+            .build();
+        let mut editor = Editor::with_config(config)?;
+        editor.set_helper(Some(CommandHelper {
+            commands: commands.to_vec(),
+        }));
+        let history = crate::agent_store::data_dir().map(|d| d.join("history"));
+        if let Some(path) = &history {
+            // A missing file just means a first run.
+            let _ = editor.load_history(path);
+        }
+        Ok(Self {
+            editor,
+            history,
+            buffer: MultilineBuffer::new(),
+        })
+    }
+
+    /// Reads one message, prompting with `prompt` (and `...` on continuation lines). `None` on
+    /// end of input (Ctrl-D); Ctrl-C discards the current message and starts over.
+    pub fn read_message(&mut self, prompt: &str) -> rustyline::Result<Option<String>> {
+        loop {
+            let prompt = if self.buffer.is_pending() {
+                "... "
+            } else {
+                prompt
+            };
+            match self.editor.readline(prompt) {
+                Ok(line) => {
+                    if let Some(message) = self.buffer.push(&line) {
+                        self.remember(&message);
+                        return Ok(Some(message));
+                    }
+                }
+                Err(ReadlineError::Interrupted) => self.buffer.clear(),
+                Err(ReadlineError::Eof) => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn remember(&mut self, message: &str) {
+        if message.trim().is_empty() {
+            return;
+        }
+        if self.editor.add_history_entry(message).unwrap_or(false)
+            && let Some(path) = &self.history
+        {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            if let Err(e) = self.editor.append_history(path) {
+                log::warn!("Could not save history to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(lines: &[&str]) -> Vec<String> {
+        let mut buffer = MultilineBuffer::new();
+        lines.iter().filter_map(|l| buffer.push(l)).collect()
+    }
+
+    #[test]
+    fn aggregates_fenced_and_continued_messages() {
+        assert_eq!(feed(&["hello", "/tools"]), ["hello", "/tools"]);
+        assert_eq!(
+            feed(&["\"\"\"", "fn main() {", "", "}", "\"\"\"", "next"]),
+            ["fn main() {\n\n}", "next"]
+        );
+        assert_eq!(
+            feed(&["first \\", "second\\", "third", "", "after"]),
+            ["first \nsecond\nthird", "after"]
+        );
+        // A pasted block arrives as one line with embedded newlines.
+        assert_eq!(feed(&["a\nb"]), ["a\nb"]);
+
+        let mut buffer = MultilineBuffer::new();
+        assert_eq!(buffer.push("\"\"\""), None);
+        assert_eq!(buffer.push("abandoned"), None);
+        buffer.clear();
+        assert!(!buffer.is_pending());
+        assert_eq!(buffer.push("fresh").as_deref(), Some("fresh"));
+    }
+
+    #[test]
+    fn completes_slash_commands_at_line_start() {
+        let commands = ["/bye", "/help", "/handoff", "/tools"];
+        assert_eq!(
+            complete_command("/h", 2, &commands),
+            (0, vec!["/help".to_string(), "/handoff".to_string()])
+        );
+        assert_eq!(
+            complete_command("/t", 2, &commands),
+            (0, vec!["/tools".to_string()])
+        );
+        assert_eq!(complete_command("/", 1, &commands).1.len(), 4);
+        assert!(complete_command("hello /h", 8, &commands).1.is_empty());
+        assert!(complete_command("/handoff co", 11, &commands).1.is_empty());
+    }
+}
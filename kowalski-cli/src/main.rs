@@ -1,23 +1,37 @@
 use clap::Parser;
+use kowalski_cli::academic::{self, AcademicAgent, AnalysisFormat};
+use kowalski_cli::agent_manager::{AgentManager, SessionOverrides};
+use kowalski_cli::agent_store::{AgentDefinition, AgentStore};
+use kowalski_cli::ask::{self, OutputFormat};
+use kowalski_cli::chat_commands::{CHAT_COMMANDS, CHAT_HELP, ChatCommand};
+use kowalski_cli::conversation_store::{self, ConversationStore};
+use kowalski_cli::line_editor::LineEditor;
+use kowalski_cli::output;
+use kowalski_cli::tool_ops::{self, ToolFormat};
 use kowalski_core::agent::Agent;
 use kowalski_core::config::Config;
+use kowalski_core::conversation::MessageRole;
+use kowalski_core::llm::ChatOptions;
+use kowalski_core::progress::{Progress, ProgressReporter};
+use kowalski_core::role::RoleCatalog;
 use kowalski_core::tools::ToolCall;
-use log::info;
+use kowalski_core::web::{HttpFetcher, ResearchEvent, ResearchOptions, ResearchReport, WebAgent};
+use log::{debug, warn};
 use serde_json::json;
 use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
 use kowalski_core::memory::consolidation::{Consolidator, MemoryWeaver};
+use kowalski_core::memory::episodic::EpisodicBuffer;
 
 #[derive(Parser, Debug)]
 #[clap(
     author,
     version,
     about = "Kowalski CLI — agents, memory, and MCP operators.",
-    long_about = "Operators: `run`, `config check`, `db migrate`, `doctor`, `mcp ping`, `mcp tools`, `federation ping-notify` (with `--features postgres`) (see --help on each)."
+    long_about = "Operators: `run`, `config check`, `db migrate`, `doctor`, `mcp ping`, `mcp tools`, `mcp-serve`, `federation ping-notify` (with `--features postgres`) (see --help on each)."
 )]
 struct Cli {
     #[clap(subcommand)]
@@ -30,6 +44,14 @@ struct Cli {
     /// Path to a configuration file (.toml) to load an agent
     #[clap(short, long)]
     config: Option<String>,
+
+    /// More output: -v session details and raw tool-call replies, -vv debug log, -vvv trace log
+    #[clap(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only print answers
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -55,35 +77,129 @@ enum Commands {
     Chat {
         /// Agent name or type
         agent: String,
-        /// Optional system prompt
+        /// System prompt for this session (overrides the agent's)
         #[clap(short, long)]
         prompt: Option<String>,
-        /// Optional temperature
+        /// Temperature for this session (overrides the agent's)
         #[clap(short, long)]
         temperature: Option<f32>,
-        /// Optional model
+        /// Model for this session (overrides the agent's)
         #[clap(short, long)]
         model: Option<String>,
+        /// Role from `roles list` whose prompt becomes the system prompt for this session
+        #[clap(long, conflicts_with = "prompt")]
+        role: Option<String>,
+        /// Image file to attach to the message (repeatable; needs a vision model)
+        #[clap(long = "image")]
+        images: Vec<std::path::PathBuf>,
+        /// Send a single message and exit instead of starting the chat loop
+        message: Option<String>,
+    },
+    /// Ask one question and exit (for scripts); reads the prompt from stdin when not given
+    Ask {
+        /// Saved agent name or agent type (default: web)
+        agent: Option<String>,
+        /// The prompt (default: read stdin)
+        prompt: Option<String>,
+        /// Output format
+        #[clap(short, long, value_enum, default_value_t)]
+        output: OutputFormat,
+        /// Model for this request (overrides the agent's)
+        #[clap(short, long)]
+        model: Option<String>,
+        /// Plain chat: do not run tools
+        #[clap(long)]
+        no_tools: bool,
+        /// Give up after this many seconds
+        #[clap(long)]
+        timeout: Option<u64>,
     },
     /// List available agent types
     List,
-    /// List active agents
+    /// List agents created with `create`
     Agents,
+    /// Delete an agent created with `create`
+    Delete {
+        /// Agent name
+        name: String,
+    },
+    /// Saved chat conversations: list, show, export, delete, resume
+    Conversation {
+        #[clap(subcommand)]
+        command: ConversationCommands,
+    },
+    /// Research helpers
+    Academic {
+        #[clap(subcommand)]
+        command: AcademicCommands,
+    },
+    /// Web research
+    Web {
+        #[clap(subcommand)]
+        command: WebCommands,
+    },
+    /// Full-screen chat with tool-activity and memory panes (Tab switches agents)
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Saved agents or agent types to open (default: all saved agents, or `web`)
+        agents: Vec<String>,
+    },
+    /// Serve agents over WebSocket (`ws://<bind>/ws`, JSON messages: create_agent, list_agents, chat)
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:3457")]
+        bind: String,
+        /// Require this bearer token (`Authorization` header or `?token=`); needed unless
+        /// `--bind` is a loopback address
+        #[clap(long)]
+        token: Option<String>,
+    },
+    /// Call an agent's tools directly, without the LLM (for debugging tools)
+    Tool {
+        #[clap(subcommand)]
+        command: ToolCommands,
+    },
     /// Consolidate memory - move from episodic history into semantic memory
     Consolidate {
         #[clap(long)]
         delete: bool,
+        /// Config TOML (default: ./config.toml; defaults when missing)
+        #[clap(short, long)]
+        config: Option<String>,
+        /// Consolidate a saved agent's memory instead
+        #[clap(long, conflicts_with = "config")]
+        agent: Option<String>,
+    },
+    /// Episodic memory maintenance
+    Memory {
+        #[clap(subcommand)]
+        command: MemoryCommands,
     },
     /// Model Context Protocol helpers
     Mcp {
         #[clap(subcommand)]
         command: McpCommands,
     },
+    /// Serve built-in tools to MCP clients (Claude Desktop, IDEs) over stdio
+    McpServe {
+        /// Config TOML for memory settings (default: ./config.toml)
+        #[clap(short, long)]
+        config: Option<String>,
+        /// Directory the file tools (fs_tool, csv_tool, stats, chart) may read; nothing outside it
+        #[clap(long, default_value = ".")]
+        root: std::path::PathBuf,
+    },
     /// Validate configuration TOML (and full Kowalski `Config` when possible)
     Config {
         #[clap(subcommand)]
         command: ConfigCommands,
     },
+    /// Built-in roles and the ones config.toml defines under `[roles]`
+    Roles {
+        #[clap(subcommand)]
+        command: RolesCommands,
+    },
     /// Run SQL migrations for `sqlite:` or `postgres://` URLs
     Db {
         #[clap(subcommand)]
@@ -118,6 +234,19 @@ enum Commands {
     },
 }
 
+#[derive(Parser, Debug)]
+enum RolesCommands {
+    /// List every role `chat --role` accepts
+    List {
+        /// Config TOML with `[roles]` (default: ./config.toml)
+        #[clap(short, long)]
+        config: Option<String>,
+        /// Print JSON instead of a table
+        #[clap(long)]
+        json: bool,
+    },
+}
+
 #[derive(Parser, Debug)]
 enum ConfigCommands {
     /// Check that TOML parses and optionally matches core `Config`
@@ -126,6 +255,27 @@ enum ConfigCommands {
         #[clap(default_value = "config.toml")]
         path: String,
     },
+    /// Print the configuration in effect (file merged over defaults), secrets masked
+    Show {
+        /// Config TOML (default: ./config.toml; defaults when missing)
+        #[clap(short, long)]
+        config: Option<String>,
+        /// Show a saved agent's configuration instead
+        #[clap(long, conflicts_with = "config")]
+        agent: Option<String>,
+        /// Print JSON instead of TOML
+        #[clap(long)]
+        json: bool,
+    },
+    /// Check the configuration against the running services: LLM backend, models, memory
+    Validate {
+        /// Config TOML (default: ./config.toml; defaults when missing)
+        #[clap(short, long)]
+        config: Option<String>,
+        /// Validate a saved agent's configuration instead
+        #[clap(long, conflicts_with = "config")]
+        agent: Option<String>,
+    },
 }
 
 #[derive(Parser, Debug)]
@@ -141,6 +291,36 @@ enum DbCommands {
     },
 }
 
+#[derive(Parser, Debug)]
+enum MemoryCommands {
+    /// Embed units stored without an embedding so vector search finds them again
+    Reindex {
+        /// Config TOML (default: ./config.toml; defaults when missing)
+        #[clap(short, long)]
+        config: Option<String>,
+        /// Reindex a saved agent's memory instead
+        #[clap(long, conflicts_with = "config")]
+        agent: Option<String>,
+    },
+    /// Show what episodic retrieval ranks for a query, with each unit's score parts
+    DebugRetrieve {
+        /// Text to retrieve memories for
+        query: String,
+        /// Number of units to show
+        #[clap(short, long, default_value_t = 10)]
+        limit: usize,
+        /// Config TOML (default: ./config.toml; defaults when missing)
+        #[clap(short, long)]
+        config: Option<String>,
+        /// Search a saved agent's memory instead
+        #[clap(long, conflicts_with = "config")]
+        agent: Option<String>,
+        /// Print JSON instead of a table
+        #[clap(long)]
+        json: bool,
+    },
+}
+
 #[derive(Parser, Debug)]
 enum FederationCommands {
     /// Send a Ping ACL via `pg_notify` on `kowalski_federation` (needs `memory.database_url` in config)
@@ -151,6 +331,155 @@ enum FederationCommands {
     },
 }
 
+#[derive(Parser, Debug)]
+enum ConversationCommands {
+    /// List saved conversations, most recent first
+    List {
+        /// Print JSON instead of a table
+        #[clap(long)]
+        json: bool,
+    },
+    /// Print a conversation's transcript
+    Show {
+        /// Conversation id (or a unique prefix)
+        id: String,
+    },
+    /// Write a conversation to stdout as Markdown or JSON lines
+    Export {
+        /// Conversation id (or a unique prefix)
+        id: String,
+        #[clap(short, long, value_enum, default_value = "md")]
+        format: ExportFormat,
+    },
+    /// Delete a saved conversation
+    Delete {
+        /// Conversation id (or a unique prefix)
+        id: String,
+    },
+    /// Continue a saved conversation in the chat loop
+    Resume {
+        /// Conversation id (or a unique prefix)
+        id: String,
+    },
+}
+
+#[derive(Parser, Debug)]
+enum AcademicCommands {
+    /// Summarize a paper (PDF, text or Markdown) section by section and list its references
+    Analyze {
+        file: std::path::PathBuf,
+        /// Model to use (overrides the agent's)
+        #[clap(short, long)]
+        model: Option<String>,
+        /// Saved agent or agent type whose settings (model, backend) to use
+        #[clap(long, default_value = "academic")]
+        agent: String,
+        #[clap(short, long, value_enum, default_value_t)]
+        format: AnalysisFormat,
+        /// Sections to summarize, e.g. abstract,methods,results (default: all but references)
+        #[clap(short, long, value_delimiter = ',')]
+        sections: Vec<String>,
+        /// Words of paper text per model call; longer sections are read in parts and merged
+        #[clap(long, default_value_t = academic::DEFAULT_CHUNK_TOKENS)]
+        chunk_tokens: usize,
+        /// Write the result to this file instead of stdout
+        #[clap(long)]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Compare papers side by side: one row per paper, one column per dimension, with quotes
+    Compare {
+        #[clap(required = true, num_args = 2..)]
+        files: Vec<std::path::PathBuf>,
+        /// Model to use (overrides the agent's)
+        #[clap(short, long)]
+        model: Option<String>,
+        /// Saved agent or agent type whose settings (model, backend) to use
+        #[clap(long, default_value = "academic")]
+        agent: String,
+        #[clap(short, long, value_enum, default_value_t = AnalysisFormat::Markdown)]
+        format: AnalysisFormat,
+        /// What to compare, e.g. method,dataset (default: method,dataset,metrics,findings,limitations)
+        #[clap(short, long, value_delimiter = ',')]
+        dimensions: Vec<String>,
+        /// Words of paper text per model call; longer sections are read in parts and merged
+        #[clap(long, default_value_t = academic::DEFAULT_CHUNK_TOKENS)]
+        chunk_tokens: usize,
+        /// Write the result to this file instead of stdout
+        #[clap(long)]
+        out: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Parser, Debug)]
+enum WebCommands {
+    /// Research a topic over several search rounds and print a report with numbered citations
+    Research {
+        topic: String,
+        /// Sources to read in total
+        #[clap(long, default_value_t = ResearchOptions::default().max_sources)]
+        max_sources: usize,
+        /// Follow-up search rounds for gaps the model finds in its notes
+        #[clap(long, default_value_t = ResearchOptions::default().max_depth)]
+        max_depth: usize,
+        /// Write the report from the page text instead of per-source summaries (fewer LLM calls)
+        #[clap(long)]
+        no_summaries: bool,
+        /// Model to use (overrides the agent's)
+        #[clap(short, long)]
+        model: Option<String>,
+        /// Saved agent or agent type whose settings (model, backend) to use
+        #[clap(long, default_value = "web")]
+        agent: String,
+        /// Print the report as JSON instead of Markdown
+        #[clap(long)]
+        json: bool,
+        /// Write the report to this file instead of stdout
+        #[clap(long)]
+        out: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Parser, Debug)]
+enum ToolCommands {
+    /// List an agent's tools
+    List {
+        /// Saved agent name or agent type
+        agent: String,
+        /// Print JSON or YAML instead of a table
+        #[clap(short, long, value_enum)]
+        output: Option<ToolFormat>,
+    },
+    /// Print a tool's parameter schema
+    Schema {
+        tool: String,
+        /// Saved agent name or agent type that has the tool
+        #[clap(short, long, default_value = ask::DEFAULT_AGENT)]
+        agent: String,
+        #[clap(short, long, value_enum, default_value_t)]
+        output: ToolFormat,
+    },
+    /// Run a tool and print its output
+    Run {
+        /// Saved agent name or agent type
+        agent: String,
+        tool: String,
+        /// Parameter as key=value, parsed by its declared type (repeatable)
+        #[clap(long = "param")]
+        params: Vec<String>,
+        /// All parameters as one JSON object
+        #[clap(long)]
+        param_json: Option<String>,
+        #[clap(short, long, value_enum, default_value_t)]
+        output: ToolFormat,
+    },
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+enum ExportFormat {
+    Md,
+    Jsonl,
+}
+
 #[derive(Parser, Debug)]
 enum McpCommands {
     /// Run initialize + tools/list against each server in [mcp] (from config TOML)
@@ -200,7 +529,7 @@ enum AgentAppCommands {
         /// Source URL or text
         source: String,
         /// Optional question for query phase
-        #[clap(short, long)]
+        #[clap(long)]
         question: Option<String>,
         /// App root path (default: examples/knowledge-compiler)
         #[clap(short, long)]
@@ -216,7 +545,7 @@ enum AgentAppCommands {
         /// Source URL or text
         source: String,
         /// Optional question for query phase
-        #[clap(short, long)]
+        #[clap(long)]
         question: Option<String>,
         /// Kowalski API base URL
         #[clap(long)]
@@ -268,96 +597,77 @@ enum AgentAppCommands {
     },
 }
 
-struct AgentManager {
-    agents: Arc<RwLock<HashMap<String, Box<dyn Agent + Send + Sync>>>>,
-    configs: Arc<RwLock<HashMap<String, Config>>>,
+/// An [`AcademicAgent`] with `agent`'s model and chat settings, caching summaries in the data
+/// directory.
+fn academic_agent(
+    manager: &AgentManager,
+    agent: &str,
+    model: Option<String>,
+    chunk_tokens: usize,
+) -> Result<AcademicAgent, Box<dyn std::error::Error>> {
+    let overrides = SessionOverrides {
+        model,
+        ..SessionOverrides::default()
+    };
+    let config = manager.resolve_config(agent, ask::AGENT_TYPES, &overrides)?;
+    let llm = kowalski_core::llm::create_llm_provider(&config)?;
+    let options = ChatOptions {
+        temperature: Some(config.chat.temperature),
+        max_tokens: Some(config.chat.max_tokens),
+        ..Default::default()
+    };
+    let academic_agent = AcademicAgent::new(llm, config.ollama.model.clone())
+        .with_options(options)
+        .with_chunk_tokens(chunk_tokens);
+    Ok(match kowalski_cli::agent_store::data_dir() {
+        Some(dir) => academic_agent.with_cache_dir(dir.join("papers")),
+        None => academic_agent,
+    })
 }
 
-impl AgentManager {
-    fn new() -> Self {
-        Self {
-            agents: Arc::new(RwLock::new(HashMap::new())),
-            configs: Arc::new(RwLock::new(HashMap::new())),
-        }
-    }
-
-    async fn create_agent_from_config(
-        &self,
-        config_path: &str,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        use kowalski_cli::config::AgentConfig;
-        use std::path::Path;
-
-        let agent_config = AgentConfig::load_from_file(Path::new(config_path))
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
-
-        println!(
-            "Loading agent '{}' of type '{}'...",
-            agent_config.name, agent_config.agent_type
-        );
-
-        self.create_agent(
-            agent_config.name.clone(),
-            &agent_config.agent_type,
-            agent_config.system_prompt.as_deref(),
-            agent_config.temperature,
-        )
-        .await?;
-
-        // If tools are specified, we might need a way to register them after creation
-        // but for now create_agent uses default tools for each type.
-        // In the future, we'll use tool_manager directly.
-
-        Ok(agent_config.name)
-    }
-
-    async fn create_agent(
-        &self,
-        name: String,
-        agent_type: &str,
-        _prompt: Option<&str>,
-        _temperature: Option<f32>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let config = Config::default();
-        use kowalski_core::template::default::DefaultTemplate;
-        let builder = DefaultTemplate::create_agent(vec![], None, Some(0.7)).await?;
-        let mut template_agent = builder.build().await?;
-        template_agent.base_mut().set_system_prompt(&format!(
-            "Starting generic agent (was requested type: {})",
-            agent_type
-        ));
-
-        let agent: Box<dyn Agent + Send + Sync> = Box::new(template_agent);
-        self.agents.write().await.insert(name.clone(), agent);
-        self.configs.write().await.insert(name, config);
-        Ok(())
-    }
-
-    async fn get_agent_mut(
-        &self,
-        name: &str,
-    ) -> Option<tokio::sync::RwLockWriteGuard<'_, HashMap<String, Box<dyn Agent + Send + Sync>>>>
-    {
-        let guard = self.agents.write().await;
-        if guard.contains_key(name) {
-            Some(guard)
-        } else {
-            None
-        }
-    }
-
-    async fn get_config(&self, name: &str) -> Option<Config> {
-        self.configs.read().await.get(name).cloned()
-    }
-
-    async fn list_agents(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let agents = self.agents.read().await;
-        println!("Active agents:");
-        for (name, _) in agents.iter() {
-            println!("- {}", name);
+/// Runs [`WebAgent::research`] with a progress line fed by its events.
+async fn run_web_research(
+    config: &Config,
+    topic: &str,
+    options: ResearchOptions,
+) -> Result<ResearchReport, Box<dyn std::error::Error>> {
+    let llm = kowalski_core::llm::create_llm_provider(config)?;
+    let fetcher = HttpFetcher::new()?.with_profiles(&config.web)?;
+    let agent = WebAgent::new(llm, config.ollama.model.clone())?.with_fetcher(Arc::new(fetcher));
+    let total = options.max_sources;
+    let mut events = agent.subscribe();
+    let progress = tokio::spawn(async move {
+        let line = output::ProgressLine::new("Researching");
+        let mut done = 0;
+        while let Ok(event) = events.recv().await {
+            let current = match event {
+                ResearchEvent::Searching { query, .. } => format!("searching: {query}"),
+                ResearchEvent::SourceRead { number, url } => {
+                    done = number;
+                    url
+                }
+                ResearchEvent::SourceUnreadable { number, url, .. } => {
+                    done = number;
+                    format!("unreadable: {url}")
+                }
+                ResearchEvent::GapsFound { queries } => {
+                    format!("following up: {}", queries.join("; "))
+                }
+                ResearchEvent::Reported { .. } => break,
+            };
+            line.report(&Progress {
+                done,
+                total: Some(total),
+                current: Some(current),
+                ..Progress::default()
+            });
         }
-        Ok(())
-    }
+        line.finish();
+    });
+    let report = agent.research(topic, options).await;
+    drop(agent);
+    let _ = progress.await;
+    Ok(report?)
 }
 
 async fn run_mcp_ping(config_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
@@ -473,9 +783,21 @@ async fn run_mcp_tools(config_path: Option<&str>) -> Result<(), Box<dyn std::err
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let cli = Cli::parse();
-    let manager = AgentManager::new();
+    let log_filter = output::init(cli.verbose, cli.quiet);
+    // Log lines would be drawn over the full-screen UI.
+    #[cfg(feature = "tui")]
+    let log_filter = match cli.command {
+        Some(Commands::Tui { .. }) => "off",
+        _ => log_filter,
+    };
+    // `[observability]` of ./config.toml: logging plus optional span export.
+    let observability = Config::load(&kowalski_cli::ops::mcp_config_path(None))
+        .map(|config| config.observability)
+        .unwrap_or_default();
+    let _telemetry = kowalski_core::logging::init_with_config(&observability, log_filter)?;
+    let manager = AgentManager::new(AgentStore::open_default());
+    let conversations = ConversationStore::open_default();
 
     let mut active_agent_name = None;
 
@@ -496,13 +818,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "default".to_string()
         });
 
-        if manager.get_agent_mut(&agent_name).await.is_none() {
+        if manager.get_agent_mut(&agent_name).await?.is_none() {
             manager
-                .create_agent(agent_name.clone(), "web", None, None)
+                .create_agent(agent_name.clone(), AgentDefinition::new("web"))
                 .await?;
         }
 
-        let mut agents_guard = manager.get_agent_mut(&agent_name).await.unwrap();
+        let mut agents_guard = manager.get_agent_mut(&agent_name).await?.unwrap();
         if let Some(agent) = agents_guard.remove(&agent_name) {
             let mut session = kowalski_cli::interactive::InteractiveSession::new(agent, "llama3");
             session.run().await?;
@@ -522,37 +844,68 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 manager.create_agent_from_config(&config_path).await?;
             } else {
                 let name = name.unwrap_or_else(|| format!("{}-agent", agent_type));
-                manager
-                    .create_agent(name, &agent_type, prompt.as_deref(), temperature)
-                    .await?;
+                let mut definition = AgentDefinition::new(agent_type);
+                definition.system_prompt = prompt;
+                definition.temperature = temperature;
+                manager.create_agent(name.clone(), definition).await?;
+                println!("Agent created successfully: {}", name);
             }
         }
-        Some(Commands::Chat { agent, .. }) => {
-            let agents_guard = manager.get_agent_mut(&agent).await;
+        Some(Commands::Chat {
+            agent,
+            prompt,
+            temperature,
+            model,
+            role,
+            images,
+            message,
+        }) => {
+            if !images.is_empty() && message.is_none() {
+                return Err("--image needs a message, e.g. kowalski chat <agent> --image photo.png \"what's in this?\"".into());
+            }
+            let prompt = match role {
+                Some(role) => Some(role_prompt(&manager, &agent, &role)?),
+                None => prompt,
+            };
+            let overrides = SessionOverrides {
+                system_prompt: prompt,
+                temperature,
+                model,
+            };
+            let agents_guard = manager.get_agent_for_session(&agent, &overrides).await?;
             if let Some(mut agents_guard) = agents_guard {
                 if let Some(agent_ref) = agents_guard.get_mut(&agent) {
                     let config = manager
                         .get_config(&agent)
                         .await
                         .unwrap_or_else(Config::default);
+                    if output::is_verbose()
+                        && let Some(definition) = manager.get_definition(&agent).await
+                    {
+                        print_session_settings(&agent, &definition, &config);
+                    }
                     let conv_id = agent_ref.start_conversation(&config.ollama.model);
-                    println!(
-                        "Chat session started with agent '{}'. Type /bye to end chat.",
-                        agent
-                    );
-                    println!("Model in use: {}", config.ollama.model);
-                    // Print registered tools
-                    let tools = agent_ref.list_tools().await;
-                    if !tools.is_empty() {
-                        info!("Registered tools:");
-                        for (name, desc) in tools {
-                            info!("  - {}: {}", name, desc);
-                        }
-                    } else {
-                        info!("No tools registered or tool listing not available.");
+                    if let Some(message) = message {
+                        let response = if images.is_empty() {
+                            agent_ref
+                                .chat_with_history(&conv_id, &message, None)
+                                .await?
+                        } else {
+                            agent_ref
+                                .chat_with_images(&conv_id, &message, &images)
+                                .await?
+                        };
+                        agent_ref
+                            .add_message(&conv_id, "assistant", &response)
+                            .await;
+                        save_conversation(&conversations, agent_ref.as_ref(), &agent, &conv_id);
+                        println!("{}", response);
+                        close_conversation(agent_ref.as_mut(), &conv_id).await;
+                        return Ok(());
                     }
-
-                    chat_loop(agent_ref, conv_id).await?;
+                    print_chat_banner(agent_ref, &agent).await;
+                    let roles = chat_roles(&manager, &agent);
+                    chat_loop(&mut agents_guard, &agent, conv_id, &conversations, &roles).await?;
                 } else {
                     println!("Agent '{}' not found.", agent);
                 }
@@ -560,8 +913,168 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("Agent '{}' not found.", agent);
             }
         }
+        Some(Commands::Ask {
+            agent,
+            prompt,
+            output,
+            model,
+            no_tools,
+            timeout,
+        }) => {
+            let saved = manager.saved_agents()?;
+            let (agent, prompt) = ask::split_args(agent, prompt, |s| {
+                saved.contains_key(s) || ask::AGENT_TYPES.contains(&s)
+            });
+            let options = ask::AskOptions {
+                overrides: SessionOverrides {
+                    model,
+                    ..SessionOverrides::default()
+                },
+                no_tools,
+                timeout: timeout.map(std::time::Duration::from_secs),
+                output,
+            };
+            ask::ask(&manager, agent, prompt, &options).await?;
+        }
         Some(Commands::List) => list_agents()?,
         Some(Commands::Agents) => manager.list_agents().await?,
+        Some(Commands::Delete { name }) => delete_agent(&manager, &name).await?,
+        Some(Commands::Conversation { command }) => {
+            conversation_command(&manager, &conversations, command).await?
+        }
+        Some(Commands::Academic {
+            command:
+                AcademicCommands::Analyze {
+                    file,
+                    model,
+                    agent,
+                    format,
+                    sections,
+                    chunk_tokens,
+                    out,
+                },
+        }) => {
+            let academic_agent = academic_agent(&manager, &agent, model, chunk_tokens)?;
+            let progress = kowalski_cli::output::ProgressLine::new("Analyzing");
+            let summary = academic_agent
+                .summarize_paper_with_progress(&file, &sections, &progress)
+                .await;
+            progress.finish();
+            let output = summary?.render(format)?;
+            match out {
+                Some(path) => fs::write(path, output + "\n")?,
+                None => println!("{}", output),
+            }
+        }
+        Some(Commands::Academic {
+            command:
+                AcademicCommands::Compare {
+                    files,
+                    model,
+                    agent,
+                    format,
+                    dimensions,
+                    chunk_tokens,
+                    out,
+                },
+        }) => {
+            let academic_agent = academic_agent(&manager, &agent, model, chunk_tokens)?;
+            let progress = kowalski_cli::output::ProgressLine::new("Comparing");
+            let matrix = academic_agent
+                .compare_papers_with_progress(&files, &dimensions, &progress)
+                .await;
+            progress.finish();
+            let output = matrix?.render(format)?;
+            match out {
+                Some(path) => fs::write(path, output + "\n")?,
+                None => println!("{}", output),
+            }
+        }
+        Some(Commands::Web {
+            command:
+                WebCommands::Research {
+                    topic,
+                    max_sources,
+                    max_depth,
+                    no_summaries,
+                    model,
+                    agent,
+                    json,
+                    out,
+                },
+        }) => {
+            let overrides = SessionOverrides {
+                model,
+                ..SessionOverrides::default()
+            };
+            let config = manager.resolve_config(&agent, ask::AGENT_TYPES, &overrides)?;
+            let options = ResearchOptions {
+                max_sources,
+                max_depth,
+                per_source_summary: !no_summaries,
+            };
+            let report = run_web_research(&config, &topic, options).await?;
+            let output = if json {
+                serde_json::to_string_pretty(&report)?
+            } else {
+                report.to_markdown()
+            };
+            match out {
+                Some(path) => fs::write(path, output + "\n")?,
+                None => println!("{}", output),
+            }
+        }
+        #[cfg(feature = "tui")]
+        Some(Commands::Tui { mut agents }) => {
+            if agents.is_empty() {
+                agents = manager.saved_agents()?.into_keys().collect();
+            }
+            if agents.is_empty() {
+                agents.push(ask::DEFAULT_AGENT.to_string());
+            }
+            kowalski_cli::tui::run(&manager, agents).await?;
+        }
+        #[cfg(feature = "server")]
+        Some(Commands::Serve { bind, token }) => {
+            let listener = tokio::net::TcpListener::bind(&bind).await?;
+            let options = kowalski_cli::ws_server::ServeOptions {
+                bearer_token: token,
+            };
+            kowalski_cli::ws_server::check_bind(listener.local_addr()?, &options)?;
+            println!("Serving agents at ws://{}/ws", listener.local_addr()?);
+            kowalski_cli::ws_server::serve(Arc::new(manager), listener, options, async {
+                let _ = tokio::signal::ctrl_c().await;
+                kowalski_core::llm::shutdown_token().cancel();
+            })
+            .await?;
+            return Ok(());
+        }
+        Some(Commands::Tool { command }) => {
+            let output = match command {
+                ToolCommands::List { agent, output } => {
+                    tool_ops::list(&tool_ops::registry(&manager, &agent).await?, output).await?
+                }
+                ToolCommands::Schema {
+                    tool,
+                    agent,
+                    output,
+                } => {
+                    let registry = tool_ops::registry(&manager, &agent).await?;
+                    tool_ops::schema(&registry, &tool, output).await?
+                }
+                ToolCommands::Run {
+                    agent,
+                    tool,
+                    params,
+                    param_json,
+                    output,
+                } => {
+                    let registry = tool_ops::registry(&manager, &agent).await?;
+                    tool_ops::run(&registry, &tool, &params, param_json.as_deref(), output).await?
+                }
+            };
+            println!("{}", output);
+        }
         Some(Commands::Mcp { command }) => match command {
             McpCommands::Ping {
                 config: config_path,
@@ -574,11 +1087,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 run_mcp_tools(config_path.as_deref()).await?;
             }
         },
+        Some(Commands::McpServe { config, root }) => {
+            kowalski_cli::ops::run_mcp_serve(config.as_deref(), &root).await?;
+        }
         Some(Commands::Config { command }) => match command {
             ConfigCommands::Check { path } => {
                 kowalski_cli::ops::run_config_check(std::path::Path::new(&path))?;
             }
+            ConfigCommands::Show {
+                config,
+                agent,
+                json,
+            } => {
+                let config = effective_config(&manager, config.as_deref(), agent.as_deref())?;
+                println!("{}", kowalski_cli::ops::render_config(&config, json)?);
+            }
+            ConfigCommands::Validate { config, agent } => {
+                let config = effective_config(&manager, config.as_deref(), agent.as_deref())?;
+                let checks = kowalski_cli::ops::validate_config(&config).await;
+                kowalski_cli::ops::report_config_checks(&checks)?;
+            }
         },
+        Some(Commands::Roles {
+            command: RolesCommands::List { config, json },
+        }) => {
+            let config = Config::load(&kowalski_cli::ops::mcp_config_path(config.as_deref()))?;
+            let roles = RoleCatalog::from_config(&config).list();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&roles)?);
+            } else {
+                for entry in roles {
+                    println!(
+                        "{:<12} {:<8} {}",
+                        entry.key,
+                        entry.source,
+                        entry.role.get_prompt().replace('\n', " ")
+                    );
+                }
+            }
+        }
         Some(Commands::Db { command }) => match command {
             DbCommands::Migrate { url, config } => {
                 kowalski_cli::ops::run_db_migrate(url, config).await?;
@@ -710,97 +1257,312 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
         },
-        Some(Commands::Consolidate { delete }) => {
-            let config = Config::default();
+        Some(Commands::Consolidate {
+            delete,
+            config,
+            agent,
+        }) => {
+            let config = effective_config(&manager, config.as_deref(), agent.as_deref())?;
             let ollama_model = &config.ollama.model;
 
-            // Create LLM provider for consolidation
-            let llm_provider: std::sync::Arc<dyn kowalski_core::llm::LLMProvider> =
-                std::sync::Arc::new(kowalski_core::llm::OllamaProvider::new(
-                    &config.ollama.host,
-                    config.ollama.port,
-                ));
+            // Create LLM provider for consolidation (honours `[embedding]`)
+            let llm_provider = kowalski_core::llm::create_llm_provider(&config)?;
 
             kowalski_core::db::run_memory_migrations_if_configured(&config).await?;
 
-            let mut weaver = Consolidator::new(&config.memory, llm_provider, ollama_model).await?;
+            let mut weaver = Consolidator::new(&config.memory, llm_provider, ollama_model)
+                .await?
+                .with_summarization(&config.summarization)
+                .with_prompts(kowalski_core::PromptRegistry::from_config(&config.prompts)?);
             weaver.run(delete).await?;
             println!("Memory consolidation complete.");
         }
+        Some(Commands::Memory {
+            command: MemoryCommands::Reindex { config, agent },
+        }) => {
+            let config = effective_config(&manager, config.as_deref(), agent.as_deref())?;
+            kowalski_core::db::run_memory_migrations_if_configured(&config).await?;
+            let llm_provider = kowalski_core::llm::create_llm_provider(&config)?;
+            let buffer = EpisodicBuffer::open(&config.memory, llm_provider).await?;
+            let progress = output::ProgressLine::new("Reindexing");
+            let report = buffer.reindex_with_progress(&progress).await;
+            progress.finish();
+            let report = report?;
+            println!(
+                "Repaired {} of {} units missing an embedding ({} units stored).",
+                report.repaired, report.missing, report.unit_count
+            );
+        }
+        Some(Commands::Memory {
+            command:
+                MemoryCommands::DebugRetrieve {
+                    query,
+                    limit,
+                    config,
+                    agent,
+                    json,
+                },
+        }) => {
+            let config = effective_config(&manager, config.as_deref(), agent.as_deref())?;
+            kowalski_core::db::run_memory_migrations_if_configured(&config).await?;
+            let llm_provider = kowalski_core::llm::create_llm_provider(&config)?;
+            let buffer = EpisodicBuffer::open(&config.memory, llm_provider).await?;
+            let scored = buffer.debug_retrieve(&query, limit).await?;
+            if json {
+                let rows: Vec<_> = scored
+                    .iter()
+                    .map(|(unit, score)| json!({"unit": unit, "score": score}))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+            } else if scored.is_empty() {
+                println!("No memories match '{}'.", query);
+            } else {
+                println!(" score semantic recency  memory");
+                for (unit, score) in scored {
+                    let preview: String =
+                        unit.content.replace('\n', " ").chars().take(60).collect();
+                    println!(
+                        "{:>6.3} {:>8.3} {:>7.3}  {} {}",
+                        score.score, score.semantic, score.recency, unit.id, preview
+                    );
+                }
+            }
+        }
         None => {
             // Enter REPL mode if no subcommand is provided
             println!("Kowalski CLI Interactive Mode. Type 'help' for commands.");
-            repl(manager).await?;
+            repl(manager, &conversations).await?;
         }
     }
     Ok(())
 }
 
+/// Most past messages `/search` prints.
+const SEARCH_HISTORY_LIMIT: usize = 10;
+
+/// `sessions/<name>.json` in the agent's data directory (see [`Config::resolved_data_dir`]), so
+/// `/save` and `/load` do not depend on the working directory; relative to it only when there
+/// is no data directory.
+fn session_path(agent: &mut (dyn Agent + Send + Sync), name: &str) -> std::path::PathBuf {
+    let dir = agent
+        .base_agent_mut()
+        .and_then(|base| base.config.resolved_data_dir())
+        .or_else(kowalski_cli::agent_store::data_dir)
+        .unwrap_or_default();
+    dir.join("sessions").join(format!("{name}.json"))
+}
+
 async fn chat_loop(
-    agent: &mut Box<dyn Agent + Send + Sync>,
+    agents: &mut HashMap<String, Box<dyn Agent + Send + Sync>>,
+    name: &str,
     mut conv_id: String,
+    conversations: &ConversationStore,
+    roles: &RoleCatalog,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let agent_name = agent.name().to_lowercase();
-    println!("Agent name: '{}'", agent_name);
+    let mut current = name.to_string();
+    let mut editor = LineEditor::new(CHAT_COMMANDS)?;
 
     loop {
-        print!("You: ");
-        io::stdout().flush()?;
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let input_trimmed = input.trim();
-
-        if input_trimmed.eq_ignore_ascii_case("/bye") {
+        let agent = agents
+            .get_mut(&current)
+            .ok_or_else(|| format!("Agent '{}' not found", current))?;
+        let Some(input) = editor.read_message(&output::user_prompt())? else {
             println!("Goodbye!");
             break;
+        };
+        let input_trimmed = input.trim();
+        if input_trimmed.is_empty() {
+            continue;
         }
 
-        if input_trimmed.starts_with("/save") {
-            let filename = input_trimmed.strip_prefix("/save").unwrap().trim();
-            if filename.is_empty() {
-                println!("Usage: /save <filename>");
-            } else {
-                match agent.export_conversation(&conv_id) {
+        if let Some(command) = ChatCommand::parse(input_trimmed) {
+            match command {
+                ChatCommand::Bye => {
+                    println!("Goodbye!");
+                    break;
+                }
+                ChatCommand::Help => print_chat_help(),
+                ChatCommand::Tools => {
+                    let tools = agent.list_tools().await;
+                    if tools.is_empty() {
+                        println!("No tools registered.");
+                    }
+                    for (name, desc) in tools {
+                        println!("  {}: {}", name, desc);
+                    }
+                }
+                ChatCommand::Save(None) => println!("Usage: /save <filename>"),
+                ChatCommand::Save(Some(filename)) => match agent.export_conversation(&conv_id) {
                     Ok(json) => {
-                        let _ = fs::create_dir_all("sessions");
-                        let path = format!("sessions/{}.json", filename);
+                        let path = session_path(agent.as_mut(), &filename);
+                        if let Some(dir) = path.parent() {
+                            let _ = fs::create_dir_all(dir);
+                        }
                         if let Err(e) = fs::write(&path, json) {
                             eprintln!("Failed to write session file: {}", e);
                         } else {
-                            println!("Conversation saved to {}", path);
+                            println!("Conversation saved to {}", path.display());
                         }
                     }
                     Err(e) => eprintln!("Failed to save conversation: {}", e),
+                },
+                ChatCommand::Load(None) => println!("Usage: /load <filename>"),
+                ChatCommand::Load(Some(filename)) => {
+                    let path = session_path(agent.as_mut(), &filename);
+                    match fs::read_to_string(&path) {
+                        Ok(json) => match agent.import_conversation(&json) {
+                            Ok(new_id) => {
+                                conv_id = new_id;
+                                println!("Conversation loaded. Current session ID: {}", conv_id);
+                            }
+                            Err(e) => eprintln!("Failed to import conversation: {}", e),
+                        },
+                        Err(e) => eprintln!("Failed to read session file: {}", e),
+                    }
                 }
-            }
-            continue;
-        }
-
-        if input_trimmed.starts_with("/load") {
-            let filename = input_trimmed.strip_prefix("/load").unwrap().trim();
-            if filename.is_empty() {
-                println!("Usage: /load <filename>");
-            } else {
-                let path = format!("sessions/{}.json", filename);
-                match fs::read_to_string(&path) {
-                    Ok(json) => match agent.import_conversation(&json) {
-                        Ok(new_id) => {
+                ChatCommand::Clear => {
+                    let model = agent
+                        .get_conversation(&conv_id)
+                        .map(|c| c.model.clone())
+                        .unwrap_or_default();
+                    close_conversation(agent.as_mut(), &conv_id).await;
+                    conv_id = agent.start_conversation(&model);
+                    println!(
+                        "Started a new conversation. Current session ID: {}",
+                        conv_id
+                    );
+                }
+                ChatCommand::Model(model) => {
+                    let Some(conversation) = agent
+                        .base_agent_mut()
+                        .and_then(|base| base.conversations.get_mut(&conv_id))
+                    else {
+                        eprintln!("This agent cannot switch models.");
+                        continue;
+                    };
+                    match model {
+                        None => println!("Model: {}", conversation.model),
+                        Some(model) => {
+                            conversation.model = model;
+                            println!("Model switched to {}.", conversation.model);
+                        }
+                    }
+                }
+                ChatCommand::Role(None) => {
+                    for entry in roles.list() {
+                        println!("  {}: {}", entry.key, entry.role.name);
+                    }
+                }
+                ChatCommand::Role(Some(key)) => {
+                    let Some(base) = agent.base_agent_mut() else {
+                        eprintln!("This agent does not support roles.");
+                        continue;
+                    };
+                    if key.eq_ignore_ascii_case("off") {
+                        base.clear_role();
+                        println!("Role cleared; all tools are available again.");
+                        continue;
+                    }
+                    let Some(role) = roles.get(&key) else {
+                        eprintln!("Unknown role '{}' (type /role to list them)", key);
+                        continue;
+                    };
+                    if let Some(conversation) = base.conversations.get_mut(&conv_id) {
+                        conversation.add_message_typed(MessageRole::System, &role.get_prompt());
+                    }
+                    base.set_role(role.clone());
+                    println!("Now acting as {}.", role.name);
+                }
+                ChatCommand::Regenerate => {
+                    match agent
+                        .regenerate_last(&conv_id, &ChatOptions::default())
+                        .await
+                    {
+                        Ok(reply) => {
+                            println!("{}", reply);
+                            save_conversation(conversations, agent.as_ref(), &current, &conv_id);
+                        }
+                        Err(e) => eprintln!("Regenerate failed: {}", e),
+                    }
+                }
+                ChatCommand::Search(None) => println!("Usage: /search <query>"),
+                ChatCommand::Search(Some(query)) => {
+                    match agent.search_history(&query, SEARCH_HISTORY_LIMIT).await {
+                        Ok(found) if found.is_empty() => println!("Nothing found."),
+                        Ok(found) => {
+                            for unit in found {
+                                println!(
+                                    "  {}  {}",
+                                    conversation_store::format_time(unit.timestamp as i64),
+                                    unit.content
+                                );
+                            }
+                        }
+                        Err(e) => eprintln!("Search failed: {}", e),
+                    }
+                }
+                ChatCommand::Remember { key, value } if key.is_empty() || value.is_empty() => {
+                    println!("Usage: /remember <key> <value>");
+                }
+                ChatCommand::Remember { key, value } => {
+                    match agent.remember_fact(&key, &value).await {
+                        Ok(()) => println!("Remembered {}: {}", key, value),
+                        Err(e) => eprintln!("Remember failed: {}", e),
+                    }
+                }
+                ChatCommand::Forget(None) => println!("Usage: /forget <key>"),
+                ChatCommand::Forget(Some(key)) => match agent.forget_fact(&key).await {
+                    Ok(true) => println!("Forgot {}.", key),
+                    Ok(false) => println!("No fact '{}'.", key),
+                    Err(e) => eprintln!("Forget failed: {}", e),
+                },
+                ChatCommand::Facts => {
+                    let facts = agent.list_facts();
+                    if facts.is_empty() {
+                        println!("No facts yet. Add one with /remember <key> <value>.");
+                    }
+                    for (key, value) in facts {
+                        println!("  {}: {}", key, value);
+                    }
+                }
+                ChatCommand::Handoff { target, .. } if target.is_empty() => {
+                    println!("Usage: /handoff <agent> [reason]");
+                }
+                ChatCommand::Handoff { target, reason } => {
+                    match hand_off(agents, &current, &conv_id, &target, &reason) {
+                        Ok((new_id, carried)) => {
+                            println!(
+                                "[handoff] '{}' -> '{}'{}: {} messages carried over, now chatting with '{}' (session {}).",
+                                current,
+                                target,
+                                if reason.is_empty() {
+                                    String::new()
+                                } else {
+                                    format!(" ({})", reason)
+                                },
+                                carried,
+                                target,
+                                new_id
+                            );
+                            current = target;
                             conv_id = new_id;
-                            println!("Conversation loaded. Current session ID: {}", conv_id);
                         }
-                        Err(e) => eprintln!("Failed to import conversation: {}", e),
-                    },
-                    Err(e) => eprintln!("Failed to read session file: {}", e),
+                        Err(e) => eprintln!("Handoff failed: {}", e),
+                    }
+                }
+                ChatCommand::Unknown(name) => {
+                    println!("Unknown command '{}'.", name);
+                    print_chat_help();
                 }
             }
             continue;
         }
 
         // Always use tool-calling chat method
-        info!("Using tool-calling chat method");
+        debug!("Using tool-calling chat method");
         match chat_with_tools(agent, &conv_id, &input).await {
             Ok(_) => {
-                info!("Tool-calling chat completed successfully");
+                debug!("Tool-calling chat completed successfully");
             }
             Err(e) => {
                 eprintln!("Tool-calling chat failed: {}", e);
@@ -808,10 +1570,177 @@ async fn chat_loop(
                 use_regular_chat(agent, &conv_id, &input).await?;
             }
         }
+        save_conversation(conversations, agent.as_ref(), &current, &conv_id);
+    }
+    if let Some(agent) = agents.get_mut(&current) {
+        close_conversation(agent.as_mut(), &conv_id).await;
     }
     Ok(())
 }
 
+/// Ends `conv_id` on `agent` once it has been saved, summarizing it into memory when
+/// `memory.summarize_on_close` is set; failures only warn.
+async fn close_conversation(agent: &mut (dyn Agent + Send + Sync), conv_id: &str) {
+    if let Err(e) = agent.close_conversation(conv_id).await {
+        warn!("Could not close conversation {}: {}", conv_id, e);
+    }
+}
+
+/// Saves `conv_id` for `conversation list/resume`; failures only warn.
+fn save_conversation(
+    conversations: &ConversationStore,
+    agent: &(dyn Agent + Send + Sync),
+    agent_name: &str,
+    conv_id: &str,
+) {
+    let Some(conversation) = agent.get_conversation(conv_id) else {
+        warn!("Could not save conversation {}: not found", conv_id);
+        return;
+    };
+    if let Err(e) = conversations.save(agent_name, conversation) {
+        warn!("Could not save conversation {}: {}", conv_id, e);
+    }
+}
+
+async fn conversation_command(
+    manager: &AgentManager,
+    conversations: &ConversationStore,
+    command: ConversationCommands,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        ConversationCommands::List { json } => {
+            let summaries: Vec<_> = conversations.list()?.iter().map(|c| c.summary()).collect();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&summaries)?);
+            } else if summaries.is_empty() {
+                println!("No saved conversations yet. Start one with: chat <agent>");
+            } else {
+                println!(
+                    "{:<36}  {:<16}  {:<20}  {:>8}  LAST ACTIVITY",
+                    "ID", "AGENT", "MODEL", "MESSAGES"
+                );
+                for s in summaries {
+                    println!(
+                        "{:<36}  {:<16}  {:<20}  {:>8}  {}",
+                        s.id, s.agent, s.model, s.messages, s.last_activity
+                    );
+                }
+            }
+        }
+        ConversationCommands::Show { id } => {
+            print!(
+                "{}",
+                conversation_store::transcript(&conversations.get(&id)?)
+            );
+        }
+        ConversationCommands::Export { id, format } => {
+            let stored = conversations.get(&id)?;
+            match format {
+                ExportFormat::Md => print!("{}", conversation_store::to_markdown(&stored)),
+                ExportFormat::Jsonl => print!("{}", conversation_store::to_jsonl(&stored)?),
+            }
+        }
+        ConversationCommands::Delete { id } => {
+            let id = conversations.remove(&id)?;
+            println!("Conversation {} deleted.", id);
+        }
+        ConversationCommands::Resume { id } => {
+            let stored = conversations.get(&id)?;
+            let name = stored.agent.clone();
+            let Some(mut agents) = manager.get_agent_mut(&name).await? else {
+                return Err(format!(
+                    "Agent '{}' of conversation {} no longer exists",
+                    name, stored.conversation.id
+                )
+                .into());
+            };
+            let agent = agents
+                .get_mut(&name)
+                .ok_or_else(|| format!("Agent '{}' not found", name))?;
+            let conv_id =
+                agent.import_conversation(&serde_json::to_string(&stored.conversation)?)?;
+            if !output::is_quiet() {
+                println!(
+                    "Resuming conversation {} with agent '{}' ({} messages). Type /bye to end chat.",
+                    conv_id,
+                    name,
+                    stored.conversation.messages.len()
+                );
+                if let Some(last) = stored
+                    .conversation
+                    .messages
+                    .iter()
+                    .rev()
+                    .find(|m| m.role == "assistant")
+                {
+                    println!("Last reply: {}", last.content.trim_end());
+                }
+            }
+            let roles = chat_roles(manager, &name);
+            chat_loop(&mut agents, &name, conv_id, conversations, &roles).await?;
+        }
+    }
+    Ok(())
+}
+
+/// "Chat session started" line (unless `-q`), plus the agent's tools with `-v`.
+async fn print_chat_banner(agent: &mut Box<dyn Agent + Send + Sync>, name: &str) {
+    if output::is_quiet() {
+        return;
+    }
+    println!(
+        "Chat session started with agent '{}'. Type /help for commands, /bye to end chat.",
+        name
+    );
+    if output::is_verbose() {
+        let tools = agent.list_tools().await;
+        if tools.is_empty() {
+            println!("No tools registered.");
+        } else {
+            println!("Tools:");
+            for (name, desc) in tools {
+                println!("  - {}: {}", name, desc);
+            }
+        }
+    }
+}
+
+fn print_chat_help() {
+    println!("Commands:");
+    for (usage, description) in CHAT_HELP {
+        println!("  {}: {}", usage, description);
+    }
+    println!();
+    println!(
+        "Multi-line messages: wrap them in \"\"\" lines, or end a line with \\ and finish with an empty line."
+    );
+    println!("Ctrl-R searches history; Tab completes commands.");
+}
+
+/// Moves conversation `conv_id` from agent `from` to agent `to` (see `Agent::accept_handoff`);
+/// returns the receiver's conversation id and the number of messages carried over.
+fn hand_off(
+    agents: &mut HashMap<String, Box<dyn Agent + Send + Sync>>,
+    from: &str,
+    conv_id: &str,
+    to: &str,
+    reason: &str,
+) -> Result<(String, usize), Box<dyn std::error::Error>> {
+    if from == to {
+        return Err(format!("already chatting with '{}'", to).into());
+    }
+    let conversation = agents
+        .get(from)
+        .and_then(|a| a.get_conversation(conv_id))
+        .cloned()
+        .ok_or_else(|| format!("no active conversation {}", conv_id))?;
+    let target = agents
+        .get_mut(to)
+        .ok_or_else(|| format!("Agent '{}' not found (see `agents`)", to))?;
+    let new_id = target.accept_handoff(&conversation, from, reason)?;
+    Ok((new_id, conversation.messages.len()))
+}
+
 async fn chat_with_tools(
     agent: &mut Box<dyn Agent + Send + Sync>,
     conv_id: &str,
@@ -839,6 +1768,22 @@ async fn use_regular_chat(
     Ok(())
 }
 
+/// Configuration for `config show/validate`: a saved agent's, or the config file's.
+fn effective_config(
+    manager: &AgentManager,
+    path: Option<&str>,
+    agent: Option<&str>,
+) -> Result<Config, Box<dyn std::error::Error>> {
+    match agent {
+        Some(agent) => {
+            manager.resolve_config(agent, ask::AGENT_TYPES, &SessionOverrides::default())
+        }
+        None => kowalski_cli::ops::load_kowalski_config_for_serve(
+            &kowalski_cli::ops::mcp_config_path(path),
+        ),
+    }
+}
+
 fn list_agents() -> Result<(), Box<dyn std::error::Error>> {
     println!("Available agent types:");
     println!("- web: Web research and information retrieval");
@@ -848,12 +1793,72 @@ fn list_agents() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn repl(manager: AgentManager) -> Result<(), Box<dyn std::error::Error>> {
+/// Roles `agent` can take: built-ins, then `[roles]` from ./config.toml, then from the agent's
+/// own config.
+fn role_catalog(
+    manager: &AgentManager,
+    agent: &str,
+) -> Result<RoleCatalog, Box<dyn std::error::Error>> {
+    let file = Config::load(&kowalski_cli::ops::mcp_config_path(None))?;
+    let mut catalog = RoleCatalog::from_config(&file);
+    let agent_config =
+        manager.resolve_config(agent, ask::AGENT_TYPES, &SessionOverrides::default())?;
+    catalog.extend(&agent_config.roles);
+    Ok(catalog)
+}
+
+/// [`role_catalog`] for `/role`; a config that does not load leaves the built-ins.
+fn chat_roles(manager: &AgentManager, agent: &str) -> RoleCatalog {
+    role_catalog(manager, agent).unwrap_or_else(|e| {
+        warn!("Only built-in roles are available: {}", e);
+        RoleCatalog::builtin()
+    })
+}
+
+/// The prompt of role `key` (see [`role_catalog`]).
+fn role_prompt(
+    manager: &AgentManager,
+    agent: &str,
+    key: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    role_catalog(manager, agent)?
+        .get(key)
+        .map(|role| role.get_prompt())
+        .ok_or_else(|| format!("Unknown role '{key}' (see `kowalski-cli roles list`)").into())
+}
+
+fn print_session_settings(name: &str, definition: &AgentDefinition, config: &Config) {
+    println!("Agent: {} ({})", name, definition.agent_type);
+    println!("Model: {}", config.ollama.model);
+    println!("Temperature: {}", config.chat.temperature);
+    println!(
+        "System prompt: {}",
+        definition.system_prompt.as_deref().unwrap_or("(default)")
+    );
+}
+
+async fn delete_agent(
+    manager: &AgentManager,
+    name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if manager.delete_agent(name).await? {
+        println!("Agent '{}' deleted.", name);
+    } else {
+        println!("Agent '{}' not found.", name);
+    }
+    Ok(())
+}
+
+async fn repl(
+    manager: AgentManager,
+    conversations: &ConversationStore,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut editor = LineEditor::new(&["/bye"])?;
     loop {
-        print!("kowalski> ");
-        io::stdout().flush()?;
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+        let Some(input) = editor.read_message("kowalski> ")? else {
+            println!("Exiting Kowalski CLI.");
+            break;
+        };
         let input = input.trim();
         if input.is_empty() {
             continue;
@@ -870,7 +1875,8 @@ async fn repl(manager: AgentManager) -> Result<(), Box<dyn std::error::Error>> {
                 println!("  create <type> [--name <name>]: Create an agent");
                 println!("  chat <name>: Chat with an agent");
                 println!("  list: List available agent types");
-                println!("  agents: List active agents");
+                println!("  agents: List created agents");
+                println!("  delete <name>: Delete an agent");
                 println!("  bye | /bye : Exit the CLI");
                 println!();
                 println!("Operators (run outside this REPL):");
@@ -898,7 +1904,7 @@ async fn repl(manager: AgentManager) -> Result<(), Box<dyn std::error::Error>> {
                         None => format!("{}-agent", agent_type),
                     };
                     manager
-                        .create_agent(agent_name.clone(), agent_type, None, None)
+                        .create_agent(agent_name.clone(), AgentDefinition::new(agent_type))
                         .await?;
                     println!("Agent created successfully: {}", agent_name);
                 } else {
@@ -908,7 +1914,7 @@ async fn repl(manager: AgentManager) -> Result<(), Box<dyn std::error::Error>> {
             "chat" => {
                 let name = parts.next();
                 if let Some(name) = name {
-                    let agents_guard = manager.get_agent_mut(name).await;
+                    let agents_guard = manager.get_agent_mut(name).await?;
                     if let Some(mut agents_guard) = agents_guard {
                         if let Some(agent_ref) = agents_guard.get_mut(name) {
                             let config = manager
@@ -916,23 +1922,17 @@ async fn repl(manager: AgentManager) -> Result<(), Box<dyn std::error::Error>> {
                                 .await
                                 .unwrap_or_else(Config::default);
                             let conv_id = agent_ref.start_conversation(&config.ollama.model);
-                            info!(
-                                "Chat session started with agent '{}'. Type /bye to end chat.",
-                                name
-                            );
-                            info!("[DEBUG] Model in use: {}", config.ollama.model);
-                            // Print registered tools
-                            let tools = agent_ref.list_tools().await;
-                            if !tools.is_empty() {
-                                info!("[DEBUG] Registered tools:");
-                                for (name, desc) in tools {
-                                    info!("  - {}: {}", name, desc);
-                                }
-                            } else {
-                                info!("[DEBUG] No tools registered or tool listing not available.");
-                            }
-
-                            chat_loop(agent_ref, conv_id.clone()).await?;
+                            debug!("Model in use: {}", config.ollama.model);
+                            print_chat_banner(agent_ref, name).await;
+                            let roles = chat_roles(&manager, name);
+                            chat_loop(
+                                &mut agents_guard,
+                                name,
+                                conv_id.clone(),
+                                conversations,
+                                &roles,
+                            )
+                            .await?;
                         } else {
                             println!("Agent '{}' not found.", name);
                         }
@@ -949,6 +1949,10 @@ async fn repl(manager: AgentManager) -> Result<(), Box<dyn std::error::Error>> {
             "agents" => {
                 manager.list_agents().await?;
             }
+            "delete" => match parts.next() {
+                Some(name) => delete_agent(&manager, name).await?,
+                None => println!("Usage: delete <name>"),
+            },
             _ => {
                 println!(
                     "Unknown command: {}. Type 'help' for a list of commands.",
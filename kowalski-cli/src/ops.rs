@@ -12,17 +12,52 @@ pub fn mcp_config_path(config_path: Option<&str>) -> PathBuf {
         .unwrap_or_else(|| PathBuf::from("config.toml"))
 }
 
-/// Load full [`Config`] for `kowalski` server mode (HTTP chat + MCP). Missing file → [`Config::default`].
+/// Load full [`Config`] for `kowalski` server mode (HTTP chat + MCP). Missing file → [`Config::default`];
+/// `KOWALSKI_*` environment variables override either (see [`Config::load`]).
 pub fn load_kowalski_config_for_serve(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
     if !path.exists() {
         log::warn!(
             "No config at {} — using defaults (Ollama localhost; add config.toml for MCP/tools)",
             path.display()
         );
-        return Ok(Config::default());
     }
-    let raw = fs::read_to_string(path)?;
-    Ok(toml::from_str(&raw)?)
+    Ok(Config::load(path)?)
+}
+
+/// `kowalski-cli mcp-serve`: expose the built-in tools to MCP clients over stdio.
+///
+/// Serves `fs_tool`, `csv_tool`, `stats`, `image_tool`, `repo_map` (and `chart` with
+/// `--features charts`) confined to `root`, `calculator`, `datetime`, `html_to_markdown` and
+/// `memory` (key-value store in the configured episodic DB, namespace `mcp`). Logs go to stderr;
+/// stdout carries only JSON-RPC.
+pub async fn run_mcp_serve(
+    config_path: Option<&str>,
+    root: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use kowalski_core::tools::manager::ToolManager;
+    use kowalski_core::tools::{
+        CalculatorTool, CsvTool, DateTimeTool, FsTool, HtmlToMarkdownTool, ImageTool, MemoryTool,
+        RepoMapTool, StatsTool,
+    };
+
+    let path = mcp_config_path(config_path);
+    let cfg = load_kowalski_config_for_serve(&path)?;
+    kowalski_core::db::run_memory_migrations_if_configured(&cfg).await?;
+
+    let registry = ToolManager::new();
+    registry.register(FsTool::new(root));
+    registry.register(CsvTool::new().with_root(root));
+    registry.register(StatsTool::new().with_root(root));
+    #[cfg(feature = "charts")]
+    registry.register(kowalski_core::tools::ChartTool::new(cfg.charts.clone()).with_root(root));
+    registry.register(ImageTool::from_config(&cfg).with_root(root));
+    registry.register(RepoMapTool::new(root));
+    registry.register(CalculatorTool::new());
+    registry.register(DateTimeTool::new());
+    registry.register(HtmlToMarkdownTool::new());
+    registry.register(MemoryTool::open(&cfg.memory, "mcp").await?);
+    kowalski_core::mcp::serve_mcp(registry).await?;
+    Ok(())
 }
 
 /// Public MCP server metadata for JSON APIs (no auth headers).
@@ -67,7 +102,8 @@ pub struct DoctorOperatorJson {
 
 /// High-signal differences versus [`Config::default`] (for operators comparing deployments).
 pub fn config_divergence_lines(c: &Config) -> Vec<String> {
-    let d = Config::default();
+    let mut d = Config::default();
+    d.resolve_data_paths();
     let mut v = Vec::new();
     if c.ollama.model != d.ollama.model {
         v.push("ollama.model".into());
@@ -78,6 +114,9 @@ pub fn config_divergence_lines(c: &Config) -> Vec<String> {
     if c.memory.database_url.is_some() {
         v.push("memory.database_url set".into());
     }
+    if c.data_dir != d.data_dir {
+        v.push("data_dir".into());
+    }
     if c.memory.episodic_path != d.memory.episodic_path {
         v.push("memory.episodic_path".into());
     }
@@ -272,6 +311,182 @@ pub fn run_config_check(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// `config show`: `config` as TOML (or pretty JSON) with API keys, auth headers and URL
+/// passwords masked. Unset optional values are left out of the TOML.
+pub fn render_config(config: &Config, json: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let mut value = serde_json::to_value(config)?;
+    kowalski_core::utils::redact::redact_secrets(&mut value);
+    if json {
+        return Ok(serde_json::to_string_pretty(&value)?);
+    }
+    strip_nulls(&mut value);
+    Ok(toml::to_string(&value)?.trim_end().to_string())
+}
+
+fn strip_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One line of `config validate`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl ConfigCheck {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Whether `model` is in the backend's model list (`llama3.2` matches `llama3.2:latest`).
+pub fn model_available(models: &[String], model: &str) -> bool {
+    models
+        .iter()
+        .any(|m| m == model || m.strip_suffix(":latest") == Some(model))
+}
+
+/// `config validate`: can the LLM backend be reached, does it have the chat and embedding
+/// models, and are the memory paths usable.
+pub async fn validate_config(config: &Config) -> Vec<ConfigCheck> {
+    let mut checks = Vec::new();
+    let backend = match config.llm.provider.as_str() {
+        "openai" | "openai_compat" => config
+            .llm
+            .openai_api_base
+            .clone()
+            .unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
+        _ => format!("http://{}:{}", config.ollama.host, config.ollama.port),
+    };
+    let models = match kowalski_core::llm::create_llm_provider(config) {
+        Ok(llm) => llm.list_models().await,
+        Err(e) => Err(e),
+    };
+    match models {
+        Ok(models) => {
+            checks.push(ConfigCheck::new(
+                "llm",
+                CheckStatus::Ok,
+                format!(
+                    "{} at {} ({} models)",
+                    config.llm.provider,
+                    backend,
+                    models.len()
+                ),
+            ));
+            let embedding_model = config.embedding.model.clone().or_else(|| {
+                (config.llm.provider == "ollama").then(|| "nomic-embed-text".to_string())
+            });
+            let wanted = [
+                ("model", Some(config.ollama.model.clone())),
+                ("embedding", embedding_model),
+            ];
+            for (name, model) in wanted {
+                let Some(model) = model else { continue };
+                checks.push(if model_available(&models, &model) {
+                    ConfigCheck::new(name, CheckStatus::Ok, model)
+                } else {
+                    ConfigCheck::new(
+                        name,
+                        CheckStatus::Warn,
+                        format!(
+                            "{} is not available on {} (try `ollama pull {}`)",
+                            model, backend, model
+                        ),
+                    )
+                });
+            }
+        }
+        Err(e) => checks.push(ConfigCheck::new(
+            "llm",
+            CheckStatus::Fail,
+            format!("{} at {} unreachable: {}", config.llm.provider, backend, e),
+        )),
+    }
+
+    let episodic = Path::new(&config.memory.episodic_path);
+    let parent = episodic
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    checks.push(match fs::metadata(parent) {
+        Ok(meta) if meta.permissions().readonly() => ConfigCheck::new(
+            "memory",
+            CheckStatus::Fail,
+            format!("{} is read-only", parent.display()),
+        ),
+        Ok(_) => ConfigCheck::new("memory", CheckStatus::Ok, episodic.display().to_string()),
+        Err(_) => ConfigCheck::new(
+            "memory",
+            CheckStatus::Warn,
+            format!(
+                "{} does not exist yet (created on first use)",
+                parent.display()
+            ),
+        ),
+    });
+    if let Some(url) = &config.memory.database_url {
+        let mut shown = serde_json::Value::String(url.clone());
+        kowalski_core::utils::redact::redact_secrets(&mut shown);
+        let shown = shown.as_str().unwrap_or_default().to_string();
+        checks.push(
+            if kowalski_core::config::memory_uses_postgres(&config.memory)
+                && !cfg!(feature = "postgres")
+            {
+                ConfigCheck::new(
+                    "database",
+                    CheckStatus::Warn,
+                    format!("{} needs a build with --features postgres", shown),
+                )
+            } else {
+                ConfigCheck::new("database", CheckStatus::Ok, shown)
+            },
+        );
+    }
+    checks
+}
+
+/// Prints `checks`; fails when any check failed.
+pub fn report_config_checks(checks: &[ConfigCheck]) -> Result<(), Box<dyn std::error::Error>> {
+    for check in checks {
+        let label = match check.status {
+            CheckStatus::Ok => "OK  ",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        };
+        println!("{} {:<9} {}", label, check.name, check.detail);
+    }
+    let failed = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        return Err(format!("{} configuration check(s) failed", failed).into());
+    }
+    Ok(())
+}
+
 /// Run `memory.database_url` migrations from `--url` or from `memory.database_url` in TOML.
 pub async fn run_db_migrate(
     url: Option<String>,
@@ -342,3 +557,32 @@ fn load_optional_config_default_path() -> Option<Config> {
     let raw = fs::read_to_string(&path).ok()?;
     toml::from_str(&raw).ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shown_config_masks_secrets() {
+        let mut config = Config::default();
+        config.llm.openai_api_key = Some("sk-live".to_string());
+        config.memory.database_url = Some("postgres://k:hunter2@db/memory".to_string());
+        for json in [false, true] {
+            let shown = render_config(&config, json).unwrap();
+            assert!(
+                !shown.contains("sk-live") && !shown.contains("hunter2"),
+                "{shown}"
+            );
+            assert!(shown.contains("llama3.2"), "{shown}");
+        }
+        assert!(toml::from_str::<toml::Table>(&render_config(&config, false).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn models_match_with_or_without_latest_tag() {
+        let models = ["llama3.2:latest".to_string(), "qwen3:8b".to_string()];
+        assert!(model_available(&models, "llama3.2"));
+        assert!(model_available(&models, "qwen3:8b"));
+        assert!(!model_available(&models, "qwen3"));
+    }
+}
@@ -0,0 +1,170 @@
+//! Terminal output settings from the global `-v` / `-q` flags.
+//!
+//! | flags | log level | chat shows |
+//! |-------|-----------|------------|
+//! | `-q` | error | the answer only |
+//! | (none) | warn | the answer plus one line per tool call (on stderr) |
+//! | `-v` | info | also session settings and raw tool-call replies |
+//! | `-vv` / `-vvv` | debug / trace | also the debug / trace log |
+//!
+//! `RUST_LOG` still overrides the log level. Colours are used only when stdout is a terminal and
+//! `NO_COLOR` is unset.
+
+use colored::Colorize;
+use kowalski_core::agent::observer::AgentObserver;
+use kowalski_core::error::KowalskiError;
+use kowalski_core::progress::{Progress, ProgressReporter};
+use kowalski_core::tools::{ToolCall, ToolOutput};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicI8, Ordering};
+
+/// -1 quiet, 0 default, 1+ verbose.
+static LEVEL: AtomicI8 = AtomicI8::new(0);
+
+/// Records the verbosity, configures colours and core reply printing, and returns the default
+/// log filter.
+pub fn init(verbose: u8, quiet: bool) -> &'static str {
+    let level = if quiet {
+        -1
+    } else {
+        verbose.min(i8::MAX as u8) as i8
+    };
+    LEVEL.store(level, Ordering::Relaxed);
+    let colors = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    colored::control::set_override(colors);
+    kowalski_core::agent::repl_trace::set_verbose_replies(level >= 1);
+    log_filter(level)
+}
+
+fn log_filter(level: i8) -> &'static str {
+    match level {
+        i8::MIN..=-1 => "error",
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    }
+}
+
+pub fn is_quiet() -> bool {
+    LEVEL.load(Ordering::Relaxed) < 0
+}
+
+pub fn is_verbose() -> bool {
+    LEVEL.load(Ordering::Relaxed) > 0
+}
+
+/// Chat prompt label.
+pub fn user_prompt() -> String {
+    format!("{} ", "You:".bold().cyan())
+}
+
+/// Prints `→ <tool> <arguments>` to stderr for each tool call, the error when one fails, and
+/// the file a `chart` call wrote.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ToolSummaryObserver;
+
+impl AgentObserver for ToolSummaryObserver {
+    fn on_tool_call(&self, tool_name: &str, parameters: &serde_json::Value) {
+        let call = ToolCall {
+            name: tool_name.to_string(),
+            parameters: parameters.clone(),
+            reasoning: None,
+        };
+        let summary = crate::tool_approval::describe(&call);
+        let args = summary
+            .strip_prefix(tool_name)
+            .unwrap_or_default()
+            .trim_start();
+        eprintln!("{} {} {}", "→".dimmed(), tool_name.yellow(), args);
+    }
+
+    fn on_tool_result(&self, tool_name: &str, result: &Result<ToolOutput, KowalskiError>) {
+        match result {
+            Err(e) => eprintln!("  {} {}: {}", "✗".red(), tool_name.yellow(), e),
+            // The file is what the user asked for; don't leave it buried in the answer.
+            Ok(output) if tool_name == "chart" => {
+                if let Some(path) = output.result.get("path").and_then(|p| p.as_str()) {
+                    eprintln!("  {} chart saved to {}", "✓".green(), path.bold());
+                }
+            }
+            Ok(_) => {}
+        }
+    }
+}
+
+/// One self-updating `⠋ label [3/7] current` line on stderr for a long operation. Silent with
+/// `-q` or when stderr is not a terminal; [`finish`](Self::finish) clears the line.
+#[derive(Debug)]
+pub struct ProgressLine {
+    label: String,
+    enabled: bool,
+}
+
+impl ProgressLine {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            enabled: !is_quiet() && std::io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn finish(&self) {
+        if self.enabled {
+            eprint!("\r\x1b[2K");
+        }
+    }
+
+    fn line(&self, progress: &Progress) -> String {
+        let frame = Self::FRAMES[progress.done % Self::FRAMES.len()];
+        let count = match progress.total {
+            Some(total) => format!("[{}/{}]", progress.done, total),
+            None => format!("[{}]", progress.done),
+        };
+        let current = progress.current.as_deref().unwrap_or_default();
+        format!("{frame} {} {count} {current}", self.label)
+            .trim_end()
+            .to_string()
+    }
+}
+
+impl ProgressReporter for ProgressLine {
+    fn report(&self, progress: &Progress) {
+        if self.enabled {
+            eprint!("\r\x1b[2K{}", self.line(progress).dimmed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_maps_to_log_filters() {
+        assert_eq!(log_filter(-1), "error");
+        assert_eq!(log_filter(0), "warn");
+        assert_eq!(log_filter(1), "info");
+        assert_eq!(log_filter(2), "debug");
+        assert_eq!(log_filter(5), "trace");
+    }
+
+    #[test]
+    fn progress_lines_count_items() {
+        let line = ProgressLine::new("Analyzing");
+        let progress = Progress {
+            done: 2,
+            total: Some(5),
+            bytes: 0,
+            current: Some("methods".to_string()),
+        };
+        assert_eq!(line.line(&progress), "⠹ Analyzing [2/5] methods");
+        let open_ended = Progress {
+            done: 1,
+            ..Progress::default()
+        };
+        assert_eq!(line.line(&open_ended), "⠙ Analyzing [1]");
+    }
+}
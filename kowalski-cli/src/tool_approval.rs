@@ -0,0 +1,87 @@
+//! Asks on the terminal before an agent runs a tool: `Run fs_tool write_file /x? [y/N]`.
+//! Anything but `y`/`yes` denies the call; text after `n` is passed to the model as the reason
+//! (`n use /tmp instead`).
+
+use kowalski_core::agent::approval::{ToolApproval, ToolApprover};
+use kowalski_core::tools::ToolCall;
+use std::io::{self, BufRead, Write};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleApprover;
+
+impl ToolApprover for ConsoleApprover {
+    fn review(&self, call: &ToolCall) -> ToolApproval {
+        print!("Run {}? [y/N] ", describe(call));
+        let _ = io::stdout().flush();
+        let mut answer = String::new();
+        match io::stdin().lock().read_line(&mut answer) {
+            Ok(_) => parse_answer(&answer),
+            Err(_) => ToolApproval::Deny(None),
+        }
+    }
+}
+
+/// `<tool> <task> <other string parameters…>`, e.g. `fs_tool write_file /x`.
+pub fn describe(call: &ToolCall) -> String {
+    let mut parts = vec![call.name.clone()];
+    if let Some(params) = call.parameters.as_object() {
+        if let Some(task) = params.get("task").and_then(|v| v.as_str()) {
+            parts.push(task.to_string());
+        }
+        for (key, value) in params {
+            match value {
+                serde_json::Value::String(s) if key != "task" => parts.push(truncate(s)),
+                serde_json::Value::Number(n) => parts.push(n.to_string()),
+                _ => {}
+            }
+        }
+    }
+    parts.join(" ")
+}
+
+fn truncate(s: &str) -> String {
+    const MAX: usize = 60;
+    let first_line = s.lines().next().unwrap_or("");
+    if first_line.chars().count() > MAX || first_line.len() < s.len() {
+        format!("{}…", first_line.chars().take(MAX).collect::<String>())
+    } else {
+        s.to_string()
+    }
+}
+
+/// `y`/`yes` approves; anything else denies, with the text after `n`/`no` as the reason.
+pub fn parse_answer(answer: &str) -> ToolApproval {
+    let answer = answer.trim();
+    let (word, rest) = answer
+        .split_once(char::is_whitespace)
+        .unwrap_or((answer, ""));
+    match word.to_ascii_lowercase().as_str() {
+        "y" | "yes" => ToolApproval::Approve,
+        "n" | "no" if !rest.trim().is_empty() => ToolApproval::Deny(Some(rest.trim().to_string())),
+        _ => ToolApproval::Deny(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_calls_and_parses_answers() {
+        let call = ToolCall {
+            name: "fs_tool".to_string(),
+            parameters: serde_json::json!({"task": "write_file", "path": "/x", "content": "a\nb"}),
+            reasoning: None,
+        };
+        assert_eq!(describe(&call), "fs_tool write_file a… /x");
+
+        assert_eq!(parse_answer("y\n"), ToolApproval::Approve);
+        assert_eq!(parse_answer("YES"), ToolApproval::Approve);
+        assert_eq!(parse_answer("\n"), ToolApproval::Deny(None));
+        assert_eq!(parse_answer("yep"), ToolApproval::Deny(None));
+        assert_eq!(
+            parse_answer("n use /tmp instead\n"),
+            ToolApproval::Deny(Some("use /tmp instead".to_string()))
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! `kowalski-cli tool list/schema/run`: call an agent's tools directly, without the LLM, to
+//! debug them.
+//!
+//! ```text
+//! kowalski-cli tool list web
+//! kowalski-cli tool schema html_to_markdown --agent web
+//! kowalski-cli tool run web html_to_markdown --param html='<h1>Hi</h1>' --param strip_boilerplate=true
+//! ```
+//!
+//! `--param key=value` values are parsed by the parameter's declared [`ParameterType`];
+//! `--param-json` supplies a whole JSON object (individual `--param`s override its keys).
+
+use crate::agent_manager::{AgentManager, SessionOverrides};
+use crate::ask::AGENT_TYPES;
+use crate::error::KowalskiCliError;
+use kowalski_core::error::KowalskiError;
+use kowalski_core::tools::manager::ToolManager;
+use kowalski_core::tools::{ParameterType, ToolInput, ToolParameter};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+/// The tools of saved agent (or agent type) `agent`.
+pub async fn registry(
+    manager: &AgentManager,
+    agent: &str,
+) -> Result<ToolManager, Box<dyn std::error::Error>> {
+    let (agent, _) = manager
+        .build_agent(agent, AGENT_TYPES, &SessionOverrides::default())
+        .await?;
+    agent
+        .tool_manager()
+        .cloned()
+        .ok_or_else(|| format!("Agent '{}' has no tool registry", agent.name()).into())
+}
+
+/// `tool list`: one `name  description` line per tool, or the list as JSON / YAML.
+pub async fn list(
+    registry: &ToolManager,
+    format: Option<ToolFormat>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    #[derive(Serialize)]
+    struct Entry {
+        name: String,
+        description: String,
+    }
+    let mut tools = registry.list_tools().await;
+    tools.sort();
+    let Some(format) = format else {
+        if tools.is_empty() {
+            return Ok("No tools registered.".to_string());
+        }
+        let width = tools.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        let lines: Vec<String> = tools
+            .iter()
+            .map(|(name, description)| format!("{:width$}  {}", name, description))
+            .collect();
+        return Ok(lines.join("\n"));
+    };
+    let entries: Vec<Entry> = tools
+        .into_iter()
+        .map(|(name, description)| Entry { name, description })
+        .collect();
+    render(&entries, format)
+}
+
+/// `tool schema`: the tool's function schema, as sent to the LLM.
+pub async fn schema(
+    registry: &ToolManager,
+    tool: &str,
+    format: ToolFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let schema = registry.generate_json_schema().await;
+    let function = schema
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|entry| &entry["function"])
+        .find(|function| function["name"] == tool)
+        .ok_or_else(|| not_found(registry, tool))?;
+    render(function, format)
+}
+
+/// `tool run`: executes `tool` with the given parameters and renders its [`ToolOutput`].
+///
+/// [`ToolOutput`]: kowalski_core::tools::ToolOutput
+pub async fn run(
+    registry: &ToolManager,
+    tool: &str,
+    params: &[String],
+    params_json: Option<&str>,
+    format: ToolFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let declared = registry
+        .parameters(tool)
+        .await
+        .ok_or_else(|| not_found(registry, tool))?;
+    let parameters = build_parameters(&declared, params, params_json)?;
+    let output = registry
+        .execute(tool, ToolInput::from_parameters(parameters))
+        .await?;
+    render(&output, format)
+}
+
+/// Merges `--param-json` and `--param key=value` pairs into a parameters object.
+pub fn build_parameters(
+    declared: &[ToolParameter],
+    params: &[String],
+    params_json: Option<&str>,
+) -> Result<Value, KowalskiCliError> {
+    let mut parameters = match params_json {
+        Some(raw) => match serde_json::from_str(raw) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => {
+                return Err(KowalskiCliError::Config(
+                    "--param-json must be a JSON object".to_string(),
+                ));
+            }
+            Err(e) => {
+                return Err(KowalskiCliError::Config(format!(
+                    "Invalid --param-json: {}",
+                    e
+                )));
+            }
+        },
+        None => Map::new(),
+    };
+    for param in params {
+        let (key, raw) = param.split_once('=').ok_or_else(|| {
+            KowalskiCliError::Config(format!("Expected --param key=value, got '{}'", param))
+        })?;
+        // Undeclared keys (e.g. `task` on multi-task tools) are passed through as strings.
+        let parameter_type = declared
+            .iter()
+            .find(|p| p.name == key)
+            .map_or(&ParameterType::String, |p| &p.parameter_type);
+        let value = parameter_type
+            .coerce(raw)
+            .map_err(|e| KowalskiCliError::Config(format!("--param {}: {}", key, e)))?;
+        parameters.insert(key.to_string(), value);
+    }
+    Ok(Value::Object(parameters))
+}
+
+fn not_found(registry: &ToolManager, tool: &str) -> Box<dyn std::error::Error> {
+    Box::new(KowalskiError::ToolNotFound {
+        name: tool.to_string(),
+        available: registry.tool_names(),
+    })
+}
+
+fn render<T: Serialize + ?Sized>(
+    value: &T,
+    format: ToolFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(match format {
+        ToolFormat::Json => serde_json::to_string_pretty(value)?,
+        ToolFormat::Yaml => serde_yaml::to_string(value)?.trim_end().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn param(name: &str, parameter_type: ParameterType) -> ToolParameter {
+        ToolParameter {
+            name: name.to_string(),
+            description: String::new(),
+            required: false,
+            default_value: None,
+            parameter_type,
+        }
+    }
+
+    #[test]
+    fn params_are_coerced_to_their_declared_types() {
+        let declared = [
+            param("limit", ParameterType::Number),
+            param("recursive", ParameterType::Boolean),
+        ];
+        let params = [
+            "limit=10".to_string(),
+            "recursive=true".to_string(),
+            "path=a=b".to_string(),
+        ];
+        let built =
+            build_parameters(&declared, &params, Some(r#"{"limit": 1, "task": "list"}"#)).unwrap();
+        assert_eq!(
+            built,
+            json!({"limit": 10, "recursive": true, "path": "a=b", "task": "list"})
+        );
+
+        assert!(build_parameters(&declared, &["limit=lots".to_string()], None).is_err());
+        assert!(build_parameters(&declared, &["limit".to_string()], None).is_err());
+        assert!(build_parameters(&declared, &[], Some("[1]")).is_err());
+    }
+}
@@ -0,0 +1,781 @@
+//! `kowalski-cli tui` (feature `tui`): full-screen chat with a conversation pane, a live
+//! tool-activity pane and a collapsible pane showing the memory injected into the prompt.
+//!
+//! | key | action |
+//! |-----|--------|
+//! | Enter | send the message |
+//! | Tab / Shift+Tab | next / previous agent (each keeps its own conversation) |
+//! | Esc | cancel the running generation |
+//! | Ctrl+Y | copy the last answer (OSC 52, so it also works over SSH) |
+//! | Ctrl+R | show / hide the memory pane |
+//! | PgUp / PgDn | scroll the conversation |
+//! | y / n | answer a tool approval prompt |
+//! | Ctrl+C | quit |
+//!
+//! The screen only uses the public agent API: an [`AgentObserver`] forwards tool calls and the
+//! prompt's memory over a channel, a [`ToolApprover`] asks in the input box, and each turn runs
+//! through [`run_tool_loop`].
+
+use crate::agent_manager::{AgentManager, BoxedAgent, SessionOverrides};
+use crate::ask::AGENT_TYPES;
+use crate::tool_approval::describe;
+use base64::Engine;
+use kowalski_core::agent::approval::{ToolApproval, ToolApprover};
+use kowalski_core::agent::injected_memories;
+use kowalski_core::agent::observer::AgentObserver;
+use kowalski_core::agent::tool_loop::{DEFAULT_MAX_TOOL_ITERATIONS, run_tool_loop};
+use kowalski_core::conversation::Message;
+use kowalski_core::error::KowalskiError;
+use kowalski_core::tools::{ToolCall, ToolOutput};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Tabs, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::Write;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// What an agent reports to the screen; sent as `(agent index, event)`.
+#[derive(Debug)]
+pub enum TuiEvent {
+    ToolStarted {
+        name: String,
+        parameters: serde_json::Value,
+    },
+    ToolFinished {
+        name: String,
+        elapsed: Duration,
+        error: Option<String>,
+    },
+    /// Memory items injected into the latest LLM request (empty when there were none).
+    MemoryRecalled(Vec<String>),
+    ApprovalRequested {
+        description: String,
+        reply: mpsc::Sender<ToolApproval>,
+    },
+    /// The turn ended with an answer or an error message.
+    TurnFinished(Result<String, String>),
+}
+
+type EventSender = mpsc::Sender<(usize, TuiEvent)>;
+
+/// Forwards one agent's tool calls and memory recall to the screen.
+pub struct ChannelObserver {
+    agent: usize,
+    events: EventSender,
+    tool_started: Mutex<Option<Instant>>,
+}
+
+impl ChannelObserver {
+    pub fn new(agent: usize, events: EventSender) -> Self {
+        Self {
+            agent,
+            events,
+            tool_started: Mutex::new(None),
+        }
+    }
+
+    fn send(&self, event: TuiEvent) {
+        // The screen is gone once the receiver is dropped; nothing left to tell.
+        let _ = self.events.send((self.agent, event));
+    }
+}
+
+impl AgentObserver for ChannelObserver {
+    fn on_llm_request(&self, _conversation_id: &str, _model: &str, messages: &[Message]) {
+        self.send(TuiEvent::MemoryRecalled(injected_memories(messages)));
+    }
+
+    fn on_tool_call(&self, tool_name: &str, parameters: &serde_json::Value) {
+        *self.tool_started.lock().unwrap() = Some(Instant::now());
+        self.send(TuiEvent::ToolStarted {
+            name: tool_name.to_string(),
+            parameters: parameters.clone(),
+        });
+    }
+
+    fn on_tool_result(&self, tool_name: &str, result: &Result<ToolOutput, KowalskiError>) {
+        let started = self.tool_started.lock().unwrap().take();
+        self.send(TuiEvent::ToolFinished {
+            name: tool_name.to_string(),
+            elapsed: started.map(|s| s.elapsed()).unwrap_or_default(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+    }
+}
+
+/// Asks in the input box before a tool runs; blocks the agent task until `y` or `n`.
+pub struct ScreenApprover {
+    agent: usize,
+    events: EventSender,
+}
+
+impl ScreenApprover {
+    pub fn new(agent: usize, events: EventSender) -> Self {
+        Self { agent, events }
+    }
+}
+
+impl ToolApprover for ScreenApprover {
+    fn review(&self, call: &ToolCall) -> ToolApproval {
+        let (reply, answer) = mpsc::channel();
+        let request = TuiEvent::ApprovalRequested {
+            description: describe(call),
+            reply,
+        };
+        if self.events.send((self.agent, request)).is_err() {
+            return ToolApproval::Deny(None);
+        }
+        answer.recv().unwrap_or(ToolApproval::Deny(None))
+    }
+}
+
+/// One row of the tool-activity pane; `elapsed` is `None` while the tool runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolActivity {
+    pub name: String,
+    pub parameters: String,
+    pub elapsed: Option<Duration>,
+    pub error: Option<String>,
+}
+
+/// Per-agent panes.
+#[derive(Debug, Default)]
+pub struct AgentView {
+    pub name: String,
+    /// `(speaker, text)`: `you`, the agent's name, or `error`.
+    pub transcript: Vec<(String, String)>,
+    pub tools: Vec<ToolActivity>,
+    pub memory: Vec<String>,
+}
+
+/// Side effects of a key press, carried out by the event loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    None,
+    Send { agent: usize, text: String },
+    Cancel(usize),
+    Copy(String),
+    Quit,
+}
+
+/// Screen state; events and keys go in, [`App::render`] draws it.
+#[derive(Debug)]
+pub struct App {
+    pub agents: Vec<AgentView>,
+    pub current: usize,
+    pub input: String,
+    /// Agent whose turn is running.
+    pub busy: Option<usize>,
+    pub show_memory: bool,
+    /// Lines scrolled up from the bottom of the conversation.
+    pub scroll: u16,
+    pub status: String,
+    approval: Option<(String, mpsc::Sender<ToolApproval>)>,
+}
+
+const READY: &str = "Message";
+
+impl App {
+    pub fn new(names: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            agents: names
+                .into_iter()
+                .map(|name| AgentView {
+                    name,
+                    ..AgentView::default()
+                })
+                .collect(),
+            current: 0,
+            input: String::new(),
+            busy: None,
+            show_memory: true,
+            scroll: 0,
+            status: READY.to_string(),
+            approval: None,
+        }
+    }
+
+    pub fn apply(&mut self, agent: usize, event: TuiEvent) {
+        let Some(view) = self.agents.get_mut(agent) else {
+            return;
+        };
+        match event {
+            TuiEvent::ToolStarted { name, parameters } => view.tools.push(ToolActivity {
+                name,
+                parameters: parameters.to_string(),
+                elapsed: None,
+                error: None,
+            }),
+            TuiEvent::ToolFinished {
+                name,
+                elapsed,
+                error,
+            } => {
+                if let Some(activity) = view
+                    .tools
+                    .iter_mut()
+                    .rev()
+                    .find(|a| a.name == name && a.elapsed.is_none())
+                {
+                    activity.elapsed = Some(elapsed);
+                    activity.error = error;
+                }
+            }
+            TuiEvent::MemoryRecalled(items) => view.memory = items,
+            TuiEvent::ApprovalRequested { description, reply } => {
+                self.approval = Some((description, reply));
+            }
+            TuiEvent::TurnFinished(result) => {
+                match result {
+                    Ok(answer) => view.transcript.push((view.name.clone(), answer)),
+                    Err(e) => view.transcript.push(("error".to_string(), e)),
+                }
+                if self.busy == Some(agent) {
+                    self.busy = None;
+                    self.status = READY.to_string();
+                }
+            }
+        }
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        if self.approval.is_some() {
+            let answer = match key.code {
+                KeyCode::Char('y') => ToolApproval::Approve,
+                KeyCode::Char('n') | KeyCode::Esc => ToolApproval::Deny(None),
+                _ => return Action::None,
+            };
+            self.answer_approval(answer);
+            if key.code != KeyCode::Esc {
+                return Action::None;
+            }
+        }
+        match key.code {
+            KeyCode::Esc => match self.busy.take() {
+                Some(agent) => {
+                    self.status = "Cancelled".to_string();
+                    Action::Cancel(agent)
+                }
+                None => Action::None,
+            },
+            KeyCode::Tab => self.switch_agent(1),
+            KeyCode::BackTab => self.switch_agent(self.agents.len().saturating_sub(1)),
+            KeyCode::Char('y') if ctrl => {
+                let view = &self.agents[self.current];
+                match view
+                    .transcript
+                    .iter()
+                    .rev()
+                    .find(|(who, _)| *who == view.name)
+                {
+                    Some((_, answer)) => {
+                        self.status = "Copied last answer".to_string();
+                        Action::Copy(answer.clone())
+                    }
+                    None => {
+                        self.status = "Nothing to copy yet".to_string();
+                        Action::None
+                    }
+                }
+            }
+            KeyCode::Char('r') if ctrl => {
+                self.show_memory = !self.show_memory;
+                Action::None
+            }
+            KeyCode::PageUp => {
+                self.scroll = self.scroll.saturating_add(5);
+                Action::None
+            }
+            KeyCode::PageDown => {
+                self.scroll = self.scroll.saturating_sub(5);
+                Action::None
+            }
+            KeyCode::Enter => {
+                let text = self.input.trim().to_string();
+                if self.busy.is_some() {
+                    self.status = "Still answering (Esc cancels)".to_string();
+                    return Action::None;
+                }
+                if text.is_empty() {
+                    return Action::None;
+                }
+                self.input.clear();
+                self.scroll = 0;
+                self.agents[self.current]
+                    .transcript
+                    .push(("you".to_string(), text.clone()));
+                self.busy = Some(self.current);
+                self.status = "Thinking… (Esc cancels)".to_string();
+                Action::Send {
+                    agent: self.current,
+                    text,
+                }
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                Action::None
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.input.push(c);
+                Action::None
+            }
+            _ => Action::None,
+        }
+    }
+
+    fn answer_approval(&mut self, answer: ToolApproval) {
+        if let Some((_, reply)) = self.approval.take() {
+            let _ = reply.send(answer);
+        }
+    }
+
+    fn switch_agent(&mut self, step: usize) -> Action {
+        if !self.agents.is_empty() {
+            self.current = (self.current + step) % self.agents.len();
+            self.scroll = 0;
+        }
+        Action::None
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let [tabs, body, input, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(4),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [conversation, side] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(body);
+        let memory_height = if self.show_memory {
+            Constraint::Percentage(40)
+        } else {
+            Constraint::Length(1)
+        };
+        let [tools, memory] = Layout::vertical([Constraint::Min(3), memory_height]).areas(side);
+
+        let names = self.agents.iter().map(|a| a.name.as_str());
+        frame.render_widget(
+            Tabs::new(names)
+                .select(self.current)
+                .highlight_style(Style::new().bold().reversed()),
+            tabs,
+        );
+        let Some(view) = self.agents.get(self.current) else {
+            return;
+        };
+        self.render_conversation(frame, view, conversation);
+        render_tools(frame, view, tools);
+        self.render_memory(frame, view, memory);
+
+        let title = match &self.approval {
+            Some((description, _)) => format!(" Run {}? [y/n] ", description),
+            None => format!(" {} ", self.status),
+        };
+        frame.render_widget(
+            Paragraph::new(self.input.as_str()).block(Block::bordered().title(title)),
+            input,
+        );
+        if self.approval.is_none() {
+            let x = input.x + 1 + self.input.chars().count() as u16;
+            frame.set_cursor_position((x.min(input.right().saturating_sub(2)), input.y + 1));
+        }
+        frame.render_widget(
+            Line::from(" Enter send  Tab agent  Esc cancel  ^Y copy  ^R memory  ^C quit".dim()),
+            help,
+        );
+    }
+
+    fn render_conversation(&self, frame: &mut Frame, view: &AgentView, area: Rect) {
+        let mut lines = Vec::new();
+        for (who, text) in &view.transcript {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            let style = match who.as_str() {
+                "you" => Style::new().cyan().bold(),
+                "error" => Style::new().red().bold(),
+                _ => Style::new().green().bold(),
+            };
+            let mut text_lines = text.lines();
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}: ", who), style),
+                Span::raw(text_lines.next().unwrap_or("").to_string()),
+            ]));
+            lines.extend(text_lines.map(|l| Line::from(l.to_string())));
+        }
+        let width = area.width.saturating_sub(2).max(1) as usize;
+        let height = area.height.saturating_sub(2);
+        let wrapped: usize = lines.iter().map(|l| l.width().max(1).div_ceil(width)).sum();
+        let offset = (wrapped as u16)
+            .saturating_sub(height)
+            .saturating_sub(self.scroll);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .scroll((offset, 0))
+                .block(Block::bordered().title(" Conversation ")),
+            area,
+        );
+    }
+
+    fn render_memory(&self, frame: &mut Frame, view: &AgentView, area: Rect) {
+        if !self.show_memory {
+            let title = format!(" Memory ({}) ^R to expand ", view.memory.len());
+            frame.render_widget(Block::new().borders(Borders::TOP).title(title), area);
+            return;
+        }
+        let items: Vec<ListItem> = view
+            .memory
+            .iter()
+            .map(|m| ListItem::new(format!("• {}", m.replace('\n', " "))))
+            .collect();
+        frame.render_widget(
+            List::new(items).block(Block::bordered().title(" Memory in prompt ")),
+            area,
+        );
+    }
+}
+
+fn render_tools(frame: &mut Frame, view: &AgentView, area: Rect) {
+    let visible = area.height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = view
+        .tools
+        .iter()
+        .skip(view.tools.len().saturating_sub(visible))
+        .map(|activity| {
+            let (mark, style) = match (&activity.elapsed, &activity.error) {
+                (None, _) => ("…", Style::new().yellow()),
+                (Some(_), None) => ("✓", Style::new().green()),
+                (Some(_), Some(_)) => ("✗", Style::new().red()),
+            };
+            let timing = activity
+                .elapsed
+                .map_or_else(|| "running".to_string(), format_duration);
+            let detail = activity.error.as_ref().unwrap_or(&activity.parameters);
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", mark), style),
+                Span::raw(format!("{} ", activity.name)).bold(),
+                Span::raw(format!("{} ", timing)).dim(),
+                Span::raw(detail.clone()),
+            ]))
+        })
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(" Tool activity ")),
+        area,
+    );
+}
+
+fn format_duration(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) {
+        format!("{}ms", elapsed.as_millis())
+    } else {
+        format!("{:.1}s", elapsed.as_secs_f32())
+    }
+}
+
+/// Copies through the terminal (OSC 52), so no clipboard library or display server is needed.
+fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", encoded)?;
+    stdout.flush()
+}
+
+struct Session {
+    agent: Arc<tokio::sync::Mutex<BoxedAgent>>,
+    conversation_id: String,
+}
+
+/// Builds `names` (saved agents or agent types) and runs the TUI until Ctrl+C.
+pub async fn run(
+    manager: &AgentManager,
+    names: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (events, received) = mpsc::channel();
+    let mut sessions = Vec::new();
+    for (index, name) in names.iter().enumerate() {
+        let (mut agent, config) = manager
+            .build_agent(name, AGENT_TYPES, &SessionOverrides::default())
+            .await?;
+        let base = agent
+            .base_agent_mut()
+            .ok_or_else(|| format!("Agent '{}' does not accept observers", name))?;
+        // The default observers and approver write to the terminal the TUI is drawing on.
+        base.clear_observers();
+        base.add_observer(Box::new(ChannelObserver::new(index, events.clone())));
+        base.set_tool_approver(Box::new(ScreenApprover::new(index, events.clone())));
+        let conversation_id = agent.start_conversation(&config.ollama.model);
+        sessions.push(Session {
+            agent: Arc::new(tokio::sync::Mutex::new(agent)),
+            conversation_id,
+        });
+    }
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, App::new(names), &sessions, events, received);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    mut app: App,
+    sessions: &[Session],
+    events: EventSender,
+    received: mpsc::Receiver<(usize, TuiEvent)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut running: Option<tokio::task::JoinHandle<()>> = None;
+    loop {
+        while let Ok((agent, event)) = received.try_recv() {
+            app.apply(agent, event);
+        }
+        terminal.draw(|frame| app.render(frame))?;
+        if !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match app.on_key(key) {
+            Action::None => {}
+            Action::Quit => break,
+            Action::Copy(text) => copy_to_clipboard(&text)?,
+            Action::Cancel(_) => {
+                if let Some(task) = running.take() {
+                    task.abort();
+                }
+            }
+            Action::Send { agent, text } => {
+                let session = &sessions[agent];
+                let handle = session.agent.clone();
+                let conversation_id = session.conversation_id.clone();
+                let events = events.clone();
+                running = Some(tokio::spawn(async move {
+                    let mut agent_ref = handle.lock().await;
+                    let result = run_tool_loop(
+                        &mut **agent_ref,
+                        &conversation_id,
+                        &text,
+                        DEFAULT_MAX_TOOL_ITERATIONS,
+                    )
+                    .await
+                    .map(|outcome| outcome.answer)
+                    .map_err(|e| e.to_string());
+                    let _ = events.send((agent, TuiEvent::TurnFinished(result)));
+                }));
+            }
+        }
+    }
+    if let Some(task) = running {
+        task.abort();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use serde_json::json;
+
+    fn screen(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(64, 16)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .map(|row| {
+                let line: String = row.iter().map(|cell| cell.symbol()).collect();
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            assert_eq!(app.on_key(key(KeyCode::Char(c))), Action::None);
+        }
+    }
+
+    /// A tool turn as the observer and approver report it.
+    fn scripted_turn(app: &mut App) -> mpsc::Receiver<ToolApproval> {
+        type_text(app, "convert it");
+        assert_eq!(
+            app.on_key(key(KeyCode::Enter)),
+            Action::Send {
+                agent: 0,
+                text: "convert it".to_string()
+            }
+        );
+        app.apply(
+            0,
+            TuiEvent::MemoryRecalled(vec!["likes tea".to_string(), "[user] hi".to_string()]),
+        );
+        let (reply, answer) = mpsc::channel();
+        app.apply(
+            0,
+            TuiEvent::ApprovalRequested {
+                description: "html_to_markdown <b>x</b>".to_string(),
+                reply,
+            },
+        );
+        answer
+    }
+
+    #[test]
+    fn scripted_tool_turn_renders_all_panes() {
+        let mut app = App::new(["web".to_string(), "code".to_string()]);
+        let answer = scripted_turn(&mut app);
+        assert_eq!(
+            screen(&app),
+            [
+                " web │ code",
+                "┌ Conversation ──────────────────────┐┌ Tool activity ─────────┐",
+                "│you: convert it                     ││                        │",
+                "│                                    ││                        │",
+                "│                                    ││                        │",
+                "│                                    ││                        │",
+                "│                                    ││                        │",
+                "│                                    │└────────────────────────┘",
+                "│                                    │┌ Memory in prompt ──────┐",
+                "│                                    ││• likes tea             │",
+                "│                                    ││• [user] hi             │",
+                "└────────────────────────────────────┘└────────────────────────┘",
+                "┌ Run html_to_markdown <b>x</b>? [y/n] ────────────────────────┐",
+                "│                                                              │",
+                "└──────────────────────────────────────────────────────────────┘",
+                " Enter send  Tab agent  Esc cancel  ^Y copy  ^R memory  ^C quit",
+            ]
+            .join("\n")
+        );
+
+        assert_eq!(app.on_key(key(KeyCode::Char('y'))), Action::None);
+        assert_eq!(answer.recv().unwrap(), ToolApproval::Approve);
+        app.apply(
+            0,
+            TuiEvent::ToolStarted {
+                name: "html_to_markdown".to_string(),
+                parameters: json!({"html": "<b>x</b>"}),
+            },
+        );
+        app.apply(
+            0,
+            TuiEvent::ToolFinished {
+                name: "html_to_markdown".to_string(),
+                elapsed: Duration::from_millis(12),
+                error: None,
+            },
+        );
+        app.apply(
+            0,
+            TuiEvent::ToolStarted {
+                name: "web_search".to_string(),
+                parameters: json!({"query": "x"}),
+            },
+        );
+        app.apply(
+            0,
+            TuiEvent::TurnFinished(Ok("It is **x**.\nAnything else?".to_string())),
+        );
+        app.on_key(ctrl('r'));
+        assert_eq!(
+            screen(&app),
+            [
+                " web │ code",
+                "┌ Conversation ──────────────────────┐┌ Tool activity ─────────┐",
+                "│you: convert it                     ││✓ html_to_markdown 12ms │",
+                "│                                    ││… web_search running {\"q│",
+                "│web: It is **x**.                   ││                        │",
+                "│Anything else?                      ││                        │",
+                "│                                    ││                        │",
+                "│                                    ││                        │",
+                "│                                    ││                        │",
+                "│                                    ││                        │",
+                "│                                    │└────────────────────────┘",
+                "└────────────────────────────────────┘ Memory (2) ^R to expand ─",
+                "┌ Message ─────────────────────────────────────────────────────┐",
+                "│                                                              │",
+                "└──────────────────────────────────────────────────────────────┘",
+                " Enter send  Tab agent  Esc cancel  ^Y copy  ^R memory  ^C quit",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn keys_switch_agents_cancel_and_copy() {
+        let mut app = App::new(["web".to_string(), "code".to_string()]);
+        assert_eq!(app.on_key(ctrl('y')), Action::None);
+        let answer = scripted_turn(&mut app);
+
+        // Esc denies the pending tool call and cancels the turn.
+        assert_eq!(app.on_key(key(KeyCode::Esc)), Action::Cancel(0));
+        assert_eq!(answer.recv().unwrap(), ToolApproval::Deny(None));
+        assert_eq!(app.busy, None);
+        assert_eq!(app.on_key(key(KeyCode::Esc)), Action::None);
+
+        app.on_key(key(KeyCode::Tab));
+        assert_eq!(app.current, 1);
+        type_text(&mut app, "hi");
+        app.on_key(key(KeyCode::Enter));
+        // Only one turn runs at a time.
+        app.on_key(key(KeyCode::BackTab));
+        type_text(&mut app, "again");
+        assert_eq!(app.on_key(key(KeyCode::Enter)), Action::None);
+        assert_eq!(app.input, "again");
+
+        app.apply(1, TuiEvent::TurnFinished(Ok("hello".to_string())));
+        assert_eq!(app.busy, None);
+        assert_eq!(app.on_key(ctrl('y')), Action::None);
+        app.on_key(key(KeyCode::Tab));
+        assert_eq!(app.on_key(ctrl('y')), Action::Copy("hello".to_string()));
+        assert_eq!(app.on_key(ctrl('c')), Action::Quit);
+    }
+
+    #[test]
+    fn observer_reports_tool_timing_and_memory() {
+        let (events, received) = mpsc::channel();
+        let observer = ChannelObserver::new(3, events);
+        observer.on_tool_call("html_to_markdown", &json!({"html": "<b>x</b>"}));
+        observer.on_tool_result(
+            "html_to_markdown",
+            &Err(KowalskiError::ToolExecution("boom".to_string())),
+        );
+        observer.on_llm_request("c1", "m", &[]);
+
+        let received: Vec<(usize, TuiEvent)> = received.try_iter().collect();
+        assert!(
+            matches!(&received[0], (3, TuiEvent::ToolStarted { name, .. }) if name == "html_to_markdown")
+        );
+        assert!(
+            matches!(&received[1], (3, TuiEvent::ToolFinished { error: Some(e), .. }) if e.contains("boom"))
+        );
+        assert!(matches!(&received[2], (3, TuiEvent::MemoryRecalled(items)) if items.is_empty()));
+    }
+}
@@ -0,0 +1,407 @@
+//! `kowalski-cli serve` (feature `server`): agents over a WebSocket at `GET /ws`, one JSON object
+//! per text frame.
+//!
+//! Client messages ([`ClientMessage`]):
+//! - `{"type": "create_agent", "name": "d1", "agent_type": "data", ...}` → `agent_created`
+//! - `{"type": "list_agents"}` → `agents` (saved agents and the agent types)
+//! - `{"type": "chat", "agent": "d1", "message": "..."}` → `start`, then `tool_call` /
+//!   `tool_result` / `token` frames as the agent works, then `done` with the whole answer
+//!
+//! Anything that fails is answered with an `error` frame; the socket stays open. As in `chat`, an
+//! agent is a saved agent or an agent type. Each connection builds its own agents on first use
+//! and keeps one conversation per agent, so clients do not see each other's history. Turns run
+//! on the shared tool loop ([`run_tool_loop_streaming`]), like `kowalski::server`'s. Tool calls
+//! have no one to approve them, so tools that change files are refused; and since anyone who can
+//! connect can run the others, [`serve`] only listens beyond loopback with a bearer token.
+
+use crate::agent_manager::{AgentManager, BoxedAgent, SessionOverrides};
+use crate::agent_store::AgentDefinition;
+use crate::ask::AGENT_TYPES;
+use axum::Router;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use futures::{SinkExt, StreamExt};
+use kowalski_core::agent::tool_loop::{ToolLoopEvent, ToolLoopOptions, run_tool_loop_streaming};
+use kowalski_core::error::KowalskiError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Options for [`router`] / [`serve`].
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    /// When set, `/ws` requires `Authorization: Bearer <token>` or `?token=<token>` (browsers
+    /// cannot set headers on a WebSocket).
+    pub bearer_token: Option<String>,
+}
+
+/// A request from the client.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Like `create`: saves the agent, replacing one with the same name.
+    CreateAgent {
+        name: String,
+        agent_type: String,
+        #[serde(default)]
+        system_prompt: Option<String>,
+        #[serde(default)]
+        temperature: Option<f32>,
+        #[serde(default)]
+        model: Option<String>,
+    },
+    ListAgents,
+    Chat {
+        agent: String,
+        message: String,
+    },
+}
+
+/// A saved agent as listed in [`ServerFrame::Agents`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentSummary {
+    pub name: String,
+    pub agent_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// A frame sent to the client.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerFrame {
+    AgentCreated {
+        name: String,
+    },
+    Agents {
+        agents: Vec<AgentSummary>,
+        types: Vec<String>,
+    },
+    Start {
+        agent: String,
+        conversation_id: String,
+    },
+    /// Part of the answer, as the model writes it (see [`ToolLoopEvent::Token`]).
+    Token {
+        content: String,
+    },
+    ToolCall {
+        name: String,
+        parameters: serde_json::Value,
+    },
+    ToolResult {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result: Option<serde_json::Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    Done {
+        agent: String,
+        content: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl From<ToolLoopEvent> for ServerFrame {
+    fn from(event: ToolLoopEvent) -> Self {
+        match event {
+            ToolLoopEvent::Token(content) => ServerFrame::Token { content },
+            ToolLoopEvent::ToolCall { name, parameters } => {
+                ServerFrame::ToolCall { name, parameters }
+            }
+            ToolLoopEvent::ToolResult {
+                name,
+                result,
+                success: true,
+            } => ServerFrame::ToolResult {
+                name,
+                result: Some(
+                    serde_json::from_str(&result).unwrap_or(serde_json::Value::String(result)),
+                ),
+                error: None,
+            },
+            ToolLoopEvent::ToolResult { name, result, .. } => ServerFrame::ToolResult {
+                name,
+                result: None,
+                error: Some(result),
+            },
+        }
+    }
+}
+
+type FrameSender = mpsc::UnboundedSender<ServerFrame>;
+
+#[derive(Clone)]
+struct ServerState {
+    manager: Arc<AgentManager>,
+    options: Arc<ServeOptions>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct WsQuery {
+    token: Option<String>,
+}
+
+/// One agent of a connection and its conversation.
+struct Session {
+    agent: BoxedAgent,
+    conversation_id: String,
+}
+
+/// Routes `GET /ws` and `GET /healthz` (which never requires auth).
+pub fn router(manager: Arc<AgentManager>, options: ServeOptions) -> Router {
+    let state = ServerState {
+        manager,
+        options: Arc::new(options),
+    };
+    Router::new()
+        .route("/ws", get(upgrade))
+        .route(
+            "/healthz",
+            get(|| async {
+                axum::Json(serde_json::json!({
+                    "status": "ok",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }))
+            }),
+        )
+        .with_state(state)
+}
+
+/// Serves [`router`] on `listener` until `shutdown` resolves. Refuses to serve on a non-loopback
+/// address without a bearer token.
+pub async fn serve<F>(
+    manager: Arc<AgentManager>,
+    listener: tokio::net::TcpListener,
+    options: ServeOptions,
+    shutdown: F,
+) -> Result<(), KowalskiError>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    check_bind(listener.local_addr()?, &options)?;
+    axum::serve(listener, router(manager, options))
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(|e| KowalskiError::Server(format!("websocket server: {e}")))
+}
+
+/// Fails for a non-loopback `addr` when `options` has no bearer token.
+pub fn check_bind(addr: std::net::SocketAddr, options: &ServeOptions) -> Result<(), KowalskiError> {
+    if !addr.ip().is_loopback() && options.bearer_token.is_none() {
+        return Err(KowalskiError::Configuration(format!(
+            "refusing to serve agents on {addr} without a bearer token: set one (--token) or bind \
+             to a loopback address"
+        )));
+    }
+    Ok(())
+}
+
+async fn upgrade(
+    State(state): State<ServerState>,
+    Query(query): Query<WsQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if let Some(expected) = state.options.bearer_token.as_deref() {
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .or(query.token.as_deref());
+        if !provided.is_some_and(|provided| tokens_match(provided, expected)) {
+            return (StatusCode::UNAUTHORIZED, "missing or invalid bearer token").into_response();
+        }
+    }
+    ws.on_upgrade(move |socket| connection(socket, state.manager))
+}
+
+/// Compares a token without returning early at the first difference, so response times do not
+/// reveal how much of it a guess got right.
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    let (provided, expected) = (provided.as_bytes(), expected.as_bytes());
+    let differences = provided
+        .iter()
+        .zip(expected)
+        .fold(provided.len() ^ expected.len(), |acc, (a, b)| {
+            acc | usize::from(a ^ b)
+        });
+    differences == 0
+}
+
+/// Handles one client: requests are answered in order, frames are written by a separate task so
+/// tool events and tokens go out while the agent works.
+async fn connection(socket: WebSocket, manager: Arc<AgentManager>) {
+    let (mut sink, mut incoming) = socket.split();
+    let (frames, mut outgoing) = mpsc::unbounded_channel::<ServerFrame>();
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = outgoing.recv().await {
+            let text = serde_json::to_string(&frame).unwrap_or_else(|_| "{}".to_string());
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut sessions: HashMap<String, Session> = HashMap::new();
+    while let Some(Ok(message)) = incoming.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let request = match serde_json::from_str::<ClientMessage>(&text) {
+            Ok(request) => request,
+            Err(e) => {
+                let _ = frames.send(ServerFrame::Error {
+                    message: format!("Invalid message: {e}"),
+                });
+                continue;
+            }
+        };
+        if let Err(e) = handle(request, &manager, &mut sessions, &frames).await {
+            let _ = frames.send(ServerFrame::Error {
+                message: e.to_string(),
+            });
+        }
+    }
+    drop(frames);
+    drop(sessions);
+    let _ = writer.await;
+}
+
+async fn handle(
+    request: ClientMessage,
+    manager: &AgentManager,
+    sessions: &mut HashMap<String, Session>,
+    frames: &FrameSender,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match request {
+        ClientMessage::CreateAgent {
+            name,
+            agent_type,
+            system_prompt,
+            temperature,
+            model,
+        } => {
+            let mut definition = AgentDefinition::new(agent_type);
+            definition.system_prompt = system_prompt;
+            definition.temperature = temperature;
+            definition.model = model;
+            manager.create_agent(name.clone(), definition).await?;
+            // The next chat builds the new definition.
+            sessions.remove(&name);
+            let _ = frames.send(ServerFrame::AgentCreated { name });
+        }
+        ClientMessage::ListAgents => {
+            let agents = manager
+                .saved_agents()?
+                .into_iter()
+                .map(|(name, definition)| AgentSummary {
+                    name,
+                    agent_type: definition.agent_type,
+                    model: definition.model,
+                })
+                .collect();
+            let _ = frames.send(ServerFrame::Agents {
+                agents,
+                types: AGENT_TYPES.iter().map(|t| t.to_string()).collect(),
+            });
+        }
+        ClientMessage::Chat { agent, message } => {
+            if !sessions.contains_key(&agent) {
+                let session = open_session(manager, &agent).await?;
+                sessions.insert(agent.clone(), session);
+            }
+            let session = sessions.get_mut(&agent).expect("session was just opened");
+            let _ = frames.send(ServerFrame::Start {
+                agent: agent.clone(),
+                conversation_id: session.conversation_id.clone(),
+            });
+            let content = chat(session, &message, frames).await?;
+            let _ = frames.send(ServerFrame::Done { agent, content });
+        }
+    }
+    Ok(())
+}
+
+/// Builds `name` without the terminal's tool summaries and approval prompts.
+async fn open_session(
+    manager: &AgentManager,
+    name: &str,
+) -> Result<Session, Box<dyn std::error::Error>> {
+    let (mut agent, config) = manager
+        .build_agent(name, AGENT_TYPES, &SessionOverrides::default())
+        .await?;
+    if let Some(base) = agent.base_agent_mut() {
+        base.clear_observers();
+        base.clear_tool_approver();
+    }
+    let conversation_id = agent.start_conversation(&config.ollama.model);
+    Ok(Session {
+        agent,
+        conversation_id,
+    })
+}
+
+/// Runs one turn on the tool loop, forwarding its events as frames; returns the answer.
+async fn chat(
+    session: &mut Session,
+    message: &str,
+    frames: &FrameSender,
+) -> Result<String, KowalskiError> {
+    let (events, mut rx) = mpsc::channel(64);
+    let turn = async move {
+        run_tool_loop_streaming(
+            session.agent.as_mut(),
+            &session.conversation_id,
+            message,
+            &ToolLoopOptions::default(),
+            &events,
+        )
+        .await
+    };
+    let forward = async {
+        while let Some(event) = rx.recv().await {
+            // The connection is gone once the receiver is dropped; nothing left to tell.
+            let _ = frames.send(ServerFrame::from(event));
+        }
+    };
+    let (outcome, ()) = tokio::join!(turn, forward);
+    Ok(outcome?.answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_binds_need_a_token() {
+        let open = ServeOptions::default();
+        let token = ServeOptions {
+            bearer_token: Some("secret".to_string()),
+        };
+        let public = "0.0.0.0:3457".parse().unwrap();
+        let err = check_bind(public, &open).unwrap_err();
+        assert!(err.to_string().contains("without a bearer token"), "{err}");
+        assert!(check_bind(public, &token).is_ok());
+        assert!(check_bind("127.0.0.1:3457".parse().unwrap(), &open).is_ok());
+        assert!(check_bind("[::1]:3457".parse().unwrap(), &open).is_ok());
+    }
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secret2", "secret"));
+        assert!(!tokens_match("", "secret"));
+    }
+}
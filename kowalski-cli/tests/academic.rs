@@ -0,0 +1,134 @@
+//! `academic analyze` on a fixture PDF against the stub Ollama, in each output format, and
+//! `academic compare`.
+
+mod common;
+
+use common::{cli, save_agent, spawn_ollama, workdir};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+fn paper() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/paper.pdf")
+}
+
+fn stdout(cmd: &mut assert_cmd::Command) -> String {
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn papers_are_summarized_per_section_in_every_format() {
+    let dir = workdir("academic");
+    let (port, bodies) = spawn_ollama();
+    save_agent(&dir, "academic", "academic", port);
+    let paper = paper();
+    let paper = paper.to_str().unwrap();
+
+    let json: Value = serde_json::from_str(&stdout(cli(&dir).args([
+        "academic",
+        "analyze",
+        paper,
+        "--format",
+        "json",
+        "--sections",
+        "abstract,methodology,discussion",
+        "-m",
+        "tiny-model",
+    ])))
+    .unwrap();
+    assert_eq!(json["title"], "Sparse Attention for Tiny Models");
+    assert_eq!(json["model"], "tiny-model");
+    let sections: Vec<&str> = json["sections"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(sections, ["abstract", "methods"]);
+    assert_eq!(json["sections"][0]["summary"], "stub reply");
+    assert_eq!(json["missing_sections"], serde_json::json!(["discussion"]));
+    assert_eq!(json["citations"].as_array().unwrap().len(), 2);
+    // The stub reply is not JSON, so it becomes the summary; the methods summary stands in for
+    // the methodology.
+    assert_eq!(json["summary"], "stub reply");
+    assert_eq!(json["key_claims"], serde_json::json!([]));
+    assert_eq!(json["research_questions"], serde_json::json!([]));
+    assert_eq!(json["methodology"], "stub reply");
+    // One summarization call per found section, each carrying that section's text, then one
+    // for the overview.
+    {
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 3);
+        assert_eq!(bodies[1]["model"], "tiny-model");
+        let prompt = bodies[1]["messages"][1]["content"].as_str().unwrap();
+        assert!(prompt.contains("methods section"), "{prompt}");
+        assert!(prompt.contains("We prune attention heads"), "{prompt}");
+        let prompt = bodies[2]["messages"][1]["content"].as_str().unwrap();
+        assert!(prompt.contains("[methods] stub reply"), "{prompt}");
+        assert!(prompt.contains("key_claims"), "{prompt}");
+    }
+
+    let markdown = stdout(cli(&dir).args(["academic", "analyze", paper, "-f", "markdown"]));
+    assert!(
+        markdown.starts_with("# Sparse Attention for Tiny Models\n\n- Source: "),
+        "{markdown}"
+    );
+    for heading in [
+        "## Summary",
+        "## Methodology",
+        "## Abstract",
+        "## Introduction",
+        "## Methods",
+        "## Results",
+    ] {
+        assert!(markdown.contains(heading), "{markdown}");
+    }
+    assert!(
+        markdown.contains("## References\n\n1. [1] A. Author."),
+        "{markdown}"
+    );
+
+    let out = dir.join("summary.txt");
+    cli(&dir)
+        .args(["academic", "analyze", paper, "-s", "results", "--out"])
+        .arg(&out)
+        .assert()
+        .success()
+        .stdout("");
+    let text = fs::read_to_string(&out).unwrap();
+    assert!(text.contains("Summary:\nstub reply"), "{text}");
+    assert!(text.contains("Results:\nstub reply"), "{text}");
+    assert!(!text.contains("Abstract:"), "{text}");
+    assert!(text.contains("References (2):"), "{text}");
+
+    // The stub reply is no JSON object, so each cell is the reply without a quote.
+    let table = stdout(cli(&dir).args([
+        "academic",
+        "compare",
+        paper,
+        paper,
+        "--dimensions",
+        "method,dataset",
+    ]));
+    assert!(
+        table.starts_with("| Paper | Method | Dataset |\n|---|---|---|\n"),
+        "{table}"
+    );
+    assert_eq!(
+        table
+            .matches("| Sparse Attention for Tiny Models | stub reply | stub reply |")
+            .count(),
+        2,
+        "{table}"
+    );
+    cli(&dir)
+        .args(["academic", "compare", paper])
+        .assert()
+        .failure();
+
+    cli(&dir)
+        .args(["academic", "analyze", "missing.pdf"])
+        .assert()
+        .failure();
+    fs::remove_dir_all(dir).unwrap();
+}
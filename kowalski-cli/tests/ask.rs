@@ -0,0 +1,78 @@
+//! `ask` one-shot mode: prompt from an argument or stdin, output formats, and exit codes.
+
+mod common;
+
+use common::{cli, save_agent, spawn_ollama, workdir};
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn ask_answers_from_argument_or_stdin() {
+    let dir = workdir("ask");
+    let (port, bodies) = spawn_ollama();
+    save_agent(&dir, "a1", "data", port);
+
+    cli(&dir)
+        .args(["ask", "a1", "what is 2+2?"])
+        .assert()
+        .success()
+        .stdout("stub reply\n");
+    let body = bodies.lock().unwrap().pop().unwrap();
+    let last = body["messages"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(last["content"], "what is 2+2?");
+
+    let output = cli(&dir)
+        .args(["ask", "a1", "--output", "json", "--model", "qwen3:8b"])
+        .write_stdin("piped question\n")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let result: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(result["agent"], "a1");
+    assert_eq!(result["model"], "qwen3:8b");
+    assert_eq!(result["answer"], "stub reply");
+    assert_eq!(result["llm_calls"], 1);
+    assert_eq!(result["tool_trace"], Value::Array(Vec::new()));
+    let body = bodies.lock().unwrap().pop().unwrap();
+    assert_eq!(body["model"], "qwen3:8b");
+    let last = body["messages"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(last["content"], "piped question");
+
+    cli(&dir)
+        .args(["ask", "a1", "--no-tools", "--output", "markdown"])
+        .write_stdin("hi")
+        .assert()
+        .success()
+        .stdout("stub reply\n");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn ask_fails_with_a_non_zero_exit_code() {
+    let dir = workdir("ask-fail");
+    // Nothing listens on this port once the listener is dropped.
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    save_agent(&dir, "down", "web", port);
+
+    cli(&dir)
+        .args(["ask", "down", "hello"])
+        .assert()
+        .failure()
+        .stdout("");
+    cli(&dir)
+        .args(["ask", "missing-agent", "hello"])
+        .assert()
+        .failure();
+    cli(&dir)
+        .args(["ask", "down"])
+        .write_stdin("   \n")
+        .assert()
+        .failure();
+    fs::remove_dir_all(dir).unwrap();
+}
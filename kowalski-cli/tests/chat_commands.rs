@@ -0,0 +1,95 @@
+//! Slash commands in the interactive `chat` loop.
+
+mod common;
+
+use common::{cli, save_agent, spawn_ollama, workdir};
+use std::fs;
+
+#[test]
+fn slash_commands_switch_model_role_and_conversation() {
+    let dir = workdir("chat-commands");
+    let (port, bodies) = spawn_ollama();
+    save_agent(&dir, "c1", "web", port);
+
+    let out = cli(&dir)
+        .args(["chat", "c1"])
+        .write_stdin(
+            "/model qwen2.5:7b\nhello\n/regenerate\n/role engineer\nagain\n/frobnicate\n/clear\n/bye\n",
+        )
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Model switched to qwen2.5:7b."), "{out}");
+    assert!(
+        out.contains("Now acting as a senior software engineer."),
+        "{out}"
+    );
+    assert!(out.contains("Unknown command '/frobnicate'."), "{out}");
+    assert!(out.contains("/regenerate: Ask again"), "{out}");
+    assert!(out.contains("Started a new conversation."), "{out}");
+
+    let bodies = bodies.lock().unwrap().clone();
+    assert!(bodies.len() >= 3, "{bodies:?}");
+    assert!(
+        bodies.iter().all(|b| b["model"] == "qwen2.5:7b"),
+        "{bodies:?}"
+    );
+    let last_user = |body: &serde_json::Value| {
+        body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|m| m["role"] == "user")
+            .map(|m| m["content"].as_str().unwrap().to_string())
+    };
+    // `/regenerate` asks again for the reply to "hello".
+    let hellos = bodies
+        .iter()
+        .filter(|b| last_user(b).as_deref() == Some("hello"))
+        .count();
+    assert_eq!(hellos, 2, "{bodies:?}");
+    let last = bodies.last().unwrap();
+    assert_eq!(last_user(last).as_deref(), Some("again"));
+    assert!(
+        last["messages"].as_array().unwrap().iter().any(|m| {
+            m["role"] == "system"
+                && m["content"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with("You are a senior software engineer.")
+        }),
+        "{last}"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn search_finds_earlier_messages_and_the_model_gets_the_tool() {
+    let dir = workdir("chat-search");
+    let (port, bodies) = spawn_ollama();
+    save_agent(&dir, "s1", "web", port);
+
+    let out = cli(&dir)
+        .args(["chat", "s1"])
+        .env("KOWALSKI_EPISODIC_PATH", dir.join("episodic"))
+        .write_stdin("when do tomatoes ripen?\n/search tomatoes\n/search\n/bye\n")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("[user] when do tomatoes ripen?"), "{out}");
+    assert!(out.contains("Usage: /search <query>"), "{out}");
+
+    let bodies = bodies.lock().unwrap().clone();
+    assert!(
+        bodies[0].to_string().contains("search_history"),
+        "{bodies:?}"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
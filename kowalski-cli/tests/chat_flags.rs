@@ -0,0 +1,79 @@
+//! `create` and `chat` flags reach the model: runs the binary against a stub Ollama that records
+//! `/api/chat` request bodies.
+
+mod common;
+
+use common::{cli, spawn_ollama, workdir};
+use serde_json::Value;
+use std::fs;
+
+fn system_prompt(body: &Value) -> &str {
+    body["messages"][0]["content"].as_str().unwrap()
+}
+
+#[test]
+fn create_and_chat_flags_shape_the_request() {
+    let dir = workdir("flags");
+    let (port, bodies) = spawn_ollama();
+
+    cli(&dir)
+        .args(["create", "web", "--name", "w1"])
+        .args(["--prompt", "You are a terse researcher."])
+        .args(["--temperature", "0.9"])
+        .assert()
+        .success();
+    // Point the saved agent at the stub.
+    let store = dir.join("data/kowalski/agents.toml");
+    let mut saved = fs::read_to_string(&store).unwrap();
+    assert!(saved.contains("system_prompt = \"You are a terse researcher.\""));
+    saved.push_str(&format!(
+        "\n[agents.w1.config.ollama]\nhost = \"127.0.0.1\"\nport = {port}\n"
+    ));
+    fs::write(&store, saved).unwrap();
+
+    cli(&dir)
+        .args(["chat", "w1", "hello"])
+        .assert()
+        .success()
+        .stdout("stub reply\n");
+    let stored = bodies.lock().unwrap().pop().unwrap();
+    assert!(system_prompt(&stored).starts_with("You are a terse researcher."));
+    assert_eq!(
+        stored["options"]["temperature"].as_f64().unwrap() as f32,
+        0.9
+    );
+    assert_eq!(
+        stored["model"],
+        kowalski_core::config::Config::default().ollama.model
+    );
+
+    let output = cli(&dir)
+        .args(["chat", "w1", "--verbose"])
+        .args(["--model", "qwen3:8b", "--temperature", "0.2"])
+        .args(["--prompt", "Answer in French.", "hello"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+    assert!(stdout.contains("Model: qwen3:8b"), "{stdout}");
+    assert!(stdout.contains("Temperature: 0.2"), "{stdout}");
+    assert!(
+        stdout.contains("System prompt: Answer in French."),
+        "{stdout}"
+    );
+    let overridden = bodies.lock().unwrap().pop().unwrap();
+    assert_eq!(overridden["model"], "qwen3:8b");
+    assert!(system_prompt(&overridden).starts_with("Answer in French."));
+    assert_eq!(
+        overridden["options"]["temperature"].as_f64().unwrap() as f32,
+        0.2
+    );
+
+    // Session flags do not change the saved agent.
+    let saved = fs::read_to_string(&store).unwrap();
+    assert!(saved.contains("You are a terse researcher."));
+    assert!(!saved.contains("qwen3:8b"));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,91 @@
+//! Shared helpers for the CLI integration tests: a stub Ollama and an isolated data directory.
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Answers `/api/chat` with a fixed reply, recording each body, `/api/embeddings` with a
+/// constant vector and `/api/tags` with `llama3.2:latest` and `nomic-embed-text:latest`; 404 for
+/// anything else.
+#[allow(dead_code)]
+pub fn spawn_ollama() -> (u16, Arc<Mutex<Vec<Value>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let recorded = bodies.clone();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                line.clear();
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let (status, reply) = if request_line.starts_with("POST /api/chat ") {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(serde_json::from_slice(&body).unwrap());
+                (
+                    "200 OK",
+                    r#"{"message":{"role":"assistant","content":"stub reply"},"done":true}"#,
+                )
+            } else if request_line.starts_with("POST /api/embeddings ") {
+                ("200 OK", r#"{"embedding":[0.1,0.2,0.3]}"#)
+            } else if request_line.starts_with("GET /api/tags ") {
+                (
+                    "200 OK",
+                    r#"{"models":[{"name":"llama3.2:latest"},{"name":"nomic-embed-text:latest"}]}"#,
+                )
+            } else {
+                ("404 Not Found", "{}")
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                reply.len()
+            );
+        }
+    });
+    (port, bodies)
+}
+
+pub fn workdir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("kowalski-cli-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("data")).unwrap();
+    dir
+}
+
+#[allow(dead_code)]
+pub fn cli(dir: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("kowalski-cli").unwrap();
+    cmd.current_dir(dir)
+        .env("XDG_DATA_HOME", dir.join("data"))
+        .env("RUST_LOG", "error");
+    cmd
+}
+
+/// Saves agent `name` of `agent_type` in `dir`'s store, pointed at Ollama on `port`.
+#[allow(dead_code)]
+pub fn save_agent(dir: &Path, name: &str, agent_type: &str, port: u16) {
+    let store = dir.join("data/kowalski/agents.toml");
+    fs::create_dir_all(store.parent().unwrap()).unwrap();
+    let mut saved = fs::read_to_string(&store).unwrap_or_default();
+    saved.push_str(&format!(
+        "\n[agents.{name}]\nagent_type = \"{agent_type}\"\n\n[agents.{name}.config.ollama]\nhost = \"127.0.0.1\"\nport = {port}\n"
+    ));
+    fs::write(store, saved).unwrap();
+}
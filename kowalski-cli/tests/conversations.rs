@@ -0,0 +1,81 @@
+//! `chat` saves its conversation; `conversation list/show/export/resume/delete` work on it.
+
+mod common;
+
+use common::{cli, save_agent, spawn_ollama, workdir};
+use serde_json::Value;
+use std::fs;
+
+fn stdout(cmd: &mut assert_cmd::Command) -> String {
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn saved_conversations_can_be_listed_exported_resumed_and_deleted() {
+    let dir = workdir("conversations");
+    let (port, bodies) = spawn_ollama();
+    save_agent(&dir, "c1", "web", port);
+
+    cli(&dir).args(["chat", "c1", "hello"]).assert().success();
+
+    let list: Value =
+        serde_json::from_str(&stdout(cli(&dir).args(["conversation", "list", "--json"]))).unwrap();
+    let list = list.as_array().unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0]["agent"], "c1");
+    let id = list[0]["id"].as_str().unwrap().to_string();
+    let messages = list[0]["messages"].as_u64().unwrap();
+
+    let table = stdout(cli(&dir).args(["conversation", "list"]));
+    assert!(table.contains(&id) && table.contains("c1"), "{table}");
+
+    let shown = stdout(cli(&dir).args(["conversation", "show", &id[..8]]));
+    assert!(shown.contains("[user]\nhello"), "{shown}");
+    assert!(shown.contains("[assistant]\nstub reply"), "{shown}");
+
+    let markdown = stdout(cli(&dir).args(["conversation", "export", &id, "--format", "md"]));
+    assert!(markdown.starts_with(&format!("# Conversation {id}")));
+    assert!(markdown.contains("## User\n\nhello"), "{markdown}");
+    let jsonl = stdout(cli(&dir).args(["conversation", "export", &id, "-f", "jsonl"]));
+    let lines: Vec<Value> = jsonl
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len() as u64, messages);
+    assert_eq!(lines.last().unwrap()["content"], "stub reply");
+
+    cli(&dir)
+        .args(["conversation", "resume", &id])
+        .write_stdin("and again\n/bye\n")
+        .assert()
+        .success();
+    let body = bodies.lock().unwrap().pop().unwrap();
+    let sent: Vec<&str> = body["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|m| m["content"].as_str())
+        .collect();
+    assert!(
+        sent.contains(&"hello") && sent.contains(&"and again"),
+        "{sent:?}"
+    );
+    let list: Value =
+        serde_json::from_str(&stdout(cli(&dir).args(["conversation", "list", "--json"]))).unwrap();
+    assert_eq!(list[0]["id"], id.as_str());
+    assert!(list[0]["messages"].as_u64().unwrap() > messages);
+
+    cli(&dir)
+        .args(["conversation", "delete", &id])
+        .assert()
+        .success();
+    assert_eq!(
+        stdout(cli(&dir).args(["conversation", "list", "--json"])).trim(),
+        "[]"
+    );
+    cli(&dir)
+        .args(["conversation", "show", &id])
+        .assert()
+        .failure();
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,98 @@
+//! Round trip: the core MCP stdio client drives `kowalski-cli mcp-serve --root <dir>`, listing
+//! the built-in tools and calling the file tools inside and outside the root.
+#![cfg(unix)]
+
+mod common;
+
+use common::workdir;
+use kowalski_core::config::{McpServerConfig, McpTransport};
+use kowalski_core::mcp::McpStdioClient;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+
+#[tokio::test]
+async fn mcp_clients_list_and_call_the_builtin_tools() {
+    let dir = workdir("mcp-serve");
+    let shared = dir.join("shared");
+    fs::create_dir_all(&shared).unwrap();
+    fs::write(shared.join("notes.txt"), "kept inside\n").unwrap();
+    fs::write(shared.join("scores.csv"), "name,score\nada,3\nbob,5\n").unwrap();
+    fs::write(dir.join("secret.txt"), "top secret\n").unwrap();
+
+    let server = McpServerConfig {
+        name: "kowalski".to_string(),
+        url: String::new(),
+        transport: McpTransport::Stdio,
+        headers: HashMap::new(),
+        command: vec![
+            env!("CARGO_BIN_EXE_kowalski-cli").to_string(),
+            "mcp-serve".to_string(),
+            "--config".to_string(),
+            dir.join("config.toml").to_string_lossy().to_string(),
+            "--root".to_string(),
+            shared.to_string_lossy().to_string(),
+        ],
+        env: HashMap::from([
+            (
+                "XDG_DATA_HOME".to_string(),
+                dir.join("data").to_string_lossy().to_string(),
+            ),
+            ("RUST_LOG".to_string(), "error".to_string()),
+        ]),
+    };
+    let client = McpStdioClient::connect(&server).await.expect("connect");
+
+    let tools = client.list_tools().await.expect("tools/list");
+    let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+    for name in [
+        "calculator",
+        "csv_tool",
+        "datetime",
+        "fs_tool",
+        "repo_map",
+        "stats",
+    ] {
+        assert!(names.contains(&name), "{names:?}");
+    }
+    let fs_tool = tools.iter().find(|t| t.name == "fs_tool").unwrap();
+    assert_eq!(fs_tool.input_schema["required"], json!(["task"]));
+
+    let read = client
+        .call_tool(
+            "fs_tool",
+            &json!({"task": "read_file", "path": "notes.txt"}),
+        )
+        .await
+        .expect("read_file");
+    let read = read.normalized_content().to_string();
+    assert!(read.contains("kept inside"), "{read}");
+
+    let escaped = client
+        .call_tool(
+            "fs_tool",
+            &json!({"task": "read_file", "path": "../secret.txt"}),
+        )
+        .await
+        .expect("tool errors are results");
+    let escaped = escaped.normalized_content().to_string();
+    assert!(!escaped.contains("top secret"), "{escaped}");
+
+    let summary = client
+        .call_tool(
+            "csv_tool",
+            &json!({"task": "process_csv_file", "path": "scores.csv"}),
+        )
+        .await
+        .expect("process_csv_file");
+    let summary = summary.normalized_content().to_string();
+    assert!(summary.contains("score"), "{summary}");
+
+    let sum = client
+        .call_tool("calculator", &json!({"expression": "6 * 7"}))
+        .await
+        .expect("calculator");
+    assert!(sum.normalized_content().to_string().contains("42"));
+    drop(client);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,60 @@
+//! `roles list` shows the built-in catalog plus `[roles]` from config.toml, and `chat --role`
+//! sends the chosen role as the system prompt.
+
+mod common;
+
+use common::{cli, save_agent, spawn_ollama, workdir};
+use std::fs;
+
+#[test]
+fn config_roles_are_listed_and_selectable_in_chat() {
+    let dir = workdir("roles");
+    let (port, bodies) = spawn_ollama();
+    save_agent(&dir, "w1", "web", port);
+    fs::write(
+        dir.join("config.toml"),
+        "[roles.pirate]\nname = \"a pirate\"\ndescription = \"Speak like one.\"\n",
+    )
+    .unwrap();
+
+    let listed = cli(&dir).args(["roles", "list"]).assert().success();
+    let listed = String::from_utf8(listed.get_output().stdout.clone()).unwrap();
+    assert!(
+        listed.contains("pirate       config   You are a pirate. Speak like one."),
+        "{listed}"
+    );
+    assert!(listed.contains("engineer     builtin"), "{listed}");
+
+    let json = cli(&dir)
+        .args(["roles", "list", "--json"])
+        .assert()
+        .success();
+    let json: serde_json::Value = serde_json::from_slice(&json.get_output().stdout).unwrap();
+    let pirate = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["key"] == "pirate")
+        .unwrap();
+    assert_eq!(pirate["role"]["description"], "Speak like one.");
+
+    cli(&dir)
+        .args(["chat", "w1", "--role", "pirate", "hello"])
+        .assert()
+        .success()
+        .stdout("stub reply\n");
+    let request = bodies.lock().unwrap().pop().unwrap();
+    assert!(
+        request["messages"][0]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("You are a pirate. Speak like one."),
+        "{request}"
+    );
+
+    cli(&dir)
+        .args(["chat", "w1", "--role", "wizard", "hello"])
+        .assert()
+        .failure();
+    fs::remove_dir_all(dir).unwrap();
+}
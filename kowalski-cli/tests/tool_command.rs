@@ -0,0 +1,107 @@
+//! `tool list/schema/run` call an agent's tools without the LLM. The agent gets its tools from
+//! `kowalski-cli mcp-serve` over stdio.
+
+mod common;
+
+use common::{cli, workdir};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+fn stdout(cmd: &mut assert_cmd::Command) -> String {
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+/// Saves agent `name` whose only MCP server is this binary's `mcp-serve`.
+fn save_tool_agent(dir: &Path, name: &str) {
+    let store = dir.join("data/kowalski/agents.toml");
+    fs::create_dir_all(store.parent().unwrap()).unwrap();
+    let server = env!("CARGO_BIN_EXE_kowalski-cli");
+    fs::write(
+        store,
+        format!(
+            "[agents.{name}]\nagent_type = \"web\"\n\n[[agents.{name}.config.mcp.servers]]\nname = \"builtin\"\ntransport = \"stdio\"\ncommand = [{server:?}, \"mcp-serve\"]\n"
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn tools_can_be_listed_described_and_run_directly() {
+    let dir = workdir("tool-command");
+    save_tool_agent(&dir, "t1");
+
+    let list = stdout(cli(&dir).args(["tool", "list", "t1"]));
+    assert!(list.contains("html_to_markdown"), "{list}");
+    let list: Value = serde_json::from_str(&stdout(
+        cli(&dir).args(["tool", "list", "t1", "-o", "json"]),
+    ))
+    .unwrap();
+    assert!(
+        list.as_array()
+            .unwrap()
+            .iter()
+            .any(|t| t["name"] == "html_to_markdown"),
+        "{list}"
+    );
+
+    let schema: Value = serde_json::from_str(&stdout(cli(&dir).args([
+        "tool",
+        "schema",
+        "html_to_markdown",
+        "--agent",
+        "t1",
+    ])))
+    .unwrap();
+    assert_eq!(schema["name"], "html_to_markdown");
+    assert_eq!(
+        schema["parameters"]["properties"]["strip_boilerplate"]["type"],
+        "boolean"
+    );
+
+    let output: Value = serde_json::from_str(&stdout(cli(&dir).args([
+        "tool",
+        "run",
+        "t1",
+        "html_to_markdown",
+        "--param",
+        "html=<nav>menu</nav><h1>Hi</h1>",
+        "--param",
+        "strip_boilerplate=true",
+    ])))
+    .unwrap();
+    let result = output["result"].to_string();
+    assert!(result.contains("Hi"), "{output}");
+    assert!(!result.contains("menu"), "{output}");
+
+    let yaml = stdout(cli(&dir).args([
+        "tool",
+        "run",
+        "t1",
+        "html_to_markdown",
+        "--param-json",
+        r#"{"html": "<p>plain</p>"}"#,
+        "-o",
+        "yaml",
+    ]));
+    assert!(yaml.starts_with("result:"), "{yaml}");
+
+    let failure = cli(&dir)
+        .args(["tool", "run", "t1", "no_such_tool"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8(failure.get_output().stderr.clone()).unwrap();
+    assert!(stderr.contains("available: "), "{stderr}");
+    cli(&dir)
+        .args([
+            "tool",
+            "run",
+            "t1",
+            "html_to_markdown",
+            "--param",
+            "strip_boilerplate=perhaps",
+        ])
+        .assert()
+        .failure();
+    fs::remove_dir_all(dir).unwrap();
+}
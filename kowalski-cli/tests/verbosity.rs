@@ -0,0 +1,49 @@
+//! Default chat output is the answer without debug noise; `-q` leaves only the answer and `-v`
+//! adds session details.
+
+mod common;
+
+use common::{cli, save_agent, spawn_ollama, workdir};
+use std::fs;
+
+fn output_of(assert: assert_cmd::assert::Assert) -> (String, String) {
+    let output = assert.success().get_output().clone();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn chat_output_follows_verbosity_flags() {
+    let dir = workdir("verbosity");
+    let (port, _bodies) = spawn_ollama();
+    save_agent(&dir, "v1", "web", port);
+
+    let (stdout, stderr) = output_of(
+        cli(&dir)
+            .env_remove("RUST_LOG")
+            .env("NO_COLOR", "1")
+            .args(["chat", "v1"])
+            .write_stdin("hello\n/bye\n")
+            .assert(),
+    );
+    assert!(stdout.contains("stub reply"), "{stdout}");
+    assert!(!stdout.contains("[DEBUG]") && !stderr.contains("[DEBUG]"));
+    assert!(!stderr.contains("INFO"), "{stderr}");
+    assert!(!stdout.contains("Model:"), "{stdout}");
+
+    let (stdout, stderr) = output_of(
+        cli(&dir)
+            .env_remove("RUST_LOG")
+            .args(["-q", "chat", "v1", "hello"])
+            .assert(),
+    );
+    assert_eq!(stdout, "stub reply\n");
+    assert_eq!(stderr, "");
+
+    let (stdout, _) = output_of(cli(&dir).args(["chat", "v1", "-v", "hello"]).assert());
+    assert!(stdout.contains("Model: "), "{stdout}");
+    assert!(stdout.ends_with("stub reply\n"), "{stdout}");
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,124 @@
+//! `serve` (feature `server`): a WebSocket client creates, lists and chats with an agent.
+
+use futures::{SinkExt, StreamExt};
+use kowalski_cli::agent_manager::{AgentFactory, AgentManager, BoxedAgent};
+use kowalski_cli::agent_store::{AgentDefinition, AgentStore};
+use kowalski_cli::ws_server::{self, ServeOptions, ServerFrame};
+use kowalski_core::config::Config;
+use kowalski_core::testing::MockBackend;
+use kowalski_core::tools::CalculatorTool;
+use kowalski_core::tools::manager::ToolManager;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio_tungstenite::tungstenite;
+
+/// Agents on a backend that asks for the calculator, then streams the answer in two tokens.
+fn scripted_factory() -> AgentFactory {
+    let backend = Arc::new(
+        MockBackend::script()
+            .responds_with_tool_call("calculator", json!({"expression": "6 * 7"}))
+            .then_chunks(["It is ", "42."])
+            .build(),
+    );
+    Arc::new(move |_definition: AgentDefinition, _config: Config| {
+        let backend = backend.clone();
+        Box::pin(async move {
+            let tools = ToolManager::new();
+            tools.register(CalculatorTool::new());
+            let agent = kowalski_core::testing::agent(backend, tools).await?;
+            Ok(Box::new(agent) as BoxedAgent)
+        })
+    })
+}
+
+#[tokio::test]
+async fn client_creates_lists_and_streams_a_chat() {
+    let dir = std::env::temp_dir().join(format!("kowalski-ws-{}", std::process::id()));
+    let manager = AgentManager::new(AgentStore::new(dir.join("agents.toml")))
+        .with_factory(scripted_factory());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let options = ServeOptions {
+        bearer_token: Some("secret".to_string()),
+    };
+    tokio::spawn(ws_server::serve(
+        Arc::new(manager),
+        listener,
+        options,
+        std::future::pending(),
+    ));
+
+    assert!(
+        tokio_tungstenite::connect_async(format!("ws://{addr}/ws"))
+            .await
+            .is_err()
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/ws?token=secret"))
+        .await
+        .unwrap();
+    let mut send = async |message: Value| {
+        socket
+            .send(tungstenite::Message::Text(message.to_string().into()))
+            .await
+            .unwrap();
+    };
+    send(json!({"type": "create_agent", "name": "calc", "agent_type": "data"})).await;
+    send(json!({"type": "list_agents"})).await;
+    send(json!({"type": "chat", "agent": "calc", "message": "what is 6 * 7?"})).await;
+    send(json!({"type": "chat", "agent": "nobody", "message": "hi"})).await;
+    send(json!({"type": "dance"})).await;
+
+    let mut frames = Vec::new();
+    while frames.len() < 10 {
+        let message = socket.next().await.unwrap().unwrap();
+        if let tungstenite::Message::Text(text) = message {
+            frames.push(serde_json::from_str::<ServerFrame>(&text).unwrap());
+        }
+    }
+
+    assert_eq!(
+        frames[0],
+        ServerFrame::AgentCreated {
+            name: "calc".to_string()
+        }
+    );
+    let ServerFrame::Agents { agents, types } = &frames[1] else {
+        panic!("expected agents, got {:?}", frames[1]);
+    };
+    assert_eq!(agents[0].name, "calc");
+    assert_eq!(agents[0].agent_type, "data");
+    assert!(types.contains(&"data".to_string()));
+    assert!(matches!(&frames[2], ServerFrame::Start { agent, .. } if agent == "calc"));
+    assert_eq!(
+        frames[3],
+        ServerFrame::ToolCall {
+            name: "calculator".to_string(),
+            parameters: json!({"expression": "6 * 7"}),
+        }
+    );
+    assert!(
+        matches!(&frames[4], ServerFrame::ToolResult { name, error: None, .. } if name == "calculator")
+    );
+    let tokens: Vec<&str> = frames[5..7]
+        .iter()
+        .map(|frame| match frame {
+            ServerFrame::Token { content } => content.as_str(),
+            other => panic!("expected a token, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(tokens, ["It is ", "42."]);
+    assert_eq!(
+        frames[7],
+        ServerFrame::Done {
+            agent: "calc".to_string(),
+            content: "It is 42.".to_string()
+        }
+    );
+    assert!(
+        matches!(&frames[8], ServerFrame::Error { message } if message.contains("'nobody' not found"))
+    );
+    assert!(
+        matches!(&frames[9], ServerFrame::Error { message } if message.starts_with("Invalid message"))
+    );
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,254 @@
+//! Benchmarks of the hot paths: vector similarity, episodic ranking and inserts, NDJSON stream
+//! reassembly, CSV summaries and tool schema generation. Where a path was optimized, the
+//! `baseline` variant (`kowalski_core::baseline`) runs alongside it. Run with
+//! `cargo bench -p kowalski-core --features bench`; reference numbers are in `benches/README.md`.
+
+use async_trait::async_trait;
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use kowalski_core::baseline;
+use kowalski_core::config::Config;
+use kowalski_core::error::KowalskiError;
+use kowalski_core::memory::episodic::{EpisodicBuffer, rank_units};
+use kowalski_core::memory::semantic::cosine_similarity;
+use kowalski_core::memory::{MemoryProvider, MemoryUnit};
+use kowalski_core::testing::MockBackend;
+use kowalski_core::tools::csv::{CsvFormat, summarize};
+use kowalski_core::tools::manager::ToolManager;
+use kowalski_core::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use kowalski_core::utils::ndjson::NdjsonBuffer;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Duration;
+
+const NOW: u64 = 1_750_000_000;
+
+fn vector(rng: &mut StdRng, dims: usize) -> Vec<f32> {
+    (0..dims).map(|_| rng.random_range(-1.0..1.0)).collect()
+}
+
+fn similarity(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(1);
+    let (a, b) = (vector(&mut rng, 768), vector(&mut rng, 768));
+    let mut group = c.benchmark_group("cosine_similarity_768");
+    group.bench_function("baseline", |bench| {
+        bench.iter(|| baseline::cosine_similarity(black_box(&a), black_box(&b)))
+    });
+    group.bench_function("optimized", |bench| {
+        bench.iter(|| cosine_similarity(black_box(&a), black_box(&b)))
+    });
+    group.finish();
+}
+
+/// `count` units with 128-dimensional embeddings spread over the last 30 days.
+fn episodic_units(count: usize) -> Vec<MemoryUnit> {
+    let mut rng = StdRng::seed_from_u64(2);
+    (0..count)
+        .map(|i| MemoryUnit {
+            id: format!("unit-{i}"),
+            timestamp: NOW - rng.random_range(0..60 * 60 * 24 * 30),
+            content: format!("note {i} about the agent's memory"),
+            embedding: Some(vector(&mut rng, 128)),
+            metadata: Default::default(),
+        })
+        .collect()
+}
+
+fn episodic_retrieval(c: &mut Criterion) {
+    let query = vector(&mut StdRng::seed_from_u64(3), 128);
+    let mut group = c.benchmark_group("episodic_retrieval");
+    group.sample_size(20);
+    for count in [1_000, 10_000, 100_000] {
+        let units = episodic_units(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("baseline", count),
+            &units,
+            |bench, units| {
+                bench.iter_batched(
+                    || units.clone(),
+                    |units| baseline::rank_units(units, "memory", Some(&query), NOW, 5),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("optimized", count),
+            &units,
+            |bench, units| {
+                bench.iter_batched(
+                    || units.clone(),
+                    |units| rank_units(units, "memory", Some(&query), NOW, 5),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Storing 200 units without embeddings in a fresh SQLite episodic buffer.
+fn episodic_insert(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let units: Vec<MemoryUnit> = episodic_units(200)
+        .into_iter()
+        .map(|unit| MemoryUnit {
+            embedding: None,
+            ..unit
+        })
+        .collect();
+    let buffer = || {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let mut config = Config::default();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        let buffer = runtime
+            .block_on(EpisodicBuffer::open(
+                &config.memory,
+                // Answers each embedding request after 1 ms, like a local embedding server.
+                Arc::new(
+                    MockBackend::script()
+                        .with_embedding(vec![0.5; 128])
+                        .with_latency(Duration::from_millis(1))
+                        .build(),
+                ),
+            ))
+            .expect("episodic buffer");
+        (dir, buffer, units.clone())
+    };
+    let mut group = c.benchmark_group("episodic_insert_200");
+    group.sample_size(10);
+    group.bench_function("baseline", |bench| {
+        bench.iter_batched(
+            buffer,
+            |(_dir, mut buffer, units)| runtime.block_on(baseline::add_each(&mut buffer, units)),
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("optimized", |bench| {
+        bench.iter_batched(
+            buffer,
+            |(_dir, mut buffer, units)| runtime.block_on(buffer.add_batch(units)),
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+/// A 1 MiB Ollama-style stream of small JSON lines, cut into 16 KiB network reads.
+fn stream_reassembly(c: &mut Criterion) {
+    let mut stream = Vec::new();
+    let mut i = 0;
+    while stream.len() < 1 << 20 {
+        stream.extend_from_slice(
+            format!(
+                "{{\"model\":\"llama3.2\",\"message\":{{\"role\":\"assistant\",\"content\":\"tok{i}\"}},\"done\":false}}\n"
+            )
+            .as_bytes(),
+        );
+        i += 1;
+    }
+    let chunks: Vec<&[u8]> = stream.chunks(16 * 1024).collect();
+    let mut group = c.benchmark_group("ndjson_reassembly");
+    group.throughput(Throughput::Bytes(stream.len() as u64));
+    group.bench_function("baseline", |bench| {
+        bench.iter(|| {
+            let mut pending = Vec::new();
+            chunks
+                .iter()
+                .map(|chunk| baseline::ndjson_push(&mut pending, chunk).len())
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("optimized", |bench| {
+        bench.iter(|| {
+            let mut buffer = NdjsonBuffer::new();
+            chunks
+                .iter()
+                .map(|chunk| buffer.push(chunk).len())
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+fn csv_summary(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut csv = String::from("id,city,temperature,humidity,note\n");
+    for i in 0..100_000 {
+        csv.push_str(&format!(
+            "{i},city{},{:.2},{},reading {i}\n",
+            i % 40,
+            rng.random_range(-20.0..40.0),
+            rng.random_range(0..100)
+        ));
+    }
+    let mut group = c.benchmark_group("csv_summary");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(csv.len() as u64));
+    group.bench_function("100k_rows", |bench| {
+        bench.iter(|| summarize(black_box(&csv), CsvFormat::default()))
+    });
+    group.finish();
+}
+
+struct NumberedTool {
+    name: String,
+}
+
+#[async_trait]
+impl Tool for NumberedTool {
+    async fn execute(&mut self, _input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        Ok(ToolOutput::new(serde_json::Value::Null, None))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "A tool with a few typed parameters"
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        [
+            ("path", ParameterType::String, true),
+            ("limit", ParameterType::Number, false),
+            ("recursive", ParameterType::Boolean, false),
+            ("columns", ParameterType::Array, false),
+        ]
+        .into_iter()
+        .map(|(name, parameter_type, required)| ToolParameter {
+            name: name.to_string(),
+            description: format!("The {name}"),
+            required,
+            default_value: (!required).then(|| "1".to_string()),
+            parameter_type,
+        })
+        .collect()
+    }
+}
+
+fn tool_schema(c: &mut Criterion) {
+    let tools = ToolManager::new();
+    for i in 0..50 {
+        tools.register(NumberedTool {
+            name: format!("tool_{i}"),
+        });
+    }
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    c.bench_function("tool_schema_50_tools", |bench| {
+        bench.iter(|| runtime.block_on(tools.generate_json_schema()))
+    });
+}
+
+criterion_group!(
+    benches,
+    similarity,
+    episodic_retrieval,
+    episodic_insert,
+    stream_reassembly,
+    csv_summary,
+    tool_schema
+);
+criterion_main!(benches);
@@ -0,0 +1,203 @@
+//! Human-in-the-loop tool approval: set a [`ToolApprover`] with
+//! [`BaseAgent::set_tool_approver`](crate::agent::BaseAgent::set_tool_approver) and every tool
+//! call the agent is about to run is passed to it first.
+//!
+//! A denied call is not executed; the tool loop receives a `Permission denied` tool result
+//! instead, so the model sees the feedback and keeps reasoning.
+//!
+//! Without an approver, calls a tool reports as destructive
+//! ([`Tool::is_destructive`](crate::tools::Tool::is_destructive), e.g. `patch apply`) are
+//! refused the same way.
+
+use crate::error::KowalskiError;
+use crate::tools::ToolCall;
+use log::debug;
+
+/// What to do with a proposed tool call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolApproval {
+    Approve,
+    /// Skip the call; the reason (if any) is passed back to the model.
+    Deny(Option<String>),
+    /// Run this call instead (edited parameters, or a different tool).
+    Modify(ToolCall),
+}
+
+/// Reviews tool calls before they run. Called inline on the agent task, so an interactive
+/// approver blocks the turn until the user answers.
+pub trait ToolApprover: Send + Sync {
+    fn review(&self, call: &ToolCall) -> ToolApproval;
+}
+
+impl<F> ToolApprover for F
+where
+    F: Fn(&ToolCall) -> ToolApproval + Send + Sync,
+{
+    fn review(&self, call: &ToolCall) -> ToolApproval {
+        self(call)
+    }
+}
+
+/// The approval step for `call`: the call to run (possibly modified by `approver`), or
+/// `PermissionDenied` when the approver denies it, or when it is `destructive` and there is no
+/// approver.
+pub(crate) fn review_call(
+    approver: Option<&dyn ToolApprover>,
+    call: ToolCall,
+    destructive: bool,
+) -> Result<ToolCall, KowalskiError> {
+    let tool_name = call.name.clone();
+    match approver {
+        Some(approver) => match approver.review(&call) {
+            ToolApproval::Approve => Ok(call),
+            ToolApproval::Deny(reason) => {
+                debug!("tool call {} denied", tool_name);
+                Err(KowalskiError::PermissionDenied(match reason {
+                    Some(reason) => format!("{} call denied by user: {}", tool_name, reason),
+                    None => format!("{} call denied by user", tool_name),
+                }))
+            }
+            ToolApproval::Modify(modified) => Ok(modified),
+        },
+        // Calls that write files or run commands need someone to approve them.
+        None if destructive => {
+            debug!(
+                "tool call {} refused: destructive and no approver",
+                tool_name
+            );
+            Err(KowalskiError::PermissionDenied(format!(
+                "{tool_name} would change files and needs approval, but no tool approver is set"
+            )))
+        }
+        None => Ok(call),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::tool_loop::run_tool_loop;
+    use crate::agent::{Agent, BaseAgent};
+    use crate::config::Config;
+    use crate::error::KowalskiError;
+    use crate::testing::{MockBackend, MockReply, memory};
+    use crate::tools::manager::ToolManager;
+    use crate::tools::{Tool, ToolInput, ToolOutput, ToolParameter};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the paths it was asked to remove.
+    struct RmTool(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Tool for RmTool {
+        async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+            let path = input.parameters["path"].as_str().unwrap_or_default();
+            self.0.lock().unwrap().push(path.to_string());
+            Ok(ToolOutput::new(serde_json::json!({"removed": path}), None))
+        }
+
+        fn name(&self) -> &str {
+            "rm"
+        }
+
+        fn description(&self) -> &str {
+            "Removes a file"
+        }
+
+        fn parameters(&self) -> Vec<ToolParameter> {
+            Vec::new()
+        }
+
+        fn is_destructive(&self, parameters: &serde_json::Value) -> bool {
+            parameters["path"] == "/"
+        }
+    }
+
+    /// Asks to delete `/x`; after a tool result, answers with that result.
+    async fn agent(removed: Arc<Mutex<Vec<String>>>) -> BaseAgent {
+        let backend = MockBackend::script()
+            .otherwise(|request| {
+                match request
+                    .last_message()
+                    .strip_prefix("Based on the tool result: ")
+                {
+                    Some(result) => MockReply::text(format!("Noted: {result}")),
+                    None => MockReply::ToolCall {
+                        name: "rm".to_string(),
+                        parameters: serde_json::json!({"path": "/x"}),
+                    },
+                }
+            })
+            .build();
+        let tools = ToolManager::new();
+        tools.register(RmTool(removed));
+        BaseAgent::new(
+            Config::default(),
+            "cleaner",
+            "test agent",
+            Arc::new(backend),
+            memory(),
+            memory(),
+            memory(),
+            tools,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn denied_calls_are_skipped_and_reported_to_the_model() {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let mut agent = agent(removed.clone()).await;
+        agent.set_tool_approver(Box::new(|call: &ToolCall| {
+            assert_eq!(call.name, "rm");
+            ToolApproval::Deny(Some("keep /x".to_string()))
+        }));
+        let id = agent.start_conversation("m1");
+
+        let outcome = run_tool_loop(&mut agent, &id, "clean up", 3).await.unwrap();
+        assert!(removed.lock().unwrap().is_empty());
+        assert!(!outcome.tool_trace[0].success);
+        assert!(outcome.answer.contains("keep /x"), "{}", outcome.answer);
+    }
+
+    #[tokio::test]
+    async fn modified_calls_run_instead() {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let mut agent = agent(removed.clone()).await;
+        agent.set_tool_approver(Box::new(|call: &ToolCall| {
+            ToolApproval::Modify(ToolCall {
+                parameters: serde_json::json!({"path": "/tmp/x"}),
+                ..call.clone()
+            })
+        }));
+        let id = agent.start_conversation("m1");
+
+        let outcome = run_tool_loop(&mut agent, &id, "clean up", 3).await.unwrap();
+        assert_eq!(*removed.lock().unwrap(), ["/tmp/x"]);
+        assert!(outcome.tool_trace[0].success);
+
+        agent.clear_tool_approver();
+        agent
+            .execute_tool("rm", &serde_json::json!({"path": "/y"}))
+            .await
+            .unwrap();
+        assert_eq!(*removed.lock().unwrap(), ["/tmp/x", "/y"]);
+    }
+
+    #[tokio::test]
+    async fn destructive_calls_need_an_approver() {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let mut agent = agent(removed.clone()).await;
+        let root = serde_json::json!({"path": "/"});
+
+        let err = agent.execute_tool("rm", &root).await.unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)), "{err}");
+        assert!(removed.lock().unwrap().is_empty());
+
+        agent.set_tool_approver(Box::new(|_: &ToolCall| ToolApproval::Approve));
+        agent.execute_tool("rm", &root).await.unwrap();
+        assert_eq!(*removed.lock().unwrap(), ["/"]);
+    }
+}
@@ -0,0 +1,412 @@
+//! Request and response interception: implement [`AgentMiddleware`] and add it with
+//! [`BaseAgent::add_middleware`](crate::agent::BaseAgent::add_middleware) to rewrite what the
+//! agent sends to the LLM or a tool, what comes back, or to block a tool call.
+//!
+//! Middlewares form a stack: `before_*` hooks run in the order they were added and `after_*`
+//! hooks in reverse, so the first middleware added sees a request first and its response last.
+//! Unlike [observers](crate::agent::observer), which only watch, middlewares change the data
+//! the agent keeps working with.
+
+use crate::agent::types::ChatRequest;
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::tools::{ToolCall, ToolOutput};
+use crate::web::truncate_chars;
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+
+/// Whether a tool call may run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Continue,
+    /// Skip the call (and the remaining middlewares); the reason is passed back to the model.
+    Block(String),
+}
+
+/// Hooks around the agent's LLM and tool calls. All methods default to no-ops.
+///
+/// Hooks run inline on the agent task and are awaited before the call goes out.
+#[async_trait]
+pub trait AgentMiddleware: Send + Sync {
+    /// About to call the LLM. `messages` include any ephemeral memory context; changes apply to
+    /// this request only, not to the stored conversation. Streamed turns pass through here too.
+    async fn before_llm_call(&self, _request: &mut ChatRequest) {}
+
+    /// The LLM's reply, before it is returned (and stored by the caller). A streamed reply comes
+    /// here once complete, so while any middleware is registered its tokens are held back and
+    /// the rewritten reply is sent as one chunk.
+    async fn after_llm_response(&self, _response: &mut Message) {}
+
+    /// About to run a tool that the role policy and the tool approver let through.
+    async fn before_tool_execution(&self, _call: &mut ToolCall) -> Decision {
+        Decision::Continue
+    }
+
+    /// A tool's successful output, before the model sees it. Failures are passed on unchanged.
+    async fn after_tool_execution(&self, _output: &mut ToolOutput) {}
+}
+
+/// Replaces every match of its patterns (API keys, e-mail addresses, ...) in what is sent to the
+/// LLM, in its replies and in tool outputs. Tool call parameters are left alone.
+#[derive(Debug, Clone)]
+pub struct RegexRedactor {
+    patterns: Vec<Regex>,
+    replacement: String,
+}
+
+impl RegexRedactor {
+    /// Fails with [`KowalskiError::Configuration`] on an invalid pattern.
+    pub fn new<I, S>(patterns: I) -> Result<Self, KowalskiError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                let pattern = pattern.as_ref();
+                Regex::new(pattern).map_err(|e| {
+                    KowalskiError::Configuration(format!(
+                        "invalid redaction pattern '{pattern}': {e}"
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            patterns,
+            replacement: "[REDACTED]".to_string(),
+        })
+    }
+
+    /// Text put in place of each match (default `[REDACTED]`).
+    pub fn with_replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for pattern in &self.patterns {
+            if pattern.is_match(&text) {
+                text = pattern
+                    .replace_all(&text, regex::NoExpand(&self.replacement))
+                    .into_owned();
+            }
+        }
+        text
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.redact(s),
+            Value::Array(items) => items.iter_mut().for_each(|v| self.redact_value(v)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.redact_value(v)),
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl AgentMiddleware for RegexRedactor {
+    async fn before_llm_call(&self, request: &mut ChatRequest) {
+        for message in &mut request.messages {
+            message.content = self.redact(&message.content);
+        }
+    }
+
+    async fn after_llm_response(&self, response: &mut Message) {
+        response.content = self.redact(&response.content);
+    }
+
+    async fn after_tool_execution(&self, output: &mut ToolOutput) {
+        self.redact_value(&mut output.result);
+    }
+}
+
+/// Caps LLM replies and tool outputs at `max_chars` characters, so one huge page or query result
+/// cannot fill the context window. A tool output over the cap becomes its JSON text, cut short.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxLengthTruncator {
+    max_chars: usize,
+}
+
+impl MaxLengthTruncator {
+    pub fn new(max_chars: usize) -> Self {
+        Self { max_chars }
+    }
+}
+
+#[async_trait]
+impl AgentMiddleware for MaxLengthTruncator {
+    async fn after_llm_response(&self, response: &mut Message) {
+        response.content = truncate_chars(&response.content, self.max_chars);
+    }
+
+    async fn after_tool_execution(&self, output: &mut ToolOutput) {
+        let text = output.result.to_string();
+        if text.chars().count() > self.max_chars {
+            output.result = Value::String(truncate_chars(&text, self.max_chars));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::tool_loop::run_tool_loop;
+    use crate::agent::{Agent, BaseAgent};
+    use crate::config::Config;
+    use crate::testing::{MockBackend, MockReply, memory};
+    use crate::tools::manager::ToolManager;
+    use crate::tools::{Tool, ToolInput, ToolParameter};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    /// Asks to look up a user; after a tool result, answers with that result (streamed a few
+    /// characters at a time).
+    fn lookup_backend() -> Arc<MockBackend> {
+        Arc::new(
+            MockBackend::script()
+                .otherwise(|request| {
+                    let reply = match request
+                        .last_message()
+                        .strip_prefix("Based on the tool result: ")
+                    {
+                        Some(result) => format!("Found: {result}"),
+                        None => r#"{"name": "lookup", "parameters": {"user": "ann"}}"#.to_string(),
+                    };
+                    let chars: Vec<char> = reply.chars().collect();
+                    MockReply::Chunks(chars.chunks(8).map(|c| c.iter().collect()).collect())
+                })
+                .build(),
+        )
+    }
+
+    /// The last message of each request the backend got.
+    fn last_messages(backend: &MockBackend) -> Vec<String> {
+        backend
+            .requests()
+            .iter()
+            .map(|r| r.last_message().to_string())
+            .collect()
+    }
+
+    /// Records the users it was asked about; answers with their e-mail address and title.
+    struct LookupTool(Log);
+
+    #[async_trait]
+    impl Tool for LookupTool {
+        async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+            let user = input.parameters["user"].as_str().unwrap_or_default();
+            self.0.lock().unwrap().push(user.to_string());
+            Ok(ToolOutput::new(
+                json!({
+                    "user": user,
+                    "email": format!("{user}@example.com"),
+                    "title": "Head of Research and Development",
+                }),
+                None,
+            ))
+        }
+
+        fn name(&self) -> &str {
+            "lookup"
+        }
+
+        fn description(&self) -> &str {
+            "Looks up a user"
+        }
+
+        fn parameters(&self) -> Vec<ToolParameter> {
+            Vec::new()
+        }
+    }
+
+    /// Logs each hook as `{name}:{hook}`; blocks tool calls when `block` is set.
+    struct Recorder {
+        name: &'static str,
+        log: Log,
+        block: bool,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, log: &Log) -> Box<Self> {
+            Box::new(Self {
+                name,
+                log: log.clone(),
+                block: false,
+            })
+        }
+
+        fn push(&self, hook: &str) {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}:{hook}", self.name));
+        }
+    }
+
+    #[async_trait]
+    impl AgentMiddleware for Recorder {
+        async fn before_llm_call(&self, _request: &mut ChatRequest) {
+            self.push("before_llm");
+        }
+
+        async fn after_llm_response(&self, _response: &mut Message) {
+            self.push("after_llm");
+        }
+
+        async fn before_tool_execution(&self, call: &mut ToolCall) -> Decision {
+            self.push("before_tool");
+            if self.block {
+                return Decision::Block(format!("{} is off limits", call.parameters["user"]));
+            }
+            Decision::Continue
+        }
+
+        async fn after_tool_execution(&self, _output: &mut ToolOutput) {
+            self.push("after_tool");
+        }
+    }
+
+    async fn agent(backend: &Arc<MockBackend>, tool_log: &Log) -> BaseAgent {
+        let tools = ToolManager::new();
+        tools.register(LookupTool(tool_log.clone()));
+        BaseAgent::new(
+            Config::default(),
+            "directory",
+            "test agent",
+            backend.clone(),
+            memory(),
+            memory(),
+            memory(),
+            tools,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn hooks_run_as_a_stack() {
+        let (tool_log, log) = (Log::default(), Log::default());
+        let mut agent = agent(&lookup_backend(), &tool_log).await;
+        agent.add_middleware(Recorder::new("a", &log));
+        agent.add_middleware(Recorder::new("b", &log));
+        let id = agent.start_conversation("m1");
+
+        let outcome = run_tool_loop(&mut agent, &id, "find ann", 3).await.unwrap();
+        assert!(outcome.tool_trace[0].success);
+        let llm_turn = ["a:before_llm", "b:before_llm", "b:after_llm", "a:after_llm"];
+        let tool_call = [
+            "a:before_tool",
+            "b:before_tool",
+            "b:after_tool",
+            "a:after_tool",
+        ];
+        assert_eq!(
+            *log.lock().unwrap(),
+            [&llm_turn[..], &tool_call[..], &llm_turn[..]].concat()
+        );
+    }
+
+    #[tokio::test]
+    async fn blocked_calls_are_skipped_and_reported_to_the_model() {
+        let (tool_log, log) = (Log::default(), Log::default());
+        let mut agent = agent(&lookup_backend(), &tool_log).await;
+        let mut guard = Recorder::new("guard", &log);
+        guard.block = true;
+        agent.add_middleware(guard);
+        agent.add_middleware(Recorder::new("inner", &log));
+        let id = agent.start_conversation("m1");
+
+        let outcome = run_tool_loop(&mut agent, &id, "find ann", 3).await.unwrap();
+        assert!(tool_log.lock().unwrap().is_empty());
+        assert!(!outcome.tool_trace[0].success);
+        assert!(
+            outcome
+                .answer
+                .contains("lookup call blocked: \"ann\" is off limits"),
+            "{}",
+            outcome.answer
+        );
+        let log = log.lock().unwrap();
+        assert!(log.contains(&"guard:before_tool".to_string()));
+        assert!(
+            !log.iter()
+                .any(|e| e.starts_with("inner:") && e.contains("tool"))
+        );
+    }
+
+    #[tokio::test]
+    async fn redactor_and_truncator_rewrite_requests_replies_and_outputs() {
+        let (backend, tool_log) = (lookup_backend(), Log::default());
+        let mut agent = agent(&backend, &tool_log).await;
+        agent.add_middleware(Box::new(MaxLengthTruncator::new(60)));
+        agent.add_middleware(Box::new(
+            RegexRedactor::new([r"[\w.]+@[\w.]+", r"sk-\w+"]).unwrap(),
+        ));
+        let id = agent.start_conversation("m1");
+
+        let outcome = run_tool_loop(&mut agent, &id, "find ann, key sk-abc123", 3)
+            .await
+            .unwrap();
+        assert_eq!(*tool_log.lock().unwrap(), ["ann"]);
+        let llm_log = last_messages(&backend);
+        assert_eq!(llm_log[0], "find ann, key [REDACTED]");
+        // Redacted first (the redactor was added last), then cut to 60 characters.
+        assert!(
+            llm_log[1].contains(r#"{\"email\":\"[REDACTED]\",\"title\":\"Head of"#),
+            "{}",
+            llm_log[1]
+        );
+        assert!(!llm_log[1].contains("Development"), "{}", llm_log[1]);
+        assert_eq!(outcome.answer.chars().count(), 61, "{}", outcome.answer);
+        assert!(outcome.answer.ends_with('…'), "{}", outcome.answer);
+
+        assert!(matches!(
+            RegexRedactor::new(["("]),
+            Err(KowalskiError::Configuration(_))
+        ));
+        assert_eq!(
+            RegexRedactor::new(["secret"])
+                .unwrap()
+                .with_replacement("$1")
+                .redact("a secret"),
+            "a $1"
+        );
+    }
+
+    #[tokio::test]
+    async fn streamed_replies_are_rewritten_before_they_are_sent_or_stored() {
+        let tool_log = Log::default();
+        let mut agent = agent(&lookup_backend(), &tool_log).await;
+        agent.add_middleware(Box::new(RegexRedactor::new([r"[\w.]+@[\w.]+"]).unwrap()));
+        let id = agent.start_conversation("m1");
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+        let answer = agent
+            .chat_with_tools_stream_final(&id, "find ann", &tx)
+            .await
+            .unwrap();
+        drop(tx);
+        let mut sent = Vec::new();
+        while let Some(delta) = rx.recv().await {
+            sent.push(delta);
+        }
+
+        assert_eq!(*tool_log.lock().unwrap(), ["ann"]);
+        assert!(answer.contains(r#""email":"[REDACTED]""#), "{answer}");
+        assert!(!answer.contains("ann@example.com"), "{answer}");
+        assert_eq!(sent, std::slice::from_ref(&answer));
+        let stored = agent
+            .get_conversation(&id)
+            .unwrap()
+            .messages
+            .last()
+            .unwrap();
+        assert_eq!(stored.content, answer);
+    }
+}
@@ -1,29 +1,63 @@
-use crate::agent::types::StreamResponse;
+use crate::agent::approval::ToolApprover;
+use crate::agent::middleware::{AgentMiddleware, Decision};
+use crate::agent::observer::{AgentObserver, TracingObserver};
+use crate::agent::prompt::SystemPromptTemplate;
+use crate::agent::timings::ChatTimings;
+use crate::agent::types::{AgentCapabilities, ChatRequest, StreamResponse};
 use crate::config::Config;
 use crate::conversation::Conversation;
-use crate::conversation::Message;
+use crate::conversation::{ImageData, Message, MessageRole};
 use crate::error::KowalskiError;
+use crate::llm::ChatOptions;
 use crate::memory::MemoryProvider;
 use crate::memory::MemoryUnit;
+use crate::memory::profile::ProfileStore;
 use crate::memory::working::WorkingMemory;
+use crate::prompts::{PromptKind, PromptRegistry};
 use crate::role::Role;
-use crate::tools::{ToolCall, ToolOutput};
+use crate::tools::{
+    HistorySearchTool, ToolCall, ToolOutput, tool_result_message, untrusted_tool_output,
+};
+use crate::utils::ndjson::NdjsonBuffer;
+use crate::utils::redact::redacted_json;
 use async_trait::async_trait;
 use futures::StreamExt;
 use log::debug;
 use log::info;
 use log::warn;
+use log::{Level, log_enabled, trace};
 use serde_json;
 use serde_json::json;
 use std::any::Any;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::io::{self, Write};
-use std::time::{SystemTime, UNIX_EPOCH};
-
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{Instrument, field};
+
+pub mod approval;
+pub mod middleware;
+pub mod observer;
+pub mod prompt;
 pub mod repl_trace;
+pub mod structured;
+pub mod timings;
+pub mod tool_loop;
 pub mod types;
 
+/// Ephemeral system hint sent with JSON-mode turns (see [`crate::config::ChatConfig::json_tool_calls`]).
+const JSON_MODE_PROMPT: &str = "Reply with a single JSON object: either {\"name\": \"<tool_name>\", \"parameters\": { ... }} to call a tool, or {\"answer\": \"<your reply>\"} when no tool is needed.";
+
+/// A prepared streamed turn (see [`BaseAgent::prepare_stream_turn`]): model, messages, the
+/// provider, and the sampling settings to stream with.
+pub type StreamTurn = (
+    String,
+    Vec<Message>,
+    std::sync::Arc<dyn crate::llm::LLMProvider>,
+    ChatOptions,
+);
+
 /// The core agent trait that all our specialized agents must implement.
 #[async_trait]
 pub trait Agent: Send + Sync {
@@ -44,6 +78,22 @@ pub trait Agent: Send + Sync {
     /// Deletes a conversation
     fn delete_conversation(&mut self, id: &str) -> bool;
 
+    /// Ends a conversation. With [`memory.summarize_on_close`](crate::config::MemoryConfig::summarize_on_close)
+    /// it is first summarized into memory (see [`Self::summarize_conversation`]); if that fails
+    /// the conversation is kept and the error returned. Returns whether the conversation existed.
+    async fn close_conversation(&mut self, id: &str) -> Result<bool, KowalskiError> {
+        Ok(self.delete_conversation(id))
+    }
+
+    /// Stores a summary of conversation `id` (topics, decisions, open questions) in episodic and
+    /// semantic memory, so later conversations can recall it. `None` when there was nothing to
+    /// summarize.
+    async fn summarize_conversation(&self, _id: &str) -> Result<Option<MemoryUnit>, KowalskiError> {
+        Err(KowalskiError::Agent(
+            "Session summaries not implemented for this agent".to_string(),
+        ))
+    }
+
     /// Chats with history (model messages) for the given conversation.
     async fn chat_with_history(
         &mut self,
@@ -52,22 +102,151 @@ pub trait Agent: Send + Sync {
         role: Option<Role>,
     ) -> Result<String, KowalskiError>;
 
-    /// Processes a stream response
+    /// [`Self::chat_with_history`], sending the reply's text to `tokens` as the model produces
+    /// it. The default sends the whole reply once it is complete.
+    async fn chat_with_history_stream(
+        &mut self,
+        conversation_id: &str,
+        content: &str,
+        tokens: &tokio::sync::mpsc::Sender<String>,
+    ) -> Result<String, KowalskiError> {
+        let reply = self
+            .chat_with_history(conversation_id, content, None)
+            .await?;
+        let _ = tokens.send(reply.clone()).await;
+        Ok(reply)
+    }
+
+    /// Feeds one raw chunk of an NDJSON chat stream. Chunks may split or batch objects; partial
+    /// lines are buffered per conversation. Returns the content of every object the chunk
+    /// completed (empty when it completed none), or `None` once the `done` object arrives with
+    /// nothing left to return.
     async fn process_stream_response(
         &mut self,
         conversation_id: &str,
         chunk: &[u8],
     ) -> Result<Option<Message>, KowalskiError>;
 
+    /// Chats with images attached to the user turn (vision models such as `llava`).
+    async fn chat_with_images(
+        &mut self,
+        _conversation_id: &str,
+        _content: &str,
+        _images: &[PathBuf],
+    ) -> Result<String, KowalskiError> {
+        Err(KowalskiError::Agent(
+            "Image input not implemented for this agent".to_string(),
+        ))
+    }
+
     /// Adds a message to a conversation
     async fn add_message(&mut self, conversation_id: &str, role: &str, content: &str);
 
+    /// Records that the model's `reply` asked for `call` and the tool returned `result` (see
+    /// [`Conversation::add_tool_exchange`]). The default adds one assistant message with
+    /// [`tool_result_message`] for agents that keep no structured history.
+    async fn add_tool_exchange(
+        &mut self,
+        conversation_id: &str,
+        _reply: &str,
+        call: &ToolCall,
+        result: &str,
+        source: Option<&str>,
+    ) {
+        let message = tool_result_message(&call.name, result, source);
+        self.add_message(conversation_id, "assistant", &message)
+            .await;
+    }
+
+    /// Sends `prompt` and returns the reply as JSON conforming to `schema` (a JSON Schema, e.g.
+    /// `{name, email, company}` to extract from text). Replies that do not parse or validate are
+    /// retried; when none does, the error is [`KowalskiError::StructuredOutput`].
+    async fn chat_structured(
+        &mut self,
+        _conversation_id: &str,
+        _prompt: &str,
+        _schema: &serde_json::Value,
+    ) -> Result<serde_json::Value, KowalskiError> {
+        Err(KowalskiError::Agent(
+            "Structured output not implemented for this agent".to_string(),
+        ))
+    }
+
+    /// Asks again for the reply to the last user message, replacing the previous reply. `options`
+    /// override the agent's sampling settings for this request only.
+    async fn regenerate_last(
+        &mut self,
+        _conversation_id: &str,
+        _options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        Err(KowalskiError::Agent(
+            "Regenerating replies not implemented for this agent".to_string(),
+        ))
+    }
+
+    /// Past messages from the agent's episodic memory that match `query`, best first (embedding
+    /// similarity and recency, or keywords when there are no embeddings). Each carries its
+    /// timestamp.
+    async fn search_history(
+        &self,
+        _query: &str,
+        _limit: usize,
+    ) -> Result<Vec<MemoryUnit>, KowalskiError> {
+        Err(KowalskiError::Agent(
+            "Searching history not implemented for this agent".to_string(),
+        ))
+    }
+
+    /// Saves a profile fact; every conversation started afterwards begins with the agent's facts
+    /// (see [`ProfileStore`]).
+    async fn remember_fact(&self, _key: &str, _value: &str) -> Result<(), KowalskiError> {
+        Err(KowalskiError::Agent(
+            "Profile facts not implemented for this agent".to_string(),
+        ))
+    }
+
+    /// Removes a profile fact; returns whether it existed.
+    async fn forget_fact(&self, _key: &str) -> Result<bool, KowalskiError> {
+        Err(KowalskiError::Agent(
+            "Profile facts not implemented for this agent".to_string(),
+        ))
+    }
+
+    /// The agent's profile facts, sorted by key.
+    fn list_facts(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
     /// Exports a conversation to a JSON string
     fn export_conversation(&self, id: &str) -> Result<String, KowalskiError>;
 
     /// Imports a conversation from a JSON string, returns the new conversation ID
     fn import_conversation(&mut self, json: &str) -> Result<String, KowalskiError>;
 
+    /// Copies conversation `id` into a new conversation (fresh id, optionally a different model)
+    /// and returns the new id. Later turns on either side do not affect the other.
+    fn fork_conversation(
+        &mut self,
+        id: &str,
+        model: Option<&str>,
+    ) -> Result<String, KowalskiError> {
+        let original: Conversation = serde_json::from_str(&self.export_conversation(id)?)?;
+        let forked = original.fork(model);
+        self.import_conversation(&serde_json::to_string(&forked)?)
+    }
+
+    /// Imports a conversation handed off by `from_agent` under a fresh id (see
+    /// [`Conversation::handed_off`]) and returns the new id.
+    fn accept_handoff(
+        &mut self,
+        conversation: &Conversation,
+        from_agent: &str,
+        reason: &str,
+    ) -> Result<String, KowalskiError> {
+        let local = conversation.handed_off(from_agent, reason);
+        self.import_conversation(&serde_json::to_string(&local)?)
+    }
+
     /// Executes a tool with the given name and input.
     async fn execute_tool(
         &mut self,
@@ -91,6 +270,9 @@ pub trait Agent: Send + Sync {
         const MAX_ITERATIONS: usize = 5; // Prevent infinite loops
         let mut last_tool_call: Option<(String, serde_json::Value)> = None;
         let mut tool_parse_hint_sent = false;
+        let dry_run = self.base_agent_mut().is_some_and(|base| base.dry_run);
+        let started = Instant::now();
+        let mut timings = ChatTimings::default();
 
         debug!("Starting chat_with_tools for input: '{}'", user_input);
 
@@ -101,19 +283,20 @@ pub trait Agent: Send + Sync {
 
             // Get response from LLM
             debug!("Calling LLM...");
+            let llm_started = Instant::now();
             let response_text = self
                 .chat_with_history(conversation_id, &current_input, None)
                 .await?;
+            let recall = self
+                .base_agent_mut()
+                .and_then(BaseAgent::take_recall_time)
+                .unwrap_or_default();
+            timings.memory_recall += recall;
+            timings
+                .llm
+                .push(llm_started.elapsed().saturating_sub(recall));
 
-            // Print response (simulate streaming effect or just print)
-            if repl_trace::repl_trace_enabled() {
-                println!("[agent] {}", response_text);
-            } else {
-                println!("{}", response_text);
-            }
-            io::stdout()
-                .flush()
-                .map_err(|e| KowalskiError::Server(e.to_string()))?;
+            repl_trace::print_reply(&response_text)?;
 
             let buffer = response_text.clone();
             debug!("Full LLM response: '{}'", buffer);
@@ -123,6 +306,13 @@ pub trait Agent: Send + Sync {
             let tool_calls = crate::utils::json::extract_tool_calls(&buffer);
 
             if !tool_calls.is_empty() {
+                if dry_run {
+                    debug!("dry run: {} call(s) planned", tool_calls.len());
+                    final_response = buffer;
+                    self.add_message(conversation_id, "assistant", &final_response)
+                        .await;
+                    break;
+                }
                 // For now, we only process the first tool call found in one turn
                 let tool_call = &tool_calls[0];
 
@@ -149,26 +339,35 @@ pub trait Agent: Send + Sync {
                     println!("[tool] {} {}", tool_call.name, params);
                 }
 
-                let tool_result = match self
+                let tool_started = Instant::now();
+                let tool_output = self
                     .execute_tool(&tool_call.name, &tool_call.parameters)
-                    .await
-                {
-                    Ok(output) => output.result.to_string(),
+                    .await;
+                timings
+                    .tools
+                    .push((tool_call.name.clone(), tool_started.elapsed()));
+                let (tool_result, source) = match tool_output {
+                    Ok(output) => (output.result.to_string(), output.source),
                     Err(e) => {
                         let err_msg = format!("{}", e);
                         debug!("Tool execution failed: {}", err_msg);
 
                         // Basic fallback/chaining logic can be integrated here if needed
-                        err_msg
+                        (err_msg, None)
                     }
                 };
 
-                let tool_message = format!("Tool result for {}: {}", tool_call.name, tool_result);
-                self.add_message(conversation_id, "assistant", &tool_message)
-                    .await;
+                self.add_tool_exchange(
+                    conversation_id,
+                    &buffer,
+                    tool_call,
+                    &tool_result,
+                    source.as_deref(),
+                )
+                .await;
                 debug!("Added tool result to conversation");
 
-                current_input = format!("Based on the tool result: {}", tool_result);
+                current_input = self.tool_result_prompt(&tool_call.name, &tool_result);
                 debug!("Continuing with new input: '{}'", current_input);
                 continue;
             }
@@ -195,11 +394,15 @@ pub trait Agent: Send + Sync {
                 .await;
             debug!("✅ Final response set: '{}'", final_response);
 
-            if let Some(tool_call) = rule_based_tool_call(user_input) {
+            if !dry_run && let Some(tool_call) = rule_based_tool_call(user_input) {
                 debug!("Rule-based tool call triggered: {:?}", tool_call);
+                let tool_started = Instant::now();
                 let tool_result = self
                     .execute_tool(&tool_call.name, &tool_call.parameters)
                     .await;
+                timings
+                    .tools
+                    .push((tool_call.name.clone(), tool_started.elapsed()));
                 let tool_result_str = match tool_result {
                     Ok(output) => output.result.to_string(),
                     Err(e) => format!("Tool execution failed: {}", e),
@@ -207,6 +410,8 @@ pub trait Agent: Send + Sync {
                 self.add_message(conversation_id, "assistant", &tool_result_str)
                     .await;
                 debug!("Rule-based tool result: {}", tool_result_str);
+                timings.total = started.elapsed();
+                report_chat_timings(self, conversation_id, timings);
                 return Ok(tool_result_str);
             }
 
@@ -221,14 +426,51 @@ pub trait Agent: Send + Sync {
             "chat_with_tools completed after {} iterations",
             iteration_count
         );
+        timings.total = started.elapsed();
+        report_chat_timings(self, conversation_id, timings);
         Ok(final_response)
     }
 
+    /// The next user turn after `tool` returned `result` ([`PromptKind::ToolResult`]).
+    fn tool_result_prompt(&self, tool: &str, result: &str) -> String {
+        PromptRegistry::default().render(
+            PromptKind::ToolResult,
+            &[("tool", tool), ("result", result)],
+        )
+    }
+
     /// Lists tools available to this agent
     async fn list_tools(&self) -> Vec<(String, String)> {
         Vec::new()
     }
 
+    /// The agent's tool registry, for callers that run tools directly (without the LLM, the
+    /// approver or observers).
+    fn tool_manager(&self) -> Option<&crate::tools::manager::ToolManager> {
+        None
+    }
+
+    /// What this agent offers: its tools, whether it streams, its default model and description.
+    /// The default reports the tools of [`Agent::tool_manager`], no streaming and no model.
+    async fn capabilities(&self) -> AgentCapabilities {
+        let tools = match self.tool_manager() {
+            Some(manager) => manager.metadata().await,
+            None => Vec::new(),
+        };
+        AgentCapabilities {
+            tools,
+            supports_streaming: false,
+            default_model: String::new(),
+            description: self.description().to_string(),
+        }
+    }
+
+    /// The [`BaseAgent`] this agent is built on, for wiring observers or a tool approver into a
+    /// boxed agent.
+    fn base_agent_mut(&mut self) -> Option<&mut BaseAgent> {
+        None
+    }
+
     fn name(&self) -> &str;
 
     /// Gets the agent's description
@@ -237,14 +479,149 @@ pub trait Agent: Send + Sync {
     fn as_any(&self) -> &dyn Any;
 }
 
+/// Hands `timings` to the agent's [`BaseAgent`] (observers and
+/// [`BaseAgent::last_chat_timings`]), or logs them when there is none.
+fn report_chat_timings<A: Agent + ?Sized>(
+    agent: &mut A,
+    conversation_id: &str,
+    timings: ChatTimings,
+) {
+    match agent.base_agent_mut() {
+        Some(base) => base.report_chat_timings(conversation_id, timings),
+        None => debug!("conversation {conversation_id}: chat_with_tools timings: {timings}"),
+    }
+}
+
+const MEMORY_START: &str = "--- Relevant Memories ---";
+const MEMORY_END: &str = "--- End Memories ---";
+
+/// The ephemeral system message carrying retrieved memories into one LLM request. The markers
+/// stay around the memories whatever the [`PromptKind::MemoryContext`] template says, so
+/// [`injected_memories`] can find them.
+fn memory_prompt(prompts: &PromptRegistry, context: &str) -> String {
+    let memories = format!("{MEMORY_START}\n{context}\n{MEMORY_END}");
+    prompts.render(PromptKind::MemoryContext, &[("memories", &memories)])
+}
+
+/// Keeps `items` in order until `max_chars` is used up; the item that crosses the budget is cut
+/// at a character boundary and the rest are dropped.
+fn within_char_budget<'a>(items: impl Iterator<Item = &'a str>, max_chars: usize) -> Vec<&'a str> {
+    let mut left = max_chars;
+    let mut kept = Vec::new();
+    for item in items {
+        if left == 0 {
+            break;
+        }
+        match item.char_indices().nth(left) {
+            Some((cut, _)) => {
+                kept.push(&item[..cut]);
+                break;
+            }
+            None => {
+                left -= item.chars().count();
+                kept.push(item);
+            }
+        }
+    }
+    kept
+}
+
+/// What a request carries of `messages`: every system message, then the last `window` others
+/// (at least the newest), in conversation order. A `tool` result whose call fell outside the
+/// window is left out too. `pinned` goes right after the leading system messages.
+fn short_term_messages(
+    messages: &[Message],
+    window: usize,
+    pinned: Option<Message>,
+) -> Vec<Message> {
+    let turns: Vec<usize> = (0..messages.len())
+        .filter(|&i| messages[i].role != "system")
+        .collect();
+    let mut first = turns.len().saturating_sub(window.max(1));
+    while first + 1 < turns.len() && messages[turns[first]].role == "tool" {
+        first += 1;
+    }
+    let cutoff = turns.get(first).copied().unwrap_or(messages.len());
+    let mut kept: Vec<Message> = messages
+        .iter()
+        .enumerate()
+        .filter(|(i, m)| m.role == "system" || *i >= cutoff)
+        .map(|(_, m)| m.clone())
+        .collect();
+    if let Some(pinned) = pinned {
+        let at = kept.iter().take_while(|m| m.role == "system").count();
+        kept.insert(at, pinned);
+    }
+    kept
+}
+
+/// A conversation message as stored in the memory tiers: `[role] content`, timestamped now.
+fn message_memory_unit(conversation_id: &str, role: &str, content: &str) -> MemoryUnit {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let timestamp = now.as_secs();
+    MemoryUnit {
+        // Use nanosecond precision to avoid collisions when multiple messages
+        // are added in the same second.
+        id: format!(
+            "{}-{}-{}-{}",
+            conversation_id,
+            timestamp,
+            now.as_nanos(),
+            role
+        ),
+        timestamp,
+        content: format!("[{}] {}", role, content),
+        embedding: None, // Embeddings are generated during consolidation
+        metadata: Default::default(),
+    }
+}
+
+/// The memory items injected into an LLM request, as seen by
+/// [`AgentObserver::on_llm_request`]; empty when the request carried no memory.
+pub fn injected_memories(messages: &[Message]) -> Vec<String> {
+    let Some(lines) = messages
+        .iter()
+        .filter(|m| m.role == "system")
+        .map(|m| m.content.lines().collect::<Vec<_>>())
+        .find(|lines| lines.iter().any(|line| line.trim() == MEMORY_START))
+    else {
+        return Vec::new();
+    };
+    let start = lines.iter().position(|line| line.trim() == MEMORY_START);
+    let end = lines.iter().rposition(|line| line.trim() == MEMORY_END);
+    let body: Vec<&str> = match (start, end) {
+        (Some(start), Some(end)) if end > start => &lines[start + 1..end],
+        (Some(start), _) => &lines[start + 1..],
+        _ => &[],
+    }
+    .iter()
+    .copied()
+    .filter(|line| !matches!(line.trim(), MEMORY_START | MEMORY_END))
+    .collect();
+    body.join("\n")
+        .split("\n---\n")
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 /// The base agent implementation that provides common functionality.
 pub struct BaseAgent {
+    /// Handle to the [`shared_http_client`](crate::llm::shared_http_client) pool.
     pub client: reqwest::Client,
     pub config: Config,
     pub conversations: HashMap<String, Conversation>,
     pub name: String,
     pub description: String,
     pub system_prompt: Option<String>,
+    /// Rendered into the leading system message of each new conversation; defaults to
+    /// [`ChatConfig::system_prompt_template`](crate::config::ChatConfig::system_prompt_template).
+    pub system_prompt_template: Option<SystemPromptTemplate>,
+    /// Tool, memory and fallback system prompts; built-ins overridden by `[prompts]`.
+    pub prompts: PromptRegistry,
     // LLM Provider
     pub llm_provider: std::sync::Arc<dyn crate::llm::LLMProvider>,
     // Memory Tiers - now using dependency injection
@@ -253,6 +630,25 @@ pub struct BaseAgent {
     pub semantic_memory: std::sync::Arc<tokio::sync::Mutex<dyn MemoryProvider + Send + Sync>>,
     // Tool Manager
     pub tool_manager: crate::tools::manager::ToolManager,
+    /// Lifecycle hooks; starts with a [`TracingObserver`].
+    pub observers: Vec<Box<dyn AgentObserver>>,
+    /// Reviews each tool call before it runs; `None` runs every call.
+    pub tool_approver: Option<Box<dyn ToolApprover>>,
+    /// Interceptors of LLM and tool calls, outermost first (see [`middleware`]).
+    pub middleware: Vec<Box<dyn AgentMiddleware>>,
+    /// Plan instead of act: tool-calling turns stop at the first reply that calls a tool and
+    /// return it without running the tool (see [`tool_loop::ToolLoopOptions::dry_run`]).
+    pub dry_run: bool,
+    /// Role whose tool restrictions apply to this agent (see [`Role::allows_tool`]); its prompt
+    /// is not added by itself.
+    pub role: Option<Role>,
+    /// Facts every new conversation starts with; `None` keeps no profile.
+    pub profile: Option<std::sync::Arc<ProfileStore>>,
+    /// Partial NDJSON lines per conversation (see [`Agent::process_stream_response`]).
+    stream_buffers: HashMap<String, NdjsonBuffer>,
+    /// Memory recall time of the last chat turn, until a tool loop takes it for its timings.
+    last_recall: Option<Duration>,
+    last_chat_timings: Option<ChatTimings>,
 }
 
 #[derive(Debug, Clone)]
@@ -279,46 +675,52 @@ impl BaseAgent {
         Self::recent_conversation_items(messages, max_items).join("\n---\n")
     }
 
+    #[tracing::instrument(name = "memory_recall", skip(self, content), fields(recalled = field::Empty))]
     async fn retrieve_memory_items(&self, content: &str, use_memory: bool) -> Vec<MemoryUnit> {
         if !use_memory {
             return Vec::new();
         }
 
-        let working_memories = self
-            .working_memory
-            .lock()
-            .await
-            .retrieve(content, self.config.working_memory_retrieval_limit)
-            .await
-            .unwrap_or_default();
-
-        let episodic_memories = self
-            .episodic_memory
-            .lock()
-            .await
-            .retrieve(content, self.config.episodic_memory_retrieval_limit)
-            .await
-            .unwrap_or_default();
-
-        let semantic_memories = self
-            .semantic_memory
-            .lock()
-            .await
-            .retrieve(content, self.config.semantic_memory_retrieval_limit)
-            .await
-            .unwrap_or_default();
+        let tiers = [
+            (
+                "working",
+                &self.working_memory,
+                self.config.working_memory_retrieval_limit,
+            ),
+            (
+                "episodic",
+                &self.episodic_memory,
+                self.config.episodic_memory_retrieval_limit,
+            ),
+            (
+                "semantic",
+                &self.semantic_memory,
+                self.config.semantic_memory_retrieval_limit,
+            ),
+        ];
+        let mut recalled = Vec::new();
+        let recall_limit = self.config.memory_recall_limit;
+        for (tier, provider, limit) in tiers {
+            // A store that is down degrades recall; it must not fail the user's turn.
+            let limit = limit.min(recall_limit);
+            let started = Instant::now();
+            let retrieved = provider.lock().await.retrieve(content, limit).await;
+            crate::metrics::record_memory_recall(tier, started.elapsed());
+            match retrieved {
+                Ok(memories) => recalled.extend(memories),
+                Err(e) => warn!("Skipping {} memory for this turn: {}", tier, e),
+            }
+        }
 
         let mut seen_ids = HashSet::new();
         let mut all_memories = Vec::new();
-        for m in working_memories
-            .into_iter()
-            .chain(episodic_memories)
-            .chain(semantic_memories)
-        {
+        for m in recalled {
             if seen_ids.insert(m.id.clone()) {
                 all_memories.push(m);
             }
         }
+        all_memories.truncate(recall_limit);
+        tracing::Span::current().record("recalled", all_memories.len());
         all_memories
     }
 
@@ -329,11 +731,11 @@ impl BaseAgent {
             return String::new();
         }
 
-        let concatenated_memories = all_memories
-            .iter()
-            .map(|m| m.content.as_str())
-            .collect::<Vec<&str>>()
-            .join("\n---\n");
+        let concatenated_memories = within_char_budget(
+            all_memories.iter().map(|m| m.content.as_str()),
+            self.config.memory_recall_max_chars,
+        )
+        .join("\n---\n");
         format!(
             "\n--- Relevant Memories ---\n{}\n--- End Memories ---",
             concatenated_memories
@@ -391,16 +793,19 @@ impl BaseAgent {
         semantic_memory: std::sync::Arc<tokio::sync::Mutex<dyn MemoryProvider + Send + Sync>>,
         tool_manager: crate::tools::manager::ToolManager,
     ) -> Result<Self, KowalskiError> {
-        let client = reqwest::ClientBuilder::new()
-            .http1_only()
-            .pool_max_idle_per_host(0)
-            .build()
-            .map_err(KowalskiError::Request)?;
+        let client = crate::llm::shared_http_client();
+        let prompts = PromptRegistry::from_config(&config.prompts)?;
 
         info!("BaseAgent created with name: {}", name);
 
         Ok(Self {
             client,
+            prompts,
+            system_prompt_template: config
+                .chat
+                .system_prompt_template
+                .as_deref()
+                .map(SystemPromptTemplate::new),
             config,
             conversations: HashMap::new(),
             name: name.to_string(),
@@ -411,9 +816,348 @@ impl BaseAgent {
             episodic_memory,
             semantic_memory,
             tool_manager,
+            observers: vec![Box::new(TracingObserver)],
+            tool_approver: None,
+            middleware: Vec::new(),
+            dry_run: false,
+            role: None,
+            profile: None,
+            stream_buffers: HashMap::new(),
+            last_recall: None,
+            last_chat_timings: None,
+        })
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn AgentObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Removes every observer, including the default [`TracingObserver`].
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    fn notify(&self, event: impl Fn(&dyn AgentObserver)) {
+        for observer in &self.observers {
+            event(observer.as_ref());
+        }
+    }
+
+    fn take_recall_time(&mut self) -> Option<Duration> {
+        self.last_recall.take()
+    }
+
+    fn report_chat_timings(&mut self, conversation_id: &str, timings: ChatTimings) {
+        self.notify(|o| o.on_chat_timings(conversation_id, &timings));
+        self.last_chat_timings = Some(timings);
+    }
+
+    /// Phase durations of the last completed tool-calling turn ([`Agent::chat_with_tools`],
+    /// [`Self::chat_with_tools_with_options`] or the shared [`tool_loop`]).
+    pub fn last_chat_timings(&self) -> Option<&ChatTimings> {
+        self.last_chat_timings.as_ref()
+    }
+
+    /// Routes every tool call through `approver` first (see [`approval`]).
+    pub fn set_tool_approver(&mut self, approver: Box<dyn ToolApprover>) {
+        self.tool_approver = Some(approver);
+    }
+
+    pub fn clear_tool_approver(&mut self) {
+        self.tool_approver = None;
+    }
+
+    /// Pushes `middleware` onto the stack: its `before_*` hooks run after those added earlier,
+    /// its `after_*` hooks before them.
+    pub fn add_middleware(&mut self, middleware: Box<dyn AgentMiddleware>) {
+        self.middleware.push(middleware);
+    }
+
+    pub fn clear_middleware(&mut self) {
+        self.middleware.clear();
+    }
+
+    /// Whether any middleware is registered. A streamed reply is then held back until
+    /// [`Self::finish_streamed_reply`] has run, since a middleware may rewrite it.
+    pub fn has_middleware(&self) -> bool {
+        !self.middleware.is_empty()
+    }
+
+    /// Passes a complete streamed reply through every middleware's `after_llm_response`; callers
+    /// of [`Self::prepare_stream_turn`] do this before showing or storing it.
+    pub async fn finish_streamed_reply(&self, reply: String) -> String {
+        self.intercept_response(reply).await
+    }
+
+    /// Passes an LLM request through every middleware's `before_llm_call`.
+    async fn intercept_request(&self, request: &mut ChatRequest) {
+        for middleware in &self.middleware {
+            middleware.before_llm_call(request).await;
+        }
+    }
+
+    /// Passes an LLM reply through every middleware's `after_llm_response`, innermost first.
+    async fn intercept_response(&self, response: String) -> String {
+        if self.middleware.is_empty() {
+            return response;
+        }
+        let mut message = Message {
+            role: "assistant".to_string(),
+            content: response,
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        };
+        for middleware in self.middleware.iter().rev() {
+            middleware.after_llm_response(&mut message).await;
+        }
+        message.content
+    }
+
+    /// Archives a user turn sent straight to the LLM in the episodic buffer, so
+    /// [`Agent::search_history`] finds what was asked as well as what was answered.
+    async fn archive_user_turn(&self, conversation_id: &str, content: &str) {
+        let unit = message_memory_unit(conversation_id, "user", content);
+        if let Err(e) = self.episodic_memory.lock().await.add(unit).await {
+            warn!("Failed to add to episodic memory: {}", e);
+        }
+    }
+
+    /// See [`Agent::search_history`].
+    pub async fn search_history(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<MemoryUnit>, KowalskiError> {
+        self.history_search_tool().search(query, limit).await
+    }
+
+    /// `result` as it enters the conversation: an [`untrusted_tool_output`] block with
+    /// [`ChatConfig::guard_tool_output`](crate::config::ChatConfig::guard_tool_output), else as is.
+    fn guarded_tool_result<'a>(&self, tool: &str, result: &'a str) -> Cow<'a, str> {
+        if self.config.chat.guard_tool_output {
+            Cow::Owned(untrusted_tool_output(tool, result))
+        } else {
+            Cow::Borrowed(result)
+        }
+    }
+
+    /// Keeps `unit` in working memory for good: every request carries it (marked `[pinned]`, see
+    /// [`PromptKind::PinnedMemory`]) however far the conversation moves past it.
+    pub async fn pin_to_working_memory(&self, unit: MemoryUnit) -> Result<(), KowalskiError> {
+        self.working_memory.lock().await.pin(unit).await
+    }
+
+    /// Releases a unit pinned with [`Self::pin_to_working_memory`]; returns whether it was pinned.
+    pub async fn unpin_from_working_memory(&self, id: &str) -> Result<bool, KowalskiError> {
+        self.working_memory.lock().await.unpin(id).await
+    }
+
+    /// What working memory contributes to a request: how many recent turns it holds (its
+    /// [`capacity`](MemoryProvider::capacity), else [`Config::working_memory_capacity`]) and
+    /// the system message listing its pinned units, if there are any. The turns themselves are
+    /// taken from the conversation, which keeps their tool calls and images.
+    pub(crate) async fn short_term_context(&self) -> (usize, Option<Message>) {
+        let (capacity, pinned) = {
+            let working = self.working_memory.lock().await;
+            (working.capacity(), working.pinned())
+        };
+        let window = capacity.unwrap_or(self.config.working_memory_capacity);
+        if pinned.is_empty() {
+            return (window, None);
+        }
+        let memories = pinned
+            .iter()
+            .map(|unit| format!("- [pinned] {}", unit.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let message = Message {
+            role: "system".to_string(),
+            content: self
+                .prompts
+                .render(PromptKind::PinnedMemory, &[("memories", &memories)]),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        };
+        (window, Some(message))
+    }
+
+    /// [`short_term_messages`] of `messages` under the window from [`Self::short_term_context`].
+    pub(crate) fn request_history(
+        messages: &[Message],
+        (window, pinned): (usize, Option<Message>),
+    ) -> Vec<Message> {
+        short_term_messages(messages, window, pinned)
+    }
+
+    /// Starts each new conversation with the facts of `profile`, right after the system prompt.
+    pub fn set_profile(&mut self, profile: std::sync::Arc<ProfileStore>) {
+        self.profile = Some(profile);
+    }
+
+    /// See [`Agent::close_conversation`].
+    pub async fn close_conversation(&mut self, id: &str) -> Result<bool, KowalskiError> {
+        if self.config.memory.summarize_on_close && self.conversations.contains_key(id) {
+            self.summarize_conversation(id).await?;
+        }
+        Ok(Agent::delete_conversation(self, id))
+    }
+
+    /// See [`Agent::summarize_conversation`]. The model ([`PromptKind::SessionSummary`], sampled
+    /// as `[summarization]` says) sees the user and assistant turns. The summary is stored as
+    /// one unit with `kind = session_summary` and the `conversation_id` in its metadata.
+    pub async fn summarize_conversation(
+        &self,
+        id: &str,
+    ) -> Result<Option<MemoryUnit>, KowalskiError> {
+        let conversation = self
+            .conversations
+            .get(id)
+            .ok_or_else(|| KowalskiError::ConversationNotFound(id.to_string()))?;
+        if !conversation.messages.iter().any(|m| m.role == "user") {
+            return Ok(None);
+        }
+        let transcript = conversation
+            .messages
+            .iter()
+            .filter(|m| matches!(m.role.as_str(), "user" | "assistant") && !m.content.is_empty())
+            .map(|m| format!("{}: {}", m.role, m.content))
+            .collect::<Vec<_>>();
+        let prompt = self.prompts.render(
+            PromptKind::SessionSummary,
+            &[("transcript", &transcript.join("\n"))],
+        );
+        let summarization = &self.config.summarization;
+        let model = summarization
+            .model
+            .clone()
+            .unwrap_or_else(|| conversation.model.clone());
+        let options = ChatOptions {
+            temperature: Some(summarization.temperature),
+            max_tokens: Some(summarization.max_tokens),
+            ..ChatOptions::default()
+        };
+        let summary = self
+            .llm_provider
+            .chat_with_options(
+                &model,
+                &[Message {
+                    role: "user".to_string(),
+                    content: prompt,
+                    tool_calls: None,
+                    images: None,
+                    tool_call_id: None,
+                    tool_name: None,
+                }],
+                &options,
+            )
+            .await?;
+
+        let content = format!("In a previous session we discussed:\n{}", summary.trim());
+        let embedding = match self.llm_provider.embed(&content).await {
+            Ok(embedding) => Some(embedding),
+            Err(e) => {
+                warn!("Failed to embed the summary of conversation {id}: {e}");
+                None
+            }
+        };
+        let unit = MemoryUnit {
+            id: format!("session-summary-{id}"),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            content,
+            embedding,
+            metadata: [
+                ("kind".to_string(), "session_summary".to_string()),
+                ("conversation_id".to_string(), id.to_string()),
+            ]
+            .into(),
+        };
+        self.episodic_memory.lock().await.add(unit.clone()).await?;
+        self.semantic_memory.lock().await.add(unit.clone()).await?;
+        debug!("Stored the summary of conversation {id}");
+        Ok(Some(unit))
+    }
+
+    /// See [`Agent::remember_fact`].
+    pub async fn remember_fact(&self, key: &str, value: &str) -> Result<(), KowalskiError> {
+        self.profile_store()?.remember(key, value).await
+    }
+
+    /// See [`Agent::forget_fact`].
+    pub async fn forget_fact(&self, key: &str) -> Result<bool, KowalskiError> {
+        self.profile_store()?.forget(key).await
+    }
+
+    /// See [`Agent::list_facts`].
+    pub fn list_facts(&self) -> Vec<(String, String)> {
+        self.profile
+            .as_ref()
+            .map(|profile| profile.facts())
+            .unwrap_or_default()
+    }
+
+    fn profile_store(&self) -> Result<&ProfileStore, KowalskiError> {
+        self.profile.as_deref().ok_or_else(|| {
+            KowalskiError::Agent(format!("Agent '{}' keeps no profile facts", self.name))
         })
     }
 
+    /// A `search_history` tool over this agent's episodic memory, for the model to call.
+    pub fn history_search_tool(&self) -> HistorySearchTool {
+        HistorySearchTool::new(self.episodic_memory.clone())
+    }
+
+    /// Restricts the tools this agent offers and runs to what `role` allows. The registry is
+    /// unchanged: clearing the role brings every tool back.
+    pub fn set_role(&mut self, role: Role) {
+        self.role = Some(role);
+    }
+
+    pub fn clear_role(&mut self) {
+        self.role = None;
+    }
+
+    /// Whether the current role (if any) lets this agent use tool `name`.
+    pub fn allows_tool(&self, name: &str) -> bool {
+        self.role.as_ref().is_none_or(|role| role.allows_tool(name))
+    }
+
+    /// A policy error for a tool the current role does not allow; the tool loops feed it back to
+    /// the model like any failed tool call.
+    fn check_role_policy(&self, tool_name: &str) -> Result<(), KowalskiError> {
+        match &self.role {
+            Some(role) if !role.allows_tool(tool_name) => {
+                debug!("tool call {} refused by role policy", tool_name);
+                Err(KowalskiError::PermissionDenied(if role.name.is_empty() {
+                    format!("{tool_name} is not allowed by the current role")
+                } else {
+                    format!("{tool_name} is not allowed by the role '{}'", role.name)
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Registered tools the current role allows, sorted.
+    pub fn available_tool_names(&self) -> Vec<String> {
+        let mut names = self.tool_manager.tool_names();
+        names.retain(|name| self.allows_tool(name));
+        names
+    }
+
+    /// `(name, description)` of the registered tools the current role allows.
+    pub async fn available_tools(&self) -> Vec<(String, String)> {
+        let mut tools = self.tool_manager.list_tools().await;
+        tools.retain(|(name, _)| self.allows_tool(name));
+        tools
+    }
+
     pub fn set_temperature(&mut self, temperature: f32) {
         self.config.chat.temperature = temperature;
     }
@@ -422,22 +1166,47 @@ impl BaseAgent {
         self.system_prompt = Some(prompt.to_string());
     }
 
-    /// Same memory + user turn as [`Agent::chat_with_history`], but returns owned messages for
-    /// [`crate::llm::LLMProvider::chat_stream`] without calling the LLM (caller streams, then
-    /// should [`Self::add_message`] with role `assistant` for the full reply).
+    pub fn set_system_prompt_template(&mut self, template: impl Into<SystemPromptTemplate>) {
+        self.system_prompt_template = Some(template.into());
+    }
+
+    /// The system prompt template filled in for a conversation starting now, if one is set.
+    pub fn render_system_prompt(&self) -> Option<String> {
+        let template = self.system_prompt_template.as_ref()?;
+        let today = chrono::Local::now().date_naive();
+        Some(template.render(&self.name, today, &self.available_tool_names()))
+    }
+
+    /// [`PromptKind::System`] filled in for a conversation starting now, for agents that have no
+    /// system prompt of their own.
+    pub fn default_system_prompt(&self) -> String {
+        let tools = self.available_tool_names();
+        let tools = if tools.is_empty() {
+            "none".to_string()
+        } else {
+            tools.join(", ")
+        };
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        self.prompts.render(
+            PromptKind::System,
+            &[
+                ("agent_name", &self.name),
+                ("date", &today),
+                ("tools", &tools),
+            ],
+        )
+    }
+
+    /// Same memory + user turn as [`Agent::chat_with_history`], but returns owned messages and
+    /// sampling settings for [`crate::llm::LLMProvider::chat_stream_with_options`] without calling
+    /// the LLM (caller streams, then should [`Self::add_message`] with role `assistant` for the
+    /// full reply).
     pub async fn prepare_stream_turn(
         &mut self,
         conversation_id: &str,
         content: &str,
         role: Option<Role>,
-    ) -> Result<
-        (
-            String,
-            Vec<Message>,
-            std::sync::Arc<dyn crate::llm::LLMProvider>,
-        ),
-        KowalskiError,
-    > {
+    ) -> Result<StreamTurn, KowalskiError> {
         self.prepare_stream_turn_with_options(conversation_id, content, role, true)
             .await
     }
@@ -448,15 +1217,11 @@ impl BaseAgent {
         content: &str,
         role: Option<Role>,
         use_memory: bool,
-    ) -> Result<
-        (
-            String,
-            Vec<Message>,
-            std::sync::Arc<dyn crate::llm::LLMProvider>,
-        ),
-        KowalskiError,
-    > {
+    ) -> Result<StreamTurn, KowalskiError> {
+        let recall_started = Instant::now();
         let memory_context = self.build_memory_context(content, use_memory).await;
+        self.last_recall = Some(recall_started.elapsed());
+        let short_term = self.short_term_context().await;
 
         let conversation = self
             .conversations
@@ -464,17 +1229,7 @@ impl BaseAgent {
             .ok_or_else(|| KowalskiError::ConversationNotFound(conversation_id.to_string()))?;
 
         if let Some(role) = role {
-            conversation.add_message("system", &role.get_prompt());
-
-            if let Some(audience) = role.get_audience() {
-                conversation.add_message("system", &audience.get_prompt());
-            }
-            if let Some(preset) = role.get_preset() {
-                conversation.add_message("system", &preset.get_prompt());
-            }
-            if let Some(style) = role.get_style() {
-                conversation.add_message("system", &style.get_prompt());
-            }
+            conversation.add_message_typed(MessageRole::System, &role.get_prompt());
         }
 
         let fallback_context = if use_memory && memory_context.is_empty() {
@@ -483,68 +1238,150 @@ impl BaseAgent {
             String::new()
         };
 
-        conversation.add_message("user", content);
+        conversation.add_message_typed(MessageRole::User, content);
 
         let model = conversation.model.clone();
-        let mut messages = conversation.messages.clone();
+        let mut messages = Self::request_history(&conversation.messages, short_term);
         let effective_context = if !memory_context.is_empty() {
             memory_context
         } else {
             fallback_context
         };
         if !effective_context.is_empty() {
-            let memory_prompt = format!(
-                "Retrieved memory context (use only if relevant to the latest user request):\n--- Relevant Memories ---\n{}\n--- End Memories ---",
-                effective_context
-            );
             let insert_at = messages.len().saturating_sub(1);
             messages.insert(
                 insert_at,
                 Message {
                     role: "system".to_string(),
-                    content: memory_prompt,
+                    content: memory_prompt(&self.prompts, &effective_context),
                     tool_calls: None,
+                    images: None,
+                    tool_call_id: None,
+                    tool_name: None,
                 },
             );
         }
+        let mut request = ChatRequest {
+            model,
+            messages,
+            stream: true,
+            temperature: self.config.chat.temperature,
+            max_tokens: self.config.chat.max_tokens as usize,
+            tools: None,
+            format: None,
+            options: self.config.chat.options.clone(),
+        };
+        self.intercept_request(&mut request).await;
+        let ChatRequest {
+            model,
+            messages,
+            temperature,
+            max_tokens,
+            options,
+            ..
+        } = request;
+        let options = ChatOptions {
+            temperature: Some(temperature),
+            max_tokens: Some(max_tokens as u32),
+            ollama: options,
+            ..ChatOptions::default()
+        };
+        self.archive_user_turn(conversation_id, content).await;
+        self.notify(|o| o.on_message_added(conversation_id, "user", content));
+        self.notify(|o| o.on_llm_request(conversation_id, &model, &messages));
         let llm = self.llm_provider.clone();
-        Ok((model, messages, llm))
+        Ok((model, messages, llm, options))
     }
 
-    /// Like [`Agent::chat_with_tools`] but emits **token deltas** over `token_tx` only for the first
-    /// LLM completion **after at least one tool execution** in this request (final natural answer).
-    pub async fn chat_with_tools_with_options(
+    /// See [`Agent::chat_with_history_stream`]. With middleware the reply is held back until
+    /// [`Self::finish_streamed_reply`] has rewritten it; JSON tool-call mode and providers that do
+    /// not stream send the whole reply at once.
+    pub async fn chat_with_history_stream_with_options(
         &mut self,
         conversation_id: &str,
-        user_input: &str,
+        content: &str,
+        tokens: &tokio::sync::mpsc::Sender<String>,
         use_memory: bool,
     ) -> Result<String, KowalskiError> {
-        let mut final_response = String::new();
-        let mut current_input = user_input.to_string();
-        let mut iteration_count = 0;
-        const MAX_ITERATIONS: usize = 5;
-        let mut last_tool_call: Option<(String, serde_json::Value)> = None;
+        let json_mode = self.config.chat.json_tool_calls && !self.available_tool_names().is_empty();
+        if json_mode || !self.llm_provider.supports_streaming() {
+            let reply = self
+                .chat_with_history_with_options(conversation_id, content, None, use_memory)
+                .await?;
+            let _ = tokens.send(reply.clone()).await;
+            return Ok(reply);
+        }
+        let (model, messages, llm, options) = self
+            .prepare_stream_turn_with_options(conversation_id, content, None, use_memory)
+            .await?;
+        let hold_back = self.has_middleware();
+        let started = Instant::now();
+        let mut full = String::new();
+        let mut stream = llm.chat_stream_with_options(&model, messages, &options);
+        while let Some(delta) = stream.next().await {
+            let delta = delta?;
+            if delta.is_empty() {
+                continue;
+            }
+            full.push_str(&delta);
+            if !hold_back {
+                let _ = tokens.send(delta).await;
+            }
+        }
+        crate::metrics::record_llm_request(started.elapsed());
+        if hold_back {
+            full = self.finish_streamed_reply(full).await;
+            if !full.is_empty() {
+                let _ = tokens.send(full.clone()).await;
+            }
+        }
+        self.notify(|o| o.on_llm_response(conversation_id, &full));
+        Ok(full)
+    }
+
+    /// Like [`Agent::chat_with_tools`] but emits **token deltas** over `token_tx` only for the first
+    /// LLM completion **after at least one tool execution** in this request (final natural answer).
+    pub async fn chat_with_tools_with_options(
+        &mut self,
+        conversation_id: &str,
+        user_input: &str,
+        use_memory: bool,
+    ) -> Result<String, KowalskiError> {
+        let mut final_response = String::new();
+        let mut current_input = user_input.to_string();
+        let mut iteration_count = 0;
+        const MAX_ITERATIONS: usize = 5;
+        let mut last_tool_call: Option<(String, serde_json::Value)> = None;
         let mut tool_parse_hint_sent = false;
+        let dry_run = self.dry_run;
+        let started = Instant::now();
+        let mut timings = ChatTimings::default();
 
         while iteration_count < MAX_ITERATIONS {
             iteration_count += 1;
+            let llm_started = Instant::now();
             let response_text = self
                 .chat_with_history_with_options(conversation_id, &current_input, None, use_memory)
                 .await?;
+            let recall = self.take_recall_time().unwrap_or_default();
+            timings.memory_recall += recall;
+            timings
+                .llm
+                .push(llm_started.elapsed().saturating_sub(recall));
 
-            if repl_trace::repl_trace_enabled() {
-                println!("[agent] {}", response_text);
-            } else {
-                println!("{}", response_text);
-            }
-            io::stdout()
-                .flush()
-                .map_err(|e| KowalskiError::Server(e.to_string()))?;
+            repl_trace::print_reply(&response_text)?;
 
             let buffer = response_text.clone();
             let tool_calls = crate::utils::json::extract_tool_calls(&buffer);
 
             if !tool_calls.is_empty() {
+                if dry_run {
+                    debug!("dry run: {} call(s) planned", tool_calls.len());
+                    final_response = buffer;
+                    self.add_message(conversation_id, "assistant", &final_response)
+                        .await;
+                    break;
+                }
                 let tool_call = &tool_calls[0];
                 let tool_call_key = (tool_call.name.clone(), tool_call.parameters.clone());
                 if let Some(last) = &last_tool_call
@@ -554,18 +1391,27 @@ impl BaseAgent {
                 }
                 last_tool_call = Some(tool_call_key);
 
-                let tool_result = match self
+                let tool_started = Instant::now();
+                let tool_output = self
                     .execute_tool(&tool_call.name, &tool_call.parameters)
-                    .await
-                {
-                    Ok(output) => output.result.to_string(),
-                    Err(e) => format!("{}", e),
+                    .await;
+                timings
+                    .tools
+                    .push((tool_call.name.clone(), tool_started.elapsed()));
+                let (tool_result, source) = match tool_output {
+                    Ok(output) => (output.result.to_string(), output.source),
+                    Err(e) => (format!("{}", e), None),
                 };
 
-                let tool_message = format!("Tool result for {}: {}", tool_call.name, tool_result);
-                self.add_message(conversation_id, "assistant", &tool_message)
-                    .await;
-                current_input = format!("Based on the tool result: {}", tool_result);
+                self.add_tool_exchange(
+                    conversation_id,
+                    &buffer,
+                    tool_call,
+                    &tool_result,
+                    source.as_deref(),
+                )
+                .await;
+                current_input = Agent::tool_result_prompt(self, &tool_call.name, &tool_result);
                 continue;
             }
 
@@ -584,6 +1430,8 @@ impl BaseAgent {
             break;
         }
 
+        timings.total = started.elapsed();
+        self.report_chat_timings(conversation_id, timings);
         Ok(final_response)
     }
 
@@ -610,6 +1458,7 @@ impl BaseAgent {
         const MAX_ITERATIONS: usize = 5;
         let mut last_tool_call: Option<(String, serde_json::Value)> = None;
         let mut tool_parse_hint_sent = false;
+        let dry_run = self.dry_run;
         // After a tool ran, the next LLM completion is streamed (final answer in the common case).
         let mut stream_next_llm_turn = false;
 
@@ -624,24 +1473,13 @@ impl BaseAgent {
             );
 
             let response_text = if use_stream {
-                let (model, messages, llm) = self
-                    .prepare_stream_turn_with_options(
-                        conversation_id,
-                        &current_input,
-                        None,
-                        use_memory,
-                    )
-                    .await?;
-                let mut full = String::new();
-                let mut stream = llm.chat_stream(&model, messages);
-                while let Some(item) = stream.next().await {
-                    let delta = item?;
-                    if !delta.is_empty() {
-                        full.push_str(&delta);
-                        let _ = token_tx.send(delta).await;
-                    }
-                }
-                full
+                self.chat_with_history_stream_with_options(
+                    conversation_id,
+                    &current_input,
+                    token_tx,
+                    use_memory,
+                )
+                .await?
             } else {
                 self.chat_with_history_with_options(
                     conversation_id,
@@ -652,19 +1490,22 @@ impl BaseAgent {
                 .await?
             };
 
-            if repl_trace::repl_trace_enabled() {
-                println!("[agent] {}", response_text);
-            } else {
-                println!("{}", response_text);
+            // A streamed reply already reached the caller through `token_tx`.
+            if !use_stream {
+                repl_trace::print_reply(&response_text)?;
             }
-            io::stdout()
-                .flush()
-                .map_err(|e| KowalskiError::Server(e.to_string()))?;
 
             let buffer = response_text.clone();
             let tool_calls = crate::utils::json::extract_tool_calls(&buffer);
 
             if !tool_calls.is_empty() {
+                if dry_run {
+                    debug!("dry run: {} call(s) planned", tool_calls.len());
+                    final_response = buffer;
+                    self.add_message(conversation_id, "assistant", &final_response)
+                        .await;
+                    break;
+                }
                 let tool_call = &tool_calls[0];
                 let tool_call_key = (tool_call.name.clone(), tool_call.parameters.clone());
                 if let Some(last) = &last_tool_call
@@ -681,19 +1522,24 @@ impl BaseAgent {
                     println!("[tool] {} {}", tool_call.name, params);
                 }
 
-                let tool_result = match self
+                let (tool_result, source) = match self
                     .execute_tool(&tool_call.name, &tool_call.parameters)
                     .await
                 {
-                    Ok(output) => output.result.to_string(),
-                    Err(e) => format!("{}", e),
+                    Ok(output) => (output.result.to_string(), output.source),
+                    Err(e) => (format!("{}", e), None),
                 };
 
-                let tool_message = format!("Tool result for {}: {}", tool_call.name, tool_result);
-                self.add_message(conversation_id, "assistant", &tool_message)
-                    .await;
+                self.add_tool_exchange(
+                    conversation_id,
+                    &buffer,
+                    tool_call,
+                    &tool_result,
+                    source.as_deref(),
+                )
+                .await;
 
-                current_input = format!("Based on the tool result: {}", tool_result);
+                current_input = Agent::tool_result_prompt(self, &tool_call.name, &tool_result);
                 stream_next_llm_turn = true;
                 continue;
             }
@@ -713,7 +1559,7 @@ impl BaseAgent {
             self.add_message(conversation_id, "assistant", &final_response)
                 .await;
 
-            if let Some(tool_call) = rule_based_tool_call(user_input) {
+            if !dry_run && let Some(tool_call) = rule_based_tool_call(user_input) {
                 let tool_result_str = match self
                     .execute_tool(&tool_call.name, &tool_call.parameters)
                     .await
@@ -745,12 +1591,13 @@ impl Agent for BaseAgent {
         let llm_provider = crate::llm::create_llm_provider(&config)?;
 
         // Create memory providers
-        let working_memory = std::sync::Arc::new(tokio::sync::Mutex::new(WorkingMemory::new(100)))
+        let working_memory = std::sync::Arc::new(tokio::sync::Mutex::new(WorkingMemory::new(
+            config.working_memory_capacity,
+        )))
             as std::sync::Arc<tokio::sync::Mutex<dyn MemoryProvider + Send + Sync>>;
 
         let episodic_memory = std::sync::Arc::new(tokio::sync::Mutex::new(
-            crate::memory::episodic::EpisodicBuffer::open(&config.memory, llm_provider.clone())
-                .await?,
+            crate::memory::helpers::open_episodic_memory(&config, llm_provider.clone()).await?,
         ))
             as std::sync::Arc<tokio::sync::Mutex<dyn MemoryProvider + Send + Sync>>;
 
@@ -770,11 +1617,29 @@ impl Agent for BaseAgent {
         .await
     }
 
+    fn tool_result_prompt(&self, tool: &str, result: &str) -> String {
+        let result = self.guarded_tool_result(tool, result);
+        self.prompts.render(
+            PromptKind::ToolResult,
+            &[("tool", tool), ("result", &result)],
+        )
+    }
+
     fn start_conversation(&mut self, model: &str) -> String {
         info!("Starting conversation with model: {}", model);
-        let conversation = Conversation::new(model);
+        let mut conversation = Conversation::new(model);
+        if let Some(prompt) = self.render_system_prompt() {
+            conversation.add_message_typed(MessageRole::System, &prompt);
+        }
+        if let Some(facts) = self.profile.as_ref().and_then(|profile| profile.render()) {
+            let message = self
+                .prompts
+                .render(PromptKind::ProfileFacts, &[("facts", &facts)]);
+            conversation.add_message_typed(MessageRole::System, &message);
+        }
         let id = conversation.id.clone();
         self.conversations.insert(id.clone(), conversation);
+        self.notify(|o| o.on_conversation_started(&id, model));
         id
     }
 
@@ -787,6 +1652,7 @@ impl Agent for BaseAgent {
     }
 
     fn delete_conversation(&mut self, id: &str) -> bool {
+        self.stream_buffers.remove(id);
         self.conversations.remove(id).is_some()
     }
 
@@ -800,6 +1666,16 @@ impl Agent for BaseAgent {
             .await
     }
 
+    async fn chat_with_history_stream(
+        &mut self,
+        conversation_id: &str,
+        content: &str,
+        tokens: &tokio::sync::mpsc::Sender<String>,
+    ) -> Result<String, KowalskiError> {
+        self.chat_with_history_stream_with_options(conversation_id, content, tokens, true)
+            .await
+    }
+
     async fn process_stream_response(
         &mut self,
         conversation_id: &str,
@@ -808,10 +1684,106 @@ impl Agent for BaseAgent {
         BaseAgent::process_stream_response(self, conversation_id, chunk).await
     }
 
+    async fn chat_with_images(
+        &mut self,
+        conversation_id: &str,
+        content: &str,
+        images: &[PathBuf],
+    ) -> Result<String, KowalskiError> {
+        BaseAgent::chat_with_images(self, conversation_id, content, images).await
+    }
+
     async fn add_message(&mut self, conversation_id: &str, role: &str, content: &str) {
         BaseAgent::add_message(self, conversation_id, role, content).await;
     }
 
+    async fn add_tool_exchange(
+        &mut self,
+        conversation_id: &str,
+        reply: &str,
+        call: &ToolCall,
+        result: &str,
+        source: Option<&str>,
+    ) {
+        BaseAgent::add_tool_exchange(self, conversation_id, reply, call, result, source).await;
+    }
+
+    async fn chat_structured(
+        &mut self,
+        conversation_id: &str,
+        prompt: &str,
+        schema: &serde_json::Value,
+    ) -> Result<serde_json::Value, KowalskiError> {
+        BaseAgent::chat_structured(self, conversation_id, prompt, schema).await
+    }
+
+    async fn regenerate_last(
+        &mut self,
+        conversation_id: &str,
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        BaseAgent::regenerate_last(self, conversation_id, options).await
+    }
+
+    async fn search_history(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<MemoryUnit>, KowalskiError> {
+        BaseAgent::search_history(self, query, limit).await
+    }
+
+    async fn close_conversation(&mut self, id: &str) -> Result<bool, KowalskiError> {
+        BaseAgent::close_conversation(self, id).await
+    }
+
+    async fn summarize_conversation(&self, id: &str) -> Result<Option<MemoryUnit>, KowalskiError> {
+        BaseAgent::summarize_conversation(self, id).await
+    }
+
+    async fn remember_fact(&self, key: &str, value: &str) -> Result<(), KowalskiError> {
+        BaseAgent::remember_fact(self, key, value).await
+    }
+
+    async fn forget_fact(&self, key: &str) -> Result<bool, KowalskiError> {
+        BaseAgent::forget_fact(self, key).await
+    }
+
+    fn list_facts(&self) -> Vec<(String, String)> {
+        BaseAgent::list_facts(self)
+    }
+
+    async fn execute_tool(
+        &mut self,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+    ) -> Result<ToolOutput, KowalskiError> {
+        BaseAgent::execute_tool(self, tool_name, tool_input).await
+    }
+
+    async fn list_tools(&self) -> Vec<(String, String)> {
+        self.available_tools().await
+    }
+
+    fn tool_manager(&self) -> Option<&crate::tools::manager::ToolManager> {
+        Some(&self.tool_manager)
+    }
+
+    async fn capabilities(&self) -> AgentCapabilities {
+        let mut tools = self.tool_manager.metadata().await;
+        tools.retain(|t| self.allows_tool(&t.name));
+        AgentCapabilities {
+            tools,
+            supports_streaming: true,
+            default_model: self.config.active_model(),
+            description: self.description.clone(),
+        }
+    }
+
+    fn base_agent_mut(&mut self) -> Option<&mut BaseAgent> {
+        Some(self)
+    }
+
     fn export_conversation(&self, id: &str) -> Result<String, KowalskiError> {
         BaseAgent::export_conversation(self, id)
     }
@@ -820,6 +1792,22 @@ impl Agent for BaseAgent {
         BaseAgent::import_conversation(self, json_str)
     }
 
+    fn fork_conversation(
+        &mut self,
+        id: &str,
+        model: Option<&str>,
+    ) -> Result<String, KowalskiError> {
+        let forked = self
+            .conversations
+            .get(id)
+            .ok_or_else(|| KowalskiError::ConversationNotFound(id.to_string()))?
+            .fork(model);
+        let (new_id, model) = (forked.id.clone(), forked.model.clone());
+        self.conversations.insert(new_id.clone(), forked);
+        self.notify(|o| o.on_conversation_started(&new_id, &model));
+        Ok(new_id)
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -841,7 +1829,140 @@ impl BaseAgent {
         role: Option<Role>,
         use_memory: bool,
     ) -> Result<String, KowalskiError> {
+        let options = self.chat_options();
+        self.chat_with_history_and_images(
+            conversation_id,
+            content,
+            role,
+            use_memory,
+            Vec::new(),
+            options,
+            true,
+        )
+        .await
+    }
+
+    /// Sampling settings from [`ChatConfig`](crate::config::ChatConfig).
+    fn chat_options(&self) -> ChatOptions {
+        ChatOptions {
+            temperature: Some(self.config.chat.temperature),
+            max_tokens: Some(self.config.chat.max_tokens),
+            ..ChatOptions::default()
+        }
+    }
+
+    /// See [`Agent::regenerate_last`]. Everything after the last user message (the reply, and any
+    /// tool results) is dropped and that message is sent again as a plain chat turn; the new
+    /// reply is stored in its place. The user message is not archived to memory a second time.
+    /// If the request fails, the conversation is left as it was.
+    pub async fn regenerate_last(
+        &mut self,
+        conversation_id: &str,
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        let conversation = self
+            .conversations
+            .get_mut(conversation_id)
+            .ok_or_else(|| KowalskiError::ConversationNotFound(conversation_id.to_string()))?;
+        let last_user = conversation
+            .messages
+            .iter()
+            .rposition(|m| m.role == "user")
+            .ok_or_else(|| {
+                KowalskiError::Agent(format!(
+                    "conversation {} has no user message to regenerate a reply for",
+                    conversation_id
+                ))
+            })?;
+        let removed = conversation.messages.split_off(last_user);
+        let user = removed[0].clone();
+
+        let defaults = self.chat_options();
+        let options = ChatOptions {
+            temperature: options.temperature.or(defaults.temperature),
+            max_tokens: options.max_tokens.or(defaults.max_tokens),
+            ..options.clone()
+        };
+        let reply = self
+            .chat_with_history_and_images(
+                conversation_id,
+                &user.content,
+                None,
+                true,
+                user.images.unwrap_or_default(),
+                options,
+                false,
+            )
+            .await;
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(e) => {
+                if let Some(conversation) = self.conversations.get_mut(conversation_id) {
+                    conversation.messages.truncate(last_user);
+                    conversation.messages.extend(removed);
+                }
+                return Err(e);
+            }
+        };
+        self.add_message(conversation_id, "assistant", &reply).await;
+        Ok(reply)
+    }
+
+    /// Like [`Agent::chat_with_history`], attaching the images at `paths` to the user turn.
+    /// All images are read and size-checked before the conversation is touched.
+    pub async fn chat_with_images(
+        &mut self,
+        conversation_id: &str,
+        content: &str,
+        paths: &[PathBuf],
+    ) -> Result<String, KowalskiError> {
+        let images = paths
+            .iter()
+            .map(|p| ImageData::from_path(p))
+            .collect::<Result<Vec<_>, _>>()?;
+        let options = self.chat_options();
+        self.chat_with_history_and_images(
+            conversation_id,
+            content,
+            None,
+            true,
+            images,
+            options,
+            true,
+        )
+        .await
+    }
+
+    #[tracing::instrument(
+        name = "chat_turn",
+        skip_all,
+        fields(
+            conversation_id = %conversation_id,
+            model = field::Empty,
+            prompt_tokens = field::Empty,
+            completion_tokens = field::Empty,
+            queue_wait_ms = field::Empty,
+            cached = field::Empty,
+        )
+    )]
+    /// `new_turn` is false when `content` was sent before (a regenerated reply): it is then not
+    /// archived or reported as an added message again.
+    #[allow(clippy::too_many_arguments)]
+    async fn chat_with_history_and_images(
+        &mut self,
+        conversation_id: &str,
+        content: &str,
+        role: Option<Role>,
+        use_memory: bool,
+        images: Vec<ImageData>,
+        options: ChatOptions,
+        new_turn: bool,
+    ) -> Result<String, KowalskiError> {
+        let recall_started = Instant::now();
         let memory_context = self.build_memory_context(content, use_memory).await;
+        self.last_recall = Some(recall_started.elapsed());
+        let short_term = self.short_term_context().await;
+        let json_mode = self.config.chat.json_tool_calls && !self.available_tool_names().is_empty();
 
         let conversation = self
             .conversations
@@ -849,17 +1970,7 @@ impl BaseAgent {
             .ok_or_else(|| KowalskiError::ConversationNotFound(conversation_id.to_string()))?;
 
         if let Some(role) = role {
-            conversation.add_message("system", &role.get_prompt());
-
-            if let Some(audience) = role.get_audience() {
-                conversation.add_message("system", &audience.get_prompt());
-            }
-            if let Some(preset) = role.get_preset() {
-                conversation.add_message("system", &preset.get_prompt());
-            }
-            if let Some(style) = role.get_style() {
-                conversation.add_message("system", &style.get_prompt());
-            }
+            conversation.add_message_typed(MessageRole::System, &role.get_prompt());
         }
 
         let fallback_context = if use_memory && memory_context.is_empty() {
@@ -869,57 +1980,157 @@ impl BaseAgent {
         };
 
         // Persist raw user input in conversation history.
-        conversation.add_message("user", content);
+        conversation.messages.push(Message {
+            role: "user".to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            images: (!images.is_empty()).then_some(images),
+            tool_call_id: None,
+            tool_name: None,
+        });
 
-        // Build request-time LLM messages: conversation history + optional memory context.
-        // Memory context is ephemeral (not persisted as conversation turns).
-        let mut llm_messages = conversation.messages.clone();
+        // Build request-time LLM messages: the recent window of the conversation, pinned
+        // memories and optional memory context. Memory context is ephemeral (not persisted as
+        // conversation turns).
+        let mut llm_messages = Self::request_history(&conversation.messages, short_term);
         let effective_context = if !memory_context.is_empty() {
             memory_context
         } else {
             fallback_context
         };
         if !effective_context.is_empty() {
-            let memory_prompt = format!(
-                "Retrieved memory context (use only if relevant to the latest user request):\n--- Relevant Memories ---\n{}\n--- End Memories ---",
-                effective_context
+            let insert_at = llm_messages.len().saturating_sub(1);
+            llm_messages.insert(
+                insert_at,
+                Message {
+                    role: "system".to_string(),
+                    content: memory_prompt(&self.prompts, &effective_context),
+                    tool_calls: None,
+                    images: None,
+                    tool_call_id: None,
+                    tool_name: None,
+                },
             );
+        }
+
+        // With tools in play the reply is a tool-call decision: constrain it to JSON if enabled.
+        if json_mode {
             let insert_at = llm_messages.len().saturating_sub(1);
             llm_messages.insert(
                 insert_at,
                 Message {
                     role: "system".to_string(),
-                    content: memory_prompt,
+                    content: JSON_MODE_PROMPT.to_string(),
                     tool_calls: None,
+                    images: None,
+                    tool_call_id: None,
+                    tool_name: None,
                 },
             );
         }
 
+        let mut request = ChatRequest {
+            model: conversation.model.clone(),
+            messages: llm_messages,
+            stream: false,
+            temperature: options.temperature.unwrap_or(self.config.chat.temperature),
+            max_tokens: options.max_tokens.unwrap_or(self.config.chat.max_tokens) as usize,
+            tools: None,
+            format: json_mode.then(|| json!("json")),
+            options: options
+                .ollama
+                .clone()
+                .or_else(|| self.config.chat.options.clone()),
+        };
+        self.intercept_request(&mut request).await;
+        let options = ChatOptions {
+            temperature: Some(request.temperature),
+            max_tokens: Some(request.max_tokens as u32),
+            ollama: request.options.clone(),
+            ..options
+        };
+        let json_mode = request.format.is_some();
+        let ChatRequest {
+            model,
+            messages: llm_messages,
+            ..
+        } = request;
+        tracing::Span::current().record("model", model.as_str());
+        if new_turn {
+            self.archive_user_turn(conversation_id, content).await;
+            self.notify(|o| o.on_message_added(conversation_id, "user", content));
+        }
+        self.notify(|o| o.on_llm_request(conversation_id, &model, &llm_messages));
+        if log_enabled!(Level::Trace) {
+            trace!(
+                "conversation {conversation_id}: LLM request (model {model}, json_mode {json_mode}): {}",
+                redacted_json(&llm_messages)
+            );
+        }
+
         // Delegate to LLM Provider
-        let response = self
-            .llm_provider
-            .chat(&conversation.model, &llm_messages)
-            .await?;
+        let started = Instant::now();
+        let response = if json_mode {
+            self.llm_provider
+                .chat_json(&model, &llm_messages, &options)
+                .await
+                .map(|raw| crate::utils::json::json_mode_answer(&raw).unwrap_or(raw))
+        } else {
+            self.llm_provider
+                .chat_with_options(&model, &llm_messages, &options)
+                .await
+        };
+        crate::metrics::record_llm_request(started.elapsed());
+        let response = response?;
+        let response = self.intercept_response(response).await;
+        self.notify(|o| o.on_llm_response(conversation_id, &response));
+        trace!("conversation {conversation_id}: LLM response: {response}");
 
         Ok(response)
     }
 
     async fn process_stream_response(
         &mut self,
-        _conversation_id: &str,
+        conversation_id: &str,
         chunk: &[u8],
     ) -> Result<Option<Message>, KowalskiError> {
-        let text = String::from_utf8(chunk.to_vec())
-            .map_err(|e| KowalskiError::Server(format!("Invalid UTF-8: {}", e)))?;
-
-        let stream_response: StreamResponse =
-            serde_json::from_str(&text).map_err(KowalskiError::Json)?;
-
-        if stream_response.done {
-            return Ok(None);
+        let buffer = self
+            .stream_buffers
+            .entry(conversation_id.to_string())
+            .or_default();
+        let mut lines = buffer.push(chunk);
+        lines.extend(buffer.take_complete());
+
+        let mut merged: Option<Message> = None;
+        let mut done = false;
+        for line in lines {
+            let stream_response: StreamResponse =
+                serde_json::from_str(&line).map_err(KowalskiError::Json)?;
+            done |= stream_response.done;
+            let message = stream_response.message;
+            match &mut merged {
+                None => merged = Some(message),
+                Some(m) => {
+                    m.content.push_str(&message.content);
+                    if message.tool_calls.is_some() {
+                        m.tool_calls = message.tool_calls;
+                    }
+                }
+            }
         }
 
-        Ok(Some(stream_response.message))
+        if done {
+            self.stream_buffers.remove(conversation_id);
+            return Ok(merged.filter(|m| !m.content.is_empty() || m.tool_calls.is_some()));
+        }
+        Ok(Some(merged.unwrap_or_else(|| Message {
+            role: "assistant".to_string(),
+            content: String::new(),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        })))
     }
 
     async fn execute_tool(
@@ -927,38 +2138,103 @@ impl BaseAgent {
         tool_name: &str,
         tool_input: &serde_json::Value,
     ) -> Result<ToolOutput, KowalskiError> {
-        let task_type = tool_input
-            .get("task")
-            .and_then(|v| v.as_str())
-            .unwrap_or("default")
-            .to_string();
-        let content = tool_input
-            .get("content")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-
-        let input = crate::tools::ToolInput::new(task_type, content, tool_input.clone());
+        let span = tracing::info_span!(
+            "tool_execution",
+            tool = tool_name,
+            status = field::Empty,
+            duration_ms = field::Empty,
+        );
+        let started = Instant::now();
+        let result = self
+            .run_tool_call(tool_name, tool_input)
+            .instrument(span.clone())
+            .await;
+        let elapsed = started.elapsed();
+        let status = match &result {
+            Ok(_) => "ok",
+            Err(KowalskiError::PermissionDenied(_)) => "denied",
+            Err(_) => "error",
+        };
+        span.record("duration_ms", elapsed.as_millis() as u64);
+        span.record("status", status);
+        crate::metrics::record_tool_execution(tool_name, status, elapsed);
+        result
+    }
 
-        self.tool_manager.execute(tool_name, input).await
+    /// Policy checks, approval and middleware around one tool call, then the call itself.
+    async fn run_tool_call(
+        &mut self,
+        tool_name: &str,
+        tool_input: &serde_json::Value,
+    ) -> Result<ToolOutput, KowalskiError> {
+        self.check_role_policy(tool_name)?;
+        let call = ToolCall {
+            name: tool_name.to_string(),
+            parameters: tool_input.clone(),
+            reasoning: None,
+        };
+        let destructive = self.tool_approver.is_none()
+            && self
+                .tool_manager
+                .is_destructive(&call.name, &call.parameters)
+                .await;
+        let mut call = approval::review_call(self.tool_approver.as_deref(), call, destructive)?;
+        for middleware in &self.middleware {
+            if let Decision::Block(reason) = middleware.before_tool_execution(&mut call).await {
+                debug!("tool call {} blocked by middleware", call.name);
+                return Err(KowalskiError::PermissionDenied(format!(
+                    "{} call blocked: {}",
+                    call.name, reason
+                )));
+            }
+        }
+        // The approver or a middleware may have swapped the tool.
+        self.check_role_policy(&call.name)?;
+        let input = crate::tools::ToolInput::from_parameters(call.parameters.clone());
+
+        self.notify(|o| o.on_tool_call(&call.name, &call.parameters));
+        let mut result = self.tool_manager.execute(&call.name, input).await;
+        if let Ok(output) = &mut result {
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_tool_execution(output).await;
+            }
+        }
+        self.notify(|o| o.on_tool_result(&call.name, &result));
+        result
     }
 
     async fn add_message(&mut self, conversation_id: &str, role: &str, content: &str) {
-        // 2. STORAGE: Archive the message to the episodic buffer
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default();
-        let timestamp = now.as_secs();
-        let nanos = now.as_nanos();
-
-        let memory_unit = MemoryUnit {
-            // Use nanosecond precision to avoid collisions when multiple messages
-            // are added in the same second.
-            id: format!("{}-{}-{}-{}", conversation_id, timestamp, nanos, role),
-            timestamp,
-            content: format!("[{}] {}", role, content),
-            embedding: None, // Embeddings are generated during consolidation
-        };
+        self.archive_message(conversation_id, role, content).await;
+        if let Some(conversation) = self.conversations.get_mut(conversation_id) {
+            conversation.add_message(role, content);
+        }
+        self.notify(|o| o.on_message_added(conversation_id, role, content));
+    }
+
+    /// See [`Agent::add_tool_exchange`]. The `tool` message holds [`tool_result_message`] and
+    /// is archived to memory; the tool-call reply is kept in the conversation only.
+    async fn add_tool_exchange(
+        &mut self,
+        conversation_id: &str,
+        reply: &str,
+        call: &ToolCall,
+        result: &str,
+        source: Option<&str>,
+    ) {
+        let result = self.guarded_tool_result(&call.name, result);
+        let message = tool_result_message(&call.name, &result, source);
+        self.archive_message(conversation_id, "tool", &message)
+            .await;
+        if let Some(conversation) = self.conversations.get_mut(conversation_id) {
+            conversation.add_tool_exchange(reply, &call.name, &call.parameters, &message);
+        }
+        self.notify(|o| o.on_message_added(conversation_id, "assistant", reply));
+        self.notify(|o| o.on_message_added(conversation_id, "tool", &message));
+    }
+
+    /// Adds a message to working and episodic memory.
+    async fn archive_message(&mut self, conversation_id: &str, role: &str, content: &str) {
+        let memory_unit = message_memory_unit(conversation_id, role, content);
 
         // Add to Tier 1 working memory
         if let Err(e) = self
@@ -968,16 +2244,12 @@ impl BaseAgent {
             .add(memory_unit.clone())
             .await
         {
-            eprintln!("Failed to add to working memory: {}", e);
+            warn!("Failed to add to working memory: {}", e);
         }
 
         // Add to Tier 2 episodic buffer
         if let Err(e) = self.episodic_memory.lock().await.add(memory_unit).await {
-            eprintln!("Failed to add to episodic memory: {}", e);
-        }
-
-        if let Some(conversation) = self.conversations.get_mut(conversation_id) {
-            conversation.add_message(role, content);
+            warn!("Failed to add to episodic memory: {}", e);
         }
     }
 
@@ -1031,3 +2303,634 @@ fn rule_based_tool_call(user_input: &str) -> Option<ToolCall> {
     // Add more rules as needed...
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockBackend, MockReply, memory};
+    use crate::tools::manager::ToolManager;
+    use std::sync::Arc;
+
+    /// A memory backend that is unreachable: every call fails.
+    struct UnreachableMemory;
+
+    #[async_trait]
+    impl MemoryProvider for UnreachableMemory {
+        async fn add(&mut self, _memory: MemoryUnit) -> Result<(), KowalskiError> {
+            Err(KowalskiError::Memory("connection refused".to_string()))
+        }
+
+        async fn retrieve(
+            &self,
+            _query: &str,
+            _retrieval_limit: usize,
+        ) -> Result<Vec<MemoryUnit>, KowalskiError> {
+            Err(KowalskiError::Memory("connection refused".to_string()))
+        }
+
+        async fn search(
+            &self,
+            _query: crate::memory::MemoryQuery,
+        ) -> Result<Vec<MemoryUnit>, KowalskiError> {
+            Err(KowalskiError::Memory("connection refused".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_memory_stores_do_not_fail_the_chat() {
+        let working = memory();
+        let mut agent = BaseAgent::new(
+            Config::default(),
+            "degraded",
+            "test agent",
+            Arc::new(MockBackend::silent()),
+            working.clone(),
+            Arc::new(tokio::sync::Mutex::new(UnreachableMemory)),
+            Arc::new(tokio::sync::Mutex::new(UnreachableMemory)),
+            ToolManager::new(),
+        )
+        .await
+        .unwrap();
+        let id = agent.start_conversation("m");
+
+        agent.chat_with_history(&id, "hello", None).await.unwrap();
+        agent.add_message(&id, "assistant", "hi there").await;
+        agent
+            .chat_with_history(&id, "hello again", None)
+            .await
+            .unwrap();
+
+        let messages = &agent.get_conversation(&id).unwrap().messages;
+        assert!(messages.iter().any(|m| m.content == "hello again"));
+        // The healthy tier still stores and recalls.
+        let recalled = working.lock().await.retrieve("there", 10).await.unwrap();
+        assert_eq!(recalled.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn profile_facts_survive_restarts_and_wait_for_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        let memory_config = crate::config::MemoryConfig {
+            episodic_path: dir.path().to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let agent = |profile: Arc<ProfileStore>| async move {
+            let mut agent = BaseAgent::new(
+                Config::default(),
+                "profiled",
+                "test agent",
+                Arc::new(MockBackend::silent()),
+                memory(),
+                memory(),
+                memory(),
+                ToolManager::new(),
+            )
+            .await
+            .unwrap();
+            agent.set_profile(profile);
+            agent
+        };
+        let system_messages = |agent: &mut BaseAgent| {
+            let id = agent.start_conversation("m");
+            agent.get_conversation(&id).unwrap().messages[..]
+                .iter()
+                .filter(|m| m.role == "system")
+                .map(|m| m.content.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let first = agent(Arc::new(
+            ProfileStore::open(&memory_config, "ada").await.unwrap(),
+        ))
+        .await;
+        first.remember_fact("name", "Ada").await.unwrap();
+        first.remember_fact("editor", "vim").await.unwrap();
+        assert!(first.forget_fact("editor").await.unwrap());
+        drop(first);
+
+        let profile = Arc::new(ProfileStore::open(&memory_config, "ada").await.unwrap());
+        let mut restarted = agent(profile.clone()).await;
+        assert_eq!(
+            restarted.list_facts(),
+            vec![("name".to_string(), "Ada".to_string())]
+        );
+        assert_eq!(
+            system_messages(&mut restarted),
+            vec!["Known facts about the user:\n- name: Ada".to_string()]
+        );
+
+        profile
+            .propose(crate::memory::profile::FactCandidate {
+                key: "city".to_string(),
+                value: "Warsaw".to_string(),
+                source: "m1".to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(!system_messages(&mut restarted)[0].contains("Warsaw"));
+        profile.approve("city").await.unwrap();
+        assert!(system_messages(&mut restarted)[0].contains("- city: Warsaw"));
+    }
+
+    #[tokio::test]
+    async fn long_conversations_send_the_window_and_pinned_memories() {
+        let backend = Arc::new(
+            crate::testing::MockBackend::script()
+                .responds_with_text("ok")
+                .build(),
+        );
+        let mut agent = crate::testing::agent(backend.clone(), ToolManager::new())
+            .await
+            .unwrap();
+        agent.working_memory = Arc::new(tokio::sync::Mutex::new(WorkingMemory::new(6)));
+        agent
+            .pin_to_working_memory(MemoryUnit {
+                id: "deadline".to_string(),
+                timestamp: 0,
+                content: "The release is on Friday".to_string(),
+                embedding: None,
+                metadata: Default::default(),
+            })
+            .await
+            .unwrap();
+        let id = agent.start_conversation("m");
+        // 100 turns: far more than working memory holds (6 units), which sets the window.
+        for i in 0..100 {
+            agent
+                .add_message(&id, "user", &format!("question {i}"))
+                .await;
+            agent
+                .add_message(&id, "assistant", &format!("answer {i}"))
+                .await;
+        }
+
+        agent.chat_with_history(&id, "latest", None).await.unwrap();
+
+        let sent = &backend.requests()[0].messages;
+        let turns: Vec<&str> = sent
+            .iter()
+            .filter(|m| m.role != "system")
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(
+            turns,
+            [
+                "answer 97",
+                "question 98",
+                "answer 98",
+                "question 99",
+                "answer 99",
+                "latest"
+            ]
+        );
+        assert_eq!(sent[0].role, "system");
+        assert!(
+            sent[1]
+                .content
+                .ends_with("\n- [pinned] The release is on Friday"),
+            "{}",
+            sent[1].content
+        );
+        // The conversation itself keeps everything.
+        assert_eq!(agent.get_conversation(&id).unwrap().messages.len(), 202);
+    }
+
+    #[test]
+    fn the_window_does_not_start_with_an_orphaned_tool_result() {
+        let message = |role: &str, content: &str| Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        };
+        let messages = [
+            message("system", "be brief"),
+            message("user", "sum 2 and 2"),
+            message("assistant", "{\"name\": \"calculator\"}"),
+            message("tool", "4"),
+            message("user", "thanks"),
+        ];
+        let kept = short_term_messages(&messages, 2, None);
+        let roles: Vec<&str> = kept.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user"]);
+        assert_eq!(short_term_messages(&messages, 0, None).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stream_chunks_are_reassembled_per_conversation() {
+        let mut agent = BaseAgent::new(
+            Config::default(),
+            "streamer",
+            "test agent",
+            Arc::new(MockBackend::silent()),
+            memory(),
+            memory(),
+            memory(),
+            ToolManager::new(),
+        )
+        .await
+        .unwrap();
+        let line =
+            br#"{"message":{"role":"assistant","content":"Hel","tool_calls":null},"done":false}
+"#;
+        let (head, tail) = line.split_at(30);
+
+        let partial = agent.process_stream_response("a", head).await.unwrap();
+        assert_eq!(partial.unwrap().content, "");
+        // Another conversation's chunk must not disturb the buffered half.
+        let other =
+            br#"{"message":{"role":"assistant","content":"x","tool_calls":null},"done":false}"#;
+        let other = agent.process_stream_response("b", other).await.unwrap();
+        assert_eq!(other.unwrap().content, "x");
+
+        let mut rest = tail.to_vec();
+        rest.extend_from_slice(
+            br#"{"message":{"role":"assistant","content":"lo","tool_calls":null},"done":false}
+"#,
+        );
+        let joined = agent.process_stream_response("a", &rest).await.unwrap();
+        assert_eq!(joined.unwrap().content, "Hello");
+
+        let done =
+            br#"{"message":{"role":"assistant","content":"","tool_calls":null},"done":true}"#;
+        assert!(
+            agent
+                .process_stream_response("a", done)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn forked_conversation_mutates_independently() {
+        let mut agent = BaseAgent::new(
+            Config::default(),
+            "forker",
+            "test agent",
+            Arc::new(MockBackend::silent()),
+            memory(),
+            memory(),
+            memory(),
+            ToolManager::new(),
+        )
+        .await
+        .unwrap();
+        let id = agent.start_conversation("m1");
+        agent.add_message(&id, "system", "Be brief.").await;
+        agent.add_message(&id, "user", "Name a colour.").await;
+        agent.add_message(&id, "assistant", "Blue.").await;
+
+        let fork = agent.fork_conversation(&id, None).unwrap();
+        let other_model = agent.fork_conversation(&id, Some("m2")).unwrap();
+        assert_ne!(fork, id);
+        assert_eq!(agent.get_conversation(&fork).unwrap().model, "m1");
+        assert_eq!(agent.get_conversation(&other_model).unwrap().model, "m2");
+
+        agent.add_message(&id, "user", "Another?").await;
+        agent.add_message(&fork, "user", "Why?").await;
+        agent.add_message(&fork, "assistant", "Calm.").await;
+
+        let contents = |conv: &str| -> Vec<String> {
+            agent
+                .get_conversation(conv)
+                .unwrap()
+                .messages
+                .iter()
+                .map(|m| m.content.clone())
+                .collect()
+        };
+        assert_eq!(
+            contents(&id),
+            ["Be brief.", "Name a colour.", "Blue.", "Another?"]
+        );
+        assert_eq!(
+            contents(&fork),
+            ["Be brief.", "Name a colour.", "Blue.", "Why?", "Calm."]
+        );
+        assert_eq!(contents(&other_model).len(), 3);
+
+        assert!(matches!(
+            agent.fork_conversation("missing", None),
+            Err(KowalskiError::ConversationNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn prompt_overrides_from_config_reach_the_agent() {
+        let build = |prompts: &[(&str, &str)]| {
+            let mut config = Config::default();
+            config.prompts.templates = prompts
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            BaseAgent::new(
+                config,
+                "localized",
+                "test agent",
+                Arc::new(MockBackend::silent()),
+                memory(),
+                memory(),
+                memory(),
+                ToolManager::new(),
+            )
+        };
+        let agent = build(&[
+            ("tool_result", "Résultat de {{tool}} : {{result}}"),
+            ("memory_context", "Souvenirs :\n{{memories}}"),
+            ("system", "Tu es {{agent_name}}."),
+        ])
+        .await
+        .unwrap();
+        assert_eq!(
+            Agent::tool_result_prompt(&agent, "calculator", "4"),
+            "Résultat de calculator : 4"
+        );
+        assert_eq!(agent.default_system_prompt(), "Tu es localized.");
+        let prompt = memory_prompt(&agent.prompts, "likes tea");
+        assert!(prompt.starts_with("Souvenirs :\n"), "{prompt}");
+        let messages = [Message {
+            role: "system".to_string(),
+            content: prompt,
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        }];
+        assert_eq!(injected_memories(&messages), ["likes tea"]);
+
+        let err = build(&[("tool_result", "{{output}}")]).await.err().unwrap();
+        assert!(matches!(err, KowalskiError::Configuration(_)), "{err}");
+    }
+
+    /// Records the memories injected into each LLM request.
+    struct InjectedMemories(Arc<std::sync::Mutex<Vec<Vec<String>>>>);
+
+    impl AgentObserver for InjectedMemories {
+        fn on_llm_request(&self, _id: &str, _model: &str, messages: &[Message]) {
+            self.0.lock().unwrap().push(injected_memories(messages));
+        }
+    }
+
+    #[tokio::test]
+    async fn recall_is_capped_by_count_and_characters() {
+        let config = Config {
+            working_memory_retrieval_limit: 10,
+            memory_recall_limit: 2,
+            memory_recall_max_chars: 25,
+            ..Config::default()
+        };
+        let working = memory();
+        for i in 0..5 {
+            working
+                .lock()
+                .await
+                .add(MemoryUnit {
+                    id: format!("m{i}"),
+                    timestamp: i,
+                    content: format!("tea note {i}: {}", "x".repeat(i as usize)),
+                    embedding: None,
+                    metadata: Default::default(),
+                })
+                .await
+                .unwrap();
+        }
+        let mut agent = BaseAgent::new(
+            config,
+            "recaller",
+            "test agent",
+            Arc::new(MockBackend::silent()),
+            working,
+            memory(),
+            memory(),
+            ToolManager::new(),
+        )
+        .await
+        .unwrap();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        agent.add_observer(Box::new(InjectedMemories(seen.clone())));
+        let id = agent.start_conversation("m");
+
+        agent.chat_with_history(&id, "tea", None).await.unwrap();
+        let injected = seen.lock().unwrap()[0].clone();
+        // Two most recent matches; the second is cut to the 25-character budget.
+        assert_eq!(injected, ["tea note 3: xxx", "tea note 4"]);
+
+        agent.config.memory_recall_max_chars = 4000;
+        agent.config.memory_recall_limit = 4;
+        agent.chat_with_history(&id, "tea", None).await.unwrap();
+        assert_eq!(seen.lock().unwrap()[1].len(), 4);
+    }
+
+    #[test]
+    fn injected_memories_are_read_back_from_the_request() {
+        let message = |role: &str, content: String| Message {
+            role: role.to_string(),
+            content,
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        };
+        let context =
+            "\n--- Relevant Memories ---\nlikes tea\n---\n[user] hi\n--- End Memories ---";
+        let messages = vec![
+            message("system", "You are helpful.".to_string()),
+            message("system", memory_prompt(&PromptRegistry::default(), context)),
+            message("user", "what do I like?".to_string()),
+        ];
+        assert_eq!(injected_memories(&messages), ["likes tea", "[user] hi"]);
+        assert!(injected_memories(&messages[2..]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn system_prompt_template_opens_each_conversation() {
+        let mut config = Config::default();
+        config.chat.system_prompt_template =
+            Some("You are {agent_name} ({date}). Tools: {tools}.".to_string());
+        let tools = ToolManager::new();
+        tools.register(crate::tools::SchemaInferenceTool::new());
+        tools.register(crate::tools::HtmlToMarkdownTool::new());
+        let mut agent = BaseAgent::new(
+            config,
+            "analyst",
+            "test agent",
+            Arc::new(MockBackend::silent()),
+            memory(),
+            memory(),
+            memory(),
+            tools,
+        )
+        .await
+        .unwrap();
+
+        let id = agent.start_conversation("m1");
+        let messages = &agent.get_conversation(&id).unwrap().messages;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "system");
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(
+            messages[0].content,
+            format!("You are analyst ({today}). Tools: html_to_markdown, infer_schema.")
+        );
+
+        agent.system_prompt_template = None;
+        let plain = agent.start_conversation("m1");
+        assert!(agent.get_conversation(&plain).unwrap().messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn regenerate_replaces_the_last_reply() {
+        let mut config = Config::default();
+        config.chat.temperature = 0.5;
+        let episodic = memory();
+        let mut agent = BaseAgent::new(
+            config,
+            "retry",
+            "test agent",
+            // Replies with the temperature it was asked for.
+            Arc::new(
+                MockBackend::script()
+                    .otherwise(|request| {
+                        MockReply::text(format!(
+                            "t={}",
+                            request.options.temperature.unwrap_or_default()
+                        ))
+                    })
+                    .build(),
+            ),
+            memory(),
+            episodic.clone(),
+            memory(),
+            ToolManager::new(),
+        )
+        .await
+        .unwrap();
+        let id = agent.start_conversation("m1");
+        assert!(
+            agent
+                .regenerate_last(&id, &ChatOptions::default())
+                .await
+                .is_err()
+        );
+
+        let reply = agent.chat_with_history(&id, "hi", None).await.unwrap();
+        agent.add_message(&id, "assistant", &reply).await;
+        assert_eq!(reply, "t=0.5");
+        let before = agent.get_conversation(&id).unwrap().messages.len();
+
+        let options = ChatOptions {
+            temperature: Some(1.2),
+            ..ChatOptions::default()
+        };
+        assert_eq!(agent.regenerate_last(&id, &options).await.unwrap(), "t=1.2");
+        let messages = &agent.get_conversation(&id).unwrap().messages;
+        assert_eq!(messages.len(), before);
+        assert_eq!(messages[before - 2].content, "hi");
+        assert_eq!(messages[before - 1].content, "t=1.2");
+        // The question is archived once; only the reply is new.
+        let archived = episodic.lock().await.retrieve("hi", 10).await.unwrap();
+        let questions = archived.iter().filter(|u| u.content == "[user] hi").count();
+        assert_eq!(questions, 1, "{archived:?}");
+
+        // A user turn without a stored reply is simply answered.
+        agent.add_message(&id, "user", "again").await;
+        let before = agent.get_conversation(&id).unwrap().messages.len();
+        assert_eq!(
+            agent
+                .regenerate_last(&id, &ChatOptions::default())
+                .await
+                .unwrap(),
+            "t=0.5"
+        );
+        assert_eq!(
+            agent.get_conversation(&id).unwrap().messages.len(),
+            before + 1
+        );
+        assert_eq!(agent.config.chat.temperature, 0.5);
+    }
+
+    #[tokio::test]
+    async fn closing_a_conversation_stores_its_summary_in_both_tiers() {
+        use crate::memory::episodic::EpisodicBuffer;
+        use crate::memory::semantic::SemanticStore;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        config.memory.summarize_on_close = true;
+        let backend = Arc::new(
+            MockBackend::script()
+                .responds_with_text("- Release moved to Friday\n- Open: who writes the notes")
+                .with_embedding(vec![0.6, 0.8])
+                .build(),
+        );
+        let episodic = Arc::new(tokio::sync::Mutex::new(
+            EpisodicBuffer::open(&config.memory, backend.clone())
+                .await
+                .unwrap(),
+        ));
+        let semantic = Arc::new(tokio::sync::Mutex::new(SemanticStore::new()));
+        let mut agent = BaseAgent::new(
+            config,
+            "closer",
+            "test agent",
+            backend.clone(),
+            memory(),
+            episodic.clone(),
+            semantic.clone(),
+            ToolManager::new(),
+        )
+        .await
+        .unwrap();
+        let id = agent.start_conversation("m");
+        agent
+            .add_message(&id, "user", "Can we move the release to Friday?")
+            .await;
+        agent.add_message(&id, "assistant", "Friday works.").await;
+
+        assert!(Agent::close_conversation(&mut agent, &id).await.unwrap());
+        assert!(agent.get_conversation(&id).is_none());
+        let prompt = &backend.requests()[0].messages[0].content;
+        assert!(prompt.contains("user: Can we move the release to Friday?"));
+        assert!(prompt.contains("assistant: Friday works."));
+
+        let expected = [
+            ("conversation_id".to_string(), id.clone()),
+            ("kind".to_string(), "session_summary".to_string()),
+        ]
+        .into();
+        let stored = episodic.lock().await.retrieve_all().await.unwrap();
+        let summary = stored
+            .iter()
+            .find(|u| u.id == format!("session-summary-{id}"))
+            .unwrap();
+        assert_eq!(summary.metadata, expected);
+        assert!(
+            summary
+                .content
+                .starts_with("In a previous session we discussed:\n- Release moved to Friday")
+        );
+        let semantic = semantic
+            .lock()
+            .await
+            .retrieve("session-summary", 5)
+            .await
+            .unwrap();
+        assert_eq!(semantic.len(), 1);
+        assert_eq!(semantic[0].metadata, expected);
+        assert_eq!(semantic[0].embedding.as_deref(), Some(&[0.6, 0.8][..]));
+
+        // Nothing said yet: nothing to remember.
+        let empty = agent.start_conversation("m");
+        assert!(
+            agent
+                .summarize_conversation(&empty)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Agent lifecycle hooks: implement [`AgentObserver`] and add it with
+//! [`BaseAgent::add_observer`](crate::agent::BaseAgent::add_observer) to log, meter or render
+//! conversations without patching the agent. [`TracingObserver`] is installed by default.
+
+use crate::agent::timings::ChatTimings;
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::tools::ToolOutput;
+use log::{debug, trace, warn};
+
+/// Callbacks fired by [`BaseAgent`](crate::agent::BaseAgent). All methods default to no-ops.
+///
+/// Observers run inline on the agent task, so keep them cheap (hand work off to a channel).
+pub trait AgentObserver: Send + Sync {
+    fn on_conversation_started(&self, _conversation_id: &str, _model: &str) {}
+
+    /// A message was appended to the conversation (user turns, assistant replies, tool results).
+    fn on_message_added(&self, _conversation_id: &str, _role: &str, _content: &str) {}
+
+    /// About to call the LLM with `messages` (including any ephemeral memory context).
+    fn on_llm_request(&self, _conversation_id: &str, _model: &str, _messages: &[Message]) {}
+
+    fn on_llm_response(&self, _conversation_id: &str, _response: &str) {}
+
+    fn on_tool_call(&self, _tool_name: &str, _parameters: &serde_json::Value) {}
+
+    fn on_tool_result(&self, _tool_name: &str, _result: &Result<ToolOutput, KowalskiError>) {}
+
+    /// A tool-calling turn finished; `timings` says where its time went.
+    fn on_chat_timings(&self, _conversation_id: &str, _timings: &ChatTimings) {}
+}
+
+/// Logs every event through the `log` facade (`debug`, message bodies at `trace`, tool
+/// failures at `warn`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingObserver;
+
+impl AgentObserver for TracingObserver {
+    fn on_conversation_started(&self, conversation_id: &str, model: &str) {
+        debug!("conversation {conversation_id} started (model {model})");
+    }
+
+    fn on_message_added(&self, conversation_id: &str, role: &str, content: &str) {
+        trace!("conversation {conversation_id} [{role}] {content}");
+    }
+
+    fn on_llm_request(&self, conversation_id: &str, model: &str, messages: &[Message]) {
+        debug!(
+            "conversation {conversation_id}: LLM request to {model} ({} messages)",
+            messages.len()
+        );
+    }
+
+    fn on_llm_response(&self, conversation_id: &str, response: &str) {
+        debug!(
+            "conversation {conversation_id}: LLM response ({} chars)",
+            response.chars().count()
+        );
+    }
+
+    fn on_tool_call(&self, tool_name: &str, parameters: &serde_json::Value) {
+        debug!("tool call {tool_name} {parameters}");
+    }
+
+    fn on_tool_result(&self, tool_name: &str, result: &Result<ToolOutput, KowalskiError>) {
+        match result {
+            Ok(_) => debug!("tool {tool_name} succeeded"),
+            Err(e) => warn!("tool {tool_name} failed: {e}"),
+        }
+    }
+
+    fn on_chat_timings(&self, conversation_id: &str, timings: &ChatTimings) {
+        debug!("conversation {conversation_id}: chat_with_tools {timings}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Agent;
+    use crate::testing::MockBackend;
+    use crate::tools::HtmlToMarkdownTool;
+    use crate::tools::manager::ToolManager;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl AgentObserver for Recorder {
+        fn on_conversation_started(&self, _id: &str, model: &str) {
+            self.0.lock().unwrap().push(format!("start {model}"));
+        }
+        fn on_message_added(&self, _id: &str, role: &str, _content: &str) {
+            self.0.lock().unwrap().push(format!("message {role}"));
+        }
+        fn on_llm_request(&self, _id: &str, _model: &str, _messages: &[Message]) {
+            self.0.lock().unwrap().push("llm_request".into());
+        }
+        fn on_llm_response(&self, _id: &str, response: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("llm_response {response}"));
+        }
+        fn on_tool_call(&self, tool_name: &str, _parameters: &serde_json::Value) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("tool_call {tool_name}"));
+        }
+        fn on_tool_result(&self, tool_name: &str, result: &Result<ToolOutput, KowalskiError>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("tool_result {tool_name} {}", result.is_ok()));
+        }
+    }
+
+    #[tokio::test]
+    async fn observer_sees_lifecycle_of_a_tool_turn() {
+        let backend = MockBackend::script()
+            .responds_with_tool_call("html_to_markdown", serde_json::json!({"html": "<b>x</b>"}))
+            .user_says("Based on the tool result")
+            .then_text("Done.")
+            .build();
+        let tools = ToolManager::new();
+        tools.register(HtmlToMarkdownTool::new());
+        let mut agent = crate::testing::agent(Arc::new(backend), tools)
+            .await
+            .unwrap();
+        let recorder = Recorder::default();
+        let events = recorder.0.clone();
+        agent.add_observer(Box::new(recorder));
+
+        let id = agent.start_conversation("m1");
+        let answer = agent.chat_with_tools(&id, "convert this").await.unwrap();
+        assert_eq!(answer, "Done.");
+
+        let events = events.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            vec![
+                "start m1",
+                "message user",
+                "llm_request",
+                r#"llm_response {"name":"html_to_markdown","parameters":{"html":"<b>x</b>"}}"#,
+                "tool_call html_to_markdown",
+                "tool_result html_to_markdown true",
+                "message assistant",
+                "message tool",
+                "message user",
+                "llm_request",
+                "llm_response Done.",
+                "message assistant",
+            ]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Templated system prompts, rendered once when a conversation starts.
+
+use chrono::NaiveDate;
+
+/// A system prompt with placeholders filled in per conversation:
+///
+/// - `{agent_name}` — the agent's name
+/// - `{date}` — today's date, `YYYY-MM-DD`
+/// - `{tools}` — registered tool names, comma-separated (`none` when there are none)
+///
+/// Anything else in braces is left as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemPromptTemplate {
+    template: String,
+}
+
+impl SystemPromptTemplate {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    pub fn render(&self, agent_name: &str, date: NaiveDate, tools: &[String]) -> String {
+        let tools = if tools.is_empty() {
+            "none".to_string()
+        } else {
+            tools.join(", ")
+        };
+        self.template
+            .replace("{agent_name}", agent_name)
+            .replace("{date}", &date.format("%Y-%m-%d").to_string())
+            .replace("{tools}", &tools)
+    }
+}
+
+impl From<&str> for SystemPromptTemplate {
+    fn from(template: &str) -> Self {
+        Self::new(template)
+    }
+}
+
+impl From<String> for SystemPromptTemplate {
+    fn from(template: String) -> Self {
+        Self::new(template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_placeholders_and_keeps_unknown_ones() {
+        let template = SystemPromptTemplate::new(
+            "You are {agent_name}. Today is {date}. Tools: {tools}. Reply as {\"answer\": ...}.",
+        );
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(
+            template.render("kowalski", date, &["fs_tool".into(), "web_search".into()]),
+            "You are kowalski. Today is 2026-03-09. Tools: fs_tool, web_search. Reply as {\"answer\": ...}."
+        );
+        assert!(template.render("k", date, &[]).contains("Tools: none."));
+    }
+}
@@ -1,6 +1,13 @@
-//! Optional `[agent]` / `[tool]` line prefixes for CLI REPL (`kowalski run`).
+//! How [`super::Agent::chat_with_tools`] prints to the terminal: optional `[agent]` / `[tool]`
+//! line prefixes for the CLI REPL (`kowalski run`), and whether replies that are only tool calls
+//! are shown.
 
+use crate::error::KowalskiError;
 use std::cell::Cell;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static VERBOSE_REPLIES: AtomicBool = AtomicBool::new(false);
 
 thread_local! {
     static ENABLED: Cell<bool> = const { Cell::new(false) };
@@ -15,6 +22,33 @@ pub(crate) fn repl_trace_enabled() -> bool {
     ENABLED.with(|c| c.get())
 }
 
+/// Also print replies that are tool calls (raw JSON), not just answers. Process-wide; off by
+/// default. `[agent]` trace mode always prints them.
+pub fn set_verbose_replies(enabled: bool) {
+    VERBOSE_REPLIES.store(enabled, Ordering::Relaxed);
+}
+
+/// Prints one LLM reply of a tool loop, skipping tool-call replies unless they were asked for
+/// (see [`set_verbose_replies`]).
+pub(crate) fn print_reply(text: &str) -> Result<(), KowalskiError> {
+    let trace = repl_trace_enabled();
+    if !trace && !VERBOSE_REPLIES.load(Ordering::Relaxed) {
+        let tool_reply = !crate::utils::json::extract_tool_calls(text).is_empty()
+            || crate::utils::json::looks_like_tool_json_attempt(text);
+        if tool_reply {
+            return Ok(());
+        }
+    }
+    if trace {
+        println!("[agent] {}", text);
+    } else {
+        println!("{}", text);
+    }
+    io::stdout()
+        .flush()
+        .map_err(|e| KowalskiError::Server(e.to_string()))
+}
+
 /// RAII: enable trace for the current thread until dropped.
 pub struct ReplTraceGuard;
 
@@ -0,0 +1,215 @@
+//! Structured output: replies constrained to a caller-provided JSON Schema
+//! (see [`Agent::chat_structured`](super::Agent::chat_structured)).
+
+use super::BaseAgent;
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::utils::json::strip_markdown_code_fences;
+use crate::utils::json_schema;
+use log::debug;
+use serde_json::Value;
+use std::time::Instant;
+
+/// Ephemeral system hint carrying the schema; the backend may enforce it as well.
+fn schema_prompt(schema: &Value) -> String {
+    format!(
+        "Reply with a single JSON value that conforms to this JSON Schema, and nothing else:\n{schema}"
+    )
+}
+
+/// Ephemeral user turn asking the model to fix its previous reply.
+fn correction_prompt(errors: &[String]) -> String {
+    format!(
+        "Your reply did not match the JSON Schema:\n- {}\nReply again with corrected JSON only.",
+        errors.join("\n- ")
+    )
+}
+
+/// Parses a reply (a ```json fence is tolerated) and checks it against `schema`.
+fn parse_reply(reply: &str, schema: &Value) -> Result<Value, Vec<String>> {
+    let value: Value = serde_json::from_str(strip_markdown_code_fences(reply).trim())
+        .map_err(|e| vec![format!("$: not valid JSON ({e})")])?;
+    let errors = json_schema::validate(&value, schema);
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+impl BaseAgent {
+    /// See [`Agent::chat_structured`](super::Agent::chat_structured). The schema goes to the
+    /// backend through [`LLMProvider::chat_structured`](crate::llm::LLMProvider::chat_structured)
+    /// and every reply is validated here as well. A reply that fails is shown its errors and
+    /// asked again, up to [`ChatConfig::structured_output_retries`](crate::config::ChatConfig::structured_output_retries)
+    /// times. Only the prompt and the accepted reply are kept in the conversation.
+    pub async fn chat_structured(
+        &mut self,
+        conversation_id: &str,
+        prompt: &str,
+        schema: &Value,
+    ) -> Result<Value, KowalskiError> {
+        let short_term = self.short_term_context().await;
+        let conversation = self
+            .conversations
+            .get(conversation_id)
+            .ok_or_else(|| KowalskiError::ConversationNotFound(conversation_id.to_string()))?;
+        let model = conversation.model.clone();
+        let mut messages = Self::request_history(&conversation.messages, short_term);
+        messages.push(Message {
+            role: "system".to_string(),
+            content: schema_prompt(schema),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        });
+        messages.push(Message {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        });
+
+        let options = self.chat_options();
+        let attempts = self.config.chat.structured_output_retries + 1;
+        let mut errors = Vec::new();
+        for attempt in 1..=attempts {
+            self.notify(|o| o.on_llm_request(conversation_id, &model, &messages));
+            let started = Instant::now();
+            let reply = self
+                .llm_provider
+                .chat_structured(&model, &messages, schema, &options)
+                .await;
+            crate::metrics::record_llm_request(started.elapsed());
+            let reply = reply?;
+            self.notify(|o| o.on_llm_response(conversation_id, &reply));
+
+            match parse_reply(&reply, schema) {
+                Ok(value) => {
+                    self.add_message(conversation_id, "user", prompt).await;
+                    self.add_message(conversation_id, "assistant", &value.to_string())
+                        .await;
+                    return Ok(value);
+                }
+                Err(problems) => {
+                    debug!(
+                        "conversation {conversation_id}: structured reply {attempt}/{attempts} rejected: {problems:?}"
+                    );
+                    messages.push(Message {
+                        role: "assistant".to_string(),
+                        content: reply,
+                        tool_calls: None,
+                        images: None,
+                        tool_call_id: None,
+                        tool_name: None,
+                    });
+                    messages.push(Message {
+                        role: "user".to_string(),
+                        content: correction_prompt(&problems),
+                        tool_calls: None,
+                        images: None,
+                        tool_call_id: None,
+                        tool_name: None,
+                    });
+                    errors = problems;
+                }
+            }
+        }
+        Err(KowalskiError::StructuredOutput { attempts, errors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::Agent;
+    use crate::error::KowalskiError;
+    use crate::testing::{self, MockBackend};
+    use crate::tools::manager::ToolManager;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn contact_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "email": {"type": "string"},
+                "company": {"type": "string"}
+            },
+            "required": ["name", "email", "company"]
+        })
+    }
+
+    #[tokio::test]
+    async fn invalid_reply_is_corrected_on_retry() {
+        let backend = MockBackend::script()
+            .user_says("Ada Lovelace")
+            .responds_with_text(r#"{"name": "Ada Lovelace", "email": "ada@example.com"}"#)
+            .then_text(
+                "```json\n{\"name\": \"Ada Lovelace\", \"email\": \"ada@example.com\", \"company\": \"Analytical Engines\"}\n```",
+            )
+            .build();
+        let backend = Arc::new(backend);
+        let mut agent = testing::agent(backend.clone(), ToolManager::new())
+            .await
+            .unwrap();
+        let id = agent.start_conversation("llama3.2");
+
+        let value = agent
+            .chat_structured(
+                &id,
+                "Ada Lovelace <ada@example.com> works at Analytical Engines",
+                &contact_schema(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(value["company"], "Analytical Engines");
+
+        let requests = backend.requests();
+        assert_eq!(requests[0].schema.as_ref(), Some(&contact_schema()));
+        assert!(
+            requests[1]
+                .last_user_message()
+                .unwrap()
+                .contains("$.company: missing required property")
+        );
+        let stored: Vec<&str> = agent.conversations[&id]
+            .messages
+            .iter()
+            .map(|m| m.role.as_str())
+            .collect();
+        assert_eq!(stored, ["system", "user", "assistant"]);
+        backend.assert_finished();
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_configured_retries() {
+        let backend = MockBackend::script()
+            .responds_with_text("not json")
+            .then_text(r#"{"name": 1}"#)
+            .build();
+        let backend = Arc::new(backend);
+        let mut agent = testing::agent(backend.clone(), ToolManager::new())
+            .await
+            .unwrap();
+        agent.config.chat.structured_output_retries = 1;
+        let id = agent.start_conversation("llama3.2");
+
+        let err = agent
+            .chat_structured(&id, "nobody here", &contact_schema())
+            .await
+            .unwrap_err();
+        match err {
+            KowalskiError::StructuredOutput { attempts, errors } => {
+                assert_eq!(attempts, 2);
+                assert!(errors.contains(&"$.name: expected string, got number".to_string()));
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        assert_eq!(agent.conversations[&id].messages.len(), 1);
+        backend.assert_finished();
+    }
+}
@@ -0,0 +1,147 @@
+//! Where the time of one tool-calling turn went (see [`ChatTimings`]).
+
+use std::fmt;
+use std::time::Duration;
+
+/// Per-phase durations of one [`Agent::chat_with_tools`](super::Agent::chat_with_tools) call or
+/// [`tool_loop`](super::tool_loop) turn.
+/// Reported to [`AgentObserver::on_chat_timings`](super::observer::AgentObserver::on_chat_timings)
+/// and kept in [`BaseAgent::last_chat_timings`](super::BaseAgent::last_chat_timings).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatTimings {
+    /// Wall time of the whole call.
+    pub total: Duration,
+    /// Memory recall before each LLM call (query embedding and store lookups), summed.
+    pub memory_recall: Duration,
+    /// LLM latency of each iteration, in order.
+    pub llm: Vec<Duration>,
+    /// Each tool run as `(tool name, duration)`, in order.
+    pub tools: Vec<(String, Duration)>,
+}
+
+impl ChatTimings {
+    pub fn llm_total(&self) -> Duration {
+        self.llm.iter().sum()
+    }
+
+    pub fn tools_total(&self) -> Duration {
+        self.tools.iter().map(|(_, d)| *d).sum()
+    }
+
+    /// Time outside the measured phases (prompt building, memory writes, bookkeeping).
+    pub fn other(&self) -> Duration {
+        self.total
+            .saturating_sub(self.memory_recall + self.llm_total() + self.tools_total())
+    }
+}
+
+impl fmt::Display for ChatTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "total {:?}: llm {:?} ({} calls), memory recall {:?}, tools {:?} ({} calls), other {:?}",
+            self.total,
+            self.llm_total(),
+            self.llm.len(),
+            self.memory_recall,
+            self.tools_total(),
+            self.tools.len(),
+            self.other()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::Agent;
+    use crate::error::KowalskiError;
+    use crate::testing::{self, MockBackend};
+    use crate::tools::manager::ToolManager;
+    use crate::tools::{Tool, ToolInput, ToolOutput, ToolParameter};
+    use async_trait::async_trait;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Takes 40ms per call.
+    struct SlowTool;
+
+    #[async_trait]
+    impl Tool for SlowTool {
+        async fn execute(&mut self, _input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+            tokio::time::sleep(Duration::from_millis(40)).await;
+            Ok(ToolOutput::new(serde_json::json!({"done": true}), None))
+        }
+
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn description(&self) -> &str {
+            "Takes its time"
+        }
+
+        fn parameters(&self) -> Vec<ToolParameter> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn phases_add_up_to_the_total() {
+        let backend = MockBackend::script()
+            .responds_with_tool_call("slow", serde_json::json!({}))
+            .then_text("Done.")
+            .build();
+        let tools = ToolManager::new();
+        tools.register(SlowTool);
+        let mut agent = testing::agent(Arc::new(backend), tools).await.unwrap();
+        let id = agent.start_conversation("llama3.2");
+
+        agent.chat_with_tools(&id, "be slow").await.unwrap();
+        let timings = agent.last_chat_timings().unwrap().clone();
+        assert_eq!(timings.llm.len(), 2);
+        assert_eq!(timings.tools.len(), 1);
+        assert_eq!(timings.tools[0].0, "slow");
+        assert!(timings.tools_total() >= Duration::from_millis(40));
+
+        let measured = timings.memory_recall + timings.llm_total() + timings.tools_total();
+        assert!(measured <= timings.total);
+        assert!(
+            timings.other() < timings.total / 2,
+            "unaccounted time too large: {timings}"
+        );
+    }
+
+    #[tokio::test]
+    async fn the_shared_tool_loop_reports_timings() {
+        let backend = MockBackend::script()
+            .responds_with_tool_call("slow", serde_json::json!({}))
+            .then_text("Done.")
+            .build();
+        let tools = ToolManager::new();
+        tools.register(SlowTool);
+        let mut agent = testing::agent(Arc::new(backend), tools).await.unwrap();
+        let id = agent.start_conversation("llama3.2");
+
+        let (events, mut rx) = tokio::sync::mpsc::channel(64);
+        let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+        crate::agent::tool_loop::run_tool_loop_streaming(
+            &mut agent,
+            &id,
+            "be slow",
+            &crate::agent::tool_loop::ToolLoopOptions::default(),
+            &events,
+        )
+        .await
+        .unwrap();
+        drop(events);
+        drain.await.unwrap();
+
+        let timings = agent.last_chat_timings().unwrap();
+        assert_eq!(timings.llm.len(), 2);
+        assert_eq!(timings.tools.len(), 1);
+        assert!(timings.tools_total() >= Duration::from_millis(40));
+        assert!(
+            timings.memory_recall + timings.llm_total() + timings.tools_total() <= timings.total
+        );
+    }
+}
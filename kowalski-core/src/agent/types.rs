@@ -1,4 +1,6 @@
+use crate::config::OllamaOptions;
 use crate::conversation::Message;
+use crate::tools::ToolMetadata;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ChatRequest {
@@ -8,6 +10,54 @@ pub struct ChatRequest {
     pub temperature: f32,
     pub max_tokens: usize,
     pub tools: Option<serde_json::Value>,
+    /// Ollama output constraint: `"json"` for any valid JSON (omitted for free-form text).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
+    /// Ollama generation options, sent as `options` plus a top-level `keep_alive`.
+    #[serde(default, flatten, with = "ollama_fields")]
+    pub options: Option<OllamaOptions>,
+}
+
+/// Where Ollama reads [`OllamaOptions`]: `keep_alive` beside `model`, the rest under `options`.
+mod ollama_fields {
+    use super::OllamaOptions;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Fields {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        options: Option<OllamaOptions>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keep_alive: Option<String>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        options: &Option<OllamaOptions>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut options = options.clone();
+        let keep_alive = options.as_mut().and_then(|o| o.keep_alive.take());
+        Fields {
+            options: options.filter(|o| *o != OllamaOptions::default()),
+            keep_alive,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<OllamaOptions>, D::Error> {
+        let Fields {
+            options,
+            keep_alive,
+        } = Fields::deserialize(deserializer)?;
+        if options.is_none() && keep_alive.is_none() {
+            return Ok(None);
+        }
+        let mut options = options.unwrap_or_default();
+        options.keep_alive = keep_alive.or(options.keep_alive);
+        Ok(Some(options))
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -15,3 +65,22 @@ pub struct StreamResponse {
     pub done: bool,
     pub message: Message,
 }
+
+/// What an agent offers, for routers and coordinators choosing one programmatically (see
+/// [`Agent::capabilities`](super::Agent::capabilities)).
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentCapabilities {
+    /// Tools the agent may call, sorted by name (its role's restrictions applied).
+    pub tools: Vec<ToolMetadata>,
+    /// Whether the agent can stream reply tokens.
+    pub supports_streaming: bool,
+    /// Model new conversations use unless the caller picks one; empty when the agent has none.
+    pub default_model: String,
+    pub description: String,
+}
+
+impl AgentCapabilities {
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.tools.iter().any(|t| t.name == name)
+    }
+}
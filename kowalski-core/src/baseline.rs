@@ -0,0 +1,225 @@
+//! Straightforward versions of hot paths that have been optimized, kept as they were so the
+//! benchmarks (`cargo bench -p kowalski-core --features bench`) can measure both and the tests
+//! below can check that the optimized versions return the same results. Only built with the
+//! `bench` feature.
+
+use crate::error::KowalskiError;
+use crate::memory::{MemoryProvider, MemoryUnit};
+
+/// Three passes: dot product, then each norm (what [`cosine_similarity`] replaced).
+///
+/// [`cosine_similarity`]: crate::memory::semantic::cosine_similarity
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Scores every unit, sorts them all and lowercases the query once per unit (what
+/// [`rank_units`](crate::memory::episodic::rank_units) replaced).
+pub fn rank_units(
+    units: Vec<MemoryUnit>,
+    query: &str,
+    query_embedding: Option<&[f32]>,
+    now: u64,
+    retrieval_limit: usize,
+) -> Vec<MemoryUnit> {
+    let max_time_span = 60 * 60 * 24 * 30u64;
+    let mut scored = Vec::new();
+    let mut fallback_results = Vec::new();
+    for unit in units {
+        if let (Some(q_emb), Some(m_emb)) = (query_embedding, unit.embedding.as_ref()) {
+            let sim = cosine_similarity(q_emb, m_emb);
+            let recency =
+                1.0 - ((now.saturating_sub(unit.timestamp)) as f32 / max_time_span as f32).min(1.0);
+            let recency = recency.max(0.0);
+            let score = 0.85 * sim + 0.15 * recency;
+            scored.push((score, unit));
+        } else {
+            let lower_query = query.to_lowercase().trim().to_string();
+            let query_words: Vec<&str> = lower_query.split_whitespace().collect();
+            let content = unit.content.to_lowercase();
+            if query_words.iter().any(|w| content.contains(w)) {
+                fallback_results.push(unit);
+            }
+        }
+    }
+    if !scored.is_empty() {
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        return scored
+            .into_iter()
+            .map(|(_, u)| u)
+            .take(retrieval_limit)
+            .collect();
+    }
+    if fallback_results.len() > retrieval_limit {
+        fallback_results[fallback_results.len() - retrieval_limit..].to_vec()
+    } else {
+        fallback_results
+    }
+}
+
+/// Stores `units` one at a time through [`MemoryProvider::add`], one embedding request each
+/// (what the episodic buffer's batched [`add_batch`](MemoryProvider::add_batch) replaced).
+pub async fn add_each(
+    memory: &mut dyn MemoryProvider,
+    units: Vec<MemoryUnit>,
+) -> Result<(), KowalskiError> {
+    for unit in units {
+        memory.add(unit).await?;
+    }
+    Ok(())
+}
+
+/// Rescans `pending` from the start and drains it once per line (what
+/// [`NdjsonBuffer::push`](crate::utils::ndjson::NdjsonBuffer::push) replaced).
+pub fn ndjson_push(pending: &mut Vec<u8>, chunk: &[u8]) -> Vec<String> {
+    pending.extend_from_slice(chunk);
+    let mut lines = Vec::new();
+    while let Some(pos) = pending.iter().position(|&b| b == b'\n') {
+        let raw: Vec<u8> = pending.drain(..=pos).collect();
+        let line = String::from_utf8_lossy(&raw).trim().to_string();
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::memory::episodic::{self, EpisodicBuffer};
+    use crate::memory::semantic;
+    use crate::testing::MockBackend;
+    use crate::utils::ndjson::NdjsonBuffer;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::sync::Arc;
+
+    fn vector(rng: &mut StdRng, dims: usize) -> Vec<f32> {
+        (0..dims).map(|_| rng.random_range(-1.0..1.0)).collect()
+    }
+
+    fn units(rng: &mut StdRng, count: usize, embedded: bool) -> Vec<MemoryUnit> {
+        let words = ["rust", "tokio", "memory", "agent", "stream", "csv"];
+        (0..count)
+            .map(|i| MemoryUnit {
+                id: format!("u{i}"),
+                // A few share a timestamp and an embedding, so ties are exercised.
+                timestamp: 1_000_000 + (i as u64 % 50) * 3_600,
+                content: format!("{} note {i}", words[i % words.len()].to_uppercase()),
+                embedding: (embedded && i % 7 != 0).then(|| {
+                    if i % 10 == 3 {
+                        vec![0.5; 16]
+                    } else {
+                        vector(rng, 16)
+                    }
+                }),
+                metadata: Default::default(),
+            })
+            .collect()
+    }
+
+    fn ids(units: &[MemoryUnit]) -> Vec<&str> {
+        units.iter().map(|u| u.id.as_str()).collect()
+    }
+
+    #[test]
+    fn cosine_matches_the_three_pass_version() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let (a, b) = (vector(&mut rng, 768), vector(&mut rng, 768));
+            assert_eq!(
+                semantic::cosine_similarity(&a, &b),
+                cosine_similarity(&a, &b)
+            );
+        }
+        assert_eq!(semantic::cosine_similarity(&[0.0; 4], &[1.0; 4]), 0.0);
+    }
+
+    #[test]
+    fn ranking_matches_the_full_sort() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let now = 1_000_000 + 40 * 3_600;
+        for embedded in [true, false] {
+            let all = units(&mut rng, 500, embedded);
+            let query = vector(&mut rng, 16);
+            for limit in [0, 1, 3, 10, 499, 500, 600] {
+                for query_embedding in [Some(query.as_slice()), Some(&[0.5; 16][..]), None] {
+                    let expected =
+                        rank_units(all.clone(), "Tokio  csv", query_embedding, now, limit);
+                    let actual = episodic::rank_units(
+                        all.clone(),
+                        "Tokio  csv",
+                        query_embedding,
+                        now,
+                        limit,
+                    );
+                    assert_eq!(ids(&actual), ids(&expected), "limit {limit}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ndjson_reassembly_matches_the_rescanning_version() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let stream: Vec<u8> = (0..400)
+            .map(|i| {
+                if i % 37 == 0 {
+                    "\n  \n".to_string()
+                } else {
+                    format!("{{\"message\":{{\"content\":\"tok {i} é\"}},\"done\":false}}\n")
+                }
+            })
+            .collect::<String>()
+            .into_bytes();
+        let mut naive = Vec::new();
+        let mut buffer = NdjsonBuffer::new();
+        let mut rest = stream.as_slice();
+        while !rest.is_empty() {
+            let (chunk, tail) = rest.split_at(rng.random_range(1..=300).min(rest.len()));
+            assert_eq!(buffer.push(chunk), ndjson_push(&mut naive, chunk));
+            rest = tail;
+        }
+        assert!(buffer.is_empty() && naive.is_empty());
+    }
+
+    #[tokio::test]
+    async fn batched_inserts_store_the_same_units_with_fewer_requests() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let all = units(&mut rng, 150, true);
+        let mut stored = Vec::new();
+        let mut requests = Vec::new();
+        for batched in [false, true] {
+            let dir = tempfile::tempdir().unwrap();
+            let mut config = Config::default();
+            config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+            let llm = Arc::new(MockBackend::script().with_embedding(vec![0.25; 16]).build());
+            let mut buffer = EpisodicBuffer::open(&config.memory, llm.clone())
+                .await
+                .unwrap();
+            if batched {
+                buffer.add_batch(all.clone()).await.unwrap();
+            } else {
+                add_each(&mut buffer, all.clone()).await.unwrap();
+            }
+            stored.push(buffer.retrieve_all().await.unwrap());
+            requests.push(llm.embed_requests().len());
+        }
+        assert_eq!(
+            serde_json::to_value(&stored[0]).unwrap(),
+            serde_json::to_value(&stored[1]).unwrap()
+        );
+        // Every 7th unit has no embedding: 22 of them, one request each, or one request per
+        // batch of up to 64 units.
+        assert_eq!(requests, [22, 3]);
+    }
+}
@@ -0,0 +1,163 @@
+//! Coding workflows: a [`CodeAgent`] works inside one project directory with a repo map for
+//! context, a writable [`FsTool`] and an allowlisted [`ShellTool`].
+//! [`CodeAgent::generate_tests`] has its LLM write unit tests for a file or function, runs them
+//! and feeds failures back until they pass.
+//!
+//! Every write goes through the agent's [`ToolApprover`] first; without one, writes are refused
+//! as for any other destructive tool call (see [`approval`](crate::agent::approval)).
+
+mod testgen;
+
+pub use testgen::{DEFAULT_TEST_ITERATIONS, TestGenerationReport, TestRun, TestRunner};
+
+use crate::agent::approval::{ToolApprover, review_call};
+use crate::error::KowalskiError;
+use crate::llm::LLMProvider;
+use crate::tools::manager::ToolManager;
+use crate::tools::{
+    FsTool, RepoMapper, ShellTool, ShellToolConfig, ToolCall, ToolInput, ToolOutput,
+};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Commands [`CodeAgent::new`] may run: the test runners.
+pub const DEFAULT_CODE_COMMANDS: &[&str] = &["cargo", "pytest"];
+
+/// Builds and test runs take longer than the shell tool's default timeout.
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 300;
+
+/// Agent for changes to a project: reads through the repo map and `fs_tool`, writes only what
+/// its approver allows, and runs allowlisted commands with `shell`.
+pub struct CodeAgent {
+    llm: Arc<dyn LLMProvider>,
+    model: String,
+    root: PathBuf,
+    mapper: RepoMapper,
+    tools: ToolManager,
+    approver: Option<Box<dyn ToolApprover>>,
+    max_iterations: usize,
+}
+
+impl CodeAgent {
+    /// Works on the project at `root`; the shell may run [`DEFAULT_CODE_COMMANDS`].
+    pub fn new(
+        llm: Arc<dyn LLMProvider>,
+        model: impl Into<String>,
+        root: impl Into<PathBuf>,
+    ) -> Self {
+        let root = root.into();
+        let shell = ShellToolConfig {
+            allowed_commands: DEFAULT_CODE_COMMANDS
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+            root: root.clone(),
+            timeout_secs: DEFAULT_COMMAND_TIMEOUT_SECS,
+            ..ShellToolConfig::default()
+        };
+        Self {
+            llm,
+            model: model.into(),
+            mapper: RepoMapper::new().with_root(root.clone()),
+            tools: tools(&root, shell),
+            root,
+            approver: None,
+            max_iterations: DEFAULT_TEST_ITERATIONS,
+        }
+    }
+
+    /// Replaces the shell settings (allowlist, timeout, output cap); commands still run under
+    /// the agent's root.
+    pub fn with_shell(mut self, config: ShellToolConfig) -> Self {
+        let config = ShellToolConfig {
+            root: self.root.clone(),
+            ..config
+        };
+        self.tools = tools(&self.root, config);
+        self
+    }
+
+    /// Reviews every write before it happens.
+    pub fn with_tool_approver(mut self, approver: Box<dyn ToolApprover>) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Write-and-run rounds of [`generate_tests`](Self::generate_tests) (default
+    /// [`DEFAULT_TEST_ITERATIONS`], at least 1).
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations.max(1);
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Text of `path` (relative to the root), or `None` when it does not exist.
+    pub async fn read_file(&self, path: &str) -> Result<Option<String>, KowalskiError> {
+        if !self.root.join(path).is_file() {
+            return Ok(None);
+        }
+        let output = self
+            .call("fs_tool", json!({"task": "read_file", "path": path}))
+            .await?;
+        Ok(output.result["content"].as_str().map(str::to_string))
+    }
+
+    /// Replaces `path` (relative to the root) with `content`, once the approver allows it.
+    /// Returns the path written, which the approver may have changed.
+    pub async fn write_file(&self, path: &str, content: &str) -> Result<String, KowalskiError> {
+        let output = self
+            .call(
+                "fs_tool",
+                json!({"task": "write_file", "path": path, "content": content}),
+            )
+            .await?;
+        Ok(output.result["path"].as_str().unwrap_or(path).to_string())
+    }
+
+    /// Runs an allowlisted `command` in `cwd` (relative to the root); the output has `stdout`,
+    /// `stderr`, `exit_code` and `success`.
+    pub async fn run_command(
+        &self,
+        command: &str,
+        args: &[String],
+        cwd: &str,
+    ) -> Result<ToolOutput, KowalskiError> {
+        self.call(
+            "shell",
+            json!({"command": command, "args": args, "cwd": cwd}),
+        )
+        .await
+    }
+
+    /// One tool call through the approval policy.
+    async fn call(&self, name: &str, parameters: Value) -> Result<ToolOutput, KowalskiError> {
+        let call = ToolCall {
+            name: name.to_string(),
+            parameters,
+            reasoning: None,
+        };
+        let destructive = self
+            .tools
+            .is_destructive(&call.name, &call.parameters)
+            .await;
+        let call = if destructive {
+            review_call(self.approver.as_deref(), call, true)?
+        } else {
+            call
+        };
+        self.tools
+            .execute(&call.name, ToolInput::from_parameters(call.parameters))
+            .await
+    }
+}
+
+fn tools(root: &Path, shell: ShellToolConfig) -> ToolManager {
+    let tools = ToolManager::new();
+    tools.register(FsTool::new(root).writable());
+    tools.register(ShellTool::new(shell));
+    tools
+}
@@ -0,0 +1,563 @@
+//! Test generation ([`CodeAgent::generate_tests`]): show the model the repo map and the source
+//! of a file or function, write the test file it replies with, run it, and send compiler or test
+//! failures back for another attempt until the tests pass or the rounds run out.
+
+use super::CodeAgent;
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::tools::repo_map::DEFAULT_MAP_TOKENS;
+use log::{info, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Default write-and-run rounds of [`CodeAgent::generate_tests`].
+pub const DEFAULT_TEST_ITERATIONS: usize = 3;
+
+/// Runner output passed back to the model after a failed round, in characters (the end is
+/// kept, where the failures are).
+const MAX_FEEDBACK_CHARS: usize = 6000;
+
+/// Lines of unchanged context around the change in the report diff.
+const DIFF_CONTEXT: usize = 3;
+
+const TESTGEN_PROMPT: &str = "You write unit tests. Given a source file, the project layout and the path of the test file to write, reply with the complete test file in one fenced code block and nothing else. Test observable behaviour, including edge cases, and only use what the source shows exists.";
+
+static CODE_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)```[\w+-]*[ \t]*\n(.*?)```").expect("CODE_BLOCK regex"));
+
+static CARGO_RESULT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"test result: \w+\. (\d+) passed; (\d+) failed").expect("CARGO_RESULT regex")
+});
+
+static PYTEST_COUNT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+) (passed|failed|error)").expect("PYTEST_COUNT regex"));
+
+/// How the generated tests are run, picked from the source file's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestRunner {
+    /// `cargo test --test <name>` in the crate of a `.rs` file.
+    Cargo,
+    /// `pytest <file>` from the root, for a `.py` file.
+    Pytest,
+}
+
+impl TestRunner {
+    fn for_source(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Self::Cargo),
+            "py" => Some(Self::Pytest),
+            _ => None,
+        }
+    }
+
+    fn language(self) -> &'static str {
+        match self {
+            Self::Cargo => "Rust",
+            Self::Pytest => "Python",
+        }
+    }
+
+    /// Passed and failed test counts in the runner's output.
+    fn counts(self, output: &str) -> (usize, usize) {
+        let mut passed = 0;
+        let mut failed = 0;
+        match self {
+            Self::Cargo => {
+                for caps in CARGO_RESULT.captures_iter(output) {
+                    passed += caps[1].parse::<usize>().unwrap_or(0);
+                    failed += caps[2].parse::<usize>().unwrap_or(0);
+                }
+            }
+            Self::Pytest => {
+                // The summary line: `==== 1 failed, 2 passed in 0.12s ====`.
+                let summary = output
+                    .lines()
+                    .rev()
+                    .find(|line| line.starts_with('=') && line.contains(" in "))
+                    .unwrap_or_default();
+                for caps in PYTEST_COUNT.captures_iter(summary) {
+                    let count = caps[1].parse::<usize>().unwrap_or(0);
+                    match &caps[2] {
+                        "passed" => passed += count,
+                        _ => failed += count,
+                    }
+                }
+            }
+        }
+        (passed, failed)
+    }
+}
+
+/// One write-and-run round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestRun {
+    /// 1-based.
+    pub iteration: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// The runner exited successfully (a build error fails the round with no tests counted).
+    pub success: bool,
+}
+
+/// Outcome of [`CodeAgent::generate_tests`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestGenerationReport {
+    /// The file or function the tests are for, as requested.
+    pub target: String,
+    pub runner: TestRunner,
+    /// Test files written, relative to the root.
+    pub test_files: Vec<String>,
+    /// Counts of the last round.
+    pub passed: usize,
+    pub failed: usize,
+    /// The last round's tests all passed.
+    pub success: bool,
+    pub runs: Vec<TestRun>,
+    /// Unified diff of the test files against their state before the workflow.
+    pub diff: String,
+}
+
+/// Where the tests go and how they are run.
+struct TestPlan {
+    runner: TestRunner,
+    source: String,
+    function: Option<String>,
+    test_file: String,
+    /// Working directory of the runner, relative to the root.
+    cwd: String,
+    /// `package.name` of the crate, for `use` paths.
+    crate_name: Option<String>,
+}
+
+impl TestPlan {
+    fn runner_command(&self) -> &'static str {
+        match self.runner {
+            TestRunner::Cargo => "cargo",
+            TestRunner::Pytest => "pytest",
+        }
+    }
+
+    fn args(&self) -> Vec<String> {
+        let stem = Path::new(&self.test_file)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match self.runner {
+            TestRunner::Cargo => vec!["test".into(), "--test".into(), stem],
+            TestRunner::Pytest => vec![self.test_file.clone()],
+        }
+    }
+}
+
+impl CodeAgent {
+    /// Generates unit tests for `target`, a file (`src/lib.rs`) or a function in one
+    /// (`src/lib.rs::add`), relative to the root.
+    ///
+    /// Rust tests go to `tests/<stem>_tests.rs` of the file's crate and run with `cargo test`;
+    /// Python tests go to `tests/test_<stem>.py` and run with `pytest`. Both commands must be on
+    /// the shell allowlist. A round whose build or tests fail sends the runner output back to the
+    /// model, for at most [`with_max_iterations`](Self::with_max_iterations) rounds. A denied
+    /// write ends the workflow with `PermissionDenied`.
+    pub async fn generate_tests(
+        &self,
+        target: &str,
+    ) -> Result<TestGenerationReport, KowalskiError> {
+        let plan = self.plan_tests(target)?;
+        let source_text = self.read_file(&plan.source).await?.ok_or_else(|| {
+            KowalskiError::ToolInvalidInput(format!("'{}' is not a file", plan.source))
+        })?;
+        let original = self.read_file(&plan.test_file).await?;
+
+        let mut messages = vec![
+            message("system", TESTGEN_PROMPT),
+            message(
+                "user",
+                self.test_request(&plan, &source_text, original.as_deref()),
+            ),
+        ];
+        let mut runs = Vec::new();
+        let mut current = original.clone();
+        let mut written = None;
+        for iteration in 1..=self.max_iterations {
+            let reply = self.llm.chat(&self.model, &messages).await?;
+            let code = code_block(&reply);
+            if code.trim().is_empty() {
+                warn!("generate_tests: round {iteration} reply had no code, stopping");
+                break;
+            }
+            let path = self.write_file(&plan.test_file, &code).await?;
+            current = self.read_file(&path).await?;
+            written = Some(path);
+
+            let output = self
+                .run_command(plan.runner_command(), &plan.args(), &plan.cwd)
+                .await?;
+            let stdout = output.result["stdout"].as_str().unwrap_or_default();
+            let stderr = output.result["stderr"].as_str().unwrap_or_default();
+            let (passed, failed) = plan.runner.counts(&format!("{stdout}\n{stderr}"));
+            let success = output.result["success"].as_bool().unwrap_or(false);
+            info!("generate_tests: round {iteration}: {passed} passed, {failed} failed");
+            runs.push(TestRun {
+                iteration,
+                passed,
+                failed,
+                success,
+            });
+            if success {
+                break;
+            }
+            messages.push(message("assistant", reply));
+            messages.push(message(
+                "user",
+                format!(
+                    "The tests did not pass ({passed} passed, {failed} failed). Fix the test file; if a test expects the wrong result, correct the test, not the source. Runner output:\n\n{}",
+                    tail_chars(&format!("{stdout}\n{stderr}"), MAX_FEEDBACK_CHARS)
+                ),
+            ));
+        }
+
+        let last = runs.last().cloned();
+        let test_files: Vec<String> = written.into_iter().collect();
+        let diff = match (test_files.first(), &current) {
+            (Some(path), Some(new)) => unified_diff(path, original.as_deref(), new),
+            _ => String::new(),
+        };
+        Ok(TestGenerationReport {
+            target: target.to_string(),
+            runner: plan.runner,
+            test_files,
+            passed: last.as_ref().map_or(0, |r| r.passed),
+            failed: last.as_ref().map_or(0, |r| r.failed),
+            success: last.is_some_and(|r| r.success),
+            runs,
+            diff,
+        })
+    }
+
+    fn plan_tests(&self, target: &str) -> Result<TestPlan, KowalskiError> {
+        let (source, function) = match target.split_once("::") {
+            Some((file, function)) => (file.trim(), Some(function.trim().to_string())),
+            None => (target.trim(), None),
+        };
+        let source_path = Path::new(source);
+        let runner = TestRunner::for_source(source_path).ok_or_else(|| {
+            KowalskiError::ToolInvalidInput(format!(
+                "no test runner for '{source}' (expected a .rs or .py file)"
+            ))
+        })?;
+        let stem = source_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match &function {
+            Some(function) => format!("{stem}_{function}"),
+            None => stem,
+        };
+        Ok(match runner {
+            TestRunner::Cargo => {
+                let crate_dir = source_path
+                    .ancestors()
+                    .skip(1)
+                    .find(|dir| self.root.join(dir).join("Cargo.toml").is_file())
+                    .ok_or_else(|| {
+                        KowalskiError::ToolInvalidInput(format!(
+                            "'{source}' is not in a crate under the root"
+                        ))
+                    })?;
+                let crate_name =
+                    std::fs::read_to_string(self.root.join(crate_dir).join("Cargo.toml"))
+                        .ok()
+                        .and_then(|text| text.parse::<toml::Table>().ok())
+                        .and_then(|manifest| {
+                            Some(
+                                manifest
+                                    .get("package")?
+                                    .get("name")?
+                                    .as_str()?
+                                    .replace('-', "_"),
+                            )
+                        });
+                TestPlan {
+                    runner,
+                    source: source.to_string(),
+                    function,
+                    test_file: join(crate_dir, &format!("tests/{name}_tests.rs")),
+                    cwd: join(crate_dir, "."),
+                    crate_name,
+                }
+            }
+            TestRunner::Pytest => TestPlan {
+                runner,
+                source: source.to_string(),
+                function,
+                test_file: format!("tests/test_{name}.py"),
+                cwd: ".".to_string(),
+                crate_name: None,
+            },
+        })
+    }
+
+    /// The first prompt: project layout around the source, the source, and where the tests go.
+    fn test_request(&self, plan: &TestPlan, source_text: &str, existing: Option<&str>) -> String {
+        let dir = Path::new(&plan.source)
+            .parent()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let layout = self.mapper.map().render(&dir, DEFAULT_MAP_TOKENS);
+        let mut request = format!(
+            "Write {} unit tests for {} in {}.\n",
+            plan.runner.language(),
+            match &plan.function {
+                Some(function) => format!("the function `{function}`"),
+                None => "the public items".to_string(),
+            },
+            plan.source
+        );
+        if let Some(crate_name) = &plan.crate_name {
+            request.push_str(&format!(
+                "The tests are an integration test of the crate `{crate_name}`: import items with `use {crate_name}::...`.\n"
+            ));
+        }
+        request.push_str(&format!(
+            "Test file: {}\n\nProject layout of {}/:\n{layout}\n\n{}:\n```\n{source_text}\n```\n",
+            plan.test_file,
+            if dir.is_empty() { "." } else { &dir },
+            plan.source
+        ));
+        if let Some(existing) = existing {
+            request.push_str(&format!(
+                "\nThe test file exists; reply with all of it, keeping its current tests:\n```\n{existing}\n```\n"
+            ));
+        }
+        request
+    }
+}
+
+/// `dir/path` for a root-relative `dir`, which is empty for the root itself.
+fn join(dir: &Path, path: &str) -> String {
+    if dir.as_os_str().is_empty() {
+        return path.to_string();
+    }
+    match path {
+        "." => dir.to_string_lossy().into_owned(),
+        _ => format!("{}/{path}", dir.to_string_lossy()),
+    }
+}
+
+fn message(role: &str, content: impl Into<String>) -> Message {
+    Message {
+        role: role.to_string(),
+        content: content.into(),
+        tool_calls: None,
+        images: None,
+        tool_call_id: None,
+        tool_name: None,
+    }
+}
+
+/// The first fenced block of `reply`, or the whole reply when it has none.
+fn code_block(reply: &str) -> String {
+    let code = match CODE_BLOCK.captures(reply).and_then(|caps| caps.get(1)) {
+        Some(code) => code.as_str(),
+        None => reply.trim(),
+    };
+    let mut code = code.trim_end().to_string();
+    code.push('\n');
+    code
+}
+
+fn tail_chars(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.trim().to_string();
+    }
+    let start = text
+        .char_indices()
+        .nth(count - max)
+        .map_or(0, |(index, _)| index);
+    format!("…{}", text[start..].trim_end())
+}
+
+/// A one-hunk unified diff from `old` (`None` for a new file) to `new`, or an empty string when
+/// they are the same.
+fn unified_diff(path: &str, old: Option<&str>, new: &str) -> String {
+    let old_lines: Vec<&str> = old.map(|o| o.lines().collect()).unwrap_or_default();
+    let new_lines: Vec<&str> = new.lines().collect();
+    if old.is_some() && old_lines == new_lines {
+        return String::new();
+    }
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let start = prefix.saturating_sub(DIFF_CONTEXT);
+    let old_end = old_lines.len() - suffix + suffix.min(DIFF_CONTEXT);
+    let new_end = new_lines.len() - suffix + suffix.min(DIFF_CONTEXT);
+    let range = |start: usize, end: usize| {
+        let len = end - start;
+        let first = if len == 0 { start } else { start + 1 };
+        format!("{first},{len}")
+    };
+
+    let mut diff = match old {
+        Some(_) => format!("--- a/{path}\n+++ b/{path}\n"),
+        None => format!("--- /dev/null\n+++ b/{path}\n"),
+    };
+    diff.push_str(&format!(
+        "@@ -{} +{} @@\n",
+        range(start, old_end),
+        range(start, new_end)
+    ));
+    for line in &old_lines[start..prefix] {
+        diff.push_str(&format!(" {line}\n"));
+    }
+    for line in &old_lines[prefix..old_lines.len() - suffix] {
+        diff.push_str(&format!("-{line}\n"));
+    }
+    for line in &new_lines[prefix..new_lines.len() - suffix] {
+        diff.push_str(&format!("+{line}\n"));
+    }
+    for line in &old_lines[old_lines.len() - suffix..old_end] {
+        diff.push_str(&format!(" {line}\n"));
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::approval::ToolApproval;
+    use crate::testing::MockBackend;
+    use crate::tools::ToolCall;
+    use std::sync::{Arc, Mutex};
+
+    const FAILING: &str = "Here are the tests:\n```rust\nuse testgen_fixture::add;\n\n#[test]\nfn adds_small_numbers() {\n    assert_eq!(add(2, 2), 5);\n}\n```\n";
+
+    const PASSING: &str = "```rust\nuse testgen_fixture::add;\n\n#[test]\nfn adds_small_numbers() {\n    assert_eq!(add(2, 2), 4);\n}\n\n#[test]\nfn wraps_on_overflow() {\n    assert_eq!(add(u32::MAX, 1), 0);\n}\n```";
+
+    /// A copy of `tests/fixtures/testgen`, a crate with one pure function.
+    fn fixture() -> tempfile::TempDir {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/testgen");
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        for file in ["Cargo.toml", "src/lib.rs"] {
+            std::fs::copy(fixture.join(file), dir.path().join(file)).unwrap();
+        }
+        dir
+    }
+
+    #[tokio::test]
+    async fn failing_tests_are_fed_back_until_they_pass() {
+        let dir = fixture();
+        let llm = Arc::new(
+            MockBackend::script()
+                .responds_with_text(FAILING)
+                .then_text(PASSING)
+                .build(),
+        );
+        let reviewed = Arc::new(Mutex::new(Vec::new()));
+        let seen = reviewed.clone();
+        let agent = CodeAgent::new(llm.clone(), "m1", dir.path()).with_tool_approver(Box::new(
+            move |call: &ToolCall| {
+                seen.lock()
+                    .unwrap()
+                    .push(call.parameters["path"].to_string());
+                ToolApproval::Approve
+            },
+        ));
+
+        let report = agent.generate_tests("src/lib.rs::add").await.unwrap();
+        assert_eq!(report.test_files, ["tests/lib_add_tests.rs"]);
+        assert_eq!(report.runs.len(), 2);
+        assert_eq!((report.runs[0].passed, report.runs[0].failed), (0, 1));
+        assert!(!report.runs[0].success);
+        assert_eq!((report.passed, report.failed), (2, 0));
+        assert!(report.success);
+        assert_eq!(reviewed.lock().unwrap().len(), 2);
+        assert!(
+            report
+                .diff
+                .starts_with("--- /dev/null\n+++ b/tests/lib_add_tests.rs\n@@ -0,0 +1,11 @@\n"),
+            "{}",
+            report.diff
+        );
+        assert!(
+            report
+                .diff
+                .contains("+    assert_eq!(add(u32::MAX, 1), 0);")
+        );
+        assert!(
+            std::fs::read_to_string(dir.path().join("tests/lib_add_tests.rs"))
+                .unwrap()
+                .contains("wraps_on_overflow")
+        );
+
+        let requests = llm.requests();
+        assert!(
+            requests[0].messages[1]
+                .content
+                .contains("use testgen_fixture::...")
+        );
+        assert!(
+            requests[0].messages[1]
+                .content
+                .contains("Project layout of src/:\nlib.rs: fn add"),
+            "{}",
+            requests[0].messages[1].content
+        );
+        let feedback = &requests[1].messages.last().unwrap().content;
+        assert!(feedback.contains("0 passed, 1 failed"), "{feedback}");
+        assert!(feedback.contains("adds_small_numbers"), "{feedback}");
+    }
+
+    #[tokio::test]
+    async fn writes_need_an_approving_approver() {
+        let dir = fixture();
+        let llm = Arc::new(
+            MockBackend::script()
+                .responds_with_text(PASSING)
+                .then_text(PASSING)
+                .build(),
+        );
+        let err = CodeAgent::new(llm.clone(), "m1", dir.path())
+            .generate_tests("src/lib.rs")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)), "{err}");
+
+        let err = CodeAgent::new(llm, "m1", dir.path())
+            .with_tool_approver(Box::new(|_: &ToolCall| {
+                ToolApproval::Deny(Some("not now".to_string()))
+            }))
+            .generate_tests("src/lib.rs")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not now"), "{err}");
+        assert!(!dir.path().join("tests").exists());
+    }
+
+    #[test]
+    fn diffs_keep_context_around_the_change() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\ni\n";
+        assert_eq!(
+            unified_diff("t.rs", Some(old), new),
+            "--- a/t.rs\n+++ b/t.rs\n@@ -2,7 +2,8 @@\n b\n c\n d\n-e\n-f\n-g\n-h\n+E\n+f\n+g\n+h\n+i\n"
+        );
+        assert_eq!(unified_diff("t.rs", Some(old), old), "");
+        assert_eq!(
+            TestRunner::Pytest.counts("===== 1 failed, 2 passed in 0.12s =====\n"),
+            (2, 1)
+        );
+    }
+}
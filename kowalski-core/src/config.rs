@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 /// Core configuration for the Kowalski system
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,18 +12,61 @@ pub struct Config {
     pub chat: ChatConfig,
     /// Memory configuration
     pub memory: MemoryConfig,
+    /// Capacity of the working memory agents create from this config, which is also how many
+    /// recent messages of a conversation each chat request carries (system messages and pinned
+    /// memories always go along).
+    pub working_memory_capacity: usize,
     /// Maximum number of memories to retrieve from working memory
     pub working_memory_retrieval_limit: usize,
     /// Maximum number of memories to retrieve from episodic memory
     pub episodic_memory_retrieval_limit: usize,
     /// Maximum number of memories to retrieve from semantic memory
     pub semantic_memory_retrieval_limit: usize,
+    /// Most memories injected into one chat request, across all tiers (each tier's limit is also
+    /// capped by this)
+    pub memory_recall_limit: usize,
+    /// Most characters of memory injected into one chat request; the last memory that fits is
+    /// cut short
+    pub memory_recall_max_chars: usize,
+    /// Where Kowalski keeps its data (episodic store, caches, saved conversations); relative
+    /// store paths such as [`MemoryConfig::episodic_path`] live under it. Defaults to
+    /// [`default_data_dir`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
     /// LLM configuration (new)
     #[serde(default)]
     pub llm: LLMConfig,
     /// MCP configuration
     #[serde(default)]
     pub mcp: McpConfig,
+    /// Federation (remote agents) configuration
+    #[serde(default)]
+    pub federation: FederationConfig,
+    /// Embedding calls (memory storage and retrieval)
+    #[serde(default)]
+    pub embedding: EmbeddingConfig,
+    /// Summarization calls made during memory consolidation
+    #[serde(default)]
+    pub summarization: SummarizationConfig,
+    /// Overrides for the built-in prompt templates
+    #[serde(default)]
+    pub prompts: PromptsConfig,
+    /// Named roles (`[roles.<key>]`), selectable alongside the built-ins of
+    /// [`RoleCatalog`](crate::role::RoleCatalog)
+    #[serde(default)]
+    pub roles: HashMap<String, crate::role::Role>,
+    /// Tracing export (`[observability]`)
+    #[serde(default)]
+    pub observability: ObservabilityConfig,
+    /// Web scraping settings (`[web]`)
+    #[serde(default)]
+    pub web: WebAgentConfig,
+    /// Chart rendering (`[charts]`)
+    #[serde(default)]
+    pub charts: ChartConfig,
+    /// Image description by a multimodal model (`[vision]`)
+    #[serde(default)]
+    pub vision: VisionConfig,
     /// Additional configurations from other agents
     #[serde(flatten)]
     pub additional: HashMap<String, serde_json::Value>,
@@ -31,7 +75,8 @@ pub struct Config {
 /// Configuration for generic LLM settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMConfig {
-    /// The provider to use: `ollama` (local) or `openai` (Chat Completions API — OpenAI or compatible).
+    /// The provider to use: `ollama` (local) or `openai` / `openai_compat` (Chat Completions API —
+    /// OpenAI, vLLM, llama.cpp server, LM Studio, …).
     pub provider: String,
     /// API key for `openai` provider (OpenAI, Groq, etc.). Many servers omit this; use `""` in TOML if needed.
     pub openai_api_key: Option<String>,
@@ -39,6 +84,19 @@ pub struct LLMConfig {
     /// `http://127.0.0.1:1234/v1` for LM Studio). If unset, the official OpenAI API base is used.
     #[serde(default)]
     pub openai_api_base: Option<String>,
+    /// Renames models for OpenAI-compatible servers, e.g. `llama3.2 = "meta-llama/Llama-3.2-3B-Instruct"`.
+    #[serde(default)]
+    pub model_map: HashMap<String, String>,
+    /// Most requests (chat, embeddings, streams) sent to the backend at once; unset = unlimited.
+    /// Shared by every agent in the process that talks to the same endpoint.
+    #[serde(default)]
+    pub max_in_flight: Option<usize>,
+    /// Most requests started per second; unset = unlimited.
+    #[serde(default)]
+    pub requests_per_second: Option<f32>,
+    /// Response cache for deterministic calls (`[llm.cache]`)
+    #[serde(default)]
+    pub cache: LlmCacheConfig,
 }
 
 impl Default for LLMConfig {
@@ -47,6 +105,40 @@ impl Default for LLMConfig {
             provider: "ollama".to_string(),
             openai_api_key: std::env::var("OPENAI_API_KEY").ok(),
             openai_api_base: None,
+            model_map: HashMap::new(),
+            max_in_flight: None,
+            requests_per_second: None,
+            cache: LlmCacheConfig::default(),
+        }
+    }
+}
+
+/// Response cache for temperature-0 chat calls and embeddings (`[llm.cache]`); see
+/// [`LlmCache`](crate::llm::LlmCache). Calls opt in with
+/// [`ChatOptions::cache`](crate::llm::ChatOptions::cache) even when `enabled` is false.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmCacheConfig {
+    /// Cache every eligible call unless it sets `cache: false`
+    pub enabled: bool,
+    /// SQLite file (or directory, for `llm_cache.sqlite` in it); unset = the episodic SQLite file
+    pub path: Option<String>,
+    /// Seconds an entry stays valid; unset = forever
+    pub ttl_secs: Option<u64>,
+    /// Most entries kept; the oldest are evicted first
+    pub max_entries: usize,
+    /// Larger responses are not stored
+    pub max_entry_bytes: usize,
+}
+
+impl Default for LlmCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            ttl_secs: Some(7 * 24 * 3600),
+            max_entries: 10_000,
+            max_entry_bytes: 1024 * 1024,
         }
     }
 }
@@ -61,6 +153,8 @@ pub struct OllamaConfig {
     pub port: u16,
     /// The model to use
     pub model: String,
+    /// Request throttling for this Ollama endpoint (`[ollama.limits]`)
+    pub limits: OllamaLimits,
     /// Additional Ollama-specific settings
     #[serde(flatten)]
     pub additional: HashMap<String, serde_json::Value>,
@@ -72,11 +166,53 @@ impl Default for OllamaConfig {
             host: "localhost".to_string(),
             port: 11434,
             model: "llama3.2".to_string(), //llama3.2 //deepseek-r1:1.5b
+            limits: OllamaLimits::default(),
             additional: HashMap::new(),
         }
     }
 }
 
+/// Throttling for requests to an Ollama endpoint (`[ollama.limits]`); unset = unlimited. Every
+/// agent in the process talking to the same host and port shares one
+/// [`RequestGovernor`](crate::llm::RequestGovernor). Fields set here take precedence over the
+/// `[llm]` ones.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OllamaLimits {
+    /// Most requests (chat, embeddings, streams, model operations) in flight at once
+    pub max_concurrent: Option<usize>,
+    /// Token bucket refill rate
+    pub requests_per_minute: Option<u32>,
+    /// Token bucket size: requests that may start back to back before the rate applies
+    /// (default 1)
+    pub burst: Option<u32>,
+}
+
+/// Ollama generation settings (`[chat.options]`); unset fields keep the model's defaults. All
+/// but `keep_alive` go to the request's `options`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OllamaOptions {
+    /// Context window in tokens; Ollama's default is small and silently drops the start of
+    /// longer prompts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    /// Fixed sampling seed, for reproducible replies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// Sequences that end the reply
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    /// How long the model stays loaded after a request: a duration such as `"10m"`, `"0"` to
+    /// unload at once, or a negative one (`"-1m"`) to keep it loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<String>,
+}
+
 /// Configuration for chat functionality
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -90,6 +226,23 @@ pub struct ChatConfig {
     pub temperature: f32,
     /// Maximum number of tokens in generated responses
     pub max_tokens: u32,
+    /// Ask the backend for JSON-only output (Ollama `format: "json"`) on turns where tools are
+    /// registered, so tool calls parse reliably. Turns without tools stay free-form.
+    pub json_tool_calls: bool,
+    /// How many times [`Agent::chat_structured`](crate::agent::Agent::chat_structured) asks again
+    /// when a reply does not match the schema (so `retries + 1` attempts in all)
+    pub structured_output_retries: u32,
+    /// Wrap tool results in an untrusted-data block before they reach the conversation (see
+    /// [`untrusted_tool_output`](crate::tools::untrusted_tool_output)), so scraped pages and
+    /// files are less likely to be taken as instructions.
+    pub guard_tool_output: bool,
+    /// System prompt rendered at the start of each conversation, with `{agent_name}`, `{date}`
+    /// and `{tools}` filled in (see [`SystemPromptTemplate`](crate::agent::prompt::SystemPromptTemplate)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt_template: Option<String>,
+    /// Ollama `options` and `keep_alive` sent with every chat request (`[chat.options]`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<OllamaOptions>,
     /// Additional chat-specific settings
     #[serde(flatten)]
     pub additional: HashMap<String, serde_json::Value>,
@@ -102,19 +255,187 @@ impl Default for ChatConfig {
             enable_streaming: true,
             temperature: 0.7,
             max_tokens: 2048,
+            json_tool_calls: false,
+            structured_output_retries: 2,
+            guard_tool_output: false,
+            system_prompt_template: None,
+            options: None,
             additional: HashMap::new(),
         }
     }
 }
 
+/// Configuration for embedding requests (`[embedding]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingConfig {
+    /// Embedding model; when unset the provider default is used (`nomic-embed-text` for Ollama,
+    /// `text-embedding-3-small` for OpenAI). Must match [`MemoryConfig::embedding_vector_dimensions`].
+    pub model: Option<String>,
+}
+
+/// Configuration for consolidation summaries (`[summarization]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummarizationConfig {
+    /// Model for summaries; when unset the consolidation caller's chat model is used
+    pub model: Option<String>,
+    /// Temperature for summaries (kept low so facts are not embellished)
+    pub temperature: f32,
+    /// Maximum number of tokens per summary
+    pub max_tokens: u32,
+}
+
+impl Default for SummarizationConfig {
+    fn default() -> Self {
+        Self {
+            model: None,
+            temperature: 0.2,
+            max_tokens: 512,
+        }
+    }
+}
+
+/// Image description (`[vision]`): the multimodal Ollama model `describe_image` sends images to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VisionConfig {
+    /// A model that accepts images, e.g. `llava` or `llama3.2-vision`
+    pub model: String,
+    /// Instruction sent with the image when the call asks no question of its own
+    pub prompt: String,
+    /// Largest image accepted, in bytes before base64
+    pub max_image_bytes: u64,
+}
+
+impl Default for VisionConfig {
+    fn default() -> Self {
+        Self {
+            model: "llava".to_string(),
+            prompt: "Describe this image in detail. Transcribe any visible text, and for \
+                     diagrams or charts explain what they show."
+                .to_string(),
+            max_image_bytes: crate::conversation::MAX_IMAGE_BYTES,
+        }
+    }
+}
+
+/// Prompt template overrides (`[prompts]`), applied over the built-ins by
+/// [`PromptRegistry`](crate::prompts::PromptRegistry).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptsConfig {
+    /// Directory of `<name>.txt` templates, e.g. `tool_result.txt`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    /// Inline templates by name (`system`, `tool_use`, `tool_result`, `memory_context`,
+    /// `consolidation_summary`, `consolidation_graph`); these win over `dir`
+    #[serde(flatten)]
+    pub templates: HashMap<String, String>,
+}
+
+/// Configuration for exporting tracing spans (`[observability]`); see
+/// [`logging::init_with_config`](crate::logging::init_with_config).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObservabilityConfig {
+    /// OTLP/HTTP collector, e.g. `http://localhost:4318` (spans go to `/v1/traces`). Unset means
+    /// spans are only logged. Requires `kowalski-core` **`--features otel`**.
+    pub otlp_endpoint: Option<String>,
+    /// `service.name` reported with every span
+    pub service_name: String,
+    /// Fraction of new traces exported (0.0-1.0)
+    pub sample_ratio: f64,
+}
+
+impl Default for ObservabilityConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "kowalski".to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+/// Web scraping settings (`[web]`), used by [`HttpFetcher::with_profiles`](crate::web::HttpFetcher::with_profiles)
+/// and [`ImageTool::from_config`](crate::tools::ImageTool::from_config).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebAgentConfig {
+    /// Named request settings (`[[web.profiles]]`) that `web_scrape` calls pick with `profile`
+    pub profiles: Vec<HttpProfile>,
+    /// Let `web_scrape`, research and `describe_image` fetch loopback, private and link-local
+    /// addresses (default: public addresses only)
+    pub allow_private_addresses: bool,
+}
+
+/// Headers, cookies and credentials sent with the requests made under this profile to its
+/// `hosts`; other URLs are fetched without them. Secrets are never written here: header and cookie values may be
+/// `env:VAR` references, and credentials name the environment variable that holds them. Cookies
+/// set by responses are kept in the profile's own jar for the rest of the session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpProfile {
+    pub name: String,
+    /// Hosts the profile's headers, cookies and credentials may be sent to (required). An entry
+    /// covers the host and its subdomains on any port, or only the given port
+    /// (`intranet.example.com`, `localhost:8080`). Fetches elsewhere carry none of them.
+    pub hosts: Vec<String>,
+    /// Extra request headers; a value `env:VAR` is read from the environment
+    pub headers: HashMap<String, String>,
+    /// Cookies sent from the first request on; a value `env:VAR` is read from the environment
+    pub cookies: HashMap<String, String>,
+    pub basic_auth: Option<BasicAuthConfig>,
+    /// Environment variable holding a bearer token for `Authorization: Bearer …`
+    pub bearer_token_env: Option<String>,
+}
+
+/// HTTP basic authentication; the password comes from the environment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BasicAuthConfig {
+    pub username: String,
+    /// Environment variable holding the password
+    pub password_env: String,
+}
+
+/// Where and how big `chart` renders images (`[charts]`); needs a build with
+/// `kowalski-core` **`--features charts`**.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChartConfig {
+    /// Directory chart files are written to, under the tool root; created on first use
+    pub output_dir: String,
+    /// Default image size in pixels; a call may override it
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ChartConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: "charts".to_string(),
+            width: 800,
+            height: 600,
+        }
+    }
+}
+
 fn default_embedding_vector_dimensions() -> usize {
     768
 }
 
+fn default_dedup_window() -> usize {
+    200
+}
+
+fn default_rerank_candidates() -> usize {
+    20
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct MemoryConfig {
-    /// **Default Tier-2 episodic store:** embedded **SQLite** file under this path (`episodic.sqlite` in the directory, or a path ending in `.sqlite`/`.db`). Used when [`Self::database_url`] is unset or does not request PostgreSQL.
+    /// **Default Tier-2 episodic store:** embedded **SQLite** file under this path (`episodic.sqlite` in the directory, or a path ending in `.sqlite`/`.db`). Used when [`Self::database_url`] is unset or does not request PostgreSQL. A relative path is under the data directory ([`Config::data_dir`]); the default is `episodic`.
     pub episodic_path: String,
     /// Optional: set to **`postgres://…`** / **`postgresql://…`** to use PostgreSQL for Tier 2 (`episodic_kv`) and Tier 3 semantic SQL (**requires** `kowalski-core` **`--features postgres`**). If omitted, Tier 2 stays on **SQLite** ([`Self::episodic_path`]) — the default.
     #[serde(default)]
@@ -122,6 +443,28 @@ pub struct MemoryConfig {
     /// Embedding width for **PostgreSQL** `semantic_memory.embedding` (`vector(N)`). Must match your embedder (e.g. **768** for Ollama `nomic-embed-text`) and the dimension in `migrations/postgres/003_semantic_memory.sql`.
     #[serde(default = "default_embedding_vector_dimensions")]
     pub embedding_vector_dimensions: usize,
+    /// Cosine similarity (0–1) at or above which a new memory counts as a near-duplicate of a
+    /// recent one: the existing unit's timestamp is refreshed instead of storing another copy.
+    /// `None` (the default) stores everything.
+    #[serde(default)]
+    pub dedup_threshold: Option<f32>,
+    /// How many of the most recent units a new memory is compared against when deduplicating.
+    #[serde(default = "default_dedup_window")]
+    pub dedup_window: usize,
+    /// Ask the model to reorder episodic retrieval results by relevance to the query before they
+    /// are used. Costs one extra LLM call per retrieval, so it is off by default.
+    #[serde(default)]
+    pub rerank: bool,
+    /// How many of the best cosine + recency matches the reranker scores.
+    #[serde(default = "default_rerank_candidates")]
+    pub rerank_candidates: usize,
+    /// Model that scores the candidates; the chat model when unset.
+    #[serde(default)]
+    pub rerank_model: Option<String>,
+    /// Summarize a conversation into episodic and semantic memory when it is closed
+    /// ([`crate::agent::Agent::close_conversation`]). Costs one LLM call per conversation.
+    #[serde(default)]
+    pub summarize_on_close: bool,
     #[serde(flatten)]
     pub additional: HashMap<String, serde_json::Value>,
 }
@@ -129,9 +472,15 @@ pub struct MemoryConfig {
 impl Default for MemoryConfig {
     fn default() -> Self {
         Self {
-            episodic_path: "../target/episodic_db".to_string(), //just for testing!
+            episodic_path: "episodic".to_string(),
             database_url: None,
             embedding_vector_dimensions: default_embedding_vector_dimensions(),
+            dedup_threshold: None,
+            dedup_window: default_dedup_window(),
+            rerank: false,
+            rerank_candidates: default_rerank_candidates(),
+            rerank_model: None,
+            summarize_on_close: false,
             additional: HashMap::new(),
         }
     }
@@ -167,6 +516,13 @@ pub fn postgres_feature_required_error() -> crate::error::KowalskiError {
     )
 }
 
+/// Build-time `otel` feature was not enabled while config sets an OTLP endpoint.
+pub fn otel_feature_required_error() -> crate::error::KowalskiError {
+    crate::error::KowalskiError::Configuration(
+        "OpenTelemetry export requires building with `--features otel` (e.g. `cargo build -p kowalski-cli --features otel`); unset `[observability] otlp_endpoint` otherwise.".to_string(),
+    )
+}
+
 /// Trait for extending configuration with additional settings
 pub trait ConfigExt {
     /// Get a reference to the core configuration
@@ -197,16 +553,243 @@ impl Default for Config {
             ollama: OllamaConfig::default(),
             llm: LLMConfig::default(),
             mcp: McpConfig::default(),
+            federation: FederationConfig::default(),
+            embedding: EmbeddingConfig::default(),
+            summarization: SummarizationConfig::default(),
+            prompts: PromptsConfig::default(),
+            roles: HashMap::new(),
+            observability: ObservabilityConfig::default(),
+            web: WebAgentConfig::default(),
+            charts: ChartConfig::default(),
+            vision: VisionConfig::default(),
             chat: ChatConfig::default(),
             memory: MemoryConfig::default(),
+            working_memory_capacity: 40,
             working_memory_retrieval_limit: 3,
             episodic_memory_retrieval_limit: 3,
             semantic_memory_retrieval_limit: 3,
+            memory_recall_limit: 9,
+            memory_recall_max_chars: 4000,
+            data_dir: None,
             additional: HashMap::new(),
         }
     }
 }
 
+/// Environment variables that override `config.toml` (env > file > defaults). Empty values are
+/// ignored.
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("KOWALSKI_OLLAMA_HOST", "ollama.host"),
+    ("KOWALSKI_OLLAMA_PORT", "ollama.port"),
+    ("KOWALSKI_MODEL", "ollama.model"),
+    ("KOWALSKI_LLM_PROVIDER", "llm.provider"),
+    ("KOWALSKI_OPENAI_API_BASE", "llm.openai_api_base"),
+    ("KOWALSKI_OPENAI_API_KEY", "llm.openai_api_key"),
+    ("KOWALSKI_DATABASE_URL", "memory.database_url"),
+    ("KOWALSKI_EPISODIC_PATH", "memory.episodic_path"),
+    ("KOWALSKI_DATA_DIR", "data_dir"),
+    ("KOWALSKI_TEMPERATURE", "chat.temperature"),
+    ("KOWALSKI_OTLP_ENDPOINT", "observability.otlp_endpoint"),
+];
+
+impl Config {
+    /// Reads `path` (TOML over defaults; a missing file means defaults), applies the
+    /// `KOWALSKI_*` environment overrides listed in [`ENV_OVERRIDES`], and places relative store
+    /// paths under the data directory ([`resolve_data_paths`](Self::resolve_data_paths)).
+    pub fn load(path: &std::path::Path) -> Result<Self, crate::error::KowalskiError> {
+        let mut config: Self = if path.exists() {
+            let raw = std::fs::read_to_string(path)?;
+            toml::from_str(&raw).map_err(|e| {
+                crate::error::KowalskiError::Configuration(format!("{}: {e}", path.display()))
+            })?
+        } else {
+            Self::default()
+        };
+        config.apply_env()?;
+        config.resolve_data_paths();
+        Ok(config)
+    }
+
+    /// The chat model as the selected provider knows it: `[ollama] model`, renamed through
+    /// `[llm] model_map` when `[llm] provider` is an OpenAI-compatible server.
+    pub fn active_model(&self) -> String {
+        match self.llm.provider.as_str() {
+            "openai" | "openai_compat" => self
+                .llm
+                .model_map
+                .get(&self.ollama.model)
+                .unwrap_or(&self.ollama.model)
+                .clone(),
+            _ => self.ollama.model.clone(),
+        }
+    }
+
+    /// [`Self::data_dir`] when set, else [`default_data_dir`]; `None` when neither is known.
+    pub fn resolved_data_dir(&self) -> Option<PathBuf> {
+        self.data_dir
+            .as_deref()
+            .filter(|d| !d.trim().is_empty())
+            .map(PathBuf::from)
+            .or_else(default_data_dir)
+    }
+
+    /// Records the data directory in [`Self::data_dir`] and makes the relative store paths
+    /// (`memory.episodic_path`, `llm.cache.path`, `federation.persistence_path`) absolute under
+    /// it, so they do not depend on the working directory. Without a data directory (no home
+    /// directory) they stay relative.
+    pub fn resolve_data_paths(&mut self) {
+        let Some(dir) = self.resolved_data_dir() else {
+            log::warn!(
+                "no data directory (set data_dir or KOWALSKI_DATA_DIR); stores stay relative to {}",
+                std::env::current_dir()
+                    .map(|d| d.display().to_string())
+                    .unwrap_or_else(|_| ".".to_string())
+            );
+            return;
+        };
+        let under = |path: &str| -> String {
+            if Path::new(path).is_absolute() {
+                path.to_string()
+            } else {
+                dir.join(path).to_string_lossy().into_owned()
+            }
+        };
+        let episodic_path = under(&self.memory.episodic_path);
+        self.memory.episodic_path = episodic_path;
+        self.llm.cache.path = self.llm.cache.path.as_deref().map(under);
+        self.federation.persistence_path = self.federation.persistence_path.as_deref().map(under);
+        self.data_dir = Some(dir.to_string_lossy().into_owned());
+    }
+
+    /// Applies the `KOWALSKI_*` overrides from the process environment.
+    pub fn apply_env(&mut self) -> Result<(), crate::error::KowalskiError> {
+        self.apply_env_from(|name| std::env::var(name).ok())
+    }
+
+    /// Applies the overrides from `lookup` (a variable name to its value); errors name the
+    /// variable whose value does not parse.
+    pub fn apply_env_from(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), crate::error::KowalskiError> {
+        let var = |name: &str| lookup(name).filter(|v| !v.trim().is_empty());
+        let parse = |name: &str, raw: &str, expected: &str| {
+            crate::error::KowalskiError::Configuration(format!("{name}={raw} is not {expected}"))
+        };
+        if let Some(host) = var("KOWALSKI_OLLAMA_HOST") {
+            self.ollama.host = host;
+        }
+        if let Some(raw) = var("KOWALSKI_OLLAMA_PORT") {
+            self.ollama.port = raw
+                .trim()
+                .parse()
+                .map_err(|_| parse("KOWALSKI_OLLAMA_PORT", &raw, "a port number"))?;
+        }
+        if let Some(model) = var("KOWALSKI_MODEL") {
+            self.ollama.model = model;
+        }
+        if let Some(provider) = var("KOWALSKI_LLM_PROVIDER") {
+            self.llm.provider = provider;
+        }
+        if let Some(base) = var("KOWALSKI_OPENAI_API_BASE") {
+            self.llm.openai_api_base = Some(base);
+        }
+        if let Some(key) = var("KOWALSKI_OPENAI_API_KEY") {
+            self.llm.openai_api_key = Some(key);
+        }
+        if let Some(url) = var("KOWALSKI_DATABASE_URL") {
+            self.memory.database_url = Some(url);
+        }
+        if let Some(path) = var("KOWALSKI_EPISODIC_PATH") {
+            self.memory.episodic_path = path;
+        }
+        if let Some(dir) = var("KOWALSKI_DATA_DIR") {
+            self.data_dir = Some(dir);
+        }
+        if let Some(raw) = var("KOWALSKI_TEMPERATURE") {
+            self.chat.temperature = raw
+                .trim()
+                .parse()
+                .map_err(|_| parse("KOWALSKI_TEMPERATURE", &raw, "a number"))?;
+        }
+        if let Some(endpoint) = var("KOWALSKI_OTLP_ENDPOINT") {
+            self.observability.otlp_endpoint = Some(endpoint);
+        }
+        Ok(())
+    }
+}
+
+/// Kowalski's data directory when the config does not set one: `$KOWALSKI_DATA_DIR`, else
+/// `kowalski` in the platform data directory (`$XDG_DATA_HOME/kowalski`, by default
+/// `~/.local/share/kowalski`, on Linux; `~/Library/Application Support/kowalski` on macOS;
+/// `%APPDATA%\kowalski` on Windows).
+pub fn default_data_dir() -> Option<PathBuf> {
+    match std::env::var_os("KOWALSKI_DATA_DIR").filter(|d| !d.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::data_dir().map(|d| d.join("kowalski")),
+    }
+}
+
+/// `path` as is when absolute, else under [`default_data_dir`] (or relative to the working
+/// directory when there is none). For store paths that did not go through
+/// [`Config::resolve_data_paths`].
+pub(crate) fn data_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    match default_data_dir() {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn default_federation_ws_listen() -> String {
+    "127.0.0.1:7420".to_string()
+}
+
+fn default_federation_heartbeat_secs() -> u64 {
+    15
+}
+
+fn default_federation_unresponsive_after_missed() -> u32 {
+    3
+}
+
+fn default_federation_remove_after_missed() -> u32 {
+    10
+}
+
+/// Configuration for remote federation over WebSocket (`[federation]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationConfig {
+    /// Address the registry node listens on for remote agents
+    #[serde(default = "default_federation_ws_listen")]
+    pub ws_listen: String,
+    /// Seconds between client heartbeats
+    #[serde(default = "default_federation_heartbeat_secs")]
+    pub heartbeat_secs: u64,
+    /// Missed heartbeats before an agent is marked unresponsive (skipped by delegation)
+    #[serde(default = "default_federation_unresponsive_after_missed")]
+    pub unresponsive_after_missed: u32,
+    /// Missed heartbeats before an agent is removed from the registry
+    #[serde(default = "default_federation_remove_after_missed")]
+    pub remove_after_missed: u32,
+    /// Directory (or `.sqlite` file) where the registry keeps registrations and task state
+    /// across restarts; unset keeps everything in memory
+    #[serde(default)]
+    pub persistence_path: Option<String>,
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            ws_listen: default_federation_ws_listen(),
+            heartbeat_secs: default_federation_heartbeat_secs(),
+            unresponsive_after_missed: default_federation_unresponsive_after_missed(),
+            remove_after_missed: default_federation_remove_after_missed(),
+            persistence_path: None,
+        }
+    }
+}
+
 /// Configuration for MCP servers
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct McpConfig {
@@ -229,6 +812,9 @@ pub struct McpServerConfig {
     /// argv for [`McpTransport::Stdio`] (program + args).
     #[serde(default)]
     pub command: Vec<String>,
+    /// Extra environment variables for the [`McpTransport::Stdio`] subprocess.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -241,3 +827,112 @@ pub enum McpTransport {
     /// Subprocess MCP (newline-delimited JSON-RPC on stdin/stdout).
     Stdio,
 }
+
+#[cfg(test)]
+mod env_override_tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn env_beats_file_and_file_beats_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[ollama]\nhost = \"gpu-box\"\nport = 9000\nmodel = \"from-file\"\n\n[observability]\nservice_name = \"kowalski-test\"\n\n[chat.options]\nnum_ctx = 8192\nkeep_alive = \"30m\"\n",
+        )
+        .unwrap();
+        let mut config: Config = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        config
+            .apply_env_from(lookup(&[
+                ("KOWALSKI_OLLAMA_PORT", "7000"),
+                ("KOWALSKI_MODEL", "from-env"),
+                ("KOWALSKI_OLLAMA_HOST", ""),
+                ("KOWALSKI_DATABASE_URL", "postgres://db/kowalski"),
+                ("KOWALSKI_TEMPERATURE", "0.1"),
+                ("KOWALSKI_OTLP_ENDPOINT", "http://collector:4318"),
+            ]))
+            .unwrap();
+        assert_eq!(config.ollama.port, 7000);
+        assert_eq!(config.ollama.model, "from-env");
+        // Empty values are ignored, so the file still wins here.
+        assert_eq!(config.ollama.host, "gpu-box");
+        assert!(memory_uses_postgres(&config.memory));
+        assert_eq!(config.chat.temperature, 0.1);
+        assert_eq!(
+            config.observability.otlp_endpoint.as_deref(),
+            Some("http://collector:4318")
+        );
+        assert_eq!(config.observability.service_name, "kowalski-test");
+        assert_eq!(config.observability.sample_ratio, 1.0);
+        let options = config.chat.options.as_ref().unwrap();
+        assert_eq!(options.num_ctx, Some(8192));
+        assert_eq!(options.keep_alive.as_deref(), Some("30m"));
+        assert!(config.chat.additional.is_empty());
+        // Untouched by file and env.
+        assert_eq!(config.chat.max_tokens, ChatConfig::default().max_tokens);
+
+        let missing = Config::load(&dir.path().join("absent.toml")).unwrap();
+        assert_eq!(missing.ollama.port, OllamaConfig::default().port);
+    }
+
+    #[test]
+    fn active_model_follows_the_selected_provider() {
+        let mut config = Config::default();
+        config.ollama.model = "llama3.2".to_string();
+        config.llm.model_map.insert(
+            "llama3.2".to_string(),
+            "meta-llama/Llama-3.2-3B".to_string(),
+        );
+        assert_eq!(config.active_model(), "llama3.2");
+        config.llm.provider = "openai_compat".to_string();
+        assert_eq!(config.active_model(), "meta-llama/Llama-3.2-3B");
+        config.llm.model_map.clear();
+        assert_eq!(config.active_model(), "llama3.2");
+    }
+
+    #[test]
+    fn relative_store_paths_move_under_the_data_dir() {
+        let mut config = Config::default();
+        config
+            .apply_env_from(lookup(&[("KOWALSKI_DATA_DIR", "/srv/kowalski")]))
+            .unwrap();
+        config.llm.cache.path = Some("cache".to_string());
+        config.federation.persistence_path = Some("/var/lib/federation".to_string());
+        config.resolve_data_paths();
+
+        assert_eq!(config.data_dir.as_deref(), Some("/srv/kowalski"));
+        assert_eq!(config.memory.episodic_path, "/srv/kowalski/episodic");
+        assert_eq!(
+            config.llm.cache.path.as_deref(),
+            Some("/srv/kowalski/cache")
+        );
+        assert_eq!(
+            config.federation.persistence_path.as_deref(),
+            Some("/var/lib/federation")
+        );
+        // Resolving again changes nothing.
+        let resolved = config.memory.episodic_path.clone();
+        config.resolve_data_paths();
+        assert_eq!(config.memory.episodic_path, resolved);
+    }
+
+    #[test]
+    fn bad_values_name_the_variable() {
+        let err = Config::default()
+            .apply_env_from(lookup(&[("KOWALSKI_OLLAMA_PORT", "eleven")]))
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("KOWALSKI_OLLAMA_PORT=eleven"),
+            "{err}"
+        );
+    }
+}
@@ -1,29 +1,164 @@
+use crate::error::KowalskiError;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use log::warn;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
 use uuid::Uuid;
 
+/// Metadata keys set on a conversation received through [`Conversation::handed_off`].
+pub const HANDOFF_FROM_AGENT: &str = "handoff.from_agent";
+pub const HANDOFF_CONVERSATION_ID: &str = "handoff.conversation_id";
+pub const HANDOFF_REASON: &str = "handoff.reason";
+
+/// Largest image (raw bytes, before base64) accepted by [`ImageData::from_path`].
+pub const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
 /// Conversation: The AI's memory of what it's been talking about.
 /// "Conversations are like dreams - they make sense at the time but are hard to explain later."
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Conversation {
     pub id: String,
     pub model: String,
     pub messages: Vec<Message>,
+    /// Free-form provenance (e.g. the `handoff.*` keys). Omitted from JSON when empty.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Who a [`Message`] is from, serialized as the wire strings `Message.role` holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRole {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+impl MessageRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::User => "user",
+            Self::Assistant => "assistant",
+            Self::Tool => "tool",
+        }
+    }
+}
+
+impl fmt::Display for MessageRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Accepts the wire strings in any case, with surrounding whitespace; anything else is an error.
+impl FromStr for MessageRole {
+    type Err = KowalskiError;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role.trim().to_ascii_lowercase().as_str() {
+            "system" => Ok(Self::System),
+            "user" => Ok(Self::User),
+            "assistant" => Ok(Self::Assistant),
+            "tool" => Ok(Self::Tool),
+            _ => Err(KowalskiError::Validation(format!(
+                "unknown message role '{role}' (expected system, user, assistant or tool)"
+            ))),
+        }
+    }
+}
+
+/// The wire string for `role`: known roles in their canonical spelling, anything else
+/// unchanged (with a warning, since backends treat it as a user message at best).
+fn normalize_role(role: &str) -> String {
+    match role.parse::<MessageRole>() {
+        Ok(role) => role.as_str().to_string(),
+        Err(e) => {
+            warn!("{e}; keeping it as given");
+            role.to_string()
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     pub role: String,
     pub content: String,
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Base64 images for vision models (Ollama `images` array). Omitted from JSON when `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<ImageData>>,
+    /// On a `tool` message: the [`ToolCall::id`] it answers (OpenAI `tool_call_id`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// On a `tool` message: the tool that produced it (Ollama `tool_name`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Message {
+    /// [`Message::role`] as a [`MessageRole`], or an error for a role outside the four known ones.
+    pub fn role_typed(&self) -> Result<MessageRole, KowalskiError> {
+        self.role.parse()
+    }
+}
+
+/// One base64-encoded image, serialized as a bare string the way Ollama expects it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ImageData(pub String);
+
+impl ImageData {
+    /// Base64-encodes raw image bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self(BASE64.encode(bytes))
+    }
+
+    /// Reads and encodes an image file, rejecting files larger than `max_bytes`.
+    pub fn from_path_with_limit(path: &Path, max_bytes: u64) -> Result<Self, KowalskiError> {
+        let meta = std::fs::metadata(path).map_err(|e| {
+            KowalskiError::Validation(format!("Cannot read image {}: {}", path.display(), e))
+        })?;
+        if !meta.is_file() {
+            return Err(KowalskiError::Validation(format!(
+                "Image path {} is not a file",
+                path.display()
+            )));
+        }
+        if meta.len() > max_bytes {
+            return Err(KowalskiError::Validation(format!(
+                "Image {} is {} bytes, larger than the {} byte limit",
+                path.display(),
+                meta.len(),
+                max_bytes
+            )));
+        }
+        let bytes = std::fs::read(path)?;
+        Ok(Self::from_bytes(&bytes))
+    }
+
+    /// Reads and encodes an image file using [`MAX_IMAGE_BYTES`].
+    pub fn from_path(path: &Path) -> Result<Self, KowalskiError> {
+        Self::from_path_with_limit(path, MAX_IMAGE_BYTES)
+    }
+
+    pub fn as_base64(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolCall {
     pub id: String,
     pub function: FunctionCall,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FunctionCall {
     pub name: String,
     pub arguments: serde_json::Value,
@@ -35,15 +170,125 @@ impl Conversation {
             id: Uuid::new_v4().to_string(),
             model: model.to_string(),
             messages: Vec::new(),
+            metadata: BTreeMap::new(),
         }
     }
 
+    /// Adds a message under a role string. Known roles are stored in their canonical spelling
+    /// (`"User"` becomes `"user"`); prefer [`Conversation::add_message_typed`].
     pub fn add_message(&mut self, role: &str, content: &str) {
+        self.messages.push(Message {
+            role: normalize_role(role),
+            content: content.to_string(),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        });
+    }
+
+    pub fn add_message_typed(&mut self, role: MessageRole, content: &str) {
         self.messages.push(Message {
             role: role.to_string(),
             content: content.to_string(),
             tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        });
+    }
+
+    /// Adds a message with images read from `paths`. Nothing is added if any image is
+    /// unreadable or exceeds [`MAX_IMAGE_BYTES`].
+    pub fn add_message_with_images<P: AsRef<Path>>(
+        &mut self,
+        role: &str,
+        content: &str,
+        paths: &[P],
+    ) -> Result<(), KowalskiError> {
+        let images = paths
+            .iter()
+            .map(|p| ImageData::from_path(p.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.messages.push(Message {
+            role: normalize_role(role),
+            content: content.to_string(),
+            tool_calls: None,
+            images: if images.is_empty() {
+                None
+            } else {
+                Some(images)
+            },
+            tool_call_id: None,
+            tool_name: None,
         });
+        Ok(())
+    }
+
+    /// Records a tool call and its result the way chat APIs expect them: an assistant message
+    /// (`reply`, the model's text) carrying the call in `tool_calls`, then a `tool` message
+    /// holding `result` that points back at the call by id and tool name. Returns the call id,
+    /// `call_<n>` for the conversation's n-th tool call.
+    pub fn add_tool_exchange(
+        &mut self,
+        reply: &str,
+        name: &str,
+        arguments: &serde_json::Value,
+        result: &str,
+    ) -> String {
+        let calls: usize = self
+            .messages
+            .iter()
+            .filter_map(|m| m.tool_calls.as_ref())
+            .map(Vec::len)
+            .sum();
+        let id = format!("call_{}", calls + 1);
+        self.messages.push(Message {
+            role: MessageRole::Assistant.to_string(),
+            content: reply.to_string(),
+            tool_calls: Some(vec![ToolCall {
+                id: id.clone(),
+                function: FunctionCall {
+                    name: name.to_string(),
+                    arguments: arguments.clone(),
+                },
+            }]),
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        });
+        self.messages.push(Message {
+            role: MessageRole::Tool.to_string(),
+            content: result.to_string(),
+            tool_calls: None,
+            images: None,
+            tool_call_id: Some(id.clone()),
+            tool_name: Some(name.to_string()),
+        });
+        id
+    }
+
+    /// Deep copy under a fresh id, optionally switching the model; the original is untouched.
+    pub fn fork(&self, model: Option<&str>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            model: model.unwrap_or(&self.model).to_string(),
+            messages: self.messages.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    /// Copy for an agent receiving this conversation in a handoff: fresh id, same messages, and
+    /// the sender, original id and reason recorded under the `handoff.*` metadata keys.
+    pub fn handed_off(&self, from_agent: &str, reason: &str) -> Self {
+        let mut copy = self.fork(None);
+        copy.metadata
+            .insert(HANDOFF_FROM_AGENT.to_string(), from_agent.to_string());
+        copy.metadata
+            .insert(HANDOFF_CONVERSATION_ID.to_string(), self.id.clone());
+        copy.metadata
+            .insert(HANDOFF_REASON.to_string(), reason.to_string());
+        copy
     }
 
     pub fn get_messages(&self) -> &[Message] {
@@ -54,3 +299,83 @@ impl Conversation {
         self.messages.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::ChatRequest;
+
+    #[test]
+    fn chat_request_serializes_images_as_base64() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pixel.png");
+        std::fs::write(&path, b"\x89PNG fake").unwrap();
+
+        let mut conv = Conversation::new("llava");
+        conv.add_message("system", "You describe images.");
+        conv.add_message_with_images("user", "what's in this?", &[&path])
+            .unwrap();
+
+        let request = ChatRequest {
+            model: conv.model.clone(),
+            messages: conv.messages.clone(),
+            stream: false,
+            temperature: 0.7,
+            max_tokens: 16,
+            tools: None,
+            format: None,
+            options: None,
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert!(json["messages"][0].get("images").is_none());
+        assert_eq!(
+            json["messages"][1]["images"][0],
+            BASE64.encode(b"\x89PNG fake")
+        );
+    }
+
+    #[test]
+    fn oversized_image_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.jpg");
+        std::fs::write(&path, vec![0u8; 64]).unwrap();
+
+        let err = ImageData::from_path_with_limit(&path, 32).unwrap_err();
+        assert!(err.to_string().contains("larger than the 32 byte limit"));
+
+        let mut conv = Conversation::new("llava");
+        assert!(
+            conv.add_message_with_images("user", "hi", &[dir.path().join("missing.png")])
+                .is_err()
+        );
+        assert!(conv.messages.is_empty());
+    }
+
+    #[test]
+    fn message_without_images_field_still_deserializes() {
+        let m: Message =
+            serde_json::from_str(r#"{"role":"user","content":"hi","tool_calls":null}"#).unwrap();
+        assert!(m.images.is_none());
+    }
+
+    #[test]
+    fn roles_are_normalized_and_unknown_ones_rejected() {
+        assert_eq!(
+            " Assistant ".parse::<MessageRole>().unwrap(),
+            MessageRole::Assistant
+        );
+        let err = "usr".parse::<MessageRole>().unwrap_err();
+        assert!(matches!(err, KowalskiError::Validation(_)), "{err}");
+        assert_eq!(serde_json::to_value(MessageRole::Tool).unwrap(), "tool");
+        assert!(serde_json::from_str::<MessageRole>(r#""usr""#).is_err());
+
+        let mut conv = Conversation::new("m");
+        conv.add_message("USER", "hi");
+        conv.add_message_typed(MessageRole::Assistant, "hello");
+        conv.add_message("usr", "typo");
+        let roles: Vec<&str> = conv.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "usr"]);
+        assert_eq!(conv.messages[0].role_typed().unwrap(), MessageRole::User);
+        assert!(conv.messages[2].role_typed().is_err());
+    }
+}
@@ -1,5 +1,9 @@
+use std::error::Error as StdError;
 use thiserror::Error;
 
+/// A boxed cause kept as the [`source`](StdError::source) of an error.
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
 #[derive(Error, Debug)]
 pub enum KowalskiError {
     #[error("Tool execution error: {0}")]
@@ -26,9 +30,6 @@ pub enum KowalskiError {
     #[error("Agent error: {0}")]
     Agent(String),
 
-    #[error("Task error: {0}")]
-    Task(String),
-
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -41,95 +42,161 @@ pub enum KowalskiError {
     #[error("Config error: {0}")]
     Config(#[from] config::ConfigError),
 
-    #[error("Template agent error: {0}")]
-    TemplateAgent(String),
-
     #[error("Web agent error: {0}")]
     WebAgent(String),
 
-    #[error("Academic agent error: {0}")]
-    AcademicAgent(String),
-
-    #[error("Tool chain error: {0}")]
-    ToolChain(String),
-
-    #[error("Task handler error: {0}")]
-    TaskHandler(String),
-
     #[error("Validation error: {0}")]
     Validation(String),
 
-    #[error("Authentication error: {0}")]
-    Authentication(String),
-
-    #[error("Authorization error: {0}")]
-    Authorization(String),
-
     #[error("Rate limit error: {0}")]
     RateLimit(String),
 
-    #[error("Timeout error: {0}")]
-    Timeout(String),
-
-    #[error("Connection error: {0}")]
-    Connection(String),
-
-    #[error("Serialization error: {0}")]
-    Serialization(String),
+    /// `operation` names what did not finish in time, with the limit.
+    #[error("Timed out: {operation}")]
+    Timeout { operation: String },
 
-    #[error("Deserialization error: {0}")]
-    Deserialization(String),
+    /// The caller gave up (e.g. a cancellation token fired) before the work finished.
+    #[error("Cancelled")]
+    Cancelled,
 
     #[error("Database error: {0}")]
     Database(String),
 
-    #[error("Cache error: {0}")]
-    Cache(String),
-
-    #[error("File system error: {0}")]
-    FileSystem(String),
-
     #[error("Memory error: {0}")]
     Memory(String),
 
-    #[error("Resource error: {0}")]
-    Resource(String),
-
-    #[error("State error: {0}")]
-    State(String),
+    /// A memory store's database failed; `tier` is `episodic`, `semantic` or `key-value`.
+    #[error("{tier} memory backend error: {source}")]
+    MemoryBackend {
+        tier: &'static str,
+        #[source]
+        source: BoxError,
+    },
 
     #[error("Initialization error: {0}")]
     Initialization(String),
 
-    #[error("Shutdown error: {0}")]
-    Shutdown(String),
-
-    #[error("Recovery error: {0}")]
-    Recovery(String),
-
-    #[error("Cleanup error: {0}")]
-    Cleanup(String),
-
+    /// The backend answered with an error that has no dedicated variant.
     #[error("Server error: {0}")]
     Server(String),
 
+    /// The backend failed on its side (HTTP 5xx); it may answer a later attempt.
+    #[error("Server unavailable: {0}")]
+    ServerUnavailable(String),
+
+    /// No connection to the Ollama server at `endpoint`.
+    #[error("Cannot reach Ollama at {endpoint}: {source} (is `ollama serve` running?)")]
+    OllamaUnreachable {
+        endpoint: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// The backend does not have `model`.
+    #[error("Model '{model}' not found (with Ollama: `ollama pull {model}`)")]
+    ModelNotFound { model: String },
+
+    #[error("Tool '{name}' not found (available: {})", list_or_none(available))]
+    ToolNotFound {
+        name: String,
+        available: Vec<String>,
+    },
+
+    /// Tool `name` failed for a reason outside the tool (I/O, a database, a subprocess).
+    #[error("Tool '{name}' failed: {source}")]
+    ToolFailed {
+        name: String,
+        #[source]
+        source: BoxError,
+    },
+
     #[error("Request error: {0}")]
     Request(#[from] reqwest::Error),
 
     #[error("Conversation not found: {0}")]
     ConversationNotFound(String),
 
-    #[error("Execution error: {0}")]
-    Execution(String),
-
-    #[error("Network error: {0}")]
-    ToolNetwork(String),
-
     #[error("Config error: {0}")]
     ToolConfig(String),
 
     #[error("Federation error: {0}")]
     Federation(String),
+
+    /// Federated agent `agent_id` could not run a task: it reported a failure, or became
+    /// unresponsive or left. Another agent may succeed.
+    #[error("Agent '{agent_id}' failed: {reason}")]
+    AgentFailed { agent_id: String, reason: String },
+
+    /// The model's reply still did not match the requested JSON Schema after every attempt
+    /// (see [`Agent::chat_structured`](crate::agent::Agent::chat_structured)).
+    #[error("Structured output invalid after {attempts} attempts: {}", errors.join("; "))]
+    StructuredOutput {
+        attempts: u32,
+        /// Problems with the last reply (parse error or schema violations).
+        errors: Vec<String>,
+    },
+}
+
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+impl KowalskiError {
+    /// A [`Self::MemoryBackend`] error from `tier`'s store.
+    pub fn memory_backend(tier: &'static str, source: impl Into<BoxError>) -> Self {
+        Self::MemoryBackend {
+            tier,
+            source: source.into(),
+        }
+    }
+
+    /// A [`Self::ToolFailed`] error for tool `name`.
+    pub fn tool_failed(name: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self::ToolFailed {
+            name: name.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Whether trying the same thing again later may succeed: the backend was unreachable,
+    /// slow, rate-limited or briefly failing, or a federated agent failed the task. Bad input,
+    /// missing models or tools, refused permissions, cancellation and other federation errors
+    /// (an invalid plan, a stopped transport) are not retryable. The federation
+    /// [`Coordinator`](crate::federation::Coordinator) dead-letters a task at once on an error
+    /// that is not.
+    ///
+    /// LLM calls are not retried automatically; the OpenAI client already backs off on
+    /// 429 and 5xx replies before it returns an error.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::OllamaUnreachable { .. }
+            | Self::Timeout { .. }
+            | Self::RateLimit(_)
+            | Self::Network(_)
+            | Self::ServerUnavailable(_)
+            | Self::AgentFailed { .. } => true,
+            Self::Request(e) => {
+                e.is_connect()
+                    || e.is_timeout()
+                    || e.status().is_some_and(|s| {
+                        s.is_server_error() || s == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    })
+            }
+            Self::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+            ),
+            _ => false,
+        }
+    }
 }
 
 impl From<String> for KowalskiError {
@@ -143,3 +210,104 @@ impl From<&str> for KowalskiError {
         KowalskiError::Agent(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LLMProvider, OllamaProvider};
+    use crate::tools::ToolInput;
+    use crate::tools::manager::ToolManager;
+
+    #[tokio::test]
+    async fn unreachable_ollama_names_the_endpoint() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let err = OllamaProvider::new("127.0.0.1", port)
+            .list_models()
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, KowalskiError::OllamaUnreachable { .. }),
+            "{err}"
+        );
+        let message = err.to_string();
+        assert!(
+            message.contains(&format!("http://127.0.0.1:{port}")),
+            "{message}"
+        );
+        assert!(message.contains("ollama serve"), "{message}");
+        assert!(err.source().is_some());
+        assert!(err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn missing_models_and_tools_say_what_exists() {
+        let err = KowalskiError::ModelNotFound {
+            model: "llama3.2".to_string(),
+        };
+        assert!(err.to_string().contains("ollama pull llama3.2"));
+        assert!(!err.is_retryable());
+
+        let tools = ToolManager::new();
+        tools.register(crate::tools::SchemaInferenceTool::new());
+        tools.register(crate::tools::HtmlToMarkdownTool::new());
+        let err = tools
+            .execute(
+                "web_search",
+                ToolInput::from_parameters(serde_json::json!({})),
+            )
+            .await
+            .unwrap_err();
+        let available = tools.tool_names().join(", ");
+        assert_eq!(
+            err.to_string(),
+            format!("Tool 'web_search' not found (available: {available})")
+        );
+        assert!(!err.is_retryable());
+
+        let err = ToolManager::new()
+            .execute(
+                "web_search",
+                ToolInput::from_parameters(serde_json::json!({})),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().ends_with("(available: none)"), "{err}");
+    }
+
+    #[test]
+    fn sources_are_kept_and_retryability_follows_the_cause() {
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "read-only");
+        let err = KowalskiError::memory_backend("episodic", io);
+        assert_eq!(err.to_string(), "episodic memory backend error: read-only");
+        assert_eq!(err.source().unwrap().to_string(), "read-only");
+        let err = KowalskiError::tool_failed("shell", std::io::Error::other("broken pipe"));
+        assert_eq!(err.source().unwrap().to_string(), "broken pipe");
+
+        let timeout = KowalskiError::Timeout {
+            operation: "task t1 on w1: no result within 5s".to_string(),
+        };
+        assert!(timeout.is_retryable());
+        assert!(KowalskiError::RateLimit("slow down".to_string()).is_retryable());
+        assert!(
+            KowalskiError::Io(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+                .is_retryable()
+        );
+        assert!(!KowalskiError::Cancelled.is_retryable());
+        assert!(!KowalskiError::Validation("empty key".to_string()).is_retryable());
+        assert!(!KowalskiError::PermissionDenied("write".to_string()).is_retryable());
+        assert!(KowalskiError::ServerUnavailable("502".to_string()).is_retryable());
+        assert!(!KowalskiError::Server("bad request".to_string()).is_retryable());
+        assert!(
+            KowalskiError::AgentFailed {
+                agent_id: "w1".to_string(),
+                reason: "task t1: tool crashed".to_string(),
+            }
+            .is_retryable()
+        );
+        assert!(!KowalskiError::Federation("invalid plan: no tasks".to_string()).is_retryable());
+    }
+}
@@ -1,10 +1,22 @@
 //! Agent Communication Language (ACL) — JSON-serializable messages for federation.
 //!
 //! Suitable for in-process brokers today and Postgres `NOTIFY` payloads later.
+//!
+//! The wire format is versioned ([`ACL_VERSION`]): fields added since version 0 have defaults,
+//! and payload kinds a receiver does not know decode as [`AclMessage::Unknown`], so envelopes
+//! from older and newer senders both parse.
 
+use crate::conversation::{Conversation, Message};
 use crate::error::KowalskiError;
+use crate::federation::delegation::TaskReport;
+use crate::federation::registry::AgentStatus;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Wire format version written by [`AclEnvelope::new`]. Envelopes without a `version` field
+/// predate versioning and read as version 0.
+pub const ACL_VERSION: u32 = 1;
+
 /// Default cap on delegation depth when the sender omits `max_delegation_depth` (strict default).
 pub const DEFAULT_MAX_DELEGATION_DEPTH: u32 = 3;
 
@@ -14,21 +26,64 @@ pub const ABSOLUTE_MAX_DELEGATION_DEPTH: u32 = 32;
 /// Wire envelope: every publish carries topic routing + provenance.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AclEnvelope {
+    /// Wire format version of the sender (0 when it did not say).
+    #[serde(default)]
+    pub version: u32,
     pub id: String,
+    /// Id of the envelope this one answers (see [`AclEnvelope::reply_to`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// When the sender created the envelope; the time of decoding for senders that omit it.
+    #[serde(default = "Utc::now")]
+    pub timestamp: DateTime<Utc>,
     pub topic: String,
     pub sender: String,
+    /// Agent the envelope is addressed to; `None` for broadcasts (see [`AclEnvelope::with_recipient`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
     pub payload: AclMessage,
 }
 
 impl AclEnvelope {
     pub fn new(topic: impl Into<String>, sender: impl Into<String>, payload: AclMessage) -> Self {
         Self {
+            version: ACL_VERSION,
             id: uuid::Uuid::new_v4().to_string(),
+            correlation_id: None,
+            timestamp: Utc::now(),
             topic: topic.into(),
             sender: sender.into(),
+            recipient: None,
             payload,
         }
     }
+
+    pub fn with_recipient(mut self, recipient: impl Into<String>) -> Self {
+        self.recipient = Some(recipient.into());
+        self
+    }
+
+    /// Reply on `request`'s topic, addressed to its sender and correlated to it by id.
+    pub fn reply_to(request: &AclEnvelope, sender: impl Into<String>, payload: AclMessage) -> Self {
+        let mut reply = Self::new(request.topic.clone(), sender, payload)
+            .with_recipient(request.sender.clone());
+        reply.correlation_id = Some(request.id.clone());
+        reply
+    }
+
+    /// Parses an envelope of any wire version: missing fields take their defaults and unknown
+    /// payload kinds become [`AclMessage::Unknown`].
+    pub fn decode(raw: &str) -> Result<Self, KowalskiError> {
+        let env: Self = serde_json::from_str(raw)?;
+        if env.version > ACL_VERSION {
+            log::debug!(
+                "envelope {} uses ACL version {} (this build speaks {ACL_VERSION})",
+                env.id,
+                env.version
+            );
+        }
+        Ok(env)
+    }
 }
 
 /// ACL payload variants (extend as orchestration grows).
@@ -39,6 +94,10 @@ pub enum AclMessage {
     Ping {
         text: String,
     },
+    /// Periodic liveness signal from an agent (see [`AgentRegistry::track_heartbeats`](crate::federation::AgentRegistry::track_heartbeats)).
+    Heartbeat {
+        agent_id: String,
+    },
     /// Orchestrator announces work matching capabilities (discovery).
     TaskOffer {
         task_id: String,
@@ -56,16 +115,57 @@ pub enum AclMessage {
         #[serde(default)]
         max_delegation_depth: Option<u32>,
     },
+    /// Worker reply to a [`AclMessage::TaskDelegate`], correlated by `task_id`.
     TaskResult {
         task_id: String,
         from_agent: String,
         outcome: String,
         success: bool,
+        /// Tool trace and usage, when the worker ran the task through a tool loop.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        report: Option<TaskReport>,
     },
     Error {
         code: String,
         message: String,
     },
+    /// An agent's liveness as seen by the registry.
+    Status {
+        agent_id: String,
+        status: AgentStatus,
+    },
+    /// Application-defined payload for messages the ACL has no variant for.
+    Custom {
+        name: String,
+        #[serde(default)]
+        data: serde_json::Value,
+    },
+    /// Hands a whole conversation to another agent, which imports it and answers its pending
+    /// user turn (see [`Agent::accept_handoff`](crate::agent::Agent::accept_handoff)).
+    Handoff {
+        handoff_id: String,
+        from_agent: String,
+        to_agent: String,
+        conversation: Conversation,
+        reason: String,
+        /// Send the receiving agent's subsequent messages back as [`AclMessage::HandoffMirror`].
+        #[serde(default)]
+        mirror: bool,
+    },
+    /// Ack for an [`AclMessage::Handoff`]: `conversation_id` is the receiver's local copy.
+    HandoffAccepted {
+        handoff_id: String,
+        from_agent: String,
+        to_agent: String,
+        conversation_id: String,
+    },
+    /// A message added to a handed-off conversation, mirrored back to the originating agent.
+    HandoffMirror {
+        handoff_id: String,
+        from_agent: String,
+        to_agent: String,
+        message: Message,
+    },
     /// Horde run lifecycle: orchestrator announces a run has begun.
     RunStarted {
         run_id: String,
@@ -134,6 +234,9 @@ pub enum AclMessage {
         #[serde(default)]
         step: Option<String>,
     },
+    /// A payload kind this build does not know (sent by a newer peer); safe to ignore.
+    #[serde(other)]
+    Unknown,
 }
 
 /// Reject [`AclMessage::TaskDelegate`] when `delegation_depth` exceeds the effective max.
@@ -185,6 +288,55 @@ mod tests {
         assert_eq!(msg, back);
     }
 
+    #[test]
+    fn envelope_round_trips_with_correlation() {
+        let request = AclEnvelope::new(
+            "federation",
+            "orch",
+            AclMessage::Custom {
+                name: "index_repo".into(),
+                data: serde_json::json!({"path": "/src", "shallow": true}),
+            },
+        );
+        let reply = AclEnvelope::reply_to(
+            &request,
+            "worker",
+            AclMessage::Status {
+                agent_id: "worker".into(),
+                status: AgentStatus::Active,
+            },
+        );
+        assert_eq!(reply.topic, "federation");
+        assert_eq!(reply.correlation_id.as_deref(), Some(request.id.as_str()));
+        assert_eq!(reply.recipient.as_deref(), Some("orch"));
+        for env in [request, reply] {
+            let json = serde_json::to_string(&env).unwrap();
+            assert_eq!(AclEnvelope::decode(&json).unwrap(), env);
+        }
+    }
+
+    #[test]
+    fn legacy_and_future_envelopes_still_decode() {
+        // Version 0: no version, timestamp or correlation id.
+        let legacy = AclEnvelope::decode(
+            r#"{"id":"e1","topic":"federation","sender":"old","payload":{"kind":"ping","text":"hi"}}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.version, 0);
+        assert_eq!(legacy.correlation_id, None);
+        assert_eq!(legacy.recipient, None);
+        assert_eq!(legacy.payload, AclMessage::Ping { text: "hi".into() });
+
+        // A newer peer's payload kind (and extra fields) decode as Unknown.
+        let future = AclEnvelope::decode(
+            r#"{"version":7,"id":"e2","timestamp":"2030-01-01T00:00:00Z","topic":"federation","sender":"new","priority":"high","payload":{"kind":"vote","ballot":[1,2]}}"#,
+        )
+        .unwrap();
+        assert_eq!(future.version, 7);
+        assert_eq!(future.payload, AclMessage::Unknown);
+        assert_eq!(future.timestamp.to_rfc3339(), "2030-01-01T00:00:00+00:00");
+    }
+
     #[test]
     fn check_depth_rejects_overflow() {
         let msg = AclMessage::TaskDelegate {
@@ -17,6 +17,14 @@ pub trait MessageBroker: Send + Sync {
     async fn publish(&self, envelope: &AclEnvelope) -> Result<(), KowalskiError>;
 }
 
+/// A [`MessageBroker`] that also delivers envelopes to local subscribers. [`MpscBroker`] is the
+/// in-process transport; [`WsTransport`](crate::federation::WsTransport) reaches a remote
+/// registry node.
+pub trait FederationTransport: MessageBroker {
+    /// Receive envelopes for `topic`. Buffer size per subscriber channel.
+    fn subscribe(&self, topic: &str, buffer: usize) -> tokio::sync::mpsc::Receiver<AclEnvelope>;
+}
+
 type SubscriberVec = Vec<tokio::sync::mpsc::Sender<AclEnvelope>>;
 
 /// Local broker: multiple [`subscribe`](MpscBroker::subscribe) handles per topic;
@@ -103,6 +111,12 @@ impl MessageBroker for MpscBroker {
     }
 }
 
+impl FederationTransport for MpscBroker {
+    fn subscribe(&self, topic: &str, buffer: usize) -> tokio::sync::mpsc::Receiver<AclEnvelope> {
+        MpscBroker::subscribe(self, topic, buffer)
+    }
+}
+
 impl Default for MpscBroker {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,480 @@
+//! Request/response delegation: a [`TaskSpec`] goes out as [`AclMessage::TaskDelegate`], a
+//! [`FederationWorker`] runs it through its agent's tool loop, and the correlated
+//! [`AclMessage::TaskResult`] comes back as a [`TaskResult`] (see
+//! [`FederationOrchestrator::delegate`](crate::federation::FederationOrchestrator::delegate)).
+
+use crate::agent::Agent;
+use crate::agent::tool_loop::{DEFAULT_MAX_TOOL_ITERATIONS, ToolTraceEntry, run_tool_loop};
+use crate::error::KowalskiError;
+use crate::federation::acl::{AclEnvelope, AclMessage, check_delegate_depth};
+use crate::federation::broker::FederationTransport;
+use crate::federation::registry::AgentRegistry;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default wait for a delegated task before it is marked [`TaskStatus::Failed`].
+pub const DEFAULT_TASK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Default delay before the first retry of a failed task.
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Where a [`TaskSpec`] should run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum TaskTarget {
+    /// A registered agent id.
+    Agent(String),
+    /// Best-ranked agent for a capability (see [`AgentRegistry::find_ranked_by_capability`]).
+    Capability(String),
+    /// Agent whose capabilities and tools best fit a task description (see [`AgentRegistry::best_match`]).
+    BestMatch(String),
+}
+
+/// Scheduling priority in a [`TaskQueue`](crate::federation::TaskQueue) (higher runs first).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// A unit of work to delegate.
+#[derive(Debug, Clone)]
+pub struct TaskSpec {
+    pub task_id: String,
+    pub instruction: String,
+    pub target: TaskTarget,
+    /// Wait for one attempt.
+    pub timeout: Duration,
+    pub priority: TaskPriority,
+    /// Extra attempts after a failure (used by [`Coordinator`](crate::federation::Coordinator)).
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles for each further retry.
+    pub retry_backoff: Duration,
+    /// No attempt starts after this instant, and attempts are cut short to meet it.
+    pub deadline: Option<Instant>,
+}
+
+impl TaskSpec {
+    pub fn new(target: TaskTarget, instruction: impl Into<String>) -> Self {
+        Self {
+            task_id: uuid::Uuid::new_v4().to_string(),
+            instruction: instruction.into(),
+            target,
+            timeout: DEFAULT_TASK_TIMEOUT,
+            priority: TaskPriority::Normal,
+            max_retries: 0,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            deadline: None,
+        }
+    }
+
+    pub fn for_agent(agent_id: impl Into<String>, instruction: impl Into<String>) -> Self {
+        Self::new(TaskTarget::Agent(agent_id.into()), instruction)
+    }
+
+    pub fn for_capability(capability: impl Into<String>, instruction: impl Into<String>) -> Self {
+        Self::new(TaskTarget::Capability(capability.into()), instruction)
+    }
+
+    /// Routes to [`AgentRegistry::best_match`] for the instruction itself.
+    pub fn best_match(instruction: impl Into<String>) -> Self {
+        let instruction = instruction.into();
+        Self::new(TaskTarget::BestMatch(instruction.clone()), instruction)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_task_id(mut self, task_id: impl Into<String>) -> Self {
+        self.task_id = task_id.into();
+        self
+    }
+
+    pub fn with_priority(mut self, priority: TaskPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+}
+
+/// Lifecycle of a delegated task, tracked in the [`AgentRegistry`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Registry entry for one delegated task.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TaskRecord {
+    pub task_id: String,
+    pub agent_id: String,
+    pub status: TaskStatus,
+    /// Failure reason for [`TaskStatus::Failed`].
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// One failed attempt at a queued task.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TaskAttempt {
+    pub agent_id: String,
+    pub error: String,
+}
+
+/// A task that failed permanently (retries exhausted or deadline passed); see
+/// [`AgentRegistry::dead_letters`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeadLetter {
+    pub task_id: String,
+    pub instruction: String,
+    pub attempts: Vec<TaskAttempt>,
+    /// Why the task was given up.
+    pub error: String,
+}
+
+/// Resource usage reported by the worker.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TaskUsage {
+    pub llm_calls: u32,
+    pub tool_calls: u32,
+    pub duration_ms: u64,
+}
+
+/// Extra detail carried on [`AclMessage::TaskResult`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TaskReport {
+    pub tool_trace: Vec<ToolTraceEntry>,
+    pub usage: TaskUsage,
+}
+
+/// Successful outcome of [`FederationOrchestrator::delegate`](crate::federation::FederationOrchestrator::delegate).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskResult {
+    pub task_id: String,
+    pub agent_id: String,
+    pub answer: String,
+    pub tool_trace: Vec<ToolTraceEntry>,
+    pub usage: TaskUsage,
+}
+
+/// Runs [`AclMessage::TaskDelegate`] messages addressed to one agent and publishes the
+/// [`AclMessage::TaskResult`] on the same topic. [`AclMessage::Handoff`] conversations are
+/// imported and answered the same way.
+#[derive(Clone)]
+pub struct FederationWorker {
+    pub agent_id: String,
+    /// Model used for the per-task conversation.
+    pub model: String,
+    pub topic: String,
+    pub max_iterations: usize,
+    /// Where Running status is recorded (the orchestrator's registry when in-process).
+    pub registry: Option<Arc<AgentRegistry>>,
+    /// Heartbeat period; `None` sends no heartbeats.
+    pub heartbeat_interval: Option<Duration>,
+}
+
+impl FederationWorker {
+    pub fn new(agent_id: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            model: model.into(),
+            topic: "federation".to_string(),
+            max_iterations: DEFAULT_MAX_TOOL_ITERATIONS,
+            registry: None,
+            heartbeat_interval: None,
+        }
+    }
+
+    pub fn with_registry(mut self, registry: Arc<AgentRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Publishes [`AclMessage::Heartbeat`] every `interval`, including while a task runs.
+    pub fn with_heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = Some(interval);
+        self
+    }
+
+    /// Subscribes to the worker topic on `transport` (in-process broker or remote connection)
+    /// and handles tasks one at a time until the transport goes away. Aborting the handle also
+    /// stops the heartbeat.
+    pub fn spawn<A, T>(self, mut agent: A, transport: Arc<T>) -> tokio::task::JoinHandle<()>
+    where
+        A: Agent + 'static,
+        T: FederationTransport + ?Sized + 'static,
+    {
+        let mut rx = transport.subscribe(&self.topic, 64);
+        tokio::spawn(async move {
+            let mut heartbeat = self.heartbeat_interval.map(tokio::time::interval);
+            loop {
+                let env = tokio::select! {
+                    env = rx.recv() => match env {
+                        Some(env) => env,
+                        None => break,
+                    },
+                    _ = next_beat(&mut heartbeat) => {
+                        self.beat(transport.as_ref()).await;
+                        continue;
+                    }
+                };
+                let replies = match &env.payload {
+                    AclMessage::TaskDelegate {
+                        task_id,
+                        to_agent,
+                        instruction,
+                        ..
+                    } if *to_agent == self.agent_id => {
+                        let run = self.run_task(&mut agent, &env, task_id, instruction);
+                        self.beating_while(run, &mut heartbeat, transport.as_ref())
+                            .await
+                            .into_iter()
+                            .collect()
+                    }
+                    AclMessage::Handoff { to_agent, .. } if *to_agent == self.agent_id => {
+                        self.run_handoff(&mut agent, &env, &mut heartbeat, transport.as_ref())
+                            .await
+                    }
+                    _ => continue,
+                };
+                for reply in replies {
+                    if let Err(e) = transport.publish(&reply).await {
+                        warn!("worker {}: failed to publish reply: {e}", self.agent_id);
+                    }
+                }
+            }
+            debug!("worker {} stopped", self.agent_id);
+        })
+    }
+
+    /// Drives `run` to completion, sending heartbeats while it is in progress.
+    async fn beating_while<F, T>(
+        &self,
+        run: F,
+        heartbeat: &mut Option<tokio::time::Interval>,
+        transport: &T,
+    ) -> F::Output
+    where
+        F: std::future::Future,
+        T: FederationTransport + ?Sized,
+    {
+        tokio::pin!(run);
+        loop {
+            tokio::select! {
+                out = &mut run => return out,
+                _ = next_beat(heartbeat) => self.beat(transport).await,
+            }
+        }
+    }
+
+    /// Accepts an [`AclMessage::Handoff`]: imports the conversation, publishes the
+    /// [`AclMessage::HandoffAccepted`] ack right away, then answers a trailing user turn. Returns
+    /// the replies still to publish: the messages added while answering when mirroring was
+    /// requested, or an [`AclMessage::Error`] when the answer failed.
+    async fn run_handoff<A: Agent, T: FederationTransport + ?Sized>(
+        &self,
+        agent: &mut A,
+        request: &AclEnvelope,
+        heartbeat: &mut Option<tokio::time::Interval>,
+        transport: &T,
+    ) -> Vec<AclEnvelope> {
+        let AclMessage::Handoff {
+            handoff_id,
+            from_agent,
+            conversation,
+            reason,
+            mirror,
+            ..
+        } = &request.payload
+        else {
+            return Vec::new();
+        };
+        let reply = |payload| AclEnvelope::reply_to(request, self.agent_id.clone(), payload);
+        let error = |message: String| {
+            reply(AclMessage::Error {
+                code: "handoff_failed".to_string(),
+                message: format!("handoff {handoff_id}: {message}"),
+            })
+        };
+
+        // The pending user turn is replayed through the tool loop, which records it again.
+        let mut history = conversation.clone();
+        let pending = match history.messages.last() {
+            Some(last) if last.role == "user" => history.messages.pop().map(|m| m.content),
+            _ => None,
+        };
+        let conversation_id = match agent.accept_handoff(&history, from_agent, reason) {
+            Ok(id) => id,
+            Err(e) => return vec![error(e.to_string())],
+        };
+        let ack = reply(AclMessage::HandoffAccepted {
+            handoff_id: handoff_id.clone(),
+            from_agent: self.agent_id.clone(),
+            to_agent: from_agent.clone(),
+            conversation_id: conversation_id.clone(),
+        });
+        if let Err(e) = transport.publish(&ack).await {
+            warn!(
+                "worker {}: failed to acknowledge handoff: {e}",
+                self.agent_id
+            );
+        }
+
+        let Some(input) = pending else {
+            return Vec::new();
+        };
+        let run = run_tool_loop(agent, &conversation_id, &input, self.max_iterations);
+        if let Err(e) = self.beating_while(run, heartbeat, transport).await {
+            return vec![error(e.to_string())];
+        }
+        if !mirror {
+            return Vec::new();
+        }
+        agent
+            .get_conversation(&conversation_id)
+            .map(|c| c.messages.iter().skip(conversation.messages.len()))
+            .into_iter()
+            .flatten()
+            .map(|message| {
+                reply(AclMessage::HandoffMirror {
+                    handoff_id: handoff_id.clone(),
+                    from_agent: self.agent_id.clone(),
+                    to_agent: from_agent.clone(),
+                    message: message.clone(),
+                })
+            })
+            .collect()
+    }
+
+    async fn beat<T: FederationTransport + ?Sized>(&self, transport: &T) {
+        let env = AclEnvelope::new(
+            self.topic.clone(),
+            self.agent_id.clone(),
+            AclMessage::Heartbeat {
+                agent_id: self.agent_id.clone(),
+            },
+        );
+        if let Err(e) = transport.publish(&env).await {
+            debug!("worker {}: heartbeat not sent: {e}", self.agent_id);
+        }
+    }
+
+    /// Runs a delegated task and returns the result to publish, or `None` when the registry
+    /// says the task is no longer queued for this worker (see [`AgentRegistry::start_task`]).
+    async fn run_task<A: Agent>(
+        &self,
+        agent: &mut A,
+        request: &AclEnvelope,
+        task_id: &str,
+        instruction: &str,
+    ) -> Option<AclEnvelope> {
+        if let Some(registry) = &self.registry
+            && !registry.start_task(task_id, &self.agent_id).unwrap_or(true)
+        {
+            debug!(
+                "worker {}: task {task_id} is no longer queued here; not running it",
+                self.agent_id
+            );
+            return None;
+        }
+        let started = Instant::now();
+        let result = match check_delegate_depth(&request.payload) {
+            Ok(()) => {
+                let conversation_id = agent.start_conversation(&self.model);
+                run_tool_loop(agent, &conversation_id, instruction, self.max_iterations).await
+            }
+            Err(e) => Err(e),
+        };
+        let (outcome, success, report) = match result {
+            Ok(run) => {
+                let usage = TaskUsage {
+                    llm_calls: run.llm_calls,
+                    tool_calls: run.tool_trace.len() as u32,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                };
+                let report = TaskReport {
+                    tool_trace: run.tool_trace,
+                    usage,
+                };
+                (run.answer, true, Some(report))
+            }
+            Err(e) => (e.to_string(), false, None),
+        };
+        Some(AclEnvelope::reply_to(
+            request,
+            self.agent_id.clone(),
+            AclMessage::TaskResult {
+                task_id: task_id.to_string(),
+                from_agent: self.agent_id.clone(),
+                outcome,
+                success,
+                report,
+            },
+        ))
+    }
+}
+
+/// Next tick of an optional heartbeat timer (never fires when heartbeats are off).
+async fn next_beat(heartbeat: &mut Option<tokio::time::Interval>) {
+    match heartbeat {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Converts a worker reply into the caller-facing result, or the worker's error.
+pub(crate) fn task_result_from_message(msg: AclMessage) -> Result<TaskResult, KowalskiError> {
+    match msg {
+        AclMessage::TaskResult {
+            task_id,
+            from_agent,
+            outcome,
+            success: true,
+            report,
+        } => {
+            let report = report.unwrap_or_default();
+            Ok(TaskResult {
+                task_id,
+                agent_id: from_agent,
+                answer: outcome,
+                tool_trace: report.tool_trace,
+                usage: report.usage,
+            })
+        }
+        AclMessage::TaskResult {
+            task_id,
+            from_agent,
+            outcome,
+            ..
+        } => Err(KowalskiError::AgentFailed {
+            agent_id: from_agent,
+            reason: format!("task {task_id}: {outcome}"),
+        }),
+        other => Err(KowalskiError::Federation(format!(
+            "unexpected reply to delegated task: {other:?}"
+        ))),
+    }
+}
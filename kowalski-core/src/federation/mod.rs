@@ -2,21 +2,57 @@
 //!
 //! Start with [`MpscBroker`] + [`AgentRegistry`] in one process; Postgres `LISTEN`/`NOTIFY`
 //! can mirror the same [`AclEnvelope`] JSON later.
+//!
+//! Agents in other processes connect over WebSocket: [`WsFederationServer`] on the registry node,
+//! [`WsTransport`] on each remote agent (both speak [`FederationTransport`], like [`MpscBroker`]).
+//!
+//! [`FederationOrchestrator::request`] sends any [`AclMessage`] to one agent and waits for the
+//! envelope that answers it ([`AclEnvelope::reply_to`]), or times out.
+//!
+//! [`FederationOrchestrator::delegate`] sends a [`TaskSpec`] to a [`FederationWorker`] and waits
+//! for its [`TaskResult`]; task status is queryable via [`AgentRegistry::task_status`].
+//!
+//! Many tasks over few workers go through a [`Coordinator`] (priorities, least-loaded dispatch,
+//! retries, dead letters in [`AgentRegistry::dead_letters`]).
+//!
+//! Agents heartbeat ([`FederationWorker::with_heartbeat`], or any frame over WebSocket); the
+//! registry's liveness monitor marks silent agents unresponsive, then removes them, and
+//! broadcasts [`RegistryEvent`]s.
+//!
+//! With `federation.persistence_path` set, [`AgentRegistry::from_config`] attaches a SQLite
+//! [`RegistryStore`]; [`AgentRegistry::recover`] reloads registrations and interrupted tasks,
+//! which [`Coordinator::resume`] puts back in the queue.
+//!
+//! [`FederationOrchestrator::handoff`] passes a whole conversation to another worker, which
+//! imports it ([`Agent::accept_handoff`](crate::agent::Agent::accept_handoff)), acks with
+//! [`AclMessage::HandoffAccepted`], and keeps answering.
+//!
+//! A [`SupervisorAgent`] plans a request into dependent subtasks, delegates them by capability,
+//! and synthesizes the results.
 
 mod acl;
 mod broker;
+mod delegation;
 mod orchestrator;
 mod persist;
 #[cfg(feature = "postgres")]
 mod pg_broker;
+mod queue;
 mod registry;
+mod store;
+mod supervisor;
+mod ws;
 
 pub use acl::{
-    ABSOLUTE_MAX_DELEGATION_DEPTH, AclEnvelope, AclMessage, DEFAULT_MAX_DELEGATION_DEPTH,
-    check_delegate_depth,
+    ABSOLUTE_MAX_DELEGATION_DEPTH, ACL_VERSION, AclEnvelope, AclMessage,
+    DEFAULT_MAX_DELEGATION_DEPTH, check_delegate_depth,
+};
+pub use broker::{FederationTransport, MessageBroker, MpscBroker};
+pub use delegation::{
+    DEFAULT_RETRY_BACKOFF, DEFAULT_TASK_TIMEOUT, DeadLetter, FederationWorker, TaskAttempt,
+    TaskPriority, TaskRecord, TaskReport, TaskResult, TaskSpec, TaskStatus, TaskTarget, TaskUsage,
 };
-pub use broker::{MessageBroker, MpscBroker};
-pub use orchestrator::{DelegationOutcome, FederationOrchestrator};
+pub use orchestrator::{DelegationOutcome, FederationOrchestrator, HandoffReceipt};
 #[cfg(feature = "postgres")]
 pub use persist::{AgentStateSnapshot, load_agent_states};
 pub use persist::{
@@ -28,4 +64,13 @@ pub use persist::{
 pub use pg_broker::{
     PgBroker, bridge_postgres_notify_to_mpsc, bridge_postgres_notify_to_mpsc_pool, pg_pool_connect,
 };
-pub use registry::{AgentRecord, AgentRegistry};
+pub use queue::{Coordinator, QueuedTask, TaskHandle, TaskQueue};
+pub use registry::{
+    AgentRecord, AgentRegistry, AgentStatus, LivenessPolicy, RecoveredState, RegistryEvent,
+};
+pub use store::{RegistryStore, StoredTask, StoredTaskSpec};
+pub use supervisor::{
+    DEFAULT_SUPERVISOR_PARALLELISM, Plan, PlannedTask, SubtaskOutcome, SupervisorAgent,
+    SupervisorEvent, SupervisorRun,
+};
+pub use ws::{WsClientOptions, WsFederationServer, WsFrame, WsTransport};
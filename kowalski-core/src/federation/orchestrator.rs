@@ -1,12 +1,31 @@
 //! Orchestration: capability-based routing over a [`MessageBroker`].
 
+use crate::conversation::{Conversation, Message};
 use crate::error::KowalskiError;
 use crate::federation::acl::{
     AclEnvelope, AclMessage, DEFAULT_MAX_DELEGATION_DEPTH, check_delegate_depth,
 };
 use crate::federation::broker::MessageBroker;
-use crate::federation::registry::AgentRegistry;
-use std::sync::Arc;
+use crate::federation::delegation::{TaskResult, TaskSpec, TaskStatus, task_result_from_message};
+use crate::federation::registry::{AgentRegistry, RegistryEvent};
+use log::{debug, info};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+type PendingResults = Arc<Mutex<HashMap<String, oneshot::Sender<AclMessage>>>>;
+type HandoffMirrors = Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Message>>>>;
+type PendingReplies = Arc<Mutex<HashMap<String, oneshot::Sender<AclEnvelope>>>>;
+
+/// How [`FederationOrchestrator::wait_for_result`] ended.
+enum Wait {
+    Reply(Box<AclMessage>),
+    ListenerStopped,
+    TimedOut,
+    /// The assigned agent went away before starting the task.
+    AgentLost,
+}
 
 /// Result of a successful [`FederationOrchestrator::delegate_first_match`] (for HTTP/Postgres fan-out).
 #[derive(Debug, Clone)]
@@ -15,6 +34,18 @@ pub struct DelegationOutcome {
     pub envelope: AclEnvelope,
 }
 
+/// Accepted [`FederationOrchestrator::handoff`].
+#[derive(Debug)]
+pub struct HandoffReceipt {
+    pub handoff_id: String,
+    /// Agent now holding the conversation.
+    pub agent_id: String,
+    /// The receiver's local conversation id.
+    pub conversation_id: String,
+    /// Messages the receiver adds afterwards, when mirroring was requested.
+    pub mirror: Option<mpsc::UnboundedReceiver<Message>>,
+}
+
 /// Holds registry + broker for one deployment (in-process or bridged to Postgres).
 pub struct FederationOrchestrator {
     pub registry: Arc<AgentRegistry>,
@@ -23,6 +54,12 @@ pub struct FederationOrchestrator {
     pub default_topic: String,
     /// Default cap on re-delegation chains (embedded in [`AclMessage::TaskDelegate`]).
     pub default_max_delegation_depth: u32,
+    /// [`delegate`](Self::delegate) calls waiting for their [`AclMessage::TaskResult`], by task id.
+    pending: PendingResults,
+    /// Mirrored messages of accepted handoffs, by handoff id.
+    mirrors: HandoffMirrors,
+    /// [`request`](Self::request) calls waiting for a correlated reply, by request envelope id.
+    replies: PendingReplies,
 }
 
 impl FederationOrchestrator {
@@ -34,9 +71,352 @@ impl FederationOrchestrator {
             orchestrator_id: "orchestrator".to_string(),
             default_topic: "federation".to_string(),
             default_max_delegation_depth: DEFAULT_MAX_DELEGATION_DEPTH,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            mirrors: Arc::new(Mutex::new(HashMap::new())),
+            replies: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Feeds [`AclMessage::TaskResult`] and [`AclMessage::HandoffAccepted`] replies from a
+    /// subscription on the worker topic to pending [`delegate`](Self::delegate) and
+    /// [`handoff`](Self::handoff) calls, envelopes correlated to a pending
+    /// [`request`](Self::request) to that call, and forwards [`AclMessage::HandoffMirror`]
+    /// messages. Required once before delegating.
+    pub fn listen_for_results(
+        &self,
+        mut rx: mpsc::Receiver<AclEnvelope>,
+    ) -> tokio::task::JoinHandle<()> {
+        let pending = self.pending.clone();
+        let mirrors = self.mirrors.clone();
+        let replies = self.replies.clone();
+        tokio::spawn(async move {
+            while let Some(env) = rx.recv().await {
+                if let Some(request_id) = &env.correlation_id {
+                    let waiter = replies
+                        .lock()
+                        .expect("pending replies lock")
+                        .remove(request_id);
+                    if let Some(tx) = waiter {
+                        let _ = tx.send(env);
+                        continue;
+                    }
+                }
+                let key = match &env.payload {
+                    AclMessage::TaskResult { task_id, .. } => task_id,
+                    AclMessage::HandoffAccepted { handoff_id, .. } => handoff_id,
+                    AclMessage::HandoffMirror {
+                        handoff_id,
+                        message,
+                        ..
+                    } => {
+                        let mut mirrors = mirrors.lock().expect("handoff mirrors lock");
+                        let delivered = mirrors
+                            .get(handoff_id)
+                            .is_some_and(|tx| tx.send(message.clone()).is_ok());
+                        if !delivered {
+                            mirrors.remove(handoff_id);
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let waiter = pending.lock().expect("pending results lock").remove(key);
+                match waiter {
+                    Some(tx) => {
+                        let _ = tx.send(env.payload);
+                    }
+                    None => debug!("reply for unknown or expired task {key}"),
+                }
+            }
+        })
+    }
+
+    /// Sends `payload` to `recipient` and waits up to `timeout` for the envelope that answers it
+    /// (one whose `correlation_id` is the request's id, see [`AclEnvelope::reply_to`]).
+    pub async fn request(
+        &self,
+        recipient: &str,
+        payload: AclMessage,
+        timeout: Duration,
+    ) -> Result<AclEnvelope, KowalskiError> {
+        if self.registry.get(recipient).is_none() {
+            return Err(KowalskiError::Federation(format!(
+                "request recipient {recipient} is not registered"
+            )));
+        }
+        let env = AclEnvelope::new(
+            self.default_topic.clone(),
+            self.orchestrator_id.clone(),
+            payload,
+        )
+        .with_recipient(recipient);
+        let (tx, rx) = oneshot::channel();
+        self.replies
+            .lock()
+            .expect("pending replies lock")
+            .insert(env.id.clone(), tx);
+        let forget = || {
+            self.replies
+                .lock()
+                .expect("pending replies lock")
+                .remove(&env.id);
+        };
+        if let Err(e) = self.publish(&env).await {
+            forget();
+            return Err(e);
+        }
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(reply)) => Ok(reply),
+            Ok(Err(_)) => {
+                forget();
+                Err(KowalskiError::Federation(
+                    "result listener stopped".to_string(),
+                ))
+            }
+            Err(_) => {
+                forget();
+                Err(KowalskiError::Timeout {
+                    operation: format!(
+                        "request {} to {recipient}: no reply within {timeout:?}",
+                        env.id
+                    ),
+                })
+            }
+        }
+    }
+
+    /// Hands `conversation` from `from_agent` to `to_agent` with an [`AclMessage::Handoff`] and
+    /// waits up to `timeout` for the [`AclMessage::HandoffAccepted`] ack. The receiver then
+    /// answers the conversation's trailing user turn; with `mirror`, the messages it adds arrive
+    /// on [`HandoffReceipt::mirror`].
+    pub async fn handoff(
+        &self,
+        from_agent: &str,
+        to_agent: &str,
+        conversation: &Conversation,
+        reason: &str,
+        mirror: bool,
+        timeout: Duration,
+    ) -> Result<HandoffReceipt, KowalskiError> {
+        if self.registry.get(to_agent).is_none() {
+            return Err(KowalskiError::Federation(format!(
+                "handoff target {to_agent} is not registered"
+            )));
+        }
+        let handoff_id = uuid::Uuid::new_v4().to_string();
+        let (tx, rx) = oneshot::channel();
+        self.pending
+            .lock()
+            .expect("pending results lock")
+            .insert(handoff_id.clone(), tx);
+        let mirror_rx = mirror.then(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.mirrors
+                .lock()
+                .expect("handoff mirrors lock")
+                .insert(handoff_id.clone(), tx);
+            rx
+        });
+        let forget = || {
+            self.pending
+                .lock()
+                .expect("pending results lock")
+                .remove(&handoff_id);
+            self.mirrors
+                .lock()
+                .expect("handoff mirrors lock")
+                .remove(&handoff_id);
+        };
+
+        let env = AclEnvelope::new(
+            self.default_topic.clone(),
+            from_agent,
+            AclMessage::Handoff {
+                handoff_id: handoff_id.clone(),
+                from_agent: from_agent.to_string(),
+                to_agent: to_agent.to_string(),
+                conversation: conversation.clone(),
+                reason: reason.to_string(),
+                mirror,
+            },
+        );
+        if let Err(e) = self.publish(&env).await {
+            forget();
+            return Err(e);
+        }
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(AclMessage::HandoffAccepted {
+                from_agent: agent_id,
+                conversation_id,
+                ..
+            })) => {
+                info!("conversation {} handed off to {agent_id}", conversation.id);
+                Ok(HandoffReceipt {
+                    handoff_id,
+                    agent_id,
+                    conversation_id,
+                    mirror: mirror_rx,
+                })
+            }
+            Ok(Ok(other)) => {
+                forget();
+                Err(KowalskiError::Federation(format!(
+                    "unexpected reply to handoff: {other:?}"
+                )))
+            }
+            Ok(Err(_)) => {
+                forget();
+                Err(KowalskiError::Federation(
+                    "result listener stopped".to_string(),
+                ))
+            }
+            Err(_) => {
+                forget();
+                Err(KowalskiError::Timeout {
+                    operation: format!(
+                        "handoff {handoff_id} to {to_agent}: no ack within {timeout:?}"
+                    ),
+                })
+            }
         }
     }
 
+    /// Routes `task` to an agent (see [`AgentRegistry::resolve`]), publishes a
+    /// [`AclMessage::TaskDelegate`], and waits up to `task.timeout` for the worker's result.
+    ///
+    /// Status moves Queued → Running (set by the worker) → Completed / Failed in the registry.
+    /// If the chosen agent becomes unresponsive or leaves while the task is still Queued, the
+    /// task is re-routed to the next agent matching its target.
+    /// Times out with [`KowalskiError::Timeout`]; a worker-side failure is
+    /// [`KowalskiError::AgentFailed`].
+    pub async fn delegate(&self, task: TaskSpec) -> Result<TaskResult, KowalskiError> {
+        let mut events = self.registry.subscribe_events();
+        let deadline = tokio::time::Instant::now() + task.timeout;
+        let mut agent_id = self.registry.resolve(&task.target)?.id;
+        let task_id = task.task_id.clone();
+
+        let (tx, mut rx) = oneshot::channel();
+        self.pending
+            .lock()
+            .expect("pending results lock")
+            .insert(task_id.clone(), tx);
+
+        let wait = loop {
+            self.registry
+                .set_task_status(&task_id, &agent_id, TaskStatus::Queued, None)?;
+            let env = AclEnvelope::new(
+                self.default_topic.clone(),
+                self.orchestrator_id.clone(),
+                AclMessage::TaskDelegate {
+                    task_id: task_id.clone(),
+                    from_agent: self.orchestrator_id.clone(),
+                    to_agent: agent_id.clone(),
+                    instruction: task.instruction.clone(),
+                    delegation_depth: 0,
+                    max_delegation_depth: Some(self.default_max_delegation_depth),
+                },
+            );
+            if let Err(e) = self.publish(&env).await {
+                self.fail_task(&task_id, &agent_id, e.to_string());
+                return Err(e);
+            }
+            match self
+                .wait_for_result(&mut rx, &mut events, &task_id, &agent_id, deadline)
+                .await
+            {
+                Wait::AgentLost => match self.registry.resolve(&task.target) {
+                    Ok(next) => {
+                        info!("re-routing task {task_id} from {agent_id} to {}", next.id);
+                        agent_id = next.id;
+                    }
+                    Err(e) => {
+                        let err = KowalskiError::AgentFailed {
+                            agent_id: agent_id.clone(),
+                            reason: format!(
+                                "became unavailable during task {task_id}, which could not be re-routed: {e}"
+                            ),
+                        };
+                        self.fail_task(&task_id, &agent_id, err.to_string());
+                        return Err(err);
+                    }
+                },
+                other => break other,
+            }
+        };
+
+        let err = match wait {
+            Wait::Reply(msg) => match task_result_from_message(*msg) {
+                Ok(result) => {
+                    self.registry.set_task_status(
+                        &task_id,
+                        &agent_id,
+                        TaskStatus::Completed,
+                        None,
+                    )?;
+                    self.registry.record_task_result(&result);
+                    return Ok(result);
+                }
+                Err(e) => e,
+            },
+            Wait::ListenerStopped => {
+                KowalskiError::Federation("result listener stopped".to_string())
+            }
+            Wait::TimedOut | Wait::AgentLost => KowalskiError::Timeout {
+                operation: format!(
+                    "task {task_id} on {agent_id}: no result within {:?}",
+                    task.timeout
+                ),
+            },
+        };
+        self.fail_task(&task_id, &agent_id, err.to_string());
+        Err(err)
+    }
+
+    /// Waits for the task's reply, the deadline, or the loss of `agent_id` while the task is
+    /// still queued.
+    async fn wait_for_result(
+        &self,
+        rx: &mut oneshot::Receiver<AclMessage>,
+        events: &mut broadcast::Receiver<RegistryEvent>,
+        task_id: &str,
+        agent_id: &str,
+        deadline: tokio::time::Instant,
+    ) -> Wait {
+        let mut events_open = true;
+        loop {
+            tokio::select! {
+                reply = &mut *rx => {
+                    return match reply {
+                        Ok(msg) => Wait::Reply(Box::new(msg)),
+                        Err(_) => Wait::ListenerStopped,
+                    };
+                }
+                _ = tokio::time::sleep_until(deadline) => return Wait::TimedOut,
+                event = events.recv(), if events_open => match event {
+                    Ok(RegistryEvent::AgentUnresponsive { agent_id: lost })
+                    | Ok(RegistryEvent::AgentLeft { agent_id: lost })
+                        if lost == agent_id
+                            && self.registry.task_status(task_id) == Some(TaskStatus::Queued) =>
+                    {
+                        return Wait::AgentLost;
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => events_open = false,
+                },
+            }
+        }
+    }
+
+    fn fail_task(&self, task_id: &str, agent_id: &str, error: String) {
+        self.pending
+            .lock()
+            .expect("pending results lock")
+            .remove(task_id);
+        let _ = self
+            .registry
+            .set_task_status(task_id, agent_id, TaskStatus::Failed, Some(error));
+    }
+
     /// Publish after validating delegation depth when applicable.
     pub async fn publish(&self, envelope: &AclEnvelope) -> Result<(), KowalskiError> {
         check_delegate_depth(&envelope.payload)?;
@@ -88,10 +468,10 @@ mod tests {
     async fn delegate_publishes_to_broker() {
         let broker = Arc::new(MpscBroker::new());
         let reg = Arc::new(AgentRegistry::new());
-        reg.register(crate::federation::AgentRecord {
-            id: "worker".into(),
-            capabilities: vec!["search".into()],
-        })
+        reg.register(crate::federation::AgentRecord::new(
+            "worker",
+            vec!["search".into()],
+        ))
         .unwrap();
         let orch = FederationOrchestrator::new(reg, broker.clone());
         let mut rx = broker.subscribe("federation", 4);
@@ -103,4 +483,83 @@ mod tests {
         let env = rx.recv().await.unwrap();
         assert!(matches!(env.payload, AclMessage::TaskDelegate { .. }));
     }
+
+    #[tokio::test]
+    async fn request_waits_for_the_correlated_reply() {
+        let broker = Arc::new(MpscBroker::new());
+        let reg = Arc::new(AgentRegistry::new());
+        reg.register(crate::federation::AgentRecord::new("worker", vec![]))
+            .unwrap();
+        let orch = FederationOrchestrator::new(reg, broker.clone());
+        orch.listen_for_results(broker.subscribe("federation", 16));
+
+        // The worker first sends an uncorrelated message, then answers each request it gets.
+        let mut inbox = broker.subscribe("federation", 16);
+        let responder = broker.clone();
+        tokio::spawn(async move {
+            while let Some(env) = inbox.recv().await {
+                if env.recipient.as_deref() != Some("worker") {
+                    continue;
+                }
+                let AclMessage::Ping { text } = &env.payload else {
+                    continue;
+                };
+                let noise = AclEnvelope::new(
+                    "federation",
+                    "worker",
+                    AclMessage::Ping {
+                        text: "unrelated".into(),
+                    },
+                );
+                responder.publish_to_topic(&noise).await.unwrap();
+                let reply = AclEnvelope::reply_to(
+                    &env,
+                    "worker",
+                    AclMessage::Ping {
+                        text: format!("pong: {text}"),
+                    },
+                );
+                responder.publish_to_topic(&reply).await.unwrap();
+            }
+        });
+
+        let reply = orch
+            .request(
+                "worker",
+                AclMessage::Ping { text: "hi".into() },
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reply.sender, "worker");
+        assert_eq!(reply.recipient.as_deref(), Some("orchestrator"));
+        assert_eq!(
+            reply.payload,
+            AclMessage::Ping {
+                text: "pong: hi".into()
+            }
+        );
+
+        let err = orch
+            .request(
+                "worker",
+                AclMessage::Heartbeat {
+                    agent_id: "orchestrator".into(),
+                },
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::Timeout { .. }), "{err}");
+        assert!(orch.replies.lock().unwrap().is_empty());
+        assert!(
+            orch.request(
+                "nobody",
+                AclMessage::Ping { text: "x".into() },
+                Duration::from_secs(1)
+            )
+            .await
+            .is_err()
+        );
+    }
 }
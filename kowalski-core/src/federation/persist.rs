@@ -41,7 +41,7 @@ pub async fn load_registry_into(
             .try_get("capabilities")
             .map_err(|e| KowalskiError::Federation(format!("registry load row: {e}")))?;
         let capabilities: Vec<String> = serde_json::from_value(caps_val).unwrap_or_default();
-        registry.register(AgentRecord { id, capabilities })?;
+        registry.register(AgentRecord::new(id, capabilities))?;
     }
     Ok(())
 }
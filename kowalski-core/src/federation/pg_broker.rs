@@ -62,7 +62,7 @@ impl PgBroker {
                 match listener.recv().await {
                     Ok(n) => {
                         let payload = n.payload();
-                        match serde_json::from_str::<AclEnvelope>(payload) {
+                        match AclEnvelope::decode(payload) {
                             Ok(env) => {
                                 if tx.send(env).await.is_err() {
                                     break;
@@ -0,0 +1,435 @@
+//! Scheduling many tasks over few workers: a priority [`TaskQueue`] drained by a [`Coordinator`]
+//! that dispatches to the least-loaded available agent, retries failures on another agent with
+//! backoff, and dead-letters tasks that run out of retries or time
+//! (see [`AgentRegistry::dead_letters`]).
+
+use crate::error::KowalskiError;
+use crate::federation::delegation::{
+    DeadLetter, TaskAttempt, TaskPriority, TaskResult, TaskSpec, TaskStatus, TaskTarget,
+};
+use crate::federation::orchestrator::FederationOrchestrator;
+use crate::federation::registry::AgentRegistry;
+use log::{debug, warn};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::sync::{Notify, oneshot};
+use tokio::task::JoinHandle;
+
+/// A task waiting in a [`TaskQueue`], with the attempts made so far.
+#[derive(Debug, Clone)]
+pub struct QueuedTask {
+    pub spec: TaskSpec,
+    pub attempts: Vec<TaskAttempt>,
+}
+
+impl QueuedTask {
+    pub fn new(spec: TaskSpec) -> Self {
+        Self {
+            spec,
+            attempts: Vec::new(),
+        }
+    }
+}
+
+struct Entry {
+    seq: u64,
+    task: QueuedTask,
+}
+
+impl Entry {
+    fn key(&self) -> (TaskPriority, Reverse<u64>) {
+        (self.task.spec.priority, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Highest [`TaskPriority`] first, FIFO within a priority.
+#[derive(Default)]
+pub struct TaskQueue {
+    heap: BinaryHeap<Entry>,
+    next_seq: u64,
+}
+
+impl TaskQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, task: QueuedTask) {
+        self.heap.push(Entry {
+            seq: self.next_seq,
+            task,
+        });
+        self.next_seq += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<QueuedTask> {
+        self.heap.pop().map(|e| e.task)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+type ResultSender = oneshot::Sender<Result<TaskResult, KowalskiError>>;
+
+/// Outcome of a [`Coordinator::submit`]: `.await` it, or poll with [`try_result`](Self::try_result).
+pub struct TaskHandle {
+    task_id: String,
+    registry: Arc<AgentRegistry>,
+    rx: oneshot::Receiver<Result<TaskResult, KowalskiError>>,
+}
+
+impl TaskHandle {
+    pub fn task_id(&self) -> &str {
+        &self.task_id
+    }
+
+    /// Current status in the registry.
+    pub fn status(&self) -> Option<TaskStatus> {
+        self.registry.task_status(&self.task_id)
+    }
+
+    /// `None` while the task is pending; the result is returned once.
+    pub fn try_result(&mut self) -> Option<Result<TaskResult, KowalskiError>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(oneshot::error::TryRecvError::Empty) => None,
+            Err(oneshot::error::TryRecvError::Closed) => Some(Err(coordinator_stopped())),
+        }
+    }
+}
+
+impl Future for TaskHandle {
+    type Output = Result<TaskResult, KowalskiError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx)
+            .poll(cx)
+            .map(|r| r.unwrap_or_else(|_| Err(coordinator_stopped())))
+    }
+}
+
+fn coordinator_stopped() -> KowalskiError {
+    KowalskiError::Federation("coordinator stopped before the task finished".to_string())
+}
+
+struct Inner {
+    orchestrator: Arc<FederationOrchestrator>,
+    queue: Mutex<TaskQueue>,
+    waiters: Mutex<HashMap<String, ResultSender>>,
+    in_flight: Mutex<HashMap<String, usize>>,
+    max_in_flight_per_agent: usize,
+    wake: Notify,
+}
+
+/// Runs submitted tasks through a [`FederationOrchestrator`] (which must be
+/// [listening for results](FederationOrchestrator::listen_for_results)).
+///
+/// Each agent runs at most `max_in_flight_per_agent` tasks at once; among available candidates for
+/// a task's target the one with the fewest in-flight tasks wins (registry rank breaks ties). A
+/// failed attempt is retried after `retry_backoff` (doubling), preferring agents not yet tried,
+/// until `max_retries` or the deadline is exhausted. An error that is not
+/// [retryable](KowalskiError::is_retryable) dead-letters the task at once.
+pub struct Coordinator {
+    inner: Arc<Inner>,
+    dispatcher: JoinHandle<()>,
+}
+
+impl Coordinator {
+    pub fn new(orchestrator: Arc<FederationOrchestrator>, max_in_flight_per_agent: usize) -> Self {
+        let inner = Arc::new(Inner {
+            orchestrator,
+            queue: Mutex::new(TaskQueue::new()),
+            waiters: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            max_in_flight_per_agent: max_in_flight_per_agent.max(1),
+            wake: Notify::new(),
+        });
+        let dispatcher = {
+            let inner = inner.clone();
+            tokio::spawn(async move {
+                loop {
+                    inner.wake.notified().await;
+                    inner.dispatch_ready();
+                }
+            })
+        };
+        Self { inner, dispatcher }
+    }
+
+    /// Queues `task` and returns a handle to its result.
+    pub fn submit(&self, task: TaskSpec) -> TaskHandle {
+        let registry = &self.inner.orchestrator.registry;
+        let _ = registry.set_task_status(&task.task_id, "", TaskStatus::Queued, None);
+        self.track(QueuedTask::new(task))
+    }
+
+    /// Re-queues tasks interrupted by a restart (see [`AgentRegistry::recover`]), keeping their
+    /// earlier attempts.
+    pub fn resume(&self, tasks: Vec<QueuedTask>) -> Vec<TaskHandle> {
+        tasks.into_iter().map(|task| self.track(task)).collect()
+    }
+
+    fn track(&self, task: QueuedTask) -> TaskHandle {
+        let (tx, rx) = oneshot::channel();
+        let task_id = task.spec.task_id.clone();
+        let registry = self.inner.orchestrator.registry.clone();
+        registry.record_queued_task(&task);
+        self.inner
+            .waiters
+            .lock()
+            .expect("coordinator waiters lock")
+            .insert(task_id.clone(), tx);
+        self.inner.enqueue(task);
+        TaskHandle {
+            task_id,
+            registry,
+            rx,
+        }
+    }
+
+    /// Tasks waiting for a worker (not counting retries in backoff).
+    pub fn queued(&self) -> usize {
+        self.inner
+            .queue
+            .lock()
+            .expect("coordinator queue lock")
+            .len()
+    }
+
+    pub fn in_flight(&self, agent_id: &str) -> usize {
+        self.inner.load(agent_id)
+    }
+}
+
+impl Drop for Coordinator {
+    fn drop(&mut self) {
+        self.dispatcher.abort();
+    }
+}
+
+impl Inner {
+    fn enqueue(&self, task: QueuedTask) {
+        self.queue
+            .lock()
+            .expect("coordinator queue lock")
+            .push(task);
+        self.wake.notify_one();
+    }
+
+    fn load(&self, agent_id: &str) -> usize {
+        self.in_flight
+            .lock()
+            .expect("coordinator in-flight lock")
+            .get(agent_id)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn adjust_load(&self, agent_id: &str, started: bool) {
+        let mut in_flight = self.in_flight.lock().expect("coordinator in-flight lock");
+        let n = in_flight.entry(agent_id.to_string()).or_default();
+        if started {
+            *n += 1;
+        } else {
+            *n = n.saturating_sub(1);
+        }
+    }
+
+    /// Starts every queued task that has a free agent, in priority order.
+    fn dispatch_ready(self: &Arc<Self>) {
+        let mut blocked = Vec::new();
+        loop {
+            let Some(task) = self.queue.lock().expect("coordinator queue lock").pop() else {
+                break;
+            };
+            if task.spec.deadline.is_some_and(|d| Instant::now() >= d) {
+                self.dead_letter(task, "deadline passed before dispatch".to_string());
+                continue;
+            }
+            match self.pick_agent(&task) {
+                Ok(Some(agent_id)) => {
+                    self.adjust_load(&agent_id, true);
+                    tokio::spawn(self.clone().run_attempt(task, agent_id));
+                }
+                Ok(None) => blocked.push(task),
+                Err(e) => self.fail_attempt(task, String::new(), e),
+            }
+        }
+        let mut queue = self.queue.lock().expect("coordinator queue lock");
+        for task in blocked {
+            queue.push(task);
+        }
+    }
+
+    /// Least-loaded free candidate, preferring agents this task has not failed on.
+    /// `Ok(None)` when every candidate is busy.
+    fn pick_agent(&self, task: &QueuedTask) -> Result<Option<String>, KowalskiError> {
+        let registry = &self.orchestrator.registry;
+        let candidates = registry.candidates(&task.spec.target);
+        if candidates.is_empty() {
+            return Err(registry
+                .resolve(&task.spec.target)
+                .err()
+                .unwrap_or_else(|| KowalskiError::NotFound("no agent available".to_string())));
+        }
+        let untried: Vec<String> = candidates
+            .iter()
+            .filter(|a| !task.attempts.iter().any(|t| t.agent_id == a.id))
+            .map(|a| a.id.clone())
+            .collect();
+        let pool = if untried.is_empty() {
+            candidates.into_iter().map(|a| a.id).collect()
+        } else {
+            untried
+        };
+        Ok(pool
+            .into_iter()
+            .map(|id| (self.load(&id), id))
+            .filter(|(load, _)| *load < self.max_in_flight_per_agent)
+            .min_by_key(|(load, _)| *load)
+            .map(|(_, id)| id))
+    }
+
+    async fn run_attempt(self: Arc<Self>, task: QueuedTask, agent_id: String) {
+        let mut spec = task.spec.clone();
+        spec.target = TaskTarget::Agent(agent_id.clone());
+        if let Some(deadline) = spec.deadline {
+            spec.timeout = spec
+                .timeout
+                .min(deadline.saturating_duration_since(Instant::now()));
+        }
+        let result = self.orchestrator.delegate(spec).await;
+        self.adjust_load(&agent_id, false);
+        match result {
+            Ok(result) => self.finish(&task.spec.task_id, Ok(result)),
+            Err(e) => self.fail_attempt(task, agent_id, e),
+        }
+        self.wake.notify_one();
+    }
+
+    fn fail_attempt(
+        self: &Arc<Self>,
+        mut task: QueuedTask,
+        agent_id: String,
+        error: KowalskiError,
+    ) {
+        let task_id = task.spec.task_id.clone();
+        warn!("task {task_id} attempt on '{agent_id}' failed: {error}");
+        task.attempts.push(TaskAttempt {
+            agent_id,
+            error: error.to_string(),
+        });
+        let attempts = task.attempts.len() as u32;
+        // `NotFound`: the picked agent left before the attempt started; another one may take it.
+        if !error.is_retryable() && !matches!(error, KowalskiError::NotFound(_)) {
+            self.dead_letter(task, format!("not retryable: {error}"));
+            return;
+        }
+        if attempts > task.spec.max_retries {
+            let reason = format!("gave up after {attempts} attempt(s): {error}");
+            self.dead_letter(task, reason);
+            return;
+        }
+        let backoff = task.spec.retry_backoff * 2u32.saturating_pow(attempts - 1);
+        if task
+            .spec
+            .deadline
+            .is_some_and(|d| Instant::now() + backoff >= d)
+        {
+            self.dead_letter(task, format!("deadline passed after: {error}"));
+            return;
+        }
+        debug!("retrying task {task_id} in {backoff:?}");
+        let _ = self.orchestrator.registry.set_task_status(
+            &task_id,
+            "",
+            TaskStatus::Queued,
+            Some(error.to_string()),
+        );
+        self.orchestrator.registry.record_queued_task(&task);
+        let inner = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(backoff).await;
+            inner.enqueue(task);
+        });
+    }
+
+    fn dead_letter(&self, task: QueuedTask, error: String) {
+        let task_id = task.spec.task_id.clone();
+        warn!("task {task_id} dead-lettered: {error}");
+        let _ = self.orchestrator.registry.record_dead_letter(DeadLetter {
+            task_id: task_id.clone(),
+            instruction: task.spec.instruction,
+            attempts: task.attempts,
+            error: error.clone(),
+        });
+        self.finish(&task_id, Err(KowalskiError::Federation(error)));
+    }
+
+    fn finish(&self, task_id: &str, result: Result<TaskResult, KowalskiError>) {
+        let waiter = self
+            .waiters
+            .lock()
+            .expect("coordinator waiters lock")
+            .remove(task_id);
+        if let Some(tx) = waiter {
+            let _ = tx.send(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_by_priority_then_submission_order() {
+        let mut q = TaskQueue::new();
+        for (id, priority) in [
+            ("low", TaskPriority::Low),
+            ("normal-1", TaskPriority::Normal),
+            ("high", TaskPriority::High),
+            ("normal-2", TaskPriority::Normal),
+        ] {
+            q.push(QueuedTask::new(
+                TaskSpec::for_capability("x", id)
+                    .with_task_id(id)
+                    .with_priority(priority),
+            ));
+        }
+        let order: Vec<String> = std::iter::from_fn(|| q.pop().map(|t| t.spec.task_id)).collect();
+        assert_eq!(order, ["high", "normal-1", "normal-2", "low"]);
+        assert!(q.is_empty());
+    }
+}
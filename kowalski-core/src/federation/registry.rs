@@ -1,41 +1,287 @@
 //! In-memory [`AgentRegistry`] for capability-based discovery (Phase 1).
 //!
-//! Postgres-backed persistence can reuse the same record shape later.
+//! With a [`RegistryStore`] attached, registrations and task state are also written to SQLite and
+//! reloaded by [`AgentRegistry::recover`] after a restart.
 
+use crate::agent::Agent;
+use crate::config::FederationConfig;
 use crate::error::KowalskiError;
+use crate::federation::acl::{AclEnvelope, AclMessage};
+use crate::federation::delegation::{DeadLetter, TaskRecord, TaskResult, TaskStatus, TaskTarget};
+use crate::federation::queue::QueuedTask;
+use crate::federation::store::{RegistryStore, StoreOp, StoredTaskSpec};
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
 
 /// Registered agent metadata (no live handles — orchestration wires those separately).
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AgentRecord {
     pub id: String,
     pub capabilities: Vec<String>,
+    /// Names of the tools the agent can run (see [`AgentRegistry::find_by_tool`]).
+    #[serde(default)]
+    pub tools: Vec<String>,
 }
 
-/// Process-local registry (thread-safe).
+impl AgentRecord {
+    pub fn new(id: impl Into<String>, capabilities: Vec<String>) -> Self {
+        Self {
+            id: id.into(),
+            capabilities,
+            tools: Vec::new(),
+        }
+    }
+
+    /// Records `tools` and adds each tool name to the capability list (if not already present).
+    pub fn with_tools(mut self, tools: Vec<String>) -> Self {
+        for tool in &tools {
+            if !self
+                .capabilities
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(tool))
+            {
+                self.capabilities.push(tool.clone());
+            }
+        }
+        self.tools = tools;
+        self
+    }
+}
+
+/// Liveness of a registered agent, driven by heartbeats (see [`AgentRegistry::check_liveness`]).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStatus {
+    Active,
+    /// Missed too many heartbeats; skipped when routing tasks until it beats again.
+    Unresponsive,
+}
+
+/// Membership changes, broadcast to [`AgentRegistry::subscribe_events`] receivers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RegistryEvent {
+    AgentJoined { agent_id: String },
+    AgentLeft { agent_id: String },
+    AgentUnresponsive { agent_id: String },
+}
+
+/// When silent agents are marked [`AgentStatus::Unresponsive`] and when they are removed.
+#[derive(Debug, Clone)]
+pub struct LivenessPolicy {
+    /// Expected time between heartbeats.
+    pub heartbeat_interval: Duration,
+    pub unresponsive_after_missed: u32,
+    pub remove_after_missed: u32,
+}
+
+impl Default for LivenessPolicy {
+    fn default() -> Self {
+        Self::from_config(&FederationConfig::default())
+    }
+}
+
+impl LivenessPolicy {
+    pub fn from_config(config: &FederationConfig) -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(config.heartbeat_secs.max(1)),
+            unresponsive_after_missed: config.unresponsive_after_missed,
+            remove_after_missed: config.remove_after_missed,
+        }
+    }
+
+    fn missed(&self, since: Duration) -> u32 {
+        let interval = self.heartbeat_interval.as_millis().max(1);
+        (since.as_millis() / interval).min(u32::MAX as u128) as u32
+    }
+}
+
+/// Process-local registry (thread-safe). Also tracks the status of delegated tasks and the
+/// liveness of each agent.
 #[derive(Clone)]
 pub struct AgentRegistry {
     inner: Arc<RwLock<HashMap<String, AgentRecord>>>,
+    tasks: Arc<RwLock<HashMap<String, TaskRecord>>>,
+    /// agent id → (status, last heartbeat or registration).
+    liveness: Arc<RwLock<HashMap<String, (AgentStatus, Instant)>>>,
+    events: broadcast::Sender<RegistryEvent>,
+    dead_letters: Arc<RwLock<Vec<DeadLetter>>>,
+    store: Option<RegistryStore>,
+}
+
+/// What [`AgentRegistry::recover`] restored.
+#[derive(Debug, Clone, Default)]
+pub struct RecoveredState {
+    /// Registrations reloaded (ids, sorted).
+    pub agents: Vec<String>,
+    /// Registrations dropped because their last heartbeat was too old.
+    pub expired: Vec<String>,
+    /// Interrupted [`Coordinator`](crate::federation::Coordinator) tasks, back in
+    /// [`TaskStatus::Queued`]; pass them to
+    /// [`Coordinator::resume`](crate::federation::Coordinator::resume).
+    pub pending: Vec<QueuedTask>,
 }
 
 impl AgentRegistry {
     pub fn new() -> Self {
         Self {
             inner: Arc::new(RwLock::new(HashMap::new())),
+            tasks: Arc::new(RwLock::new(HashMap::new())),
+            liveness: Arc::new(RwLock::new(HashMap::new())),
+            events: broadcast::channel(64).0,
+            dead_letters: Arc::new(RwLock::new(Vec::new())),
+            store: None,
         }
     }
 
+    /// Persists registrations and task state to `store` from now on (see [`Self::recover`]).
+    pub fn with_store(mut self, store: RegistryStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Registry persisted at [`FederationConfig::persistence_path`] (in-memory when unset).
+    /// Call [`recover`](Self::recover) to reload the previous state.
+    pub async fn from_config(config: &FederationConfig) -> Result<Self, KowalskiError> {
+        let registry = Self::new();
+        Ok(match RegistryStore::from_config(config).await? {
+            Some(store) => registry.with_store(store),
+            None => registry,
+        })
+    }
+
+    fn persist(&self, op: StoreOp) {
+        if let Some(store) = &self.store {
+            store.send(op);
+        }
+    }
+
+    /// Waits until all changes are written to the attached store (no-op without one).
+    pub async fn flush(&self) {
+        if let Some(store) = &self.store {
+            store.flush().await;
+        }
+    }
+
+    /// Reloads registrations and tasks from the attached store. Registrations silent for longer
+    /// than [`RegistryStore::stale_after`] are dropped; the rest keep the age of their last
+    /// heartbeat for the liveness monitor. Tasks that were Queued or Running go back to
+    /// [`TaskStatus::Queued`] and, when their spec was kept, are returned for resubmission;
+    /// the others are marked Failed. Without a store this returns an empty state.
+    pub async fn recover(&self) -> Result<RecoveredState, KowalskiError> {
+        let Some(store) = &self.store else {
+            return Ok(RecoveredState::default());
+        };
+        let stored = store.load().await?;
+        let mut recovered = RecoveredState {
+            expired: stored.expired,
+            ..Default::default()
+        };
+        for id in &recovered.expired {
+            info!("federation agent {id} expired while the registry was down");
+        }
+        let now = Instant::now();
+        for (record, age) in stored.agents {
+            let id = record.id.clone();
+            let joined = {
+                let mut g = self.inner.write().map_err(|e| {
+                    KowalskiError::Federation(format!("registry lock poisoned: {e}"))
+                })?;
+                g.insert(id.clone(), record).is_none()
+            };
+            if let Ok(mut l) = self.liveness.write() {
+                let seen = now.checked_sub(age).unwrap_or(now);
+                l.insert(id.clone(), (AgentStatus::Active, seen));
+            }
+            if joined {
+                self.emit(RegistryEvent::AgentJoined {
+                    agent_id: id.clone(),
+                });
+            }
+            recovered.agents.push(id);
+        }
+
+        for task in stored.tasks {
+            let mut record = task.record;
+            if let Some(letter) = task.dead_letter
+                && let Ok(mut g) = self.dead_letters.write()
+            {
+                g.push(letter);
+            }
+            if matches!(record.status, TaskStatus::Queued | TaskStatus::Running) {
+                match &task.spec {
+                    Some(spec) => {
+                        record.status = TaskStatus::Queued;
+                        record.agent_id.clear();
+                        recovered.pending.push(QueuedTask {
+                            spec: spec.to_spec(&record.task_id),
+                            attempts: task.attempts,
+                        });
+                    }
+                    None => {
+                        record.status = TaskStatus::Failed;
+                        record.error = Some("interrupted by a registry restart".to_string());
+                    }
+                }
+                self.persist(StoreOp::TaskStatus(record.clone()));
+            }
+            if let Ok(mut g) = self.tasks.write() {
+                g.insert(record.task_id.clone(), record);
+            }
+        }
+        info!(
+            "federation registry recovered {} agent(s), {} pending task(s)",
+            recovered.agents.len(),
+            recovered.pending.len()
+        );
+        Ok(recovered)
+    }
+
+    /// Adds or replaces `record`. Registration counts as a heartbeat; a new id emits
+    /// [`RegistryEvent::AgentJoined`].
     pub fn register(&self, record: AgentRecord) -> Result<(), KowalskiError> {
-        let mut g = self
-            .inner
-            .write()
-            .map_err(|e| KowalskiError::Federation(format!("registry lock poisoned: {e}")))?;
-        g.insert(record.id.clone(), record);
+        let id = record.id.clone();
+        let joined = {
+            let mut g = self
+                .inner
+                .write()
+                .map_err(|e| KowalskiError::Federation(format!("registry lock poisoned: {e}")))?;
+            g.insert(id.clone(), record).is_none()
+        };
+        self.set_liveness(&id, AgentStatus::Active)?;
+        if let Some(record) = self.get(&id) {
+            self.persist(StoreOp::UpsertAgent(record));
+        }
+        if joined {
+            self.emit(RegistryEvent::AgentJoined { agent_id: id });
+        }
         Ok(())
     }
 
+    /// Registers a live agent: its capabilities are the manual `tags` plus the names of the tools
+    /// it reports via [`Agent::list_tools`].
+    pub async fn register_agent<A: Agent + ?Sized>(
+        &self,
+        id: &str,
+        agent: &A,
+        tags: &[&str],
+    ) -> Result<AgentRecord, KowalskiError> {
+        let tools = agent
+            .list_tools()
+            .await
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let record =
+            AgentRecord::new(id, tags.iter().map(|t| t.to_string()).collect()).with_tools(tools);
+        self.register(record.clone())?;
+        Ok(record)
+    }
+
     pub fn deregister(&self, id: &str) -> Result<(), KowalskiError> {
         let mut g = self
             .inner
@@ -43,9 +289,123 @@ impl AgentRegistry {
             .map_err(|e| KowalskiError::Federation(format!("registry lock poisoned: {e}")))?;
         g.remove(id)
             .ok_or_else(|| KowalskiError::NotFound(format!("agent {id}")))?;
+        drop(g);
+        if let Ok(mut l) = self.liveness.write() {
+            l.remove(id);
+        }
+        self.persist(StoreOp::RemoveAgent(id.to_string()));
+        self.emit(RegistryEvent::AgentLeft {
+            agent_id: id.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Receives every [`RegistryEvent`] emitted after this call.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<RegistryEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit(&self, event: RegistryEvent) {
+        debug!("registry event {event:?}");
+        // No receivers is fine.
+        let _ = self.events.send(event);
+    }
+
+    fn set_liveness(&self, id: &str, status: AgentStatus) -> Result<(), KowalskiError> {
+        self.liveness
+            .write()
+            .map_err(|e| KowalskiError::Federation(format!("registry lock poisoned: {e}")))?
+            .insert(id.to_string(), (status, Instant::now()));
         Ok(())
     }
 
+    /// Records a heartbeat from `id`; an unresponsive agent becomes [`AgentStatus::Active`] again.
+    pub fn heartbeat(&self, id: &str) -> Result<(), KowalskiError> {
+        if self.get(id).is_none() {
+            return Err(KowalskiError::NotFound(format!("agent {id}")));
+        }
+        self.set_liveness(id, AgentStatus::Active)?;
+        self.persist(StoreOp::Touch(id.to_string()));
+        Ok(())
+    }
+
+    pub fn agent_status(&self, id: &str) -> Option<AgentStatus> {
+        self.liveness
+            .read()
+            .ok()?
+            .get(id)
+            .map(|(status, _)| *status)
+    }
+
+    /// Registered and not [`AgentStatus::Unresponsive`].
+    pub fn is_available(&self, id: &str) -> bool {
+        self.get(id).is_some() && self.agent_status(id) != Some(AgentStatus::Unresponsive)
+    }
+
+    /// Marks agents that missed `unresponsive_after_missed` heartbeats as unresponsive and removes
+    /// those that missed `remove_after_missed`. Returns the events emitted by this sweep.
+    pub fn check_liveness(&self, policy: &LivenessPolicy) -> Vec<RegistryEvent> {
+        let now = Instant::now();
+        let mut events = Vec::new();
+        let mut remove = Vec::new();
+        if let Ok(mut l) = self.liveness.write() {
+            for (id, (status, seen)) in l.iter_mut() {
+                let missed = policy.missed(now.saturating_duration_since(*seen));
+                if missed >= policy.remove_after_missed {
+                    remove.push(id.clone());
+                } else if missed >= policy.unresponsive_after_missed
+                    && *status == AgentStatus::Active
+                {
+                    *status = AgentStatus::Unresponsive;
+                    warn!("federation agent {id} unresponsive ({missed} missed heartbeats)");
+                    events.push(RegistryEvent::AgentUnresponsive {
+                        agent_id: id.clone(),
+                    });
+                }
+            }
+        }
+        for event in &events {
+            self.emit(event.clone());
+        }
+        remove.sort();
+        for id in remove {
+            if self.deregister(&id).is_ok() {
+                info!("federation agent {id} removed after missing heartbeats");
+                events.push(RegistryEvent::AgentLeft { agent_id: id });
+            }
+        }
+        events
+    }
+
+    /// Runs [`check_liveness`](Self::check_liveness) once per heartbeat interval.
+    pub fn spawn_liveness_monitor(&self, policy: LivenessPolicy) -> tokio::task::JoinHandle<()> {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(policy.heartbeat_interval);
+            loop {
+                tick.tick().await;
+                registry.check_liveness(&policy);
+            }
+        })
+    }
+
+    /// Records [`AclMessage::Heartbeat`]s from a subscription on the federation topic.
+    pub fn track_heartbeats(
+        &self,
+        mut rx: mpsc::Receiver<AclEnvelope>,
+    ) -> tokio::task::JoinHandle<()> {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            while let Some(env) = rx.recv().await {
+                if let AclMessage::Heartbeat { agent_id } = &env.payload
+                    && let Err(e) = registry.heartbeat(agent_id)
+                {
+                    debug!("heartbeat ignored: {e}");
+                }
+            }
+        })
+    }
+
     pub fn get(&self, id: &str) -> Option<AgentRecord> {
         self.inner.read().ok()?.get(id).cloned()
     }
@@ -78,6 +438,209 @@ impl AgentRegistry {
         });
         v
     }
+
+    /// Agents that registered the tool `name` (exact, case-insensitive).
+    pub fn find_by_tool(&self, name: &str) -> Vec<AgentRecord> {
+        let mut v: Vec<AgentRecord> = self
+            .list()
+            .into_iter()
+            .filter(|a| a.tools.iter().any(|t| t.eq_ignore_ascii_case(name)))
+            .collect();
+        v.sort_by(|a, b| a.id.cmp(&b.id));
+        v
+    }
+
+    /// Available agent whose capabilities and tools share the most keywords with
+    /// `task_description` (`sql_query` contributes `sql` and `query`). `None` when nothing matches.
+    pub fn best_match(&self, task_description: &str) -> Option<AgentRecord> {
+        self.ranked_matches(task_description).into_iter().next()
+    }
+
+    /// Available agents with at least one keyword in common with `task_description`, best first.
+    fn ranked_matches(&self, task_description: &str) -> Vec<AgentRecord> {
+        let words: HashSet<String> = keywords(task_description).collect();
+        let mut scored: Vec<(usize, AgentRecord)> = self
+            .list()
+            .into_iter()
+            .filter(|a| self.is_available(&a.id))
+            .map(|a| {
+                let tokens: HashSet<String> = a
+                    .capabilities
+                    .iter()
+                    .chain(&a.tools)
+                    .flat_map(|c| keywords(c))
+                    .collect();
+                let score = tokens.intersection(&words).count();
+                (score, a)
+            })
+            .filter(|(score, _)| *score > 0)
+            .collect();
+        scored.sort_by(|(sa, a), (sb, b)| sb.cmp(sa).then_with(|| a.id.cmp(&b.id)));
+        scored.into_iter().map(|(_, a)| a).collect()
+    }
+
+    /// Every available agent a [`TaskTarget`] could run on, best first (see [`resolve`](Self::resolve)).
+    pub fn candidates(&self, target: &TaskTarget) -> Vec<AgentRecord> {
+        match target {
+            TaskTarget::Agent(id) => self
+                .get(id)
+                .filter(|_| self.is_available(id))
+                .into_iter()
+                .collect(),
+            TaskTarget::Capability(cap) => self
+                .find_ranked_by_capability(cap)
+                .into_iter()
+                .filter(|a| self.is_available(&a.id))
+                .collect(),
+            TaskTarget::BestMatch(description) => self.ranked_matches(description),
+        }
+    }
+
+    /// Agent a [`TaskTarget`] resolves to: the named agent, the best-ranked capability match,
+    /// or the [`best_match`](Self::best_match) for a task description. Unresponsive agents are
+    /// skipped (a named one is an error).
+    pub fn resolve(&self, target: &TaskTarget) -> Result<AgentRecord, KowalskiError> {
+        if let Some(agent) = self.candidates(target).into_iter().next() {
+            return Ok(agent);
+        }
+        Err(match target {
+            TaskTarget::Agent(id) if self.get(id).is_some() => KowalskiError::AgentFailed {
+                agent_id: id.clone(),
+                reason: "unresponsive".to_string(),
+            },
+            TaskTarget::Agent(id) => KowalskiError::NotFound(format!("agent {id}")),
+            TaskTarget::Capability(cap) => {
+                KowalskiError::NotFound(format!("no agent with capability '{cap}'"))
+            }
+            TaskTarget::BestMatch(description) => {
+                KowalskiError::NotFound(format!("no agent matches task '{description}'"))
+            }
+        })
+    }
+
+    /// Records (or updates) the status of a delegated task.
+    pub fn set_task_status(
+        &self,
+        task_id: &str,
+        agent_id: &str,
+        status: TaskStatus,
+        error: Option<String>,
+    ) -> Result<(), KowalskiError> {
+        let mut g = self
+            .tasks
+            .write()
+            .map_err(|e| KowalskiError::Federation(format!("registry lock poisoned: {e}")))?;
+        let record = TaskRecord {
+            task_id: task_id.to_string(),
+            agent_id: agent_id.to_string(),
+            status,
+            error,
+        };
+        g.insert(task_id.to_string(), record.clone());
+        drop(g);
+        self.persist(StoreOp::TaskStatus(record));
+        Ok(())
+    }
+
+    /// Moves `task_id` from Queued to Running on `agent_id`, or records it as Running when the
+    /// registry does not track it. Returns `false` and changes nothing when the task is queued
+    /// for another agent, already running, or finished: a late or duplicate delivery must not
+    /// revive a task the orchestrator has given up on.
+    pub fn start_task(&self, task_id: &str, agent_id: &str) -> Result<bool, KowalskiError> {
+        let mut g = self
+            .tasks
+            .write()
+            .map_err(|e| KowalskiError::Federation(format!("registry lock poisoned: {e}")))?;
+        if let Some(current) = g.get(task_id)
+            && (current.status != TaskStatus::Queued
+                || !(current.agent_id.is_empty() || current.agent_id == agent_id))
+        {
+            return Ok(false);
+        }
+        let record = TaskRecord {
+            task_id: task_id.to_string(),
+            agent_id: agent_id.to_string(),
+            status: TaskStatus::Running,
+            error: None,
+        };
+        g.insert(task_id.to_string(), record.clone());
+        drop(g);
+        self.persist(StoreOp::TaskStatus(record));
+        Ok(true)
+    }
+
+    /// Keeps a queued task's spec and attempts in the attached store so [`Self::recover`] can
+    /// resume it (no-op without a store).
+    pub fn record_queued_task(&self, task: &QueuedTask) {
+        self.persist(StoreOp::TaskSpec {
+            task_id: task.spec.task_id.clone(),
+            spec: StoredTaskSpec::from(&task.spec),
+            attempts: task.attempts.clone(),
+        });
+    }
+
+    /// Keeps a completed task's answer in the attached store (no-op without a store).
+    pub fn record_task_result(&self, result: &TaskResult) {
+        self.persist(StoreOp::TaskAnswer {
+            task_id: result.task_id.clone(),
+            answer: result.answer.clone(),
+        });
+    }
+
+    pub fn task(&self, task_id: &str) -> Option<TaskRecord> {
+        self.tasks.read().ok()?.get(task_id).cloned()
+    }
+
+    pub fn task_status(&self, task_id: &str) -> Option<TaskStatus> {
+        self.task(task_id).map(|t| t.status)
+    }
+
+    /// Records a task that exhausted its retries (also marks it [`TaskStatus::Failed`]).
+    pub fn record_dead_letter(&self, letter: DeadLetter) -> Result<(), KowalskiError> {
+        let agent_id = letter
+            .attempts
+            .last()
+            .map(|a| a.agent_id.clone())
+            .unwrap_or_default();
+        self.set_task_status(
+            &letter.task_id,
+            &agent_id,
+            TaskStatus::Failed,
+            Some(letter.error.clone()),
+        )?;
+        self.persist(StoreOp::DeadLetter(letter.clone()));
+        self.dead_letters
+            .write()
+            .map_err(|e| KowalskiError::Federation(format!("registry lock poisoned: {e}")))?
+            .push(letter);
+        Ok(())
+    }
+
+    /// Tasks that failed permanently, oldest first.
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters
+            .read()
+            .map(|g| g.clone())
+            .unwrap_or_default()
+    }
+
+    /// All tracked tasks, ordered by task id.
+    pub fn list_tasks(&self) -> Vec<TaskRecord> {
+        let mut v: Vec<TaskRecord> = self
+            .tasks
+            .read()
+            .map(|g| g.values().cloned().collect())
+            .unwrap_or_default();
+        v.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+        v
+    }
+}
+
+/// Lowercase alphanumeric words of at least three characters.
+fn keywords(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| w.len() >= 3)
+        .map(str::to_lowercase)
 }
 
 fn capability_match_score(agent: &AgentRecord, c: &str) -> i32 {
@@ -107,10 +670,10 @@ mod tests {
     #[test]
     fn register_and_find() {
         let r = AgentRegistry::new();
-        r.register(AgentRecord {
-            id: "a1".into(),
-            capabilities: vec!["web_search".into(), "pdf".into()],
-        })
+        r.register(AgentRecord::new(
+            "a1",
+            vec!["web_search".into(), "pdf".into()],
+        ))
         .unwrap();
         let hits = r.find_by_capability("web");
         assert_eq!(hits.len(), 1);
@@ -120,18 +683,142 @@ mod tests {
     #[test]
     fn ranked_prefers_exact_capability() {
         let r = AgentRegistry::new();
-        r.register(AgentRecord {
-            id: "broad".into(),
-            capabilities: vec!["chat_assistant".into()],
-        })
-        .unwrap();
-        r.register(AgentRecord {
-            id: "exact".into(),
-            capabilities: vec!["chat".into(), "mcp".into()],
-        })
-        .unwrap();
+        r.register(AgentRecord::new("broad", vec!["chat_assistant".into()]))
+            .unwrap();
+        r.register(AgentRecord::new("exact", vec!["chat".into(), "mcp".into()]))
+            .unwrap();
         let ranked = r.find_ranked_by_capability("chat");
         assert_eq!(ranked[0].id, "exact");
         assert_eq!(ranked[1].id, "broad");
     }
+
+    fn data_and_web_agents() -> AgentRegistry {
+        let r = AgentRegistry::new();
+        r.register(
+            AgentRecord::new("data", vec!["csv".into(), "statistics".into()])
+                .with_tools(vec!["sql_query".into(), "fs_tool".into()]),
+        )
+        .unwrap();
+        r.register(
+            AgentRecord::new("web", vec!["web".into(), "search".into()])
+                .with_tools(vec!["html_to_markdown".into(), "web_search".into()]),
+        )
+        .unwrap();
+        r
+    }
+
+    #[test]
+    fn discovers_by_capability_tool_and_description() {
+        let r = data_and_web_agents();
+        assert_eq!(r.find_by_capability("csv")[0].id, "data");
+        assert_eq!(r.find_by_capability("sql")[0].id, "data");
+        assert_eq!(r.find_by_tool("FS_TOOL")[0].id, "data");
+        assert!(r.find_by_tool("fs").is_empty());
+        assert_eq!(r.find_by_tool("web_search")[0].id, "web");
+
+        let best = |d: &str| r.best_match(d).map(|a| a.id);
+        assert_eq!(
+            best("Analyze the sales CSV and compute statistics").as_deref(),
+            Some("data")
+        );
+        assert_eq!(
+            best("Search the web and convert the page to markdown").as_deref(),
+            Some("web")
+        );
+        assert_eq!(best("Write a haiku"), None);
+
+        let target = TaskTarget::BestMatch("run a sql query over orders".into());
+        assert_eq!(r.resolve(&target).unwrap().id, "data");
+        assert!(matches!(
+            r.resolve(&TaskTarget::Capability("audio".into())),
+            Err(KowalskiError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn liveness_marks_unresponsive_then_removes() {
+        let r = AgentRegistry::new();
+        let mut events = r.subscribe_events();
+        r.register(AgentRecord::new("quiet", vec!["chat".into()]))
+            .unwrap();
+        r.register(AgentRecord::new("backup", vec!["chat".into()]))
+            .unwrap();
+        let policy = LivenessPolicy {
+            heartbeat_interval: Duration::from_millis(20),
+            unresponsive_after_missed: 2,
+            remove_after_missed: 5,
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        r.heartbeat("backup").unwrap();
+        assert_eq!(
+            r.check_liveness(&policy),
+            vec![RegistryEvent::AgentUnresponsive {
+                agent_id: "quiet".into()
+            }]
+        );
+        assert_eq!(r.agent_status("quiet"), Some(AgentStatus::Unresponsive));
+        let target = TaskTarget::Capability("chat".into());
+        assert_eq!(r.resolve(&target).unwrap().id, "backup");
+        assert!(r.resolve(&TaskTarget::Agent("quiet".into())).is_err());
+
+        std::thread::sleep(Duration::from_millis(60));
+        r.heartbeat("backup").unwrap();
+        r.check_liveness(&policy);
+        assert!(r.get("quiet").is_none());
+        assert!(r.heartbeat("quiet").is_err());
+
+        let seen: Vec<RegistryEvent> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert_eq!(
+            seen,
+            vec![
+                RegistryEvent::AgentJoined {
+                    agent_id: "quiet".into()
+                },
+                RegistryEvent::AgentJoined {
+                    agent_id: "backup".into()
+                },
+                RegistryEvent::AgentUnresponsive {
+                    agent_id: "quiet".into()
+                },
+                RegistryEvent::AgentLeft {
+                    agent_id: "quiet".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn tracks_task_status() {
+        let r = AgentRegistry::new();
+        assert_eq!(r.task_status("t1"), None);
+        r.set_task_status("t1", "a1", TaskStatus::Queued, None)
+            .unwrap();
+        r.set_task_status("t1", "a1", TaskStatus::Failed, Some("boom".into()))
+            .unwrap();
+        let t = r.task("t1").unwrap();
+        assert_eq!(t.status, TaskStatus::Failed);
+        assert_eq!(t.error.as_deref(), Some("boom"));
+        assert_eq!(r.list_tasks().len(), 1);
+    }
+
+    #[test]
+    fn only_queued_tasks_start_running() {
+        let r = AgentRegistry::new();
+        assert!(r.start_task("untracked", "a1").unwrap());
+        assert_eq!(r.task_status("untracked"), Some(TaskStatus::Running));
+
+        r.set_task_status("t1", "a1", TaskStatus::Queued, None)
+            .unwrap();
+        assert!(!r.start_task("t1", "a2").unwrap());
+        assert!(r.start_task("t1", "a1").unwrap());
+        assert!(!r.start_task("t1", "a1").unwrap());
+
+        r.set_task_status("t1", "a1", TaskStatus::Failed, Some("timed out".into()))
+            .unwrap();
+        assert!(!r.start_task("t1", "a1").unwrap());
+        let t = r.task("t1").unwrap();
+        assert_eq!(t.status, TaskStatus::Failed);
+        assert_eq!(t.error.as_deref(), Some("timed out"));
+    }
 }
@@ -0,0 +1,377 @@
+//! SQLite persistence for the [`AgentRegistry`](crate::federation::AgentRegistry): registrations
+//! and task state are written on change and reloaded by
+//! [`AgentRegistry::recover`](crate::federation::AgentRegistry::recover) after a restart.
+//!
+//! Writes go through a background task so the registry's synchronous API stays unchanged; call
+//! [`RegistryStore::flush`] to wait for them.
+
+use crate::config::FederationConfig;
+use crate::error::KowalskiError;
+use crate::federation::delegation::{
+    DeadLetter, TaskAttempt, TaskPriority, TaskRecord, TaskSpec, TaskTarget,
+};
+use crate::federation::registry::{AgentRecord, LivenessPolicy};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+
+/// Schema for the federation state file (same as `migrations/sqlite/004_federation_state.sql`).
+const FEDERATION_STATE_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS federation_agents (
+    agent_id TEXT PRIMARY KEY NOT NULL,
+    record TEXT NOT NULL,
+    last_seen INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS federation_tasks (
+    task_id TEXT PRIMARY KEY NOT NULL,
+    payload TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+"#;
+
+fn store_err(e: sqlx::Error) -> KowalskiError {
+    KowalskiError::Federation(format!("registry store: {e}"))
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// `path` ending in `.sqlite`/`.db` is used as is; anything else is a directory holding
+/// `federation.sqlite`. Relative paths are under the data directory.
+fn federation_db_file(path: &str) -> Result<PathBuf, KowalskiError> {
+    let p = crate::config::data_path(path.trim_end_matches('/'));
+    let file_path = if p.extension().is_some_and(|e| e == "sqlite" || e == "db") {
+        p
+    } else {
+        p.join("federation.sqlite")
+    };
+    if let Some(parent) = file_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            KowalskiError::Federation(format!("create federation state directory: {e}"))
+        })?;
+    }
+    Ok(file_path)
+}
+
+/// The parts of a [`TaskSpec`] that survive a restart (the deadline is process-local and dropped).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredTaskSpec {
+    pub instruction: String,
+    pub target: TaskTarget,
+    pub timeout_ms: u64,
+    pub priority: TaskPriority,
+    pub max_retries: u32,
+    pub retry_backoff_ms: u64,
+}
+
+impl From<&TaskSpec> for StoredTaskSpec {
+    fn from(spec: &TaskSpec) -> Self {
+        Self {
+            instruction: spec.instruction.clone(),
+            target: spec.target.clone(),
+            timeout_ms: spec.timeout.as_millis() as u64,
+            priority: spec.priority,
+            max_retries: spec.max_retries,
+            retry_backoff_ms: spec.retry_backoff.as_millis() as u64,
+        }
+    }
+}
+
+impl StoredTaskSpec {
+    pub fn to_spec(&self, task_id: &str) -> TaskSpec {
+        TaskSpec::new(self.target.clone(), self.instruction.clone())
+            .with_task_id(task_id)
+            .with_timeout(Duration::from_millis(self.timeout_ms))
+            .with_priority(self.priority)
+            .with_max_retries(
+                self.max_retries,
+                Duration::from_millis(self.retry_backoff_ms),
+            )
+    }
+}
+
+/// Everything persisted about one task.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredTask {
+    pub record: TaskRecord,
+    /// Present for tasks submitted through a [`Coordinator`](crate::federation::Coordinator);
+    /// only these can be resumed.
+    #[serde(default)]
+    pub spec: Option<StoredTaskSpec>,
+    #[serde(default)]
+    pub attempts: Vec<TaskAttempt>,
+    /// Answer of a completed task.
+    #[serde(default)]
+    pub answer: Option<String>,
+    #[serde(default)]
+    pub dead_letter: Option<DeadLetter>,
+}
+
+impl StoredTask {
+    fn new(task_id: &str) -> Self {
+        Self {
+            record: TaskRecord {
+                task_id: task_id.to_string(),
+                agent_id: String::new(),
+                status: crate::federation::delegation::TaskStatus::Queued,
+                error: None,
+            },
+            spec: None,
+            attempts: Vec::new(),
+            answer: None,
+            dead_letter: None,
+        }
+    }
+}
+
+pub(crate) enum StoreOp {
+    UpsertAgent(AgentRecord),
+    Touch(String),
+    RemoveAgent(String),
+    TaskStatus(TaskRecord),
+    TaskSpec {
+        task_id: String,
+        spec: StoredTaskSpec,
+        attempts: Vec<TaskAttempt>,
+    },
+    TaskAnswer {
+        task_id: String,
+        answer: String,
+    },
+    DeadLetter(DeadLetter),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Registry state loaded from disk (see [`RegistryStore::load`]).
+pub(crate) struct StoredState {
+    /// Live registrations with the age of their last heartbeat.
+    pub agents: Vec<(AgentRecord, Duration)>,
+    /// Registrations whose last heartbeat is older than [`RegistryStore::stale_after`]; already
+    /// deleted from the store.
+    pub expired: Vec<String>,
+    pub tasks: Vec<StoredTask>,
+}
+
+/// SQLite file holding registrations (record + last heartbeat) and tasks (spec, status,
+/// attempts, result). Cheap to clone; all clones share one writer.
+#[derive(Clone)]
+pub struct RegistryStore {
+    pool: SqlitePool,
+    tx: mpsc::UnboundedSender<StoreOp>,
+    stale_after: Duration,
+}
+
+impl RegistryStore {
+    /// Opens (or creates) the store at `path`. Registrations not heard from for `stale_after`
+    /// are dropped on load.
+    pub async fn open(path: &str, stale_after: Duration) -> Result<Self, KowalskiError> {
+        let file = federation_db_file(path)?;
+        info!("Opening federation state at {}", file.display());
+        let opts = SqliteConnectOptions::new()
+            .filename(&file)
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(opts).await.map_err(store_err)?;
+        sqlx::raw_sql(FEDERATION_STATE_SCHEMA)
+            .execute(&pool)
+            .await
+            .map_err(store_err)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_writer(pool.clone(), rx));
+        Ok(Self {
+            pool,
+            tx,
+            stale_after,
+        })
+    }
+
+    /// Opens the store at [`FederationConfig::persistence_path`] (`None` when unset). A
+    /// registration expires after `remove_after_missed` heartbeat intervals of silence.
+    pub async fn from_config(config: &FederationConfig) -> Result<Option<Self>, KowalskiError> {
+        let Some(path) = config.persistence_path.as_deref() else {
+            return Ok(None);
+        };
+        let policy = LivenessPolicy::from_config(config);
+        let stale_after = policy.heartbeat_interval * policy.remove_after_missed.max(1);
+        Self::open(path, stale_after).await.map(Some)
+    }
+
+    pub fn stale_after(&self) -> Duration {
+        self.stale_after
+    }
+
+    pub(crate) fn send(&self, op: StoreOp) {
+        if self.tx.send(op).is_err() {
+            warn!("federation state writer stopped; change not persisted");
+        }
+    }
+
+    /// Waits until every change made so far is on disk.
+    pub async fn flush(&self) {
+        let (tx, rx) = oneshot::channel();
+        self.send(StoreOp::Flush(tx));
+        let _ = rx.await;
+    }
+
+    pub(crate) async fn load(&self) -> Result<StoredState, KowalskiError> {
+        self.flush().await;
+        let now = now_millis();
+        let mut agents = Vec::new();
+        let mut expired = Vec::new();
+        let rows = sqlx::query(
+            "SELECT agent_id, record, last_seen FROM federation_agents ORDER BY agent_id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(store_err)?;
+        for row in rows {
+            let id: String = row.try_get("agent_id").map_err(store_err)?;
+            let record: String = row.try_get("record").map_err(store_err)?;
+            let last_seen: i64 = row.try_get("last_seen").map_err(store_err)?;
+            let age = Duration::from_millis(now.saturating_sub(last_seen).max(0) as u64);
+            if age > self.stale_after {
+                expired.push(id);
+                continue;
+            }
+            match serde_json::from_str(&record) {
+                Ok(record) => agents.push((record, age)),
+                Err(e) => warn!("skipping unreadable registration {id}: {e}"),
+            }
+        }
+        for id in &expired {
+            sqlx::query("DELETE FROM federation_agents WHERE agent_id = ?")
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .map_err(store_err)?;
+        }
+
+        let rows = sqlx::query("SELECT task_id, payload FROM federation_tasks ORDER BY task_id")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(store_err)?;
+        let mut tasks = Vec::new();
+        for row in rows {
+            let id: String = row.try_get("task_id").map_err(store_err)?;
+            let payload: String = row.try_get("payload").map_err(store_err)?;
+            match serde_json::from_str(&payload) {
+                Ok(task) => tasks.push(task),
+                Err(e) => warn!("skipping unreadable task {id}: {e}"),
+            }
+        }
+        Ok(StoredState {
+            agents,
+            expired,
+            tasks,
+        })
+    }
+}
+
+async fn run_writer(pool: SqlitePool, mut rx: mpsc::UnboundedReceiver<StoreOp>) {
+    while let Some(op) = rx.recv().await {
+        if let Err(e) = apply(&pool, op).await {
+            warn!("federation state write failed: {e}");
+        }
+    }
+}
+
+async fn apply(pool: &SqlitePool, op: StoreOp) -> Result<(), KowalskiError> {
+    match op {
+        StoreOp::UpsertAgent(record) => {
+            let json = serde_json::to_string(&record)?;
+            sqlx::query(
+                r#"INSERT INTO federation_agents (agent_id, record, last_seen) VALUES (?, ?, ?)
+                   ON CONFLICT (agent_id) DO UPDATE SET record = excluded.record, last_seen = excluded.last_seen"#,
+            )
+            .bind(&record.id)
+            .bind(json)
+            .bind(now_millis())
+            .execute(pool)
+            .await
+            .map_err(store_err)?;
+        }
+        StoreOp::Touch(id) => {
+            sqlx::query("UPDATE federation_agents SET last_seen = ? WHERE agent_id = ?")
+                .bind(now_millis())
+                .bind(id)
+                .execute(pool)
+                .await
+                .map_err(store_err)?;
+        }
+        StoreOp::RemoveAgent(id) => {
+            sqlx::query("DELETE FROM federation_agents WHERE agent_id = ?")
+                .bind(id)
+                .execute(pool)
+                .await
+                .map_err(store_err)?;
+        }
+        StoreOp::TaskStatus(record) => {
+            let task_id = record.task_id.clone();
+            update_task(pool, &task_id, |t| t.record = record).await?;
+        }
+        StoreOp::TaskSpec {
+            task_id,
+            spec,
+            attempts,
+        } => {
+            update_task(pool, &task_id, |t| {
+                t.spec = Some(spec);
+                t.attempts = attempts;
+            })
+            .await?;
+        }
+        StoreOp::TaskAnswer { task_id, answer } => {
+            update_task(pool, &task_id, |t| t.answer = Some(answer)).await?;
+        }
+        StoreOp::DeadLetter(letter) => {
+            let task_id = letter.task_id.clone();
+            update_task(pool, &task_id, |t| {
+                t.attempts = letter.attempts.clone();
+                t.dead_letter = Some(letter);
+            })
+            .await?;
+        }
+        StoreOp::Flush(done) => {
+            let _ = done.send(());
+        }
+    }
+    Ok(())
+}
+
+/// Read-modify-write of one task row (the writer is the only one updating rows).
+async fn update_task(
+    pool: &SqlitePool,
+    task_id: &str,
+    change: impl FnOnce(&mut StoredTask),
+) -> Result<(), KowalskiError> {
+    let existing: Option<String> =
+        sqlx::query_scalar("SELECT payload FROM federation_tasks WHERE task_id = ?")
+            .bind(task_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(store_err)?;
+    let mut task = existing
+        .and_then(|p| serde_json::from_str(&p).ok())
+        .unwrap_or_else(|| StoredTask::new(task_id));
+    change(&mut task);
+    sqlx::query(
+        r#"INSERT INTO federation_tasks (task_id, payload, updated_at) VALUES (?, ?, ?)
+           ON CONFLICT (task_id) DO UPDATE SET payload = excluded.payload, updated_at = excluded.updated_at"#,
+    )
+    .bind(task_id)
+    .bind(serde_json::to_string(&task)?)
+    .bind(now_millis())
+    .execute(pool)
+    .await
+    .map_err(store_err)?;
+    Ok(())
+}
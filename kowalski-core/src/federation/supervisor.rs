@@ -0,0 +1,396 @@
+//! Plan-and-execute over the federation: a [`SupervisorAgent`] asks its LLM to split a request into
+//! a [`Plan`] of capability-tagged subtasks, runs them through
+//! [`FederationOrchestrator::delegate`] in dependency order (independent steps in parallel), and
+//! writes the final answer from all subtask outputs. Progress is broadcast as [`SupervisorEvent`]s.
+
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::federation::delegation::{DEFAULT_TASK_TIMEOUT, TaskSpec};
+use crate::federation::orchestrator::FederationOrchestrator;
+use crate::llm::LLMProvider;
+use crate::utils::json::strip_markdown_code_fences;
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Default number of subtasks delegated at the same time.
+pub const DEFAULT_SUPERVISOR_PARALLELISM: usize = 4;
+
+const PLAN_PROMPT: &str = r#"You are a supervisor coordinating specialist agents. Split the user's request into the smallest set of subtasks, each handled by one agent capability.
+Reply with JSON only, in this shape:
+{"tasks": [{"id": "t1", "description": "...", "required_capability": "...", "depends_on": []}]}
+Use "depends_on" to list the ids of subtasks whose results a subtask needs. Subtasks without dependencies run in parallel."#;
+
+const SYNTHESIS_PROMPT: &str = "You are a supervisor. Specialist agents completed the subtasks below for the user's request. Write the final answer for the user, using every subtask result.";
+
+/// One step of a [`Plan`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlannedTask {
+    /// Unique within the plan; assigned (`t1`, `t2`, …) when the model leaves it out.
+    #[serde(default)]
+    pub id: String,
+    pub description: String,
+    /// Routed with [`TaskSpec::for_capability`].
+    pub required_capability: String,
+    /// Ids of the steps whose outputs this one needs.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// Subtasks forming a dependency DAG.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Plan {
+    pub tasks: Vec<PlannedTask>,
+}
+
+impl Plan {
+    /// Parses a planning reply: `{"tasks": [...]}` or a bare array, optionally fenced or slightly
+    /// malformed. The result is [validated](Self::validate).
+    pub fn parse(reply: &str) -> Result<Self, KowalskiError> {
+        let text = strip_markdown_code_fences(reply);
+        let start = text.find(['{', '[']).unwrap_or(0);
+        let raw = &text[start..];
+        let value: serde_json::Value = serde_json::from_str(raw).or_else(|_| {
+            llm_json::repair_json(raw, &llm_json::RepairOptions::default())
+                .map_err(|e| KowalskiError::Federation(format!("unreadable plan: {e}")))
+                .and_then(|fixed| serde_json::from_str(&fixed).map_err(KowalskiError::Json))
+        })?;
+        let tasks = match value {
+            serde_json::Value::Array(_) => value,
+            serde_json::Value::Object(mut obj) => obj
+                .remove("tasks")
+                .ok_or_else(|| KowalskiError::Federation("plan has no \"tasks\"".to_string()))?,
+            _ => return Err(KowalskiError::Federation("plan is not JSON".to_string())),
+        };
+        let mut plan = Plan {
+            tasks: serde_json::from_value(tasks)?,
+        };
+        for (i, task) in plan.tasks.iter_mut().enumerate() {
+            if task.id.trim().is_empty() {
+                task.id = format!("t{}", i + 1);
+            }
+        }
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    /// Rejects empty plans, duplicate ids, unknown dependencies and cycles.
+    pub fn validate(&self) -> Result<(), KowalskiError> {
+        let invalid = |why: String| Err(KowalskiError::Federation(format!("invalid plan: {why}")));
+        if self.tasks.is_empty() {
+            return invalid("no tasks".to_string());
+        }
+        let mut ids = HashSet::new();
+        for task in &self.tasks {
+            if !ids.insert(task.id.as_str()) {
+                return invalid(format!("duplicate task id '{}'", task.id));
+            }
+        }
+        for task in &self.tasks {
+            if let Some(dep) = task.depends_on.iter().find(|d| !ids.contains(d.as_str())) {
+                return invalid(format!("'{}' depends on unknown task '{dep}'", task.id));
+            }
+        }
+        let mut done: HashSet<&str> = HashSet::new();
+        while done.len() < self.tasks.len() {
+            let ready: Vec<&str> = self
+                .tasks
+                .iter()
+                .filter(|t| !done.contains(t.id.as_str()))
+                .filter(|t| t.depends_on.iter().all(|d| done.contains(d.as_str())))
+                .map(|t| t.id.as_str())
+                .collect();
+            if ready.is_empty() {
+                return invalid("dependency cycle".to_string());
+            }
+            done.extend(ready);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PlannedTask> {
+        self.tasks.iter().find(|t| t.id == id)
+    }
+}
+
+/// Output of one executed [`PlannedTask`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SubtaskOutcome {
+    pub task_id: String,
+    pub agent_id: String,
+    pub output: String,
+}
+
+/// Everything a [`SupervisorAgent::run`] produced.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SupervisorRun {
+    pub plan: Plan,
+    /// In completion order.
+    pub results: Vec<SubtaskOutcome>,
+    pub answer: String,
+}
+
+/// Progress of a supervisor run, broadcast to [`SupervisorAgent::subscribe`] receivers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SupervisorEvent {
+    PlanCreated { plan: Plan },
+    SubtaskStarted { task_id: String, capability: String },
+    SubtaskCompleted { result: SubtaskOutcome },
+    SubtaskFailed { task_id: String, error: String },
+    Synthesized { answer: String },
+}
+
+/// Coordinator agent: plans with its own LLM, delegates the steps to federated workers, and
+/// synthesizes their results. A failed step stops the run (steps already running are dropped).
+pub struct SupervisorAgent {
+    llm: Arc<dyn LLMProvider>,
+    model: String,
+    orchestrator: Arc<FederationOrchestrator>,
+    task_timeout: Duration,
+    max_parallel: usize,
+    events: broadcast::Sender<SupervisorEvent>,
+}
+
+impl SupervisorAgent {
+    /// `orchestrator` must be [listening for results](FederationOrchestrator::listen_for_results).
+    pub fn new(
+        llm: Arc<dyn LLMProvider>,
+        model: impl Into<String>,
+        orchestrator: Arc<FederationOrchestrator>,
+    ) -> Self {
+        Self {
+            llm,
+            model: model.into(),
+            orchestrator,
+            task_timeout: DEFAULT_TASK_TIMEOUT,
+            max_parallel: DEFAULT_SUPERVISOR_PARALLELISM,
+            events: broadcast::channel(64).0,
+        }
+    }
+
+    /// Wait for each delegated step.
+    pub fn with_task_timeout(mut self, timeout: Duration) -> Self {
+        self.task_timeout = timeout;
+        self
+    }
+
+    /// Steps delegated at the same time (at least one).
+    pub fn with_max_parallel(mut self, max_parallel: usize) -> Self {
+        self.max_parallel = max_parallel.max(1);
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SupervisorEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit(&self, event: SupervisorEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Plans, executes and synthesizes `request`.
+    pub async fn run(&self, request: &str) -> Result<SupervisorRun, KowalskiError> {
+        let plan = self.plan(request).await?;
+        let results = self.execute(request, &plan).await?;
+        let answer = self.synthesize(request, &plan, &results).await?;
+        Ok(SupervisorRun {
+            plan,
+            results,
+            answer,
+        })
+    }
+
+    /// Asks the LLM for a [`Plan`], listing the capabilities currently registered.
+    pub async fn plan(&self, request: &str) -> Result<Plan, KowalskiError> {
+        let capabilities: BTreeSet<String> = self
+            .orchestrator
+            .registry
+            .list()
+            .into_iter()
+            .flat_map(|a| a.capabilities)
+            .collect();
+        let capabilities = capabilities.into_iter().collect::<Vec<_>>().join(", ");
+        let messages = [
+            message("system", PLAN_PROMPT),
+            message(
+                "user",
+                &format!("Available capabilities: {capabilities}\n\nRequest: {request}"),
+            ),
+        ];
+        let reply = self.llm.chat(&self.model, &messages).await?;
+        let plan = Plan::parse(&reply)?;
+        info!("supervisor planned {} subtask(s)", plan.tasks.len());
+        self.emit(SupervisorEvent::PlanCreated { plan: plan.clone() });
+        Ok(plan)
+    }
+
+    /// Delegates every step once its dependencies are done, passing their outputs along.
+    pub async fn execute(
+        &self,
+        request: &str,
+        plan: &Plan,
+    ) -> Result<Vec<SubtaskOutcome>, KowalskiError> {
+        plan.validate()?;
+        let mut outputs: HashMap<&str, SubtaskOutcome> = HashMap::new();
+        let mut started: HashSet<&str> = HashSet::new();
+        let mut results = Vec::new();
+        let mut running = FuturesUnordered::new();
+        loop {
+            for task in &plan.tasks {
+                if running.len() >= self.max_parallel {
+                    break;
+                }
+                if started.contains(task.id.as_str())
+                    || !task
+                        .depends_on
+                        .iter()
+                        .all(|d| outputs.contains_key(d.as_str()))
+                {
+                    continue;
+                }
+                started.insert(task.id.as_str());
+                let spec = TaskSpec::for_capability(
+                    task.required_capability.clone(),
+                    subtask_instruction(request, plan, task, &outputs),
+                )
+                .with_timeout(self.task_timeout);
+                self.emit(SupervisorEvent::SubtaskStarted {
+                    task_id: task.id.clone(),
+                    capability: task.required_capability.clone(),
+                });
+                running.push(async move { (task, self.orchestrator.delegate(spec).await) });
+            }
+            let Some((task, result)) = running.next().await else {
+                break;
+            };
+            match result {
+                Ok(result) => {
+                    let outcome = SubtaskOutcome {
+                        task_id: task.id.clone(),
+                        agent_id: result.agent_id,
+                        output: result.answer,
+                    };
+                    self.emit(SupervisorEvent::SubtaskCompleted {
+                        result: outcome.clone(),
+                    });
+                    outputs.insert(task.id.as_str(), outcome.clone());
+                    results.push(outcome);
+                }
+                Err(e) => {
+                    warn!("supervisor subtask {} failed: {e}", task.id);
+                    self.emit(SupervisorEvent::SubtaskFailed {
+                        task_id: task.id.clone(),
+                        error: e.to_string(),
+                    });
+                    return Err(KowalskiError::Federation(format!(
+                        "subtask {} ({}) failed: {e}",
+                        task.id, task.description
+                    )));
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Final turn with the request and every subtask result in context.
+    pub async fn synthesize(
+        &self,
+        request: &str,
+        plan: &Plan,
+        results: &[SubtaskOutcome],
+    ) -> Result<String, KowalskiError> {
+        let mut context = format!("Request: {request}\n");
+        for result in results {
+            let description = plan
+                .get(&result.task_id)
+                .map(|t| t.description.as_str())
+                .unwrap_or_default();
+            context.push_str(&format!(
+                "\n### [{}] {description} (by {})\n{}\n",
+                result.task_id, result.agent_id, result.output
+            ));
+        }
+        let messages = [
+            message("system", SYNTHESIS_PROMPT),
+            message("user", &context),
+        ];
+        let answer = self.llm.chat(&self.model, &messages).await?;
+        self.emit(SupervisorEvent::Synthesized {
+            answer: answer.clone(),
+        });
+        Ok(answer)
+    }
+}
+
+fn message(role: &str, content: &str) -> Message {
+    Message {
+        role: role.to_string(),
+        content: content.to_string(),
+        tool_calls: None,
+        images: None,
+        tool_call_id: None,
+        tool_name: None,
+    }
+}
+
+/// The step's description, the overall request, and the outputs of its dependencies.
+fn subtask_instruction(
+    request: &str,
+    plan: &Plan,
+    task: &PlannedTask,
+    outputs: &HashMap<&str, SubtaskOutcome>,
+) -> String {
+    let mut instruction = format!("{}\n\nOverall request: {request}", task.description);
+    if !task.depends_on.is_empty() {
+        instruction.push_str("\n\nResults of earlier steps:");
+        for dep in &task.depends_on {
+            let description = plan
+                .get(dep)
+                .map(|t| t.description.as_str())
+                .unwrap_or_default();
+            let output = outputs
+                .get(dep.as_str())
+                .map(|o| o.output.as_str())
+                .unwrap_or_default();
+            instruction.push_str(&format!("\n\n[{dep}] {description}\n{output}"));
+        }
+    }
+    instruction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fenced_plans_and_rejects_bad_graphs() {
+        let plan = Plan::parse(
+            "Here you go:\n```json\n{\"tasks\": [{\"description\": \"search\", \"required_capability\": \"web\"}, {\"description\": \"sum up\", \"required_capability\": \"llm\", \"depends_on\": [\"t1\"]}]}\n```",
+        )
+        .unwrap();
+        assert_eq!(plan.tasks[0].id, "t1");
+        assert_eq!(plan.tasks[1].depends_on, ["t1"]);
+
+        let cyclic = r#"[{"id": "a", "description": "x", "required_capability": "c", "depends_on": ["b"]},
+                         {"id": "b", "description": "y", "required_capability": "c", "depends_on": ["a"]}]"#;
+        assert!(
+            Plan::parse(cyclic)
+                .unwrap_err()
+                .to_string()
+                .contains("cycle")
+        );
+        let dangling = r#"[{"id": "a", "description": "x", "required_capability": "c", "depends_on": ["zz"]}]"#;
+        assert!(
+            Plan::parse(dangling)
+                .unwrap_err()
+                .to_string()
+                .contains("unknown task")
+        );
+    }
+}
@@ -0,0 +1,512 @@
+//! Remote federation over WebSocket.
+//!
+//! A registry node runs [`WsFederationServer`] next to its [`AgentRegistry`] and [`MpscBroker`].
+//! Remote agents connect with [`WsTransport`], send [`WsFrame::Register`] (id, capabilities,
+//! topics), then exchange [`AclEnvelope`]s as JSON text frames and heartbeat periodically.
+//! Clients reconnect with exponential backoff; duplicates (including the server echoing a
+//! client's own publish) are dropped by envelope id in the client's local broker.
+
+use crate::config::FederationConfig;
+use crate::error::KowalskiError;
+use crate::federation::acl::AclEnvelope;
+use crate::federation::broker::{FederationTransport, MessageBroker, MpscBroker};
+use crate::federation::registry::{AgentRecord, AgentRegistry};
+use async_trait::async_trait;
+use futures::{Sink, SinkExt, StreamExt};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+/// One JSON text frame on the federation socket.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsFrame {
+    /// First frame from a client, repeated after every reconnect. `topics` are forwarded to it.
+    Register {
+        agent: AgentRecord,
+        topics: Vec<String>,
+    },
+    Envelope {
+        envelope: Box<AclEnvelope>,
+    },
+    Heartbeat {
+        agent_id: String,
+    },
+}
+
+fn ws_error(e: impl std::fmt::Display) -> KowalskiError {
+    KowalskiError::Federation(format!("websocket: {e}"))
+}
+
+async fn send_frame<S>(sink: &mut S, frame: &WsFrame) -> Result<(), KowalskiError>
+where
+    S: Sink<WsMessage> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    let text = serde_json::to_string(frame)?;
+    sink.send(WsMessage::Text(text.into()))
+        .await
+        .map_err(ws_error)
+}
+
+#[derive(Clone)]
+struct ServerState {
+    registry: Arc<AgentRegistry>,
+    broker: Arc<MpscBroker>,
+    /// agent id → (connection id, last frame time); the connection id stops a stale socket from
+    /// deregistering an agent that already reconnected.
+    sessions: Arc<Mutex<HashMap<String, (u64, Instant)>>>,
+    next_connection: Arc<AtomicU64>,
+}
+
+impl ServerState {
+    /// Any frame from a registered client counts as a heartbeat.
+    fn touch(&self, agent_id: &str, connection: u64) {
+        self.sessions
+            .lock()
+            .expect("ws sessions lock")
+            .insert(agent_id.to_string(), (connection, Instant::now()));
+        let _ = self.registry.heartbeat(agent_id);
+    }
+}
+
+/// Registry node: accepts [`WsTransport`] clients and bridges them to a local broker.
+pub struct WsFederationServer {
+    local_addr: SocketAddr,
+    state: ServerState,
+    accept_task: JoinHandle<()>,
+}
+
+impl WsFederationServer {
+    /// Binds `addr` (e.g. `127.0.0.1:7420`, port `0` for any) and starts accepting clients.
+    pub async fn bind(
+        addr: &str,
+        registry: Arc<AgentRegistry>,
+        broker: Arc<MpscBroker>,
+    ) -> Result<Self, KowalskiError> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let state = ServerState {
+            registry,
+            broker,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            next_connection: Arc::new(AtomicU64::new(1)),
+        };
+        info!("Federation WebSocket listening on ws://{local_addr}");
+        let accept_state = state.clone();
+        let accept_task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer)) => {
+                        let state = accept_state.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_connection(stream, state).await {
+                                debug!("federation client {peer}: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => warn!("federation accept failed: {e}"),
+                }
+            }
+        });
+        Ok(Self {
+            local_addr,
+            state,
+            accept_task,
+        })
+    }
+
+    /// Binds [`FederationConfig::ws_listen`].
+    pub async fn from_config(
+        config: &FederationConfig,
+        registry: Arc<AgentRegistry>,
+        broker: Arc<MpscBroker>,
+    ) -> Result<Self, KowalskiError> {
+        Self::bind(&config.ws_listen, registry, broker).await
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// `ws://host:port` for [`WsTransport::connect`].
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.local_addr)
+    }
+
+    /// When a connected agent last sent a frame (register, envelope or heartbeat).
+    pub fn last_seen(&self, agent_id: &str) -> Option<Instant> {
+        self.state
+            .sessions
+            .lock()
+            .ok()?
+            .get(agent_id)
+            .map(|(_, seen)| *seen)
+    }
+
+    /// Ids of agents with an open connection, sorted.
+    pub fn connected_agents(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .state
+            .sessions
+            .lock()
+            .map(|s| s.keys().cloned().collect())
+            .unwrap_or_default();
+        ids.sort();
+        ids
+    }
+}
+
+impl Drop for WsFederationServer {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+async fn handle_connection(stream: TcpStream, state: ServerState) -> Result<(), KowalskiError> {
+    let ws = tokio_tungstenite::accept_async(stream)
+        .await
+        .map_err(ws_error)?;
+    let (mut sink, mut source) = ws.split();
+
+    let (agent, topics) = loop {
+        match source.next().await {
+            Some(Ok(WsMessage::Text(text))) => match serde_json::from_str(text.as_str())? {
+                WsFrame::Register { agent, topics } => break (agent, topics),
+                _ => return Err(ws_error("first frame must be `register`")),
+            },
+            Some(Ok(WsMessage::Close(_))) | None => return Ok(()),
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(ws_error(e)),
+        }
+    };
+    let connection = state.next_connection.fetch_add(1, Ordering::Relaxed);
+    let agent_id = agent.id.clone();
+    state.registry.register(agent)?;
+    state.touch(&agent_id, connection);
+    info!("federation agent {agent_id} connected (topics {topics:?})");
+
+    let (out_tx, mut out_rx) = mpsc::channel::<AclEnvelope>(64);
+    let forwarders: Vec<JoinHandle<()>> = topics
+        .iter()
+        .map(|topic| {
+            let mut rx = state.broker.subscribe(topic, 64);
+            let tx = out_tx.clone();
+            tokio::spawn(async move {
+                while let Some(env) = rx.recv().await {
+                    if tx.send(env).await.is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(out_tx);
+    // Writing runs on its own task: a publish below may wait on this connection's own
+    // forwarders, which only drain while frames are being written.
+    let mut writer = tokio::spawn(async move {
+        while let Some(envelope) = out_rx.recv().await {
+            let frame = WsFrame::Envelope {
+                envelope: Box::new(envelope),
+            };
+            if send_frame(&mut sink, &frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let result = tokio::select! {
+        result = read_frames(&mut source, &state, &agent_id, connection) => result,
+        _ = &mut writer => Ok(()),
+    };
+
+    writer.abort();
+    for forwarder in forwarders {
+        forwarder.abort();
+    }
+    let current = {
+        let mut sessions = state.sessions.lock().expect("ws sessions lock");
+        let current = sessions.get(&agent_id).map(|(c, _)| *c) == Some(connection);
+        if current {
+            sessions.remove(&agent_id);
+        }
+        current
+    };
+    if current {
+        let _ = state.registry.deregister(&agent_id);
+        info!("federation agent {agent_id} disconnected");
+    }
+    result
+}
+
+/// Handles a registered peer's frames until it disconnects. Envelopes are published with
+/// `agent_id` as their sender, whatever the peer claimed.
+async fn read_frames<S>(
+    source: &mut S,
+    state: &ServerState,
+    agent_id: &str,
+    connection: u64,
+) -> Result<(), KowalskiError>
+where
+    S: futures::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        let text = match source.next().await {
+            Some(Ok(WsMessage::Text(text))) => text,
+            Some(Ok(WsMessage::Close(_))) | None | Some(Err(_)) => return Ok(()),
+            Some(Ok(_)) => continue,
+        };
+        match serde_json::from_str::<WsFrame>(text.as_str()) {
+            Ok(WsFrame::Envelope { mut envelope }) => {
+                state.touch(agent_id, connection);
+                if envelope.sender != agent_id {
+                    debug!(
+                        "envelope {} from {agent_id} claimed sender {}",
+                        envelope.id, envelope.sender
+                    );
+                    envelope.sender = agent_id.to_string();
+                }
+                state.broker.publish_to_topic(&envelope).await?;
+            }
+            Ok(WsFrame::Heartbeat { .. }) => state.touch(agent_id, connection),
+            Ok(WsFrame::Register { agent, .. }) if agent.id == agent_id => {
+                state.registry.register(agent)?;
+                state.touch(agent_id, connection);
+            }
+            Ok(WsFrame::Register { agent, .. }) => {
+                warn!(
+                    "connection for {agent_id} tried to register as {}",
+                    agent.id
+                );
+            }
+            Err(e) => warn!("bad frame from {agent_id}: {e}"),
+        }
+    }
+}
+
+/// Heartbeat and reconnect timing for [`WsTransport`].
+#[derive(Debug, Clone)]
+pub struct WsClientOptions {
+    pub heartbeat_interval: Duration,
+    /// First reconnect delay; doubles after each failed attempt up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for WsClientOptions {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(15),
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl WsClientOptions {
+    pub fn from_config(config: &FederationConfig) -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(config.heartbeat_secs.max(1)),
+            ..Self::default()
+        }
+    }
+}
+
+/// Remote agent's connection to a [`WsFederationServer`]. Publishing delivers locally and
+/// sends to the server; envelopes from the server arrive on [`FederationTransport::subscribe`]
+/// receivers for the topics given at connect time.
+pub struct WsTransport {
+    agent_id: String,
+    inbox: Arc<MpscBroker>,
+    outgoing: mpsc::Sender<AclEnvelope>,
+    connected: Arc<AtomicBool>,
+    reconnects: Arc<AtomicU32>,
+    task: JoinHandle<()>,
+}
+
+impl WsTransport {
+    /// Starts connecting to `url` in the background (retrying until it succeeds).
+    pub fn connect(
+        url: impl Into<String>,
+        agent: AgentRecord,
+        topics: Vec<String>,
+        options: WsClientOptions,
+    ) -> Self {
+        let inbox = Arc::new(MpscBroker::new());
+        let (outgoing, outgoing_rx) = mpsc::channel(256);
+        let connected = Arc::new(AtomicBool::new(false));
+        let reconnects = Arc::new(AtomicU32::new(0));
+        let agent_id = agent.id.clone();
+        let task = tokio::spawn(run_client(ClientLoop {
+            url: url.into(),
+            agent,
+            topics,
+            options,
+            inbox: inbox.clone(),
+            outgoing: outgoing_rx,
+            connected: connected.clone(),
+            reconnects: reconnects.clone(),
+        }));
+        Self {
+            agent_id,
+            inbox,
+            outgoing,
+            connected,
+            reconnects,
+            task,
+        }
+    }
+
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Acquire)
+    }
+
+    /// Successful connections after the first one.
+    pub fn reconnect_count(&self) -> u32 {
+        self.reconnects.load(Ordering::Relaxed)
+    }
+
+    /// Waits until the socket is up and registered, or fails with [`KowalskiError::Timeout`].
+    pub async fn wait_connected(&self, timeout: Duration) -> Result<(), KowalskiError> {
+        let deadline = Instant::now() + timeout;
+        while !self.is_connected() {
+            if Instant::now() >= deadline {
+                return Err(KowalskiError::Timeout {
+                    operation: format!("{} not connected within {timeout:?}", self.agent_id),
+                });
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WsTransport {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[async_trait]
+impl MessageBroker for WsTransport {
+    async fn publish(&self, envelope: &AclEnvelope) -> Result<(), KowalskiError> {
+        // Local delivery first also records the id, so the server's echo is dropped.
+        self.inbox.publish_to_topic(envelope).await?;
+        self.outgoing
+            .send(envelope.clone())
+            .await
+            .map_err(|_| KowalskiError::Federation("websocket transport stopped".to_string()))
+    }
+}
+
+impl FederationTransport for WsTransport {
+    fn subscribe(&self, topic: &str, buffer: usize) -> mpsc::Receiver<AclEnvelope> {
+        self.inbox.subscribe(topic, buffer)
+    }
+}
+
+struct ClientLoop {
+    url: String,
+    agent: AgentRecord,
+    topics: Vec<String>,
+    options: WsClientOptions,
+    inbox: Arc<MpscBroker>,
+    outgoing: mpsc::Receiver<AclEnvelope>,
+    connected: Arc<AtomicBool>,
+    reconnects: Arc<AtomicU32>,
+}
+
+async fn run_client(mut c: ClientLoop) {
+    let mut backoff = c.options.initial_backoff;
+    let mut connections = 0u32;
+    // Envelope whose send failed; retried first after reconnecting.
+    let mut pending: Option<AclEnvelope> = None;
+    loop {
+        match tokio_tungstenite::connect_async(c.url.as_str()).await {
+            Ok((ws, _)) => {
+                let (mut sink, mut source) = ws.split();
+                let register = WsFrame::Register {
+                    agent: c.agent.clone(),
+                    topics: c.topics.clone(),
+                };
+                if send_frame(&mut sink, &register).await.is_ok() {
+                    if connections > 0 {
+                        c.reconnects.fetch_add(1, Ordering::Relaxed);
+                    }
+                    connections += 1;
+                    backoff = c.options.initial_backoff;
+                    c.connected.store(true, Ordering::Release);
+                    debug!("{} connected to {}", c.agent.id, c.url);
+
+                    let mut heartbeat = tokio::time::interval(c.options.heartbeat_interval);
+                    heartbeat.tick().await;
+                    let stopped = loop {
+                        if let Some(envelope) = pending.take()
+                            && send_frame(
+                                &mut sink,
+                                &WsFrame::Envelope {
+                                    envelope: Box::new(envelope.clone()),
+                                },
+                            )
+                            .await
+                            .is_err()
+                        {
+                            pending = Some(envelope);
+                            break false;
+                        }
+                        tokio::select! {
+                            env = c.outgoing.recv() => {
+                                let Some(envelope) = env else { break true };
+                                let frame = WsFrame::Envelope { envelope: Box::new(envelope.clone()) };
+                                if send_frame(&mut sink, &frame).await.is_err() {
+                                    pending = Some(envelope);
+                                    break false;
+                                }
+                            }
+                            _ = heartbeat.tick() => {
+                                let frame = WsFrame::Heartbeat { agent_id: c.agent.id.clone() };
+                                if send_frame(&mut sink, &frame).await.is_err() {
+                                    break false;
+                                }
+                            }
+                            msg = source.next() => match msg {
+                                Some(Ok(WsMessage::Text(text))) => {
+                                    match serde_json::from_str::<WsFrame>(text.as_str()) {
+                                        Ok(WsFrame::Envelope { envelope }) => {
+                                            let _ = c.inbox.publish_to_topic(&envelope).await;
+                                        }
+                                        Ok(_) => {}
+                                        Err(e) => warn!("bad frame from {}: {e}", c.url),
+                                    }
+                                }
+                                Some(Ok(WsMessage::Close(_))) | None | Some(Err(_)) => break false,
+                                Some(Ok(_)) => {}
+                            }
+                        }
+                    };
+                    c.connected.store(false, Ordering::Release);
+                    if stopped {
+                        let _ = sink.close().await;
+                        return;
+                    }
+                    info!("{} lost connection to {}; reconnecting", c.agent.id, c.url);
+                }
+            }
+            Err(e) => debug!("{} connect to {} failed: {e}", c.agent.id, c.url),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(c.options.max_backoff);
+    }
+}
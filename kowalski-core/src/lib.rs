@@ -1,4 +1,7 @@
 pub mod agent;
+#[cfg(any(test, feature = "bench"))]
+pub mod baseline;
+pub mod code;
 pub mod config;
 pub mod conversation;
 pub mod db;
@@ -9,12 +12,19 @@ pub mod llm;
 pub mod logging;
 pub mod mcp;
 pub mod memory;
+pub mod metrics;
 pub mod model;
+pub mod progress;
+pub mod prompts;
 pub mod role;
 pub mod template;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod text;
 pub mod tool_chain;
 pub mod tools;
 pub mod utils;
+pub mod web;
 
 pub use agent::repl_trace::ReplTraceGuard;
 pub use agent::{Agent, BaseAgent, MessageHandler};
@@ -42,7 +52,8 @@ pub use mcp::{
 };
 pub use model::ModelManager;
 pub use model::*;
-pub use role::{Audience, Preset, Role, Style};
+pub use prompts::{PromptKind, PromptRegistry, PromptTemplate};
+pub use role::{Audience, Preset, Role, RoleCatalog, RoleEntry, Style};
 pub use tool_chain::*;
 pub use tools::ToolCall;
 pub use tools::*;
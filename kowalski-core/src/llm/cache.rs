@@ -0,0 +1,611 @@
+//! Response cache for deterministic LLM calls.
+//!
+//! Temperature-0 chat and structured calls return the same reply for the same input,
+//! and so do embeddings; consolidation summaries and analysis prompts repeat them constantly. A
+//! [`CachedProvider`] answers those from an [`LlmCache`] (an `llm_cache` table in SQLite, next to
+//! the episodic store by default) keyed by a SHA-256 of the model, messages and options. A call is
+//! cached only when its temperature is 0 and it asks for it with
+//! [`ChatOptions::cache`](super::ChatOptions::cache), or `[llm.cache] enabled` is set;
+//! [`ChatOptions::bypass_cache`](super::ChatOptions::bypass_cache) forces a fresh reply. Streams
+//! are never cached. A hit records `cached = true` and zero prompt and completion tokens on the
+//! current span, as no tokens were billed.
+
+use super::provider::{ChatOptions, LLMProvider, TokenStream, record_token_usage};
+use crate::config::{Config, LlmCacheConfig};
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::memory::episodic::episodic_db_file;
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
+
+const LLM_CACHE_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS llm_cache (
+    key TEXT PRIMARY KEY,
+    response TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS llm_cache_created_at ON llm_cache (created_at);
+"#;
+
+fn cache_err(e: sqlx::Error) -> KowalskiError {
+    KowalskiError::Memory(format!("LLM cache: {e}"))
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Where the cache database lives; resolved on first use so an unused cache creates no file.
+#[derive(Debug, Clone)]
+enum Location {
+    /// `[llm.cache] path`, or the episodic store's file when unset.
+    File {
+        path: Option<String>,
+        episodic_path: String,
+    },
+    Memory,
+}
+
+impl Location {
+    fn file(&self) -> Result<Option<PathBuf>, KowalskiError> {
+        match self {
+            Self::File {
+                path: Some(path), ..
+            } => {
+                let path = crate::config::data_path(path.trim_end_matches('/'));
+                let file = if path.extension().is_some_and(|e| e == "sqlite" || e == "db") {
+                    path
+                } else {
+                    path.join("llm_cache.sqlite")
+                };
+                if let Some(parent) = file.parent()
+                    && !parent.as_os_str().is_empty()
+                {
+                    std::fs::create_dir_all(parent).map_err(|e| {
+                        KowalskiError::Memory(format!("create LLM cache directory: {e}"))
+                    })?;
+                }
+                Ok(Some(file))
+            }
+            Self::File {
+                path: None,
+                episodic_path,
+            } => episodic_db_file(episodic_path).map(Some),
+            Self::Memory => Ok(None),
+        }
+    }
+}
+
+/// Counters of an [`LlmCache`] since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LlmCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Responses written (misses and bypassed calls that succeeded and fit).
+    pub stores: u64,
+}
+
+/// Persistent store of LLM responses with a TTL and an entry cap.
+pub struct LlmCache {
+    settings: LlmCacheConfig,
+    location: Location,
+    pool: OnceCell<SqlitePool>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stores: AtomicU64,
+}
+
+impl LlmCache {
+    /// The cache configured in `[llm.cache]`, in `path` or the episodic SQLite file.
+    pub fn from_config(config: &Config) -> Self {
+        Self::with_location(
+            config.llm.cache.clone(),
+            Location::File {
+                path: config.llm.cache.path.clone(),
+                episodic_path: config.memory.episodic_path.clone(),
+            },
+        )
+    }
+
+    /// A cache that lives only as long as this value (for tests and one-off runs).
+    pub fn in_memory(settings: LlmCacheConfig) -> Self {
+        Self::with_location(settings, Location::Memory)
+    }
+
+    fn with_location(settings: LlmCacheConfig, location: Location) -> Self {
+        Self {
+            settings,
+            location,
+            pool: OnceCell::new(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+        }
+    }
+
+    pub fn settings(&self) -> &LlmCacheConfig {
+        &self.settings
+    }
+
+    pub fn stats(&self) -> LlmCacheStats {
+        LlmCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn pool(&self) -> Result<&SqlitePool, KowalskiError> {
+        self.pool
+            .get_or_try_init(|| async {
+                let options = match self.location.file()? {
+                    Some(file) => {
+                        debug!("Opening LLM cache at {}", file.display());
+                        SqliteConnectOptions::new()
+                            .filename(file)
+                            .create_if_missing(true)
+                    }
+                    None => SqliteConnectOptions::new().in_memory(true),
+                };
+                // An in-memory database exists per connection, so keep exactly one open.
+                let pool = match self.location {
+                    Location::Memory => SqlitePoolOptions::new()
+                        .max_connections(1)
+                        .idle_timeout(None)
+                        .max_lifetime(None)
+                        .connect_with(options)
+                        .await
+                        .map_err(cache_err)?,
+                    Location::File { .. } => {
+                        SqlitePool::connect_with(options).await.map_err(cache_err)?
+                    }
+                };
+                sqlx::raw_sql(LLM_CACHE_SCHEMA)
+                    .execute(&pool)
+                    .await
+                    .map_err(cache_err)?;
+                Ok(pool)
+            })
+            .await
+    }
+
+    /// The cached response for `key`, unless missing or older than the TTL.
+    pub async fn get(&self, key: &str) -> Result<Option<String>, KowalskiError> {
+        let pool = self.pool().await?;
+        let oldest = self
+            .settings
+            .ttl_secs
+            .map_or(i64::MIN, |ttl| now_secs().saturating_sub(ttl as i64));
+        let response: Option<String> =
+            sqlx::query_scalar("SELECT response FROM llm_cache WHERE key = ? AND created_at >= ?")
+                .bind(key)
+                .bind(oldest)
+                .fetch_optional(pool)
+                .await
+                .map_err(cache_err)?;
+        let counter = if response.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        crate::metrics::record_llm_cache(response.is_some());
+        Ok(response)
+    }
+
+    /// Stores `response` under `key`, then drops expired entries and the oldest ones over
+    /// `max_entries`. Responses over `max_entry_bytes` are skipped.
+    pub async fn put(&self, key: &str, response: &str) -> Result<(), KowalskiError> {
+        if response.len() > self.settings.max_entry_bytes {
+            debug!(
+                "LLM cache: not storing a {} byte response (limit {})",
+                response.len(),
+                self.settings.max_entry_bytes
+            );
+            return Ok(());
+        }
+        let pool = self.pool().await?;
+        let now = now_secs();
+        sqlx::query(
+            "INSERT INTO llm_cache (key, response, created_at) VALUES (?, ?, ?)
+             ON CONFLICT(key) DO UPDATE SET response = excluded.response, created_at = excluded.created_at",
+        )
+        .bind(key)
+        .bind(response)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(cache_err)?;
+        self.stores.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(ttl) = self.settings.ttl_secs {
+            sqlx::query("DELETE FROM llm_cache WHERE created_at < ?")
+                .bind(now.saturating_sub(ttl as i64))
+                .execute(pool)
+                .await
+                .map_err(cache_err)?;
+        }
+        sqlx::query(
+            "DELETE FROM llm_cache WHERE key IN
+             (SELECT key FROM llm_cache ORDER BY created_at DESC, rowid DESC LIMIT -1 OFFSET ?)",
+        )
+        .bind(self.settings.max_entries as i64)
+        .execute(pool)
+        .await
+        .map_err(cache_err)?;
+        Ok(())
+    }
+
+    /// Number of stored entries, expired ones included until the next write.
+    pub async fn len(&self) -> Result<usize, KowalskiError> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM llm_cache")
+            .fetch_one(self.pool().await?)
+            .await
+            .map_err(cache_err)?;
+        Ok(count as usize)
+    }
+
+    pub async fn is_empty(&self) -> Result<bool, KowalskiError> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Removes every entry.
+    pub async fn clear(&self) -> Result<(), KowalskiError> {
+        sqlx::query("DELETE FROM llm_cache")
+            .execute(self.pool().await?)
+            .await
+            .map_err(cache_err)?;
+        Ok(())
+    }
+}
+
+/// Answers eligible calls of `inner` from an [`LlmCache`]; everything else passes through.
+pub struct CachedProvider {
+    inner: Arc<dyn LLMProvider>,
+    cache: Arc<LlmCache>,
+    /// Mixed into every key so backends (and embedding models) never share entries.
+    scope: String,
+}
+
+impl CachedProvider {
+    pub fn new(inner: Arc<dyn LLMProvider>, cache: Arc<LlmCache>) -> Self {
+        Self {
+            inner,
+            cache,
+            scope: String::new(),
+        }
+    }
+
+    /// Keys entries by `scope` as well, e.g. the endpoint URL and embedding model.
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = scope.into();
+        self
+    }
+
+    pub fn cache(&self) -> &Arc<LlmCache> {
+        &self.cache
+    }
+
+    /// Whether a chat call with `options` may use the cache at all.
+    fn cacheable(&self, options: &ChatOptions) -> bool {
+        options.temperature == Some(0.0) && options.cache.unwrap_or(self.cache.settings.enabled)
+    }
+
+    fn key(&self, request: Value) -> String {
+        let digest = Sha256::digest(json!([self.scope, request]).to_string());
+        digest.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn chat_key(
+        &self,
+        kind: &str,
+        model: &str,
+        messages: &[Message],
+        schema: Option<&Value>,
+        options: &ChatOptions,
+    ) -> String {
+        self.key(json!({
+            "kind": kind,
+            "model": model,
+            "messages": messages,
+            "schema": schema,
+            "temperature": options.temperature,
+            "max_tokens": options.max_tokens,
+        }))
+    }
+
+    /// The cached reply for `key` unless bypassed. Cache failures are logged and count as a miss.
+    async fn lookup(&self, key: &str, bypass: bool) -> Option<String> {
+        if bypass {
+            return None;
+        }
+        match self.cache.get(key).await {
+            Ok(Some(response)) => {
+                tracing::Span::current().record("cached", true);
+                record_token_usage(Some(0), Some(0));
+                Some(response)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("{e}");
+                None
+            }
+        }
+    }
+
+    async fn store(&self, key: &str, response: &str) {
+        if let Err(e) = self.cache.put(key, response).await {
+            warn!("{e}");
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for CachedProvider {
+    async fn chat(&self, model: &str, messages: &[Message]) -> Result<String, KowalskiError> {
+        self.inner.chat(model, messages).await
+    }
+
+    async fn chat_with_options(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        if !self.cacheable(options) {
+            return self.inner.chat_with_options(model, messages, options).await;
+        }
+        let key = self.chat_key("chat", model, messages, None, options);
+        if let Some(response) = self.lookup(&key, options.bypass_cache).await {
+            return Ok(response);
+        }
+        let response = self
+            .inner
+            .chat_with_options(model, messages, options)
+            .await?;
+        self.store(&key, &response).await;
+        Ok(response)
+    }
+
+    /// Not cached: the cache keeps text replies only.
+    async fn chat_message(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<Message, KowalskiError> {
+        self.inner.chat_message(model, messages, options).await
+    }
+
+    async fn chat_json(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.inner.chat_json(model, messages, options).await
+    }
+
+    async fn chat_structured(
+        &self,
+        model: &str,
+        messages: &[Message],
+        schema: &Value,
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        if !self.cacheable(options) {
+            return self
+                .inner
+                .chat_structured(model, messages, schema, options)
+                .await;
+        }
+        let key = self.chat_key("structured", model, messages, Some(schema), options);
+        if let Some(response) = self.lookup(&key, options.bypass_cache).await {
+            return Ok(response);
+        }
+        let response = self
+            .inner
+            .chat_structured(model, messages, schema, options)
+            .await?;
+        self.store(&key, &response).await;
+        Ok(response)
+    }
+
+    /// Embeddings are deterministic, so they are cached whenever `[llm.cache] enabled` is set.
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, KowalskiError> {
+        if !self.cache.settings.enabled {
+            return self.inner.embed(text).await;
+        }
+        let key = self.key(json!({"kind": "embed", "text": text}));
+        if let Some(embedding) = self
+            .lookup(&key, false)
+            .await
+            .and_then(|cached| serde_json::from_str(&cached).ok())
+        {
+            return Ok(embedding);
+        }
+        let embedding = self.inner.embed(text).await?;
+        if let Ok(serialized) = serde_json::to_string(&embedding) {
+            self.store(&key, &serialized).await;
+        }
+        Ok(embedding)
+    }
+
+    /// Cached embeddings are reused; the rest are embedded in one batch and cached.
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, KowalskiError> {
+        if !self.cache.settings.enabled {
+            return self.inner.embed_batch(texts).await;
+        }
+        let mut embeddings = Vec::with_capacity(texts.len());
+        let mut missing = Vec::new();
+        for (i, text) in texts.iter().enumerate() {
+            let key = self.key(json!({"kind": "embed", "text": text}));
+            let cached = self
+                .lookup(&key, false)
+                .await
+                .and_then(|cached| serde_json::from_str::<Vec<f32>>(&cached).ok());
+            if cached.is_none() {
+                missing.push((i, key));
+            }
+            embeddings.push(cached.unwrap_or_default());
+        }
+        if missing.is_empty() {
+            return Ok(embeddings);
+        }
+        let texts: Vec<String> = missing.iter().map(|(i, _)| texts[*i].clone()).collect();
+        let fresh = self.inner.embed_batch(&texts).await?;
+        for ((i, key), embedding) in missing.into_iter().zip(fresh) {
+            if let Ok(serialized) = serde_json::to_string(&embedding) {
+                self.store(&key, &serialized).await;
+            }
+            embeddings[i] = embedding;
+        }
+        Ok(embeddings)
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, KowalskiError> {
+        self.inner.list_models().await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn chat_stream(&self, model: &str, messages: Vec<Message>) -> TokenStream<'_> {
+        self.inner.chat_stream(model, messages)
+    }
+
+    fn chat_stream_with_options(
+        &self,
+        model: &str,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> TokenStream<'_> {
+        self.inner
+            .chat_stream_with_options(model, messages, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    fn schema() -> Value {
+        json!({"type": "object", "required": ["title"]})
+    }
+
+    fn deterministic() -> ChatOptions {
+        ChatOptions {
+            temperature: Some(0.0),
+            cache: Some(true),
+            ..ChatOptions::default()
+        }
+    }
+
+    fn messages(text: &str) -> Vec<Message> {
+        vec![Message {
+            role: "user".to_string(),
+            content: text.to_string(),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        }]
+    }
+
+    #[tokio::test]
+    async fn repeated_structured_call_hits_the_cache() {
+        let backend = Arc::new(
+            MockBackend::script()
+                .responds_with_text(r#"{"title": "Attention Is All You Need"}"#)
+                .build(),
+        );
+        let cache = Arc::new(LlmCache::in_memory(LlmCacheConfig::default()));
+        let provider = CachedProvider::new(backend.clone(), cache.clone());
+
+        let prompt = messages("Extract the title");
+        let first = provider
+            .chat_structured("llama3.2", &prompt, &schema(), &deterministic())
+            .await
+            .unwrap();
+        let second = provider
+            .chat_structured("llama3.2", &prompt, &schema(), &deterministic())
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(backend.requests().len(), 1);
+        assert_eq!(
+            cache.stats(),
+            LlmCacheStats {
+                hits: 1,
+                misses: 1,
+                stores: 1
+            }
+        );
+        backend.assert_finished();
+    }
+
+    #[tokio::test]
+    async fn only_opted_in_temperature_zero_calls_are_served_from_cache() {
+        let backend = Arc::new(
+            MockBackend::script()
+                .responds_with_text("a")
+                .then_text("b")
+                .then_text("c")
+                .then_text("d")
+                .build(),
+        );
+        let cache = Arc::new(LlmCache::in_memory(LlmCacheConfig {
+            max_entries: 1,
+            ..LlmCacheConfig::default()
+        }));
+        let provider = CachedProvider::new(backend.clone(), cache.clone());
+        let prompt = messages("Summarize");
+
+        let warm = ChatOptions {
+            temperature: Some(0.7),
+            ..deterministic()
+        };
+        let not_opted_in = ChatOptions {
+            cache: None,
+            ..deterministic()
+        };
+        let bypass = ChatOptions {
+            bypass_cache: true,
+            ..deterministic()
+        };
+        let call = |options: ChatOptions| {
+            let provider = &provider;
+            let prompt = &prompt;
+            async move {
+                provider
+                    .chat_with_options("m", prompt, &options)
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!(call(warm.clone()).await, "a");
+        assert_eq!(call(warm).await, "b");
+        assert_eq!(call(not_opted_in).await, "c");
+        assert!(cache.is_empty().await.unwrap());
+
+        assert_eq!(call(bypass).await, "d");
+        // The bypassed reply was stored and now serves the cached calls.
+        assert_eq!(call(deterministic()).await, "d");
+        assert_eq!(cache.len().await.unwrap(), 1);
+        assert_eq!(backend.requests().len(), 4);
+        backend.assert_finished();
+    }
+}
@@ -0,0 +1,27 @@
+//! The HTTP client shared by the LLM providers, [`ModelManager`](crate::model::ModelManager) and
+//! [`BaseAgent`](crate::agent::BaseAgent).
+//!
+//! Clones of a `reqwest::Client` share one connection pool, so an agent that calls Ollama many
+//! times per turn (chat, tool follow-ups, embeddings for every memory tier) reuses a kept-alive
+//! connection instead of paying a TCP (and TLS) handshake per request.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// How long an idle connection stays in the pool. Below the idle timeouts of the usual proxies
+/// and load balancers in front of OpenAI-compatible servers, so a reused connection is rarely
+/// one the other side has already closed.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The process-wide pooled client; every call returns a handle to the same pool.
+pub fn shared_http_client() -> reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            reqwest::Client::builder()
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .build()
+                .unwrap_or_default()
+        })
+        .clone()
+}
@@ -0,0 +1,540 @@
+//! Client-side throttling for outbound LLM requests.
+//!
+//! A [`RequestGovernor`] caps how many requests are in flight at once and how fast they start
+//! (even spacing per second, or a per-minute token bucket that allows bursts). Wrap any provider
+//! in a [`RateLimitedProvider`]; agents holding clones of the same `Arc<RequestGovernor>` share
+//! the budget. [`crate::llm::create_llm_provider`] does this automatically when `[ollama.limits]`
+//! or `[llm] max_in_flight` / `requests_per_second` is set, with one governor per endpoint for
+//! the whole process. [`ModelManager`](crate::model::ModelManager) can share it too.
+
+use super::provider::{ChatOptions, LLMProvider, TokenStream};
+use crate::config::{LLMConfig, OllamaLimits};
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// Limits for a [`RequestGovernor`]; `None` leaves that dimension unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimits {
+    pub max_in_flight: Option<usize>,
+    pub requests_per_second: Option<f32>,
+    /// Token bucket refill rate.
+    pub requests_per_minute: Option<u32>,
+    /// Token bucket size (default 1).
+    pub burst: Option<u32>,
+}
+
+impl RateLimits {
+    /// The limits configured in `[llm]`, or `None` when neither is set.
+    pub fn from_config(config: &LLMConfig) -> Option<Self> {
+        let limits = Self {
+            max_in_flight: config.max_in_flight.filter(|n| *n > 0),
+            requests_per_second: config.requests_per_second.filter(|r| *r > 0.0),
+            ..Self::default()
+        };
+        (limits != Self::default()).then_some(limits)
+    }
+
+    /// `[llm]` limits overridden field by field by `[ollama.limits]`, or `None` when nothing is
+    /// set.
+    pub fn for_ollama(llm: &LLMConfig, ollama: &OllamaLimits) -> Option<Self> {
+        let base = Self::from_config(llm).unwrap_or_default();
+        let limits = Self {
+            max_in_flight: ollama
+                .max_concurrent
+                .filter(|n| *n > 0)
+                .or(base.max_in_flight),
+            requests_per_minute: ollama.requests_per_minute.filter(|n| *n > 0),
+            burst: ollama.burst.filter(|n| *n > 0),
+            ..base
+        };
+        (limits != Self::default()).then_some(limits)
+    }
+}
+
+/// Counters of a [`RequestGovernor`] since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GovernorStats {
+    /// Requests that got a permit.
+    pub requests: u64,
+    /// Requests waiting for a permit right now.
+    pub waiting: usize,
+    /// Permits held right now.
+    pub in_flight: usize,
+    /// Time spent queued, summed over all requests.
+    pub total_queue_wait: Duration,
+    /// Longest single queue wait.
+    pub max_queue_wait: Duration,
+}
+
+/// Token bucket state for `requests_per_minute`.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+#[derive(Debug)]
+pub struct RequestGovernor {
+    limits: RateLimits,
+    in_flight: Option<Arc<Semaphore>>,
+    /// Earliest start of the next request when `requests_per_second` is set.
+    next_start: tokio::sync::Mutex<Instant>,
+    bucket: tokio::sync::Mutex<Bucket>,
+    waiting: AtomicUsize,
+    holding: Arc<AtomicUsize>,
+    stats: Mutex<GovernorStats>,
+}
+
+/// Earlier name of [`RequestGovernor`].
+pub type RateLimiter = RequestGovernor;
+
+/// Held while a request runs; dropping it frees the in-flight slot.
+#[derive(Debug)]
+pub struct RatePermit {
+    _slot: Option<OwnedSemaphorePermit>,
+    holding: Arc<AtomicUsize>,
+    queue_wait: Duration,
+}
+
+impl RatePermit {
+    /// How long the request waited for this permit.
+    pub fn queue_wait(&self) -> Duration {
+        self.queue_wait
+    }
+}
+
+impl Drop for RatePermit {
+    fn drop(&mut self) {
+        self.holding.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Decrements the waiting count however `acquire` ends (permit, error or drop).
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl RequestGovernor {
+    pub fn new(limits: RateLimits) -> Self {
+        let burst = limits.burst.unwrap_or(1).max(1);
+        Self {
+            limits,
+            in_flight: limits
+                .max_in_flight
+                .map(|n| Arc::new(Semaphore::new(n.max(1)))),
+            next_start: tokio::sync::Mutex::new(Instant::now()),
+            bucket: tokio::sync::Mutex::new(Bucket {
+                tokens: f64::from(burst),
+                refilled: Instant::now(),
+            }),
+            waiting: AtomicUsize::new(0),
+            holding: Arc::new(AtomicUsize::new(0)),
+            stats: Mutex::new(GovernorStats::default()),
+        }
+    }
+
+    /// The process-wide governor for `key` (e.g. the endpoint URL), created with `limits` on
+    /// first use. Later callers get the existing governor even if they ask for other limits.
+    pub fn shared(key: &str, limits: RateLimits) -> Arc<Self> {
+        static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<RequestGovernor>>>> = OnceLock::new();
+        let mut registry = REGISTRY
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        registry
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(Self::new(limits)))
+            .clone()
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        self.limits
+    }
+
+    pub fn stats(&self) -> GovernorStats {
+        let stats = *self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        GovernorStats {
+            waiting: self.waiting.load(Ordering::SeqCst),
+            in_flight: self.holding.load(Ordering::SeqCst),
+            ..stats
+        }
+    }
+
+    /// Waits for a free in-flight slot, then for the start rate. Dropping the future gives up
+    /// the place in the queue. The wait is recorded in [`Self::stats`], on the current span's
+    /// `queue_wait_ms` field and in the queue-wait metric.
+    pub async fn acquire(&self) -> Result<RatePermit, KowalskiError> {
+        let queued = Instant::now();
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        let waiting = Waiting(&self.waiting);
+        let slot =
+            match &self.in_flight {
+                Some(semaphore) => Some(semaphore.clone().acquire_owned().await.map_err(|_| {
+                    KowalskiError::RateLimit("Request governor closed".to_string())
+                })?),
+                None => None,
+            };
+        if let Some(rps) = self.limits.requests_per_second {
+            let start = {
+                let mut next = self.next_start.lock().await;
+                let start = (*next).max(Instant::now());
+                *next = start + Duration::from_secs_f32(1.0 / rps);
+                start
+            };
+            tokio::time::sleep_until(start).await;
+        }
+        if let Some(rpm) = self.limits.requests_per_minute {
+            self.take_token(rpm).await;
+        }
+        drop(waiting);
+
+        let queue_wait = queued.elapsed();
+        self.holding.fetch_add(1, Ordering::SeqCst);
+        {
+            let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+            stats.requests += 1;
+            stats.total_queue_wait += queue_wait;
+            stats.max_queue_wait = stats.max_queue_wait.max(queue_wait);
+        }
+        tracing::Span::current().record("queue_wait_ms", queue_wait.as_millis() as u64);
+        crate::metrics::record_queue_wait(queue_wait);
+        Ok(RatePermit {
+            _slot: slot,
+            holding: self.holding.clone(),
+            queue_wait,
+        })
+    }
+
+    /// Like [`Self::acquire`], but gives up with [`KowalskiError::Cancelled`] once `cancel` is
+    /// cancelled while the request is still queued.
+    pub async fn acquire_cancellable(
+        &self,
+        cancel: &CancellationToken,
+    ) -> Result<RatePermit, KowalskiError> {
+        tokio::select! {
+            biased;
+            _ = cancel.cancelled() => Err(KowalskiError::Cancelled),
+            permit = self.acquire() => permit,
+        }
+    }
+
+    /// Takes one token from the `requests_per_minute` bucket, waiting for the refill if empty.
+    async fn take_token(&self, rpm: u32) {
+        let per_second = f64::from(rpm) / 60.0;
+        let capacity = f64::from(self.limits.burst.unwrap_or(1).max(1));
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                let refill = now.duration_since(bucket.refilled).as_secs_f64() * per_second;
+                bucket.tokens = (bucket.tokens + refill).min(capacity);
+                bucket.refilled = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / per_second)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Runs every chat, embedding and streaming request of `inner` through a [`RequestGovernor`].
+pub struct RateLimitedProvider {
+    inner: Arc<dyn LLMProvider>,
+    governor: Arc<RequestGovernor>,
+    cancel: Option<CancellationToken>,
+}
+
+impl RateLimitedProvider {
+    pub fn new(inner: Arc<dyn LLMProvider>, governor: Arc<RequestGovernor>) -> Self {
+        Self {
+            inner,
+            governor,
+            cancel: None,
+        }
+    }
+
+    /// Requests still queued when `cancel` fires fail instead of waiting (e.g. on shutdown).
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub fn governor(&self) -> &Arc<RequestGovernor> {
+        &self.governor
+    }
+
+    async fn permit(&self) -> Result<RatePermit, KowalskiError> {
+        match &self.cancel {
+            Some(cancel) => self.governor.acquire_cancellable(cancel).await,
+            None => self.governor.acquire().await,
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for RateLimitedProvider {
+    async fn chat(&self, model: &str, messages: &[Message]) -> Result<String, KowalskiError> {
+        let _permit = self.permit().await?;
+        self.inner.chat(model, messages).await
+    }
+
+    async fn chat_with_options(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        let _permit = self.permit().await?;
+        self.inner.chat_with_options(model, messages, options).await
+    }
+
+    async fn chat_message(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<Message, KowalskiError> {
+        let _permit = self.permit().await?;
+        self.inner.chat_message(model, messages, options).await
+    }
+
+    async fn chat_json(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        let _permit = self.permit().await?;
+        self.inner.chat_json(model, messages, options).await
+    }
+
+    async fn chat_structured(
+        &self,
+        model: &str,
+        messages: &[Message],
+        schema: &serde_json::Value,
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        let _permit = self.permit().await?;
+        self.inner
+            .chat_structured(model, messages, schema, options)
+            .await
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, KowalskiError> {
+        let _permit = self.permit().await?;
+        self.inner.embed(text).await
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, KowalskiError> {
+        let _permit = self.permit().await?;
+        self.inner.embed_batch(texts).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, KowalskiError> {
+        let _permit = self.permit().await?;
+        self.inner.list_models().await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn chat_stream(&self, model: &str, messages: Vec<Message>) -> TokenStream<'_> {
+        self.chat_stream_with_options(model, messages, &ChatOptions::default())
+    }
+
+    /// The in-flight slot is held until the stream ends.
+    fn chat_stream_with_options(
+        &self,
+        model: &str,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> TokenStream<'_> {
+        let model = model.to_string();
+        let options = options.clone();
+        Box::pin(async_stream::stream! {
+            let _permit = match self.permit().await {
+                Ok(permit) => permit,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+            let mut tokens = self.inner.chat_stream_with_options(&model, messages, &options);
+            while let Some(token) = tokens.next().await {
+                yield token;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    /// Answers every call after 20ms.
+    fn slow_backend() -> Arc<MockBackend> {
+        Arc::new(
+            MockBackend::script()
+                .otherwise(|_| crate::testing::MockReply::text("ok"))
+                .with_embedding(vec![0.0])
+                .with_latency(Duration::from_millis(20))
+                .build(),
+        )
+    }
+
+    /// Chat and embedding calls the backend received.
+    fn calls(backend: &MockBackend) -> usize {
+        backend.requests().len() + backend.embed_requests().len()
+    }
+
+    #[tokio::test]
+    async fn concurrency_never_exceeds_max_in_flight() {
+        let backend = slow_backend();
+        let governor = Arc::new(RequestGovernor::new(RateLimits {
+            max_in_flight: Some(2),
+            ..RateLimits::default()
+        }));
+        // Two "agents" sharing one governor.
+        let a = Arc::new(RateLimitedProvider::new(backend.clone(), governor.clone()));
+        let b = Arc::new(RateLimitedProvider::new(backend.clone(), governor.clone()));
+
+        let mut tasks = Vec::new();
+        for i in 0..20 {
+            let provider = if i % 2 == 0 { a.clone() } else { b.clone() };
+            tasks.push(tokio::spawn(async move {
+                if i % 5 == 0 {
+                    provider.embed("x").await.map(|_| ())
+                } else {
+                    provider.chat("m", &[]).await.map(|_| ())
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(calls(&backend), 20);
+        assert_eq!(backend.peak_in_flight(), 2);
+
+        let stats = governor.stats();
+        assert_eq!((stats.requests, stats.waiting, stats.in_flight), (20, 0, 0));
+        // Ten rounds of two 20ms calls: the last ones queued for about 180ms.
+        assert!(stats.max_queue_wait >= Duration::from_millis(150));
+        assert!(stats.total_queue_wait > stats.max_queue_wait);
+    }
+
+    #[tokio::test]
+    async fn requests_per_second_spaces_out_starts() {
+        let governor = RequestGovernor::new(RateLimits {
+            requests_per_second: Some(50.0),
+            ..RateLimits::default()
+        });
+        let started = Instant::now();
+        for _ in 0..4 {
+            drop(governor.acquire().await.unwrap());
+        }
+        // The first request starts immediately, then one every 20ms.
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[tokio::test]
+    async fn token_bucket_allows_a_burst_then_refills() {
+        let governor = RequestGovernor::new(RateLimits {
+            requests_per_minute: Some(1200),
+            burst: Some(3),
+            ..RateLimits::default()
+        });
+        let started = Instant::now();
+        for _ in 0..3 {
+            drop(governor.acquire().await.unwrap());
+        }
+        assert!(started.elapsed() < Duration::from_millis(40));
+        // 1200/min is one token every 50ms.
+        drop(governor.acquire().await.unwrap());
+        assert!(started.elapsed() >= Duration::from_millis(45));
+    }
+
+    #[tokio::test]
+    async fn cancelled_requests_leave_the_queue() {
+        let backend = slow_backend();
+        let governor = Arc::new(RequestGovernor::new(RateLimits {
+            max_in_flight: Some(1),
+            ..RateLimits::default()
+        }));
+        let cancel = CancellationToken::new();
+        let provider = Arc::new(
+            RateLimitedProvider::new(backend.clone(), governor.clone())
+                .with_cancellation(cancel.clone()),
+        );
+        let held = governor.acquire().await.unwrap();
+
+        let queued = tokio::spawn({
+            let provider = provider.clone();
+            async move { provider.chat("m", &[]).await }
+        });
+        while governor.stats().waiting == 0 {
+            tokio::task::yield_now().await;
+        }
+        cancel.cancel();
+        let err = queued.await.unwrap().unwrap_err();
+        assert!(matches!(err, KowalskiError::Cancelled));
+        assert_eq!(governor.stats().waiting, 0);
+        assert_eq!(calls(&backend), 0);
+        drop(held);
+        assert_eq!(governor.stats().in_flight, 0);
+    }
+
+    #[test]
+    fn limits_come_from_config_only_when_set() {
+        let mut config = LLMConfig::default();
+        assert_eq!(RateLimits::from_config(&config), None);
+        config.max_in_flight = Some(0);
+        assert_eq!(RateLimits::from_config(&config), None);
+        config.requests_per_second = Some(2.5);
+        assert_eq!(
+            RateLimits::from_config(&config),
+            Some(RateLimits {
+                requests_per_second: Some(2.5),
+                ..RateLimits::default()
+            })
+        );
+    }
+
+    #[test]
+    fn ollama_limits_override_llm_limits() {
+        let mut llm = LLMConfig::default();
+        let mut ollama = OllamaLimits::default();
+        assert_eq!(RateLimits::for_ollama(&llm, &ollama), None);
+        llm.max_in_flight = Some(8);
+        llm.requests_per_second = Some(2.0);
+        ollama.max_concurrent = Some(2);
+        ollama.requests_per_minute = Some(30);
+        assert_eq!(
+            RateLimits::for_ollama(&llm, &ollama),
+            Some(RateLimits {
+                max_in_flight: Some(2),
+                requests_per_second: Some(2.0),
+                requests_per_minute: Some(30),
+                burst: None,
+            })
+        );
+    }
+}
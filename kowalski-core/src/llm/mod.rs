@@ -1,31 +1,112 @@
+pub mod cache;
+pub mod http;
+pub mod limiter;
 pub mod ollama;
 pub mod openai;
 pub mod provider;
 
+pub use cache::{CachedProvider, LlmCache, LlmCacheStats};
+pub use http::shared_http_client;
+pub use limiter::{
+    GovernorStats, RateLimitedProvider, RateLimiter, RateLimits, RatePermit, RequestGovernor,
+};
 pub use ollama::OllamaProvider;
 pub use openai::OpenAIProvider;
-pub use provider::{LLMProvider, TokenStream, chat_stream_single_chunk};
+pub use provider::{ChatOptions, LLMProvider, TokenStream, chat_stream_single_chunk};
 
 use crate::config::Config;
 use crate::error::KowalskiError;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use tokio_util::sync::CancellationToken;
 
-/// Creates an LLM provider based on the configuration
+/// Creates an LLM provider based on the configuration, throttled when `[llm]` or
+/// `[ollama.limits]` sets rate limits. Deterministic calls go through the `[llm.cache]`
+/// [`LlmCache`] first, so cache hits take no rate-limit permit. Requests still queued for a
+/// permit fail once [`shutdown_token`] is cancelled.
 pub fn create_llm_provider(config: &Config) -> Result<Arc<dyn LLMProvider>, KowalskiError> {
+    let provider = create_unlimited_provider(config);
+    let provider: Arc<dyn LLMProvider> = match request_governor(config) {
+        Some(governor) => Arc::new(
+            RateLimitedProvider::new(provider, governor).with_cancellation(shutdown_token()),
+        ),
+        None => provider,
+    };
+    let endpoint = match config.llm.provider.as_str() {
+        "openai" | "openai_compat" => openai_endpoint(config),
+        _ => ollama_endpoint(config),
+    };
+    let scope = format!(
+        "{endpoint} {}",
+        config.embedding.model.as_deref().unwrap_or_default()
+    );
+    let cache = Arc::new(LlmCache::from_config(config));
+    Ok(Arc::new(
+        CachedProvider::new(provider, cache).with_scope(scope),
+    ))
+}
+
+/// The process-wide [`RequestGovernor`] for the configured endpoint, or `None` when no limits
+/// are set. Agents and [`ModelManager`](crate::model::ModelManager)s built from the same
+/// endpoint share it.
+pub fn request_governor(config: &Config) -> Option<Arc<RequestGovernor>> {
+    match config.llm.provider.as_str() {
+        "openai" | "openai_compat" => Some(RequestGovernor::shared(
+            &openai_endpoint(config),
+            RateLimits::from_config(&config.llm)?,
+        )),
+        _ => ollama_governor(config),
+    }
+}
+
+/// The shared [`RequestGovernor`] of the Ollama endpoint, whichever provider is selected.
+pub(crate) fn ollama_governor(config: &Config) -> Option<Arc<RequestGovernor>> {
+    let limits = RateLimits::for_ollama(&config.llm, &config.ollama.limits)?;
+    Some(RequestGovernor::shared(&ollama_endpoint(config), limits))
+}
+
+/// The process-wide shutdown token. Servers cancel it when they stop, so requests that are still
+/// waiting for a rate-limit permit fail with [`KowalskiError::Cancelled`] instead of holding up
+/// the shutdown.
+pub fn shutdown_token() -> CancellationToken {
+    static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+    TOKEN.get_or_init(CancellationToken::new).clone()
+}
+
+fn openai_endpoint(config: &Config) -> String {
+    config
+        .llm
+        .openai_api_base
+        .as_deref()
+        .unwrap_or("https://api.openai.com/v1")
+        .to_string()
+}
+
+pub(crate) fn ollama_endpoint(config: &Config) -> String {
+    format!("http://{}:{}", config.ollama.host, config.ollama.port)
+}
+
+fn create_unlimited_provider(config: &Config) -> Arc<dyn LLMProvider> {
     match config.llm.provider.as_str() {
-        "openai" => {
+        "openai" | "openai_compat" => {
             let api_key = config.llm.openai_api_key.clone().unwrap_or_default();
             let base = config.llm.openai_api_base.as_deref();
-            Ok(Arc::new(OpenAIProvider::new(&api_key, base)))
+            let mut provider =
+                OpenAIProvider::new(&api_key, base).with_model_map(config.llm.model_map.clone());
+            if let Some(model) = &config.embedding.model {
+                provider = provider.with_embedding_model(model);
+            }
+            Arc::new(provider)
+        }
+        _ => {
+            let mut provider = OllamaProvider::new(&config.ollama.host, config.ollama.port);
+            if let Some(model) = &config.embedding.model {
+                provider = provider.with_embedding_model(model);
+            }
+            if let Some(options) = &config.chat.options {
+                provider = provider.with_options(options.clone());
+            }
+            Arc::new(provider)
         }
-        "ollama" => Ok(Arc::new(OllamaProvider::new(
-            &config.ollama.host,
-            config.ollama.port,
-        ))),
-        _ => Ok(Arc::new(OllamaProvider::new(
-            &config.ollama.host,
-            config.ollama.port,
-        ))),
     }
 }
 
@@ -51,4 +132,12 @@ mod tests {
         c.llm.openai_api_base = Some("http://127.0.0.1:1234/v1".to_string());
         assert!(create_llm_provider(&c).is_ok());
     }
+
+    #[test]
+    fn openai_compat_is_an_alias_for_openai() {
+        let mut c = Config::default();
+        c.llm.provider = "openai_compat".to_string();
+        c.llm.openai_api_base = Some("http://127.0.0.1:8000/v1".to_string());
+        assert!(create_llm_provider(&c).is_ok());
+    }
 }
@@ -1,56 +1,171 @@
-use super::provider::{LLMProvider, TokenStream};
-use crate::agent::types::ChatRequest;
+use super::provider::{ChatOptions, LLMProvider, TokenStream, record_token_usage};
+use crate::config::OllamaOptions;
 use crate::conversation::Message;
 use crate::error::KowalskiError;
+use crate::utils::ndjson::NdjsonBuffer;
+use crate::utils::redact::redacted_json;
 use async_trait::async_trait;
 use futures::StreamExt;
+use log::{Level, log_enabled, trace};
 use reqwest::Client;
+use serde::Serialize;
+
+/// Embedding model used when none is configured.
+pub const DEFAULT_OLLAMA_EMBEDDING_MODEL: &str = "nomic-embed-text";
+
+/// Sampling settings when a call brings none.
+const DEFAULT_TEMPERATURE: f32 = 0.7;
+const DEFAULT_NUM_PREDICT: u32 = 2048;
 
 pub struct OllamaProvider {
     base_url: String,
     client: Client,
+    embedding_model: String,
+    options: Option<OllamaOptions>,
 }
 
 impl OllamaProvider {
     pub fn new(host: &str, port: u16) -> Self {
         let base_url = format!("http://{}:{}", host, port);
-        let client = Client::new();
-        Self { base_url, client }
+        Self {
+            base_url,
+            client: super::shared_http_client(),
+            embedding_model: DEFAULT_OLLAMA_EMBEDDING_MODEL.to_string(),
+            options: None,
+        }
+    }
+
+    /// Sends requests with `client` instead of the [`super::shared_http_client`].
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Model for [`LLMProvider::embed`] (see [`crate::config::EmbeddingConfig`]).
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
+
+    /// Sends `options` with every chat request unless the call brings its own
+    /// ([`ChatOptions::ollama`]).
+    pub fn with_options(mut self, options: OllamaOptions) -> Self {
+        self.options = Some(options);
+        self
     }
 }
 
-#[async_trait]
-impl LLMProvider for OllamaProvider {
-    async fn chat(&self, model: &str, messages: &[Message]) -> Result<String, KowalskiError> {
-        let url = format!("{}/api/chat", self.base_url);
-        let request = ChatRequest {
+/// An `/api/chat` body. Ollama reads sampling settings only under `options`, so temperature and
+/// `max_tokens` (as `num_predict`) go there with the [`OllamaOptions`]; `keep_alive` stays beside
+/// `model`.
+#[derive(Debug, Serialize)]
+struct ChatBody {
+    model: String,
+    messages: Vec<Message>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
+    options: SamplingOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SamplingOptions {
+    temperature: f32,
+    num_predict: u32,
+    #[serde(flatten)]
+    ollama: OllamaOptions,
+}
+
+/// A failed `send()` to the server at `endpoint`.
+fn unreachable(endpoint: &str, source: reqwest::Error) -> KowalskiError {
+    KowalskiError::OllamaUnreachable {
+        endpoint: endpoint.to_string(),
+        source,
+    }
+}
+
+/// Error for a non-success reply about `model`: Ollama answers 404 with
+/// `model "…" not found, try pulling it first` when it does not have it. 429 and 5xx replies
+/// are [retryable](KowalskiError::is_retryable).
+fn error_reply(status: reqwest::StatusCode, body: &str, model: &str) -> KowalskiError {
+    if status == reqwest::StatusCode::NOT_FOUND && body.contains("not found") {
+        KowalskiError::ModelNotFound {
+            model: model.to_string(),
+        }
+    } else if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        KowalskiError::RateLimit(format!("Ollama error: {}", body))
+    } else if status.is_server_error() {
+        KowalskiError::ServerUnavailable(format!("Ollama error {status}: {body}"))
+    } else {
+        KowalskiError::Server(format!("Ollama error: {}", body))
+    }
+}
+
+impl OllamaProvider {
+    /// The body for one call; its Ollama options replace the provider's.
+    fn chat_body(
+        &self,
+        model: &str,
+        messages: Vec<Message>,
+        stream: bool,
+        format: Option<serde_json::Value>,
+        options: &ChatOptions,
+    ) -> ChatBody {
+        let mut ollama = options
+            .ollama
+            .clone()
+            .or_else(|| self.options.clone())
+            .unwrap_or_default();
+        let keep_alive = ollama.keep_alive.take();
+        ChatBody {
             model: model.to_string(),
-            messages: messages.to_vec(),
-            stream: false,
-            temperature: 0.7,
-            max_tokens: 2048,
-            tools: None,
-        };
+            messages,
+            stream,
+            format,
+            options: SamplingOptions {
+                temperature: options.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+                num_predict: options.max_tokens.unwrap_or(DEFAULT_NUM_PREDICT),
+                ollama,
+            },
+            keep_alive,
+        }
+    }
 
+    /// Non-streaming `/api/chat`; `format` is passed through as Ollama's output constraint.
+    async fn send_chat(
+        &self,
+        model: &str,
+        messages: &[Message],
+        format: Option<serde_json::Value>,
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        let url = format!("{}/api/chat", self.base_url);
+        let request = self.chat_body(model, messages.to_vec(), false, format, options);
+
+        if log_enabled!(Level::Trace) {
+            trace!("POST {url}: {}", redacted_json(&request));
+        }
         let response = self
             .client
             .post(&url)
             .json(&request)
             .send()
             .await
-            .map_err(|e| KowalskiError::Server(format!("Failed to connect to Ollama: {}", e)))?;
+            .map_err(|e| unreachable(&self.base_url, e))?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(KowalskiError::Server(format!(
-                "Ollama error: {}",
-                error_text
-            )));
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| KowalskiError::Server(format!("Failed to read Ollama response: {}", e)))?;
+        trace!("POST {url} -> {status}: {body}");
+        if !status.is_success() {
+            return Err(error_reply(status, &body, model));
         }
 
-        let response_json: serde_json::Value = response
-            .json()
-            .await
+        let response_json: serde_json::Value = serde_json::from_str(&body)
             .map_err(|e| KowalskiError::Server(format!("Failed to parse JSON: {}", e)))?;
 
         let content = response_json["message"]["content"]
@@ -59,27 +174,71 @@ impl LLMProvider for OllamaProvider {
                 "No content in Ollama response".to_string(),
             ))?
             .to_string();
+        record_token_usage(
+            response_json["prompt_eval_count"].as_u64(),
+            response_json["eval_count"].as_u64(),
+        );
 
         Ok(content)
     }
+}
 
+#[async_trait]
+impl LLMProvider for OllamaProvider {
+    async fn chat(&self, model: &str, messages: &[Message]) -> Result<String, KowalskiError> {
+        self.send_chat(model, messages, None, &ChatOptions::default())
+            .await
+    }
+
+    async fn chat_with_options(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.send_chat(model, messages, None, options).await
+    }
+
+    async fn chat_json(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.send_chat(model, messages, Some(serde_json::json!("json")), options)
+            .await
+    }
+
+    async fn chat_structured(
+        &self,
+        model: &str,
+        messages: &[Message],
+        schema: &serde_json::Value,
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.send_chat(model, messages, Some(schema.clone()), options)
+            .await
+    }
+
+    #[tracing::instrument(name = "embedding", skip_all, fields(model = %self.embedding_model))]
     async fn embed(&self, text: &str) -> Result<Vec<f32>, KowalskiError> {
+        crate::metrics::record_embedding_request();
         let url = format!("{}/api/embeddings", self.base_url);
         let response = self
             .client
             .post(&url)
             .json(&serde_json::json!({
-                "model": "nomic-embed-text",
+                "model": self.embedding_model,
                 "prompt": text
             }))
             .send()
             .await
-            .map_err(|e| {
-                KowalskiError::Memory(format!("Failed to call Ollama embedding: {}", e))
-            })?;
+            .map_err(|e| unreachable(&self.base_url, e))?;
 
-        if !response.status().is_success() {
-            return Err(KowalskiError::Memory("Ollama embedding failed".to_string()));
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(embedding_error(status, &body, &self.embedding_model));
         }
 
         let json: serde_json::Value = response
@@ -87,16 +246,90 @@ impl LLMProvider for OllamaProvider {
             .await
             .map_err(|e| KowalskiError::Memory(format!("Failed to parse embedding JSON: {}", e)))?;
 
-        let embedding = json["embedding"]
+        json["embedding"]
             .as_array()
+            .map(|values| vector(values))
             .ok_or(KowalskiError::Memory(
                 "No embedding field in response".to_string(),
+            ))
+    }
+
+    /// One `/api/embed` request for all of `texts`.
+    #[tracing::instrument(name = "embedding", skip_all, fields(model = %self.embedding_model, texts = texts.len()))]
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, KowalskiError> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        crate::metrics::record_embedding_request();
+        let url = format!("{}/api/embed", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({
+                "model": self.embedding_model,
+                "input": texts
+            }))
+            .send()
+            .await
+            .map_err(|e| unreachable(&self.base_url, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(embedding_error(status, &body, &self.embedding_model));
+        }
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| KowalskiError::Memory(format!("Failed to parse embedding JSON: {}", e)))?;
+        let embeddings: Vec<Vec<f32>> = json["embeddings"]
+            .as_array()
+            .ok_or(KowalskiError::Memory(
+                "No embeddings field in response".to_string(),
             ))?
             .iter()
-            .map(|v| v.as_f64().unwrap_or(0.0) as f32)
-            .collect();
+            .map(|embedding| embedding.as_array().map(|values| vector(values)))
+            .collect::<Option<_>>()
+            .ok_or(KowalskiError::Memory(
+                "Malformed embeddings in response".to_string(),
+            ))?;
+        if embeddings.len() != texts.len() {
+            return Err(KowalskiError::Memory(format!(
+                "Ollama returned {} embeddings for {} texts",
+                embeddings.len(),
+                texts.len()
+            )));
+        }
+        Ok(embeddings)
+    }
 
-        Ok(embedding)
+    async fn list_models(&self) -> Result<Vec<String>, KowalskiError> {
+        let url = format!("{}/api/tags", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| unreachable(&self.base_url, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(error_reply(status, &error_text, ""));
+        }
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| KowalskiError::Server(format!("Failed to parse JSON: {}", e)))?;
+        Ok(json["models"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["name"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
     }
 
     fn supports_streaming(&self) -> bool {
@@ -104,30 +337,37 @@ impl LLMProvider for OllamaProvider {
     }
 
     fn chat_stream(&self, model: &str, messages: Vec<Message>) -> TokenStream<'_> {
+        self.chat_stream_with_options(model, messages, &ChatOptions::default())
+    }
+
+    fn chat_stream_with_options(
+        &self,
+        model: &str,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> TokenStream<'_> {
         let url = format!("{}/api/chat", self.base_url);
-        let request = ChatRequest {
-            model: model.to_string(),
-            messages,
-            stream: true,
-            temperature: 0.7,
-            max_tokens: 2048,
-            tools: None,
-        };
+        let request = self.chat_body(model, messages, true, None, options);
+        if log_enabled!(Level::Trace) {
+            trace!("POST {url} (stream): {}", redacted_json(&request));
+        }
         let client = self.client.clone();
+        let endpoint = self.base_url.clone();
         Box::pin(async_stream::stream! {
             let response = match client.post(&url).json(&request).send().await {
                 Ok(r) => r,
                 Err(e) => {
-                    yield Err(KowalskiError::Server(format!("Ollama stream: {e}")));
+                    yield Err(unreachable(&endpoint, e));
                     return;
                 }
             };
-            if !response.status().is_success() {
+            let status = response.status();
+            if !status.is_success() {
                 let t = response.text().await.unwrap_or_default();
-                yield Err(KowalskiError::Server(format!("Ollama error: {t}")));
+                yield Err(error_reply(status, &t, &request.model));
                 return;
             }
-            let mut buf: Vec<u8> = Vec::new();
+            let mut buf = NdjsonBuffer::new();
             let mut bytes_stream = response.bytes_stream();
             while let Some(chunk) = bytes_stream.next().await {
                 let chunk = match chunk {
@@ -137,24 +377,40 @@ impl LLMProvider for OllamaProvider {
                         return;
                     }
                 };
-                buf.extend_from_slice(&chunk);
-                while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
-                    let raw: Vec<u8> = buf.drain(..=pos).collect();
-                    let line = String::from_utf8_lossy(&raw);
-                    let line = line.trim();
-                    if line.is_empty() {
-                        continue;
+                for line in buf.push(&chunk) {
+                    if let Some(content) = stream_content(&line) {
+                        yield Ok(content);
                     }
-                    let v: serde_json::Value = match serde_json::from_str(line) {
-                        Ok(v) => v,
-                        Err(_) => continue,
-                    };
-                    if let Some(c) = v["message"]["content"].as_str()
-                        && !c.is_empty() {
-                            yield Ok(c.to_string());
-                        }
                 }
             }
+            // The last object often arrives without a trailing newline.
+            if let Some(content) = buf.take_complete().as_deref().and_then(stream_content) {
+                yield Ok(content);
+            }
         })
     }
 }
+
+/// Error for a failed embedding request; unclassified server errors are memory errors.
+fn embedding_error(status: reqwest::StatusCode, body: &str, model: &str) -> KowalskiError {
+    match error_reply(status, body, model) {
+        KowalskiError::Server(_) => KowalskiError::Memory("Ollama embedding failed".to_string()),
+        e => e,
+    }
+}
+
+fn vector(values: &[serde_json::Value]) -> Vec<f32> {
+    values
+        .iter()
+        .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+        .collect()
+}
+
+/// Non-empty `message.content` of one `/api/chat` stream line.
+fn stream_content(line: &str) -> Option<String> {
+    let v: serde_json::Value = serde_json::from_str(line).ok()?;
+    v["message"]["content"]
+        .as_str()
+        .filter(|c| !c.is_empty())
+        .map(str::to_string)
+}
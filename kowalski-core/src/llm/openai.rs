@@ -1,25 +1,59 @@
-use super::provider::LLMProvider;
 use super::provider::TokenStream;
-use crate::conversation::Message;
+use super::provider::{ChatOptions, LLMProvider, record_token_usage};
+use crate::conversation::{Message, MessageRole};
 use crate::error::KowalskiError;
+use crate::utils::redact::redacted_json;
 use async_openai::{
     Client,
     config::OpenAIConfig,
+    error::OpenAIError,
     types::{
         chat::{
+            ChatCompletionMessageToolCall, ChatCompletionMessageToolCalls,
             ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
-            ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
-            CreateChatCompletionRequestArgs,
+            ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestToolMessageArgs,
+            ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs, FunctionCall,
         },
         embeddings::CreateEmbeddingRequestArgs,
     },
 };
 use async_trait::async_trait;
 use futures::StreamExt;
+use log::{Level, log_enabled, trace};
+use std::collections::HashMap;
+
+const DEFAULT_OPENAI_API_BASE: &str = "https://api.openai.com/v1";
+
+/// Error for a failed API call. The client has already backed off and retried 429 and 5xx
+/// replies; those that still fail stay [retryable](KowalskiError::is_retryable). A 5xx body
+/// that is not an OpenAI error object comes back with no `type` or `code`.
+fn api_error(context: &str, e: OpenAIError) -> KowalskiError {
+    match e {
+        OpenAIError::Reqwest(e) => KowalskiError::Request(e),
+        OpenAIError::ApiError(api) => {
+            let kind = api.r#type.as_deref().or(api.code.as_deref());
+            match kind {
+                Some("rate_limit_exceeded" | "requests" | "tokens") => {
+                    KowalskiError::RateLimit(format!("{context}: {api}"))
+                }
+                Some("server_error" | "service_unavailable") | None => {
+                    KowalskiError::ServerUnavailable(format!("{context}: {api}"))
+                }
+                Some(_) => KowalskiError::Server(format!("{context}: {api}")),
+            }
+        }
+        e => KowalskiError::Server(format!("{context}: {e}")),
+    }
+}
 
 pub struct OpenAIProvider {
     client: Client<OpenAIConfig>,
     embedding_model: String,
+    api_base: String,
+    api_key: String,
+    http: reqwest::Client,
+    /// Kowalski model name -> server model name (e.g. `llama3.2` -> `meta-llama/Llama-3.2-3B-Instruct`).
+    model_map: HashMap<String, String>,
 }
 
 impl OpenAIProvider {
@@ -27,62 +61,156 @@ impl OpenAIProvider {
     /// `api_base` should be the full OpenAI API root (e.g. `https://api.openai.com/v1` or `http://localhost:1234/v1`).
     pub fn new(api_key: &str, api_base: Option<&str>) -> Self {
         let mut config = OpenAIConfig::new().with_api_key(api_key);
+        let mut resolved_base = DEFAULT_OPENAI_API_BASE.to_string();
         if let Some(base) = api_base {
             let trimmed = base.trim();
             if !trimmed.is_empty() {
                 config = config.with_api_base(trimmed);
+                resolved_base = trimmed.trim_end_matches('/').to_string();
             }
         }
-        let client = Client::with_config(config);
+        let http = super::shared_http_client();
+        let client = Client::with_config(config).with_http_client(http.clone());
         Self {
             client,
             embedding_model: "text-embedding-3-small".to_string(),
+            api_base: resolved_base,
+            api_key: api_key.to_string(),
+            http,
+            model_map: HashMap::new(),
         }
     }
+
+    /// Renames models before they are sent to the server (see [`crate::config::LLMConfig::model_map`]).
+    pub fn with_model_map(mut self, model_map: HashMap<String, String>) -> Self {
+        self.model_map = model_map;
+        self
+    }
+
+    /// Model for [`LLMProvider::embed`] (see [`crate::config::EmbeddingConfig`]).
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
+
+    fn resolve_model(&self, model: &str) -> String {
+        self.model_map
+            .get(model)
+            .cloned()
+            .unwrap_or_else(|| model.to_string())
+    }
 }
 
 #[async_trait]
 impl LLMProvider for OpenAIProvider {
     async fn chat(&self, model: &str, messages: &[Message]) -> Result<String, KowalskiError> {
+        self.chat_with_options(model, messages, &ChatOptions::default())
+            .await
+    }
+
+    /// The reply's text, or its native tool calls as the JSON the tool loop reads.
+    async fn chat_with_options(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        let message = self.chat_message(model, messages, options).await?;
+        match &message.tool_calls {
+            Some(calls) if message.content.trim().is_empty() => {
+                Ok(tool_calls_as_text(calls.iter().map(|c| {
+                    (c.function.name.as_str(), &c.function.arguments)
+                })))
+            }
+            _ => Ok(message.content),
+        }
+    }
+
+    async fn chat_message(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<Message, KowalskiError> {
         let openai_messages = messages_to_openai(messages)?;
 
-        let request = CreateChatCompletionRequestArgs::default()
-            .model(model)
-            .messages(openai_messages)
+        let mut args = CreateChatCompletionRequestArgs::default();
+        args.model(self.resolve_model(model))
+            .messages(openai_messages);
+        if let Some(temperature) = options.temperature {
+            args.temperature(temperature);
+        }
+        if let Some(max_tokens) = options.max_tokens {
+            args.max_completion_tokens(max_tokens);
+        }
+        let request = args
             .build()
             .map_err(|e| KowalskiError::Initialization(format!("OpenAI request error: {}", e)))?;
 
-        let response = self
-            .client
-            .chat()
-            .create(request)
-            .await
-            .map_err(|e| KowalskiError::Server(format!("OpenAI API error: {}", e)))?;
+        if log_enabled!(Level::Trace) {
+            trace!(
+                "POST {}/chat/completions: {}",
+                self.api_base,
+                redacted_json(&request)
+            );
+        }
+        let response = self.client.chat().create(request).await.map_err(|e| {
+            trace!("POST {}/chat/completions failed: {e}", self.api_base);
+            api_error("OpenAI API error", e)
+        })?;
+        if log_enabled!(Level::Trace) {
+            trace!(
+                "POST {}/chat/completions -> {}",
+                self.api_base,
+                redacted_json(&response)
+            );
+        }
 
-        let content = response
+        let reply = response
             .choices
             .first()
-            .and_then(|choice| choice.message.content.clone())
+            .map(|choice| &choice.message)
+            .filter(|m| m.content.is_some() || m.tool_calls.as_ref().is_some_and(|c| !c.is_empty()))
             .ok_or(KowalskiError::Server(
                 "No content in OpenAI response".to_string(),
             ))?;
+        let tool_calls = reply.tool_calls.as_deref().map(tool_calls_from_openai);
+        let content = reply.content.clone().unwrap_or_default();
+        if let Some(usage) = &response.usage {
+            record_token_usage(
+                Some(usage.prompt_tokens.into()),
+                Some(usage.completion_tokens.into()),
+            );
+        }
 
-        Ok(content)
+        Ok(Message {
+            role: "assistant".to_string(),
+            content,
+            tool_calls,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        })
     }
 
+    #[tracing::instrument(name = "embedding", skip_all, fields(model = %self.embedding_model))]
     async fn embed(&self, text: &str) -> Result<Vec<f32>, KowalskiError> {
+        crate::metrics::record_embedding_request();
         let request = CreateEmbeddingRequestArgs::default()
-            .model(&self.embedding_model)
+            .model(self.resolve_model(&self.embedding_model))
             .input(text)
             .build()
             .map_err(|e| KowalskiError::Initialization(format!("OpenAI embedding error: {}", e)))?;
 
-        let response = self
-            .client
-            .embeddings()
-            .create(request)
-            .await
-            .map_err(|e| KowalskiError::Memory(format!("OpenAI embedding API error: {}", e)))?;
+        let response =
+            self.client
+                .embeddings()
+                .create(request)
+                .await
+                .map_err(|e| match api_error("OpenAI embedding API error", e) {
+                    KowalskiError::Server(message) => KowalskiError::Memory(message),
+                    e => e,
+                })?;
 
         let embedding = response
             .data
@@ -95,23 +223,108 @@ impl LLMProvider for OpenAIProvider {
         Ok(embedding)
     }
 
+    /// One embeddings request with all of `texts` as input.
+    #[tracing::instrument(name = "embedding", skip_all, fields(model = %self.embedding_model, texts = texts.len()))]
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, KowalskiError> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        crate::metrics::record_embedding_request();
+        let request = CreateEmbeddingRequestArgs::default()
+            .model(self.resolve_model(&self.embedding_model))
+            .input(texts.to_vec())
+            .build()
+            .map_err(|e| KowalskiError::Initialization(format!("OpenAI embedding error: {}", e)))?;
+
+        let response =
+            self.client
+                .embeddings()
+                .create(request)
+                .await
+                .map_err(|e| match api_error("OpenAI embedding API error", e) {
+                    KowalskiError::Server(message) => KowalskiError::Memory(message),
+                    e => e,
+                })?;
+
+        let mut data = response.data;
+        if data.len() != texts.len() {
+            return Err(KowalskiError::Memory(format!(
+                "OpenAI returned {} embeddings for {} texts",
+                data.len(),
+                texts.len()
+            )));
+        }
+        data.sort_by_key(|d| d.index);
+        Ok(data.into_iter().map(|d| d.embedding).collect())
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, KowalskiError> {
+        let mut request = self.http.get(format!("{}/models", self.api_base));
+        if !self.api_key.is_empty() {
+            request = request.bearer_auth(&self.api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| KowalskiError::Server(format!("OpenAI list models: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            let message = format!("OpenAI list models error: {}", error_text);
+            return Err(if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                KowalskiError::RateLimit(message)
+            } else if status.is_server_error() {
+                KowalskiError::ServerUnavailable(message)
+            } else {
+                KowalskiError::Server(message)
+            });
+        }
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| KowalskiError::Server(format!("Failed to parse JSON: {}", e)))?;
+        Ok(json["data"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["id"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
     fn supports_streaming(&self) -> bool {
         true
     }
 
     fn chat_stream(&self, model: &str, messages: Vec<Message>) -> TokenStream<'_> {
+        self.chat_stream_with_options(model, messages, &ChatOptions::default())
+    }
+
+    fn chat_stream_with_options(
+        &self,
+        model: &str,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> TokenStream<'_> {
         let openai_messages = match messages_to_openai(&messages) {
             Ok(m) => m,
             Err(e) => {
                 return Box::pin(futures::stream::once(async move { Err(e) }));
             }
         };
-        let request = match CreateChatCompletionRequestArgs::default()
-            .model(model.to_string())
+        let mut args = CreateChatCompletionRequestArgs::default();
+        args.model(self.resolve_model(model))
             .messages(openai_messages)
-            .stream(true)
-            .build()
-        {
+            .stream(true);
+        if let Some(temperature) = options.temperature {
+            args.temperature(temperature);
+        }
+        if let Some(max_tokens) = options.max_tokens {
+            args.max_completion_tokens(max_tokens);
+        }
+        let request = match args.build() {
             Ok(r) => r,
             Err(e) => {
                 return Box::pin(futures::stream::once(async move {
@@ -121,33 +334,56 @@ impl LLMProvider for OpenAIProvider {
                 }));
             }
         };
+        if log_enabled!(Level::Trace) {
+            trace!(
+                "POST {}/chat/completions (stream): {}",
+                self.api_base,
+                redacted_json(&request)
+            );
+        }
         let client = self.client.clone();
         Box::pin(async_stream::stream! {
             let mut stream = match client.chat().create_stream(request).await {
                 Ok(s) => s,
                 Err(e) => {
-                    yield Err(KowalskiError::Server(format!("OpenAI stream: {e}")));
+                    yield Err(api_error("OpenAI stream", e));
                     return;
                 }
             };
+            let mut streamed_text = false;
+            // Native tool calls arrive in pieces: index -> (name, arguments so far).
+            let mut calls: std::collections::BTreeMap<u32, (String, String)> = Default::default();
             while let Some(item) = stream.next().await {
                 match item {
                     Ok(resp) => {
                         for choice in resp.choices {
                             if let Some(ref c) = choice.delta.content
                                 && !c.is_empty() {
+                                    streamed_text = true;
                                     yield Ok(c.clone());
                                 }
+                            for chunk in choice.delta.tool_calls.unwrap_or_default() {
+                                let call = calls.entry(chunk.index).or_default();
+                                if let Some(function) = chunk.function {
+                                    call.0.push_str(function.name.as_deref().unwrap_or_default());
+                                    call.1.push_str(function.arguments.as_deref().unwrap_or_default());
+                                }
+                            }
                         }
                     }
                     Err(e) => {
-                        yield Err(KowalskiError::Server(format!(
-                            "OpenAI stream chunk: {e}"
-                        )));
+                        yield Err(api_error("OpenAI stream chunk", e));
                         return;
                     }
                 }
             }
+            if !streamed_text && !calls.is_empty() {
+                let calls: Vec<(String, serde_json::Value)> = calls
+                    .into_values()
+                    .map(|(name, arguments)| (name, parse_arguments(arguments)))
+                    .collect();
+                yield Ok(tool_calls_as_text(calls.iter().map(|(n, a)| (n.as_str(), a))));
+            }
         })
     }
 }
@@ -158,8 +394,10 @@ fn messages_to_openai(
     let mut openai_messages: Vec<ChatCompletionRequestMessage> = Vec::new();
 
     for msg in messages {
-        match msg.role.as_str() {
-            "system" => {
+        // Tool results without a call id and unknown roles go in as user messages tagged with
+        // the role.
+        match msg.role_typed() {
+            Ok(MessageRole::System) => {
                 openai_messages.push(ChatCompletionRequestMessage::System(
                     ChatCompletionRequestSystemMessageArgs::default()
                         .content(msg.content.clone())
@@ -169,7 +407,7 @@ fn messages_to_openai(
                         })?,
                 ));
             }
-            "user" => {
+            Ok(MessageRole::User) => {
                 openai_messages.push(ChatCompletionRequestMessage::User(
                     ChatCompletionRequestUserMessageArgs::default()
                         .content(msg.content.clone())
@@ -179,10 +417,40 @@ fn messages_to_openai(
                         })?,
                 ));
             }
-            "assistant" => {
+            Ok(MessageRole::Assistant) => {
+                let mut args = ChatCompletionRequestAssistantMessageArgs::default();
+                if !msg.content.is_empty() || msg.tool_calls.is_none() {
+                    args.content(msg.content.clone());
+                }
+                if let Some(calls) = &msg.tool_calls {
+                    args.tool_calls(
+                        calls
+                            .iter()
+                            .map(|call| {
+                                ChatCompletionMessageToolCalls::Function(
+                                    ChatCompletionMessageToolCall {
+                                        id: call.id.clone(),
+                                        function: FunctionCall {
+                                            name: call.function.name.clone(),
+                                            arguments: call.function.arguments.to_string(),
+                                        },
+                                    },
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    );
+                }
                 openai_messages.push(ChatCompletionRequestMessage::Assistant(
-                    ChatCompletionRequestAssistantMessageArgs::default()
+                    args.build().map_err(|e| {
+                        KowalskiError::Initialization(format!("OpenAI message error: {}", e))
+                    })?,
+                ));
+            }
+            Ok(MessageRole::Tool) if msg.tool_call_id.is_some() => {
+                openai_messages.push(ChatCompletionRequestMessage::Tool(
+                    ChatCompletionRequestToolMessageArgs::default()
                         .content(msg.content.clone())
+                        .tool_call_id(msg.tool_call_id.clone().unwrap_or_default())
                         .build()
                         .map_err(|e| {
                             KowalskiError::Initialization(format!("OpenAI message error: {}", e))
@@ -203,3 +471,70 @@ fn messages_to_openai(
     }
     Ok(openai_messages)
 }
+
+/// Native tool calls as [`crate::conversation::ToolCall`]s; arguments that are not JSON are
+/// kept as a string.
+fn tool_calls_from_openai(
+    calls: &[ChatCompletionMessageToolCalls],
+) -> Vec<crate::conversation::ToolCall> {
+    calls
+        .iter()
+        .filter_map(|call| match call {
+            ChatCompletionMessageToolCalls::Function(call) => Some(crate::conversation::ToolCall {
+                id: call.id.clone(),
+                function: crate::conversation::FunctionCall {
+                    name: call.function.name.clone(),
+                    arguments: parse_arguments(call.function.arguments.clone()),
+                },
+            }),
+            ChatCompletionMessageToolCalls::Custom(_) => None,
+        })
+        .collect()
+}
+
+fn parse_arguments(arguments: String) -> serde_json::Value {
+    serde_json::from_str(&arguments).unwrap_or(serde_json::Value::String(arguments))
+}
+
+/// Tool calls as `{"name": …, "parameters": …}` lines, the text form the tool loop parses.
+fn tool_calls_as_text<'a>(calls: impl Iterator<Item = (&'a str, &'a serde_json::Value)>) -> String {
+    calls
+        .map(|(name, parameters)| {
+            serde_json::json!({"name": name, "parameters": parameters}).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::Conversation;
+    use serde_json::json;
+
+    #[test]
+    fn tool_exchanges_become_tool_calls_and_tool_messages() {
+        let mut conversation = Conversation::new("gpt-4o-mini");
+        conversation.add_message_typed(MessageRole::User, "2+2?");
+        conversation.add_tool_exchange(
+            r#"{"name": "calculator", "parameters": {"expression": "2+2"}}"#,
+            "calculator",
+            &json!({"expression": "2+2"}),
+            "4",
+        );
+
+        let messages = messages_to_openai(&conversation.messages).unwrap();
+        let json = serde_json::to_value(&messages).unwrap();
+        assert_eq!(json[1]["role"], "assistant");
+        assert_eq!(json[1]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(json[1]["tool_calls"][0]["type"], "function");
+        assert_eq!(
+            json[1]["tool_calls"][0]["function"],
+            json!({"name": "calculator", "arguments": "{\"expression\":\"2+2\"}"})
+        );
+        assert_eq!(
+            json[2],
+            json!({"role": "tool", "content": "4", "tool_call_id": "call_1"})
+        );
+    }
+}
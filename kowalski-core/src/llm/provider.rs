@@ -1,3 +1,4 @@
+use crate::config::OllamaOptions;
 use crate::conversation::Message;
 use crate::error::KowalskiError;
 use async_trait::async_trait;
@@ -7,18 +8,131 @@ use std::pin::Pin;
 /// Incremental assistant text from [`LLMProvider::chat_stream`].
 pub type TokenStream<'a> = Pin<Box<dyn Stream<Item = Result<String, KowalskiError>> + Send + 'a>>;
 
+/// Per-call generation settings; `None` keeps the provider default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatOptions {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Serve the reply from the [`LlmCache`](super::cache::LlmCache) when possible; `None`
+    /// follows `[llm.cache] enabled`. Only temperature-0 calls are ever cached.
+    pub cache: Option<bool>,
+    /// Skip the cache lookup and call the backend; the fresh reply replaces the cached one.
+    pub bypass_cache: bool,
+    /// Ollama `options` / `keep_alive` for this call instead of the provider's
+    /// (`[chat.options]`); other backends ignore them.
+    pub ollama: Option<OllamaOptions>,
+}
+
 #[async_trait]
 pub trait LLMProvider: Send + Sync {
     /// Send a chat request to the LLM
     async fn chat(&self, model: &str, messages: &[Message]) -> Result<String, KowalskiError>;
 
+    /// Like [`Self::chat`] with explicit sampling settings (e.g. a short, cool summarization call).
+    /// Providers that cannot honour them fall back to [`Self::chat`].
+    async fn chat_with_options(
+        &self,
+        model: &str,
+        messages: &[Message],
+        _options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.chat(model, messages).await
+    }
+
+    /// Like [`Self::chat_with_options`], returning the whole assistant message: backends with
+    /// native tool calling fill [`Message::tool_calls`]. The default wraps the text reply.
+    async fn chat_message(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<Message, KowalskiError> {
+        let content = self.chat_with_options(model, messages, options).await?;
+        Ok(Message {
+            role: "assistant".to_string(),
+            content,
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        })
+    }
+
+    /// Like [`Self::chat`], but asks the backend to constrain the reply to a single JSON object
+    /// (used for tool-call decisions), with explicit sampling settings. Providers without a JSON
+    /// mode fall back to [`Self::chat_with_options`].
+    async fn chat_json(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.chat_with_options(model, messages, options).await
+    }
+
+    /// Like [`Self::chat_json`], but asks the backend to constrain the reply to `schema` (Ollama
+    /// structured outputs) with explicit sampling settings. Providers without schema support fall
+    /// back to [`Self::chat_json`]; callers still validate the reply.
+    async fn chat_structured(
+        &self,
+        model: &str,
+        messages: &[Message],
+        _schema: &serde_json::Value,
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.chat_json(model, messages, options).await
+    }
+
     /// Generate embeddings for the given text
     async fn embed(&self, text: &str) -> Result<Vec<f32>, KowalskiError>;
 
+    /// Embeddings of several texts, in order. Backends with a batch endpoint embed them in one
+    /// request; the default calls [`Self::embed`] for each.
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, KowalskiError> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            embeddings.push(self.embed(text).await?);
+        }
+        Ok(embeddings)
+    }
+
+    /// Models the server can serve (Ollama `/api/tags`, OpenAI `/models`).
+    async fn list_models(&self) -> Result<Vec<String>, KowalskiError> {
+        Err(KowalskiError::Server(
+            "Listing models is not supported by this provider".to_string(),
+        ))
+    }
+
     fn supports_streaming(&self) -> bool;
 
     /// Token deltas (concatenate for the full reply). Empty strings may be omitted by callers.
     fn chat_stream(&self, model: &str, messages: Vec<Message>) -> TokenStream<'_>;
+
+    /// Like [`Self::chat_stream`] with explicit sampling settings. Providers that cannot honour
+    /// them fall back to [`Self::chat_stream`].
+    fn chat_stream_with_options(
+        &self,
+        model: &str,
+        messages: Vec<Message>,
+        _options: &ChatOptions,
+    ) -> TokenStream<'_> {
+        self.chat_stream(model, messages)
+    }
+}
+
+/// Records the token counts a backend reported on the current span (the agent's `chat_turn`
+/// span declares `prompt_tokens` and `completion_tokens`; other spans ignore them) and in the
+/// token metrics.
+pub(crate) fn record_token_usage(prompt_tokens: Option<u64>, completion_tokens: Option<u64>) {
+    let span = tracing::Span::current();
+    if let Some(n) = prompt_tokens {
+        span.record("prompt_tokens", n);
+        crate::metrics::record_tokens("prompt", n);
+    }
+    if let Some(n) = completion_tokens {
+        span.record("completion_tokens", n);
+        crate::metrics::record_tokens("completion", n);
+    }
 }
 
 /// Single-chunk stream when a provider does not implement native token streaming.
@@ -1,55 +1,157 @@
-use env_logger::Builder;
+//! Logging and tracing setup: a `tracing-subscriber` fmt layer on stderr, which also prints `log`
+//! records, and with the `otel` feature an OpenTelemetry layer exporting spans over OTLP.
+
+use crate::config::ObservabilityConfig;
+use crate::error::KowalskiError;
 use log::LevelFilter;
-use std::io::Write;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::time::ChronoLocal;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// `2024-05-01 14:03:00  INFO message` lines on stderr, for events passing `filter`.
+fn fmt_layer<S>(filter: EnvFilter) -> impl Layer<S> + Send + Sync
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_timer(ChronoLocal::new(TIME_FORMAT.to_string()))
+        .with_target(false)
+        .with_filter(filter)
+}
+
+fn init_filter(filter: EnvFilter) {
+    tracing_subscriber::registry()
+        .with(fmt_layer(filter))
+        .init();
+}
 
 /// Initialize the logging system with default settings
 pub fn init() {
-    Builder::new()
-        .format(|buf, record| {
-            writeln!(
-                buf,
-                "{} [{}] - {}",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                record.level(),
-                record.args()
-            )
-        })
-        .filter(None, LevelFilter::Info)
-        .init();
+    init_with_level(LevelFilter::Info);
 }
 
 /// Initialize the logging system with custom settings
 pub fn init_with_level(level: LevelFilter) {
-    Builder::new()
-        .format(|buf, record| {
-            writeln!(
-                buf,
-                "{} [{}] - {}",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                record.level(),
-                record.args()
-            )
-        })
-        .filter(None, level)
-        .init();
+    init_filter(EnvFilter::new(level.as_str().to_lowercase()));
 }
 
 /// Initialize the logging system with custom settings and module filters
 pub fn init_with_filters(filters: &[(&str, LevelFilter)]) {
-    let mut builder = Builder::new();
-    builder.format(|buf, record| {
-        writeln!(
-            buf,
-            "{} [{}] - {}",
-            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-            record.level(),
-            record.args()
-        )
-    });
-
-    for (module, level) in filters {
-        builder.filter(Some(module), *level);
+    let directives: Vec<String> = filters
+        .iter()
+        .map(|(module, level)| format!("{module}={}", level.as_str().to_lowercase()))
+        .collect();
+    init_filter(EnvFilter::new(directives.join(",")));
+}
+
+/// Initialize logging (`RUST_LOG`, else `default_filter`, e.g. `"info"`) and, when
+/// [`ObservabilityConfig::otlp_endpoint`] is set, span export to that collector. Keep the guard
+/// alive until exit: dropping it flushes and stops the exporter.
+///
+/// Setting an endpoint without the `otel` feature is a configuration error.
+pub fn init_with_config(
+    config: &ObservabilityConfig,
+    default_filter: &str,
+) -> Result<TelemetryGuard, KowalskiError> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let already_set =
+        |e| KowalskiError::Initialization(format!("a global subscriber is already set: {e}"));
+    let registry = tracing_subscriber::registry().with(fmt_layer(filter));
+    match config.otlp_endpoint.as_deref() {
+        None => {
+            registry.try_init().map_err(already_set)?;
+            Ok(TelemetryGuard::default())
+        }
+        #[cfg(feature = "otel")]
+        Some(endpoint) => {
+            let provider = otel::provider(endpoint, config)?;
+            registry
+                .with(otel::layer(&provider, config))
+                .try_init()
+                .map_err(already_set)?;
+            Ok(TelemetryGuard {
+                provider: Some(provider),
+            })
+        }
+        #[cfg(not(feature = "otel"))]
+        Some(_) => Err(crate::config::otel_feature_required_error()),
+    }
+}
+
+/// Returned by [`init_with_config`]; flushes exported spans when dropped.
+#[must_use = "dropping the guard stops span export"]
+#[derive(Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to flush OpenTelemetry spans: {e}");
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use super::*;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+
+    /// Batch exporter to `<endpoint>/v1/traces`, sampling `sample_ratio` of new traces.
+    pub(super) fn provider(
+        endpoint: &str,
+        config: &ObservabilityConfig,
+    ) -> Result<SdkTracerProvider, KowalskiError> {
+        let endpoint = endpoint.trim_end_matches('/');
+        let url = if endpoint.ends_with("/v1/traces") {
+            endpoint.to_string()
+        } else {
+            format!("{endpoint}/v1/traces")
+        };
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(url)
+            .build()
+            .map_err(|e| KowalskiError::Initialization(format!("OTLP exporter: {e}")))?;
+        let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio.clamp(0.0, 1.0),
+        )));
+        Ok(SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(sampler)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(config.service_name.clone())
+                    .build(),
+            )
+            .build())
     }
 
-    builder.init();
+    /// Exports spans at `INFO` and above, whatever the log filter.
+    pub(super) fn layer<S>(
+        provider: &SdkTracerProvider,
+        config: &ObservabilityConfig,
+    ) -> impl Layer<S> + Send + Sync
+    where
+        S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+    {
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(config.service_name.clone()))
+            .with_filter(tracing_subscriber::filter::LevelFilter::INFO)
+    }
 }
@@ -92,6 +92,7 @@ impl McpClient {
             transport: McpTransport::Http,
             headers: HashMap::new(),
             command: Vec::new(),
+            env: HashMap::new(),
         })
         .await
     }
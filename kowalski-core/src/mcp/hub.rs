@@ -129,13 +129,25 @@ impl McpHub {
         self.tools.values()
     }
 
+    /// `mcp:<server>/<tool>` for a bound tool: where its results come from.
+    pub fn tool_source(&self, tool_name: &str) -> Option<String> {
+        self.tools
+            .get(tool_name)
+            .map(|binding| format!("mcp:{}/{}", binding.server_name, binding.remote_name))
+    }
+
     pub async fn call_tool(
         &self,
         tool_name: &str,
         args: &serde_json::Value,
     ) -> Result<serde_json::Value, KowalskiError> {
         let binding = self.tools.get(tool_name).ok_or_else(|| {
-            KowalskiError::ToolExecution(format!("Unknown MCP tool {}", tool_name))
+            let mut available: Vec<String> = self.tools.keys().cloned().collect();
+            available.sort();
+            KowalskiError::ToolNotFound {
+                name: tool_name.to_string(),
+                available,
+            }
         })?;
 
         let response = binding.client.call_tool(&binding.remote_name, args).await?;
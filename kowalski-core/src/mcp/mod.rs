@@ -11,6 +11,8 @@
 //!   `server_name::tool_name`, and routes `call_tool` to the owning client.
 //! - **`McpToolProxy`**: Adapts MCP tools to the core [`crate::tools::Tool`] trait so the existing
 //!   `ToolManager` and ReAct loop can execute them.
+//! - **`serve_mcp`** ([`server`]): the reverse direction — an MCP **server** on stdio exposing a
+//!   [`crate::tools::manager::ToolManager`] (`tools/list`, `tools/call`); CLI: `kowalski-cli mcp-serve`.
 //! - **System prompt**: [`crate::template::TemplateAgent`] appends `ToolManager::generate_json_schema()`
 //!   to the system message when tools are present (see `TemplateAgentConfig::tool_prompt_appendix`).
 //!
@@ -24,22 +26,27 @@
 //! ## Follow-ups
 //!
 //! - **Optional GET listener** for server-initiated messages (open SSE without a preceding POST) — not implemented.
-//! - **Stdio MCP**: [`crate::mcp::stdio::McpStdioClient`] — newline JSON-RPC over a subprocess (`McpServerConfig::command`).
+//! - **Stdio MCP**: [`crate::mcp::stdio::McpStdioClient`] — newline JSON-RPC over a subprocess (`McpServerConfig::command`,
+//!   extra `McpServerConfig::env`); the subprocess is relaunched on the next request after it exits.
 //!
 //! **Prompt refresh:** [`crate::template::TemplateAgent::register_tool`] and [`crate::template::TemplateAgent::refresh_tool_prompt_appendix`] update `tool_prompt_appendix` when the tool set changes.
 //!
 //! ## Tests
 //!
 //! - `kowalski-core/tests/mcp_client_http_mock.rs` — local Axum mock for JSON and SSE responses.
+//! - `kowalski-core/tests/mcp_server_stdio.rs` — scripted JSON-RPC against [`serve_mcp_io`].
+//! - `kowalski-core/tests/mcp_client_stdio_mock.rs` — `tests/fixtures/mock_mcp_stdio.sh` over stdio (env, relaunch, agent registration).
 
 pub mod client;
 pub mod hub;
+pub mod server;
 pub mod stdio;
 pub mod tool;
 pub mod types;
 
 pub use client::McpClient;
 pub use hub::{McpConnection, McpHub, McpToolBinding};
+pub use server::{serve_mcp, serve_mcp_io};
 pub use stdio::McpStdioClient;
 pub use tool::McpToolProxy;
 pub use types::{CallToolResponse, McpToolDescription};
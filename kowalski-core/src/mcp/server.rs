@@ -0,0 +1,174 @@
+//! MCP **server** over stdio: exposes every tool in a [`ToolManager`] to external MCP clients
+//! (Claude Desktop, IDEs, other agents) as newline-delimited JSON-RPC 2.0.
+//!
+//! - `tools/list` advertises each tool with an `inputSchema` built from its [`crate::tools::ToolParameter`]s.
+//! - `tools/call` validates arguments (`-32602 Invalid params` on failure) and runs the tool; a tool
+//!   error is returned as a normal result with `isError: true`, as the MCP spec asks.
+
+use crate::error::KowalskiError;
+use crate::tools::ToolInput;
+use crate::tools::manager::ToolManager;
+use log::{debug, info};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn rpc_result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn rpc_error(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message.into() } })
+}
+
+/// Serves `registry` on this process's stdin/stdout until stdin closes.
+pub async fn serve_mcp(registry: ToolManager) -> Result<(), KowalskiError> {
+    info!("Serving MCP tools over stdio");
+    serve_mcp_io(
+        registry,
+        BufReader::new(tokio::io::stdin()),
+        tokio::io::stdout(),
+    )
+    .await
+}
+
+/// Serves `registry` on any line-oriented reader/writer pair (one JSON-RPC message per line).
+pub async fn serve_mcp_io<R, W>(
+    registry: ToolManager,
+    reader: R,
+    mut writer: W,
+) -> Result<(), KowalskiError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&registry, &message).await,
+            Err(e) => Some(rpc_error(
+                Value::Null,
+                PARSE_ERROR,
+                format!("parse error: {e}"),
+            )),
+        };
+        if let Some(reply) = reply {
+            let mut out = serde_json::to_string(&reply)?;
+            out.push('\n');
+            writer.write_all(out.as_bytes()).await?;
+            writer.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Handles one JSON-RPC message; returns `None` for notifications (no `id`).
+pub async fn handle_message(registry: &ToolManager, message: &Value) -> Option<Value> {
+    let method = message.get("method").and_then(Value::as_str);
+    let Some(id) = message.get("id").cloned() else {
+        debug!("MCP server: notification {:?}", method);
+        return None;
+    };
+    let Some(method) = method else {
+        return Some(rpc_error(id, INVALID_REQUEST, "missing method"));
+    };
+    let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+    debug!("MCP server <- {}", method);
+
+    Some(match method {
+        "initialize" => rpc_result(
+            id,
+            json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "serverInfo": { "name": "kowalski", "version": env!("CARGO_PKG_VERSION") },
+                "capabilities": { "tools": { "listChanged": false } },
+            }),
+        ),
+        "ping" => rpc_result(id, json!({})),
+        "tools/list" => rpc_result(id, json!({ "tools": list_tools(registry).await })),
+        "tools/call" => call_tool(registry, id, &params).await,
+        other => rpc_error(id, METHOD_NOT_FOUND, format!("method not found: {other}")),
+    })
+}
+
+async fn list_tools(registry: &ToolManager) -> Vec<Value> {
+    let mut tools: Vec<Value> = registry
+        .generate_json_schema()
+        .await
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|f| {
+            json!({
+                "name": f["function"]["name"],
+                "description": f["function"]["description"],
+                "inputSchema": f["function"]["parameters"],
+            })
+        })
+        .collect();
+    tools.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    tools
+}
+
+async fn call_tool(registry: &ToolManager, id: Value, params: &Value) -> Value {
+    let Some(name) = params.get("name").and_then(Value::as_str) else {
+        return rpc_error(id, INVALID_PARAMS, "tools/call requires `name`");
+    };
+    let Some(tool) = registry.get(name) else {
+        return rpc_error(id, INVALID_PARAMS, format!("unknown tool: {name}"));
+    };
+    let arguments = match params.get("arguments") {
+        None | Some(Value::Null) => json!({}),
+        Some(args @ Value::Object(_)) => args.clone(),
+        Some(_) => return rpc_error(id, INVALID_PARAMS, "`arguments` must be an object"),
+    };
+    let task = arguments
+        .get("task")
+        .and_then(Value::as_str)
+        .unwrap_or("default")
+        .to_string();
+    let content = arguments
+        .get("content")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let input = ToolInput::new(task, content, arguments);
+
+    let mut tool = tool.lock().await;
+    if let Err(e) = tool.validate_input(&input) {
+        return rpc_error(id, INVALID_PARAMS, e.to_string());
+    }
+    match tool.execute(input).await {
+        Ok(output) => {
+            let text = match &output.result {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            let mut result = json!({
+                "content": [{ "type": "text", "text": text }],
+                "isError": false,
+            });
+            if output.result.is_object() {
+                result["structuredContent"] = output.result;
+            }
+            rpc_result(id, result)
+        }
+        Err(e) => rpc_result(
+            id,
+            json!({
+                "content": [{ "type": "text", "text": e.to_string() }],
+                "isError": true,
+            }),
+        ),
+    }
+}
@@ -1,9 +1,12 @@
 //! MCP over **stdio**: newline-delimited JSON-RPC 2.0 (one request / one response per line).
+//!
+//! The subprocess gets [`McpServerConfig::env`] on top of the inherited environment and is
+//! relaunched on the next request after it exits.
 
 use crate::config::{McpServerConfig, McpTransport};
 use crate::error::KowalskiError;
 use crate::mcp::types::{CallToolResponse, InitializeResult, ToolListResult};
-use log::debug;
+use log::{debug, warn};
 use serde::de::DeserializeOwned;
 use serde_json::{Value, json};
 use std::process::Stdio;
@@ -15,6 +18,10 @@ use tokio::sync::Mutex;
 const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
 
 /// JSON-RPC line client for a local MCP subprocess.
+///
+/// The subprocess is supervised: if it exits or its pipes break, the failing request returns an
+/// error and the next request relaunches it (and repeats the `initialize` handshake). Requests
+/// are serialized, so replies cannot interleave.
 #[derive(Clone)]
 pub struct McpStdioClient {
     inner: Arc<StdioInner>,
@@ -22,30 +29,27 @@ pub struct McpStdioClient {
 
 struct StdioInner {
     name: String,
-    #[allow(dead_code)]
-    child: tokio::process::Child,
-    stdin: Mutex<tokio::process::ChildStdin>,
-    stdout: Mutex<BufReader<tokio::process::ChildStdout>>,
+    server: McpServerConfig,
+    process: Mutex<Option<StdioProcess>>,
     id: AtomicU64,
+    restarts: AtomicU64,
 }
 
-impl McpStdioClient {
-    pub async fn connect(server: &McpServerConfig) -> Result<Self, KowalskiError> {
-        if !matches!(server.transport, McpTransport::Stdio) {
-            return Err(KowalskiError::Configuration(
-                "McpStdioClient::connect: transport must be stdio".into(),
-            ));
-        }
-        if server.command.is_empty() {
-            return Err(KowalskiError::Configuration(
-                "stdio MCP requires `command` = [program, ...args]".into(),
-            ));
-        }
+struct StdioProcess {
+    child: tokio::process::Child,
+    stdin: tokio::process::ChildStdin,
+    stdout: BufReader<tokio::process::ChildStdout>,
+}
+
+impl StdioProcess {
+    fn spawn(server: &McpServerConfig) -> Result<Self, KowalskiError> {
         let mut cmd = tokio::process::Command::new(&server.command[0]);
         cmd.args(&server.command[1..])
+            .envs(&server.env)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::null());
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
         let mut child = cmd.spawn().map_err(|e| {
             KowalskiError::Configuration(format!("stdio MCP spawn {}: {e}", server.command[0]))
         })?;
@@ -56,84 +60,133 @@ impl McpStdioClient {
         let stdout = child.stdout.take().ok_or_else(|| {
             KowalskiError::Configuration("stdio MCP: stdout not available".into())
         })?;
-
-        let client = Self {
-            inner: Arc::new(StdioInner {
-                name: server.name.clone(),
-                child,
-                stdin: Mutex::new(stdin),
-                stdout: Mutex::new(BufReader::new(stdout)),
-                id: AtomicU64::new(1),
-            }),
-        };
-
-        let _info: InitializeResult = client
-            .send_request(
-                "initialize",
-                Some(json!({
-                    "clientInfo": { "name": "Kowalski", "version": env!("CARGO_PKG_VERSION") },
-                    "protocolVersion": MCP_PROTOCOL_VERSION,
-                    "capabilities": { "tools": true },
-                })),
-            )
-            .await?;
-        client
-            .send_notification("notifications/initialized", Some(json!({})))
-            .await?;
-
-        Ok(client)
+        Ok(Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
     }
 
-    async fn send_notification(
-        &self,
-        method: &str,
-        params: Option<Value>,
-    ) -> Result<(), KowalskiError> {
-        let payload = json!({
-            "jsonrpc": "2.0",
-            "method": method,
-            "params": params.unwrap_or_else(|| json!({})),
-        });
-        self.write_line(&payload).await
+    fn has_exited(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
     }
 
-    async fn write_line(&self, v: &Value) -> Result<(), KowalskiError> {
+    async fn write_line(&mut self, v: &Value) -> Result<(), KowalskiError> {
         let mut line = serde_json::to_string(v).map_err(KowalskiError::Json)?;
         line.push('\n');
-        let mut g = self.inner.stdin.lock().await;
-        g.write_all(line.as_bytes())
+        self.stdin
+            .write_all(line.as_bytes())
             .await
             .map_err(|e| KowalskiError::Network(format!("stdio MCP write: {e}")))?;
-        g.flush()
+        self.stdin
+            .flush()
             .await
             .map_err(|e| KowalskiError::Network(format!("stdio MCP flush: {e}")))?;
         Ok(())
     }
 
-    async fn send_request<T: DeserializeOwned>(
-        &self,
+    async fn notify(&mut self, method: &str, params: Option<Value>) -> Result<(), KowalskiError> {
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params.unwrap_or_else(|| json!({})),
+        });
+        self.write_line(&payload).await
+    }
+
+    /// Sends one request and reads lines until the reply with the same `id`; server
+    /// notifications and non-JSON log lines in between are skipped.
+    async fn request(
+        &mut self,
+        id: u64,
         method: &str,
         params: Option<Value>,
-    ) -> Result<T, KowalskiError> {
-        let id = self.inner.id.fetch_add(1, Ordering::SeqCst);
+    ) -> Result<Value, KowalskiError> {
         let payload = json!({
             "jsonrpc": "2.0",
             "id": id,
             "method": method,
             "params": params.unwrap_or_else(|| json!({})),
         });
-        debug!("MCP stdio {} -> {}", self.inner.name, method);
         self.write_line(&payload).await?;
 
-        let mut line = String::new();
-        self.inner
-            .stdout
-            .lock()
-            .await
-            .read_line(&mut line)
-            .await
-            .map_err(|e| KowalskiError::Network(format!("stdio MCP read: {e}")))?;
-        let body: Value = serde_json::from_str(line.trim()).map_err(KowalskiError::Json)?;
+        loop {
+            let mut line = String::new();
+            let n = self
+                .stdout
+                .read_line(&mut line)
+                .await
+                .map_err(|e| KowalskiError::Network(format!("stdio MCP read: {e}")))?;
+            if n == 0 {
+                return Err(KowalskiError::Network(
+                    "stdio MCP: server closed stdout".into(),
+                ));
+            }
+            let Ok(body) = serde_json::from_str::<Value>(line.trim()) else {
+                debug!("stdio MCP: skipping non-JSON line");
+                continue;
+            };
+            if body.get("id").and_then(Value::as_u64) == Some(id) {
+                return Ok(body);
+            }
+        }
+    }
+}
+
+impl McpStdioClient {
+    pub async fn connect(server: &McpServerConfig) -> Result<Self, KowalskiError> {
+        if !matches!(server.transport, McpTransport::Stdio) {
+            return Err(KowalskiError::Configuration(
+                "McpStdioClient::connect: transport must be stdio".into(),
+            ));
+        }
+        if server.command.is_empty() {
+            return Err(KowalskiError::Configuration(
+                "stdio MCP requires `command` = [program, ...args]".into(),
+            ));
+        }
+        let client = Self {
+            inner: Arc::new(StdioInner {
+                name: server.name.clone(),
+                server: server.clone(),
+                process: Mutex::new(None),
+                id: AtomicU64::new(1),
+                restarts: AtomicU64::new(0),
+            }),
+        };
+        let process = client.launch().await?;
+        *client.inner.process.lock().await = Some(process);
+        Ok(client)
+    }
+
+    /// How many times the subprocess has been relaunched after exiting.
+    pub fn restart_count(&self) -> u64 {
+        self.inner.restarts.load(Ordering::SeqCst)
+    }
+
+    /// Spawns the subprocess and performs the MCP `initialize` handshake.
+    async fn launch(&self) -> Result<StdioProcess, KowalskiError> {
+        let mut process = StdioProcess::spawn(&self.inner.server)?;
+        let id = self.inner.id.fetch_add(1, Ordering::SeqCst);
+        let body = process
+            .request(
+                id,
+                "initialize",
+                Some(json!({
+                    "clientInfo": { "name": "Kowalski", "version": env!("CARGO_PKG_VERSION") },
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": { "tools": true },
+                })),
+            )
+            .await?;
+        let _info: InitializeResult = self.parse_result(body)?;
+        process
+            .notify("notifications/initialized", Some(json!({})))
+            .await?;
+        Ok(process)
+    }
+
+    fn parse_result<T: DeserializeOwned>(&self, body: Value) -> Result<T, KowalskiError> {
         if let Some(err) = body.get("error") {
             return Err(KowalskiError::ToolExecution(format!(
                 "MCP stdio {}: {}",
@@ -146,6 +199,35 @@ impl McpStdioClient {
         serde_json::from_value(result).map_err(KowalskiError::Json)
     }
 
+    async fn send_request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<T, KowalskiError> {
+        let mut guard = self.inner.process.lock().await;
+        if guard.as_mut().is_none_or(StdioProcess::has_exited) {
+            warn!(
+                "stdio MCP '{}' is not running; relaunching",
+                self.inner.name
+            );
+            *guard = None;
+            *guard = Some(self.launch().await?);
+            self.inner.restarts.fetch_add(1, Ordering::SeqCst);
+        }
+        let process = guard.as_mut().expect("process launched above");
+
+        debug!("MCP stdio {} -> {}", self.inner.name, method);
+        let id = self.inner.id.fetch_add(1, Ordering::SeqCst);
+        match process.request(id, method, params).await {
+            Ok(body) => self.parse_result(body),
+            Err(e) => {
+                // Broken pipe / EOF: drop the process so the next request relaunches it.
+                *guard = None;
+                Err(e)
+            }
+        }
+    }
+
     pub async fn list_tools(
         &self,
     ) -> Result<Vec<crate::mcp::types::McpToolDescription>, KowalskiError> {
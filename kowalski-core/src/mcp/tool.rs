@@ -76,7 +76,11 @@ impl Tool for McpToolProxy {
     async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
         self.validate_input(&input)?;
         let value = self.hub.call_tool(&self.name, &input.parameters).await?;
-        Ok(ToolOutput::new(value, None))
+        let output = ToolOutput::new(value, None);
+        Ok(match self.hub.tool_source(&self.name) {
+            Some(source) => output.with_source(source),
+            None => output,
+        })
     }
 
     fn name(&self) -> &str {
@@ -1,14 +1,23 @@
 #[cfg(feature = "postgres")]
 use crate::memory::semantic_pg::PostgresSemanticStore;
 use crate::{
-    config::{MemoryConfig, memory_uses_postgres},
+    config::{MemoryConfig, SummarizationConfig, memory_uses_postgres},
     error::KowalskiError,
-    memory::{MemoryProvider, MemoryUnit, episodic::EpisodicBuffer, semantic::SemanticStore},
+    llm::ChatOptions,
+    memory::{
+        MemoryProvider, MemoryUnit,
+        episodic::EpisodicBuffer,
+        profile::{FactCandidate, ProfileStore},
+        semantic::SemanticStore,
+    },
+    prompts::{PromptKind, PromptRegistry},
+    utils::json::strip_markdown_code_fences,
 };
-use log::{debug, info};
+use log::{debug, info, warn};
 #[cfg(feature = "postgres")]
 use sqlx::postgres::PgPool;
 use std::error::Error;
+use std::sync::Arc;
 
 /// Trait for memory consolidation strategies ("Weavers")
 #[async_trait::async_trait]
@@ -21,6 +30,9 @@ pub struct Consolidator {
     semantic_memory: Box<dyn MemoryProvider + Send + Sync>,
     llm_provider: std::sync::Arc<dyn crate::llm::LLMProvider>,
     model: String,
+    options: ChatOptions,
+    prompts: PromptRegistry,
+    profile: Option<Arc<ProfileStore>>,
 }
 
 impl Consolidator {
@@ -59,33 +71,127 @@ impl Consolidator {
             semantic_memory,
             llm_provider,
             model: model.to_string(),
+            options: ChatOptions::default(),
+            prompts: PromptRegistry::default(),
+            profile: None,
         })
     }
 
+    /// Applies `[summarization]`: its model (if set) replaces the one passed to [`Self::new`], and
+    /// its temperature / token limit are sent with every summary and graph call.
+    pub fn with_summarization(mut self, summarization: &SummarizationConfig) -> Self {
+        if let Some(model) = &summarization.model {
+            self.model = model.clone();
+        }
+        self.options = ChatOptions {
+            temperature: Some(summarization.temperature),
+            max_tokens: Some(summarization.max_tokens),
+            ..ChatOptions::default()
+        };
+        self
+    }
+
+    /// Uses `prompts` for the summary and graph requests instead of the built-ins.
+    pub fn with_prompts(mut self, prompts: PromptRegistry) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// Also asks for profile facts in each memory and [proposes](ProfileStore::propose) them to
+    /// `profile`; they are saved only once approved.
+    pub fn with_fact_extraction(mut self, profile: Arc<ProfileStore>) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
     async fn summarize_with_llm(&self, content: &str) -> Result<String, KowalskiError> {
-        let prompt = format!("Summarize the following text:\n\n{}", content);
+        let prompt = self
+            .prompts
+            .render(PromptKind::ConsolidationSummary, &[("text", content)]);
         let messages = vec![crate::conversation::Message {
             role: "user".to_string(),
             content: prompt,
             tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
         }];
-        self.llm_provider.chat(&self.model, &messages).await
+        self.llm_provider
+            .chat_with_options(&self.model, &messages, &self.options)
+            .await
     }
 
     async fn create_graph_with_llm(&self, content: &str) -> Result<String, KowalskiError> {
-        let prompt = format!(
-            "Create a graph representation of the following text in the format {{ \"subject\": \"...\", \"predicate\": \"...\", \"object\": \"...\" }}:\n\n{}",
-            content
-        );
+        let prompt = self
+            .prompts
+            .render(PromptKind::ConsolidationGraph, &[("text", content)]);
+        let messages = vec![crate::conversation::Message {
+            role: "user".to_string(),
+            content: prompt,
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        }];
+        self.llm_provider
+            .chat_with_options(&self.model, &messages, &self.options)
+            .await
+    }
+
+    /// Candidate facts in `memory`; a reply that is not a JSON object yields none.
+    async fn extract_facts_with_llm(
+        &self,
+        memory: &MemoryUnit,
+    ) -> Result<Vec<FactCandidate>, KowalskiError> {
+        let prompt = self
+            .prompts
+            .render(PromptKind::ConsolidationFacts, &[("text", &memory.content)]);
         let messages = vec![crate::conversation::Message {
             role: "user".to_string(),
             content: prompt,
             tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
         }];
-        self.llm_provider.chat(&self.model, &messages).await
+        let reply = self
+            .llm_provider
+            .chat_with_options(&self.model, &messages, &self.options)
+            .await?;
+        Ok(parse_facts(&reply, &memory.id))
     }
 }
 
+/// `{"key": "value", ...}` from a model reply (code fences ignored); non-string values are
+/// written out as JSON.
+fn parse_facts(reply: &str, source: &str) -> Vec<FactCandidate> {
+    let text = strip_markdown_code_fences(reply);
+    let Some(start) = text.find('{') else {
+        return Vec::new();
+    };
+    let Ok(serde_json::Value::Object(facts)) =
+        serde_json::from_str::<serde_json::Value>(&text[start..])
+    else {
+        warn!(
+            "Ignoring profile facts that are not a JSON object: {}",
+            reply
+        );
+        return Vec::new();
+    };
+    facts
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| FactCandidate {
+            key,
+            value: value
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| value.to_string()),
+            source: source.to_string(),
+        })
+        .collect()
+}
+
 #[async_trait::async_trait]
 impl MemoryWeaver for Consolidator {
     async fn run(&mut self, delete_original: bool) -> Result<(), Box<dyn Error>> {
@@ -112,6 +218,7 @@ impl MemoryWeaver for Consolidator {
                 timestamp: memory.timestamp,
                 content: summary,
                 embedding: summary_embedding,
+                metadata: Default::default(),
             };
 
             let graph_memory = MemoryUnit {
@@ -119,12 +226,19 @@ impl MemoryWeaver for Consolidator {
                 timestamp: memory.timestamp,
                 content: graph_representation,
                 embedding: graph_embedding,
+                metadata: Default::default(),
             };
 
             // Add the new memories to the semantic store
             self.semantic_memory.add(summary_memory).await?;
             self.semantic_memory.add(graph_memory).await?;
 
+            if let Some(profile) = &self.profile {
+                for candidate in self.extract_facts_with_llm(&memory).await? {
+                    profile.propose(candidate).await?;
+                }
+            }
+
             info!("Successfully processed and stored memory: {}", memory.id);
 
             // Optionally, delete the original memory from the episodic store
@@ -138,3 +252,58 @@ impl MemoryWeaver for Consolidator {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    #[tokio::test]
+    async fn extracted_facts_wait_as_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let memory = MemoryConfig {
+            episodic_path: dir.path().to_string_lossy().to_string(),
+            ..MemoryConfig::default()
+        };
+        let llm = Arc::new(
+            MockBackend::script()
+                .responds_with_text("Ada uses helix.")
+                .then_text("{\"subject\": \"Ada\", \"predicate\": \"uses\", \"object\": \"helix\"}")
+                .then_text("```json\n{\"editor\": \"helix\", \"name\": \"Ada\"}\n```")
+                .with_embedding(vec![1.0, 0.0])
+                .build(),
+        );
+        let mut episodic = EpisodicBuffer::open(&memory, llm.clone()).await.unwrap();
+        episodic
+            .add(MemoryUnit {
+                id: "m1".to_string(),
+                timestamp: 1,
+                content: "I'm Ada and I edit everything in helix".to_string(),
+                embedding: None,
+                metadata: Default::default(),
+            })
+            .await
+            .unwrap();
+        drop(episodic);
+
+        let profile = Arc::new(ProfileStore::open(&memory, "ada").await.unwrap());
+        profile.remember("name", "Ada").await.unwrap();
+        let mut weaver = Consolidator::new(&memory, llm, "m")
+            .await
+            .unwrap()
+            .with_fact_extraction(profile.clone());
+        weaver.run(false).await.unwrap();
+
+        // The name was already known; only the editor needs confirming.
+        let candidates = profile.candidates().await.unwrap();
+        assert_eq!(
+            candidates,
+            vec![FactCandidate {
+                key: "editor".to_string(),
+                value: "helix".to_string(),
+                source: "m1".to_string(),
+            }]
+        );
+        assert_eq!(profile.render().as_deref(), Some("- name: Ada"));
+    }
+}
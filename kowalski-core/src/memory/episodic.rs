@@ -1,19 +1,25 @@
 // Tier 2: Episodic Buffer (The Journal)
 // Default: embedded SQLite (`episodic_path`). Optional: PostgreSQL `episodic_kv` when `memory.database_url` is `postgres://…` and the `postgres` feature is enabled.
 
+use crate::progress::{NoProgress, Progress, ProgressReporter};
 use crate::{
     config::{MemoryConfig, memory_uses_postgres},
     error::KowalskiError,
-    memory::{MemoryProvider, MemoryQuery, MemoryUnit},
+    memory::{
+        MemoryProvider, MemoryQuery, MemoryUnit, find_near_duplicate,
+        rerank::{Reranker, rerank},
+        semantic::cosine_similarity,
+    },
 };
 use async_trait::async_trait;
-use log::{debug, error, info};
+use futures::stream::{self, StreamExt};
+use log::{debug, error, info, warn};
 use serde_json;
 use sqlx::Row;
 #[cfg(feature = "postgres")]
 use sqlx::postgres::PgPool;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -25,13 +31,14 @@ CREATE TABLE IF NOT EXISTS episodic_kv (
 );
 "#;
 
-/// Resolve filesystem path for the episodic DB file and ensure parent directories exist.
-fn episodic_db_file(episodic_path: &str) -> Result<PathBuf, KowalskiError> {
-    let p = episodic_path.trim_end_matches('/');
-    let file_path: PathBuf = if p.ends_with(".sqlite") || p.ends_with(".db") {
-        PathBuf::from(p)
+/// Resolve filesystem path for the episodic DB file (relative paths are under the data
+/// directory) and ensure parent directories exist.
+pub(crate) fn episodic_db_file(episodic_path: &str) -> Result<PathBuf, KowalskiError> {
+    let p = crate::config::data_path(episodic_path.trim_end_matches('/'));
+    let file_path: PathBuf = if p.extension().is_some_and(|e| e == "sqlite" || e == "db") {
+        p
     } else {
-        Path::new(p).join("episodic.sqlite")
+        p.join("episodic.sqlite")
     };
     if let Some(parent) = file_path.parent()
         && !parent.as_os_str().is_empty()
@@ -42,6 +49,32 @@ fn episodic_db_file(episodic_path: &str) -> Result<PathBuf, KowalskiError> {
     Ok(file_path)
 }
 
+/// Size and age summary of the episodic store, for operators (see [`EpisodicBuffer::stats`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EpisodicStats {
+    pub unit_count: usize,
+    /// SQLite: `page_count * page_size` of the database file. PostgreSQL: `pg_total_relation_size`.
+    pub approx_size_bytes: u64,
+    pub oldest_timestamp: Option<u64>,
+    pub newest_timestamp: Option<u64>,
+}
+
+/// Embedding requests [`EpisodicBuffer::reindex`] keeps in flight at once.
+const REINDEX_CONCURRENCY: usize = 4;
+/// Texts embedded per request by [`EpisodicBuffer::add_batch`](MemoryProvider::add_batch).
+const EMBED_BATCH: usize = 64;
+
+/// Outcome of [`EpisodicBuffer::reindex`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReindexReport {
+    /// All stored units.
+    pub unit_count: usize,
+    /// Units that had no embedding.
+    pub missing: usize,
+    /// Units embedded and written back.
+    pub repaired: usize,
+}
+
 /// A persistent memory store: **SQLite is the default** (single file under [`MemoryConfig::episodic_path`]);
 /// **PostgreSQL** `episodic_kv` is opt-in via `postgres://` URL + `postgres` feature.
 ///
@@ -56,6 +89,15 @@ pub struct EpisodicBuffer {
     #[cfg(not(feature = "postgres"))]
     sqlite: SqlitePool,
     llm_provider: Arc<dyn crate::llm::LLMProvider>,
+    /// See [`MemoryConfig::dedup_threshold`].
+    dedup_threshold: Option<f32>,
+    dedup_window: usize,
+    /// The last `dedup_window` units, newest first, that deduplication compares against. Loaded
+    /// on the first deduplicated insert, then kept current by this buffer's own writes.
+    recent: tokio::sync::Mutex<Option<Vec<MemoryUnit>>>,
+    /// Reorders the top [`MemoryConfig::rerank_candidates`] matches; see [`Self::with_reranker`].
+    reranker: Option<Arc<dyn Reranker>>,
+    rerank_candidates: usize,
 }
 
 impl EpisodicBuffer {
@@ -64,6 +106,7 @@ impl EpisodicBuffer {
     /// * **Default — SQLite:** Tier 2 uses a file derived from [`MemoryConfig::episodic_path`]:
     ///   - If `episodic_path` ends with `.sqlite` or `.db`, that file is used.
     ///   - Otherwise it is treated as a **directory** and `episodic.sqlite` is created inside it.
+    ///   - A relative path is under the data directory ([`crate::config::default_data_dir`]).
     /// * **Opt-in — PostgreSQL:** If [`crate::config::memory_uses_postgres`] is true, Tier 2 uses table `episodic_kv`
     ///   in that database (run migrations first, e.g. [`crate::db::run_memory_migrations_if_configured`]).
     pub async fn open(
@@ -85,6 +128,11 @@ impl EpisodicBuffer {
                     sqlite: None,
                     postgres: Some(pool),
                     llm_provider,
+                    dedup_threshold: memory.dedup_threshold,
+                    dedup_window: memory.dedup_window,
+                    recent: tokio::sync::Mutex::new(None),
+                    reranker: None,
+                    rerank_candidates: memory.rerank_candidates,
                 });
             }
             #[cfg(not(feature = "postgres"))]
@@ -112,6 +160,11 @@ impl EpisodicBuffer {
                 sqlite: Some(pool),
                 postgres: None,
                 llm_provider,
+                dedup_threshold: memory.dedup_threshold,
+                dedup_window: memory.dedup_window,
+                recent: tokio::sync::Mutex::new(None),
+                reranker: None,
+                rerank_candidates: memory.rerank_candidates,
             })
         }
         #[cfg(not(feature = "postgres"))]
@@ -119,6 +172,11 @@ impl EpisodicBuffer {
             Ok(Self {
                 sqlite: pool,
                 llm_provider,
+                dedup_threshold: memory.dedup_threshold,
+                dedup_window: memory.dedup_window,
+                recent: tokio::sync::Mutex::new(None),
+                reranker: None,
+                rerank_candidates: memory.rerank_candidates,
             })
         }
     }
@@ -130,15 +188,15 @@ impl EpisodicBuffer {
             let rows = sqlx::query("SELECT id, payload FROM episodic_kv ORDER BY id")
                 .fetch_all(pool)
                 .await
-                .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
             rows.into_iter()
                 .map(|row| -> Result<(String, String), KowalskiError> {
                     let id: String = row
                         .try_get("id")
-                        .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                        .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                     let payload: String = row
                         .try_get("payload")
-                        .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                        .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                     Ok((id, payload))
                 })
                 .collect::<Result<Vec<_>, _>>()?
@@ -149,15 +207,15 @@ impl EpisodicBuffer {
                 let rows = sqlx::query("SELECT id, payload FROM episodic_kv ORDER BY id")
                     .fetch_all(pool)
                     .await
-                    .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                    .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                 rows.into_iter()
                     .map(|row| -> Result<(String, String), KowalskiError> {
                         let id: String = row
                             .try_get("id")
-                            .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                         let payload: String = row
                             .try_get("payload")
-                            .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                         Ok((id, payload))
                     })
                     .collect::<Result<Vec<_>, _>>()?
@@ -166,15 +224,15 @@ impl EpisodicBuffer {
                 let rows = sqlx::query("SELECT id, payload FROM episodic_kv ORDER BY id")
                     .fetch_all(pool)
                     .await
-                    .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                    .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                 rows.into_iter()
                     .map(|row| -> Result<(String, String), KowalskiError> {
                         let id: String = row
                             .try_get("id")
-                            .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                         let payload: String = row
                             .try_get("payload")
-                            .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                         Ok((id, payload))
                     })
                     .collect::<Result<Vec<_>, _>>()?
@@ -189,13 +247,16 @@ impl EpisodicBuffer {
     }
 
     pub async fn delete(&mut self, id: &str) -> Result<(), KowalskiError> {
+        if let Some(recent) = self.recent.get_mut() {
+            recent.retain(|u| u.id != id);
+        }
         #[cfg(not(feature = "postgres"))]
         {
             sqlx::query("DELETE FROM episodic_kv WHERE id = ?")
                 .bind(id)
                 .execute(&self.sqlite)
                 .await
-                .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
         }
         #[cfg(feature = "postgres")]
         match (&self.sqlite, &self.postgres) {
@@ -204,14 +265,119 @@ impl EpisodicBuffer {
                     .bind(id)
                     .execute(pool)
                     .await
-                    .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                    .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
             }
             (None, Some(pool)) => {
                 sqlx::query("DELETE FROM episodic_kv WHERE id = $1")
                     .bind(id)
                     .execute(pool)
                     .await
-                    .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                    .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
+            }
+            _ => {
+                return Err(KowalskiError::Memory(
+                    "episodic buffer: expected exactly one of sqlite or postgres pool".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts stored units and reports the store size and the oldest/newest unit timestamps.
+    pub async fn stats(&self) -> Result<EpisodicStats, KowalskiError> {
+        let units = self.load_all_units().await?;
+        let approx_size_bytes = self.approx_size_bytes().await?;
+        Ok(EpisodicStats {
+            unit_count: units.len(),
+            approx_size_bytes,
+            oldest_timestamp: units.iter().map(|u| u.timestamp).min(),
+            newest_timestamp: units.iter().map(|u| u.timestamp).max(),
+        })
+    }
+
+    /// Embeds every unit stored without an embedding (e.g. added while the embedding model was
+    /// unreachable) and writes it back, so vector search can find it again.
+    pub async fn reindex(&self) -> Result<ReindexReport, KowalskiError> {
+        self.reindex_with_progress(&NoProgress).await
+    }
+
+    /// [`reindex`](Self::reindex), reporting each unit it tried. Units whose embedding still
+    /// fails are logged and left as they were; run it again later.
+    pub async fn reindex_with_progress(
+        &self,
+        progress: &dyn ProgressReporter,
+    ) -> Result<ReindexReport, KowalskiError> {
+        let units = self.load_all_units().await?;
+        let unit_count = units.len();
+        let missing: Vec<MemoryUnit> = units
+            .into_iter()
+            .filter(|u| u.embedding.as_ref().is_none_or(|e| e.is_empty()))
+            .collect();
+        info!(
+            "[EpisodicBuffer] Reindexing {} of {} units",
+            missing.len(),
+            unit_count
+        );
+        let mut report = ReindexReport {
+            unit_count,
+            missing: missing.len(),
+            repaired: 0,
+        };
+        let mut state = Progress {
+            total: Some(missing.len()),
+            ..Progress::default()
+        };
+        let mut embedded = stream::iter(missing)
+            .map(|unit| async move {
+                let embedding = self.llm_provider.embed(&unit.content).await;
+                (unit, embedding)
+            })
+            .buffered(REINDEX_CONCURRENCY);
+        while let Some((mut unit, embedding)) = embedded.next().await {
+            state.bytes += unit.content.len() as u64;
+            state.current = Some(unit.id.clone());
+            match embedding {
+                Ok(embedding) if !embedding.is_empty() => {
+                    unit.embedding = Some(embedding);
+                    self.upsert_unit(&unit).await?;
+                    report.repaired += 1;
+                }
+                Ok(_) => warn!("Empty embedding for memory {}; left as is", unit.id),
+                Err(e) => warn!("Failed to embed memory {}: {}; left as is", unit.id, e),
+            }
+            state.done += 1;
+            progress.report(&state);
+        }
+        if report.repaired > 0 {
+            // Repaired units now carry embeddings; reload the dedup window on the next insert.
+            *self.recent.lock().await = None;
+        }
+        Ok(report)
+    }
+
+    /// Reclaims space left by deleted rows (`VACUUM`). Run after bulk deletes such as consolidation.
+    pub async fn compact(&self) -> Result<(), KowalskiError> {
+        info!("[EpisodicBuffer] Compacting episodic store");
+        #[cfg(not(feature = "postgres"))]
+        {
+            sqlx::query("VACUUM")
+                .execute(&self.sqlite)
+                .await
+                .map_err(|e| KowalskiError::Memory(format!("episodic compact: {e}")))?;
+        }
+        #[cfg(feature = "postgres")]
+        match (&self.sqlite, &self.postgres) {
+            (Some(pool), None) => {
+                sqlx::query("VACUUM")
+                    .execute(pool)
+                    .await
+                    .map_err(|e| KowalskiError::Memory(format!("episodic compact: {e}")))?;
+            }
+            (None, Some(pool)) => {
+                sqlx::query("VACUUM episodic_kv")
+                    .execute(pool)
+                    .await
+                    .map_err(|e| KowalskiError::Memory(format!("episodic compact: {e}")))?;
             }
             _ => {
                 return Err(KowalskiError::Memory(
@@ -222,6 +388,35 @@ impl EpisodicBuffer {
         Ok(())
     }
 
+    async fn approx_size_bytes(&self) -> Result<u64, KowalskiError> {
+        const SQLITE_SIZE: &str =
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()";
+        #[cfg(not(feature = "postgres"))]
+        let size: i64 = sqlx::query_scalar(SQLITE_SIZE)
+            .fetch_one(&self.sqlite)
+            .await
+            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
+        #[cfg(feature = "postgres")]
+        let size: i64 = match (&self.sqlite, &self.postgres) {
+            (Some(pool), None) => sqlx::query_scalar(SQLITE_SIZE)
+                .fetch_one(pool)
+                .await
+                .map_err(|e| KowalskiError::memory_backend("episodic", e))?,
+            (None, Some(pool)) => {
+                sqlx::query_scalar("SELECT pg_total_relation_size('episodic_kv')")
+                    .fetch_one(pool)
+                    .await
+                    .map_err(|e| KowalskiError::memory_backend("episodic", e))?
+            }
+            _ => {
+                return Err(KowalskiError::Memory(
+                    "episodic buffer: expected exactly one of sqlite or postgres pool".into(),
+                ));
+            }
+        };
+        Ok(size.max(0) as u64)
+    }
+
     fn memory_units_from_pairs(pairs: Vec<(String, String)>) -> Vec<MemoryUnit> {
         let mut memories = Vec::with_capacity(pairs.len());
         for (_id, payload) in pairs {
@@ -233,10 +428,41 @@ impl EpisodicBuffer {
         memories
     }
 
-    pub async fn add_with_embedding(
-        &mut self,
-        mut memory: MemoryUnit,
-    ) -> Result<(), KowalskiError> {
+    pub async fn add_with_embedding(&mut self, memory: MemoryUnit) -> Result<(), KowalskiError> {
+        self.store(memory).await
+    }
+
+    /// Embeds the units of `memories` that have no embedding with one
+    /// [`embed_batch`](crate::llm::LLMProvider::embed_batch) request. When it fails, [`Self::store`]
+    /// embeds them one at a time instead.
+    async fn embed_missing(&self, memories: &mut [MemoryUnit]) {
+        let missing: Vec<usize> = (0..memories.len())
+            .filter(|&i| memories[i].embedding.is_none())
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        let texts: Vec<String> = missing
+            .iter()
+            .map(|&i| memories[i].content.clone())
+            .collect();
+        match self.llm_provider.embed_batch(&texts).await {
+            Ok(embeddings) => {
+                for (i, embedding) in missing.into_iter().zip(embeddings) {
+                    memories[i].embedding = Some(embedding);
+                }
+            }
+            Err(e) => warn!(
+                "Failed to embed {} memories in one batch: {}",
+                texts.len(),
+                e
+            ),
+        }
+    }
+
+    /// Embeds `memory` if needed, then stores it, or, with deduplication on, refreshes the
+    /// timestamp of a recent near-duplicate instead.
+    async fn store(&self, mut memory: MemoryUnit) -> Result<(), KowalskiError> {
         info!("[EpisodicBuffer] Adding memory unit: {}", memory.id);
         debug!("Adding memory unit to episodic buffer: {}", memory.id);
         if memory.embedding.is_none() {
@@ -247,14 +473,44 @@ impl EpisodicBuffer {
                 }
             }
         }
-        self.upsert_unit(&memory).await
+        let Some(threshold) = self.dedup_threshold else {
+            return self.upsert_unit(&memory).await;
+        };
+        let mut recent = self.recent.lock().await;
+        let recent = match &mut *recent {
+            Some(recent) => recent,
+            None => {
+                let mut units = self.load_all_units().await?;
+                units.sort_by_key(|u| std::cmp::Reverse(u.timestamp));
+                units.truncate(self.dedup_window);
+                recent.insert(units)
+            }
+        };
+        let unit = match find_near_duplicate(recent, &memory, threshold) {
+            Some(i) => {
+                let mut existing = recent[i].clone();
+                debug!(
+                    "[EpisodicBuffer] {} duplicates {}; refreshing it instead",
+                    memory.id, existing.id
+                );
+                existing.timestamp = existing.timestamp.max(memory.timestamp);
+                existing
+            }
+            None => memory,
+        };
+        self.upsert_unit(&unit).await?;
+        recent.retain(|u| u.id != unit.id);
+        let at = recent.partition_point(|u| u.timestamp > unit.timestamp);
+        recent.insert(at, unit);
+        recent.truncate(self.dedup_window);
+        Ok(())
     }
 
     async fn upsert_unit(&self, memory: &MemoryUnit) -> Result<(), KowalskiError> {
         let key = memory.id.clone();
         let value = serde_json::to_string(memory).map_err(|e| {
             error!("Failed to serialize memory unit {}: {}", key, e);
-            KowalskiError::Memory(e.to_string())
+            KowalskiError::Json(e)
         })?;
         #[cfg(not(feature = "postgres"))]
         {
@@ -268,7 +524,7 @@ impl EpisodicBuffer {
             .await
             .map_err(|e| {
                 error!("Failed to write episodic row {}: {}", key, e);
-                KowalskiError::Memory(e.to_string())
+                KowalskiError::memory_backend("episodic", e)
             })?;
         }
         #[cfg(feature = "postgres")]
@@ -284,7 +540,7 @@ impl EpisodicBuffer {
                 .await
                 .map_err(|e| {
                     error!("Failed to write episodic row {}: {}", key, e);
-                    KowalskiError::Memory(e.to_string())
+                    KowalskiError::memory_backend("episodic", e)
                 })?;
             }
             (None, Some(pool)) => {
@@ -298,7 +554,7 @@ impl EpisodicBuffer {
                 .await
                 .map_err(|e| {
                     error!("Failed to write episodic row {}: {}", key, e);
-                    KowalskiError::Memory(e.to_string())
+                    KowalskiError::memory_backend("episodic", e)
                 })?;
             }
             _ => {
@@ -317,15 +573,15 @@ impl EpisodicBuffer {
             let rows = sqlx::query("SELECT id, payload FROM episodic_kv")
                 .fetch_all(pool)
                 .await
-                .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
             rows.into_iter()
                 .map(|row| -> Result<(String, String), KowalskiError> {
                     let id: String = row
                         .try_get("id")
-                        .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                        .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                     let payload: String = row
                         .try_get("payload")
-                        .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                        .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                     Ok((id, payload))
                 })
                 .collect::<Result<Vec<_>, _>>()?
@@ -336,15 +592,15 @@ impl EpisodicBuffer {
                 let rows = sqlx::query("SELECT id, payload FROM episodic_kv")
                     .fetch_all(pool)
                     .await
-                    .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                    .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                 rows.into_iter()
                     .map(|row| -> Result<(String, String), KowalskiError> {
                         let id: String = row
                             .try_get("id")
-                            .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                         let payload: String = row
                             .try_get("payload")
-                            .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                         Ok((id, payload))
                     })
                     .collect::<Result<Vec<_>, _>>()?
@@ -353,15 +609,15 @@ impl EpisodicBuffer {
                 let rows = sqlx::query("SELECT id, payload FROM episodic_kv")
                     .fetch_all(pool)
                     .await
-                    .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                    .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                 rows.into_iter()
                     .map(|row| -> Result<(String, String), KowalskiError> {
                         let id: String = row
                             .try_get("id")
-                            .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                         let payload: String = row
                             .try_get("payload")
-                            .map_err(|e| KowalskiError::Memory(e.to_string()))?;
+                            .map_err(|e| KowalskiError::memory_backend("episodic", e))?;
                         Ok((id, payload))
                     })
                     .collect::<Result<Vec<_>, _>>()?
@@ -388,76 +644,223 @@ impl EpisodicBuffer {
         query: &str,
         retrieval_limit: usize,
     ) -> Result<Vec<MemoryUnit>, KowalskiError> {
+        self.ranked(query, retrieval_limit).await
+    }
+
+    /// Has `reranker` reorder the best `[memory] rerank_candidates` cosine + recency matches of
+    /// every retrieval before the top ones are returned.
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    /// The `limit` units for `query`: [`rank_units`], then the reranker when one is set.
+    async fn ranked(&self, query: &str, limit: usize) -> Result<Vec<MemoryUnit>, KowalskiError> {
         info!("[EpisodicBuffer][RETRIEVE] Query: '{}'", query);
         let query_embedding = self.llm_provider.embed(query).await.ok();
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        let max_time_span = 60 * 60 * 24 * 30u64;
         let units = self.load_all_units().await?;
-        let mut scored = Vec::new();
-        let mut fallback_results = Vec::new();
-        for unit in units {
-            if let (Some(q_emb), Some(m_emb)) = (query_embedding.as_ref(), unit.embedding.as_ref())
-            {
-                let sim = cosine_similarity(q_emb, m_emb);
-                let recency = 1.0
-                    - ((now.saturating_sub(unit.timestamp)) as f32 / max_time_span as f32).min(1.0);
-                let recency = recency.max(0.0);
-                let score = 0.85 * sim + 0.15 * recency;
-                scored.push((score, unit));
-            } else {
-                let lower_query = query.to_lowercase().trim().to_string();
-                let query_words: Vec<&str> = lower_query.split_whitespace().collect();
-                let content = unit.content.to_lowercase();
-                if query_words.iter().any(|w| content.contains(w)) {
-                    fallback_results.push(unit);
-                }
+        let Some(reranker) = &self.reranker else {
+            return Ok(rank_units(
+                units,
+                query,
+                query_embedding.as_deref(),
+                now,
+                limit,
+            ));
+        };
+        let candidates = rank_units(
+            units,
+            query,
+            query_embedding.as_deref(),
+            now,
+            limit.max(self.rerank_candidates),
+        );
+        Ok(rerank(reranker.as_ref(), query, candidates, limit).await)
+    }
+
+    /// What [`retrieve`](MemoryProvider::retrieve) ranks for `query`, with the parts of each
+    /// unit's score, best first. For tuning and for explaining surprising results; a reranker,
+    /// if set, is not applied.
+    pub async fn debug_retrieve(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<(MemoryUnit, RetrievalScore)>, KowalskiError> {
+        let query_embedding = match self.llm_provider.embed(query).await {
+            Ok(embedding) => Some(embedding),
+            Err(e) => {
+                warn!("Failed to embed query '{query}': {e}; using keyword matches");
+                None
             }
-        }
-        if !scored.is_empty() {
-            scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-            return Ok(scored
-                .into_iter()
-                .map(|(_, u)| u)
-                .take(retrieval_limit)
-                .collect());
-        }
-        let results = if fallback_results.len() > retrieval_limit {
-            fallback_results[fallback_results.len() - retrieval_limit..].to_vec()
-        } else {
-            fallback_results
         };
-        Ok(results)
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let units = self.load_all_units().await?;
+        Ok(rank_units_scored(
+            units,
+            query,
+            query_embedding.as_deref(),
+            now,
+            limit,
+        ))
     }
 }
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
-    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
-    if norm_a == 0.0 || norm_b == 0.0 {
-        0.0
-    } else {
-        dot / (norm_a * norm_b)
+/// How far back recency still counts when ranking, in seconds (30 days).
+pub(crate) const RECENCY_HORIZON_SECS: u64 = 60 * 60 * 24 * 30;
+
+/// Parts of an episodic ranking score (see [`EpisodicBuffer::debug_retrieve`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RetrievalScore {
+    /// What units are ranked by: 0.85 × `semantic` + 0.15 × `recency`.
+    pub score: f32,
+    /// Cosine similarity of the query and unit embeddings; 0 for keyword matches.
+    pub semantic: f32,
+    /// 1 for a unit stored now, falling linearly to 0 for one 30 days old.
+    pub recency: f32,
+}
+
+fn recency(timestamp: u64, now: u64) -> f32 {
+    let age = now.saturating_sub(timestamp) as f32 / RECENCY_HORIZON_SECS as f32;
+    (1.0 - age.min(1.0)).max(0.0)
+}
+
+/// Episodic ranking score: 0.85 × cosine similarity + 0.15 × recency, where recency falls
+/// linearly from 1 (now) to 0 (30 days ago).
+pub(crate) fn episodic_score(
+    query: &[f32],
+    unit: &[f32],
+    timestamp: u64,
+    now: u64,
+) -> RetrievalScore {
+    let semantic = cosine_similarity(query, unit);
+    let recency = recency(timestamp, now);
+    RetrievalScore {
+        score: 0.85 * semantic + 0.15 * recency,
+        semantic,
+        recency,
+    }
+}
+
+/// The `limit` units [`EpisodicBuffer`] retrieves for `query` out of `units`. With a query
+/// embedding, the embedded units ranked by [`episodic_score`] (ties keep store order). Otherwise,
+/// or when no unit has an embedding, the last `limit` units containing a word of the query
+/// (case-insensitive).
+///
+/// Only the top `limit` scores are sorted, and the keyword fallback stops once it has `limit`
+/// matches; `crate::baseline::rank_units` is the straightforward version it is checked against.
+pub fn rank_units(
+    units: Vec<MemoryUnit>,
+    query: &str,
+    query_embedding: Option<&[f32]>,
+    now: u64,
+    limit: usize,
+) -> Vec<MemoryUnit> {
+    rank_units_scored(units, query, query_embedding, now, limit)
+        .into_iter()
+        .map(|(unit, _)| unit)
+        .collect()
+}
+
+/// [`rank_units`] with the score of each unit. Keyword matches score only their recency.
+pub fn rank_units_scored(
+    units: Vec<MemoryUnit>,
+    query: &str,
+    query_embedding: Option<&[f32]>,
+    now: u64,
+    limit: usize,
+) -> Vec<(MemoryUnit, RetrievalScore)> {
+    if limit == 0 {
+        return Vec::new();
+    }
+    if let Some(query_embedding) = query_embedding {
+        let mut scored: Vec<(RetrievalScore, usize)> = units
+            .iter()
+            .enumerate()
+            .filter_map(|(i, unit)| {
+                let embedding = unit.embedding.as_deref()?;
+                Some((
+                    episodic_score(query_embedding, embedding, unit.timestamp, now),
+                    i,
+                ))
+            })
+            .collect();
+        if !scored.is_empty() {
+            let by_rank = |a: &(RetrievalScore, usize), b: &(RetrievalScore, usize)| {
+                b.0.score.total_cmp(&a.0.score).then(a.1.cmp(&b.1))
+            };
+            if scored.len() > limit {
+                scored.select_nth_unstable_by(limit - 1, by_rank);
+                scored.truncate(limit);
+            }
+            scored.sort_unstable_by(by_rank);
+            let mut units: Vec<Option<MemoryUnit>> = units.into_iter().map(Some).collect();
+            return scored
+                .into_iter()
+                .filter_map(|(score, i)| Some((units[i].take()?, score)))
+                .collect();
+        }
     }
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let mut matches: Vec<(MemoryUnit, RetrievalScore)> = units
+        .into_iter()
+        .rev()
+        .filter(|unit| {
+            let content = unit.content.to_lowercase();
+            words.iter().any(|w| content.contains(w))
+        })
+        .take(limit)
+        .map(|unit| {
+            let recency = recency(unit.timestamp, now);
+            let score = RetrievalScore {
+                score: 0.15 * recency,
+                semantic: 0.0,
+                recency,
+            };
+            (unit, score)
+        })
+        .collect();
+    matches.reverse();
+    matches
 }
 
 #[async_trait]
 impl MemoryProvider for EpisodicBuffer {
-    async fn add(&mut self, mut memory: MemoryUnit) -> Result<(), KowalskiError> {
-        info!("[EpisodicBuffer] Adding memory unit: {}", memory.id);
-        debug!("Adding memory unit to episodic buffer: {}", memory.id);
-        if memory.embedding.is_none() {
-            match self.llm_provider.embed(&memory.content).await {
-                Ok(embedding) => memory.embedding = Some(embedding),
-                Err(e) => {
-                    error!("Failed to get embedding for memory {}: {}", memory.id, e);
-                }
+    async fn add(&mut self, memory: MemoryUnit) -> Result<(), KowalskiError> {
+        self.store(memory).await
+    }
+
+    /// Embeds up to [`EMBED_BATCH`] units per backend request instead of one request per unit.
+    async fn add_batch_with_progress(
+        &mut self,
+        memories: Vec<MemoryUnit>,
+        progress: &dyn ProgressReporter,
+    ) -> Result<(), KowalskiError> {
+        let mut state = Progress {
+            total: Some(memories.len()),
+            ..Progress::default()
+        };
+        let mut rest = memories;
+        while !rest.is_empty() {
+            let tail = rest.split_off(rest.len().min(EMBED_BATCH));
+            let mut batch = std::mem::replace(&mut rest, tail);
+            self.embed_missing(&mut batch).await;
+            for memory in batch {
+                state.bytes += memory.content.len() as u64;
+                state.current = Some(memory.id.clone());
+                self.store(memory).await?;
+                state.done += 1;
+                progress.report(&state);
             }
         }
-        self.upsert_unit(&memory).await
+        Ok(())
     }
 
     async fn retrieve(
@@ -465,52 +868,279 @@ impl MemoryProvider for EpisodicBuffer {
         query: &str,
         retrieval_limit: usize,
     ) -> Result<Vec<MemoryUnit>, KowalskiError> {
-        info!("[EpisodicBuffer][RETRIEVE] Query: '{}'", query);
-        let query_embedding = self.llm_provider.embed(query).await.ok();
+        self.ranked(query, retrieval_limit).await
+    }
+
+    async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryUnit>, KowalskiError> {
+        debug!("Searching episodic buffer with query: {:?}", query);
+        self.retrieve(&query.text_query, 3).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn unit(id: &str, timestamp: u64) -> MemoryUnit {
+        MemoryUnit {
+            id: id.to_string(),
+            timestamp,
+            content: format!("episode {id}"),
+            embedding: Some(vec![0.1, 0.2, 0.3]),
+            metadata: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn stats_reports_count_and_time_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        let llm = crate::llm::create_llm_provider(&config).unwrap();
+        let mut buffer = EpisodicBuffer::open(&config.memory, llm).await.unwrap();
+
+        assert_eq!(buffer.stats().await.unwrap().unit_count, 0);
+
+        for (i, ts) in [300u64, 100, 200].into_iter().enumerate() {
+            buffer.add(unit(&format!("u{i}"), ts)).await.unwrap();
+        }
+        let stats = buffer.stats().await.unwrap();
+        assert_eq!(stats.unit_count, 3);
+        assert_eq!(stats.oldest_timestamp, Some(100));
+        assert_eq!(stats.newest_timestamp, Some(300));
+        assert!(stats.approx_size_bytes > 0);
+
+        buffer.delete("u0").await.unwrap();
+        buffer.compact().await.unwrap();
+        let stats = buffer.stats().await.unwrap();
+        assert_eq!(stats.unit_count, 2);
+        assert_eq!(stats.newest_timestamp, Some(200));
+    }
+
+    #[tokio::test]
+    async fn near_duplicates_refresh_instead_of_inserting() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        config.memory.dedup_threshold = Some(0.95);
+        let llm = crate::llm::create_llm_provider(&config).unwrap();
+        let mut buffer = EpisodicBuffer::open(&config.memory, llm).await.unwrap();
+
+        let mut first = unit("a", 100);
+        first.content = "The user prefers dark mode".to_string();
+        let mut again = first.clone();
+        again.id = "b".to_string();
+        again.timestamp = 250;
+        buffer.add_batch(vec![first, again]).await.unwrap();
+
+        let units = buffer.retrieve_all().await.unwrap();
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].id, "a");
+        assert_eq!(units[0].timestamp, 250);
+
+        // Reworded, with a nearly identical embedding: still a duplicate.
+        let mut reworded = unit("c", 300);
+        reworded.content = "User likes dark mode".to_string();
+        reworded.embedding = Some(vec![0.1, 0.2, 0.31]);
+        // Unrelated direction: stored.
+        let mut other = unit("d", 400);
+        other.embedding = Some(vec![0.3, -0.2, 0.0]);
+        buffer.add_batch(vec![reworded, other]).await.unwrap();
+
+        let units = buffer.retrieve_all().await.unwrap();
+        assert_eq!(
+            units.iter().map(|u| u.id.as_str()).collect::<Vec<_>>(),
+            ["a", "d"]
+        );
+        assert_eq!(units[0].timestamp, 300);
+
+        // A deleted unit no longer absorbs its duplicates.
+        buffer.delete("a").await.unwrap();
+        let mut after_delete = unit("e", 500);
+        after_delete.content = "The user prefers dark mode".to_string();
+        buffer.add(after_delete).await.unwrap();
+        // A reopened buffer compares against what is already stored.
+        let llm = crate::llm::create_llm_provider(&config).unwrap();
+        let mut reopened = EpisodicBuffer::open(&config.memory, llm).await.unwrap();
+        let mut repeat = unit("f", 600);
+        repeat.content = "The user prefers dark mode".to_string();
+        reopened.add(repeat).await.unwrap();
+        let units = reopened.retrieve_all().await.unwrap();
+        assert_eq!(
+            units.iter().map(|u| u.id.as_str()).collect::<Vec<_>>(),
+            ["d", "e"]
+        );
+        assert_eq!(units[1].timestamp, 600);
+    }
+
+    /// Prefers units mentioning `word`, and records how many candidates it was shown.
+    struct KeywordReranker {
+        word: &'static str,
+        seen: std::sync::Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl Reranker for KeywordReranker {
+        async fn score(
+            &self,
+            _query: &str,
+            candidates: &[MemoryUnit],
+        ) -> Result<Vec<f32>, KowalskiError> {
+            self.seen.lock().unwrap().push(candidates.len());
+            Ok(candidates
+                .iter()
+                .map(|u| f32::from(u8::from(u.content.contains(self.word))))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn reranker_reorders_the_top_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        config.memory.rerank_candidates = 3;
+        let llm = crate::llm::create_llm_provider(&config).unwrap();
+        let mut buffer = EpisodicBuffer::open(&config.memory, llm).await.unwrap();
+        for (id, content) in [
+            ("a", "note about lunch"),
+            ("b", "note about peanuts in lunch"),
+            ("c", "note about the weather"),
+            ("d", "note about a meeting"),
+        ] {
+            let mut memory = unit(id, 100);
+            memory.content = content.to_string();
+            memory.embedding = None;
+            buffer.add(memory).await.unwrap();
+        }
+        // No embeddings, so retrieval falls back to the latest keyword matches.
+        let plain = buffer.retrieve("note", 2).await.unwrap();
+        assert_eq!(
+            plain.iter().map(|u| u.id.as_str()).collect::<Vec<_>>(),
+            ["c", "d"]
+        );
+
+        let reranker = Arc::new(KeywordReranker {
+            word: "peanuts",
+            seen: std::sync::Mutex::new(Vec::new()),
+        });
+        let buffer = buffer.with_reranker(reranker.clone());
+        let reranked = buffer.retrieve("note", 2).await.unwrap();
+        assert_eq!(
+            reranked.iter().map(|u| u.id.as_str()).collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+        assert_eq!(*reranker.seen.lock().unwrap(), [3]);
+    }
+
+    #[tokio::test]
+    async fn reindex_embeds_units_stored_without_one() {
+        use crate::progress::Progress;
+        use crate::testing::MockBackend;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        // Embedding "fails" (empty vectors), as when the model is unreachable.
+        let down = Arc::new(MockBackend::script().with_embedding(Vec::new()).build());
+        let buffer = EpisodicBuffer::open(&config.memory, down).await.unwrap();
+        for id in ["a", "b", "c"] {
+            let mut memory = unit(id, 100);
+            memory.embedding = None;
+            buffer.upsert_unit(&memory).await.unwrap();
+        }
+        buffer.upsert_unit(&unit("d", 100)).await.unwrap();
+        let report = buffer.reindex().await.unwrap();
+        assert_eq!((report.missing, report.repaired), (3, 0));
+
+        let up = Arc::new(
+            MockBackend::script()
+                .with_embedding(vec![0.5, 0.5, 0.0])
+                .build(),
+        );
+        let buffer = EpisodicBuffer::open(&config.memory, up).await.unwrap();
+        let updates = std::sync::Mutex::new(Vec::new());
+        let reporter = |p: &Progress| updates.lock().unwrap().push(p.done);
+        let report = buffer.reindex_with_progress(&reporter).await.unwrap();
+        assert_eq!(
+            report,
+            ReindexReport {
+                unit_count: 4,
+                missing: 3,
+                repaired: 3,
+            }
+        );
+        assert_eq!(*updates.lock().unwrap(), [1, 2, 3]);
+
+        let units = buffer.retrieve_all().await.unwrap();
+        for u in &units {
+            let expected = if u.id == "d" {
+                vec![0.1, 0.2, 0.3]
+            } else {
+                vec![0.5, 0.5, 0.0]
+            };
+            assert_eq!(
+                u.embedding.as_deref(),
+                Some(expected.as_slice()),
+                "{}",
+                u.id
+            );
+        }
+        assert_eq!(buffer.reindex().await.unwrap().missing, 0);
+    }
+
+    #[tokio::test]
+    async fn debug_retrieve_reports_score_parts_best_first() {
+        use crate::testing::MockBackend;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        let llm = Arc::new(MockBackend::script().with_embedding(vec![1.0, 0.0]).build());
+        let buffer = EpisodicBuffer::open(&config.memory, llm).await.unwrap();
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        let max_time_span = 60 * 60 * 24 * 30u64;
-        let units = self.load_all_units().await?;
-        let mut scored = Vec::new();
-        let mut fallback_results = Vec::new();
-        for unit in units {
-            if let (Some(q_emb), Some(m_emb)) = (query_embedding.as_ref(), unit.embedding.as_ref())
-            {
-                let sim = cosine_similarity(q_emb, m_emb);
-                let recency = 1.0
-                    - ((now.saturating_sub(unit.timestamp)) as f32 / max_time_span as f32).min(1.0);
-                let recency = recency.max(0.0);
-                let score = 0.85 * sim + 0.15 * recency;
-                scored.push((score, unit));
-            } else {
-                let lower_query = query.to_lowercase().trim().to_string();
-                let query_words: Vec<&str> = lower_query.split_whitespace().collect();
-                let content = unit.content.to_lowercase();
-                if query_words.iter().any(|w| content.contains(w)) {
-                    fallback_results.push(unit);
-                }
-            }
-        }
-        if !scored.is_empty() {
-            scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-            return Ok(scored
-                .into_iter()
-                .map(|(_, u)| u)
-                .take(retrieval_limit)
-                .collect());
+        let day = 60 * 60 * 24;
+        for (id, embedding, age) in [
+            ("close-old", [1.0, 0.1], 20 * day),
+            ("far-new", [0.1, 1.0], 0),
+            ("close-new", [1.0, 0.2], day),
+            ("ancient", [1.0, 0.0], 90 * day),
+        ] {
+            let mut memory = unit(id, now - age);
+            memory.embedding = Some(embedding.to_vec());
+            buffer.upsert_unit(&memory).await.unwrap();
         }
-        let results = if fallback_results.len() > retrieval_limit {
-            fallback_results[fallback_results.len() - retrieval_limit..].to_vec()
-        } else {
-            fallback_results
-        };
-        Ok(results)
-    }
 
-    async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryUnit>, KowalskiError> {
-        debug!("Searching episodic buffer with query: {:?}", query);
-        self.retrieve(&query.text_query, 3).await
+        let scored = buffer.debug_retrieve("episode", 3).await.unwrap();
+        assert_eq!(
+            scored
+                .iter()
+                .map(|(u, _)| u.id.as_str())
+                .collect::<Vec<_>>(),
+            ["close-new", "close-old", "ancient"]
+        );
+        for (unit, parts) in &scored {
+            for part in [parts.score, parts.semantic, parts.recency] {
+                assert!((0.0..=1.0).contains(&part), "{}: {parts:?}", unit.id);
+            }
+            let combined = 0.85 * parts.semantic + 0.15 * parts.recency;
+            assert!((parts.score - combined).abs() < 1e-6);
+        }
+        assert!(scored.windows(2).all(|w| w[0].1.score >= w[1].1.score));
+        assert_eq!(scored[2].1.recency, 0.0);
+        assert_eq!(
+            buffer
+                .retrieve("episode", 3)
+                .await
+                .unwrap()
+                .iter()
+                .map(|u| u.id.as_str())
+                .collect::<Vec<_>>(),
+            ["close-new", "close-old", "ancient"]
+        );
     }
 }
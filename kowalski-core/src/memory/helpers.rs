@@ -5,6 +5,7 @@ use crate::config::{Config, memory_uses_postgres};
 use crate::error::KowalskiError;
 use crate::memory::MemoryProvider;
 use crate::memory::episodic::EpisodicBuffer;
+use crate::memory::rerank::LlmReranker;
 use crate::memory::semantic::SemanticStore;
 #[cfg(feature = "postgres")]
 use crate::memory::semantic_pg::PostgresSemanticStore;
@@ -45,18 +46,36 @@ pub async fn create_semantic_memory(
         }
     }
     drop(llm);
-    Ok(Arc::new(Mutex::new(SemanticStore::new())))
+    Ok(Arc::new(Mutex::new(SemanticStore::new().with_dedup(
+        config.memory.dedup_threshold,
+        config.memory.dedup_window,
+    ))))
+}
+
+/// Tier 2 episodic memory ([`EpisodicBuffer::open`]), reranked by an [`LlmReranker`] when
+/// `[memory] rerank` is on.
+pub async fn open_episodic_memory(
+    config: &Config,
+    llm: Arc<dyn crate::llm::LLMProvider>,
+) -> Result<EpisodicBuffer, KowalskiError> {
+    let episodic = EpisodicBuffer::open(&config.memory, llm.clone()).await?;
+    if !config.memory.rerank {
+        return Ok(episodic);
+    }
+    Ok(episodic.with_reranker(Arc::new(LlmReranker::from_config(config, llm))))
 }
 
 /// Creates the standard set of memory providers from a config
 pub async fn create_memory_providers(
     config: &Config,
 ) -> Result<(MemoryProviderArc, MemoryProviderArc, MemoryProviderArc), KowalskiError> {
-    let working_memory = Arc::new(Mutex::new(WorkingMemory::new(100))) as MemoryProviderArc;
+    let working_memory = Arc::new(Mutex::new(WorkingMemory::new(
+        config.working_memory_capacity,
+    ))) as MemoryProviderArc;
 
     let llm_provider = crate::llm::create_llm_provider(config)?;
     let episodic_memory = Arc::new(Mutex::new(
-        EpisodicBuffer::open(&config.memory, llm_provider.clone()).await?,
+        open_episodic_memory(config, llm_provider.clone()).await?,
     )) as MemoryProviderArc;
 
     let semantic_memory = create_semantic_memory(config, llm_provider).await?;
@@ -0,0 +1,211 @@
+// Explicit key-value scratch memory (`agent_kv`), stored next to the episodic buffer.
+// Default: the episodic SQLite file. Optional: PostgreSQL when `memory.database_url` is `postgres://…`.
+
+use crate::{
+    config::{MemoryConfig, memory_uses_postgres},
+    error::KowalskiError,
+    memory::episodic::episodic_db_file,
+};
+use log::info;
+#[cfg(feature = "postgres")]
+use sqlx::postgres::PgPool;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Schema for the key-value table (same as `migrations/sqlite/003_agent_kv.sql`).
+const AGENT_KV_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS agent_kv (
+    namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (namespace, key)
+);
+"#;
+
+#[derive(Clone)]
+enum KvPool {
+    Sqlite(SqlitePool),
+    #[cfg(feature = "postgres")]
+    Postgres(PgPool),
+}
+
+fn kv_err(e: sqlx::Error) -> KowalskiError {
+    KowalskiError::memory_backend("key-value", e)
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// String key-value store in its own table (`agent_kv`), separate from the embedding-based tiers.
+///
+/// Keys are scoped by a namespace so several agents can share one database file.
+pub struct KeyValueStore {
+    pool: KvPool,
+    namespace: String,
+}
+
+impl KeyValueStore {
+    /// Opens the store in the same database as [`crate::memory::episodic::EpisodicBuffer::open`]
+    /// would for `memory`, creating the `agent_kv` table if needed.
+    pub async fn open(memory: &MemoryConfig, namespace: &str) -> Result<Self, KowalskiError> {
+        if memory_uses_postgres(memory) {
+            #[cfg(feature = "postgres")]
+            {
+                let url = memory
+                    .database_url
+                    .as_ref()
+                    .expect("memory_uses_postgres implies database_url is set");
+                info!("Opening key-value store on PostgreSQL (agent_kv)");
+                let pool = PgPool::connect(url.as_str()).await.map_err(kv_err)?;
+                sqlx::query(AGENT_KV_SCHEMA)
+                    .execute(&pool)
+                    .await
+                    .map_err(kv_err)?;
+                return Ok(Self {
+                    pool: KvPool::Postgres(pool),
+                    namespace: namespace.to_string(),
+                });
+            }
+            #[cfg(not(feature = "postgres"))]
+            {
+                return Err(crate::config::postgres_feature_required_error());
+            }
+        }
+
+        let file = episodic_db_file(&memory.episodic_path)?;
+        info!("Opening key-value store at {}", file.display());
+        let opts = SqliteConnectOptions::new()
+            .filename(&file)
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(opts).await.map_err(kv_err)?;
+        sqlx::query(AGENT_KV_SCHEMA)
+            .execute(&pool)
+            .await
+            .map_err(kv_err)?;
+        Ok(Self {
+            pool: KvPool::Sqlite(pool),
+            namespace: namespace.to_string(),
+        })
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// The same database under another namespace, sharing this store's connections.
+    pub fn scoped(&self, namespace: &str) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            namespace: namespace.to_string(),
+        }
+    }
+
+    /// Stores `value` under `key`, replacing any previous value.
+    pub async fn set(&self, key: &str, value: &str) -> Result<(), KowalskiError> {
+        match &self.pool {
+            KvPool::Sqlite(pool) => {
+                sqlx::query(
+                    "INSERT INTO agent_kv (namespace, key, value, updated_at) VALUES (?, ?, ?, ?)
+                     ON CONFLICT(namespace, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                )
+                .bind(&self.namespace)
+                .bind(key)
+                .bind(value)
+                .bind(now_secs())
+                .execute(pool)
+                .await
+                .map_err(kv_err)?;
+            }
+            #[cfg(feature = "postgres")]
+            KvPool::Postgres(pool) => {
+                sqlx::query(
+                    "INSERT INTO agent_kv (namespace, key, value, updated_at) VALUES ($1, $2, $3, $4)
+                     ON CONFLICT (namespace, key) DO UPDATE SET value = EXCLUDED.value, updated_at = EXCLUDED.updated_at",
+                )
+                .bind(&self.namespace)
+                .bind(key)
+                .bind(value)
+                .bind(now_secs())
+                .execute(pool)
+                .await
+                .map_err(kv_err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value stored under `key`, if any.
+    pub async fn get(&self, key: &str) -> Result<Option<String>, KowalskiError> {
+        match &self.pool {
+            KvPool::Sqlite(pool) => {
+                sqlx::query_scalar("SELECT value FROM agent_kv WHERE namespace = ? AND key = ?")
+                    .bind(&self.namespace)
+                    .bind(key)
+                    .fetch_optional(pool)
+                    .await
+                    .map_err(kv_err)
+            }
+            #[cfg(feature = "postgres")]
+            KvPool::Postgres(pool) => {
+                sqlx::query_scalar("SELECT value FROM agent_kv WHERE namespace = $1 AND key = $2")
+                    .bind(&self.namespace)
+                    .bind(key)
+                    .fetch_optional(pool)
+                    .await
+                    .map_err(kv_err)
+            }
+        }
+    }
+
+    /// Removes `key`; returns whether it existed.
+    pub async fn delete(&self, key: &str) -> Result<bool, KowalskiError> {
+        let affected = match &self.pool {
+            KvPool::Sqlite(pool) => {
+                sqlx::query("DELETE FROM agent_kv WHERE namespace = ? AND key = ?")
+                    .bind(&self.namespace)
+                    .bind(key)
+                    .execute(pool)
+                    .await
+                    .map_err(kv_err)?
+                    .rows_affected()
+            }
+            #[cfg(feature = "postgres")]
+            KvPool::Postgres(pool) => {
+                sqlx::query("DELETE FROM agent_kv WHERE namespace = $1 AND key = $2")
+                    .bind(&self.namespace)
+                    .bind(key)
+                    .execute(pool)
+                    .await
+                    .map_err(kv_err)?
+                    .rows_affected()
+            }
+        };
+        Ok(affected > 0)
+    }
+
+    /// All keys in this namespace, sorted.
+    pub async fn keys(&self) -> Result<Vec<String>, KowalskiError> {
+        match &self.pool {
+            KvPool::Sqlite(pool) => {
+                sqlx::query_scalar("SELECT key FROM agent_kv WHERE namespace = ? ORDER BY key")
+                    .bind(&self.namespace)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(kv_err)
+            }
+            #[cfg(feature = "postgres")]
+            KvPool::Postgres(pool) => {
+                sqlx::query_scalar("SELECT key FROM agent_kv WHERE namespace = $1 ORDER BY key")
+                    .bind(&self.namespace)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(kv_err)
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
 pub mod consolidation;
 pub mod episodic;
 pub mod helpers;
+pub mod kv;
+pub mod profile;
+pub mod rerank;
 pub mod semantic;
 #[cfg(feature = "postgres")]
 pub mod semantic_pg;
@@ -13,8 +16,10 @@ pub use semantic_pg::PostgresSemanticStore;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::error::KowalskiError;
+use crate::progress::{NoProgress, Progress, ProgressReporter};
 
 /// Represents a single unit of memory, which could be a message, a fact, or a summary.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -23,6 +28,11 @@ pub struct MemoryUnit {
     pub timestamp: u64,
     pub content: String,
     pub embedding: Option<Vec<f32>>,
+    /// Tags such as `kind` and `conversation_id` (see
+    /// [`BaseAgent::summarize_conversation`](crate::agent::BaseAgent::summarize_conversation)).
+    /// Kept by the episodic buffer and the in-process and PostgreSQL semantic stores.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
 /// The core trait for any memory system in Kowalski.
@@ -32,6 +42,32 @@ pub trait MemoryProvider {
     /// Adds a memory unit to the store.
     async fn add(&mut self, memory: MemoryUnit) -> Result<(), KowalskiError>;
 
+    /// Adds several memory units in order (see [`add`](Self::add)).
+    async fn add_batch(&mut self, memories: Vec<MemoryUnit>) -> Result<(), KowalskiError> {
+        self.add_batch_with_progress(memories, &NoProgress).await
+    }
+
+    /// [`add_batch`](Self::add_batch), reporting each unit added (embedding them can take a
+    /// while).
+    async fn add_batch_with_progress(
+        &mut self,
+        memories: Vec<MemoryUnit>,
+        progress: &dyn ProgressReporter,
+    ) -> Result<(), KowalskiError> {
+        let mut state = Progress {
+            total: Some(memories.len()),
+            ..Progress::default()
+        };
+        for memory in memories {
+            state.bytes += memory.content.len() as u64;
+            state.current = Some(memory.id.clone());
+            self.add(memory).await?;
+            state.done += 1;
+            progress.report(&state);
+        }
+        Ok(())
+    }
+
     /// Retrieves a set of memories based on a query, limited to retrieval_limit.
     async fn retrieve(
         &self,
@@ -41,6 +77,57 @@ pub trait MemoryProvider {
 
     /// A more advanced retrieval method using a structured query.
     async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryUnit>, KowalskiError>;
+
+    /// Keeps `memory` however many units are added later (see
+    /// [`WorkingMemory`](working::WorkingMemory)); pinning the same id again replaces it. Stores
+    /// that never evict do not pin.
+    async fn pin(&mut self, _memory: MemoryUnit) -> Result<(), KowalskiError> {
+        Err(KowalskiError::Memory(
+            "this memory store does not pin units".to_string(),
+        ))
+    }
+
+    /// Releases the pinned unit `id`; returns whether it was pinned.
+    async fn unpin(&mut self, _id: &str) -> Result<bool, KowalskiError> {
+        Ok(false)
+    }
+
+    /// Pinned units, in the order they were pinned.
+    fn pinned(&self) -> Vec<MemoryUnit> {
+        Vec::new()
+    }
+
+    /// How many recent units the store keeps before evicting the oldest; `None` for stores
+    /// that do not evict. An agent's working memory capacity is the number of recent turns each
+    /// request carries.
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Index into `recent` of the unit `candidate` duplicates: identical content, or embeddings with
+/// cosine similarity of at least `threshold`. The most similar match wins.
+pub(crate) fn find_near_duplicate(
+    recent: &[MemoryUnit],
+    candidate: &MemoryUnit,
+    threshold: f32,
+) -> Option<usize> {
+    recent
+        .iter()
+        .enumerate()
+        .filter_map(|(i, unit)| {
+            let similarity = if unit.content == candidate.content {
+                1.0
+            } else {
+                match (&unit.embedding, &candidate.embedding) {
+                    (Some(a), Some(b)) => semantic::cosine_similarity(a, b),
+                    _ => return None,
+                }
+            };
+            (similarity >= threshold).then_some((i, similarity))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
 }
 
 /// A structured query for more advanced memory retrieval.
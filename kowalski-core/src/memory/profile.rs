@@ -0,0 +1,301 @@
+//! Profile facts: short `key: value` notes about the user ("name: Ada", "editor: helix") that
+//! outlive conversations and restarts. Every new conversation gets them as one compact system
+//! message (see [`crate::agent::BaseAgent::set_profile`]).
+//!
+//! Facts are only saved explicitly ([`ProfileStore::remember`]). The consolidator may
+//! [propose](ProfileStore::propose) candidates it finds in episodic memory; those wait in a
+//! separate namespace until [approved](ProfileStore::approve) and are never injected before.
+
+use crate::config::MemoryConfig;
+use crate::error::KowalskiError;
+use crate::memory::kv::KeyValueStore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use tokio::sync::broadcast;
+
+/// Profile the CLI agents share.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// A fact the consolidator found but nobody has confirmed yet.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FactCandidate {
+    pub key: String,
+    pub value: String,
+    /// Id of the episodic memory it came from.
+    pub source: String,
+}
+
+/// Changes to a profile, broadcast to [`ProfileStore::subscribe`] receivers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProfileEvent {
+    /// Waiting for [`ProfileStore::approve`] or [`ProfileStore::reject`].
+    CandidateProposed {
+        candidate: FactCandidate,
+    },
+    CandidateRejected {
+        key: String,
+    },
+    /// Saved directly or by approving a candidate.
+    FactRemembered {
+        key: String,
+        value: String,
+    },
+    FactForgotten {
+        key: String,
+    },
+}
+
+/// What a candidate waits as, keyed by its fact key.
+#[derive(Serialize, Deserialize)]
+struct PendingFact {
+    value: String,
+    source: String,
+}
+
+/// Facts of one profile in the [`KeyValueStore`] (namespace `profile:<name>`), with candidates
+/// under `profile-candidates:<name>`. Facts are also kept in memory so a conversation can start
+/// without touching the database.
+pub struct ProfileStore {
+    facts: KeyValueStore,
+    candidates: KeyValueStore,
+    cache: RwLock<BTreeMap<String, String>>,
+    events: broadcast::Sender<ProfileEvent>,
+}
+
+impl ProfileStore {
+    /// Opens profile `name` in the database of `memory` and loads its facts.
+    pub async fn open(memory: &MemoryConfig, name: &str) -> Result<Self, KowalskiError> {
+        let store = KeyValueStore::open(memory, &format!("profile:{name}")).await?;
+        Self::from_store(store, name).await
+    }
+
+    /// Like [`Self::open`] on an already opened store (its namespace is not used).
+    pub async fn from_store(store: KeyValueStore, name: &str) -> Result<Self, KowalskiError> {
+        let facts = store.scoped(&format!("profile:{name}"));
+        let candidates = store.scoped(&format!("profile-candidates:{name}"));
+        let mut cache = BTreeMap::new();
+        for key in facts.keys().await? {
+            if let Some(value) = facts.get(&key).await? {
+                cache.insert(key, value);
+            }
+        }
+        Ok(Self {
+            facts,
+            candidates,
+            cache: RwLock::new(cache),
+            events: broadcast::channel(64).0,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProfileEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit(&self, event: ProfileEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Saves `value` under `key` (both trimmed, neither empty), replacing any previous value and
+    /// any candidate for the key.
+    pub async fn remember(&self, key: &str, value: &str) -> Result<(), KowalskiError> {
+        let (key, value) = (key.trim(), value.trim());
+        if key.is_empty() || value.is_empty() {
+            return Err(KowalskiError::Validation(
+                "profile facts need a key and a value".to_string(),
+            ));
+        }
+        self.facts.set(key, value).await?;
+        self.candidates.delete(key).await?;
+        self.cache
+            .write()
+            .unwrap()
+            .insert(key.to_string(), value.to_string());
+        self.emit(ProfileEvent::FactRemembered {
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Removes the fact `key`; returns whether it existed.
+    pub async fn forget(&self, key: &str) -> Result<bool, KowalskiError> {
+        let key = key.trim();
+        let existed = self.facts.delete(key).await?;
+        self.cache.write().unwrap().remove(key);
+        if existed {
+            self.emit(ProfileEvent::FactForgotten {
+                key: key.to_string(),
+            });
+        }
+        Ok(existed)
+    }
+
+    /// Confirmed facts, sorted by key.
+    pub fn facts(&self) -> Vec<(String, String)> {
+        self.cache
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// One `- key: value` line per fact, or `None` without facts.
+    pub fn render(&self) -> Option<String> {
+        let facts = self.cache.read().unwrap();
+        (!facts.is_empty()).then(|| {
+            facts
+                .iter()
+                .map(|(key, value)| format!("- {key}: {value}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+
+    /// Queues `candidate` for approval, replacing an earlier candidate for its key. Returns
+    /// `false` (and queues nothing) when the profile already holds exactly this fact.
+    pub async fn propose(&self, candidate: FactCandidate) -> Result<bool, KowalskiError> {
+        let key = candidate.key.trim();
+        let value = candidate.value.trim();
+        if key.is_empty() || value.is_empty() {
+            return Ok(false);
+        }
+        if self.cache.read().unwrap().get(key).map(String::as_str) == Some(value) {
+            return Ok(false);
+        }
+        let pending = PendingFact {
+            value: value.to_string(),
+            source: candidate.source.clone(),
+        };
+        self.candidates
+            .set(key, &serde_json::to_string(&pending)?)
+            .await?;
+        self.emit(ProfileEvent::CandidateProposed {
+            candidate: FactCandidate {
+                key: key.to_string(),
+                value: value.to_string(),
+                source: candidate.source,
+            },
+        });
+        Ok(true)
+    }
+
+    /// Candidates waiting for approval, sorted by key.
+    pub async fn candidates(&self) -> Result<Vec<FactCandidate>, KowalskiError> {
+        let mut out = Vec::new();
+        for key in self.candidates.keys().await? {
+            if let Some(candidate) = self.candidate(&key).await? {
+                out.push(candidate);
+            }
+        }
+        Ok(out)
+    }
+
+    async fn candidate(&self, key: &str) -> Result<Option<FactCandidate>, KowalskiError> {
+        let Some(raw) = self.candidates.get(key).await? else {
+            return Ok(None);
+        };
+        let pending: PendingFact = serde_json::from_str(&raw)?;
+        Ok(Some(FactCandidate {
+            key: key.to_string(),
+            value: pending.value,
+            source: pending.source,
+        }))
+    }
+
+    /// Saves the candidate for `key` as a fact.
+    pub async fn approve(&self, key: &str) -> Result<FactCandidate, KowalskiError> {
+        let key = key.trim();
+        let candidate = self.candidate(key).await?.ok_or_else(|| {
+            KowalskiError::Validation(format!("no candidate fact '{key}' to approve"))
+        })?;
+        self.remember(&candidate.key, &candidate.value).await?;
+        Ok(candidate)
+    }
+
+    /// Drops the candidate for `key`; returns whether there was one.
+    pub async fn reject(&self, key: &str) -> Result<bool, KowalskiError> {
+        let key = key.trim();
+        let existed = self.candidates.delete(key).await?;
+        if existed {
+            self.emit(ProfileEvent::CandidateRejected {
+                key: key.to_string(),
+            });
+        }
+        Ok(existed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_config(dir: &tempfile::TempDir) -> MemoryConfig {
+        MemoryConfig {
+            episodic_path: dir.path().to_string_lossy().to_string(),
+            ..MemoryConfig::default()
+        }
+    }
+
+    fn candidate(key: &str, value: &str) -> FactCandidate {
+        FactCandidate {
+            key: key.to_string(),
+            value: value.to_string(),
+            source: "m1".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn facts_persist_and_candidates_wait_for_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = memory_config(&dir);
+        {
+            let profile = ProfileStore::open(&cfg, "ada").await.unwrap();
+            profile.remember(" editor ", " helix ").await.unwrap();
+            profile.remember("name", "Ada").await.unwrap();
+            assert!(profile.propose(candidate("city", "Warsaw")).await.unwrap());
+            // Already known: nothing to confirm.
+            assert!(!profile.propose(candidate("name", "Ada")).await.unwrap());
+        }
+
+        let profile = ProfileStore::open(&cfg, "ada").await.unwrap();
+        let mut events = profile.subscribe();
+        assert_eq!(
+            profile.render().as_deref(),
+            Some("- editor: helix\n- name: Ada")
+        );
+        assert_eq!(
+            profile.candidates().await.unwrap(),
+            vec![candidate("city", "Warsaw")]
+        );
+
+        profile.approve("city").await.unwrap();
+        assert!(profile.candidates().await.unwrap().is_empty());
+        assert_eq!(
+            events.recv().await.unwrap(),
+            ProfileEvent::FactRemembered {
+                key: "city".to_string(),
+                value: "Warsaw".to_string(),
+            }
+        );
+        assert!(profile.forget("editor").await.unwrap());
+        assert!(!profile.forget("editor").await.unwrap());
+        assert_eq!(
+            profile.facts(),
+            vec![
+                ("city".to_string(), "Warsaw".to_string()),
+                ("name".to_string(), "Ada".to_string()),
+            ]
+        );
+
+        let other = ProfileStore::open(&cfg, "bob").await.unwrap();
+        assert!(other.render().is_none());
+        assert!(other.approve("city").await.is_err());
+        assert!(matches!(
+            other.remember("", "x").await,
+            Err(KowalskiError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+//! Optional second ranking pass for episodic retrieval. Cosine + recency picks the best
+//! `[memory] rerank_candidates` units; a [`Reranker`] scores each for relevance to the query and
+//! the best-scored ones are kept. [`LlmReranker`] asks the model; other scorers (e.g. a
+//! cross-encoder) implement the trait.
+
+use crate::config::Config;
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::llm::{ChatOptions, LLMProvider};
+use crate::memory::MemoryUnit;
+use async_trait::async_trait;
+use log::warn;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Characters of each candidate shown to the model.
+const CANDIDATE_CHARS: usize = 600;
+
+/// Scores retrieved memories for relevance to a query.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// One score per candidate, in order; higher is more relevant.
+    async fn score(
+        &self,
+        query: &str,
+        candidates: &[MemoryUnit],
+    ) -> Result<Vec<f32>, KowalskiError>;
+}
+
+/// The `limit` candidates `reranker` scores highest (ties keep retrieval order). When scoring
+/// fails or returns the wrong number of scores, the first `limit` candidates in retrieval order.
+pub async fn rerank(
+    reranker: &dyn Reranker,
+    query: &str,
+    mut candidates: Vec<MemoryUnit>,
+    limit: usize,
+) -> Vec<MemoryUnit> {
+    if candidates.len() < 2 {
+        candidates.truncate(limit);
+        return candidates;
+    }
+    let scores = match reranker.score(query, &candidates).await {
+        Ok(scores) if scores.len() == candidates.len() => scores,
+        Ok(scores) => {
+            warn!(
+                "Reranker returned {} scores for {} candidates; keeping retrieval order",
+                scores.len(),
+                candidates.len()
+            );
+            candidates.truncate(limit);
+            return candidates;
+        }
+        Err(e) => {
+            warn!("Reranking failed, keeping retrieval order: {e}");
+            candidates.truncate(limit);
+            return candidates;
+        }
+    };
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    order.truncate(limit);
+    let mut candidates: Vec<Option<MemoryUnit>> = candidates.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|i| candidates[i].take())
+        .collect()
+}
+
+/// Asks a chat model to rate each candidate from 0 to 10 and reply with `{"scores": [..]}`,
+/// through [`LLMProvider::chat_json`] (plain chat on backends without a JSON mode).
+pub struct LlmReranker {
+    llm: Arc<dyn LLMProvider>,
+    model: String,
+}
+
+impl LlmReranker {
+    pub fn new(llm: Arc<dyn LLMProvider>, model: impl Into<String>) -> Self {
+        Self {
+            llm,
+            model: model.into(),
+        }
+    }
+
+    /// Uses `[memory] rerank_model`, or the selected provider's chat model
+    /// ([`Config::active_model`]) when it is unset.
+    pub fn from_config(config: &Config, llm: Arc<dyn LLMProvider>) -> Self {
+        let model = config
+            .memory
+            .rerank_model
+            .clone()
+            .unwrap_or_else(|| config.active_model());
+        Self::new(llm, model)
+    }
+
+    fn prompt(query: &str, candidates: &[MemoryUnit]) -> String {
+        let mut prompt = format!(
+            "Rate how relevant each numbered memory is to the query, from 0 (unrelated) to 10 \
+             (answers it directly). Reply with a JSON object {{\"scores\": [..]}} holding exactly \
+             {} numbers, one per memory, in order.\n\nQuery: {query}\n\nMemories:\n",
+            candidates.len()
+        );
+        for (i, unit) in candidates.iter().enumerate() {
+            let content: String = unit.content.chars().take(CANDIDATE_CHARS).collect();
+            let content = content.split_whitespace().collect::<Vec<_>>().join(" ");
+            prompt.push_str(&format!("[{}] {content}\n", i + 1));
+        }
+        prompt
+    }
+}
+
+/// Scores from `{"scores": [..]}` or a bare `[..]`, possibly inside a code fence.
+fn parse_scores(reply: &str) -> Result<Vec<f32>, KowalskiError> {
+    let text = crate::utils::json::strip_markdown_code_fences(reply);
+    let value: Value = serde_json::from_str(text.trim()).map_err(|e| {
+        KowalskiError::Validation(format!("reranker reply is not JSON ({e}): {reply}"))
+    })?;
+    let scores = match &value {
+        Value::Array(_) => &value,
+        _ => &value["scores"],
+    };
+    scores
+        .as_array()
+        .and_then(|scores| {
+            scores
+                .iter()
+                .map(|s| {
+                    s.as_f64()
+                        .or_else(|| s.as_str().and_then(|s| s.trim().parse().ok()))
+                        .map(|s| s as f32)
+                })
+                .collect::<Option<Vec<f32>>>()
+        })
+        .ok_or_else(|| {
+            KowalskiError::Validation(format!("reranker reply has no list of scores: {reply}"))
+        })
+}
+
+#[async_trait]
+impl Reranker for LlmReranker {
+    async fn score(
+        &self,
+        query: &str,
+        candidates: &[MemoryUnit],
+    ) -> Result<Vec<f32>, KowalskiError> {
+        let messages = [Message {
+            role: "user".to_string(),
+            content: Self::prompt(query, candidates),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        }];
+        let reply = self
+            .llm
+            .chat_json(&self.model, &messages, &ChatOptions::default())
+            .await?;
+        parse_scores(&reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+
+    fn unit(id: &str, content: &str) -> MemoryUnit {
+        MemoryUnit {
+            id: id.to_string(),
+            timestamp: 0,
+            content: content.to_string(),
+            embedding: None,
+            metadata: Default::default(),
+        }
+    }
+
+    fn ids(units: &[MemoryUnit]) -> Vec<&str> {
+        units.iter().map(|u| u.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn llm_scores_reorder_candidates() {
+        let llm = Arc::new(
+            MockBackend::script()
+                .responds_with_text("```json\n{\"scores\": [2, 9, \"5\"]}\n```")
+                .then_text("not json")
+                .build(),
+        );
+        let reranker = LlmReranker::new(llm.clone(), "judge");
+        let candidates = vec![
+            unit("a", "The user has a cat"),
+            unit("b", "The user is allergic to peanuts"),
+            unit("c", "The user cooks on Sundays"),
+        ];
+
+        let ranked = rerank(&reranker, "food allergies", candidates.clone(), 2).await;
+        assert_eq!(ids(&ranked), ["b", "c"]);
+        let request = &llm.requests()[0];
+        assert_eq!(request.model, "judge");
+        let prompt = request.last_user_message().unwrap();
+        assert!(prompt.contains("Query: food allergies"), "{prompt}");
+        assert!(
+            prompt.contains("[2] The user is allergic to peanuts"),
+            "{prompt}"
+        );
+
+        // A reply without scores keeps the retrieval order.
+        let ranked = rerank(&reranker, "food allergies", candidates, 2).await;
+        assert_eq!(ids(&ranked), ["a", "b"]);
+    }
+
+    #[test]
+    fn parses_bare_lists_and_rejects_other_replies() {
+        assert_eq!(parse_scores("[1, 0.5]").unwrap(), [1.0, 0.5]);
+        assert!(parse_scores("{\"scores\": [\"high\"]}").is_err());
+        assert!(parse_scores("{\"answer\": 3}").is_err());
+    }
+}
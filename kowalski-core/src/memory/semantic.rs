@@ -3,24 +3,28 @@
 
 use crate::{
     error::KowalskiError,
-    memory::{MemoryProvider, MemoryQuery, MemoryUnit},
+    memory::{MemoryProvider, MemoryQuery, MemoryUnit, find_near_duplicate},
 };
 use async_trait::async_trait;
 use log::{debug, info, warn};
 use std::collections::HashMap;
 
-/// Cosine similarity in \[−1, 1\]; returns 0 if lengths differ or norms are zero.
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+/// Cosine similarity in \[−1, 1\]; returns 0 if lengths differ or norms are zero. One pass
+/// accumulates the dot product and both norms.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() || a.is_empty() {
         return 0.0;
     }
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let na: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let nb: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let (mut dot, mut na, mut nb) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        na += x * x;
+        nb += y * y;
+    }
     if na == 0.0 || nb == 0.0 {
         return 0.0;
     }
-    dot / (na * nb)
+    dot / (na.sqrt() * nb.sqrt())
 }
 
 /// Long-term memory: **in-memory** embedding index (cosine search) plus a **lightweight relation map**
@@ -34,6 +38,9 @@ pub struct SemanticStore {
     embedded_entries: Vec<MemoryUnit>,
     /// Directed edges from each subject: `subject -> [(predicate, object), ...]`.
     relations: HashMap<String, Vec<(String, String)>>,
+    /// See [`MemoryConfig::dedup_threshold`](crate::config::MemoryConfig::dedup_threshold).
+    dedup_threshold: Option<f32>,
+    dedup_window: usize,
 }
 
 impl SemanticStore {
@@ -43,8 +50,18 @@ impl SemanticStore {
         Self {
             embedded_entries: Vec::new(),
             relations: HashMap::new(),
+            dedup_threshold: None,
+            dedup_window: 0,
         }
     }
+
+    /// Skips embedded memories within `threshold` cosine similarity of one of the last `window`
+    /// entries, refreshing that entry's timestamp instead. `None` turns deduplication off.
+    pub fn with_dedup(mut self, threshold: Option<f32>, window: usize) -> Self {
+        self.dedup_threshold = threshold;
+        self.dedup_window = window;
+        self
+    }
 }
 
 impl Default for SemanticStore {
@@ -61,11 +78,27 @@ impl MemoryProvider for SemanticStore {
         if let Some(embedding) = &memory.embedding
             && !embedding.is_empty()
         {
+            if let Some(threshold) = self.dedup_threshold {
+                let start = self
+                    .embedded_entries
+                    .len()
+                    .saturating_sub(self.dedup_window);
+                let recent = &mut self.embedded_entries[start..];
+                if let Some(i) = find_near_duplicate(recent, &memory, threshold) {
+                    debug!(
+                        "{} duplicates {}; refreshing it instead",
+                        memory.id, recent[i].id
+                    );
+                    recent[i].timestamp = recent[i].timestamp.max(memory.timestamp);
+                    return Ok(());
+                }
+            }
             self.embedded_entries.push(MemoryUnit {
                 id: memory.id.clone(),
                 timestamp: memory.timestamp,
                 content: memory.content.clone(),
                 embedding: Some(embedding.clone()),
+                metadata: memory.metadata.clone(),
             });
             info!(
                 "Added memory unit {} to in-process vector index.",
@@ -127,6 +160,7 @@ impl MemoryProvider for SemanticStore {
                         content: format!("{} (similarity {:.4})", m.content, score),
                         timestamp: m.timestamp,
                         embedding: None,
+                        metadata: m.metadata.clone(),
                     },
                 ));
             }
@@ -149,6 +183,7 @@ impl MemoryProvider for SemanticStore {
                     ),
                     timestamp: 0,
                     embedding: None,
+                    metadata: Default::default(),
                 });
             }
         }
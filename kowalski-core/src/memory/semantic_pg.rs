@@ -8,10 +8,20 @@ use log::{debug, info, warn};
 use pgvector::Vector;
 use sqlx::Row;
 use sqlx::postgres::PgPool;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+/// Reads the `metadata` column (a JSON object, see `006_semantic_metadata.sql`).
+fn row_metadata(row: &sqlx::postgres::PgRow) -> Result<BTreeMap<String, String>, KowalskiError> {
+    let text: String = row
+        .try_get("metadata")
+        .map_err(|e| KowalskiError::Memory(format!("semantic row decode: {e}")))?;
+    serde_json::from_str(&text)
+        .map_err(|e| KowalskiError::Memory(format!("semantic row metadata: {e}")))
+}
+
 /// Semantic store using **`semantic_memory`** and **`semantic_relation`** tables (see `migrations/postgres/003_semantic_memory.sql`).
+/// A unit's `metadata` is kept as JSON in `semantic_memory.metadata`.
 ///
 /// [`MemoryProvider::retrieve`] embeds the query via [`LLMProvider::embed`] and runs **cosine-distance** ordering (`<=>`).
 pub struct PostgresSemanticStore {
@@ -100,16 +110,18 @@ impl MemoryProvider for PostgresSemanticStore {
             self.expect_embedding_vec(emb, "semantic add")?;
             let v = Vector::from(emb.to_vec());
             sqlx::query(
-                r#"INSERT INTO semantic_memory (id, content_text, embedding)
-                       VALUES ($1, $2, $3)
+                r#"INSERT INTO semantic_memory (id, content_text, embedding, metadata)
+                       VALUES ($1, $2, $3, $4)
                        ON CONFLICT (id) DO UPDATE SET
                          content_text = EXCLUDED.content_text,
                          embedding = EXCLUDED.embedding,
+                         metadata = EXCLUDED.metadata,
                          created_at = NOW()"#,
             )
             .bind(&memory.id)
             .bind(&memory.content)
             .bind(v)
+            .bind(serde_json::to_string(&memory.metadata)?)
             .execute(&self.pool)
             .await
             .map_err(|e| KowalskiError::Memory(format!("semantic_memory insert: {e}")))?;
@@ -135,7 +147,7 @@ impl MemoryProvider for PostgresSemanticStore {
             Ok(query_emb) if query_emb.len() == self.embedding_dims => {
                 let v = Vector::from(query_emb);
                 let rows = sqlx::query(
-                    r#"SELECT id, content_text,
+                    r#"SELECT id, content_text, metadata,
                               EXTRACT(EPOCH FROM created_at)::bigint AS ts,
                               (embedding <=> $1) AS dist
                        FROM semantic_memory
@@ -168,6 +180,7 @@ impl MemoryProvider for PostgresSemanticStore {
                         content: format!("{} (similarity {:.4})", content_text, score),
                         timestamp: ts.max(0) as u64,
                         embedding: None,
+                        metadata: row_metadata(&row)?,
                     });
                 }
                 if !out.is_empty() {
@@ -191,7 +204,7 @@ impl MemoryProvider for PostgresSemanticStore {
 
         let pattern = format!("%{q}%");
         let rows = sqlx::query(
-            r#"SELECT id, content_text, EXTRACT(EPOCH FROM created_at)::bigint AS ts
+            r#"SELECT id, content_text, metadata, EXTRACT(EPOCH FROM created_at)::bigint AS ts
                FROM semantic_memory
                WHERE id ILIKE $1 OR content_text ILIKE $1
                ORDER BY created_at DESC
@@ -219,6 +232,7 @@ impl MemoryProvider for PostgresSemanticStore {
                 content: content_text,
                 timestamp: ts.max(0) as u64,
                 embedding: None,
+                metadata: row_metadata(&row)?,
             });
         }
         Ok(out)
@@ -232,7 +246,7 @@ impl MemoryProvider for PostgresSemanticStore {
             if vector.len() == self.embedding_dims {
                 let v = Vector::from(vector);
                 let rows = sqlx::query(
-                    r#"SELECT id, content_text,
+                    r#"SELECT id, content_text, metadata,
                               EXTRACT(EPOCH FROM created_at)::bigint AS ts,
                               (embedding <=> $1) AS dist
                        FROM semantic_memory
@@ -264,6 +278,7 @@ impl MemoryProvider for PostgresSemanticStore {
                         content: format!("{} (similarity {:.4})", content_text, score),
                         timestamp: ts.max(0) as u64,
                         embedding: None,
+                        metadata: row_metadata(&row)?,
                     });
                 }
             } else {
@@ -301,6 +316,7 @@ impl MemoryProvider for PostgresSemanticStore {
                 ),
                 timestamp: 0,
                 embedding: None,
+                metadata: Default::default(),
             });
         }
 
@@ -65,6 +65,7 @@ async fn test_memory_isolation() {
         timestamp: 1000,
         content: "Secret 1 for Agent 1".to_string(),
         embedding: None,
+        metadata: Default::default(),
     };
     agent1
         .working_memory
@@ -104,6 +105,7 @@ async fn test_memory_isolation() {
         timestamp: 1001,
         content: "Secret 2 for Agent 2".to_string(),
         embedding: None,
+        metadata: Default::default(),
     };
     agent2
         .working_memory
@@ -148,6 +150,7 @@ async fn test_memory_isolation() {
             timestamp: 2000,
             content: "Episodic 1".to_string(),
             embedding: None,
+            metadata: Default::default(),
         })
         .await
         .unwrap();
@@ -160,6 +163,7 @@ async fn test_memory_isolation() {
             timestamp: 2000,
             content: "Episodic 2".to_string(),
             embedding: None,
+            metadata: Default::default(),
         })
         .await
         .unwrap();
@@ -184,3 +188,78 @@ async fn test_memory_isolation() {
         "Episodic Memory Isolation: Setup successful (Validation skipped due to external dependency)"
     );
 }
+
+#[tokio::test]
+async fn add_batch_reports_each_unit() {
+    use crate::memory::MemoryProvider;
+    use crate::memory::working::WorkingMemory;
+    use crate::progress::Progress;
+    use std::sync::Mutex;
+
+    let units: Vec<MemoryUnit> = (1..=3)
+        .map(|i| MemoryUnit {
+            id: format!("m{i}"),
+            timestamp: i,
+            content: "note".to_string(),
+            embedding: None,
+            metadata: Default::default(),
+        })
+        .collect();
+    let updates = Mutex::new(Vec::new());
+    let reporter = |p: &Progress| updates.lock().unwrap().push(p.clone());
+    let mut memory = WorkingMemory::new(10);
+    memory
+        .add_batch_with_progress(units, &reporter)
+        .await
+        .unwrap();
+
+    let updates = updates.into_inner().unwrap();
+    assert_eq!(updates.len(), 3);
+    assert_eq!(
+        updates[2],
+        Progress {
+            done: 3,
+            total: Some(3),
+            bytes: 12,
+            current: Some("m3".to_string()),
+        }
+    );
+}
+
+#[tokio::test]
+async fn pinned_units_survive_eviction() {
+    use crate::memory::MemoryProvider;
+    use crate::memory::working::WorkingMemory;
+
+    let unit = |id: &str, content: &str| MemoryUnit {
+        id: id.to_string(),
+        timestamp: 0,
+        content: content.to_string(),
+        embedding: None,
+        metadata: Default::default(),
+    };
+    let mut memory = WorkingMemory::new(3);
+    memory
+        .pin(unit("deadline", "The release is on Friday"))
+        .await
+        .unwrap();
+    for i in 0..50 {
+        memory
+            .add(unit(&format!("m{i}"), &format!("turn {i}")))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(memory.len(), 3);
+    let recent = memory.retrieve("turn", 10).await.unwrap();
+    assert_eq!(recent[0].id, "m47");
+    assert_eq!(memory.pinned()[0].content, "The release is on Friday");
+    memory
+        .pin(unit("deadline", "The release is on Monday"))
+        .await
+        .unwrap();
+    assert_eq!(memory.pinned().len(), 1);
+    assert_eq!(memory.pinned()[0].content, "The release is on Monday");
+    assert!(memory.unpin("deadline").await.unwrap());
+    assert!(memory.pinned().is_empty());
+}
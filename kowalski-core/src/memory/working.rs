@@ -12,9 +12,11 @@ use log::{debug, info};
 ///
 /// It holds a collection of `MemoryUnit`s up to a defined capacity.
 /// When the capacity is exceeded, the oldest memory unit is discarded.
+/// Pinned units are kept apart and never discarded; every chat request carries them.
 pub struct WorkingMemory {
     store: Vec<MemoryUnit>,
     capacity: usize,
+    pinned: Vec<MemoryUnit>,
 }
 
 impl WorkingMemory {
@@ -28,10 +30,11 @@ impl WorkingMemory {
         Self {
             store: Vec::with_capacity(capacity),
             capacity,
+            pinned: Vec::new(),
         }
     }
 
-    /// Returns the current number of units in memory.
+    /// Returns the current number of units in memory (pinned units not included).
     pub fn len(&self) -> usize {
         self.store.len()
     }
@@ -97,4 +100,27 @@ impl MemoryProvider for WorkingMemory {
         // For working memory, a simple text search is usually sufficient.
         self.retrieve(&query.text_query, query.top_k).await
     }
+
+    async fn pin(&mut self, memory: MemoryUnit) -> Result<(), KowalskiError> {
+        debug!("Pinning memory unit: {}", memory.id);
+        match self.pinned.iter_mut().find(|unit| unit.id == memory.id) {
+            Some(unit) => *unit = memory,
+            None => self.pinned.push(memory),
+        }
+        Ok(())
+    }
+
+    async fn unpin(&mut self, id: &str) -> Result<bool, KowalskiError> {
+        let before = self.pinned.len();
+        self.pinned.retain(|unit| unit.id != id);
+        Ok(self.pinned.len() < before)
+    }
+
+    fn pinned(&self) -> Vec<MemoryUnit> {
+        self.pinned.clone()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
 }
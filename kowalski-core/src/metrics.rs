@@ -0,0 +1,113 @@
+//! Operational metrics through the [`metrics`](https://docs.rs/metrics) facade (feature
+//! **`metrics`**). Agents record them wherever a recorder is installed; [`prometheus_handle`]
+//! installs a Prometheus one. Without the feature the `record_*` calls compile to nothing.
+//!
+//! | Metric | Kind | Labels |
+//! |--------|------|--------|
+//! | `kowalski_llm_requests_total` | counter | |
+//! | `kowalski_llm_latency_seconds` | histogram | |
+//! | `kowalski_llm_queue_wait_seconds` | histogram | |
+//! | `kowalski_llm_cache_requests_total` | counter | `result` (`hit`, `miss`) |
+//! | `kowalski_tokens_total` | counter | `kind` (`prompt`, `completion`) |
+//! | `kowalski_tool_executions_total` | counter | `tool`, `status` (`ok`, `denied`, `error`) |
+//! | `kowalski_tool_duration_seconds` | histogram | `tool` |
+//! | `kowalski_memory_recall_duration_seconds` | histogram | `tier` (`working`, `episodic`, `semantic`) |
+//! | `kowalski_embedding_requests_total` | counter | |
+
+#![cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+
+use std::time::Duration;
+
+pub const LLM_REQUESTS_TOTAL: &str = "kowalski_llm_requests_total";
+pub const LLM_LATENCY_SECONDS: &str = "kowalski_llm_latency_seconds";
+pub const LLM_QUEUE_WAIT_SECONDS: &str = "kowalski_llm_queue_wait_seconds";
+pub const LLM_CACHE_REQUESTS_TOTAL: &str = "kowalski_llm_cache_requests_total";
+pub const TOKENS_TOTAL: &str = "kowalski_tokens_total";
+pub const TOOL_EXECUTIONS_TOTAL: &str = "kowalski_tool_executions_total";
+pub const TOOL_DURATION_SECONDS: &str = "kowalski_tool_duration_seconds";
+pub const MEMORY_RECALL_DURATION_SECONDS: &str = "kowalski_memory_recall_duration_seconds";
+pub const EMBEDDING_REQUESTS_TOTAL: &str = "kowalski_embedding_requests_total";
+
+#[cfg(feature = "metrics")]
+pub use metrics_exporter_prometheus::PrometheusHandle;
+
+/// Histogram buckets in seconds, from a fast tool call to a slow generation.
+#[cfg(feature = "metrics")]
+const BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+/// The Prometheus recorder's handle (`handle.render()` is the text for a `/metrics` response).
+/// The first call installs the recorder globally; later calls return the same handle. Fails when
+/// another `metrics` recorder is already installed.
+#[cfg(feature = "metrics")]
+pub fn prometheus_handle() -> Result<PrometheusHandle, crate::error::KowalskiError> {
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use std::sync::Mutex;
+
+    static HANDLE: Mutex<Option<PrometheusHandle>> = Mutex::new(None);
+    let mut handle = HANDLE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(handle) = handle.as_ref() {
+        return Ok(handle.clone());
+    }
+    let init_error = |e: metrics_exporter_prometheus::BuildError| {
+        crate::error::KowalskiError::Initialization(format!("metrics recorder: {e}"))
+    };
+    let installed = PrometheusBuilder::new()
+        .set_buckets(BUCKETS)
+        .map_err(init_error)?
+        .install_recorder()
+        .map_err(init_error)?;
+    Ok(handle.insert(installed).clone())
+}
+
+/// One chat call to the LLM, successful or not.
+pub(crate) fn record_llm_request(latency: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::counter!(LLM_REQUESTS_TOTAL).increment(1);
+        ::metrics::histogram!(LLM_LATENCY_SECONDS).record(latency.as_secs_f64());
+    }
+}
+
+/// Time a request waited for a [`RequestGovernor`](crate::llm::RequestGovernor) permit.
+pub(crate) fn record_queue_wait(wait: Duration) {
+    #[cfg(feature = "metrics")]
+    ::metrics::histogram!(LLM_QUEUE_WAIT_SECONDS).record(wait.as_secs_f64());
+}
+
+/// One [`LlmCache`](crate::llm::LlmCache) lookup.
+pub(crate) fn record_llm_cache(hit: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let result = if hit { "hit" } else { "miss" };
+        ::metrics::counter!(LLM_CACHE_REQUESTS_TOTAL, "result" => result).increment(1);
+    }
+}
+
+/// Tokens a backend reported; `kind` is `prompt` or `completion`.
+pub(crate) fn record_tokens(kind: &'static str, count: u64) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(TOKENS_TOTAL, "kind" => kind).increment(count);
+}
+
+pub(crate) fn record_tool_execution(tool: &str, status: &'static str, duration: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::counter!(TOOL_EXECUTIONS_TOTAL, "tool" => tool.to_string(), "status" => status)
+            .increment(1);
+        ::metrics::histogram!(TOOL_DURATION_SECONDS, "tool" => tool.to_string())
+            .record(duration.as_secs_f64());
+    }
+}
+
+pub(crate) fn record_memory_recall(tier: &'static str, duration: Duration) {
+    #[cfg(feature = "metrics")]
+    ::metrics::histogram!(MEMORY_RECALL_DURATION_SECONDS, "tier" => tier)
+        .record(duration.as_secs_f64());
+}
+
+pub(crate) fn record_embedding_request() {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(EMBEDDING_REQUESTS_TOTAL).increment(1);
+}
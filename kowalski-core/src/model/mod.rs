@@ -1,6 +1,10 @@
+use crate::config::Config;
 use crate::error::KowalskiError;
+use crate::llm::{RatePermit, RequestGovernor};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModelInfo {
@@ -27,26 +31,78 @@ pub struct PullResponse {
 pub struct ModelManager {
     client: Client,
     base_url: String,
+    governor: Option<Arc<RequestGovernor>>,
+    cancel: Option<CancellationToken>,
 }
 
 impl ModelManager {
-    /// Creates a new model manager with the specified base URL.
+    /// Creates a new model manager with the specified base URL, on the
+    /// [`shared_http_client`](crate::llm::shared_http_client).
     pub fn new(base_url: String) -> Result<Self, KowalskiError> {
-        let client = reqwest::ClientBuilder::new()
-            .pool_max_idle_per_host(0)
-            .build()
-            .map_err(KowalskiError::Request)?;
+        Ok(Self {
+            client: crate::llm::shared_http_client(),
+            base_url,
+            governor: None,
+            cancel: None,
+        })
+    }
+
+    /// A model manager for the configured Ollama endpoint. It shares the endpoint's
+    /// [`request_governor`](crate::llm::request_governor) with the agents, and stops waiting for
+    /// a permit on [`shutdown_token`](crate::llm::shutdown_token).
+    pub fn from_config(config: &Config) -> Result<Self, KowalskiError> {
+        let mut manager = Self::new(crate::llm::ollama_endpoint(config))?
+            .with_cancellation(crate::llm::shutdown_token());
+        manager.governor = crate::llm::ollama_governor(config);
+        Ok(manager)
+    }
+
+    /// Sends requests with `client` instead, e.g. one with a proxy or custom timeouts.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
 
-        Ok(Self { client, base_url })
+    /// Counts model operations against `governor`, e.g. the endpoint's shared one from
+    /// [`crate::llm::request_governor`], so a pull does not run alongside a full chat budget.
+    pub fn with_governor(mut self, governor: Arc<RequestGovernor>) -> Self {
+        self.governor = Some(governor);
+        self
+    }
+
+    /// Operations still queued for a permit fail with [`KowalskiError::Cancelled`] once `cancel`
+    /// fires.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    async fn permit(&self) -> Result<Option<RatePermit>, KowalskiError> {
+        let Some(governor) = &self.governor else {
+            return Ok(None);
+        };
+        match &self.cancel {
+            Some(cancel) => Ok(Some(governor.acquire_cancellable(cancel).await?)),
+            None => Ok(Some(governor.acquire().await?)),
+        }
+    }
+
+    fn unreachable(&self, source: reqwest::Error) -> KowalskiError {
+        KowalskiError::OllamaUnreachable {
+            endpoint: self.base_url.clone(),
+            source,
+        }
     }
 
     /// Lists available models
     pub async fn list_models(&self) -> Result<ModelsResponse, KowalskiError> {
+        let _permit = self.permit().await?;
         let response = self
             .client
             .get(format!("{}/api/tags", self.base_url))
             .send()
-            .await?;
+            .await
+            .map_err(|e| self.unreachable(e))?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -65,6 +121,7 @@ impl ModelManager {
 
     /// Pulls a model from the server
     pub async fn pull_model(&self, model_name: &str) -> Result<PullResponse, KowalskiError> {
+        let _permit = self.permit().await?;
         let response = self
             .client
             .post(format!("{}/api/pull", self.base_url))
@@ -72,7 +129,8 @@ impl ModelManager {
                 "name": model_name
             }))
             .send()
-            .await?;
+            .await
+            .map_err(|e| self.unreachable(e))?;
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -83,3 +141,33 @@ impl ModelManager {
         Ok(pull_response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_config_shares_the_ollama_governor_whichever_provider_is_selected() {
+        let mut config = Config::default();
+        config.ollama.port = 39_871;
+        config.ollama.limits.max_concurrent = Some(2);
+        config.llm.provider = "openai".to_string();
+
+        let manager = ModelManager::from_config(&config).unwrap();
+        let governor = manager.governor.as_ref().expect("governor");
+        assert!(Arc::ptr_eq(
+            governor,
+            &crate::llm::ollama_governor(&config).unwrap()
+        ));
+        assert_eq!(manager.base_url, "http://localhost:39871");
+        assert!(manager.cancel.is_some());
+
+        config.ollama.limits.max_concurrent = None;
+        assert!(
+            ModelManager::from_config(&config)
+                .unwrap()
+                .governor
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! Progress of long operations (a crawl, a batch of memories, a paper analysis) for callers that
+//! show it: pass a [`ProgressReporter`] to the `*_with_progress` variant of the operation.
+
+/// Where an operation is. `done` counts finished items; `total` is `None` while unknown (e.g. a
+/// crawl still discovering pages).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: Option<usize>,
+    /// Bytes read or processed so far.
+    pub bytes: u64,
+    /// The item being worked on, or the one just finished.
+    pub current: Option<String>,
+}
+
+/// Receives [`Progress`] updates, at least one per item. Any `Fn(&Progress)` closure is one.
+pub trait ProgressReporter: Send + Sync {
+    fn report(&self, progress: &Progress);
+}
+
+impl<F: Fn(&Progress) + Send + Sync> ProgressReporter for F {
+    fn report(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+/// Ignores updates; what the plain (non-`_with_progress`) variants use.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn report(&self, _progress: &Progress) {}
+}
@@ -0,0 +1,354 @@
+//! Prompt text the agent sends on its own behalf (system prompt, tool instructions, tool results,
+//! memory context, consolidation), kept as `{{variable}}` templates so it can be localized or
+//! tuned from `[prompts]` without forking.
+
+use crate::config::PromptsConfig;
+use crate::error::KowalskiError;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The prompts an agent renders at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PromptKind {
+    /// System prompt for a conversation when none was configured.
+    System,
+    /// How to call tools; appended to the system prompt of builder-made agents.
+    ToolUse,
+    /// The next user turn after a tool ran.
+    ToolResult,
+    /// Ephemeral system message carrying retrieved memories into one request.
+    MemoryContext,
+    /// System message carrying the profile facts into each new conversation.
+    ProfileFacts,
+    /// System message carrying the pinned working memories into every request.
+    PinnedMemory,
+    /// Consolidation: summary of one episodic memory.
+    ConsolidationSummary,
+    /// Consolidation: subject/predicate/object graph of one episodic memory.
+    ConsolidationGraph,
+    /// Consolidation: candidate profile facts in one episodic memory, as a JSON object.
+    ConsolidationFacts,
+    /// Summary of a closed conversation, stored as a memory.
+    SessionSummary,
+}
+
+impl PromptKind {
+    pub const ALL: [Self; 10] = [
+        Self::System,
+        Self::ToolUse,
+        Self::ToolResult,
+        Self::MemoryContext,
+        Self::ProfileFacts,
+        Self::PinnedMemory,
+        Self::ConsolidationSummary,
+        Self::ConsolidationGraph,
+        Self::ConsolidationFacts,
+        Self::SessionSummary,
+    ];
+
+    /// Key under `[prompts]` and file stem (`<name>.txt`) in the templates directory.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::ToolUse => "tool_use",
+            Self::ToolResult => "tool_result",
+            Self::MemoryContext => "memory_context",
+            Self::ProfileFacts => "profile_facts",
+            Self::PinnedMemory => "pinned_memory",
+            Self::ConsolidationSummary => "consolidation_summary",
+            Self::ConsolidationGraph => "consolidation_graph",
+            Self::ConsolidationFacts => "consolidation_facts",
+            Self::SessionSummary => "session_summary",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Variables the template may use.
+    pub fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            Self::System => &["agent_name", "date", "tools"],
+            Self::ToolUse => &["tools"],
+            Self::ToolResult => &["tool", "result"],
+            Self::MemoryContext | Self::PinnedMemory => &["memories"],
+            Self::ProfileFacts => &["facts"],
+            Self::SessionSummary => &["transcript"],
+            Self::ConsolidationSummary | Self::ConsolidationGraph | Self::ConsolidationFacts => {
+                &["text"]
+            }
+        }
+    }
+
+    pub fn builtin(self) -> &'static str {
+        match self {
+            Self::System => "You are a helpful assistant.",
+            Self::ToolUse => {
+                "You can use these tools:\n{{tools}}\n\nTo use a tool, reply with only a JSON object: {\"name\": \"<tool>\", \"parameters\": {...}}. The result comes back in the next message; then answer the user in plain text. Answer directly when no tool is needed."
+            }
+            Self::ToolResult => "Based on the tool result: {{result}}",
+            Self::MemoryContext => {
+                "Retrieved memory context (use only if relevant to the latest user request):\n{{memories}}"
+            }
+            Self::ProfileFacts => "Known facts about the user:\n{{facts}}",
+            Self::PinnedMemory => "Pinned memory (kept for the whole conversation):\n{{memories}}",
+            Self::ConsolidationSummary => "Summarize the following text:\n\n{{text}}",
+            Self::ConsolidationGraph => {
+                "Create a graph representation of the following text in the format { \"subject\": \"...\", \"predicate\": \"...\", \"object\": \"...\" }:\n\n{{text}}"
+            }
+            Self::ConsolidationFacts => {
+                "List lasting facts the user states about themselves in the following text (name, preferences, tools, location) as one flat JSON object of short snake_case keys to short values, e.g. {\"name\": \"Ada\", \"editor\": \"helix\"}. Reply {} when there are none.\n\n{{text}}"
+            }
+            Self::SessionSummary => {
+                "Summarize this conversation in a few short bullet points: the topics discussed, the decisions made and the questions left open. Reply with the bullet points only.\n\n{{transcript}}"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Text(String),
+    Variable(String),
+}
+
+/// Text with `{{variable}}` placeholders. Single braces are plain text, so JSON examples need no
+/// escaping.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl PromptTemplate {
+    /// Parses `source`; `{{name}}` must be one of `allowed` (any name when `allowed` is empty)
+    /// and every `{{` must be closed.
+    pub fn parse(source: &str, allowed: &[&str]) -> Result<Self, KowalskiError> {
+        let mut segments = Vec::new();
+        let mut rest = source;
+        while let Some(open) = rest.find("{{") {
+            if open > 0 {
+                segments.push(Segment::Text(rest[..open].to_string()));
+            }
+            let after = &rest[open + 2..];
+            let close = after.find("}}").ok_or_else(|| {
+                KowalskiError::Configuration(format!(
+                    "unclosed '{{{{' in prompt template: {source}"
+                ))
+            })?;
+            let name = after[..close].trim();
+            if !allowed.is_empty() && !allowed.contains(&name) {
+                return Err(KowalskiError::Configuration(format!(
+                    "unknown placeholder '{{{{{name}}}}}' (expected one of: {})",
+                    allowed.join(", ")
+                )));
+            }
+            segments.push(Segment::Variable(name.to_string()));
+            rest = &after[close + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Fills in `vars`; a placeholder without a value renders empty.
+    pub fn render(&self, vars: &[(&str, &str)]) -> String {
+        let mut out = String::with_capacity(self.source.len());
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Variable(name) => {
+                    if let Some((_, value)) = vars.iter().find(|(key, _)| key == name) {
+                        out.push_str(value);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Every [`PromptKind`]'s template: the built-in text unless `[prompts]` overrides it.
+#[derive(Debug, Clone)]
+pub struct PromptRegistry {
+    templates: HashMap<PromptKind, PromptTemplate>,
+}
+
+impl Default for PromptRegistry {
+    fn default() -> Self {
+        let templates = PromptKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let template = PromptTemplate::parse(kind.builtin(), kind.placeholders())
+                    .unwrap_or_else(|e| unreachable!("built-in {} prompt: {e}", kind.name()));
+                (kind, template)
+            })
+            .collect();
+        Self { templates }
+    }
+}
+
+impl PromptRegistry {
+    /// Built-ins, then `<name>.txt` files from `prompts.dir`, then inline `[prompts]` entries.
+    /// Unknown template names and placeholders are errors.
+    pub fn from_config(config: &PromptsConfig) -> Result<Self, KowalskiError> {
+        let mut registry = Self::default();
+        if let Some(dir) = &config.dir {
+            registry.load_dir(Path::new(dir))?;
+        }
+        for (name, source) in &config.templates {
+            let kind = PromptKind::from_name(name).ok_or_else(|| unknown_prompt(name))?;
+            registry.set(kind, source)?;
+        }
+        Ok(registry)
+    }
+
+    /// Replaces templates from `<name>.txt` files in `dir`; other files are ignored.
+    pub fn load_dir(&mut self, dir: &Path) -> Result<(), KowalskiError> {
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            KowalskiError::Configuration(format!("prompts dir {}: {e}", dir.display()))
+        })?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("txt") {
+                continue;
+            }
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let kind = PromptKind::from_name(stem).ok_or_else(|| unknown_prompt(stem))?;
+            let source = std::fs::read_to_string(&path)?;
+            self.set(kind, source.trim_end_matches('\n'))
+                .map_err(|e| in_context(path.display(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Replaces one template after checking its placeholders.
+    pub fn set(&mut self, kind: PromptKind, source: &str) -> Result<(), KowalskiError> {
+        let template = PromptTemplate::parse(source, kind.placeholders())
+            .map_err(|e| in_context(format!("prompts.{}", kind.name()), e))?;
+        self.templates.insert(kind, template);
+        Ok(())
+    }
+
+    pub fn get(&self, kind: PromptKind) -> &PromptTemplate {
+        &self.templates[&kind]
+    }
+
+    pub fn render(&self, kind: PromptKind, vars: &[(&str, &str)]) -> String {
+        self.get(kind).render(vars)
+    }
+}
+
+/// Prefixes a configuration error with where it came from.
+fn in_context(place: impl std::fmt::Display, error: KowalskiError) -> KowalskiError {
+    match error {
+        KowalskiError::Configuration(message) => {
+            KowalskiError::Configuration(format!("{place}: {message}"))
+        }
+        other => other,
+    }
+}
+
+fn unknown_prompt(name: &str) -> KowalskiError {
+    let known: Vec<&str> = PromptKind::ALL.iter().map(|kind| kind.name()).collect();
+    KowalskiError::Configuration(format!(
+        "unknown prompt template '{name}' (expected one of: {})",
+        known.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_render_with_sample_data() {
+        let prompts = PromptRegistry::default();
+        let samples: [(&str, &str); 9] = [
+            ("agent_name", "kowalski"),
+            ("date", "2026-03-09"),
+            ("tools", "- calculator: does sums"),
+            ("tool", "calculator"),
+            ("result", "{\"result\":4}"),
+            ("memories", "likes tea"),
+            ("facts", "- name: Ada"),
+            ("text", "we met on Monday"),
+            ("transcript", "user: hi"),
+        ];
+        for kind in PromptKind::ALL {
+            let rendered = prompts.render(kind, &samples);
+            assert!(!rendered.contains("{{"), "{}: {rendered}", kind.name());
+        }
+        assert_eq!(
+            prompts.render(PromptKind::ToolResult, &samples),
+            "Based on the tool result: {\"result\":4}"
+        );
+        assert!(prompts.render(PromptKind::ToolUse, &samples).contains(
+            "- calculator: does sums\n\nTo use a tool, reply with only a JSON object: {\"name\""
+        ));
+        assert!(
+            prompts
+                .render(PromptKind::ConsolidationSummary, &samples)
+                .ends_with("\n\nwe met on Monday")
+        );
+    }
+
+    #[test]
+    fn config_and_directory_overrides_take_effect() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("tool_result.txt"),
+            "Résultat de {{ tool }} : {{result}}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.md"), "ignored").unwrap();
+        let config = PromptsConfig {
+            dir: Some(dir.path().display().to_string()),
+            templates: [("system".to_string(), "Tu es {{agent_name}}.".to_string())].into(),
+        };
+        let prompts = PromptRegistry::from_config(&config).unwrap();
+        assert_eq!(
+            prompts.render(
+                PromptKind::ToolResult,
+                &[("tool", "calculator"), ("result", "4")]
+            ),
+            "Résultat de calculator : 4"
+        );
+        assert_eq!(
+            prompts.render(PromptKind::System, &[("agent_name", "Kowalski")]),
+            "Tu es Kowalski."
+        );
+        assert_eq!(
+            prompts.get(PromptKind::MemoryContext).as_str(),
+            PromptKind::MemoryContext.builtin()
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_and_names_fail_fast() {
+        let bad = |name: &str, source: &str| {
+            PromptRegistry::from_config(&PromptsConfig {
+                dir: None,
+                templates: [(name.to_string(), source.to_string())].into(),
+            })
+            .unwrap_err()
+            .to_string()
+        };
+        let err = bad("tool_result", "Result: {{output}}");
+        assert!(err.contains("prompts.tool_result"), "{err}");
+        assert!(err.contains("unknown placeholder '{{output}}'"), "{err}");
+        assert!(bad("tool_result", "Result: {{result").contains("unclosed"));
+        assert!(bad("greeting", "hi").contains("unknown prompt template 'greeting'"));
+    }
+}
@@ -0,0 +1,323 @@
+//! Built-in roles and fragments, plus the roles a config defines under `[roles]`.
+
+use super::{Audience, Preset, Role, Style};
+use crate::config::Config;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const ROLES: &[(&str, &str, &str)] = &[
+    (
+        "engineer",
+        "a senior software engineer",
+        "Answer precisely and concisely, show code when it helps and point out trade-offs.",
+    ),
+    (
+        "teacher",
+        "a patient teacher",
+        "Explain step by step, check understanding and use examples before jargon.",
+    ),
+    (
+        "analyst",
+        "a data analyst",
+        "Ground every claim in the data given, state assumptions and quantify uncertainty.",
+    ),
+    (
+        "writer",
+        "a technical writer",
+        "Write clear, well-structured prose with headings and no filler.",
+    ),
+    (
+        "reviewer",
+        "a critical reviewer",
+        "Find weaknesses, missing evidence and risks; be specific and constructive.",
+    ),
+    (
+        "translator",
+        "a professional translator",
+        "Translate faithfully, keep the tone and formatting, and add nothing.",
+    ),
+];
+
+const AUDIENCES: &[(&str, &str, &str)] = &[
+    (
+        "executive",
+        "executives",
+        "Lead with the decision and its impact; keep details to what they need to act.",
+    ),
+    (
+        "engineer",
+        "engineers",
+        "They know the field; technical terms and code are welcome.",
+    ),
+    (
+        "child",
+        "a ten-year-old",
+        "Use short words, everyday comparisons and no jargon.",
+    ),
+    (
+        "general",
+        "a general audience",
+        "Assume no specialist knowledge and define terms on first use.",
+    ),
+    (
+        "scientist",
+        "scientists",
+        "They expect precise terminology, citations and stated limitations.",
+    ),
+];
+
+const PRESETS: &[(&str, &str, &str)] = &[
+    (
+        "eli5",
+        "explain like I'm five",
+        "Explain the idea as simply as possible, with one concrete analogy.",
+    ),
+    (
+        "beginner",
+        "beginner",
+        "No prior experience; introduce each concept before using it.",
+    ),
+    (
+        "expert",
+        "expert",
+        "Skip the basics and go straight to the details that matter.",
+    ),
+];
+
+const STYLES: &[(&str, &str, &str)] = &[
+    (
+        "concise",
+        "concise",
+        "Short sentences, no preamble, no repetition.",
+    ),
+    (
+        "formal_report",
+        "formal report",
+        "Formal tone with a summary, numbered sections and a conclusion.",
+    ),
+    (
+        "friendly",
+        "friendly",
+        "Warm and conversational, but still to the point.",
+    ),
+    (
+        "bullet_points",
+        "bullet points",
+        "Answer as a bulleted list; one idea per bullet.",
+    ),
+];
+
+/// `(key, name, description)` of the entry named `key`.
+fn lookup(
+    table: &'static [(&'static str, &'static str, &'static str)],
+    key: &str,
+) -> Option<(&'static str, &'static str)> {
+    table
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|&(_, name, description)| (name, description))
+}
+
+fn keys(table: &[(&'static str, &str, &str)]) -> Vec<&'static str> {
+    table.iter().map(|&(key, _, _)| key).collect()
+}
+
+impl Role {
+    /// A built-in role by key (see [`Role::preset_names`]), e.g. `Role::preset("engineer")`.
+    pub fn preset(key: &str) -> Option<Self> {
+        lookup(ROLES, key).map(|(name, description)| Self::new(name, description))
+    }
+
+    pub fn preset_names() -> Vec<&'static str> {
+        keys(ROLES)
+    }
+
+    /// A built-in translator into `language`.
+    pub fn translator(language: &str) -> Self {
+        let (name, description) = lookup(ROLES, "translator").unwrap_or_default();
+        Self::new(name, &format!("{description} Translate into {language}."))
+    }
+}
+
+impl Audience {
+    /// A built-in audience by key, e.g. `Audience::preset("executive")`.
+    pub fn preset(key: &str) -> Option<Self> {
+        lookup(AUDIENCES, key).map(|(name, description)| Self::new(name, description))
+    }
+
+    pub fn preset_names() -> Vec<&'static str> {
+        keys(AUDIENCES)
+    }
+}
+
+impl Preset {
+    /// A built-in preset by key, e.g. `Preset::preset("eli5")`.
+    #[allow(clippy::self_named_constructors)] // same name as the other fragments' lookups
+    pub fn preset(key: &str) -> Option<Self> {
+        lookup(PRESETS, key).map(|(name, description)| Self::new(name, description))
+    }
+
+    pub fn preset_names() -> Vec<&'static str> {
+        keys(PRESETS)
+    }
+}
+
+impl Style {
+    /// A built-in style by key, e.g. `Style::preset("formal_report")`.
+    pub fn preset(key: &str) -> Option<Self> {
+        lookup(STYLES, key).map(|(name, description)| Self::new(name, description))
+    }
+
+    pub fn preset_names() -> Vec<&'static str> {
+        keys(STYLES)
+    }
+}
+
+/// One role in a [`RoleCatalog`], as listed for UIs.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleEntry {
+    /// The key to select it by, e.g. `chat --role engineer`
+    pub key: String,
+    /// `builtin` or `config`
+    pub source: &'static str,
+    pub role: Role,
+}
+
+/// Roles selectable by key: the built-ins, then `[roles]` from a config (which replace
+/// built-ins of the same key).
+#[derive(Debug, Clone, Default)]
+pub struct RoleCatalog {
+    roles: BTreeMap<String, (Role, &'static str)>,
+}
+
+impl RoleCatalog {
+    pub fn builtin() -> Self {
+        let roles = ROLES
+            .iter()
+            .map(|&(key, name, description)| {
+                (key.to_string(), (Role::new(name, description), "builtin"))
+            })
+            .collect();
+        Self { roles }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        let mut catalog = Self::builtin();
+        catalog.extend(&config.roles);
+        catalog
+    }
+
+    /// Adds (or replaces) roles defined in a config.
+    pub fn extend<'a>(&mut self, roles: impl IntoIterator<Item = (&'a String, &'a Role)>) {
+        for (key, role) in roles {
+            self.roles.insert(key.clone(), (role.clone(), "config"));
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Role> {
+        self.roles.get(key).map(|(role, _)| role)
+    }
+
+    /// Every role, sorted by key.
+    pub fn list(&self) -> Vec<RoleEntry> {
+        self.roles
+            .iter()
+            .map(|(key, (role, source))| RoleEntry {
+                key: key.clone(),
+                source,
+                role: role.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_are_found_by_key() {
+        let engineer = Role::preset("engineer").unwrap();
+        assert_eq!(engineer.name, "a senior software engineer");
+        assert!(Role::preset("wizard").is_none());
+        assert_eq!(Audience::preset("executive").unwrap().name, "executives");
+        assert!(Preset::preset("eli5").is_some());
+        assert!(Style::preset("formal_report").is_some());
+        for key in Style::preset_names() {
+            assert!(Style::preset(key).is_some(), "{key}");
+        }
+        assert!(
+            Role::translator("Polish")
+                .description
+                .ends_with("Translate into Polish.")
+        );
+
+        let catalog = RoleCatalog::builtin();
+        let keys: Vec<String> = catalog.list().into_iter().map(|e| e.key).collect();
+        assert_eq!(keys, {
+            let mut names = Role::preset_names();
+            names.sort();
+            names
+        });
+    }
+
+    #[test]
+    fn config_roles_join_and_override_the_builtins() {
+        let config: Config = toml::from_str(
+            r#"
+[roles.pirate]
+name = "a pirate"
+description = "Speak like one."
+style = { name = "shouty", description = "ALL CAPS." }
+
+[roles.engineer]
+name = "our staff engineer"
+"#,
+        )
+        .unwrap();
+        let catalog = RoleCatalog::from_config(&config);
+        let pirate = catalog.get("pirate").unwrap();
+        assert_eq!(
+            pirate.get_prompt(),
+            "You are a pirate. Speak like one.\nUse the following style: shouty. ALL CAPS."
+        );
+        assert_eq!(catalog.get("engineer").unwrap().name, "our staff engineer");
+        let sources: Vec<(String, &str)> = catalog
+            .list()
+            .into_iter()
+            .map(|e| (e.key, e.source))
+            .filter(|(key, _)| key == "engineer" || key == "pirate" || key == "teacher")
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                ("engineer".to_string(), "config"),
+                ("pirate".to_string(), "config"),
+                ("teacher".to_string(), "builtin"),
+            ]
+        );
+    }
+
+    #[test]
+    fn roles_round_trip_through_toml_and_json() {
+        let role = Role::preset("analyst")
+            .unwrap()
+            .with_audience(Audience::preset("executive").unwrap())
+            .with_preset(Preset::preset("expert").unwrap())
+            .with_style(Style::preset("bullet_points").unwrap());
+        let toml_text = toml::to_string(&role).unwrap();
+        let from_toml: Role = toml::from_str(&toml_text).unwrap();
+        assert_eq!(from_toml, role);
+        let from_json: Role = serde_json::from_str(&serde_json::to_string(&role).unwrap()).unwrap();
+        assert_eq!(from_json, role);
+
+        // Fragments leave out what they do not set.
+        let fragment = Role::default().with_style(Style::preset("concise").unwrap());
+        let json = serde_json::to_value(&fragment).unwrap();
+        assert!(json.get("audience").is_none(), "{json}");
+        assert_eq!(
+            toml::from_str::<Role>(&toml::to_string(&fragment).unwrap()).unwrap(),
+            fragment
+        );
+    }
+}
@@ -1,31 +1,58 @@
 use serde::{Deserialize, Serialize};
 
+mod catalog;
+
+pub use catalog::{RoleCatalog, RoleEntry};
+
 /// Role: The AI's personality for this conversation.
 /// "Roles are like costumes - they change how you act but not who you are."
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Roles compose: `Role::default()` with only an audience or a style is a reusable fragment,
+/// and [`Role::merge`] combines fragments into one role.
+///
+/// Serializes to TOML/JSON as `name`, `description` and optional `audience` / `preset` /
+/// `style` tables and `allowed_tools` / `denied_tools` lists; anything left out deserializes as
+/// empty.
+///
+/// A role can also narrow the tools an agent offers and runs: `allowed_tools` (when set) is the
+/// only tools it may use and `denied_tools` are never used. See [`Role::allows_tool`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Role {
     pub name: String,
     pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub audience: Option<Audience>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub preset: Option<Preset>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub style: Option<Style>,
+    /// Tools this role may use; `None` allows every tool the agent has
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    /// Tools this role may never use, even when allowed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub denied_tools: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Audience {
     pub name: String,
+    #[serde(default)]
     pub description: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Preset {
     pub name: String,
+    #[serde(default)]
     pub description: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Style {
     pub name: String,
+    #[serde(default)]
     pub description: String,
 }
 
@@ -37,6 +64,8 @@ impl Role {
             audience: None,
             preset: None,
             style: None,
+            allowed_tools: None,
+            denied_tools: Vec::new(),
         }
     }
 
@@ -55,8 +84,85 @@ impl Role {
         self
     }
 
+    /// Limits the role to `tools`.
+    pub fn with_allowed_tools<S: Into<String>>(
+        mut self,
+        tools: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.allowed_tools = Some(tools.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Keeps the role away from `tools`.
+    pub fn with_denied_tools<S: Into<String>>(
+        mut self,
+        tools: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.denied_tools = tools.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether this role may use tool `name`: listed in `allowed_tools` (if set) and not in
+    /// `denied_tools`.
+    pub fn allows_tool(&self, name: &str) -> bool {
+        let allowed = self
+            .allowed_tools
+            .as_ref()
+            .is_none_or(|tools| tools.iter().any(|tool| tool == name));
+        allowed && !self.denied_tools.iter().any(|tool| tool == name)
+    }
+
+    /// Combines `self` with `other`: `self`'s name, audience, preset and style win, and `other`
+    /// supplies the ones `self` lacks. Descriptions are joined, `self`'s first. Tool restrictions
+    /// add up: only tools both allow, none that either denies. Merging is deterministic, so
+    /// `a.merge(b).merge(c)` always yields the same prompt.
+    pub fn merge(mut self, other: Role) -> Self {
+        if self.name.is_empty() {
+            self.name = other.name;
+        }
+        self.description = match (self.description.is_empty(), other.description.is_empty()) {
+            (_, true) => self.description,
+            (true, false) => other.description,
+            (false, false) if self.description == other.description => self.description,
+            (false, false) => format!("{} {}", self.description, other.description),
+        };
+        self.audience = self.audience.or(other.audience);
+        self.preset = self.preset.or(other.preset);
+        self.style = self.style.or(other.style);
+        self.allowed_tools = match (self.allowed_tools, other.allowed_tools) {
+            (Some(mine), Some(theirs)) => Some(
+                mine.into_iter()
+                    .filter(|tool| theirs.contains(tool))
+                    .collect(),
+            ),
+            (mine, theirs) => mine.or(theirs),
+        };
+        for tool in other.denied_tools {
+            if !self.denied_tools.contains(&tool) {
+                self.denied_tools.push(tool);
+            }
+        }
+        self
+    }
+
+    /// The full system prompt, one line per part in this order: role (`You are …`), audience,
+    /// preset, style. Parts that are not set are left out.
     pub fn get_prompt(&self) -> String {
-        format!("You are {}. {}", self.name, self.description)
+        let role = match (self.name.is_empty(), self.description.is_empty()) {
+            (true, true) => None,
+            (true, false) => Some(self.description.clone()),
+            (false, _) => Some(
+                format!("You are {}. {}", self.name, self.description)
+                    .trim_end()
+                    .to_string(),
+            ),
+        };
+        role.into_iter()
+            .chain(self.audience.as_ref().map(Audience::get_prompt))
+            .chain(self.preset.as_ref().map(Preset::get_prompt))
+            .chain(self.style.as_ref().map(Style::get_prompt))
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
     pub fn get_audience(&self) -> Option<&Audience> {
@@ -116,3 +222,62 @@ impl Style {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merged_fragments_appear_in_a_fixed_order() {
+        let scientists =
+            Role::default().with_audience(Audience::new("scientists", "They know statistics."));
+        let concise = Role::new("Reviewer", "You review papers.")
+            .with_style(Style::new("concise", "Short sentences."));
+
+        let role = Role::new("Editor", "You edit abstracts.")
+            .merge(scientists)
+            .merge(concise);
+        assert_eq!(role.name, "Editor");
+        assert_eq!(role.description, "You edit abstracts. You review papers.");
+        assert_eq!(
+            role.get_prompt(),
+            "You are Editor. You edit abstracts. You review papers.\n\
+             You are speaking to scientists. They know statistics.\n\
+             Use the following style: concise. Short sentences."
+        );
+
+        // The role's own fragments win over inherited ones.
+        let terse = Role::default().with_style(Style::new("terse", "One line."));
+        let merged = role.clone().merge(terse);
+        assert_eq!(merged.get_style().unwrap().name, "concise");
+        assert_eq!(merged.get_prompt(), role.get_prompt());
+    }
+
+    #[test]
+    fn a_fragment_alone_is_just_its_parts() {
+        let fragment = Role::default().with_preset(Preset::new("Beginner", "No prior experience"));
+        assert_eq!(
+            fragment.get_prompt(),
+            "Use the following preset: Beginner. No prior experience"
+        );
+    }
+
+    #[test]
+    fn tool_restrictions_narrow_when_merged() {
+        let reviewer = Role::new("Reviewer", "").with_allowed_tools(["fs_tool", "calculator"]);
+        assert!(reviewer.allows_tool("fs_tool"));
+        assert!(!reviewer.allows_tool("shell"));
+        assert!(Role::default().allows_tool("shell"));
+
+        let offline = Role::default()
+            .with_allowed_tools(["calculator", "web_search"])
+            .with_denied_tools(["web_search"]);
+        let merged = reviewer.merge(offline);
+        assert_eq!(merged.allowed_tools, Some(vec!["calculator".to_string()]));
+        assert_eq!(merged.denied_tools, ["web_search"]);
+        assert!(!merged.allows_tool("fs_tool"));
+        assert!(merged.allows_tool("calculator"));
+        // Restrictions do not show up in the prompt.
+        assert_eq!(merged.get_prompt(), "You are Reviewer.");
+    }
+}
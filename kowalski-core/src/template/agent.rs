@@ -1,7 +1,9 @@
 use crate::agent::BaseAgent;
 use crate::config::Config;
+use crate::conversation::MessageRole;
 use crate::error::KowalskiError;
 use crate::mcp::McpHub;
+use crate::role::Role;
 use crate::template::config::TemplateAgentConfig;
 use crate::tools::{TaskType, Tool, ToolInput, ToolOutput};
 use async_trait::async_trait;
@@ -55,8 +57,7 @@ impl TemplateAgent {
             }
         }
 
-        template_config.tool_prompt_appendix =
-            Self::build_tool_prompt_appendix(&base.tool_manager).await;
+        template_config.tool_prompt_appendix = Self::build_tool_prompt_appendix(&base).await;
 
         Ok(Self {
             base,
@@ -65,10 +66,16 @@ impl TemplateAgent {
         })
     }
 
-    async fn build_tool_prompt_appendix(
-        tool_manager: &crate::tools::manager::ToolManager,
-    ) -> String {
-        let schema = tool_manager.generate_json_schema().await;
+    /// Schemas of the tools `base` offers: registered and allowed by its role.
+    async fn build_tool_prompt_appendix(base: &BaseAgent) -> String {
+        let mut schema = base.tool_manager.generate_json_schema().await;
+        if let Some(functions) = schema.as_array_mut() {
+            functions.retain(|f| {
+                f["function"]["name"]
+                    .as_str()
+                    .is_some_and(|name| base.allows_tool(name))
+            });
+        }
         let empty = schema.as_array().map(|a| a.is_empty()).unwrap_or(true);
         if empty {
             return String::new();
@@ -116,16 +123,21 @@ impl TemplateAgent {
         tool: Box<dyn Tool + Send + Sync>,
     ) -> Result<(), KowalskiError> {
         self.base.tool_manager.register_boxed(tool);
-        self.config.tool_prompt_appendix =
-            Self::build_tool_prompt_appendix(&self.base.tool_manager).await;
+        self.config.tool_prompt_appendix = Self::build_tool_prompt_appendix(&self.base).await;
         Ok(())
     }
 
     /// Recomputes the tool schema appendix from the current [`BaseAgent::tool_manager`].
     /// Call this if tools are registered without going through [`Self::register_tool`].
     pub async fn refresh_tool_prompt_appendix(&mut self) {
-        self.config.tool_prompt_appendix =
-            Self::build_tool_prompt_appendix(&self.base.tool_manager).await;
+        self.config.tool_prompt_appendix = Self::build_tool_prompt_appendix(&self.base).await;
+    }
+
+    /// Restricts tools to what `role` allows ([`BaseAgent::set_role`]) and drops the others from
+    /// the tool schema of new conversations.
+    pub async fn set_role(&mut self, role: Role) {
+        self.base.set_role(role);
+        self.refresh_tool_prompt_appendix().await;
     }
 
     /// Registers a task handler with the agent
@@ -143,32 +155,8 @@ impl TemplateAgent {
         tool_name: &str,
         tool_input: &serde_json::Value,
     ) -> Result<ToolOutput, KowalskiError> {
-        // Delegate to base agent which uses ToolManager
-        // But here we might want to convert Value to ToolInput?
-        // BaseAgent::execute_tool already does that handling (lines 508+ in agent/mod.rs)
-        // Wait, `execute_tool` in TemplateAgent was likely calling internal `tool.execute`.
-        // Now we call `self.base.execute_tool(...)`.
-        // `BaseAgent` implements specific `execute_tool`.
-        // Does `BaseAgent` struct have `execute_tool` method?
-        // `impl Agent for BaseAgent` has it.
-        // `TemplateAgent` holds `base: BaseAgent`.
-        // We can call `self.base.tool_manager.execute(...)`.
-        // But we need to construct `ToolInput`.
-
-        // Replicating BaseAgent logic:
-        let task_type = tool_input
-            .get("task")
-            .and_then(|v| v.as_str())
-            .unwrap_or("default")
-            .to_string();
-        let content = tool_input
-            .get("content")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-
-        let input = crate::tools::ToolInput::new(task_type, content, tool_input.clone());
-        self.base.tool_manager.execute(tool_name, input).await
+        // BaseAgent builds the ToolInput and notifies observers.
+        crate::agent::Agent::execute_tool(&mut self.base, tool_name, tool_input).await
     }
 
     /// Executes a task using the appropriate tool or handler
@@ -222,24 +210,17 @@ impl TemplateAgent {
         )))
     }
 
-    /// Lists all registered tools (name, description)
+    /// Lists the registered tools the role allows (name, description)
     pub async fn list_tools(&self) -> Vec<(String, String)> {
-        self.base.tool_manager.list_tools().await
+        self.base.available_tools().await
     }
 
-    /// Prepare [`crate::llm::LLMProvider::chat_stream`] after the same context injection as chat (memories + user turn).
+    /// Prepare [`crate::llm::LLMProvider::chat_stream_with_options`] after the same context injection as chat (memories + user turn).
     pub async fn prepare_stream_turn(
         &mut self,
         conversation_id: &str,
         user: &str,
-    ) -> Result<
-        (
-            String,
-            Vec<crate::conversation::Message>,
-            std::sync::Arc<dyn crate::llm::LLMProvider>,
-        ),
-        KowalskiError,
-    > {
+    ) -> Result<crate::agent::StreamTurn, KowalskiError> {
         self.base_mut()
             .prepare_stream_turn(conversation_id, user, None)
             .await
@@ -250,14 +231,7 @@ impl TemplateAgent {
         conversation_id: &str,
         user: &str,
         use_memory: bool,
-    ) -> Result<
-        (
-            String,
-            Vec<crate::conversation::Message>,
-            std::sync::Arc<dyn crate::llm::LLMProvider>,
-        ),
-        KowalskiError,
-    > {
+    ) -> Result<crate::agent::StreamTurn, KowalskiError> {
         self.base_mut()
             .prepare_stream_turn_with_options(conversation_id, user, None, use_memory)
             .await
@@ -338,18 +312,32 @@ impl crate::agent::Agent for TemplateAgent {
     }
 
     fn start_conversation(&mut self, model: &str) -> String {
-        let mut system_prompt = self
+        let appendix = self.config.tool_prompt_appendix.clone();
+        let fallback = self
             .base
             .system_prompt
             .clone()
             .unwrap_or_else(|| self.config.system_prompt.clone());
-        if system_prompt.trim().is_empty() {
-            system_prompt = "You are a helpful assistant.".to_string();
-        }
-        system_prompt.push_str(&self.config.tool_prompt_appendix);
+        let fallback = if fallback.trim().is_empty() {
+            self.base.default_system_prompt()
+        } else {
+            fallback
+        };
+        // A system prompt template, when set, already opened the conversation; it goes after the
+        // persona prompt, and the tool schema after both.
+        let templated = self.base.system_prompt_template.is_some();
         let conv_id = self.base_mut().start_conversation(model);
         if let Some(conversation) = self.base_mut().conversations.get_mut(&conv_id) {
-            conversation.add_message("system", &system_prompt);
+            let mut system_prompt = fallback;
+            if templated && !conversation.messages.is_empty() {
+                let rendered = conversation.messages.remove(0).content;
+                system_prompt.push_str("\n\n");
+                system_prompt.push_str(&rendered);
+            }
+            system_prompt.push_str(&appendix);
+            let rest = std::mem::take(&mut conversation.messages);
+            conversation.add_message_typed(MessageRole::System, &system_prompt);
+            conversation.messages.extend(rest);
         }
         conv_id
     }
@@ -377,6 +365,17 @@ impl crate::agent::Agent for TemplateAgent {
             .await
     }
 
+    async fn chat_with_history_stream(
+        &mut self,
+        conversation_id: &str,
+        content: &str,
+        tokens: &tokio::sync::mpsc::Sender<String>,
+    ) -> Result<String, KowalskiError> {
+        self.base_mut()
+            .chat_with_history_stream(conversation_id, content, tokens)
+            .await
+    }
+
     async fn process_stream_response(
         &mut self,
         conversation_id: &str,
@@ -387,12 +386,88 @@ impl crate::agent::Agent for TemplateAgent {
             .await
     }
 
+    async fn chat_with_images(
+        &mut self,
+        conversation_id: &str,
+        content: &str,
+        images: &[std::path::PathBuf],
+    ) -> Result<String, KowalskiError> {
+        self.base_mut()
+            .chat_with_images(conversation_id, content, images)
+            .await
+    }
+
     async fn add_message(&mut self, conversation_id: &str, role: &str, content: &str) {
         self.base_mut()
             .add_message(conversation_id, role, content)
             .await;
     }
 
+    async fn add_tool_exchange(
+        &mut self,
+        conversation_id: &str,
+        reply: &str,
+        call: &crate::tools::ToolCall,
+        result: &str,
+        source: Option<&str>,
+    ) {
+        self.base_mut()
+            .add_tool_exchange(conversation_id, reply, call, result, source)
+            .await;
+    }
+
+    async fn chat_structured(
+        &mut self,
+        conversation_id: &str,
+        prompt: &str,
+        schema: &serde_json::Value,
+    ) -> Result<serde_json::Value, KowalskiError> {
+        self.base_mut()
+            .chat_structured(conversation_id, prompt, schema)
+            .await
+    }
+
+    async fn regenerate_last(
+        &mut self,
+        conversation_id: &str,
+        options: &crate::llm::ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.base_mut()
+            .regenerate_last(conversation_id, options)
+            .await
+    }
+
+    async fn search_history(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<crate::memory::MemoryUnit>, KowalskiError> {
+        self.base().search_history(query, limit).await
+    }
+
+    async fn close_conversation(&mut self, id: &str) -> Result<bool, KowalskiError> {
+        self.base_mut().close_conversation(id).await
+    }
+
+    async fn summarize_conversation(
+        &self,
+        id: &str,
+    ) -> Result<Option<crate::memory::MemoryUnit>, KowalskiError> {
+        self.base().summarize_conversation(id).await
+    }
+
+    async fn remember_fact(&self, key: &str, value: &str) -> Result<(), KowalskiError> {
+        self.base().remember_fact(key, value).await
+    }
+
+    async fn forget_fact(&self, key: &str) -> Result<bool, KowalskiError> {
+        self.base().forget_fact(key).await
+    }
+
+    fn list_facts(&self) -> Vec<(String, String)> {
+        self.base().list_facts()
+    }
+
     async fn execute_tool(
         &mut self,
         tool_name: &str,
@@ -405,6 +480,25 @@ impl crate::agent::Agent for TemplateAgent {
         self.list_tools().await
     }
 
+    fn tool_result_prompt(&self, tool: &str, result: &str) -> String {
+        self.base.tool_result_prompt(tool, result)
+    }
+
+    fn tool_manager(&self) -> Option<&crate::tools::manager::ToolManager> {
+        Some(&self.base.tool_manager)
+    }
+
+    async fn capabilities(&self) -> crate::agent::types::AgentCapabilities {
+        crate::agent::types::AgentCapabilities {
+            description: crate::agent::Agent::description(self).to_string(),
+            ..crate::agent::Agent::capabilities(&self.base).await
+        }
+    }
+
+    fn base_agent_mut(&mut self) -> Option<&mut BaseAgent> {
+        Some(&mut self.base)
+    }
+
     fn export_conversation(&self, id: &str) -> Result<String, KowalskiError> {
         self.base().export_conversation(id)
     }
@@ -413,6 +507,14 @@ impl crate::agent::Agent for TemplateAgent {
         self.base_mut().import_conversation(json_str)
     }
 
+    fn fork_conversation(
+        &mut self,
+        id: &str,
+        model: Option<&str>,
+    ) -> Result<String, KowalskiError> {
+        crate::agent::Agent::fork_conversation(self.base_mut(), id, model)
+    }
+
     fn name(&self) -> &str {
         "Template Agent"
     }
@@ -1,11 +1,15 @@
 use crate::agent::BaseAgent;
 use crate::config::Config;
 use crate::error::KowalskiError;
+use crate::prompts::{PromptKind, PromptRegistry};
+use crate::role::Role;
 use crate::template::agent::TaskHandler;
 use crate::template::agent::TemplateAgent;
 use crate::template::config::TemplateAgentConfig;
+use crate::template::default::DefaultToolset;
 use crate::tools::Tool;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -16,11 +20,16 @@ type SharedMemoryProvider =
 pub struct AgentBuilder {
     base: BaseAgent,
     config: TemplateAgentConfig,
+    /// Core configuration the agent is built with (see [`Self::with_config`]).
+    agent_config: Config,
     tool_chain: Arc<RwLock<Vec<Box<dyn Tool + Send + Sync>>>>,
     task_handlers: Arc<RwLock<HashMap<String, Box<dyn TaskHandler>>>>,
     system_prompt: String,
     temperature: f32,
     tools: Vec<Box<dyn Tool + Send + Sync>>,
+    default_tools: DefaultToolset,
+    sandbox_root: PathBuf,
+    role: Option<Role>,
 }
 
 impl AgentBuilder {
@@ -45,7 +54,7 @@ impl AgentBuilder {
             .expect("Failed to create memory providers");
 
         let base = BaseAgent::new(
-            default_config,
+            default_config.clone(),
             "Template Agent",
             "A base implementation for building specialized agents",
             llm_provider,
@@ -60,14 +69,25 @@ impl AgentBuilder {
         Self {
             base,
             config,
+            agent_config: default_config,
             tool_chain: Arc::new(RwLock::new(Vec::new())),
             task_handlers: Arc::new(RwLock::new(HashMap::new())),
             system_prompt: String::new(),
             temperature: 0.7,
             tools: Vec::new(),
+            default_tools: DefaultToolset::empty(),
+            sandbox_root: PathBuf::from("."),
+            role: None,
         }
     }
 
+    /// Core configuration for the agent (model, memory, `[prompts]`, ...); default:
+    /// [`Config::default`].
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.agent_config = config;
+        self
+    }
+
     /// Sets the agent's system prompt
     pub fn with_system_prompt(mut self, prompt: &str) -> Self {
         self.system_prompt = prompt.to_string();
@@ -92,23 +112,70 @@ impl AgentBuilder {
         self
     }
 
+    /// Selects the built-in tools to register (none unless set; [`DefaultTemplate`] uses
+    /// [`DefaultToolset::all`]).
+    ///
+    /// [`DefaultTemplate`]: crate::template::default::DefaultTemplate
+    pub fn with_default_tools(mut self, toolset: DefaultToolset) -> Self {
+        self.default_tools = toolset;
+        self
+    }
+
+    /// Directory the built-in `fs_tool` may read (default: the working directory)
+    pub fn with_sandbox_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.sandbox_root = root.into();
+        self
+    }
+
+    /// Restricts the agent's tools to what `role` allows (see [`Role::allows_tool`]); only those
+    /// are described in the system prompt.
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = Some(role);
+        self
+    }
+
     /// Builds the final agent
     pub async fn build(self) -> Result<TemplateAgent, KowalskiError> {
-        // Configure base agent
-        // let mut base = self.base;
-        // base.set_temperature(self.temperature);
-        // if !self.system_prompt.is_empty() {
-        //     base.set_system_prompt(&self.system_prompt);
-        // }
-
-        // Create template agent
-        let mut agent = TemplateAgent::new(Config::default()).await?;
-
-        // Register tools
-        for tool in self.tools {
+        let mut agent = TemplateAgent::new(self.agent_config).await?;
+
+        if let Some(role) = self.role {
+            agent.base_mut().set_role(role);
+        }
+        let tools = self.default_tools.tools(&self.sandbox_root);
+        let base = agent.base();
+        let guidance = tool_guidance(
+            &base.prompts,
+            tools
+                .iter()
+                .chain(&self.tools)
+                .filter(|tool| base.allows_tool(tool.name())),
+        );
+        for tool in tools.into_iter().chain(self.tools) {
             agent.register_tool(tool).await?;
         }
 
+        let base = agent.base_mut();
+        base.set_temperature(self.temperature);
+        if !self.system_prompt.is_empty() {
+            base.set_system_prompt(&format!("{}{}", self.system_prompt, guidance));
+        }
+
         Ok(agent)
     }
 }
+
+/// How to call `tools` ([`PromptKind::ToolUse`]), appended to the builder's system prompt; empty
+/// without tools.
+fn tool_guidance<'a>(
+    prompts: &PromptRegistry,
+    tools: impl Iterator<Item = &'a Box<dyn Tool + Send + Sync>>,
+) -> String {
+    let lines: Vec<String> = tools
+        .map(|tool| format!("- {}: {}", tool.name(), tool.description()))
+        .collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+    let guidance = prompts.render(PromptKind::ToolUse, &[("tools", &lines.join("\n"))]);
+    format!("\n\n{guidance}")
+}
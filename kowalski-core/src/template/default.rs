@@ -1,10 +1,104 @@
 use crate::template::builder::AgentBuilder;
-use crate::tools::Tool;
+use crate::tools::{
+    CalculatorTool, ConfigFileTool, CsvTool, DateTimeTool, FsTool, SqlTool, StatsTool, Tool,
+};
+use std::ops::{BitOr, Sub};
+use std::path::Path;
+
+/// Built-in tools an [`AgentBuilder`] registers. Combine with `|` and trim with `-`:
+/// `DefaultToolset::all() - DefaultToolset::FS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DefaultToolset(u8);
+
+impl DefaultToolset {
+    /// `fs_tool`: read-only, confined to the sandbox root (the working directory by default).
+    pub const FS: Self = Self(1);
+    /// `calculator`
+    pub const CALCULATOR: Self = Self(1 << 1);
+    /// `datetime`
+    pub const DATETIME: Self = Self(1 << 2);
+    /// `csv_tool`; `process_csv_file` reads under the sandbox root.
+    pub const CSV: Self = Self(1 << 3);
+    /// `config_file` (YAML/TOML parsing and schema checks)
+    pub const CONFIG_FILE: Self = Self(1 << 4);
+    /// `stats` (describe, correlation, histogram, outliers); files are read under the sandbox root.
+    pub const STATS: Self = Self(1 << 5);
+    /// `sql_query` (read-only SQL over a CSV or JSON file); files are read under the sandbox root.
+    pub const SQL: Self = Self(1 << 6);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn all() -> Self {
+        Self(
+            Self::FS.0
+                | Self::CALCULATOR.0
+                | Self::DATETIME.0
+                | Self::CSV.0
+                | Self::CONFIG_FILE.0
+                | Self::STATS.0
+                | Self::SQL.0,
+        )
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The selected tools; `fs_tool`, `csv_tool`, `stats` and `sql_query` read files only under
+    /// `sandbox_root`.
+    pub fn tools(self, sandbox_root: &Path) -> Vec<Box<dyn Tool + Send + Sync>> {
+        let mut tools: Vec<Box<dyn Tool + Send + Sync>> = Vec::new();
+        if self.contains(Self::FS) {
+            tools.push(Box::new(FsTool::new(sandbox_root)));
+        }
+        if self.contains(Self::CALCULATOR) {
+            tools.push(Box::new(CalculatorTool::new()));
+        }
+        if self.contains(Self::DATETIME) {
+            tools.push(Box::new(DateTimeTool::new()));
+        }
+        if self.contains(Self::CSV) {
+            tools.push(Box::new(CsvTool::new().with_root(sandbox_root)));
+        }
+        if self.contains(Self::CONFIG_FILE) {
+            tools.push(Box::new(ConfigFileTool::new()));
+        }
+        if self.contains(Self::STATS) {
+            tools.push(Box::new(StatsTool::new().with_root(sandbox_root)));
+        }
+        if self.contains(Self::SQL) {
+            tools.push(Box::new(SqlTool::new().with_root(sandbox_root)));
+        }
+        tools
+    }
+}
+
+impl BitOr for DefaultToolset {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl Sub for DefaultToolset {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 & !rhs.0)
+    }
+}
 
 pub struct DefaultTemplate;
 
 impl DefaultTemplate {
-    /// Creates a new general-purpose agent with customizable tools
+    /// Creates a new general-purpose agent with the full [`DefaultToolset`] plus `tools`
     pub async fn create_agent(
         tools: Vec<Box<dyn Tool + Send + Sync>>,
         system_prompt: Option<String>,
@@ -17,6 +111,7 @@ impl DefaultTemplate {
         let builder = AgentBuilder::new()
             .await
             .with_system_prompt(&prompt)
+            .with_default_tools(DefaultToolset::all())
             .with_tools(tools)
             .with_temperature(temp);
 
@@ -28,3 +123,168 @@ impl DefaultTemplate {
         Self::create_agent(Vec::new(), None, None).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Agent;
+    use serde_json::json;
+
+    async fn tool_names(builder: AgentBuilder) -> Vec<String> {
+        let agent = builder.build().await.unwrap();
+        let mut names: Vec<String> = agent
+            .list_tools()
+            .await
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn toolsets_combine_and_trim() {
+        let set = DefaultToolset::all() - DefaultToolset::FS;
+        assert!(!set.contains(DefaultToolset::FS));
+        assert!(set.contains(DefaultToolset::CALCULATOR | DefaultToolset::CSV));
+        assert!((set - set).is_empty());
+        assert_eq!(set | DefaultToolset::FS, DefaultToolset::all());
+    }
+
+    #[tokio::test]
+    async fn default_agent_ships_the_builtin_tools() {
+        let builder = DefaultTemplate::create_default_agent().await.unwrap();
+        assert_eq!(
+            tool_names(builder).await,
+            [
+                "calculator",
+                "config_file",
+                "csv_tool",
+                "datetime",
+                "fs_tool",
+                "sql_query",
+                "stats"
+            ]
+        );
+
+        let trimmed = DefaultTemplate::create_default_agent()
+            .await
+            .unwrap()
+            .with_default_tools(DefaultToolset::all() - DefaultToolset::FS);
+        assert_eq!(
+            tool_names(trimmed).await,
+            [
+                "calculator",
+                "config_file",
+                "csv_tool",
+                "datetime",
+                "sql_query",
+                "stats"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn builder_config_reaches_the_agent() {
+        let mut config = crate::config::Config::default();
+        config.ollama.model = "custom-model".to_string();
+        config.prompts.templates.insert(
+            "tool_use".to_string(),
+            "Tools on hand:\n{{tools}}".to_string(),
+        );
+        let mut agent = AgentBuilder::new()
+            .await
+            .with_config(config)
+            .with_system_prompt("You count.")
+            .with_default_tools(DefaultToolset::CALCULATOR)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(agent.base().config.ollama.model, "custom-model");
+        let id = agent.start_conversation("m");
+        let prompt = &agent.get_conversation(&id).unwrap().messages[0].content;
+        assert!(
+            prompt.starts_with("You count.\n\nTools on hand:\n- calculator: "),
+            "{prompt}"
+        );
+    }
+
+    #[tokio::test]
+    async fn data_agent_reports_the_csv_tool() {
+        let agent = AgentBuilder::new()
+            .await
+            .with_system_prompt("You analyse tabular data.")
+            .with_default_tools(DefaultToolset::CSV | DefaultToolset::CALCULATOR)
+            .build()
+            .await
+            .unwrap();
+
+        let capabilities = agent.capabilities().await;
+        let names: Vec<&str> = capabilities.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["calculator", "csv_tool"]);
+        assert!(capabilities.has_tool("csv_tool"));
+        let csv = &capabilities.tools[1];
+        assert!(!csv.description.is_empty());
+        assert!(!csv.parameters.is_empty());
+        assert!(capabilities.supports_streaming);
+        assert_eq!(
+            capabilities.default_model,
+            agent.base().config.active_model()
+        );
+        assert_eq!(capabilities.description, agent.description());
+    }
+
+    #[tokio::test]
+    async fn fs_tool_is_rooted_at_the_sandbox_and_prompt_lists_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("inside.txt"), "inside").unwrap();
+        let mut agent = DefaultTemplate::create_default_agent()
+            .await
+            .unwrap()
+            .with_sandbox_root(dir.path())
+            .build()
+            .await
+            .unwrap();
+
+        let read = agent
+            .execute_tool(
+                "fs_tool",
+                &json!({"task": "read_file", "path": "inside.txt"}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(read.result["content"], "inside");
+        assert!(
+            agent
+                .execute_tool("fs_tool", &json!({"task": "list_dir", "path": ".."}))
+                .await
+                .is_err()
+        );
+
+        let id = agent.start_conversation("m");
+        let prompt = &agent.get_conversation(&id).unwrap().messages[0].content;
+        assert!(
+            prompt.starts_with("You are a versatile AI assistant"),
+            "{prompt}"
+        );
+        assert!(prompt.contains("- fs_tool: "), "{prompt}");
+        assert!(prompt.contains("reply with only a JSON object"), "{prompt}");
+
+        // A system prompt template adds to the persona instead of replacing it.
+        agent
+            .base_mut()
+            .set_system_prompt_template("Working as {agent_name}.");
+        let id = agent.start_conversation("m");
+        let messages = &agent.get_conversation(&id).unwrap().messages;
+        assert_eq!(messages.len(), 1);
+        let prompt = &messages[0].content;
+        assert!(
+            prompt.starts_with("You are a versatile AI assistant"),
+            "{prompt}"
+        );
+        let rendered = format!("\n\nWorking as {}.", agent.name());
+        let schema = prompt.find("--- Available tools ---").unwrap();
+        assert!(prompt[..schema].contains(&rendered), "{prompt}");
+    }
+}
@@ -0,0 +1,593 @@
+//! Test doubles for agents (feature **`testing`**). [`MockBackend`] is an [`LLMProvider`] that
+//! replays a script of replies (text, streamed chunks, tool calls, errors) instead of calling a
+//! model, and records every request, so a whole tool loop runs without a network:
+//! `MockBackend::script().user_says("list files").responds_with_tool_call("fs_tool", json!({..}))
+//! .then_text("done").build()`, then [`agent`] wraps it in a [`BaseAgent`].
+//!
+//! Tests whose call count is not fixed (echo workers, federation, concurrency) answer with
+//! [`MockScript::otherwise`] once the script runs out, and [`MockScript::with_latency`] plus
+//! [`MockBackend::peak_in_flight`] show how many calls overlapped.
+
+use crate::agent::BaseAgent;
+use crate::config::Config;
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::llm::{ChatOptions, LLMProvider, TokenStream};
+use crate::memory::MemoryProvider;
+use crate::memory::working::WorkingMemory;
+use crate::prompts::PromptKind;
+use crate::tools::manager::ToolManager;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// One scripted reply.
+#[derive(Debug)]
+pub enum MockReply {
+    Text(String),
+    /// Streamed as these deltas; a blocking call gets them concatenated.
+    Chunks(Vec<String>),
+    /// `{"name": .., "parameters": ..}`, the JSON-in-text call the tool loop parses.
+    ToolCall {
+        name: String,
+        parameters: Value,
+    },
+    Error(KowalskiError),
+    /// `reply`, sent after `delay`.
+    Delayed(Duration, Box<MockReply>),
+}
+
+impl MockReply {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    /// Sends `self` only after `delay`, e.g. for a slow worker.
+    pub fn after(self, delay: Duration) -> Self {
+        Self::Delayed(delay, Box::new(self))
+    }
+
+    /// The total delay and the reply behind it.
+    fn split_delay(self) -> (Duration, MockReply) {
+        match self {
+            Self::Delayed(delay, reply) => {
+                let (more, reply) = reply.split_delay();
+                (delay + more, reply)
+            }
+            reply => (Duration::ZERO, reply),
+        }
+    }
+
+    fn into_result(self) -> Result<String, KowalskiError> {
+        match self {
+            Self::Text(text) => Ok(text),
+            Self::Chunks(chunks) => Ok(chunks.concat()),
+            Self::ToolCall { name, parameters } => {
+                Ok(json!({ "name": name, "parameters": parameters }).to_string())
+            }
+            Self::Error(e) => Err(e),
+            Self::Delayed(_, reply) => reply.into_result(),
+        }
+    }
+}
+
+/// A closure field, printed as `<fn>`.
+struct Callback<F: ?Sized>(Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<fn>")
+    }
+}
+
+type Responder = Callback<dyn Fn(&MockRequest) -> MockReply + Send + Sync>;
+type Embedder = Callback<dyn Fn(&str) -> Vec<f32> + Send + Sync>;
+
+#[derive(Debug)]
+struct Step {
+    expected_user: Option<String>,
+    reply: MockReply,
+}
+
+/// A request [`MockBackend`] received.
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub model: String,
+    pub messages: Vec<Message>,
+    /// Sent through [`LLMProvider::chat_stream`].
+    pub streamed: bool,
+    /// Sent through [`LLMProvider::chat_json`] or [`LLMProvider::chat_structured`].
+    pub json_mode: bool,
+    /// The JSON Schema passed to [`LLMProvider::chat_structured`].
+    pub schema: Option<Value>,
+    /// Sampling settings of the call (default for [`LLMProvider::chat`] and streams).
+    pub options: ChatOptions,
+}
+
+impl MockRequest {
+    pub fn last_user_message(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| m.content.as_str())
+    }
+
+    /// Whether a system message offers the tool `name` (agents list their tools there).
+    pub fn offers_tool(&self, name: &str) -> bool {
+        self.messages
+            .iter()
+            .any(|m| m.role == "system" && m.content.contains(name))
+    }
+
+    /// Content of the last message, whatever its role.
+    pub fn last_message(&self) -> &str {
+        self.messages
+            .last()
+            .map(|m| m.content.as_str())
+            .unwrap_or_default()
+    }
+}
+
+/// Builds a [`MockBackend`] script, one reply per LLM call in order.
+#[derive(Debug, Default)]
+pub struct MockScript {
+    steps: Vec<Step>,
+    expected_user: Option<String>,
+    otherwise: Option<Responder>,
+    embedder: Option<Embedder>,
+    latency: Duration,
+}
+
+impl MockScript {
+    /// The next request's last user message must contain `text`.
+    pub fn user_says(mut self, text: impl Into<String>) -> Self {
+        self.expected_user = Some(text.into());
+        self
+    }
+
+    pub fn responds_with(mut self, reply: MockReply) -> Self {
+        self.steps.push(Step {
+            expected_user: self.expected_user.take(),
+            reply,
+        });
+        self
+    }
+
+    pub fn responds_with_text(self, text: impl Into<String>) -> Self {
+        self.responds_with(MockReply::Text(text.into()))
+    }
+
+    pub fn responds_with_tool_call(self, name: impl Into<String>, parameters: Value) -> Self {
+        self.responds_with(MockReply::ToolCall {
+            name: name.into(),
+            parameters,
+        })
+    }
+
+    pub fn responds_with_chunks<S: Into<String>>(
+        self,
+        chunks: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.responds_with(MockReply::Chunks(
+            chunks.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    pub fn responds_with_error(self, error: KowalskiError) -> Self {
+        self.responds_with(MockReply::Error(error))
+    }
+
+    pub fn then_text(self, text: impl Into<String>) -> Self {
+        self.responds_with_text(text)
+    }
+
+    pub fn then_tool_call(self, name: impl Into<String>, parameters: Value) -> Self {
+        self.responds_with_tool_call(name, parameters)
+    }
+
+    pub fn then_chunks<S: Into<String>>(self, chunks: impl IntoIterator<Item = S>) -> Self {
+        self.responds_with_chunks(chunks)
+    }
+
+    pub fn then_error(self, error: KowalskiError) -> Self {
+        self.responds_with_error(error)
+    }
+
+    /// Answers every request past the end of the script with `respond` instead of panicking.
+    pub fn otherwise(
+        mut self,
+        respond: impl Fn(&MockRequest) -> MockReply + Send + Sync + 'static,
+    ) -> Self {
+        self.otherwise = Some(Callback(Arc::new(respond)));
+        self
+    }
+
+    /// Replies past the end of the script with the last message of the request, after `prefix`.
+    pub fn echoes(self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        self.otherwise(move |request| {
+            MockReply::text(format!("{prefix}{}", request.last_message()))
+        })
+    }
+
+    /// What [`LLMProvider::embed`] returns for any text (default: empty).
+    pub fn with_embedding(self, embedding: Vec<f32>) -> Self {
+        self.with_embedder(move |_| embedding.clone())
+    }
+
+    /// Embeds each text with `embed`.
+    pub fn with_embedder(
+        mut self,
+        embed: impl Fn(&str) -> Vec<f32> + Send + Sync + 'static,
+    ) -> Self {
+        self.embedder = Some(Callback(Arc::new(embed)));
+        self
+    }
+
+    /// Every call takes at least `latency` (a batch embedding counts once).
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn build(self) -> MockBackend {
+        MockBackend {
+            steps: Mutex::new(self.steps.into()),
+            requests: Mutex::new(Vec::new()),
+            otherwise: self.otherwise,
+            embedder: self.embedder,
+            latency: self.latency,
+            embed_requests: Mutex::new(Vec::new()),
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// Counts a call as running until dropped.
+struct InFlight<'a>(&'a MockBackend);
+
+impl<'a> InFlight<'a> {
+    fn enter(backend: &'a MockBackend) -> Self {
+        let now = backend.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        backend.peak_in_flight.fetch_max(now, Ordering::SeqCst);
+        Self(backend)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Scripted [`LLMProvider`]. Each chat call takes the next reply; a call past the end of the
+/// script without an [`otherwise`](MockScript::otherwise) responder, or one whose user message
+/// misses a [`user_says`](MockScript::user_says) expectation, panics with the request it got.
+#[derive(Debug)]
+pub struct MockBackend {
+    steps: Mutex<VecDeque<Step>>,
+    requests: Mutex<Vec<MockRequest>>,
+    otherwise: Option<Responder>,
+    embedder: Option<Embedder>,
+    latency: Duration,
+    embed_requests: Mutex<Vec<Vec<String>>>,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+}
+
+impl MockBackend {
+    pub fn script() -> MockScript {
+        MockScript::default()
+    }
+
+    /// Answers every chat with an empty reply, for agents whose replies do not matter.
+    pub fn silent() -> Self {
+        Self::script().otherwise(|_| MockReply::text("")).build()
+    }
+
+    /// Requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Texts of each embedding request so far: one per [`LLMProvider::embed`] call, and one
+    /// per [`LLMProvider::embed_batch`] call, which the mock answers like a batch endpoint.
+    pub fn embed_requests(&self) -> Vec<Vec<String>> {
+        self.embed_requests.lock().unwrap().clone()
+    }
+
+    /// The most chat and embedding calls that ran at the same time.
+    pub fn peak_in_flight(&self) -> usize {
+        self.peak_in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Replies not yet used.
+    pub fn remaining(&self) -> usize {
+        self.steps.lock().unwrap().len()
+    }
+
+    /// Panics unless every scripted reply was used.
+    pub fn assert_finished(&self) {
+        let remaining = self.remaining();
+        assert_eq!(
+            remaining, 0,
+            "MockBackend: {remaining} scripted replies unused"
+        );
+    }
+
+    fn next_reply(&self, request: MockRequest) -> MockReply {
+        let mut requests = self.requests.lock().unwrap();
+        let number = requests.len() + 1;
+        let Some(step) = self.steps.lock().unwrap().pop_front() else {
+            let Some(otherwise) = &self.otherwise else {
+                panic!("MockBackend: request {number} has no scripted reply: {request:?}");
+            };
+            let reply = (otherwise.0)(&request);
+            requests.push(request);
+            return reply;
+        };
+        if let Some(expected) = &step.expected_user {
+            let said = request.last_user_message().unwrap_or_default();
+            assert!(
+                said.contains(expected.as_str()),
+                "MockBackend: request {number} should have a user message containing \
+                 {expected:?}, got {said:?}"
+            );
+        }
+        requests.push(request);
+        step.reply
+    }
+
+    /// Answers a blocking call, counted in flight until its reply is sent.
+    async fn answer(&self, request: MockRequest) -> Result<String, KowalskiError> {
+        let _call = InFlight::enter(self);
+        let (delay, reply) = self.next_reply(request).split_delay();
+        tokio::time::sleep(self.latency + delay).await;
+        reply.into_result()
+    }
+
+    async fn embedding(&self, texts: &[String]) -> Vec<Vec<f32>> {
+        let _call = InFlight::enter(self);
+        self.embed_requests.lock().unwrap().push(texts.to_vec());
+        tokio::time::sleep(self.latency).await;
+        texts
+            .iter()
+            .map(|text| match &self.embedder {
+                Some(embed) => (embed.0)(text),
+                None => Vec::new(),
+            })
+            .collect()
+    }
+}
+
+fn request(
+    model: &str,
+    messages: &[Message],
+    json_mode: bool,
+    schema: Option<&Value>,
+    options: &ChatOptions,
+) -> MockRequest {
+    MockRequest {
+        model: model.to_string(),
+        messages: messages.to_vec(),
+        streamed: false,
+        json_mode,
+        schema: schema.cloned(),
+        options: options.clone(),
+    }
+}
+
+#[async_trait]
+impl LLMProvider for MockBackend {
+    async fn chat(&self, model: &str, messages: &[Message]) -> Result<String, KowalskiError> {
+        self.chat_with_options(model, messages, &ChatOptions::default())
+            .await
+    }
+
+    async fn chat_with_options(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.answer(request(model, messages, false, None, options))
+            .await
+    }
+
+    async fn chat_json(
+        &self,
+        model: &str,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.answer(request(model, messages, true, None, options))
+            .await
+    }
+
+    async fn chat_structured(
+        &self,
+        model: &str,
+        messages: &[Message],
+        schema: &Value,
+        options: &ChatOptions,
+    ) -> Result<String, KowalskiError> {
+        self.answer(request(model, messages, true, Some(schema), options))
+            .await
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, KowalskiError> {
+        Ok(self
+            .embedding(&[text.to_string()])
+            .await
+            .pop()
+            .unwrap_or_default())
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, KowalskiError> {
+        Ok(self.embedding(texts).await)
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    fn chat_stream(&self, model: &str, messages: Vec<Message>) -> TokenStream<'_> {
+        let (delay, reply) = self
+            .next_reply(MockRequest {
+                model: model.to_string(),
+                messages,
+                streamed: true,
+                json_mode: false,
+                schema: None,
+                options: ChatOptions::default(),
+            })
+            .split_delay();
+        let items: Vec<Result<String, KowalskiError>> = match reply {
+            MockReply::Chunks(chunks) => chunks.into_iter().map(Ok).collect(),
+            reply => vec![reply.into_result()],
+        };
+        let delay = self.latency + delay;
+        if delay.is_zero() {
+            return Box::pin(futures::stream::iter(items));
+        }
+        Box::pin(
+            futures::stream::once(async move {
+                tokio::time::sleep(delay).await;
+                futures::stream::iter(items)
+            })
+            .flatten(),
+        )
+    }
+}
+
+/// An in-process [`WorkingMemory`] of 10 units, for any of an agent's memory tiers.
+pub fn memory() -> Arc<tokio::sync::Mutex<dyn MemoryProvider + Send + Sync>> {
+    Arc::new(tokio::sync::Mutex::new(WorkingMemory::new(10)))
+}
+
+/// A [`BaseAgent`] on `backend` with `tools` and in-process memories only (no database, no
+/// embedding server). Its system prompt lists the tools, as builder-made agents' does.
+pub async fn agent(
+    backend: Arc<MockBackend>,
+    tools: ToolManager,
+) -> Result<BaseAgent, KowalskiError> {
+    let mut agent = BaseAgent::new(
+        Config::default(),
+        "mock",
+        "agent on a scripted backend",
+        backend,
+        memory(),
+        memory(),
+        memory(),
+        tools,
+    )
+    .await?;
+    let tools: Vec<String> = agent
+        .available_tools()
+        .await
+        .into_iter()
+        .map(|(name, description)| format!("- {name}: {description}"))
+        .collect();
+    let mut prompt = agent.default_system_prompt();
+    if !tools.is_empty() {
+        let guidance = agent
+            .prompts
+            .render(PromptKind::ToolUse, &[("tools", &tools.join("\n"))]);
+        prompt = format!("{prompt}\n\n{guidance}");
+    }
+    agent.set_system_prompt_template(prompt);
+    Ok(agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Agent;
+    use crate::tools::FsTool;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn drives_a_two_step_react_loop_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        let backend = Arc::new(
+            MockBackend::script()
+                .user_says("what files are there?")
+                .responds_with_tool_call("fs_tool", json!({"task": "list_dir"}))
+                .user_says("Based on the tool result")
+                .then_text("There is one file, notes.txt.")
+                .build(),
+        );
+        let tools = ToolManager::new();
+        tools.register(FsTool::new(dir.path()));
+        let mut agent = agent(backend.clone(), tools).await.unwrap();
+
+        let id = agent.start_conversation("llama3.2");
+        let answer = agent
+            .chat_with_tools(&id, "what files are there?")
+            .await
+            .unwrap();
+        assert_eq!(answer, "There is one file, notes.txt.");
+        backend.assert_finished();
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.model == "llama3.2"));
+        assert!(requests[0].offers_tool("fs_tool"));
+        assert!(requests[1].messages.len() > requests[0].messages.len());
+        let tool_result = requests[1].last_user_message().unwrap();
+        assert!(tool_result.contains("notes.txt"), "{tool_result}");
+    }
+
+    #[tokio::test]
+    async fn streams_chunks_and_replays_errors() {
+        let backend = MockBackend::script()
+            .responds_with_chunks(["Hel", "lo"])
+            .then_error(KowalskiError::Server("model not loaded".to_string()))
+            .then_chunks(["a", "b"])
+            .build();
+
+        let chunks: Vec<String> = backend
+            .chat_stream("m", Vec::new())
+            .map(|c| c.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks, ["Hel", "lo"]);
+        assert!(backend.chat("m", &[]).await.is_err());
+        assert_eq!(
+            backend
+                .chat_json("m", &[], &ChatOptions::default())
+                .await
+                .unwrap(),
+            "ab"
+        );
+
+        let requests = backend.requests();
+        assert!(requests[0].streamed);
+        assert!(!requests[1].streamed && !requests[1].json_mode);
+        assert!(requests[2].json_mode);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "has no scripted reply")]
+    async fn calls_past_the_script_panic() {
+        let backend = MockBackend::script()
+            .responds_with_text("only once")
+            .build();
+        let _ = backend.chat("m", &[]).await;
+        let _ = backend.chat("m", &[]).await;
+    }
+}
@@ -0,0 +1,177 @@
+//! Split long text into overlapping or paragraph-sized chunks.
+//!
+//! Every [`Chunk`] carries the byte range it covers in the source, and `chunk.text` is always
+//! `&source[start..end]`. Chunks tile the source: the first starts at `0`, the last ends at
+//! `source.len()`, and without overlap each chunk starts where the previous one ended.
+
+use serde::{Deserialize, Serialize};
+
+/// A slice of a larger document, with byte offsets into the original text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    pub text: String,
+    /// Byte offset (inclusive) into the source text.
+    pub start: usize,
+    /// Byte offset (exclusive) into the source text.
+    pub end: usize,
+}
+
+impl Chunk {
+    fn from_source(source: &str, start: usize, end: usize) -> Self {
+        Self {
+            text: source[start..end].to_string(),
+            start,
+            end,
+        }
+    }
+}
+
+/// Byte spans of whitespace-separated words; used as a cheap, model-agnostic token estimate.
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut word_start: Option<usize> = None;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if let Some(s) = word_start.take() {
+                spans.push((s, i));
+            }
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+    if let Some(s) = word_start {
+        spans.push((s, text.len()));
+    }
+    spans
+}
+
+/// Splits `text` into windows of at most `max_tokens` tokens, each sharing `overlap` tokens with
+/// the previous window.
+///
+/// Tokens are approximated as whitespace-separated words. Trailing whitespace belongs to the chunk
+/// before it, so with `overlap == 0` the chunks concatenate back to `text` exactly. `overlap` is
+/// clamped to `max_tokens - 1` so the window always advances; `max_tokens == 0` is treated as `1`.
+pub fn chunk_by_tokens(text: &str, max_tokens: usize, overlap: usize) -> Vec<Chunk> {
+    let words = word_spans(text);
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let max_tokens = max_tokens.max(1);
+    let step = max_tokens - overlap.min(max_tokens - 1);
+
+    let mut chunks = Vec::new();
+    let mut i = 0;
+    loop {
+        let j = (i + max_tokens).min(words.len());
+        let start = if i == 0 { 0 } else { words[i].0 };
+        let end = if j == words.len() {
+            text.len()
+        } else {
+            words[j].0
+        };
+        chunks.push(Chunk::from_source(text, start, end));
+        if j == words.len() {
+            break;
+        }
+        i += step;
+    }
+    chunks
+}
+
+/// Splits `text` on blank lines, one chunk per paragraph.
+///
+/// Blank lines following a paragraph stay in that paragraph's chunk, so the chunks concatenate
+/// back to `text` exactly.
+pub fn chunk_by_paragraphs(text: &str) -> Vec<Chunk> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    let mut prev_blank = true;
+    for line in text.split_inclusive('\n') {
+        let blank = line.trim().is_empty();
+        if !blank && prev_blank {
+            starts.push(offset);
+        }
+        prev_blank = blank;
+        offset += line.len();
+    }
+    if starts.is_empty() {
+        return Vec::new();
+    }
+    starts[0] = 0;
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(idx, &start)| {
+            let end = starts.get(idx + 1).copied().unwrap_or(text.len());
+            Chunk::from_source(text, start, end)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rebuilds the source by keeping each chunk only up to where the next one starts.
+    fn reconstruct(chunks: &[Chunk]) -> String {
+        let mut out = String::new();
+        for (i, c) in chunks.iter().enumerate() {
+            match chunks.get(i + 1) {
+                Some(next) => out.push_str(&c.text[..next.start - c.start]),
+                None => out.push_str(&c.text),
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn tokens_without_overlap_concatenate_to_source() {
+        let text = "one two  three\nfour five six seven ";
+        let chunks = chunk_by_tokens(text, 3, 0);
+        assert_eq!(chunks.len(), 3);
+        let joined: String = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(joined, text);
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks.last().unwrap().end, text.len());
+    }
+
+    #[test]
+    fn tokens_with_overlap_share_words() {
+        let text = "a b c d e f g";
+        let chunks = chunk_by_tokens(text, 4, 2);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].text.trim(), "a b c d");
+        assert_eq!(chunks[1].text.trim(), "c d e f");
+        assert_eq!(chunks[2].text.trim(), "e f g");
+        assert!(chunks[1].start < chunks[0].end);
+        assert_eq!(reconstruct(&chunks), text);
+    }
+
+    #[test]
+    fn tokens_offsets_match_text() {
+        let text = "zażółć gęślą jaźń — unicode words here";
+        for c in chunk_by_tokens(text, 2, 1) {
+            assert_eq!(&text[c.start..c.end], c.text);
+        }
+    }
+
+    #[test]
+    fn tokens_clamps_degenerate_arguments() {
+        let chunks = chunk_by_tokens("x y z", 0, 5);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunk_by_tokens("   ", 10, 0).is_empty());
+    }
+
+    #[test]
+    fn paragraphs_split_on_blank_lines() {
+        let text = "\nFirst para\nstill first.\n\n  \nSecond.\n\nThird";
+        let chunks = chunk_by_paragraphs(text);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].text.contains("still first."));
+        assert_eq!(chunks[1].text.trim(), "Second.");
+        assert_eq!(chunks[2].text, "Third");
+        let joined: String = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(joined, text);
+    }
+}
@@ -0,0 +1,5 @@
+//! Text helpers for preparing long documents before embedding or LLM calls.
+
+pub mod chunk;
+
+pub use chunk::{Chunk, chunk_by_paragraphs, chunk_by_tokens};
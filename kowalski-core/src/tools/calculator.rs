@@ -0,0 +1,247 @@
+use crate::error::KowalskiError;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde_json::json;
+
+/// Evaluates arithmetic so the model doesn't have to: `+ - * / % ^`, parentheses, `pi`, `e` and
+/// `sqrt abs round floor ceil ln log sin cos tan`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CalculatorTool;
+
+impl CalculatorTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Evaluates `expression`; errors name the position that could not be parsed.
+pub fn evaluate(expression: &str) -> Result<f64, KowalskiError> {
+    let mut parser = Parser {
+        chars: expression.chars().collect(),
+        pos: 0,
+    };
+    let value = parser.expression()?;
+    parser.skip_spaces();
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected input"));
+    }
+    if !value.is_finite() {
+        return Err(KowalskiError::ToolExecution(format!(
+            "'{expression}' has no finite value"
+        )));
+    }
+    Ok(value)
+}
+
+/// Recursive descent: expression = term (('+' | '-') term)*, term = unary (('*' | '/' | '%')
+/// unary)*, unary = '-' unary | power, power = atom ('^' unary)?. So `-2^2` is -4 and `^` is
+/// right-associative.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> KowalskiError {
+        KowalskiError::ToolInvalidInput(format!("{message} at position {}", self.pos + 1))
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_spaces();
+        if self.chars.get(self.pos) == Some(&expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<f64, KowalskiError> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, KowalskiError> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(KowalskiError::ToolExecution("division by zero".to_string()));
+                }
+                value /= divisor;
+            } else if self.eat('%') {
+                value %= self.unary()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<f64, KowalskiError> {
+        if self.eat('-') {
+            return Ok(-self.unary()?);
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<f64, KowalskiError> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(base.powf(self.unary()?));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64, KowalskiError> {
+        self.skip_spaces();
+        if self.eat('(') {
+            let value = self.expression()?;
+            if !self.eat(')') {
+                return Err(self.error("expected ')'"));
+            }
+            return Ok(value);
+        }
+        let start = self.pos;
+        match self.chars.get(self.pos) {
+            Some(c) if c.is_ascii_digit() || *c == '.' => {
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+                {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().collect();
+                number.parse().map_err(|_| {
+                    self.pos = start;
+                    self.error("invalid number")
+                })
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| c.is_ascii_alphanumeric())
+                {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                match name.as_str() {
+                    "pi" => return Ok(std::f64::consts::PI),
+                    "e" => return Ok(std::f64::consts::E),
+                    _ => {}
+                }
+                let function: fn(f64) -> f64 = match name.as_str() {
+                    "sqrt" => f64::sqrt,
+                    "abs" => f64::abs,
+                    "round" => f64::round,
+                    "floor" => f64::floor,
+                    "ceil" => f64::ceil,
+                    "ln" => f64::ln,
+                    "log" => f64::log10,
+                    "sin" => f64::sin,
+                    "cos" => f64::cos,
+                    "tan" => f64::tan,
+                    _ => {
+                        self.pos = start;
+                        return Err(self.error(&format!("unknown name '{name}'")));
+                    }
+                };
+                if !self.eat('(') {
+                    return Err(self.error(&format!("expected '(' after {name}")));
+                }
+                let argument = self.expression()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(function(argument))
+            }
+            _ => Err(self.error("expected a number")),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for CalculatorTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let expression = input
+            .parameters
+            .get("expression")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| {
+                KowalskiError::ToolInvalidInput(
+                    "Missing required parameter: expression".to_string(),
+                )
+            })?;
+        let result = evaluate(expression)?;
+        Ok(
+            ToolOutput::new(json!({"expression": expression, "result": result}), None)
+                .with_source(self.name()),
+        )
+    }
+
+    fn name(&self) -> &str {
+        "calculator"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluates an arithmetic expression, e.g. (17.5 * 3) / 4 or sqrt(2) ^ 2. Supports + - * / % ^, parentheses, pi, e and sqrt abs round floor ceil ln log sin cos tan."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![ToolParameter {
+            name: "expression".to_string(),
+            description: "Arithmetic expression to evaluate".to_string(),
+            required: true,
+            default_value: None,
+            parameter_type: ParameterType::String,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_with_precedence_and_functions() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(evaluate("2 ^ -1").unwrap(), 0.5);
+        assert_eq!(evaluate("10 % 4 - 1.5").unwrap(), 0.5);
+        assert_eq!(evaluate("sqrt(16) + abs(-1)").unwrap(), 5.0);
+        assert!((evaluate("cos(pi)").unwrap() + 1.0).abs() < 1e-12);
+
+        assert!(matches!(
+            evaluate("1 / 0"),
+            Err(KowalskiError::ToolExecution(_))
+        ));
+        let err = evaluate("2 * foo(1)").unwrap_err();
+        assert!(err.to_string().contains("unknown name 'foo' at position 5"));
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("1 2").is_err());
+    }
+}
@@ -0,0 +1,1135 @@
+//! Dependency graphs of a Rust workspace for code agents. [`CargoGraphTool`] (`cargo_graph`)
+//! reads the workspace `Cargo.toml` and its members' manifests directly (no `cargo metadata`,
+//! so it works offline and without a lockfile) to build the crate graph, and follows `mod`
+//! declarations from each crate root to build a module graph whose edges are the `use`
+//! statements and `crate::`/`super::`/`self::` paths between modules. It answers
+//! `dependents_of`, `dependencies_of`, `find_cycles` and `path_between`, and can write either
+//! graph as Graphviz DOT.
+
+use crate::error::KowalskiError;
+use crate::tools::fs::{relative, resolve_new, resolve_within};
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Longest list put in a result when `max_items` is not given.
+pub const DEFAULT_MAX_ITEMS: usize = 50;
+/// Dependency tables read from each manifest, and whether they are dev-only.
+const DEPENDENCY_TABLES: &[(&str, bool)] = &[
+    ("dependencies", false),
+    ("build-dependencies", false),
+    ("dev-dependencies", true),
+];
+
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").unwrap());
+static MOD_DECL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(?:r#)?([A-Za-z_]\w*)\s*;").unwrap()
+});
+static USE_DECL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\buse\s+(::)?([A-Za-z_][^;]*);").unwrap());
+static TEST_MODULE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"#\[cfg\(test\)\]\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+\w+\s*\{").unwrap()
+});
+/// A path in code, e.g. `crate::ast::Node` or `dcore::ast::Node::new`.
+static CODE_PATH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Za-z_]\w*(?:::[A-Za-z_]\w*)+").unwrap());
+
+/// Directed graph over crate or module names; edges point from the user to what it uses.
+#[derive(Debug, Default)]
+struct Graph {
+    edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Graph {
+    fn add_node(&mut self, node: &str) {
+        self.edges.entry(node.to_string()).or_default();
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str) {
+        if from == to {
+            return;
+        }
+        self.add_node(to);
+        self.edges
+            .entry(from.to_string())
+            .or_default()
+            .insert(to.to_string());
+    }
+
+    fn contains(&self, node: &str) -> bool {
+        self.edges.contains_key(node)
+    }
+
+    fn reversed(&self) -> Graph {
+        let mut reversed = Graph::default();
+        for (from, targets) in &self.edges {
+            reversed.add_node(from);
+            for to in targets {
+                reversed.add_edge(to, from);
+            }
+        }
+        reversed
+    }
+
+    /// Nodes reachable from `node` (itself excluded), or only its direct successors.
+    fn reachable(&self, node: &str, transitive: bool) -> BTreeSet<String> {
+        let mut seen = BTreeSet::new();
+        let mut queue: VecDeque<&str> = VecDeque::from([node]);
+        while let Some(current) = queue.pop_front() {
+            for next in self.edges.get(current).into_iter().flatten() {
+                if next != node && seen.insert(next.clone()) && transitive {
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen
+    }
+
+    /// A shortest path from `from` to `to`, both included.
+    fn path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut previous: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(current) = queue.pop_front() {
+            for next in self.edges.get(current).into_iter().flatten() {
+                if next == from || previous.contains_key(next.as_str()) {
+                    continue;
+                }
+                previous.insert(next, current);
+                if next == to {
+                    let mut path = vec![to.to_string()];
+                    let mut step = to;
+                    while let Some(&prev) = previous.get(step) {
+                        path.push(prev.to_string());
+                        step = prev;
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    /// Strongly connected components with more than one node (Tarjan), each sorted.
+    fn cycles(&self) -> Vec<Vec<String>> {
+        struct Tarjan<'a> {
+            graph: &'a Graph,
+            index: HashMap<&'a str, usize>,
+            low: HashMap<&'a str, usize>,
+            stack: Vec<&'a str>,
+            on_stack: BTreeSet<&'a str>,
+            components: Vec<Vec<String>>,
+        }
+
+        impl<'a> Tarjan<'a> {
+            fn visit(&mut self, node: &'a str) {
+                let index = self.index.len();
+                self.index.insert(node, index);
+                self.low.insert(node, index);
+                self.stack.push(node);
+                self.on_stack.insert(node);
+                let graph = self.graph;
+                for next in graph.edges.get(node).into_iter().flatten() {
+                    let next = next.as_str();
+                    if !self.index.contains_key(next) {
+                        self.visit(next);
+                        let low = self.low[node].min(self.low[next]);
+                        self.low.insert(node, low);
+                    } else if self.on_stack.contains(next) {
+                        let low = self.low[node].min(self.index[next]);
+                        self.low.insert(node, low);
+                    }
+                }
+                if self.low[node] == self.index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = self.stack.pop() {
+                        self.on_stack.remove(member);
+                        component.push(member.to_string());
+                        if member == node {
+                            break;
+                        }
+                    }
+                    if component.len() > 1 {
+                        component.sort();
+                        self.components.push(component);
+                    }
+                }
+            }
+        }
+
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: HashMap::new(),
+            low: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            components: Vec::new(),
+        };
+        for node in self.edges.keys() {
+            if !tarjan.index.contains_key(node.as_str()) {
+                tarjan.visit(node);
+            }
+        }
+        let mut components = tarjan.components;
+        components.sort();
+        components
+    }
+
+    /// One shortest cycle through `node`, starting and ending with it.
+    fn cycle_through(&self, node: &str) -> Option<Vec<String>> {
+        self.edges
+            .get(node)?
+            .iter()
+            .filter_map(|next| self.path(next, node))
+            .min_by_key(Vec::len)
+            .map(|mut path| {
+                path.insert(0, node.to_string());
+                path
+            })
+    }
+
+    fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph \"{name}\" {{\n    rankdir=LR;\n");
+        for (from, targets) in &self.edges {
+            if targets.is_empty() {
+                let _ = writeln!(dot, "    \"{from}\";");
+            }
+            for to in targets {
+                let _ = writeln!(dot, "    \"{from}\" -> \"{to}\";");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// A workspace member as read from its manifest.
+#[derive(Debug)]
+struct Member {
+    name: String,
+    dir: PathBuf,
+    /// The crate's name in Rust paths (`[lib] name`, or the package name with `_` for `-`).
+    lib_name: String,
+    /// Dependency names as written in code (`dcore` for `dcore = { package = "demo-core" }`),
+    /// with the package each refers to and whether it is dev-only.
+    dependencies: Vec<(String, String, bool)>,
+}
+
+/// Crate and module graphs of one workspace.
+#[derive(Debug)]
+struct WorkspaceGraph {
+    members: Vec<Member>,
+    /// Workspace crates, with edges for dependencies between them.
+    crates: Graph,
+    /// Dependencies on crates outside the workspace, per member.
+    external: BTreeMap<String, BTreeSet<String>>,
+    /// Modules (`demo_core::ast::visit`), with edges for imports between them.
+    modules: Graph,
+}
+
+fn read_manifest(path: &Path) -> Result<toml::Value, KowalskiError> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        KowalskiError::ToolExecution(format!("cannot read {}: {e}", path.display()))
+    })?;
+    text.parse::<toml::Value>()
+        .map_err(|e| KowalskiError::ToolExecution(format!("cannot parse {}: {e}", path.display())))
+}
+
+/// Whether `name` matches a `members` pattern segment with `*` wildcards.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !name.starts_with(first) || name.len() < first.len() + last.len() || !name.ends_with(last) {
+        return false;
+    }
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Member directories named by `[workspace] members` (last path segment may hold `*`), minus
+/// `exclude`; the root itself when it also has a `[package]`.
+fn member_dirs(root: &Path, manifest: &toml::Value) -> Vec<PathBuf> {
+    let strings = |key: &str| -> Vec<String> {
+        manifest
+            .get("workspace")
+            .and_then(|w| w.get(key))
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.trim_end_matches('/').to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let excluded: BTreeSet<PathBuf> = strings("exclude").iter().map(|e| root.join(e)).collect();
+    let mut dirs = BTreeSet::new();
+    if manifest.get("package").is_some() {
+        dirs.insert(root.to_path_buf());
+    }
+    for pattern in strings("members") {
+        let (parent, last) = match pattern.rsplit_once('/') {
+            Some((parent, last)) => (root.join(parent), last.to_string()),
+            None => (root.to_path_buf(), pattern.clone()),
+        };
+        if !last.contains('*') {
+            dirs.insert(parent.join(last));
+            continue;
+        }
+        for entry in std::fs::read_dir(&parent).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if wildcard_match(&last, &name) && entry.path().join("Cargo.toml").is_file() {
+                dirs.insert(entry.path());
+            }
+        }
+    }
+    dirs.into_iter()
+        .filter(|dir| !excluded.contains(dir) && dir.join("Cargo.toml").is_file())
+        .collect()
+}
+
+fn read_member(dir: &Path) -> Result<Member, KowalskiError> {
+    let manifest = read_manifest(&dir.join("Cargo.toml"))?;
+    let name = manifest
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .ok_or_else(|| {
+            KowalskiError::ToolExecution(format!(
+                "{} has no [package] name",
+                dir.join("Cargo.toml").display()
+            ))
+        })?
+        .to_string();
+    let lib_name = manifest
+        .get("lib")
+        .and_then(|l| l.get("name"))
+        .and_then(|n| n.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| name.replace('-', "_"));
+    let mut tables: Vec<(&toml::Value, bool)> = Vec::new();
+    for (key, dev) in DEPENDENCY_TABLES {
+        if let Some(table) = manifest.get(*key) {
+            tables.push((table, *dev));
+        }
+        // `[target.'cfg(..)'.dependencies]`
+        for target in manifest
+            .get("target")
+            .and_then(|t| t.as_table())
+            .into_iter()
+            .flat_map(|t| t.values())
+        {
+            if let Some(table) = target.get(*key) {
+                tables.push((table, *dev));
+            }
+        }
+    }
+    let mut dependencies = Vec::new();
+    for (table, dev) in tables {
+        for (key, spec) in table.as_table().into_iter().flatten() {
+            let package = spec.get("package").and_then(|p| p.as_str()).unwrap_or(key);
+            dependencies.push((key.replace('-', "_"), package.to_string(), dev));
+        }
+    }
+    Ok(Member {
+        name,
+        dir: dir.to_path_buf(),
+        lib_name,
+        dependencies,
+    })
+}
+
+/// The file holding module `child`, declared in `file`.
+fn child_module_file(file: &Path, child: &str) -> Option<PathBuf> {
+    let dir = file.parent()?;
+    let owns_dir = matches!(
+        file.file_name().and_then(|n| n.to_str()),
+        Some("lib.rs" | "main.rs" | "mod.rs")
+    );
+    let dir = if owns_dir {
+        dir.to_path_buf()
+    } else {
+        dir.join(file.file_stem()?)
+    };
+    [
+        dir.join(format!("{child}.rs")),
+        dir.join(child).join("mod.rs"),
+    ]
+    .into_iter()
+    .find(|f| f.is_file())
+}
+
+/// `source` without inline `#[cfg(test)] mod .. { .. }` blocks, whose imports would make
+/// every tested module look coupled to what its tests use.
+fn strip_test_modules(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(found) = TEST_MODULE.find(rest) {
+        out.push_str(&rest[..found.start()]);
+        let mut depth = 1;
+        let body = &rest[found.end()..];
+        let end = body
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .map_or(body.len(), |(i, _)| i + 1);
+        rest = &body[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A module's path segments and its source without comments or test modules.
+type ModuleSource = (Vec<String>, String);
+
+/// Modules reachable through `mod x;` declarations from the crate root `file`.
+fn discover_modules(segments: Vec<String>, file: &Path, out: &mut Vec<ModuleSource>) {
+    let Ok(source) = std::fs::read_to_string(file) else {
+        return;
+    };
+    let source = strip_test_modules(&COMMENT.replace_all(&source, ""));
+    let children: Vec<String> = MOD_DECL
+        .captures_iter(&source)
+        .map(|c| c[1].to_string())
+        .collect();
+    out.push((segments.clone(), source));
+    for child in children {
+        if let Some(child_file) = child_module_file(file, &child) {
+            let mut child_segments = segments.clone();
+            child_segments.push(child);
+            discover_modules(child_segments, &child_file, out);
+        }
+    }
+}
+
+/// Paths named by a `use` tree: `a::{b, c::{self, D}}` gives `a::b`, `a::c`, `a::c::D`.
+fn expand_use_tree(tree: &str, prefix: &[String], out: &mut Vec<Vec<String>>) {
+    let tree = tree.trim();
+    if let Some(open) = tree.find('{') {
+        let mut path = prefix.to_vec();
+        path.extend(split_path(tree[..open].trim().trim_end_matches("::")));
+        let inner = tree[open + 1..].trim_end().trim_end_matches('}');
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in inner.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    expand_use_tree(&inner[start..i], &path, out);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        expand_use_tree(&inner[start..], &path, out);
+        return;
+    }
+    let tree = tree.split(" as ").next().unwrap_or_default().trim();
+    if tree.is_empty() {
+        return;
+    }
+    let mut path = prefix.to_vec();
+    path.extend(split_path(tree));
+    if matches!(path.last().map(String::as_str), Some("self" | "*")) {
+        path.pop();
+    }
+    if !path.is_empty() {
+        out.push(path);
+    }
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    path.split("::")
+        .map(|s| s.trim().trim_start_matches("r#"))
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Collects the workspace at `root`: members, their dependencies and their modules.
+fn build_graph(root: &Path, include_dev: bool) -> Result<WorkspaceGraph, KowalskiError> {
+    let manifest_path = root.join("Cargo.toml");
+    if !manifest_path.is_file() {
+        return Err(KowalskiError::ToolInvalidInput(format!(
+            "no Cargo.toml in {}",
+            root.display()
+        )));
+    }
+    let manifest = read_manifest(&manifest_path)?;
+    let members = member_dirs(root, &manifest)
+        .iter()
+        .map(|dir| read_member(dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    let names: HashMap<&str, &Member> = members.iter().map(|m| (m.name.as_str(), m)).collect();
+
+    let mut crates = Graph::default();
+    let mut external: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for member in &members {
+        crates.add_node(&member.name);
+        for (_, package, dev) in &member.dependencies {
+            if names.contains_key(package.as_str()) {
+                if include_dev || !dev {
+                    crates.add_edge(&member.name, package);
+                }
+            } else if !dev {
+                external
+                    .entry(member.name.clone())
+                    .or_default()
+                    .insert(package.clone());
+            }
+        }
+    }
+
+    let mut parsed: Vec<(&Member, Vec<ModuleSource>)> = Vec::new();
+    let mut modules = Graph::default();
+    for member in &members {
+        let entry = ["src/lib.rs", "src/main.rs"]
+            .iter()
+            .map(|f| member.dir.join(f))
+            .find(|f| f.is_file());
+        let mut found = Vec::new();
+        if let Some(entry) = entry {
+            discover_modules(vec![member.lib_name.clone()], &entry, &mut found);
+        }
+        for (segments, _) in &found {
+            modules.add_node(&segments.join("::"));
+        }
+        parsed.push((member, found));
+    }
+
+    for (member, found) in &parsed {
+        // Crates this one can name in paths, by the name it uses for them.
+        let mut externs: HashMap<&str, &str> = HashMap::new();
+        externs.insert(&member.lib_name, &member.lib_name);
+        for (alias, package, _) in &member.dependencies {
+            if let Some(target) = names.get(package.as_str()) {
+                externs.insert(alias, &target.lib_name);
+            }
+        }
+        for (segments, source) in found {
+            let from = segments.join("::");
+            let mut paths = Vec::new();
+            for capture in USE_DECL.captures_iter(source) {
+                expand_use_tree(&capture[2], &[], &mut paths);
+            }
+            let code = USE_DECL.replace_all(source, "");
+            paths.extend(CODE_PATH.find_iter(&code).map(|m| split_path(m.as_str())));
+            for path in paths {
+                if let Some(to) = resolve_module(&modules, segments, &externs, &path) {
+                    modules.add_edge(&from, &to);
+                }
+            }
+        }
+    }
+
+    Ok(WorkspaceGraph {
+        members,
+        crates,
+        external,
+        modules,
+    })
+}
+
+/// The module `path` (as written in module `current`) refers to: the longest prefix of the
+/// absolute path that is a known module. `None` for paths outside the workspace.
+fn resolve_module(
+    modules: &Graph,
+    current: &[String],
+    externs: &HashMap<&str, &str>,
+    path: &[String],
+) -> Option<String> {
+    let first = path.first()?.as_str();
+    let (mut absolute, rest): (Vec<String>, &[String]) = match first {
+        "crate" => (vec![current[0].clone()], &path[1..]),
+        "self" => (current.to_vec(), &path[1..]),
+        "super" => {
+            let ups = path.iter().take_while(|s| *s == "super").count();
+            if ups >= current.len() {
+                return None;
+            }
+            (current[..current.len() - ups].to_vec(), &path[ups..])
+        }
+        _ => {
+            let mut child = current.to_vec();
+            child.push(first.to_string());
+            if modules.contains(&child.join("::")) {
+                (current.to_vec(), path)
+            } else {
+                (vec![externs.get(first)?.to_string()], &path[1..])
+            }
+        }
+    };
+    absolute.extend(rest.iter().cloned());
+    (1..=absolute.len())
+        .rev()
+        .map(|len| absolute[..len].join("::"))
+        .find(|id| modules.contains(id))
+        .filter(|id| *id != current.join("::"))
+}
+
+/// Dependency and module graph queries over a Rust workspace under `root`.
+pub struct CargoGraphTool {
+    root: PathBuf,
+}
+
+impl CargoGraphTool {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+fn string_param<'a>(params: &'a Value, key: &str) -> Option<&'a str> {
+    params
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+fn bool_param(params: &Value, key: &str, default: bool) -> Result<bool, KowalskiError> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(default),
+        Some(Value::Bool(b)) => Ok(*b),
+        Some(Value::String(s)) if s.trim() == "true" => Ok(true),
+        Some(Value::String(s)) if s.trim() == "false" => Ok(false),
+        Some(other) => Err(KowalskiError::ToolInvalidInput(format!(
+            "'{key}' must be true or false, got {other}"
+        ))),
+    }
+}
+
+/// Up to `max` items, with how many were left out.
+fn capped(items: impl IntoIterator<Item = String>, max: usize) -> (Vec<String>, usize) {
+    let items: Vec<String> = items.into_iter().collect();
+    let more = items.len().saturating_sub(max);
+    (items.into_iter().take(max).collect(), more)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Crate,
+    Module,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Crate => "crate",
+            Level::Module => "module",
+        }
+    }
+}
+
+impl WorkspaceGraph {
+    fn graph(&self, level: Level) -> &Graph {
+        match level {
+            Level::Crate => &self.crates,
+            Level::Module => &self.modules,
+        }
+    }
+
+    /// The node `name` refers to: a crate by package or lib name, a module by its full path
+    /// (`demo-core::ast` and `demo_core::ast` both work) or by a suffix only one module has.
+    fn node(&self, level: Level, name: &str) -> Result<String, KowalskiError> {
+        let graph = self.graph(level);
+        if graph.contains(name) {
+            return Ok(name.to_string());
+        }
+        let found = match level {
+            Level::Crate => self
+                .members
+                .iter()
+                .find(|m| m.lib_name == name || m.name.replace('-', "_") == name.replace('-', "_"))
+                .map(|m| m.name.clone()),
+            Level::Module => {
+                let (head, tail) = name.split_once("::").unwrap_or((name, ""));
+                let head = self
+                    .members
+                    .iter()
+                    .find(|m| m.name == head || m.lib_name == head.replace('-', "_"))
+                    .map_or(head.to_string(), |m| m.lib_name.clone());
+                let full = if tail.is_empty() {
+                    head
+                } else {
+                    format!("{head}::{tail}")
+                };
+                if graph.contains(&full) {
+                    Some(full)
+                } else {
+                    let suffix = format!("::{name}");
+                    let matches: Vec<&String> = graph
+                        .edges
+                        .keys()
+                        .filter(|id| id.ends_with(&suffix))
+                        .collect();
+                    match matches.as_slice() {
+                        [only] => Some((*only).clone()),
+                        [] => None,
+                        several => {
+                            return Err(KowalskiError::ToolInvalidInput(format!(
+                                "'{name}' matches several modules: {}",
+                                several
+                                    .iter()
+                                    .map(|s| s.as_str())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            )));
+                        }
+                    }
+                }
+            }
+        };
+        found.ok_or_else(|| {
+            let known: Vec<&str> = graph.edges.keys().take(10).map(String::as_str).collect();
+            KowalskiError::ToolInvalidInput(format!(
+                "no {} named '{name}' in the workspace (e.g. {})",
+                level.as_str(),
+                known.join(", ")
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for CargoGraphTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let params = &input.parameters;
+        let task = match input.task_type.as_str() {
+            "default" => "find_cycles",
+            task @ ("dependents_of" | "dependencies_of" | "find_cycles" | "path_between") => task,
+            other => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "Unknown task '{other}' for cargo_graph; use dependents_of, dependencies_of, \
+                     find_cycles or path_between"
+                )));
+            }
+        };
+        let path = string_param(params, "path").unwrap_or(".");
+        let (root, dir) = resolve_within(&self.root, path, self.name())?;
+        let include_dev = bool_param(params, "include_dev", false)?;
+        let transitive = bool_param(params, "transitive", false)?;
+        let max = match params.get("max_items") {
+            None | Some(Value::Null) => DEFAULT_MAX_ITEMS,
+            Some(value) => value
+                .as_u64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .filter(|n| *n > 0)
+                .ok_or_else(|| {
+                    KowalskiError::ToolInvalidInput(format!(
+                        "'max_items' must be a positive integer, got {value}"
+                    ))
+                })? as usize,
+        };
+        let explicit_level = match string_param(params, "level") {
+            None => None,
+            Some("crate") => Some(Level::Crate),
+            Some("module") => Some(Level::Module),
+            Some(other) => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "'level' must be crate or module, got '{other}'"
+                )));
+            }
+        };
+        // Names with `::` are modules unless the caller says otherwise.
+        let level_of = |name: &str| {
+            explicit_level.unwrap_or(if name.contains("::") {
+                Level::Module
+            } else {
+                Level::Crate
+            })
+        };
+        let required = |key: &str| {
+            string_param(params, key).ok_or_else(|| {
+                KowalskiError::ToolInvalidInput(format!("'{key}' is required for {task}"))
+            })
+        };
+
+        let workspace = build_graph(&dir, include_dev)?;
+        let (result, level) = match task {
+            "dependents_of" | "dependencies_of" => {
+                let level = level_of(required("target")?);
+                let target = workspace.node(level, required("target")?)?;
+                let graph = workspace.graph(level);
+                let found = if task == "dependents_of" {
+                    graph.reversed().reachable(&target, transitive)
+                } else {
+                    graph.reachable(&target, transitive)
+                };
+                let (items, more) = capped(found, max);
+                let mut result = json!({
+                    "level": level.as_str(),
+                    "target": target,
+                    "transitive": transitive,
+                });
+                result[task.trim_end_matches("_of")] = json!(items);
+                if more > 0 {
+                    result["more"] = json!(more);
+                }
+                if task == "dependencies_of" && level == Level::Crate {
+                    let (items, more) = capped(
+                        workspace.external.get(&target).cloned().unwrap_or_default(),
+                        max,
+                    );
+                    result["external"] = json!(items);
+                    if more > 0 {
+                        result["more_external"] = json!(more);
+                    }
+                }
+                (result, Some(level))
+            }
+            "path_between" => {
+                let (from, to) = (required("from")?, required("to")?);
+                let level = explicit_level.unwrap_or(if from.contains("::") || to.contains("::") {
+                    Level::Module
+                } else {
+                    Level::Crate
+                });
+                let (from, to) = (workspace.node(level, from)?, workspace.node(level, to)?);
+                let path = workspace.graph(level).path(&from, &to);
+                (
+                    json!({"level": level.as_str(), "from": from, "to": to, "path": path}),
+                    Some(level),
+                )
+            }
+            _ => {
+                let levels = match explicit_level {
+                    Some(level) => vec![level],
+                    None => vec![Level::Crate, Level::Module],
+                };
+                let mut result = json!({});
+                for level in &levels {
+                    let graph = workspace.graph(*level);
+                    let cycles = graph.cycles();
+                    let total = cycles.len();
+                    let shown: Vec<Value> = cycles
+                        .into_iter()
+                        .take(max)
+                        .map(|members| {
+                            let cycle = graph.cycle_through(&members[0]);
+                            let (members, more) = capped(members, max);
+                            let mut entry = json!({"members": members, "cycle": cycle});
+                            if more > 0 {
+                                entry["more"] = json!(more);
+                            }
+                            entry
+                        })
+                        .collect();
+                    result[format!("{}_cycles", level.as_str())] = json!(shown);
+                    if total > max {
+                        result[format!("more_{}_cycles", level.as_str())] = json!(total - max);
+                    }
+                }
+                (result, explicit_level)
+            }
+        };
+
+        let mut result = result;
+        if let Some(dot_path) = string_param(params, "dot") {
+            let level = level.unwrap_or(Level::Module);
+            let (_, file) = resolve_new(&root, dot_path, self.name())?;
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file, workspace.graph(level).to_dot(level.as_str()))?;
+            result["dot"] = json!(relative(&root, &file));
+        }
+        let workspace_label = relative(&root, &dir);
+        Ok(ToolOutput::new(
+            result,
+            Some(json!({
+                "task": task,
+                "workspace": workspace_label,
+                "crates": workspace.crates.edges.len(),
+                "modules": workspace.modules.edges.len(),
+            })),
+        )
+        .with_source(workspace_label))
+    }
+
+    fn name(&self) -> &str {
+        "cargo_graph"
+    }
+
+    fn description(&self) -> &str {
+        "Dependency graphs of a Rust workspace: crates (from Cargo.toml files) and modules \
+         (from mod/use statements). Tasks: dependents_of and dependencies_of a 'target' crate \
+         or module (a::b), find_cycles, path_between 'from' and 'to'. 'dot' writes the graph \
+         to a Graphviz file."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        let optional = |name: &str, description: &str, default: Option<&str>, ty| ToolParameter {
+            name: name.to_string(),
+            description: description.to_string(),
+            required: false,
+            default_value: default.map(str::to_string),
+            parameter_type: ty,
+        };
+        vec![
+            optional(
+                "task",
+                "dependents_of, dependencies_of, find_cycles or path_between",
+                Some("find_cycles"),
+                ParameterType::String,
+            ),
+            optional(
+                "path",
+                "Workspace directory relative to the project root",
+                Some("."),
+                ParameterType::String,
+            ),
+            optional(
+                "target",
+                "Crate (kowalski-core) or module (kowalski_core::agent) to query",
+                None,
+                ParameterType::String,
+            ),
+            optional("from", "Start of path_between", None, ParameterType::String),
+            optional("to", "End of path_between", None, ParameterType::String),
+            optional(
+                "level",
+                "crate or module; by default names with :: are modules",
+                None,
+                ParameterType::String,
+            ),
+            optional(
+                "transitive",
+                "Include indirect dependents or dependencies",
+                Some("false"),
+                ParameterType::Boolean,
+            ),
+            optional(
+                "include_dev",
+                "Count dev-dependencies between workspace crates",
+                Some("false"),
+                ParameterType::Boolean,
+            ),
+            optional(
+                "max_items",
+                "Longest list returned",
+                Some(&DEFAULT_MAX_ITEMS.to_string()),
+                ParameterType::Number,
+            ),
+            optional(
+                "dot",
+                "File to write the graph to in DOT format",
+                None,
+                ParameterType::String,
+            ),
+        ]
+    }
+
+    /// Only writing a DOT file changes anything.
+    fn is_destructive(&self, parameters: &Value) -> bool {
+        string_param(parameters, "dot").is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/workspace");
+
+    async fn run(params: Value) -> Result<ToolOutput, KowalskiError> {
+        CargoGraphTool::new(FIXTURE)
+            .execute(ToolInput::from_parameters(params))
+            .await
+    }
+
+    #[tokio::test]
+    async fn crate_dependents_and_dependencies() {
+        let out = run(json!({"task": "dependents_of", "target": "demo-core"}))
+            .await
+            .unwrap();
+        assert_eq!(out.result["dependents"], json!(["demo-app", "demo-util"]));
+
+        // The excluded `scratch` crate depends on demo-app but is not a member.
+        let out = run(json!({"task": "dependents_of", "target": "demo_util", "transitive": true}))
+            .await
+            .unwrap();
+        assert_eq!(out.result["target"], "demo-util");
+        assert_eq!(out.result["dependents"], json!(["demo-app"]));
+
+        let out = run(json!({"task": "dependencies_of", "target": "demo-util"}))
+            .await
+            .unwrap();
+        assert_eq!(out.result["dependencies"], json!(["demo-core"]));
+        assert_eq!(out.result["external"], json!(["regex"]));
+        let out = run(json!({"task": "dependencies_of", "target": "demo-app", "max_items": 1}))
+            .await
+            .unwrap();
+        assert_eq!(out.result["dependencies"], json!(["demo-core"]));
+        assert_eq!(out.result["more"], 1);
+        assert_eq!(out.result["external"], json!(["cc"]));
+
+        let err = run(json!({"task": "dependents_of", "target": "demo-web"}))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("no crate named 'demo-web'"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn finds_module_cycles_and_dev_dependency_cycles() {
+        let out = run(json!({"task": "find_cycles"})).await.unwrap();
+        assert_eq!(out.result["crate_cycles"], json!([]));
+        let cycles = out.result["module_cycles"].as_array().unwrap();
+        assert_eq!(cycles.len(), 1, "{cycles:?}");
+        // parser -> ast::visit -> ast -> parser, and ast <-> parser directly.
+        assert_eq!(
+            cycles[0]["members"],
+            json!([
+                "demo_core::ast",
+                "demo_core::ast::visit",
+                "demo_core::parser"
+            ])
+        );
+        assert_eq!(
+            cycles[0]["cycle"],
+            json!(["demo_core::ast", "demo_core::parser", "demo_core::ast"])
+        );
+
+        // demo-core's tests use demo-util, which depends on demo-core.
+        let out = run(json!({"task": "find_cycles", "level": "crate", "include_dev": true}))
+            .await
+            .unwrap();
+        assert_eq!(
+            out.result["crate_cycles"][0]["members"],
+            json!(["demo-core", "demo-util"])
+        );
+        assert!(out.result.get("module_cycles").is_none());
+    }
+
+    #[tokio::test]
+    async fn module_queries_follow_imports_across_crates() {
+        // `fmt` names `dcore::ast::Node`, demo-core under its renamed dependency key.
+        let out = run(json!({"task": "dependents_of", "target": "demo-core::ast"}))
+            .await
+            .unwrap();
+        assert_eq!(out.result["level"], "module");
+        assert_eq!(
+            out.result["dependents"],
+            json!([
+                "demo_core",
+                "demo_core::ast::visit",
+                "demo_core::parser",
+                "demo_util::fmt"
+            ])
+        );
+        let out = run(json!({"task": "dependencies_of", "target": "demo_core::parser"}))
+            .await
+            .unwrap();
+        // The commented-out `use crate::lexer::Lexer` is not counted.
+        assert_eq!(
+            out.result["dependencies"],
+            json!(["demo_core::ast", "demo_core::ast::visit", "demo_core::span"])
+        );
+
+        // `span` is the only module by that name.
+        // The unit tests in span.rs use the parser; that is not a dependency.
+        let out = run(json!({"task": "dependencies_of", "target": "demo_core::span"}))
+            .await
+            .unwrap();
+        assert_eq!(out.result["dependencies"], json!([]));
+
+        let out = run(json!({"task": "path_between", "from": "demo_app::cli", "to": "span"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            out.result["path"],
+            json!(["demo_app::cli", "demo_core::parser", "demo_core::span"])
+        );
+        let out = run(json!({"task": "path_between", "from": "demo-core", "to": "demo-app"}))
+            .await
+            .unwrap();
+        assert_eq!(out.result["path"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn writes_dot_files_inside_the_root_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tool = CargoGraphTool::new(dir.path());
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"a\", \"b\"]\n",
+        )
+        .unwrap();
+        for (name, deps) in [("a", "b = { path = \"../b\" }"), ("b", "")] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+            std::fs::write(
+                dir.path().join(name).join("Cargo.toml"),
+                format!("[package]\nname = \"{name}\"\n\n[dependencies]\n{deps}\n"),
+            )
+            .unwrap();
+        }
+
+        let params = json!({"task": "find_cycles", "level": "crate", "dot": "graphs/crates.dot"});
+        assert!(tool.is_destructive(&params));
+        assert!(!tool.is_destructive(&json!({"task": "find_cycles"})));
+        let out = tool
+            .execute(ToolInput::from_parameters(params))
+            .await
+            .unwrap();
+        assert_eq!(out.result["dot"], "graphs/crates.dot");
+        let dot = std::fs::read_to_string(dir.path().join("graphs/crates.dot")).unwrap();
+        assert!(dot.contains("\"a\" -> \"b\";"), "{dot}");
+        assert!(dot.contains("\"b\";"), "{dot}");
+
+        let err = tool
+            .execute(ToolInput::from_parameters(
+                json!({"task": "find_cycles", "dot": "../out.dot"}),
+            ))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)), "{err}");
+    }
+
+    #[test]
+    fn use_trees_expand_to_paths() {
+        let mut paths = Vec::new();
+        expand_use_tree(
+            "crate::ast::{self, visit::{Visitor as V, *}, Node}",
+            &[],
+            &mut paths,
+        );
+        let paths: Vec<String> = paths.iter().map(|p| p.join("::")).collect();
+        assert_eq!(
+            paths,
+            [
+                "crate::ast",
+                "crate::ast::visit::Visitor",
+                "crate::ast::visit",
+                "crate::ast::Node"
+            ]
+        );
+    }
+}
@@ -0,0 +1,978 @@
+use crate::config::ChartConfig;
+use crate::error::KowalskiError;
+use crate::tools::csv::{CsvFormat, invalid};
+use crate::tools::fs::{resolve_new, resolve_within};
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use plotters::prelude::*;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Rows drawn in one chart; past this a histogram is the readable choice anyway.
+const MAX_ROWS: usize = 50_000;
+const DEFAULT_BINS: usize = 10;
+const MAX_BINS: usize = 200;
+const MAX_SIDE: u32 = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Line,
+    Bar,
+    Scatter,
+    Histogram,
+}
+
+impl Kind {
+    fn parse(name: &str) -> Result<Self, KowalskiError> {
+        match name {
+            "line" => Ok(Self::Line),
+            "bar" => Ok(Self::Bar),
+            "scatter" => Ok(Self::Scatter),
+            "histogram" => Ok(Self::Histogram),
+            other => Err(KowalskiError::ToolInvalidInput(format!(
+                "Unknown chart '{other}' (expected line, bar, scatter or histogram)"
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Line => "line",
+            Self::Bar => "bar",
+            Self::Scatter => "scatter",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+/// Rows as strings, from inline JSON objects or a CSV file.
+struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn from_rows(rows: &[Value]) -> Result<Self, KowalskiError> {
+        let mut columns: Vec<String> = Vec::new();
+        for row in rows {
+            let object = row.as_object().ok_or_else(|| {
+                KowalskiError::ToolInvalidInput("rows must be JSON objects".to_string())
+            })?;
+            for key in object.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+        let rows = rows
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|c| match row.get(c) {
+                        None | Some(Value::Null) => String::new(),
+                        Some(Value::String(s)) => s.clone(),
+                        Some(other) => other.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Self { columns, rows })
+    }
+
+    fn from_csv(path: &Path, format: CsvFormat) -> Result<Self, KowalskiError> {
+        let mut reader = format.reader(std::io::BufReader::new(std::fs::File::open(path)?));
+        let columns = format.columns(&mut reader)?;
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(invalid)?;
+            rows.push(record.iter().map(str::to_string).collect());
+            if rows.len() > MAX_ROWS {
+                break;
+            }
+        }
+        Ok(Self { columns, rows })
+    }
+
+    fn index(&self, name: &str) -> Result<usize, KowalskiError> {
+        self.columns.iter().position(|c| c == name).ok_or_else(|| {
+            KowalskiError::ToolInvalidInput(format!(
+                "Unknown column '{name}' (columns: {})",
+                self.columns.join(", ")
+            ))
+        })
+    }
+
+    fn cell(&self, row: usize, column: usize) -> &str {
+        self.rows[row].get(column).map_or("", |c| c.trim())
+    }
+
+    /// The number in a cell; `None` when it is empty, an error when it is text.
+    fn number(&self, row: usize, column: usize) -> Result<Option<f64>, KowalskiError> {
+        let cell = self.cell(row, column);
+        if cell.is_empty() {
+            return Ok(None);
+        }
+        match cell.parse::<f64>() {
+            Ok(x) if x.is_finite() => Ok(Some(x)),
+            Ok(_) => Ok(None),
+            Err(_) => Err(KowalskiError::ToolInvalidInput(format!(
+                "Column '{}' is not numeric (row {}: '{cell}')",
+                self.columns[column],
+                row + 1
+            ))),
+        }
+    }
+
+    /// Row indices per series: one unnamed series, or one per distinct `group` value in
+    /// first-seen order.
+    fn series(&self, group: Option<usize>) -> Vec<(Option<String>, Vec<usize>)> {
+        let Some(group) = group else {
+            return vec![(None, (0..self.rows.len()).collect())];
+        };
+        let mut series: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+        for row in 0..self.rows.len() {
+            let name = self.cell(row, group);
+            match series.iter_mut().find(|(n, _)| n.as_deref() == Some(name)) {
+                Some((_, rows)) => rows.push(row),
+                None => series.push((Some(name.to_string()), vec![row])),
+            }
+        }
+        series
+    }
+}
+
+/// What is drawn for one series.
+enum Marks {
+    Line(Vec<(f64, f64)>),
+    Points(Vec<(f64, f64)>),
+    /// `(x0, x1, height)`
+    Bars(Vec<(f64, f64, f64)>),
+}
+
+impl Marks {
+    fn len(&self) -> usize {
+        match self {
+            Marks::Line(p) | Marks::Points(p) => p.len(),
+            Marks::Bars(b) => b.len(),
+        }
+    }
+}
+
+/// A chart reduced to axes and marks, ready to draw.
+struct Plot {
+    x: (f64, f64),
+    y: (f64, f64),
+    /// Names for the integer x positions when x is categorical.
+    categories: Option<Vec<String>>,
+    series: Vec<(Option<String>, Marks)>,
+}
+
+struct Labels {
+    title: String,
+    x: String,
+    y: String,
+}
+
+/// Positions of the x values: the numbers themselves, or 0, 1, ... for distinct labels when any
+/// cell is not a number.
+fn x_positions(table: &Table, x: usize) -> (Vec<Option<f64>>, Option<Vec<String>>) {
+    let numbers: Vec<Option<f64>> = (0..table.rows.len())
+        .map(|r| table.number(r, x).ok().flatten())
+        .collect();
+    let categorical =
+        (0..table.rows.len()).any(|r| !table.cell(r, x).is_empty() && numbers[r].is_none());
+    if !categorical {
+        return (numbers, None);
+    }
+    let mut categories: Vec<String> = Vec::new();
+    let positions = (0..table.rows.len())
+        .map(|r| {
+            let cell = table.cell(r, x);
+            if cell.is_empty() {
+                return None;
+            }
+            let i = categories
+                .iter()
+                .position(|c| c == cell)
+                .unwrap_or_else(|| {
+                    categories.push(cell.to_string());
+                    categories.len() - 1
+                });
+            Some(i as f64)
+        })
+        .collect();
+    (positions, Some(categories))
+}
+
+/// `(min, max)` widened so a single value or a flat line still gets an axis.
+fn span(values: impl Iterator<Item = f64>) -> Option<(f64, f64)> {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+        (lo.min(v), hi.max(v))
+    });
+    if min > max {
+        return None;
+    }
+    if min == max {
+        return Some((min - 1.0, max + 1.0));
+    }
+    Some((min, max))
+}
+
+fn padded((min, max): (f64, f64)) -> (f64, f64) {
+    let pad = (max - min) * 0.05;
+    (min - pad, max + pad)
+}
+
+/// Bar heights start at zero.
+fn bar_range(heights: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = span(heights.chain([0.0])).unwrap_or((0.0, 1.0));
+    let pad = (max - min) * 0.05;
+    (if min < 0.0 { min - pad } else { 0.0 }, max + pad)
+}
+
+/// `slots` bars side by side in `[start, end)`, the `slot`-th of them.
+fn slot(start: f64, end: f64, slot: usize, slots: usize) -> (f64, f64) {
+    let width = (end - start) / slots as f64;
+    (
+        start + width * slot as f64,
+        start + width * (slot + 1) as f64,
+    )
+}
+
+fn xy_plot(
+    table: &Table,
+    kind: Kind,
+    x: usize,
+    y: usize,
+    group: Option<usize>,
+) -> Result<Plot, KowalskiError> {
+    let (xs, categories) = x_positions(table, x);
+    let mut series = Vec::new();
+    for (name, rows) in table.series(group) {
+        let mut points = Vec::new();
+        for row in rows {
+            if let (Some(x), Some(y)) = (xs[row], table.number(row, y)?) {
+                points.push((x, y));
+            }
+        }
+        if kind == Kind::Line {
+            points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        let marks = match kind {
+            Kind::Line => Marks::Line(points),
+            _ => Marks::Points(points),
+        };
+        series.push((name, marks));
+    }
+    let points = || {
+        series.iter().flat_map(|(_, m)| match m {
+            Marks::Line(p) | Marks::Points(p) => p.iter().copied(),
+            Marks::Bars(_) => [].iter().copied(),
+        })
+    };
+    let x_span = match &categories {
+        Some(c) => (-0.5, c.len() as f64 - 0.5),
+        None => padded(span(points().map(|p| p.0)).ok_or_else(no_data)?),
+    };
+    let y_span = padded(span(points().map(|p| p.1)).ok_or_else(no_data)?);
+    Ok(Plot {
+        x: x_span,
+        y: y_span,
+        categories,
+        series,
+    })
+}
+
+/// One bar per x category and series; repeated categories are summed, and without a `y`
+/// column the rows are counted.
+fn bar_plot(
+    table: &Table,
+    x: usize,
+    y: Option<usize>,
+    group: Option<usize>,
+) -> Result<Plot, KowalskiError> {
+    let mut categories: Vec<String> = Vec::new();
+    for row in 0..table.rows.len() {
+        let cell = table.cell(row, x);
+        if !cell.is_empty() && !categories.iter().any(|c| c == cell) {
+            categories.push(cell.to_string());
+        }
+    }
+    if categories.is_empty() {
+        return Err(no_data());
+    }
+    let groups = table.series(group);
+    let slots = groups.len();
+    let mut series = Vec::new();
+    for (i, (name, rows)) in groups.into_iter().enumerate() {
+        let mut totals = vec![0.0; categories.len()];
+        for row in rows {
+            let cell = table.cell(row, x);
+            let Some(c) = categories.iter().position(|c| c == cell) else {
+                continue;
+            };
+            totals[c] += match y {
+                Some(y) => table.number(row, y)?.unwrap_or(0.0),
+                None => 1.0,
+            };
+        }
+        let bars = totals
+            .iter()
+            .enumerate()
+            .map(|(c, &height)| {
+                let (x0, x1) = slot(c as f64 - 0.4, c as f64 + 0.4, i, slots);
+                (x0, x1, height)
+            })
+            .collect();
+        series.push((name, Marks::Bars(bars)));
+    }
+    let y_span = bar_range(series.iter().flat_map(|(_, m)| match m {
+        Marks::Bars(b) => b.iter().map(|b| b.2).collect::<Vec<_>>(),
+        _ => Vec::new(),
+    }));
+    Ok(Plot {
+        x: (-0.5, categories.len() as f64 - 0.5),
+        y: y_span,
+        categories: Some(categories),
+        series,
+    })
+}
+
+/// Equal-width bins over all values of `x`; groups share the bins, side by side.
+fn histogram_plot(
+    table: &Table,
+    x: usize,
+    group: Option<usize>,
+    bins: usize,
+) -> Result<Plot, KowalskiError> {
+    let mut groups = Vec::new();
+    for (name, rows) in table.series(group) {
+        let mut values = Vec::new();
+        for row in rows {
+            if let Some(v) = table.number(row, x)? {
+                values.push(v);
+            }
+        }
+        groups.push((name, values));
+    }
+    let (min, max) =
+        span(groups.iter().flat_map(|(_, v)| v.iter().copied())).ok_or_else(no_data)?;
+    let bins = bins.clamp(1, MAX_BINS);
+    let width = (max - min) / bins as f64;
+    let slots = groups.len();
+    let mut series = Vec::new();
+    for (i, (name, values)) in groups.into_iter().enumerate() {
+        let mut counts = vec![0usize; bins];
+        for v in values {
+            counts[(((v - min) / width) as usize).min(bins - 1)] += 1;
+        }
+        let bars = counts
+            .iter()
+            .enumerate()
+            .map(|(b, &count)| {
+                let start = min + width * b as f64;
+                let (x0, x1) = slot(start, start + width, i, slots);
+                (x0, x1, count as f64)
+            })
+            .collect();
+        series.push((name, Marks::Bars(bars)));
+    }
+    let y_span = bar_range(series.iter().flat_map(|(_, m)| match m {
+        Marks::Bars(b) => b.iter().map(|b| b.2).collect::<Vec<_>>(),
+        _ => Vec::new(),
+    }));
+    Ok(Plot {
+        x: (min, max),
+        y: y_span,
+        categories: None,
+        series,
+    })
+}
+
+fn no_data() -> KowalskiError {
+    KowalskiError::ToolInvalidInput("Nothing to plot: no rows with values".to_string())
+}
+
+fn draw_error(e: impl std::fmt::Display) -> KowalskiError {
+    KowalskiError::ToolExecution(format!("chart rendering failed: {e}"))
+}
+
+/// Draws `plot` as an SVG document.
+fn render_svg(plot: &Plot, labels: &Labels, size: (u32, u32)) -> Result<String, KowalskiError> {
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, size).into_drawing_area();
+        root.fill(&WHITE).map_err(draw_error)?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(&labels.title, ("sans-serif", 22))
+            .margin(16)
+            .x_label_area_size(48)
+            .y_label_area_size(64)
+            .build_cartesian_2d(plot.x.0..plot.x.1, plot.y.0..plot.y.1)
+            .map_err(draw_error)?;
+        let category_label = |x: &f64| {
+            let i = x.round();
+            match &plot.categories {
+                Some(c) if (x - i).abs() < 1e-6 && i >= 0.0 && (i as usize) < c.len() => {
+                    c[i as usize].clone()
+                }
+                Some(_) => String::new(),
+                None => format!("{x}"),
+            }
+        };
+        let mut mesh = chart.configure_mesh();
+        mesh.x_desc(&labels.x).y_desc(&labels.y);
+        if let Some(categories) = &plot.categories {
+            mesh.x_labels(categories.len() * 2 + 1)
+                .x_label_formatter(&category_label)
+                .disable_x_mesh();
+        }
+        mesh.draw().map_err(draw_error)?;
+
+        for (i, (name, marks)) in plot.series.iter().enumerate() {
+            let color = Palette99::pick(i).to_rgba();
+            let drawn = match marks {
+                Marks::Line(points) => chart
+                    .draw_series(LineSeries::new(points.clone(), color.stroke_width(2)))
+                    .map_err(draw_error)?,
+                Marks::Points(points) => chart
+                    .draw_series(points.iter().map(|&p| Circle::new(p, 4, color.filled())))
+                    .map_err(draw_error)?,
+                Marks::Bars(bars) => chart
+                    .draw_series(bars.iter().map(|&(x0, x1, height)| {
+                        Rectangle::new([(x0, 0.0), (x1, height)], color.filled())
+                    }))
+                    .map_err(draw_error)?,
+            };
+            if let Some(name) = name {
+                drawn.label(name.as_str()).legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 12, y + 5)], color.filled())
+                });
+            }
+        }
+        if plot.series.iter().any(|(name, _)| name.is_some()) {
+            chart
+                .configure_series_labels()
+                .background_style(WHITE.mix(0.85))
+                .border_style(BLACK)
+                .draw()
+                .map_err(draw_error)?;
+        }
+        root.present().map_err(draw_error)?;
+    }
+    Ok(svg)
+}
+
+/// System fonts for PNG text, loaded once per process.
+fn font_database() -> Arc<resvg::usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<resvg::usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = resvg::usvg::fontdb::Database::new();
+            fonts.load_system_fonts();
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
+/// Rasterizes an SVG document to PNG bytes at its own size.
+fn svg_to_png(svg: &str) -> Result<Vec<u8>, KowalskiError> {
+    let options = resvg::usvg::Options {
+        fontdb: font_database(),
+        ..Default::default()
+    };
+    let tree = resvg::usvg::Tree::from_str(svg, &options).map_err(draw_error)?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| draw_error("empty image"))?;
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::default(),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(draw_error)
+}
+
+/// Renders line, bar, scatter and histogram charts to SVG or PNG files in
+/// [`ChartConfig::output_dir`] under the tool root. Data is inline `rows` (JSON objects) or a CSV
+/// `path` under the tool root; `group` splits the rows into one series per category.
+#[derive(Debug, Clone)]
+pub struct ChartTool {
+    config: ChartConfig,
+    root: PathBuf,
+}
+
+impl Default for ChartTool {
+    fn default() -> Self {
+        Self::new(ChartConfig::default())
+    }
+}
+
+impl ChartTool {
+    /// CSV files are read, and charts written, under the working directory.
+    pub fn new(config: ChartConfig) -> Self {
+        Self {
+            config,
+            root: PathBuf::from("."),
+        }
+    }
+
+    /// Reads `path` and writes [`ChartConfig::output_dir`] relative to `root`; paths that
+    /// resolve outside it are refused.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    pub fn config(&self) -> &ChartConfig {
+        &self.config
+    }
+
+    fn table(&self, input: &ToolInput) -> Result<Table, KowalskiError> {
+        let params = &input.parameters;
+        if let Some(rows) = params.get("rows").and_then(|v| v.as_array()) {
+            return Table::from_rows(rows);
+        }
+        match params.get("path").and_then(|v| v.as_str()) {
+            Some(path) if !path.trim().is_empty() => {
+                let file = resolve_within(&self.root, path, self.name())?.1;
+                Table::from_csv(&file, CsvFormat::from_input(input)?)
+            }
+            _ => Err(KowalskiError::ToolInvalidInput(
+                "chart needs data: inline 'rows' or a CSV 'path'".to_string(),
+            )),
+        }
+    }
+
+    /// The output file: `file` (a bare name) or `<chart>-<timestamp>`, with the format's
+    /// extension.
+    fn output_path(
+        &self,
+        file: Option<&str>,
+        kind: Kind,
+        ext: &str,
+    ) -> Result<PathBuf, KowalskiError> {
+        let stem = match file.map(str::trim).filter(|f| !f.is_empty()) {
+            Some(file) => {
+                let bare = Path::new(file).file_name().and_then(|n| n.to_str()) == Some(file);
+                if !bare || file.starts_with('.') {
+                    return Err(KowalskiError::ToolInvalidInput(format!(
+                        "file must be a plain file name, got '{file}'"
+                    )));
+                }
+                file.strip_suffix(&format!(".{ext}"))
+                    .unwrap_or(file)
+                    .to_string()
+            }
+            None => format!(
+                "{}-{}",
+                kind.name(),
+                chrono::Local::now().format("%Y%m%d-%H%M%S-%3f")
+            ),
+        };
+        // An absolute output_dir is accepted when it lies under the root.
+        let (root, _) = resolve_within(&self.root, ".", self.name())?;
+        let dir = Path::new(&self.config.output_dir);
+        let file = dir
+            .strip_prefix(&root)
+            .unwrap_or(dir)
+            .join(format!("{stem}.{ext}"));
+        Ok(resolve_new(&root, &file.to_string_lossy(), self.name())?.1)
+    }
+
+    /// Reads the data and renders the chart; blocking, so it runs off the async executor.
+    fn draw(
+        &self,
+        input: &ToolInput,
+        kind: Kind,
+        ext: &str,
+        size: (u32, u32),
+    ) -> Result<(Plot, Vec<u8>), KowalskiError> {
+        let params = &input.parameters;
+        let str_param = |name: &str| {
+            params
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        let table = self.table(input)?;
+        if table.rows.len() > MAX_ROWS {
+            return Err(KowalskiError::ToolInvalidInput(format!(
+                "More than {MAX_ROWS} rows; aggregate first or use a histogram of a sample"
+            )));
+        }
+        let x_name = str_param("x")
+            .ok_or_else(|| KowalskiError::ToolInvalidInput("chart needs an 'x' column".into()))?;
+        let x = table.index(x_name)?;
+        let y = str_param("y").map(|name| table.index(name)).transpose()?;
+        let group = str_param("group")
+            .map(|name| table.index(name))
+            .transpose()?;
+        let needs_y = || {
+            y.ok_or_else(|| {
+                KowalskiError::ToolInvalidInput(format!(
+                    "a {} chart needs a 'y' column",
+                    kind.name()
+                ))
+            })
+        };
+        let plot = match kind {
+            Kind::Line | Kind::Scatter => xy_plot(&table, kind, x, needs_y()?, group)?,
+            Kind::Bar => bar_plot(&table, x, y, group)?,
+            Kind::Histogram => {
+                let bins = params
+                    .get("bins")
+                    .and_then(|v| v.as_u64())
+                    .map_or(DEFAULT_BINS, |n| n as usize);
+                histogram_plot(&table, x, group, bins)?
+            }
+        };
+        let y_default = match (kind, str_param("y")) {
+            (Kind::Histogram, _) | (Kind::Bar, None) => "count",
+            (_, y) => y.unwrap_or_default(),
+        };
+        let labels = Labels {
+            title: str_param("title").unwrap_or_default().to_string(),
+            x: str_param("x_label").unwrap_or(x_name).to_string(),
+            y: str_param("y_label").unwrap_or(y_default).to_string(),
+        };
+
+        let svg = render_svg(&plot, &labels, size)?;
+        let bytes = if ext == "png" {
+            svg_to_png(&svg)?
+        } else {
+            svg.into_bytes()
+        };
+        Ok((plot, bytes))
+    }
+}
+
+#[async_trait]
+impl Tool for ChartTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let params = &input.parameters;
+        let str_param = |name: &str| {
+            params
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        let size_param = |name: &str, default: u32| {
+            params
+                .get(name)
+                .and_then(|v| v.as_u64())
+                .map_or(default, |n| n.clamp(100, MAX_SIDE as u64) as u32)
+        };
+        // The chart type may also come as the task.
+        let task = Some(input.task_type.as_str()).filter(|t| *t != "default");
+        let kind = Kind::parse(str_param("chart").or(task).ok_or_else(|| {
+            KowalskiError::ToolInvalidInput("chart needs a 'chart' type".to_string())
+        })?)?;
+        let ext = match str_param("format").unwrap_or("svg") {
+            "svg" => "svg",
+            "png" => "png",
+            other => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "Unknown format '{other}' (expected svg or png)"
+                )));
+            }
+        };
+        let size = (
+            size_param("width", self.config.width),
+            size_param("height", self.config.height),
+        );
+        let path = self.output_path(str_param("file"), kind, ext)?;
+
+        // Reading the CSV and drawing are blocking work.
+        let tool = self.clone();
+        let (plot, bytes) = tokio::task::spawn_blocking(move || tool.draw(&input, kind, ext, size))
+            .await
+            .map_err(|e| KowalskiError::tool_failed("chart", e))??;
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, &bytes).await?;
+
+        let series: Vec<&str> = plot
+            .series
+            .iter()
+            .filter_map(|(name, _)| name.as_deref())
+            .collect();
+        let points: usize = plot.series.iter().map(|(_, marks)| marks.len()).sum();
+        let shown = path.display().to_string();
+        Ok(ToolOutput::new(
+            json!({
+                "path": shown,
+                "chart": kind.name(),
+                "format": ext,
+                "series": series,
+                "points": points,
+            }),
+            Some(json!({"width": size.0, "height": size.1, "bytes": bytes.len()})),
+        )
+        .with_source(shown))
+    }
+
+    fn name(&self) -> &str {
+        "chart"
+    }
+
+    fn description(&self) -> &str {
+        "Draws a line, bar, scatter or histogram chart and saves it as an SVG or PNG file, returning its path. Data is inline rows (JSON objects) or a CSV file in path; x and y name the columns, group splits the data into one series per category. Bar charts sum y per x category (or count rows without y); histograms bin the x column."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        let param =
+            |name: &str, description: &str, parameter_type, default: Option<&str>| ToolParameter {
+                name: name.to_string(),
+                description: description.to_string(),
+                required: false,
+                default_value: default.map(str::to_string),
+                parameter_type,
+            };
+        vec![
+            ToolParameter {
+                required: true,
+                ..param(
+                    "chart",
+                    "line, bar, scatter or histogram",
+                    ParameterType::String,
+                    None,
+                )
+            },
+            ToolParameter {
+                required: true,
+                ..param(
+                    "x",
+                    "Column for the x axis (the binned column for histogram)",
+                    ParameterType::String,
+                    None,
+                )
+            },
+            param(
+                "y",
+                "Column for the y axis (line, scatter; optional for bar)",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "group",
+                "Category column: one series per distinct value",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "rows",
+                "Inline data: an array of JSON objects",
+                ParameterType::Array,
+                None,
+            ),
+            param(
+                "path",
+                "CSV file relative to the tool root (instead of rows)",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "delimiter",
+                "CSV field separator, one character (or \\t / tab)",
+                ParameterType::String,
+                Some(","),
+            ),
+            param(
+                "has_headers",
+                "Whether the CSV's first row names the columns",
+                ParameterType::Boolean,
+                Some("true"),
+            ),
+            param("title", "Chart title", ParameterType::String, None),
+            param(
+                "x_label",
+                "x axis label (default: the x column)",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "y_label",
+                "y axis label (default: the y column, or count)",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "bins",
+                "Number of equal-width bins (histogram)",
+                ParameterType::Number,
+                Some("10"),
+            ),
+            param("format", "svg or png", ParameterType::String, Some("svg")),
+            param(
+                "file",
+                "Output file name in the chart directory (default: <chart>-<timestamp>)",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "width",
+                "Image width in pixels",
+                ParameterType::Number,
+                None,
+            ),
+            param(
+                "height",
+                "Image height in pixels",
+                ParameterType::Number,
+                None,
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALES: &str = "month,region,sales\nJan,north,10\nJan,south,7\nFeb,north,12\nFeb,south,9\nMar,north,15\nMar,south,4\n";
+
+    fn tool(dir: &Path) -> ChartTool {
+        std::fs::write(dir.join("sales.csv"), SALES).unwrap();
+        ChartTool::new(ChartConfig {
+            output_dir: "out".to_string(),
+            ..ChartConfig::default()
+        })
+        .with_root(dir)
+    }
+
+    async fn render(tool: &mut ChartTool, params: Value) -> (ToolOutput, String) {
+        let out = tool
+            .execute(ToolInput::from_parameters(params))
+            .await
+            .unwrap();
+        let svg = std::fs::read_to_string(out.result["path"].as_str().unwrap()).unwrap();
+        assert!(svg.trim_start().starts_with("<svg"), "{svg}");
+        assert!(svg.trim_end().ends_with("</svg>"));
+        (out, svg)
+    }
+
+    #[tokio::test]
+    async fn renders_every_chart_type_to_svg() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tool = tool(dir.path());
+
+        let (out, svg) = render(
+            &mut tool,
+            json!({"chart": "line", "path": "sales.csv", "x": "month", "y": "sales",
+                   "group": "region", "title": "Sales by month", "file": "line"}),
+        )
+        .await;
+        assert_eq!(out.result["series"], json!(["north", "south"]));
+        assert_eq!(out.result["points"], 6);
+        assert_eq!(
+            out.metadata,
+            Some(json!({"width": 800, "height": 600, "bytes": svg.len()}))
+        );
+        assert!(out.result["path"].as_str().unwrap().ends_with("line.svg"));
+        assert!(svg.contains("<polyline"));
+        for text in ["Sales by month", "Jan", "Mar", "north", "south"] {
+            assert!(svg.contains(text), "missing {text}");
+        }
+
+        let (out, svg) = render(
+            &mut tool,
+            json!({"chart": "bar", "path": "sales.csv", "x": "month", "y": "sales",
+                   "y_label": "units", "width": 400, "height": 300}),
+        )
+        .await;
+        assert_eq!(out.metadata.as_ref().unwrap()["width"], 400);
+        // One bar per month, summed over regions.
+        assert_eq!(out.result["points"], 3);
+        assert!(svg.contains("<rect") && svg.contains("units"));
+
+        let (out, svg) = render(
+            &mut tool,
+            json!({"chart": "scatter", "x": "a", "y": "b",
+                   "rows": [{"a": 1, "b": 2.5}, {"a": 2, "b": null}, {"a": 3, "b": 4}]}),
+        )
+        .await;
+        // The row without b is skipped.
+        assert_eq!(out.result["points"], 2);
+        assert_eq!(svg.matches("<circle").count(), 2);
+
+        let (out, svg) = render(
+            &mut tool,
+            json!({"chart": "histogram", "path": "sales.csv", "x": "sales", "bins": 4}),
+        )
+        .await;
+        assert_eq!(out.result["points"], 4);
+        assert!(svg.contains("<rect") && svg.contains("count"));
+    }
+
+    #[tokio::test]
+    async fn writes_png_and_rejects_bad_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tool = tool(dir.path());
+
+        let out = tool
+            .execute(ToolInput::from_parameters(json!({
+                "chart": "bar", "path": "sales.csv", "x": "region", "format": "png",
+                "width": 200, "height": 150,
+            })))
+            .await
+            .unwrap();
+        let png = std::fs::read(out.result["path"].as_str().unwrap()).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        for params in [
+            json!({"chart": "pie", "path": "sales.csv", "x": "month"}),
+            json!({"chart": "line", "path": "sales.csv", "x": "month"}),
+            json!({"chart": "line", "path": "sales.csv", "x": "month", "y": "region"}),
+            json!({"chart": "bar", "path": "../sales.csv", "x": "month"}),
+            json!({"chart": "bar", "path": "sales.csv", "x": "month", "file": "../escape"}),
+            json!({"chart": "bar", "x": "month"}),
+        ] {
+            assert!(
+                tool.execute(ToolInput::from_parameters(params.clone()))
+                    .await
+                    .is_err(),
+                "{params}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn output_dir_is_confined_to_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        let params = json!({"chart": "bar", "path": "sales.csv", "x": "region", "file": "c"});
+
+        let inside = root.canonicalize().unwrap().join("charts");
+        let mut tool = tool(&root);
+        tool.config.output_dir = inside.display().to_string();
+        let out = tool
+            .execute(ToolInput::from_parameters(params.clone()))
+            .await
+            .unwrap();
+        assert_eq!(
+            out.result["path"],
+            inside.join("c.svg").display().to_string()
+        );
+
+        for outside in ["../elsewhere".to_string(), dir.path().display().to_string()] {
+            tool.config.output_dir = outside.clone();
+            let err = tool
+                .execute(ToolInput::from_parameters(params.clone()))
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(err, KowalskiError::PermissionDenied(_)),
+                "{outside}: {err}"
+            );
+        }
+        assert!(!dir.path().join("elsewhere").exists());
+        assert!(!dir.path().join("c.svg").exists());
+    }
+}
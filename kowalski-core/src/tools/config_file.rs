@@ -0,0 +1,255 @@
+use crate::error::KowalskiError;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use crate::utils::json_schema;
+use async_trait::async_trait;
+use serde_json::{Map, Value, json};
+
+/// Where a YAML or TOML document failed to parse (1-based line and column).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl SyntaxError {
+    fn to_json(&self) -> Value {
+        json!({"line": self.line, "column": self.column, "message": self.message})
+    }
+}
+
+/// Parses YAML into JSON. Documents with non-string keys or tags that JSON cannot hold fail like
+/// syntax errors.
+pub fn parse_yaml(content: &str) -> Result<Value, SyntaxError> {
+    serde_yaml::from_str(content).map_err(|e| {
+        let (line, column) = e.location().map_or((1, 1), |l| (l.line(), l.column()));
+        SyntaxError {
+            line,
+            column,
+            message: e.to_string(),
+        }
+    })
+}
+
+/// Parses TOML into JSON; datetimes become their RFC 3339 strings.
+pub fn parse_toml(content: &str) -> Result<Value, SyntaxError> {
+    match content.parse::<toml::Table>() {
+        Ok(table) => Ok(toml_to_json(toml::Value::Table(table))),
+        Err(e) => {
+            let (line, column) = line_column(content, e.span().map_or(0, |s| s.start));
+            Err(SyntaxError {
+                line,
+                column,
+                message: e.message().to_string(),
+            })
+        }
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(n) => json!(n),
+        toml::Value::Float(f) => json!(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect::<Map<_, _>>(),
+        ),
+    }
+}
+
+/// 1-based line and column (in characters) of byte `offset` in `text`.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+/// Parses `content` as `format` (`yaml`, `toml` or `json`).
+fn parse(content: &str, format: &str) -> Result<Result<Value, SyntaxError>, KowalskiError> {
+    Ok(match format {
+        "yaml" | "yml" => parse_yaml(content),
+        "toml" => parse_toml(content),
+        "json" => serde_json::from_str(content).map_err(|e| SyntaxError {
+            line: e.line(),
+            column: e.column(),
+            message: e.to_string(),
+        }),
+        other => {
+            return Err(KowalskiError::ToolInvalidInput(format!(
+                "Unknown format '{other}' (expected yaml, toml or json)"
+            )));
+        }
+    })
+}
+
+/// Parses YAML and TOML configuration text into JSON and checks it against a JSON Schema.
+/// Syntax errors are reported in the result (`valid: false` with line and column), not as tool
+/// failures, so the model can point at the broken line.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConfigFileTool;
+
+impl ConfigFileTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Tool for ConfigFileTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let content = input
+            .parameters
+            .get("content")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                KowalskiError::ToolInvalidInput("Missing required parameter: content".to_string())
+            })?;
+        let result = match input.task_type.as_str() {
+            task @ ("parse_yaml" | "parse_toml") => {
+                let format = task.trim_start_matches("parse_");
+                match parse(content, format)? {
+                    Ok(data) => json!({"format": format, "valid": true, "data": data}),
+                    Err(e) => json!({"format": format, "valid": false, "error": e.to_json()}),
+                }
+            }
+            "validate_against_schema" => {
+                let schema = input
+                    .parameters
+                    .get("schema")
+                    .filter(|s| s.is_object())
+                    .ok_or_else(|| {
+                        KowalskiError::ToolInvalidInput(
+                            "validate_against_schema needs a JSON Schema object in 'schema'"
+                                .to_string(),
+                        )
+                    })?;
+                let format = input
+                    .parameters
+                    .get("format")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("yaml");
+                match parse(content, format)? {
+                    Ok(data) => {
+                        let errors = json_schema::validate(&data, schema);
+                        json!({"format": format, "valid": errors.is_empty(), "errors": errors})
+                    }
+                    Err(e) => json!({"format": format, "valid": false, "error": e.to_json()}),
+                }
+            }
+            other => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "Unknown config_file task '{other}' (expected parse_yaml, parse_toml or validate_against_schema)"
+                )));
+            }
+        };
+        Ok(ToolOutput::new(result, None).with_source(self.name()))
+    }
+
+    fn name(&self) -> &str {
+        "config_file"
+    }
+
+    fn description(&self) -> &str {
+        "Parses YAML or TOML configuration text into JSON and validates it against a JSON Schema. Tasks: parse_yaml, parse_toml, validate_against_schema. Syntax errors are reported with line and column."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "task".to_string(),
+                description: "parse_yaml, parse_toml or validate_against_schema".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "content".to_string(),
+                description: "The configuration text".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "format".to_string(),
+                description: "Format of content for validate_against_schema: yaml, toml or json"
+                    .to_string(),
+                required: false,
+                default_value: Some("yaml".to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "schema".to_string(),
+                description: "JSON Schema the parsed document must match (validate_against_schema)"
+                    .to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::Object,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(params: Value) -> Value {
+        ConfigFileTool
+            .execute(ToolInput::from_parameters(params))
+            .await
+            .unwrap()
+            .result
+    }
+
+    #[tokio::test]
+    async fn parses_nested_yaml_and_validates_it() {
+        let yaml = "service:\n  name: api\n  replicas: 3\n  ports:\n    - 80\n    - 443\n  env:\n    DEBUG: false\n";
+        let out = run(json!({"task": "parse_yaml", "content": yaml})).await;
+        assert_eq!(out["valid"], true);
+        assert_eq!(
+            out["data"],
+            json!({"service": {"name": "api", "replicas": 3, "ports": [80, 443], "env": {"DEBUG": false}}})
+        );
+
+        let schema = json!({
+            "type": "object",
+            "properties": {"service": {"type": "object", "required": ["name", "image"]}},
+            "required": ["service"]
+        });
+        let out =
+            run(json!({"task": "validate_against_schema", "content": yaml, "schema": schema}))
+                .await;
+        assert_eq!(out["valid"], false);
+        assert_eq!(
+            out["errors"],
+            json!(["$.service.image: missing required property"])
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_where_toml_is_broken() {
+        let toml = "[server]\nhost = \"localhost\"\nport = = 8080\n";
+        let out = run(json!({"task": "parse_toml", "content": toml})).await;
+        assert_eq!(out["valid"], false);
+        assert_eq!(out["error"]["line"], 3);
+        assert_eq!(out["error"]["column"], 8);
+
+        let out = run(json!({"task": "parse_toml", "content": "[a.b]\nwhen = 1979-05-27\n"})).await;
+        assert_eq!(out["data"], json!({"a": {"b": {"when": "1979-05-27"}}}));
+        assert!(
+            ConfigFileTool
+                .execute(ToolInput::from_parameters(
+                    json!({"task": "parse_ini", "content": "a=1"})
+                ))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use crate::error::KowalskiError;
+use crate::tools::fs::{relative, resolve_within};
+use crate::tools::profile;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde_json::{Map, json};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const SAMPLE_ROWS: usize = 5;
+
+/// Summarizes CSV data: columns, row count, a few sample rows and count/min/max/mean for every
+/// numeric column. The default task reads CSV text from `content`; `process_csv_file` streams a
+/// file under the tool root in one pass, so files of any size work in constant memory.
+#[derive(Debug, Clone)]
+pub struct CsvTool {
+    root: PathBuf,
+}
+
+impl Default for CsvTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvTool {
+    /// Files for `process_csv_file` are read under the working directory.
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("."),
+        }
+    }
+
+    /// Reads `process_csv_file` paths relative to `root`; paths that resolve outside it are
+    /// refused, as with [`FsTool`](crate::tools::FsTool).
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+}
+
+#[derive(Default)]
+struct NumericStats {
+    count: usize,
+    min: f64,
+    max: f64,
+    sum: f64,
+    /// A non-empty, non-numeric cell was seen; the column is not numeric.
+    mixed: bool,
+}
+
+impl NumericStats {
+    fn add(&mut self, cell: &str) {
+        let cell = cell.trim();
+        if cell.is_empty() || self.mixed {
+            return;
+        }
+        match cell.parse::<f64>() {
+            Ok(value) => {
+                if self.count == 0 {
+                    self.min = value;
+                    self.max = value;
+                } else {
+                    self.min = self.min.min(value);
+                    self.max = self.max.max(value);
+                }
+                self.count += 1;
+                self.sum += value;
+            }
+            Err(_) => self.mixed = true,
+        }
+    }
+}
+
+/// How CSV text is split: the field separator and whether the first record names the columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvFormat {
+    pub delimiter: u8,
+    /// When `false`, every record is data and columns are named `column_0`, `column_1`, ...
+    pub has_headers: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_headers: true,
+        }
+    }
+}
+
+impl CsvFormat {
+    /// Reads the `delimiter` and `has_headers` parameters.
+    pub(crate) fn from_input(input: &ToolInput) -> Result<Self, KowalskiError> {
+        let has_headers = match input.parameters.get("has_headers") {
+            None | Some(serde_json::Value::Null) => true,
+            Some(serde_json::Value::Bool(b)) => *b,
+            Some(serde_json::Value::String(s)) => {
+                ParameterType::Boolean.coerce(s)?.as_bool().unwrap_or(true)
+            }
+            Some(other) => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "has_headers must be true or false, got {other}"
+                )));
+            }
+        };
+        Ok(Self {
+            delimiter: delimiter(input)?,
+            has_headers,
+        })
+    }
+
+    pub(crate) fn reader<R: Read>(&self, input: R) -> csv::Reader<R> {
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(self.has_headers)
+            .flexible(true)
+            .from_reader(input)
+    }
+
+    /// Column names: the header row, or `column_0..n` sized by the first record when there is
+    /// none (that record is still returned by [`csv::Reader::records`]).
+    pub(crate) fn columns<R: Read>(
+        &self,
+        reader: &mut csv::Reader<R>,
+    ) -> Result<Vec<String>, KowalskiError> {
+        let first = reader.headers().map_err(invalid)?;
+        Ok(if self.has_headers {
+            first.iter().map(str::to_string).collect()
+        } else {
+            (0..first.len()).map(|i| format!("column_{i}")).collect()
+        })
+    }
+}
+
+pub(crate) fn invalid(e: csv::Error) -> KowalskiError {
+    KowalskiError::ToolInvalidInput(format!("Invalid CSV: {e}"))
+}
+
+/// Parses `content` in `format` and returns the summary [`CsvTool`] reports.
+pub fn summarize(content: &str, format: CsvFormat) -> Result<serde_json::Value, KowalskiError> {
+    summarize_reader(content.as_bytes(), format)
+}
+
+/// Like [`summarize`], but streams the file at `path`: records are read one at a time and only
+/// the running statistics and the sample rows are kept. Blocking; the tool runs it off the
+/// async runtime.
+pub fn summarize_file(path: &Path, format: CsvFormat) -> Result<serde_json::Value, KowalskiError> {
+    let file = std::fs::File::open(path)?;
+    summarize_reader(std::io::BufReader::new(file), format)
+}
+
+fn summarize_reader<R: Read>(
+    input: R,
+    format: CsvFormat,
+) -> Result<serde_json::Value, KowalskiError> {
+    let mut reader = format.reader(input);
+    let columns = format.columns(&mut reader)?;
+    let mut stats: Vec<NumericStats> = columns.iter().map(|_| NumericStats::default()).collect();
+    let mut sample = Vec::new();
+    let mut rows = 0usize;
+    for record in reader.records() {
+        let record = record.map_err(invalid)?;
+        rows += 1;
+        for (column, cell) in stats.iter_mut().zip(record.iter()) {
+            column.add(cell);
+        }
+        if sample.len() < SAMPLE_ROWS {
+            let row: Map<String, serde_json::Value> = columns
+                .iter()
+                .zip(record.iter())
+                .map(|(name, cell)| (name.clone(), json!(cell)))
+                .collect();
+            sample.push(row);
+        }
+    }
+    let numeric: Map<String, serde_json::Value> = columns
+        .iter()
+        .zip(&stats)
+        .filter(|(_, s)| s.count > 0 && !s.mixed)
+        .map(|(name, s)| {
+            (
+                name.clone(),
+                json!({"count": s.count, "min": s.min, "max": s.max, "mean": s.sum / s.count as f64}),
+            )
+        })
+        .collect();
+    Ok(json!({
+        "columns": columns,
+        "rows": rows,
+        "sample": sample,
+        "numeric_columns": numeric,
+    }))
+}
+
+fn delimiter(input: &ToolInput) -> Result<u8, KowalskiError> {
+    match input.parameters.get("delimiter").and_then(|v| v.as_str()) {
+        None | Some("") => Ok(b','),
+        Some("\\t") | Some("tab") => Ok(b'\t'),
+        Some(d) if d.len() == 1 => Ok(d.as_bytes()[0]),
+        Some(d) => Err(KowalskiError::ToolInvalidInput(format!(
+            "delimiter must be a single character, got '{d}'"
+        ))),
+    }
+}
+
+#[async_trait]
+impl Tool for CsvTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let format = CsvFormat::from_input(&input)?;
+        match input.task_type.as_str() {
+            "process_csv_file" => {
+                let path = input
+                    .parameters
+                    .get("path")
+                    .and_then(|v| v.as_str())
+                    .filter(|p| !p.is_empty())
+                    .ok_or_else(|| {
+                        KowalskiError::ToolInvalidInput(
+                            "process_csv_file needs a 'path'".to_string(),
+                        )
+                    })?;
+                let (root, file) = resolve_within(&self.root, path, self.name())?;
+                let bytes = std::fs::metadata(&file)?.len();
+                let streamed = file.clone();
+                let mut summary =
+                    tokio::task::spawn_blocking(move || summarize_file(&streamed, format))
+                        .await
+                        .map_err(|e| KowalskiError::tool_failed("csv_tool", e))??;
+                summary["path"] = json!(relative(&root, &file));
+                summary["bytes"] = json!(bytes);
+                Ok(ToolOutput::new(summary, None).with_source(file.display().to_string()))
+            }
+            "profile_dataset" => {
+                // An explicit delimiter wins over sniffing.
+                let delimiter = input
+                    .parameters
+                    .get("delimiter")
+                    .and_then(|v| v.as_str())
+                    .filter(|d| !d.is_empty())
+                    .map(|_| format.delimiter as char);
+                let path = input
+                    .parameters
+                    .get("path")
+                    .and_then(|v| v.as_str())
+                    .filter(|p| !p.is_empty());
+                let Some(path) = path else {
+                    let content = input
+                        .parameters
+                        .get("content")
+                        .and_then(|v| v.as_str())
+                        .filter(|s| !s.trim().is_empty())
+                        .ok_or_else(|| {
+                            KowalskiError::ToolInvalidInput(
+                                "profile_dataset needs a 'path' or 'content'".to_string(),
+                            )
+                        })?;
+                    let profile = profile::profile(content, delimiter, format.has_headers)?;
+                    return Ok(ToolOutput::new(json!(profile), None).with_source(self.name()));
+                };
+                let (root, file) = resolve_within(&self.root, path, self.name())?;
+                let read = file.clone();
+                let profile = tokio::task::spawn_blocking(move || {
+                    profile::profile_file(&read, delimiter, format.has_headers)
+                })
+                .await
+                .map_err(|e| KowalskiError::tool_failed("csv_tool", e))??;
+                let mut report = json!(profile);
+                report["path"] = json!(relative(&root, &file));
+                Ok(ToolOutput::new(report, None).with_source(file.display().to_string()))
+            }
+            "default" | "summarize" => {
+                let content = input
+                    .parameters
+                    .get("content")
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.trim().is_empty())
+                    .ok_or_else(|| {
+                        KowalskiError::ToolInvalidInput(
+                            "Missing required parameter: content".to_string(),
+                        )
+                    })?;
+                Ok(ToolOutput::new(summarize(content, format)?, None).with_source(self.name()))
+            }
+            other => Err(KowalskiError::ToolInvalidInput(format!(
+                "Unknown csv_tool task '{other}' (expected summarize, process_csv_file or profile_dataset)"
+            ))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "csv_tool"
+    }
+
+    fn description(&self) -> &str {
+        "Summarizes CSV data: columns, row count, sample rows and min/max/mean of numeric columns. Tasks: summarize (CSV text in content), process_csv_file (streams a file under the working directory, for files too large to pass as text) and profile_dataset (detects the delimiter and quoting, and reports per-column type with confidence, null rate, distinct count, examples and quality issues; path or content). Set delimiter for ; or tab separated data, and has_headers=false when the first row is data."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "task".to_string(),
+                description: "summarize, process_csv_file or profile_dataset".to_string(),
+                required: false,
+                default_value: Some("summarize".to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "content".to_string(),
+                description: "CSV text (summarize, profile_dataset)".to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "path".to_string(),
+                description: "CSV file relative to the tool root (process_csv_file, profile_dataset)"
+                    .to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "delimiter".to_string(),
+                description:
+                    "Field separator, one character (or \\t / tab); profile_dataset detects it when unset"
+                        .to_string(),
+                required: false,
+                default_value: Some(",".to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "has_headers".to_string(),
+                description: "Whether the first row names the columns; if false they are column_0, column_1, ...".to_string(),
+                required: false,
+                default_value: Some("true".to_string()),
+                parameter_type: ParameterType::Boolean,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn summarizes_columns_and_numeric_stats() {
+        let out = CsvTool::new()
+            .execute(ToolInput::from_parameters(json!({
+                "content": "name;age;score\nada;36;9.5\nbob;;7\ncyd;n/a;8\n",
+                "delimiter": ";",
+            })))
+            .await
+            .unwrap();
+        let summary = out.result;
+        assert_eq!(summary["columns"], json!(["name", "age", "score"]));
+        assert_eq!(summary["rows"], 3);
+        assert_eq!(
+            summary["sample"][1],
+            json!({"name": "bob", "age": "", "score": "7"})
+        );
+        // `age` has a non-numeric cell, so only `score` gets stats.
+        assert_eq!(
+            summary["numeric_columns"],
+            json!({"score": {"count": 3, "min": 7.0, "max": 9.5, "mean": 8.166666666666666}})
+        );
+
+        assert!(
+            CsvTool::new()
+                .execute(ToolInput::from_parameters(
+                    json!({"content": "a,b", "delimiter": "::"})
+                ))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_tab_delimited_and_headerless_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("scores.tsv"),
+            "name\tscore\nada\t9\nbob\t7\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("raw.csv"), "ada;9\nbob;7\ncyd;8\n").unwrap();
+        let mut tool = CsvTool::new().with_root(dir.path());
+
+        let tsv = tool
+            .execute(ToolInput::from_parameters(json!({
+                "task": "process_csv_file",
+                "path": "scores.tsv",
+                "delimiter": "\\t",
+            })))
+            .await
+            .unwrap()
+            .result;
+        assert_eq!(tsv["columns"], json!(["name", "score"]));
+        assert_eq!(tsv["rows"], 2);
+        assert_eq!(tsv["sample"][0], json!({"name": "ada", "score": "9"}));
+
+        let raw = tool
+            .execute(ToolInput::from_parameters(json!({
+                "task": "process_csv_file",
+                "path": "raw.csv",
+                "delimiter": ";",
+                "has_headers": false,
+            })))
+            .await
+            .unwrap()
+            .result;
+        // The first line is data, not a header.
+        assert_eq!(raw["columns"], json!(["column_0", "column_1"]));
+        assert_eq!(raw["rows"], 3);
+        assert_eq!(
+            raw["sample"][0],
+            json!({"column_0": "ada", "column_1": "9"})
+        );
+        assert_eq!(
+            raw["numeric_columns"],
+            json!({"column_1": {"count": 3, "min": 7.0, "max": 9.0, "mean": 8.0}})
+        );
+
+        let bad = tool
+            .execute(ToolInput::from_parameters(
+                json!({"content": "a,b\n1,2\n", "has_headers": "maybe"}),
+            ))
+            .await;
+        assert!(bad.is_err());
+    }
+
+    #[tokio::test]
+    async fn streams_a_large_file_in_one_pass() {
+        let dir = tempfile::tempdir().unwrap();
+        let rows = 50_000;
+        let mut csv = String::from("id,label,value\n");
+        for i in 1..=rows {
+            csv.push_str(&format!("{i},row{i},{}\n", i % 100));
+        }
+        std::fs::write(dir.path().join("big.csv"), &csv).unwrap();
+        let mut tool = CsvTool::new().with_root(dir.path());
+
+        let out = tool
+            .execute(ToolInput::from_parameters(
+                json!({"task": "process_csv_file", "path": "big.csv"}),
+            ))
+            .await
+            .unwrap();
+        let summary = out.result;
+        assert_eq!(summary["rows"], rows);
+        assert_eq!(summary["path"], "big.csv");
+        assert_eq!(summary["bytes"], csv.len());
+        assert_eq!(summary["sample"].as_array().unwrap().len(), SAMPLE_ROWS);
+        assert_eq!(
+            summary["numeric_columns"]["id"],
+            json!({"count": rows, "min": 1.0, "max": 50000.0, "mean": 25000.5})
+        );
+        assert_eq!(
+            summary["numeric_columns"]["value"],
+            json!({"count": rows, "min": 0.0, "max": 99.0, "mean": 49.5})
+        );
+        // Same numbers as summarizing the text in memory.
+        assert_eq!(
+            summary["numeric_columns"],
+            summarize(&csv, CsvFormat::default()).unwrap()["numeric_columns"]
+        );
+        assert!(out.source.unwrap().ends_with("big.csv"));
+
+        let outside = tool
+            .execute(ToolInput::from_parameters(
+                json!({"task": "process_csv_file", "path": "../big.csv"}),
+            ))
+            .await;
+        assert!(outside.is_err());
+    }
+
+    #[tokio::test]
+    async fn profiles_a_fixture_file_or_inline_text() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let mut tool = CsvTool::new().with_root(&root);
+        let out = tool
+            .execute(ToolInput::from_parameters(json!({
+                "task": "profile_dataset",
+                "path": "data/semicolons.csv",
+            })))
+            .await
+            .unwrap();
+        let report = out.result;
+        assert_eq!(report["path"], "data/semicolons.csv");
+        assert_eq!(report["dialect"], json!({"delimiter": ";"}));
+        assert_eq!(report["columns"][2]["type"], "float");
+        assert_eq!(report["columns"][2]["decimal_comma"], true);
+
+        // Told the delimiter is a comma, the semicolon lines are one column.
+        let forced = tool
+            .execute(ToolInput::from_parameters(json!({
+                "task": "profile_dataset",
+                "content": "a;b\n1;2\n",
+                "delimiter": ",",
+            })))
+            .await
+            .unwrap()
+            .result;
+        assert_eq!(forced["columns"].as_array().unwrap().len(), 1);
+        assert!(
+            tool.execute(ToolInput::from_parameters(
+                json!({"task": "profile_dataset"})
+            ))
+            .await
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::error::KowalskiError;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde_json::{Value, json};
+
+/// Date and time facts the model cannot know or tends to get wrong: `now`, `diff` between two
+/// dates and `add` days to a date.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DateTimeTool;
+
+impl DateTimeTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// `YYYY-MM-DD` (midnight UTC) or RFC 3339.
+fn parse_instant(raw: &str) -> Result<DateTime<Utc>, KowalskiError> {
+    let raw = raw.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    DateTime::parse_from_rfc3339(raw)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| {
+            KowalskiError::ToolInvalidInput(format!(
+                "'{raw}' is not a date (YYYY-MM-DD) or RFC 3339 timestamp"
+            ))
+        })
+}
+
+fn required<'a>(parameters: &'a Value, name: &str) -> Result<&'a str, KowalskiError> {
+    parameters
+        .get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| {
+            KowalskiError::ToolInvalidInput(format!("Missing required parameter: {name}"))
+        })
+}
+
+#[async_trait]
+impl Tool for DateTimeTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let parameters = &input.parameters;
+        let result = match input.task_type.as_str() {
+            "now" | "default" => {
+                let now = Local::now();
+                json!({
+                    "local": now.to_rfc3339(),
+                    "utc": now.with_timezone(&Utc).to_rfc3339(),
+                    "weekday": now.format("%A").to_string(),
+                    "unix": now.timestamp(),
+                })
+            }
+            "diff" => {
+                let from = parse_instant(required(parameters, "from")?)?;
+                let to = parse_instant(required(parameters, "to")?)?;
+                let elapsed = to - from;
+                json!({"days": elapsed.num_days(), "seconds": elapsed.num_seconds()})
+            }
+            "add" => {
+                let date = parse_instant(required(parameters, "date")?)?;
+                let days = parameters
+                    .get("days")
+                    .and_then(|v| v.as_i64().or_else(|| v.as_str()?.trim().parse().ok()))
+                    .ok_or_else(|| {
+                        KowalskiError::ToolInvalidInput("days must be a whole number".to_string())
+                    })?;
+                let shifted = date + Duration::days(days);
+                json!({
+                    "date": shifted.format("%Y-%m-%d").to_string(),
+                    "weekday": shifted.format("%A").to_string(),
+                })
+            }
+            other => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "Unknown datetime task '{other}' (expected now, diff or add)"
+                )));
+            }
+        };
+        Ok(ToolOutput::new(result, None).with_source(self.name()))
+    }
+
+    fn name(&self) -> &str {
+        "datetime"
+    }
+
+    fn description(&self) -> &str {
+        "Date and time: now (current local and UTC time), diff (days between from and to), add (date plus days). Dates are YYYY-MM-DD or RFC 3339."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        let string = |name: &str, description: &str| ToolParameter {
+            name: name.to_string(),
+            description: description.to_string(),
+            required: false,
+            default_value: None,
+            parameter_type: ParameterType::String,
+        };
+        vec![
+            ToolParameter {
+                required: true,
+                default_value: Some("now".to_string()),
+                ..string("task", "now, diff or add")
+            },
+            string("from", "diff: start date"),
+            string("to", "diff: end date"),
+            string("date", "add: date to shift"),
+            ToolParameter {
+                parameter_type: ParameterType::Number,
+                ..string("days", "add: days to add (negative to subtract)")
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(params: Value) -> Result<Value, KowalskiError> {
+        DateTimeTool
+            .execute(ToolInput::from_parameters(params))
+            .await
+            .map(|out| out.result)
+    }
+
+    #[tokio::test]
+    async fn diffs_and_shifts_dates() {
+        let diff = run(json!({"task": "diff", "from": "2024-02-28", "to": "2024-03-01T12:00:00Z"}))
+            .await
+            .unwrap();
+        assert_eq!(diff["days"], 2);
+        assert_eq!(diff["seconds"], 2 * 86_400 + 12 * 3600);
+
+        let added = run(json!({"task": "add", "date": "2024-12-30", "days": 3}))
+            .await
+            .unwrap();
+        assert_eq!(added, json!({"date": "2025-01-02", "weekday": "Thursday"}));
+
+        let now = run(json!({})).await.unwrap();
+        assert!(now["unix"].as_i64().unwrap() > 1_700_000_000);
+
+        assert!(
+            run(json!({"task": "diff", "from": "yesterday", "to": "2024-01-01"}))
+                .await
+                .is_err()
+        );
+        assert!(
+            run(json!({"task": "add", "date": "2024-01-01"}))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use crate::error::KowalskiError;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::{Component, Path, PathBuf};
+
+const DEFAULT_MAX_READ_BYTES: usize = 64 * 1024;
+
+/// File access confined to a root directory: `list_dir` and `read_file`, plus `write_file` when
+/// built [`writable`](Self::writable). Paths are relative to the root; anything that resolves
+/// outside it (`..`, absolute paths, symlinks) is refused.
+#[derive(Debug, Clone)]
+pub struct FsTool {
+    root: PathBuf,
+    max_read_bytes: usize,
+    writable: bool,
+}
+
+impl FsTool {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            max_read_bytes: DEFAULT_MAX_READ_BYTES,
+            writable: false,
+        }
+    }
+
+    /// Enables `write_file`. Writes are destructive calls, so an agent only runs them through
+    /// its tool approver.
+    pub fn writable(mut self) -> Self {
+        self.writable = true;
+        self
+    }
+
+    /// `read_file` returns at most this many bytes (default 64 KiB).
+    pub fn with_max_read_bytes(mut self, max_read_bytes: usize) -> Self {
+        self.max_read_bytes = max_read_bytes;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn resolve(&self, path: &str) -> Result<(PathBuf, PathBuf), KowalskiError> {
+        resolve_within(&self.root, path, self.name())
+    }
+
+    fn list_dir(&self, path: &str) -> Result<serde_json::Value, KowalskiError> {
+        let (root, dir) = self.resolve(path)?;
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            entries.push(json!({
+                "name": entry.file_name().to_string_lossy(),
+                "type": if metadata.is_dir() { "dir" } else { "file" },
+                "size": metadata.len(),
+            }));
+        }
+        entries.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        Ok(json!({"path": relative(&root, &dir), "entries": entries}))
+    }
+
+    fn read_file(&self, path: &str) -> Result<serde_json::Value, KowalskiError> {
+        let (root, file) = self.resolve(path)?;
+        let bytes = std::fs::read(&file)?;
+        let truncated = bytes.len() > self.max_read_bytes;
+        let content = String::from_utf8_lossy(&bytes[..bytes.len().min(self.max_read_bytes)]);
+        Ok(json!({
+            "path": relative(&root, &file),
+            "content": content,
+            "size": bytes.len(),
+            "truncated": truncated,
+        }))
+    }
+
+    /// Creates or replaces `path` (and its missing parent directories) with `content`.
+    fn write_file(&self, path: &str, content: &str) -> Result<serde_json::Value, KowalskiError> {
+        if !self.writable {
+            return Err(KowalskiError::PermissionDenied(
+                "fs_tool is read-only; write_file is disabled".to_string(),
+            ));
+        }
+        let (root, file) = match self.resolve(path) {
+            Err(KowalskiError::ToolInvalidInput(_)) => resolve_new(&self.root, path, self.name())?,
+            resolved => resolved?,
+        };
+        if file.is_dir() {
+            return Err(KowalskiError::ToolInvalidInput(format!(
+                "'{path}' is a directory"
+            )));
+        }
+        let created = !file.exists();
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&file, content)?;
+        Ok(json!({
+            "path": relative(&root, &file),
+            "size": content.len(),
+            "created": created,
+        }))
+    }
+}
+
+/// Resolves `path` against `root` and rejects anything that escapes it. Returns the
+/// canonical root and path; errors name `tool`.
+pub(crate) fn resolve_within(
+    root: &Path,
+    path: &str,
+    tool: &str,
+) -> Result<(PathBuf, PathBuf), KowalskiError> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| KowalskiError::ToolConfig(format!("{tool} root {}: {e}", root.display())))?;
+    let resolved = root
+        .join(Path::new(path))
+        .canonicalize()
+        .map_err(|e| KowalskiError::ToolInvalidInput(format!("'{path}': {e}")))?;
+    if !resolved.starts_with(&root) {
+        return Err(KowalskiError::PermissionDenied(format!(
+            "'{path}' is outside the {tool} root"
+        )));
+    }
+    Ok((root, resolved))
+}
+
+/// `path` under `root` for a file that does not exist yet: relative, without `..`, and with its
+/// closest existing parent inside the root. Errors name `tool`.
+pub(crate) fn resolve_new(
+    root: &Path,
+    path: &str,
+    tool: &str,
+) -> Result<(PathBuf, PathBuf), KowalskiError> {
+    let (root, _) = resolve_within(root, ".", tool)?;
+    let relative_path = Path::new(path);
+    if relative_path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(KowalskiError::PermissionDenied(format!(
+            "'{path}' is outside the {tool} root"
+        )));
+    }
+    let target = root.join(relative_path);
+    let mut parent = target.parent();
+    while let Some(dir) = parent {
+        if dir.exists() {
+            let dir = dir.canonicalize()?;
+            if !dir.starts_with(&root) {
+                return Err(KowalskiError::PermissionDenied(format!(
+                    "'{path}' is outside the {tool} root"
+                )));
+            }
+            break;
+        }
+        parent = dir.parent();
+    }
+    Ok((root, target))
+}
+
+pub(crate) fn relative(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+        Ok(rel) => rel.display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+#[async_trait]
+impl Tool for FsTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let path = input
+            .parameters
+            .get("path")
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.is_empty())
+            .unwrap_or(".");
+        let result = match input.task_type.as_str() {
+            "list_dir" | "default" => self.list_dir(path)?,
+            "read_file" => self.read_file(path)?,
+            "write_file" => {
+                let content = input
+                    .parameters
+                    .get("content")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        KowalskiError::ToolInvalidInput(
+                            "Missing required parameter: content".to_string(),
+                        )
+                    })?;
+                self.write_file(path, content)?
+            }
+            other => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "Unknown fs_tool task '{other}' (expected list_dir, read_file or write_file)"
+                )));
+            }
+        };
+        let source = match result["path"].as_str() {
+            Some(".") | None => self.root.clone(),
+            Some(relative) => self.root.join(relative),
+        };
+        Ok(ToolOutput::new(
+            result,
+            Some(json!({"root": self.root.display().to_string()})),
+        )
+        .with_source(source.display().to_string()))
+    }
+
+    fn name(&self) -> &str {
+        "fs_tool"
+    }
+
+    fn description(&self) -> &str {
+        if self.writable {
+            "Reads and writes files under the working directory. Tasks: list_dir (entries of a directory), read_file (text of a file) and write_file (replace a file with content)."
+        } else {
+            "Reads files under the working directory (read-only). Tasks: list_dir (entries of a directory) and read_file (text of a file)."
+        }
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "task".to_string(),
+                description: if self.writable {
+                    "list_dir, read_file or write_file"
+                } else {
+                    "list_dir or read_file"
+                }
+                .to_string(),
+                required: true,
+                default_value: Some("list_dir".to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "path".to_string(),
+                description: "Path relative to the tool root".to_string(),
+                required: false,
+                default_value: Some(".".to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "content".to_string(),
+                description: "New file text (write_file)".to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+        ]
+    }
+
+    fn is_destructive(&self, parameters: &serde_json::Value) -> bool {
+        parameters["task"] == "write_file"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(params: serde_json::Value) -> ToolInput {
+        ToolInput::from_parameters(params)
+    }
+
+    #[tokio::test]
+    async fn lists_and_reads_inside_the_root_only() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("notes")).unwrap();
+        std::fs::write(dir.path().join("notes/a.txt"), "hello").unwrap();
+        let mut fs = FsTool::new(dir.path()).with_max_read_bytes(3);
+
+        let out = fs
+            .execute(input(json!({"task": "list_dir"})))
+            .await
+            .unwrap();
+        assert_eq!(out.result["path"], ".");
+        assert_eq!(out.result["entries"][0]["name"], "notes");
+        assert_eq!(out.result["entries"][0]["type"], "dir");
+
+        let out = fs
+            .execute(input(json!({"task": "read_file", "path": "notes/a.txt"})))
+            .await
+            .unwrap();
+        assert_eq!(out.result["content"], "hel");
+        assert_eq!(out.result["truncated"], true);
+
+        for path in ["..", "/etc/hostname", "notes/../../"] {
+            let err = fs
+                .execute(input(json!({"task": "read_file", "path": path})))
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    KowalskiError::PermissionDenied(_) | KowalskiError::ToolInvalidInput(_)
+                ),
+                "{path}: {err}"
+            );
+        }
+        assert!(
+            fs.execute(input(json!({"task": "write_file", "path": "x"})))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_need_a_writable_tool_and_stay_in_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str| input(json!({"task": "write_file", "path": path, "content": "x"}));
+
+        let err = FsTool::new(dir.path())
+            .execute(write("a.txt"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)), "{err}");
+
+        let mut fs = FsTool::new(dir.path()).writable();
+        assert!(fs.is_destructive(&json!({"task": "write_file"})));
+        assert!(!fs.is_destructive(&json!({"task": "read_file"})));
+        let out = fs.execute(write("tests/a_test.rs")).await.unwrap();
+        assert_eq!(out.result["path"], "tests/a_test.rs");
+        assert_eq!(out.result["created"], true);
+        let out = fs.execute(write("tests/a_test.rs")).await.unwrap();
+        assert_eq!(out.result["created"], false);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("tests/a_test.rs")).unwrap(),
+            "x"
+        );
+        for path in ["../x", "/tmp/x", "tests"] {
+            assert!(fs.execute(write(path)).await.is_err(), "{path}");
+        }
+    }
+}
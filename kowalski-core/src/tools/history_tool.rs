@@ -0,0 +1,227 @@
+use crate::error::KowalskiError;
+use crate::memory::{MemoryProvider, MemoryUnit};
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 20;
+
+type SharedMemory = Arc<Mutex<dyn MemoryProvider + Send + Sync>>;
+
+/// `search_history`: past messages from an agent's episodic memory that match a query (by
+/// embedding similarity and recency, or by keyword when there are no embeddings), with when they
+/// were said. Get one for an agent with
+/// [`BaseAgent::history_search_tool`](crate::agent::BaseAgent::history_search_tool).
+#[derive(Clone)]
+pub struct HistorySearchTool {
+    episodic: SharedMemory,
+}
+
+impl HistorySearchTool {
+    pub fn new(episodic: SharedMemory) -> Self {
+        Self { episodic }
+    }
+
+    /// Up to `limit` matches for `query`, best first.
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<MemoryUnit>, KowalskiError> {
+        if query.trim().is_empty() {
+            return Err(KowalskiError::ToolInvalidInput(
+                "Missing required parameter: query".to_string(),
+            ));
+        }
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        self.episodic.lock().await.retrieve(query, limit).await
+    }
+}
+
+/// `2024-05-01 14:03 UTC` for a Unix timestamp.
+pub fn format_timestamp(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl Tool for HistorySearchTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let query = input
+            .parameters
+            .get("query")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let limit = input
+            .parameters
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_LIMIT, |n| n as usize)
+            .clamp(1, MAX_LIMIT);
+        let matches: Vec<_> = self
+            .search(query, limit)
+            .await?
+            .into_iter()
+            .map(|unit| {
+                json!({
+                    "time": format_timestamp(unit.timestamp),
+                    "timestamp": unit.timestamp,
+                    "content": unit.content,
+                })
+            })
+            .collect();
+        Ok(
+            ToolOutput::new(json!({ "query": query, "matches": matches }), None)
+                .with_source("episodic memory"),
+        )
+    }
+
+    fn name(&self) -> &str {
+        "search_history"
+    }
+
+    fn description(&self) -> &str {
+        "Searches past conversations with this agent (e.g. what was discussed about a topic last week). Returns matching messages with their time."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "query".to_string(),
+                description: "Topic or words to look for".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "limit".to_string(),
+                description: format!("Number of messages (1-{MAX_LIMIT})"),
+                required: false,
+                default_value: Some(DEFAULT_LIMIT.to_string()),
+                parameter_type: ParameterType::Number,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{Agent, BaseAgent};
+    use crate::config::Config;
+    use crate::llm::LLMProvider;
+    use crate::memory::episodic::EpisodicBuffer;
+    use crate::testing::{MockBackend, memory};
+    use crate::tools::manager::ToolManager;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// Embeds text by topic: `[rust, garden]`.
+    fn topic_embedding(text: &str) -> Vec<f32> {
+        let text = text.to_lowercase();
+        let has = |words: &[&str]| {
+            if words.iter().any(|w| text.contains(w)) {
+                1.0
+            } else {
+                0.05
+            }
+        };
+        vec![
+            has(&["rust", "borrow", "cargo"]),
+            has(&["garden", "tomato"]),
+        ]
+    }
+
+    #[tokio::test]
+    async fn finds_seeded_episodes_by_topic() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        let llm: Arc<dyn LLMProvider> =
+            Arc::new(MockBackend::script().with_embedder(topic_embedding).build());
+        let mut episodic = EpisodicBuffer::open(&config.memory, llm.clone())
+            .await
+            .unwrap();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let week = 7 * 24 * 60 * 60;
+        for (id, age, content) in [
+            (
+                "c1-1",
+                week,
+                "[user] Why does the borrow checker reject my closure?",
+            ),
+            (
+                "c1-2",
+                week - 60,
+                "[assistant] The borrow outlives the closure; move the value in.",
+            ),
+            (
+                "c2-1",
+                3600,
+                "[user] When should I plant tomatoes in my garden?",
+            ),
+        ] {
+            episodic
+                .add(MemoryUnit {
+                    id: id.to_string(),
+                    timestamp: now - age,
+                    content: content.to_string(),
+                    embedding: None,
+                    metadata: Default::default(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let agent = BaseAgent::new(
+            config,
+            "historian",
+            "test agent",
+            llm,
+            memory(),
+            Arc::new(Mutex::new(episodic)),
+            memory(),
+            ToolManager::new(),
+        )
+        .await
+        .unwrap();
+
+        let found = Agent::search_history(&agent, "rust borrow checker", 2)
+            .await
+            .unwrap();
+        let ids: Vec<&str> = found.iter().map(|u| u.id.as_str()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"c1-1") && ids.contains(&"c1-2"), "{ids:?}");
+        assert_eq!(found[0].timestamp, now - week + 60, "newest match first");
+
+        let mut tool = agent.history_search_tool();
+        let output = tool
+            .execute(ToolInput::from_parameters(
+                json!({"query": "tomato garden", "limit": 1}),
+            ))
+            .await
+            .unwrap();
+        let matches = output.result["matches"].as_array().unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0]["timestamp"], now - 3600);
+        assert_eq!(matches[0]["time"], format_timestamp(now - 3600));
+        assert!(
+            matches[0]["content"].as_str().unwrap().contains("tomatoes"),
+            "{output:?}"
+        );
+        assert_eq!(output.source.as_deref(), Some("episodic memory"));
+
+        assert!(matches!(
+            agent.search_history("  ", 3).await,
+            Err(KowalskiError::ToolInvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::error::KowalskiError;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Elements dropped before conversion: never useful as page content.
+static ALWAYS_STRIP: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<(script|style|noscript|template)\b[^>]*>.*?</\s*(script|style|noscript|template)\s*>")
+        .expect("ALWAYS_STRIP regex")
+});
+
+/// Page chrome dropped when `strip_boilerplate` is on.
+static BOILERPLATE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?is)<(nav|footer|header|aside|form)\b[^>]*>.*?</\s*(nav|footer|header|aside|form)\s*>",
+    )
+    .expect("BOILERPLATE regex")
+});
+
+static EXTRA_BLANK_LINES: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\n{3,}").expect("EXTRA_BLANK_LINES regex"));
+
+/// Converts scraped HTML into Markdown (headings, lists, links, code blocks), optionally
+/// dropping navigation/footer boilerplate first.
+#[derive(Debug, Default, Clone)]
+pub struct HtmlToMarkdownTool;
+
+impl HtmlToMarkdownTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Converts `html` to Markdown. With `strip_boilerplate`, `<nav>`, `<header>`, `<footer>`,
+    /// `<aside>` and `<form>` are removed before conversion.
+    pub fn convert(html: &str, strip_boilerplate: bool) -> String {
+        let mut cleaned = ALWAYS_STRIP.replace_all(html, "").into_owned();
+        if strip_boilerplate {
+            cleaned = BOILERPLATE.replace_all(&cleaned, "").into_owned();
+        }
+        let markdown = html2md::parse_html(&cleaned);
+        EXTRA_BLANK_LINES
+            .replace_all(markdown.trim(), "\n\n")
+            .into_owned()
+    }
+}
+
+#[async_trait]
+impl Tool for HtmlToMarkdownTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let html = input
+            .parameters
+            .get("html")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .unwrap_or(input.content);
+        if html.trim().is_empty() {
+            return Err(KowalskiError::ToolInvalidInput(
+                "Missing required parameter: html".to_string(),
+            ));
+        }
+        let strip_boilerplate = input
+            .parameters
+            .get("strip_boilerplate")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let markdown = Self::convert(&html, strip_boilerplate);
+        Ok(ToolOutput::new(
+            serde_json::json!({ "markdown": markdown }),
+            Some(serde_json::json!({
+                "input_bytes": html.len(),
+                "output_bytes": markdown.len(),
+                "strip_boilerplate": strip_boilerplate,
+            })),
+        )
+        .with_source(self.name()))
+    }
+
+    fn name(&self) -> &str {
+        "html_to_markdown"
+    }
+
+    fn description(&self) -> &str {
+        "Converts HTML into clean Markdown, preserving headings, lists, links and code blocks. Drops nav/header/footer boilerplate by default."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "html".to_string(),
+                description: "HTML document or fragment to convert".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "strip_boilerplate".to_string(),
+                description:
+                    "Remove <nav>, <header>, <footer>, <aside> and <form> before converting"
+                        .to_string(),
+                required: false,
+                default_value: Some("true".to_string()),
+                parameter_type: ParameterType::Boolean,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head><style>body { color: red; }</style></head><body>
+<nav><a href="/">Home</a> | <a href="/about">About</a></nav>
+<h1>Kowalski</h1>
+<p>Read the <a href="https://example.com/docs">docs</a>.</p>
+<ul><li>memory</li><li>tools</li></ul>
+<pre><code>cargo run</code></pre>
+<footer>Copyright 2026</footer>
+<script>track();</script>
+</body></html>"#;
+
+    #[tokio::test]
+    async fn converts_snippet_and_strips_boilerplate() {
+        let mut tool = HtmlToMarkdownTool::new();
+        let out = tool
+            .execute(ToolInput::new(
+                "convert".to_string(),
+                String::new(),
+                serde_json::json!({ "html": PAGE }),
+            ))
+            .await
+            .unwrap();
+        let md = out.result["markdown"].as_str().unwrap();
+
+        assert!(md.contains("Kowalski\n=="), "heading missing: {md}");
+        assert!(md.contains("[docs](https://example.com/docs)"));
+        assert!(md.contains("* memory"));
+        assert!(md.contains("cargo run"));
+        assert!(!md.contains("About"));
+        assert!(!md.contains("Copyright"));
+        assert!(!md.contains("track()"));
+        assert!(!md.contains("color: red"));
+    }
+
+    #[test]
+    fn keeps_boilerplate_when_asked() {
+        let md = HtmlToMarkdownTool::convert(PAGE, false);
+        assert!(md.contains("Copyright 2026"));
+        assert!(!md.contains("track()"));
+    }
+
+    #[tokio::test]
+    async fn empty_html_is_rejected() {
+        let mut tool = HtmlToMarkdownTool::new();
+        let err = tool
+            .execute(ToolInput::new(
+                "convert".to_string(),
+                "  ".to_string(),
+                serde_json::json!({}),
+            ))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::ToolInvalidInput(_)));
+    }
+}
@@ -0,0 +1,328 @@
+use crate::config::{Config, VisionConfig};
+use crate::conversation::ImageData;
+use crate::error::KowalskiError;
+use crate::tools::fs::resolve_within;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use crate::web::guard::{check_url, fetch_client};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// Describes images with a multimodal Ollama model (`[vision] model`, e.g. `llava`): the
+/// `describe_image` task reads a file under the tool root or downloads an `http(s)` URL,
+/// base64-encodes it and sends it to `/api/generate` in the `images` field. Lets agents reason
+/// about screenshots and diagrams. URLs must be on public addresses unless
+/// [`with_private_addresses`](Self::with_private_addresses) allows others.
+#[derive(Debug, Clone)]
+pub struct ImageTool {
+    base_url: String,
+    config: VisionConfig,
+    root: PathBuf,
+    client: reqwest::Client,
+    allow_private: bool,
+}
+
+impl ImageTool {
+    /// Sends images to the Ollama server at `base_url` (e.g. `http://localhost:11434`); files
+    /// are read under the working directory.
+    pub fn new(base_url: impl Into<String>, config: VisionConfig) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            config,
+            root: PathBuf::from("."),
+            client: crate::llm::shared_http_client(),
+            allow_private: false,
+        }
+    }
+
+    /// The configured Ollama endpoint and `[vision]` settings; image URLs follow
+    /// `[web] allow_private_addresses`.
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            format!("http://{}:{}", config.ollama.host, config.ollama.port),
+            config.vision.clone(),
+        )
+        .with_private_addresses(config.web.allow_private_addresses)
+    }
+
+    /// Reads image paths relative to `root`; paths that resolve outside it are refused.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Also downloads image URLs on loopback, private and link-local addresses.
+    pub fn with_private_addresses(mut self, allow: bool) -> Self {
+        self.allow_private = allow;
+        self
+    }
+
+    pub fn config(&self) -> &VisionConfig {
+        &self.config
+    }
+
+    /// The image at `image`, a path under the root or an `http(s)` URL, and its size in bytes.
+    async fn load(&self, image: &str) -> Result<(ImageData, usize), KowalskiError> {
+        let max = self.config.max_image_bytes;
+        let too_large = |len: u64| {
+            KowalskiError::ToolInvalidInput(format!(
+                "Image {image} is larger than the {max} byte limit ({len} bytes)"
+            ))
+        };
+        if image.starts_with("http://") || image.starts_with("https://") {
+            let url = url::Url::parse(image)?;
+            if !self.allow_private {
+                check_url(&url)?;
+            }
+            // Not the pooled Ollama client: this one keeps to public addresses.
+            let mut response = fetch_client(self.allow_private)?.get(url).send().await?;
+            if !response.status().is_success() {
+                return Err(KowalskiError::ToolExecution(format!(
+                    "Fetching {image} failed: {}",
+                    response.status()
+                )));
+            }
+            if let Some(len) = response.content_length().filter(|len| *len > max) {
+                return Err(too_large(len));
+            }
+            let mut bytes = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                bytes.extend_from_slice(&chunk);
+                if bytes.len() as u64 > max {
+                    return Err(too_large(bytes.len() as u64));
+                }
+            }
+            return Ok((ImageData::from_bytes(&bytes), bytes.len()));
+        }
+        let (_, file) = resolve_within(&self.root, image, self.name())?;
+        let len = std::fs::metadata(&file)?.len();
+        if len > max {
+            return Err(too_large(len));
+        }
+        let data = ImageData::from_path_with_limit(&file, max)
+            .map_err(|e| KowalskiError::ToolInvalidInput(e.to_string()))?;
+        Ok((data, len as usize))
+    }
+
+    async fn describe_image(&self, input: &ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let params = &input.parameters;
+        let text = |name: &str| {
+            params
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let image = text("image").ok_or_else(|| {
+            KowalskiError::ToolInvalidInput(
+                "describe_image needs an 'image' path or URL".to_string(),
+            )
+        })?;
+        let prompt = text("prompt").unwrap_or(&self.config.prompt);
+        let model = text("model").unwrap_or(&self.config.model);
+        let (data, bytes) = self.load(image).await?;
+
+        let response = self
+            .client
+            .post(format!("{}/api/generate", self.base_url))
+            .json(&json!({
+                "model": model,
+                "prompt": prompt,
+                "images": [data],
+                "stream": false,
+            }))
+            .send()
+            .await?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            let reason = body["error"].as_str().unwrap_or("no reason given");
+            return Err(KowalskiError::ToolExecution(format!(
+                "{model} could not describe {image}: {status}: {reason}"
+            )));
+        }
+        let description = body["response"].as_str().unwrap_or_default().trim();
+        if description.is_empty() {
+            return Err(KowalskiError::ToolExecution(format!(
+                "{model} returned no description of {image}"
+            )));
+        }
+        Ok(ToolOutput::new(
+            json!({"description": description, "model": model}),
+            Some(json!({"image_bytes": bytes})),
+        )
+        .with_source(image))
+    }
+}
+
+#[async_trait]
+impl Tool for ImageTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        match input.task_type.as_str() {
+            "describe_image" | "default" => self.describe_image(&input).await,
+            other => Err(KowalskiError::ToolInvalidInput(format!(
+                "Unknown task '{other}' for image_tool; use describe_image"
+            ))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "image_tool"
+    }
+
+    fn description(&self) -> &str {
+        "Describes an image (screenshot, photo, diagram, chart) with a vision model. Task \
+         describe_image: 'image' is a file path or http(s) URL; 'prompt' asks something specific \
+         about it."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "task".to_string(),
+                description: "describe_image".to_string(),
+                required: true,
+                default_value: Some("describe_image".to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "image".to_string(),
+                description: "Image file relative to the tool root, or an http(s) URL".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "prompt".to_string(),
+                description: "What to ask about the image (default: a full description)"
+                    .to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "model".to_string(),
+                description: "Vision model to use instead of the configured one".to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::Value;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/images");
+
+    /// A mock Ollama answering `/api/generate` (recording each body) and serving the fixture
+    /// image at `/two_bars.png`.
+    async fn spawn_ollama() -> (String, Arc<Mutex<Vec<Value>>>) {
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let recorded = bodies.clone();
+        let app = Router::new()
+            .route(
+                "/api/generate",
+                post(move |Json(body): Json<Value>| async move {
+                    recorded.lock().unwrap().push(body);
+                    Json(json!({"response": " Two red bars and two blue bars. ", "done": true}))
+                }),
+            )
+            .route(
+                "/two_bars.png",
+                get(|| async { std::fs::read(Path::new(FIXTURES).join("two_bars.png")).unwrap() }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), bodies)
+    }
+
+    fn input(params: Value) -> ToolInput {
+        ToolInput::from_parameters(params)
+    }
+
+    #[tokio::test]
+    async fn describes_files_and_urls_with_the_vision_model() {
+        let (base_url, bodies) = spawn_ollama().await;
+        let mut tool = ImageTool::new(&base_url, VisionConfig::default())
+            .with_root(FIXTURES)
+            .with_private_addresses(true);
+        let expected = ImageData::from_path(&Path::new(FIXTURES).join("two_bars.png")).unwrap();
+
+        let out = tool
+            .execute(input(
+                json!({"task": "describe_image", "image": "two_bars.png"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(out.result["description"], "Two red bars and two blue bars.");
+        assert_eq!(out.result["model"], "llava");
+        assert_eq!(out.source.as_deref(), Some("two_bars.png"));
+
+        let url = format!("{base_url}/two_bars.png");
+        tool.execute(input(json!({
+            "image": url,
+            "prompt": "How many bars?",
+            "model": "llama3.2-vision",
+        })))
+        .await
+        .unwrap();
+
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0]["model"], "llava");
+        assert_eq!(bodies[0]["prompt"], VisionConfig::default().prompt);
+        assert_eq!(bodies[0]["stream"], false);
+        assert_eq!(bodies[0]["images"], json!([expected.as_base64()]));
+        assert_eq!(bodies[1]["model"], "llama3.2-vision");
+        assert_eq!(bodies[1]["prompt"], "How many bars?");
+        assert_eq!(bodies[1]["images"], bodies[0]["images"]);
+    }
+
+    #[tokio::test]
+    async fn refuses_large_images_and_paths_outside_the_root() {
+        let (base_url, bodies) = spawn_ollama().await;
+        let config = VisionConfig {
+            max_image_bytes: 16,
+            ..VisionConfig::default()
+        };
+        let mut tool = ImageTool::new(&base_url, config)
+            .with_root(FIXTURES)
+            .with_private_addresses(true);
+
+        for image in ["two_bars.png", &format!("{base_url}/two_bars.png")] {
+            let err = tool
+                .execute(input(json!({"image": image})))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("16 byte limit"), "{err}");
+        }
+        let err = tool
+            .execute(input(json!({"image": "../data/semicolons.csv"})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)), "{err}");
+        assert!(tool.execute(input(json!({}))).await.is_err());
+        assert!(bodies.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn refuses_urls_on_local_addresses_by_default() {
+        let (base_url, bodies) = spawn_ollama().await;
+        let mut tool = ImageTool::new(&base_url, VisionConfig::default());
+        let err = tool
+            .execute(input(json!({"image": format!("{base_url}/two_bars.png")})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)), "{err}");
+        assert!(bodies.lock().unwrap().is_empty());
+    }
+}
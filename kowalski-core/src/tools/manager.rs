@@ -1,5 +1,5 @@
 use crate::error::KowalskiError;
-use crate::tools::{Tool, ToolInput, ToolOutput};
+use crate::tools::{Tool, ToolInput, ToolMetadata, ToolOutput, ToolParameter};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
@@ -52,9 +52,10 @@ impl ToolManager {
 
     /// Execute a tool
     pub async fn execute(&self, name: &str, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
-        let tool = self
-            .get(name)
-            .ok_or_else(|| KowalskiError::ToolExecution(format!("Tool '{}' not found", name)))?;
+        let tool = self.get(name).ok_or_else(|| KowalskiError::ToolNotFound {
+            name: name.to_string(),
+            available: self.tool_names(),
+        })?;
 
         let mut tool_guard = tool.lock().await;
         tool_guard.execute(input).await
@@ -81,6 +82,32 @@ impl ToolManager {
         descriptions
     }
 
+    /// Declared parameters of tool `name`, or `None` if it is not registered.
+    pub async fn parameters(&self, name: &str) -> Option<Vec<ToolParameter>> {
+        let tool = self.get(name)?;
+        let tool_guard = tool.lock().await;
+        Some(tool_guard.parameters())
+    }
+
+    /// Whether calling tool `name` with `parameters` is destructive (see
+    /// [`Tool::is_destructive`]); `false` when it is not registered.
+    pub async fn is_destructive(&self, name: &str, parameters: &serde_json::Value) -> bool {
+        match self.get(name) {
+            Some(tool) => tool.lock().await.is_destructive(parameters),
+            None => false,
+        }
+    }
+
+    /// Names of all registered tools, sorted.
+    pub fn tool_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = match self.tools.read() {
+            Ok(tools) => tools.keys().cloned().collect(),
+            Err(_) => Vec::new(),
+        };
+        names.sort();
+        names
+    }
+
     /// List all registered tools (name, description)
     pub async fn list_tools(&self) -> Vec<(String, String)> {
         let tools_snapshot: Vec<SharedTool> = if let Ok(tools) = self.tools.read() {
@@ -100,6 +127,27 @@ impl ToolManager {
         result
     }
 
+    /// Name, description and parameters of every registered tool, sorted by name.
+    pub async fn metadata(&self) -> Vec<ToolMetadata> {
+        let tools_snapshot: Vec<SharedTool> = if let Ok(tools) = self.tools.read() {
+            tools.values().cloned().collect()
+        } else {
+            return Vec::new();
+        };
+
+        let mut result = Vec::new();
+        for tool in tools_snapshot {
+            let tool_guard = tool.lock().await;
+            result.push(ToolMetadata {
+                name: tool_guard.name().to_string(),
+                description: tool_guard.description().to_string(),
+                parameters: tool_guard.parameters(),
+            });
+        }
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+        result
+    }
+
     /// Generate a JSON schema for all registered tools (OpenAI-style function calling format)
     pub async fn generate_json_schema(&self) -> serde_json::Value {
         let tools_snapshot: Vec<SharedTool> = if let Ok(tools) = self.tools.read() {
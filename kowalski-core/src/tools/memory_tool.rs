@@ -0,0 +1,198 @@
+use crate::config::MemoryConfig;
+use crate::error::KowalskiError;
+use crate::memory::kv::KeyValueStore;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+
+/// Explicit key-value memory the model can call: `remember` (key, value), `recall` (key) and
+/// `forget` (key). Values persist in [`KeyValueStore`], next to the episodic buffer.
+#[derive(Clone)]
+pub struct MemoryTool {
+    store: Arc<KeyValueStore>,
+}
+
+impl MemoryTool {
+    pub fn new(store: Arc<KeyValueStore>) -> Self {
+        Self { store }
+    }
+
+    /// Opens a [`KeyValueStore`] for `memory` under `namespace` (e.g. the agent name).
+    pub async fn open(memory: &MemoryConfig, namespace: &str) -> Result<Self, KowalskiError> {
+        Ok(Self::new(Arc::new(
+            KeyValueStore::open(memory, namespace).await?,
+        )))
+    }
+
+    fn required_str<'a>(input: &'a ToolInput, name: &str) -> Result<&'a str, KowalskiError> {
+        input
+            .parameters
+            .get(name)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| {
+                KowalskiError::ToolInvalidInput(format!("Missing required parameter: {name}"))
+            })
+    }
+}
+
+#[async_trait]
+impl Tool for MemoryTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let task = input
+            .parameters
+            .get("task")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&input.task_type)
+            .to_string();
+        let key = Self::required_str(&input, "key")?;
+
+        let result = match task.as_str() {
+            "remember" => {
+                let value = Self::required_str(&input, "value")?;
+                self.store.set(key, value).await?;
+                json!({ "key": key, "stored": true })
+            }
+            "recall" => {
+                let value = self.store.get(key).await?;
+                json!({ "key": key, "found": value.is_some(), "value": value })
+            }
+            "forget" => {
+                let forgotten = self.store.delete(key).await?;
+                json!({ "key": key, "forgotten": forgotten })
+            }
+            other => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "Unknown task '{other}' (expected remember, recall or forget)"
+                )));
+            }
+        };
+        Ok(ToolOutput::new(
+            result,
+            Some(json!({ "task": task, "namespace": self.store.namespace() })),
+        )
+        .with_source(format!("memory:{}/{key}", self.store.namespace())))
+    }
+
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn description(&self) -> &str {
+        "Explicit key-value memory that persists across turns. Tasks: remember (key, value), recall (key), forget (key)."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "task".to_string(),
+                description: "One of: remember, recall, forget".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "key".to_string(),
+                description: "Name of the fact, e.g. \"user_name\"".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "value".to_string(),
+                description: "Value to store (remember only)".to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(params: serde_json::Value) -> ToolInput {
+        let task = params["task"].as_str().unwrap_or_default().to_string();
+        ToolInput::new(task, String::new(), params)
+    }
+
+    fn memory_config(dir: &tempfile::TempDir) -> MemoryConfig {
+        MemoryConfig {
+            episodic_path: dir.path().to_string_lossy().to_string(),
+            ..MemoryConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn remember_recall_forget_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tool = MemoryTool::open(&memory_config(&dir), "agent-a")
+            .await
+            .unwrap();
+
+        let out = tool
+            .execute(input(
+                json!({"task": "remember", "key": "user_name", "value": "Bob"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(out.result["stored"], true);
+
+        let out = tool
+            .execute(input(json!({"task": "recall", "key": "user_name"})))
+            .await
+            .unwrap();
+        assert_eq!(out.result["value"], "Bob");
+
+        let out = tool
+            .execute(input(json!({"task": "forget", "key": "user_name"})))
+            .await
+            .unwrap();
+        assert_eq!(out.result["forgotten"], true);
+
+        let out = tool
+            .execute(input(json!({"task": "recall", "key": "user_name"})))
+            .await
+            .unwrap();
+        assert_eq!(out.result["found"], false);
+        assert!(out.result["value"].is_null());
+    }
+
+    #[tokio::test]
+    async fn values_persist_and_namespaces_are_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = memory_config(&dir);
+        {
+            let store = KeyValueStore::open(&cfg, "agent-a").await.unwrap();
+            store.set("city", "Warsaw").await.unwrap();
+            store.set("city", "Krakow").await.unwrap();
+        }
+        let a = KeyValueStore::open(&cfg, "agent-a").await.unwrap();
+        let b = KeyValueStore::open(&cfg, "agent-b").await.unwrap();
+        assert_eq!(a.get("city").await.unwrap().as_deref(), Some("Krakow"));
+        assert_eq!(a.keys().await.unwrap(), vec!["city"]);
+        assert_eq!(b.get("city").await.unwrap(), None);
+        assert!(!b.delete("city").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_task_and_missing_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tool = MemoryTool::open(&memory_config(&dir), "agent-a")
+            .await
+            .unwrap();
+        let err = tool
+            .execute(input(json!({"task": "remember", "key": "k"})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::ToolInvalidInput(_)));
+        let err = tool
+            .execute(input(json!({"task": "list", "key": "k"})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::ToolInvalidInput(_)));
+    }
+}
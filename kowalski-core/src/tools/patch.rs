@@ -0,0 +1,1005 @@
+//! Unified-diff patches for code agents. `propose` checks a diff the model wrote against the
+//! current files and reports, per hunk, where it applies. `apply` writes it inside the tool root
+//! after backing up what it changes. `revert_last_patch` undoes the last applied patch. Backups
+//! are a `git stash` entry when every changed file is tracked in a git work tree, and `.bak`
+//! copies next to the files otherwise.
+//!
+//! Hunks are placed by their context, starting at the line the header names and moving outwards,
+//! so a diff made against a slightly older file still lands. The `fuzz` knob sets how strict that
+//! is: 0 wants every context line (trailing whitespace aside); each step up lets one more context
+//! line at either end of a hunk differ, and ignores whitespace inside lines.
+
+use crate::error::KowalskiError;
+use crate::tools::fs::{relative, resolve_new, resolve_within};
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+
+/// Default for the `fuzz` parameter.
+pub const DEFAULT_FUZZ: usize = 1;
+/// Highest accepted `fuzz`.
+const MAX_FUZZ: usize = 3;
+
+static HUNK_HEADER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hunk {
+    /// 1-based line of the old file the hunk starts at (0 for an empty old side).
+    old_start: usize,
+    lines: Vec<HunkLine>,
+    /// `\ No newline at end of file` follows the new side's last line.
+    no_newline_at_end: bool,
+}
+
+impl Hunk {
+    /// Old- and new-side lines with `lead` context lines dropped from the start and `trail`
+    /// from the end.
+    fn sides(&self, lead: usize, trail: usize) -> (Vec<&str>, Vec<&str>) {
+        let (mut old, mut new) = (Vec::new(), Vec::new());
+        for line in &self.lines[lead..self.lines.len() - trail] {
+            match line {
+                HunkLine::Context(text) => {
+                    old.push(text.as_str());
+                    new.push(text.as_str());
+                }
+                HunkLine::Remove(text) => old.push(text),
+                HunkLine::Add(text) => new.push(text),
+            }
+        }
+        (old, new)
+    }
+
+    fn leading_context(&self) -> usize {
+        self.lines
+            .iter()
+            .take_while(|l| matches!(l, HunkLine::Context(_)))
+            .count()
+    }
+
+    fn trailing_context(&self) -> usize {
+        self.lines
+            .iter()
+            .rev()
+            .take_while(|l| matches!(l, HunkLine::Context(_)))
+            .count()
+    }
+
+    fn old_len(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|l| !matches!(l, HunkLine::Add(_)))
+            .count()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Change {
+    Modified,
+    Created,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FilePatch {
+    path: String,
+    change: Change,
+    hunks: Vec<Hunk>,
+}
+
+/// `a/src/lib.rs` → `src/lib.rs`; `None` for `/dev/null`.
+fn header_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or_default().trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// The file patches of a unified diff; prose and a surrounding code fence are skipped.
+fn parse_diff(text: &str) -> Result<Vec<FilePatch>, KowalskiError> {
+    let invalid = |message: String| KowalskiError::ToolInvalidInput(message);
+    let lines: Vec<&str> = text.lines().collect();
+    let is_file_header = |i: usize| {
+        lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))
+    };
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if is_file_header(i) {
+            let old = header_path(&lines[i][4..]);
+            let new = header_path(&lines[i + 1][4..]);
+            let (path, change) = match (old, new) {
+                (Some(old), Some(new)) if old != new => {
+                    return Err(invalid(format!(
+                        "renaming {old} to {new} is not supported; delete and create instead"
+                    )));
+                }
+                (None, Some(new)) => (new, Change::Created),
+                (Some(old), None) => (old, Change::Deleted),
+                (Some(_), Some(new)) => (new, Change::Modified),
+                (None, None) => return Err(invalid("a file header names no file".to_string())),
+            };
+            files.push(FilePatch {
+                path,
+                change,
+                hunks: Vec::new(),
+            });
+            i += 2;
+            continue;
+        }
+        let Some(header) = HUNK_HEADER.captures(lines[i]) else {
+            i += 1;
+            continue;
+        };
+        let Some(file) = files.last_mut() else {
+            return Err(invalid(format!(
+                "hunk '{}' comes before any ---/+++ file header",
+                lines[i]
+            )));
+        };
+        let count = |n: usize| header.get(n).map_or(1, |m| m.as_str().parse().unwrap_or(1));
+        let old_start: usize = header[1].parse().unwrap_or(0);
+        let old_count = count(2);
+        let mut hunk = Hunk {
+            old_start,
+            lines: Vec::new(),
+            no_newline_at_end: false,
+        };
+        i += 1;
+        while i < lines.len() && !lines[i].starts_with("@@") && !is_file_header(i) {
+            let line = lines[i];
+            match line.chars().next() {
+                Some(' ') => hunk.lines.push(HunkLine::Context(line[1..].to_string())),
+                // Blank context lines often lose their leading space on the way.
+                None => hunk.lines.push(HunkLine::Context(String::new())),
+                Some('-') => hunk.lines.push(HunkLine::Remove(line[1..].to_string())),
+                Some('+') => hunk.lines.push(HunkLine::Add(line[1..].to_string())),
+                Some('\\') => {
+                    if matches!(
+                        hunk.lines.last(),
+                        Some(HunkLine::Add(_) | HunkLine::Context(_))
+                    ) {
+                        hunk.no_newline_at_end = true;
+                    }
+                }
+                _ => break,
+            }
+            i += 1;
+        }
+        // Blank lines after the hunk (before prose or the closing fence) are not part of it.
+        while hunk.old_len() > old_count
+            && hunk.lines.last() == Some(&HunkLine::Context(String::new()))
+        {
+            hunk.lines.pop();
+        }
+        if hunk.lines.is_empty() {
+            return Err(invalid(format!("an empty hunk in {}", file.path)));
+        }
+        file.hunks.push(hunk);
+    }
+    if files.is_empty() {
+        return Err(invalid(
+            "no unified diff found: expected ---/+++ file headers followed by @@ hunks".to_string(),
+        ));
+    }
+    if let Some(file) = files.iter().find(|f| f.hunks.is_empty()) {
+        return Err(invalid(format!("{} has no hunks", file.path)));
+    }
+    Ok(files)
+}
+
+/// Where one hunk went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct HunkResult {
+    /// 1-based position in the file's hunks.
+    hunk: usize,
+    applied: bool,
+    /// 1-based line of the current file the hunk's old side starts at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    /// Lines between where the header says and where the hunk matched.
+    offset: isize,
+    /// Context lines at each end that did not have to match.
+    fuzz: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn normalize(line: &str, fuzz: usize) -> String {
+    if fuzz == 0 {
+        line.trim_end().to_string()
+    } else {
+        line.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// `original` with `hunks` applied, and how each went. Hunks that do not match are left out.
+fn apply_hunks(original: &str, hunks: &[Hunk], fuzz: usize) -> (String, Vec<HunkResult>) {
+    let ends_with_newline = original.is_empty() || original.ends_with('\n');
+    let lines: Vec<&str> = if original.is_empty() {
+        Vec::new()
+    } else {
+        original
+            .strip_suffix('\n')
+            .unwrap_or(original)
+            .split('\n')
+            .collect()
+    };
+    let mut out: Vec<&str> = Vec::new();
+    let mut results = Vec::new();
+    let mut cursor = 0;
+    let mut offset: isize = 0;
+    let mut no_newline = false;
+    for (index, hunk) in hunks.iter().enumerate() {
+        let mut placed = None;
+        for level in 0..=fuzz {
+            let lead = level.min(hunk.leading_context());
+            let trail = level.min(hunk.trailing_context());
+            if lead + trail >= hunk.lines.len() {
+                break;
+            }
+            let (old, new) = hunk.sides(lead, trail);
+            let start = hunk.old_start.saturating_sub(1) + lead;
+            let expected = (start as isize + offset).max(cursor as isize) as usize;
+            let last = match lines.len().checked_sub(old.len()) {
+                Some(last) if last >= cursor => last,
+                _ => continue,
+            };
+            let expected = expected.min(last);
+            let wanted: Vec<String> = old.iter().map(|l| normalize(l, level)).collect();
+            let matches_at = |at: usize| {
+                wanted
+                    .iter()
+                    .zip(&lines[at..at + old.len()])
+                    .all(|(want, have)| *want == normalize(have, level))
+            };
+            // Closest to where the header says first, then further away.
+            let found = (0..=last - cursor).find_map(|distance| {
+                [expected.checked_sub(distance), Some(expected + distance)]
+                    .into_iter()
+                    .flatten()
+                    .filter(|at| (cursor..=last).contains(at))
+                    .find(|&at| matches_at(at))
+            });
+            if let Some(at) = found {
+                placed = Some((at, old.len(), new, level, start));
+                break;
+            }
+        }
+        match placed {
+            Some((at, old_len, new, level, start)) => {
+                out.extend_from_slice(&lines[cursor..at]);
+                out.extend(new);
+                cursor = at + old_len;
+                offset = at as isize - start as isize;
+                if hunk.no_newline_at_end && cursor == lines.len() {
+                    no_newline = true;
+                }
+                results.push(HunkResult {
+                    hunk: index + 1,
+                    applied: true,
+                    line: Some(at + 1),
+                    offset,
+                    fuzz: level,
+                    error: None,
+                });
+            }
+            None => {
+                let first = hunk.sides(0, 0).0.first().map(|l| l.trim().to_string());
+                results.push(HunkResult {
+                    hunk: index + 1,
+                    applied: false,
+                    line: None,
+                    offset: 0,
+                    fuzz,
+                    error: Some(match first {
+                        Some(first) => format!(
+                            "context not found near line {} (hunk starts with '{first}')",
+                            hunk.old_start
+                        ),
+                        None => format!("nothing to anchor the hunk at line {}", hunk.old_start),
+                    }),
+                });
+            }
+        }
+    }
+    out.extend_from_slice(&lines[cursor..]);
+    let mut text = out.join("\n");
+    if !text.is_empty() && ends_with_newline && !no_newline {
+        text.push('\n');
+    }
+    (text, results)
+}
+
+/// A file of a checked patch: where it is, what it holds now and what it will hold.
+struct PlannedFile {
+    label: String,
+    full: PathBuf,
+    change: Change,
+    /// `None` for a file the patch creates.
+    current: Option<String>,
+    /// `None` for a file the patch deletes.
+    patched: Option<String>,
+    hunks: Vec<HunkResult>,
+    error: Option<String>,
+}
+
+impl PlannedFile {
+    fn ok(&self) -> bool {
+        self.error.is_none() && self.hunks.iter().all(|h| h.applied)
+    }
+
+    fn report(&self) -> Value {
+        let mut report = json!({
+            "path": self.label,
+            "change": self.change,
+            "hunks": self.hunks,
+        });
+        if let Some(error) = &self.error {
+            report["error"] = json!(error);
+        }
+        report
+    }
+}
+
+/// How the files of an applied patch were saved.
+#[derive(Debug, Clone)]
+enum Backup {
+    /// `.bak` copies; `(file, backup)` per file that existed.
+    Files(Vec<(PathBuf, PathBuf)>),
+    /// A commit made with `git stash create` (recorded in `git stash list` when `stored`) or
+    /// `HEAD` when the tracked files had no local changes.
+    Git {
+        repo: PathBuf,
+        commit: String,
+        stored: bool,
+        files: Vec<PathBuf>,
+    },
+}
+
+impl Backup {
+    fn describe(&self) -> String {
+        match self {
+            Self::Files(_) => "files".to_string(),
+            Self::Git { commit, stored, .. } => {
+                let short = &commit[..commit.len().min(12)];
+                if *stored {
+                    format!("git stash {short}")
+                } else {
+                    format!("git HEAD {short}")
+                }
+            }
+        }
+    }
+}
+
+struct AppliedPatch {
+    labels: Vec<String>,
+    backup: Backup,
+    /// Files the patch created, removed again on revert.
+    created: Vec<PathBuf>,
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String, KowalskiError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .map_err(|e| KowalskiError::ToolExecution(format!("git {}: {e}", args.join(" "))))?;
+    if !output.status.success() {
+        return Err(KowalskiError::ToolExecution(format!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A `.bak` path next to `file` that is not taken yet.
+fn backup_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".bak");
+    let mut candidate = PathBuf::from(&name);
+    let mut n = 1;
+    while candidate.exists() {
+        let mut numbered = name.clone();
+        numbered.push(format!(".{n}"));
+        candidate = PathBuf::from(numbered);
+        n += 1;
+    }
+    candidate
+}
+
+/// Checks and applies unified diffs inside a root directory, keeping backups so the last patch
+/// can be reverted. `apply` and `revert_last_patch` report themselves as destructive, so agents
+/// only run them through a tool approver.
+pub struct PatchTool {
+    root: PathBuf,
+    fuzz: usize,
+    /// Last diff `propose` found valid; `apply` uses it when no diff is given.
+    proposed: Option<String>,
+    applied: Vec<AppliedPatch>,
+}
+
+impl PatchTool {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            fuzz: DEFAULT_FUZZ,
+            proposed: None,
+            applied: Vec::new(),
+        }
+    }
+
+    /// Default strictness of context matching (0 = exact, up to 3); see the module docs.
+    pub fn with_fuzz(mut self, fuzz: usize) -> Self {
+        self.fuzz = fuzz.min(MAX_FUZZ);
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Reads the files `diff` touches and works out their patched contents.
+    fn plan(&self, diff: &str, fuzz: usize) -> Result<Vec<PlannedFile>, KowalskiError> {
+        let mut planned = Vec::new();
+        for file in parse_diff(diff)? {
+            let resolved = match file.change {
+                Change::Created => resolve_new(&self.root, &file.path, self.name()),
+                _ => resolve_within(&self.root, &file.path, self.name()),
+            };
+            let (root, full) = match resolved {
+                Ok(resolved) => resolved,
+                Err(e @ KowalskiError::PermissionDenied(_)) => return Err(e),
+                Err(e) => {
+                    planned.push(PlannedFile {
+                        label: file.path.clone(),
+                        full: PathBuf::new(),
+                        change: file.change,
+                        current: None,
+                        patched: None,
+                        hunks: Vec::new(),
+                        error: Some(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+            let label = relative(&root, &full);
+            let (current, error) = match file.change {
+                Change::Created if full.exists() => (None, Some("already exists".to_string())),
+                Change::Created => (None, None),
+                _ if !full.is_file() => (None, Some("not a file".to_string())),
+                _ => match std::fs::read_to_string(&full) {
+                    Ok(text) => (Some(text), None),
+                    Err(e) => (None, Some(e.to_string())),
+                },
+            };
+            let (mut patched, hunks) = match &error {
+                Some(_) => (None, Vec::new()),
+                None => {
+                    let (text, hunks) =
+                        apply_hunks(current.as_deref().unwrap_or_default(), &file.hunks, fuzz);
+                    (Some(text), hunks)
+                }
+            };
+            let mut error = error;
+            if file.change == Change::Deleted {
+                let leftover = patched.take().is_some_and(|text| !text.trim().is_empty());
+                if leftover && hunks.iter().all(|h| h.applied) {
+                    error = Some("the deletion leaves lines behind".to_string());
+                }
+            }
+            planned.push(PlannedFile {
+                label,
+                full,
+                change: file.change,
+                current,
+                patched,
+                hunks,
+                error,
+            });
+        }
+        Ok(planned)
+    }
+
+    /// Saves the current state of the files `planned` changes.
+    fn back_up(&self, planned: &[PlannedFile]) -> Result<Backup, KowalskiError> {
+        let existing: Vec<&Path> = planned
+            .iter()
+            .filter(|f| f.current.is_some())
+            .map(|f| f.full.as_path())
+            .collect();
+        let (root, _) = resolve_within(&self.root, ".", self.name())?;
+        if !existing.is_empty()
+            && git(&root, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|out| out == "true")
+        {
+            let mut args = vec!["ls-files", "--error-unmatch", "--"];
+            let paths: Vec<String> = existing.iter().map(|p| p.display().to_string()).collect();
+            args.extend(paths.iter().map(String::as_str));
+            if git(&root, &args).is_ok() {
+                let message = format!(
+                    "kowalski patch: {}",
+                    planned
+                        .iter()
+                        .map(|f| f.label.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                let stash = git(&root, &["stash", "create", &message])?;
+                let (commit, stored) = if stash.is_empty() {
+                    (git(&root, &["rev-parse", "HEAD"])?, false)
+                } else {
+                    git(&root, &["stash", "store", "-m", &message, &stash])?;
+                    (stash, true)
+                };
+                return Ok(Backup::Git {
+                    repo: root,
+                    commit,
+                    stored,
+                    files: existing.iter().map(|p| p.to_path_buf()).collect(),
+                });
+            }
+        }
+        let mut copies = Vec::new();
+        for file in existing {
+            let backup = backup_path(file);
+            std::fs::copy(file, &backup).map_err(|e| {
+                KowalskiError::ToolExecution(format!("backing up {}: {e}", file.display()))
+            })?;
+            copies.push((file.to_path_buf(), backup));
+        }
+        Ok(Backup::Files(copies))
+    }
+
+    /// The `diff`, `fuzz` (the tool's default when unset) and `partial` parameters.
+    fn params<'a>(
+        &self,
+        input: &'a ToolInput,
+    ) -> Result<(Option<&'a str>, usize, bool), KowalskiError> {
+        let params = &input.parameters;
+        let diff = params
+            .get("diff")
+            .and_then(|v| v.as_str())
+            .filter(|d| !d.trim().is_empty());
+        let fuzz = match params.get("fuzz") {
+            None | Some(Value::Null) => self.fuzz,
+            Some(value) => value
+                .as_u64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .filter(|f| *f as usize <= MAX_FUZZ)
+                .ok_or_else(|| {
+                    KowalskiError::ToolInvalidInput(format!(
+                        "'fuzz' must be 0 to {MAX_FUZZ}, got {value}"
+                    ))
+                })? as usize,
+        };
+        let partial = params
+            .get("partial")
+            .is_some_and(|v| v.as_bool() == Some(true) || v.as_str() == Some("true"));
+        Ok((diff, fuzz, partial))
+    }
+
+    fn propose(&mut self, input: &ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let (diff, fuzz, _) = self.params(input)?;
+        let diff = diff.ok_or_else(|| {
+            KowalskiError::ToolInvalidInput("propose needs a unified 'diff'".to_string())
+        })?;
+        let planned = self.plan(diff, fuzz)?;
+        let valid = planned.iter().all(PlannedFile::ok);
+        self.proposed = valid.then(|| diff.to_string());
+        Ok(ToolOutput::new(
+            json!({
+                "valid": valid,
+                "files": planned.iter().map(PlannedFile::report).collect::<Vec<_>>(),
+            }),
+            Some(json!({"fuzz": fuzz})),
+        ))
+    }
+
+    fn apply(&mut self, input: &ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let (diff, fuzz, partial) = self.params(input)?;
+        let diff = match diff {
+            Some(diff) => diff.to_string(),
+            None => self.proposed.clone().ok_or_else(|| {
+                KowalskiError::ToolInvalidInput(
+                    "apply needs a unified 'diff' (or a valid one from propose first)".to_string(),
+                )
+            })?,
+        };
+        let mut planned = self.plan(&diff, fuzz)?;
+        let complete = planned.iter().all(PlannedFile::ok);
+        if !complete && !partial {
+            return Ok(ToolOutput::new(
+                json!({
+                    "applied": false,
+                    "files": planned.iter().map(PlannedFile::report).collect::<Vec<_>>(),
+                }),
+                Some(
+                    json!({"fuzz": fuzz, "reason": "some hunks do not apply; nothing was written"}),
+                ),
+            ));
+        }
+        // With `partial`, files with an error are skipped and the hunks that match are written.
+        planned.retain(|f| f.error.is_none() && f.hunks.iter().any(|h| h.applied));
+        if planned.is_empty() {
+            return Err(KowalskiError::ToolExecution(
+                "no hunk of the patch applies".to_string(),
+            ));
+        }
+        let backup = self.back_up(&planned)?;
+        let mut created = Vec::new();
+        for file in &planned {
+            let written = match (&file.patched, file.change) {
+                (None, _) => std::fs::remove_file(&file.full),
+                (Some(text), Change::Created) => {
+                    if let Some(parent) = file.full.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    created.push(file.full.clone());
+                    std::fs::write(&file.full, text)
+                }
+                (Some(text), _) => std::fs::write(&file.full, text),
+            };
+            written.map_err(|e| {
+                KowalskiError::ToolExecution(format!("writing {}: {e}", file.label))
+            })?;
+        }
+        self.proposed = None;
+        let backup_label = backup.describe();
+        self.applied.push(AppliedPatch {
+            labels: planned.iter().map(|f| f.label.clone()).collect(),
+            backup,
+            created,
+        });
+        Ok(ToolOutput::new(
+            json!({
+                "applied": true,
+                "complete": complete,
+                "files": planned.iter().map(PlannedFile::report).collect::<Vec<_>>(),
+                "backup": backup_label,
+            }),
+            Some(json!({"fuzz": fuzz})),
+        ))
+    }
+
+    fn revert_last_patch(&mut self) -> Result<ToolOutput, KowalskiError> {
+        let patch = self.applied.pop().ok_or_else(|| {
+            KowalskiError::ToolInvalidInput("no applied patch to revert".to_string())
+        })?;
+        let failed = |e: String| KowalskiError::ToolExecution(format!("reverting: {e}"));
+        for file in &patch.created {
+            if file.exists() {
+                std::fs::remove_file(file).map_err(|e| failed(e.to_string()))?;
+            }
+        }
+        match &patch.backup {
+            Backup::Files(copies) => {
+                for (file, backup) in copies {
+                    std::fs::rename(backup, file).map_err(|e| failed(e.to_string()))?;
+                }
+            }
+            Backup::Git {
+                repo,
+                commit,
+                stored,
+                files,
+            } => {
+                let mut args = vec!["restore", "--worktree", "--source"];
+                args.push(commit);
+                args.push("--");
+                let paths: Vec<String> = files.iter().map(|p| p.display().to_string()).collect();
+                args.extend(paths.iter().map(String::as_str));
+                git(repo, &args)?;
+                if *stored {
+                    let stashes = git(repo, &["stash", "list", "--format=%H"])?;
+                    if let Some(index) = stashes.lines().position(|h| h == commit) {
+                        git(repo, &["stash", "drop", &format!("stash@{{{index}}}")])?;
+                    }
+                }
+            }
+        }
+        Ok(ToolOutput::new(
+            json!({"reverted": patch.labels, "backup": patch.backup.describe()}),
+            None,
+        ))
+    }
+}
+
+#[async_trait]
+impl Tool for PatchTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        match input.task_type.as_str() {
+            "propose" | "default" => self.propose(&input),
+            "apply" => self.apply(&input),
+            "revert_last_patch" => self.revert_last_patch(),
+            other => Err(KowalskiError::ToolInvalidInput(format!(
+                "Unknown task '{other}' for patch; use propose, apply or revert_last_patch"
+            ))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "patch"
+    }
+
+    fn description(&self) -> &str {
+        "Changes files with a unified diff (---/+++ headers, @@ hunks with 3 lines of context). \
+         Task propose checks the diff against the current files and reports each hunk; apply \
+         writes it (after approval) and keeps a backup; revert_last_patch undoes the last apply."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "task".to_string(),
+                description: "propose, apply or revert_last_patch".to_string(),
+                required: true,
+                default_value: Some("propose".to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "diff".to_string(),
+                description: "Unified diff with paths relative to the project root (apply \
+                              defaults to the last valid proposal)"
+                    .to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "fuzz".to_string(),
+                description: "Context lines at each hunk end allowed to differ, 0 (exact) to 3"
+                    .to_string(),
+                required: false,
+                default_value: Some(DEFAULT_FUZZ.to_string()),
+                parameter_type: ParameterType::Number,
+            },
+            ToolParameter {
+                name: "partial".to_string(),
+                description: "apply: write the hunks that match even if others do not".to_string(),
+                required: false,
+                default_value: Some("false".to_string()),
+                parameter_type: ParameterType::Boolean,
+            },
+        ]
+    }
+
+    fn is_destructive(&self, parameters: &Value) -> bool {
+        matches!(
+            parameters.get("task").and_then(|t| t.as_str()),
+            Some("apply" | "revert_last_patch")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIB: &str = "fn one() {}\n\nfn two() {\n    let x = 2;\n    x\n}\n\nfn three() {}\n";
+
+    fn input(params: Value) -> ToolInput {
+        ToolInput::from_parameters(params)
+    }
+
+    fn read(dir: &Path, path: &str) -> String {
+        std::fs::read_to_string(dir.join(path)).unwrap()
+    }
+
+    /// Header says line 1, but `fn two` now starts on line 3.
+    const SHIFTED: &str = "Here is the fix:\n```diff\n--- a/lib.rs\n+++ b/lib.rs\n@@ -1,4 +1,4 @@\n fn two() {\n-    let x = 2;\n+    let x = 3;\n     x\n }\n```\n";
+
+    #[tokio::test]
+    async fn proposes_then_applies_a_clean_patch() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), LIB).unwrap();
+        let mut tool = PatchTool::new(dir.path());
+
+        let proposal = tool
+            .execute(input(json!({"task": "propose", "diff": SHIFTED})))
+            .await
+            .unwrap();
+        assert_eq!(proposal.result["valid"], true);
+        let hunk = &proposal.result["files"][0]["hunks"][0];
+        assert_eq!(
+            (&hunk["applied"], &hunk["line"], &hunk["offset"]),
+            (&json!(true), &json!(3), &json!(2))
+        );
+        assert_eq!(read(dir.path(), "lib.rs"), LIB);
+
+        // apply without a diff takes the proposal.
+        let applied = tool.execute(input(json!({"task": "apply"}))).await.unwrap();
+        assert_eq!(applied.result["applied"], true);
+        assert_eq!(applied.result["backup"], "files");
+        assert_eq!(read(dir.path(), "lib.rs"), LIB.replace("x = 2", "x = 3"));
+        assert_eq!(read(dir.path(), "lib.rs.bak"), LIB);
+        assert!(tool.is_destructive(&json!({"task": "apply"})));
+        assert!(!tool.is_destructive(&json!({"task": "propose"})));
+    }
+
+    #[tokio::test]
+    async fn mismatched_context_is_rejected_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), LIB).unwrap();
+        let mut tool = PatchTool::new(dir.path());
+        // The second context line does not exist in the file; fuzz 1 only drops the first.
+        let diff = "--- a/lib.rs\n+++ b/lib.rs\n@@ -3,4 +3,4 @@\n fn two() {\n     let y = 2;\n-    x\n+    x + 1\n }\n";
+
+        let out = tool
+            .execute(input(json!({"task": "apply", "diff": diff})))
+            .await
+            .unwrap();
+        assert_eq!(out.result["applied"], false);
+        let hunk = &out.result["files"][0]["hunks"][0];
+        assert_eq!(hunk["applied"], false);
+        assert!(
+            hunk["error"]
+                .as_str()
+                .unwrap()
+                .contains("context not found near line 3"),
+            "{hunk}"
+        );
+        assert_eq!(read(dir.path(), "lib.rs"), LIB);
+        assert!(!dir.path().join("lib.rs.bak").exists());
+
+        // A wrong first context line only applies once fuzz lets it go.
+        let edge = "--- a/lib.rs\n+++ b/lib.rs\n@@ -3,4 +3,4 @@\n fn deux() {\n     let x = 2;\n-    x\n+    x + 1\n }\n";
+        let strict = tool
+            .execute(input(json!({"task": "propose", "diff": edge, "fuzz": 0})))
+            .await
+            .unwrap();
+        assert_eq!(strict.result["valid"], false);
+        let fuzzy = tool
+            .execute(input(json!({"task": "propose", "diff": edge})))
+            .await
+            .unwrap();
+        assert_eq!(fuzzy.result["valid"], true);
+        assert_eq!(fuzzy.result["files"][0]["hunks"][0]["fuzz"], 1);
+
+        let outside = "--- /dev/null\n+++ b/../x.rs\n@@ -0,0 +1 @@\n+x\n";
+        let err = tool
+            .execute(input(json!({"task": "propose", "diff": outside})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)), "{err}");
+        assert!(
+            tool.execute(input(json!({"task": "apply", "diff": "no diff here"})))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn multi_file_patches_apply_and_revert_together() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), LIB).unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Demo\n\nOld text.\n").unwrap();
+        std::fs::write(dir.path().join("old.txt"), "bye\n").unwrap();
+        let mut tool = PatchTool::new(dir.path());
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,3 +1,3 @@\n-fn one() {}\n+fn one() -> u8 { 1 }\n \n fn two() {\n@@ -6,3 +6,4 @@\n }\n \n fn three() {}\n+fn four() {}\n--- a/README.md\n+++ b/README.md\n@@ -1,3 +1,3 @@\n # Demo\n\n-Old text.\n+New text.\n--- /dev/null\n+++ b/src/new/mod.rs\n@@ -0,0 +1,2 @@\n+//! New module.\n+pub fn hello() {}\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n";
+
+        let out = tool
+            .execute(input(json!({"task": "apply", "diff": diff})))
+            .await
+            .unwrap();
+        assert_eq!(out.result["applied"], true, "{}", out.result);
+        let changes: Vec<(&str, &str)> = out.result["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| (f["path"].as_str().unwrap(), f["change"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("src/lib.rs", "modified"),
+                ("README.md", "modified"),
+                ("src/new/mod.rs", "created"),
+                ("old.txt", "deleted"),
+            ]
+        );
+        assert_eq!(out.result["files"][0]["hunks"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            read(dir.path(), "src/lib.rs"),
+            LIB.replace("fn one() {}", "fn one() -> u8 { 1 }") + "fn four() {}\n"
+        );
+        assert_eq!(read(dir.path(), "README.md"), "# Demo\n\nNew text.\n");
+        assert_eq!(
+            read(dir.path(), "src/new/mod.rs"),
+            "//! New module.\npub fn hello() {}\n"
+        );
+        assert!(!dir.path().join("old.txt").exists());
+
+        let reverted = tool
+            .execute(input(json!({"task": "revert_last_patch"})))
+            .await
+            .unwrap();
+        assert_eq!(reverted.result["reverted"].as_array().unwrap().len(), 4);
+        assert_eq!(read(dir.path(), "src/lib.rs"), LIB);
+        assert_eq!(read(dir.path(), "README.md"), "# Demo\n\nOld text.\n");
+        assert_eq!(read(dir.path(), "old.txt"), "bye\n");
+        assert!(!dir.path().join("src/new/mod.rs").exists());
+        assert!(!dir.path().join("src/lib.rs.bak").exists());
+        assert!(
+            tool.execute(input(json!({"task": "revert_last_patch"})))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn git_work_trees_are_backed_up_with_a_stash() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        let Ok(_) = git(repo, &["init", "-q"]) else {
+            return; // git is not installed
+        };
+        std::fs::write(repo.join("lib.rs"), LIB).unwrap();
+        for args in [
+            &["add", "lib.rs"][..],
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@t",
+                "commit",
+                "-qm",
+                "init",
+            ],
+        ] {
+            git(repo, args).unwrap();
+        }
+        // An uncommitted change of the user's survives apply and revert.
+        let local = LIB.replace("fn three() {}", "fn three() { todo!() }");
+        std::fs::write(repo.join("lib.rs"), &local).unwrap();
+        let mut tool = PatchTool::new(repo);
+
+        let out = tool
+            .execute(input(json!({"task": "apply", "diff": SHIFTED})))
+            .await
+            .unwrap();
+        assert!(
+            out.result["backup"]
+                .as_str()
+                .unwrap()
+                .starts_with("git stash "),
+            "{}",
+            out.result
+        );
+        assert_eq!(read(repo, "lib.rs"), local.replace("x = 2", "x = 3"));
+        assert!(!repo.join("lib.rs.bak").exists());
+        assert!(
+            git(repo, &["stash", "list"])
+                .unwrap()
+                .contains("kowalski patch: lib.rs")
+        );
+
+        tool.execute(input(json!({"task": "revert_last_patch"})))
+            .await
+            .unwrap();
+        assert_eq!(read(repo, "lib.rs"), local);
+        assert_eq!(git(repo, &["stash", "list"]).unwrap(), "");
+    }
+}
@@ -0,0 +1,761 @@
+//! Dataset profiling for delimited files: the dialect (delimiter, quoting), a type with
+//! confidence per column, null rates, distinct counts, example values and data-quality issues.
+//! `csv_tool` runs it as the `profile_dataset` task, and [`DatasetProfiler`] puts the profile of
+//! every data file mentioned in a conversation in front of the model.
+
+use crate::agent::middleware::AgentMiddleware;
+use crate::agent::types::ChatRequest;
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::tools::InferredType;
+use crate::tools::csv::invalid;
+use crate::tools::fs::{relative, resolve_within};
+use crate::tools::stats::NULL_MARKERS;
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+/// Bytes read from the start of a file to sniff its dialect.
+const SNIFF_BYTES: usize = 64 * 1024;
+const SNIFF_RECORDS: usize = 50;
+const CANDIDATE_DELIMITERS: &[u8] = b",;\t|";
+/// Distinct values tracked per column; beyond this the count is a lower bound.
+const DISTINCT_CAP: usize = 1000;
+const EXAMPLES: usize = 3;
+const EXAMPLE_CHARS: usize = 40;
+const MAX_ISSUES: usize = 20;
+/// Columns listed in [`DatasetProfile::to_prompt`]; the rest are only counted.
+const PROMPT_COLUMNS: usize = 40;
+
+/// `3,14` or `1.234,56`: a number written with a decimal comma.
+static DECIMAL_COMMA: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[+-]?(\d{1,3}(\.\d{3})+|\d+),\d+$").unwrap());
+/// A path to a delimited file in free text, e.g. `data/sales.csv`.
+static DATA_FILE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:^|[\s`'(\x22])([\w./~-]*[\w-]\.(?:csv|tsv|psv))\b").unwrap()
+});
+
+/// How a delimited file is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Dialect {
+    pub delimiter: char,
+    /// The quote character, when some fields are quoted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote: Option<char>,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: None,
+        }
+    }
+}
+
+impl Dialect {
+    /// Picks the delimiter (`,` `;` tab `|`) that splits the first records into the most fields,
+    /// most consistently; comma when none splits them.
+    pub fn sniff(sample: &str) -> Self {
+        let mut best: Option<(u8, usize, usize)> = None;
+        for &delimiter in CANDIDATE_DELIMITERS {
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(delimiter)
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(sample.as_bytes());
+            let mut lengths: HashMap<usize, usize> = HashMap::new();
+            // The last record of a truncated sample may be cut short.
+            for record in reader.records().take(SNIFF_RECORDS).flatten() {
+                *lengths.entry(record.len()).or_default() += 1;
+            }
+            let Some((&fields, &agreeing)) = lengths
+                .iter()
+                .max_by_key(|&(fields, count)| (*count, *fields))
+            else {
+                continue;
+            };
+            if fields < 2 {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((_, best_agreeing, best_fields)) => {
+                    (agreeing, fields) > (best_agreeing, best_fields)
+                }
+            };
+            if better {
+                best = Some((delimiter, agreeing, fields));
+            }
+        }
+        Self::with_delimiter(sample, best.map(|(d, _, _)| d as char).unwrap_or(','))
+    }
+
+    /// The dialect of `sample` when its delimiter is already known: only quoting is detected.
+    pub fn with_delimiter(sample: &str, delimiter: char) -> Self {
+        let quoted = sample
+            .lines()
+            .any(|line| line.starts_with('"') || line.contains(&format!("{delimiter}\"")[..]));
+        Self {
+            delimiter,
+            quote: quoted.then_some('"'),
+        }
+    }
+
+    fn detect(sample: &str, delimiter: Option<char>) -> Self {
+        match delimiter {
+            Some(delimiter) => Self::with_delimiter(sample, delimiter),
+            None => Self::sniff(sample),
+        }
+    }
+
+    fn reader<R: Read>(&self, input: R, has_headers: bool) -> csv::Reader<R> {
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter as u8)
+            .has_headers(has_headers)
+            .flexible(true)
+            .from_reader(input)
+    }
+}
+
+/// One column of a [`DatasetProfile`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnProfile {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: InferredType,
+    /// Share of non-null values that fit `type` (integers count as floats).
+    pub confidence: f64,
+    pub null_rate: f64,
+    pub distinct: usize,
+    /// More than `distinct` values exist; counting stopped there.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub distinct_capped: bool,
+    pub examples: Vec<String>,
+    /// Numbers are written with a decimal comma (`3,14`).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub decimal_comma: bool,
+}
+
+/// What `profile_dataset` reports about a delimited file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatasetProfile {
+    pub dialect: Dialect,
+    pub rows: usize,
+    pub columns: Vec<ColumnProfile>,
+    /// Quality problems in plain words (mixed types, constant or empty columns, repeated header
+    /// rows, rows with the wrong number of fields).
+    pub issues: Vec<String>,
+}
+
+#[derive(Default)]
+struct ColumnState {
+    values: usize,
+    nulls: usize,
+    /// Values per type, indexed like [`TYPE_ORDER`].
+    counts: [usize; 5],
+    /// First value seen of each type, for issue messages.
+    firsts: [Option<String>; 5],
+    distinct: HashSet<String>,
+    distinct_capped: bool,
+    examples: Vec<String>,
+    decimal_comma: bool,
+}
+
+const TYPE_ORDER: [InferredType; 5] = [
+    InferredType::Integer,
+    InferredType::Float,
+    InferredType::Boolean,
+    InferredType::Date,
+    InferredType::String,
+];
+
+fn slot(ty: InferredType) -> usize {
+    TYPE_ORDER.iter().position(|t| *t == ty).unwrap_or(4)
+}
+
+/// Type of one non-null cell ([`InferredType::of_text`]), and whether it is a number written
+/// with a decimal comma; `decimal_comma` allows `3,14` (only sensible when the delimiter is not
+/// a comma).
+fn cell_type(value: &str, decimal_comma: bool) -> (InferredType, bool) {
+    match InferredType::of_text(value) {
+        InferredType::String if decimal_comma && DECIMAL_COMMA.is_match(value) => {
+            (InferredType::Float, true)
+        }
+        ty => (ty, false),
+    }
+}
+
+fn example(value: &str) -> String {
+    crate::web::truncate_chars(value, EXAMPLE_CHARS)
+}
+
+fn rate(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        (part as f64 / whole as f64 * 100.0).round() / 100.0
+    }
+}
+
+impl ColumnState {
+    fn add(&mut self, raw: &str, decimal_comma: bool) {
+        self.values += 1;
+        let value = raw.trim();
+        if NULL_MARKERS.iter().any(|m| value.eq_ignore_ascii_case(m)) {
+            self.nulls += 1;
+            return;
+        }
+        let (ty, comma) = cell_type(value, decimal_comma);
+        self.decimal_comma |= comma;
+        self.counts[slot(ty)] += 1;
+        self.firsts[slot(ty)].get_or_insert_with(|| example(value));
+        if self.distinct.len() < DISTINCT_CAP {
+            if self.distinct.insert(value.to_string()) && self.examples.len() < EXAMPLES {
+                self.examples.push(example(value));
+            }
+        } else if !self.distinct.contains(value) {
+            self.distinct_capped = true;
+        }
+    }
+
+    fn finish(self, name: String, issues: &mut Vec<String>) -> ColumnProfile {
+        let present = self.values - self.nulls;
+        let [int, float, boolean, date, string] = self.counts;
+        let numeric = if float > 0 {
+            InferredType::Float
+        } else {
+            InferredType::Integer
+        };
+        let (ty, fitting) = [
+            (numeric, int + float),
+            (InferredType::Boolean, boolean),
+            (InferredType::Date, date),
+            (InferredType::String, string),
+        ]
+        .into_iter()
+        .fold((InferredType::String, 0), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        });
+        let confidence = rate(fitting, present);
+        if present == 0 {
+            issues.push(format!("column '{name}' is empty"));
+        } else if fitting < present {
+            let stray = (0..TYPE_ORDER.len())
+                .filter(|&i| match ty {
+                    InferredType::Integer | InferredType::Float => i > 1,
+                    _ => i != slot(ty),
+                })
+                .find_map(|i| self.firsts[i].clone())
+                .unwrap_or_default();
+            issues.push(format!(
+                "column '{name}' mixes types: {} of {present} values are not {} (e.g. '{stray}')",
+                present - fitting,
+                type_name(ty),
+            ));
+        } else if self.distinct.len() == 1 && present > 1 {
+            issues.push(format!(
+                "column '{name}' holds a single value ('{}')",
+                self.examples[0]
+            ));
+        }
+        let null_rate = rate(self.nulls, self.values);
+        if present > 0 && null_rate >= 0.5 {
+            issues.push(format!(
+                "column '{name}' is {:.0}% empty",
+                null_rate * 100.0
+            ));
+        }
+        ColumnProfile {
+            name,
+            ty,
+            confidence,
+            null_rate,
+            distinct: self.distinct.len(),
+            distinct_capped: self.distinct_capped,
+            examples: self.examples,
+            decimal_comma: self.decimal_comma,
+        }
+    }
+}
+
+fn type_name(ty: InferredType) -> &'static str {
+    match ty {
+        InferredType::Integer => "integer",
+        InferredType::Float => "float",
+        InferredType::Boolean => "bool",
+        InferredType::Date => "date",
+        InferredType::String => "string",
+    }
+}
+
+/// Profiles delimited `content`, sniffing the delimiter unless one is given.
+pub fn profile(
+    content: &str,
+    delimiter: Option<char>,
+    has_headers: bool,
+) -> Result<DatasetProfile, KowalskiError> {
+    let dialect = Dialect::detect(sniff_sample(content), delimiter);
+    profile_reader(content.as_bytes(), dialect, has_headers)
+}
+
+/// Like [`profile`], but streams the file at `path` after sniffing its first 64 KiB. Blocking.
+pub fn profile_file(
+    path: &Path,
+    delimiter: Option<char>,
+    has_headers: bool,
+) -> Result<DatasetProfile, KowalskiError> {
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    std::fs::File::open(path)?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)?;
+    let dialect = Dialect::detect(sniff_sample(&String::from_utf8_lossy(&head)), delimiter);
+    let file = std::fs::File::open(path)?;
+    profile_reader(std::io::BufReader::new(file), dialect, has_headers)
+}
+
+/// The start of `content`, cut at a line end.
+fn sniff_sample(content: &str) -> &str {
+    if content.len() <= SNIFF_BYTES {
+        return content;
+    }
+    let mut end = SNIFF_BYTES;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head = &content[..end];
+    head.rfind('\n').map(|i| &head[..i]).unwrap_or(head)
+}
+
+fn profile_reader<R: Read>(
+    input: R,
+    dialect: Dialect,
+    has_headers: bool,
+) -> Result<DatasetProfile, KowalskiError> {
+    let mut reader = dialect.reader(input, has_headers);
+    let first = reader.headers().map_err(invalid)?.clone();
+    let header: Vec<String> = first.iter().map(|h| h.trim().to_string()).collect();
+    let names: Vec<String> = if has_headers {
+        header.clone()
+    } else {
+        (0..header.len()).map(|i| format!("column_{i}")).collect()
+    };
+    let decimal_comma = dialect.delimiter != ',';
+    let mut columns: Vec<ColumnState> = names.iter().map(|_| ColumnState::default()).collect();
+    let mut rows = 0usize;
+    let mut repeated_headers = Vec::new();
+    let mut ragged = 0usize;
+    for record in reader.records() {
+        let record = record.map_err(invalid)?;
+        rows += 1;
+        if has_headers
+            && record
+                .iter()
+                .map(str::trim)
+                .eq(header.iter().map(String::as_str))
+        {
+            repeated_headers.push(record.position().map_or(rows + 1, |p| p.line() as usize));
+            continue;
+        }
+        if record.len() != names.len() {
+            ragged += 1;
+        }
+        // A missing trailing field counts as null.
+        for (i, column) in columns.iter_mut().enumerate() {
+            column.add(record.get(i).unwrap_or(""), decimal_comma);
+        }
+    }
+
+    let mut issues = Vec::new();
+    if !repeated_headers.is_empty() {
+        let at: Vec<String> = repeated_headers
+            .iter()
+            .take(5)
+            .map(|r| r.to_string())
+            .collect();
+        issues.push(format!(
+            "header row repeated {} time(s) in the data (lines {})",
+            repeated_headers.len(),
+            at.join(", ")
+        ));
+    }
+    if ragged > 0 {
+        issues.push(format!(
+            "{ragged} row(s) do not have {} fields",
+            names.len()
+        ));
+    }
+    let columns: Vec<ColumnProfile> = names
+        .into_iter()
+        .zip(columns)
+        .map(|(name, state)| state.finish(name, &mut issues))
+        .collect();
+    issues.truncate(MAX_ISSUES);
+    Ok(DatasetProfile {
+        dialect,
+        rows: rows - repeated_headers.len(),
+        columns,
+        issues,
+    })
+}
+
+impl DatasetProfile {
+    /// A few lines per column for the model's context, headed by `label` (usually the path).
+    pub fn to_prompt(&self, label: &str) -> String {
+        let delimiter = match self.dialect.delimiter {
+            '\t' => "tab".to_string(),
+            other => format!("'{other}'"),
+        };
+        let mut out = format!(
+            "{label}: {} rows, {} columns, delimiter {delimiter}{}\n",
+            self.rows,
+            self.columns.len(),
+            if self.dialect.quote.is_some() {
+                ", quoted fields"
+            } else {
+                ""
+            }
+        );
+        for column in self.columns.iter().take(PROMPT_COLUMNS) {
+            out.push_str(&format!(
+                "- {}: {} ({:.0}%{}), {:.0}% null, {}{} distinct, e.g. {}\n",
+                column.name,
+                type_name(column.ty),
+                column.confidence * 100.0,
+                if column.decimal_comma {
+                    ", decimal comma"
+                } else {
+                    ""
+                },
+                column.null_rate * 100.0,
+                column.distinct,
+                if column.distinct_capped { "+" } else { "" },
+                column.examples.join(", ")
+            ));
+        }
+        if self.columns.len() > PROMPT_COLUMNS {
+            out.push_str(&format!(
+                "- ... and {} more columns\n",
+                self.columns.len() - PROMPT_COLUMNS
+            ));
+        }
+        if !self.issues.is_empty() {
+            out.push_str("Issues:\n");
+            for issue in &self.issues {
+                out.push_str(&format!("- {issue}\n"));
+            }
+        }
+        out
+    }
+}
+
+/// Middleware for data agents: the first time a `.csv` / `.tsv` / `.psv` file under `root` is
+/// mentioned in a user message, it is profiled; from then on its profile is added (as a system
+/// message, for that request only) to every LLM request whose conversation mentions the file.
+/// Profiles are cached per path, length and modification time, so each version of a file is
+/// read once.
+pub struct DatasetProfiler {
+    root: PathBuf,
+    cache: Mutex<HashMap<PathBuf, (FileStamp, String)>>,
+}
+
+/// Length and modification time of a profiled file; a change means the profile is stale.
+type FileStamp = (u64, Option<std::time::SystemTime>);
+
+impl Default for DatasetProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DatasetProfiler {
+    /// Profiles files under the working directory.
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Only files under `root` are read; mentioned paths outside it are ignored.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Number of files profiled so far.
+    pub fn cached(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// Prompt text of the profile of `path`, profiling it on first use; `None` when the path
+    /// is not a readable file under the root.
+    async fn profile_of(&self, path: &str) -> Option<String> {
+        let (root, file) = resolve_within(&self.root, path, "profile_dataset").ok()?;
+        let metadata = tokio::fs::metadata(&file).await.ok()?;
+        let stamp = (metadata.len(), metadata.modified().ok());
+        if let Some((cached, text)) = self.cache.lock().unwrap().get(&file)
+            && *cached == stamp
+        {
+            return Some(text.clone());
+        }
+        let label = relative(&root, &file);
+        let read = file.clone();
+        let profile = tokio::task::spawn_blocking(move || profile_file(&read, None, true))
+            .await
+            .ok()?;
+        let text = match profile {
+            Ok(profile) => profile.to_prompt(&label),
+            Err(e) => {
+                log::debug!("profile_dataset {label}: {e}");
+                return None;
+            }
+        };
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(file, (stamp, text.clone()));
+        Some(text)
+    }
+}
+
+/// Data file paths mentioned in `text`, in order, without duplicates.
+pub fn mentioned_data_files(text: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    for captures in DATA_FILE.captures_iter(text) {
+        let path = captures[1].to_string();
+        if !seen.contains(&path) {
+            seen.push(path);
+        }
+    }
+    seen
+}
+
+#[async_trait]
+impl AgentMiddleware for DatasetProfiler {
+    async fn before_llm_call(&self, request: &mut ChatRequest) {
+        let mut paths = Vec::new();
+        for message in request.messages.iter().filter(|m| m.role == "user") {
+            for path in mentioned_data_files(&message.content) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        let mut profiles = Vec::new();
+        for path in paths {
+            if let Some(text) = self.profile_of(&path).await {
+                profiles.push(text);
+            }
+        }
+        if profiles.is_empty() {
+            return;
+        }
+        let insert_at = request.messages.len().saturating_sub(1);
+        request.messages.insert(
+            insert_at,
+            Message {
+                role: "system".to_string(),
+                content: format!(
+                    "Profiles of the data files in this conversation (from profile_dataset):\n\n{}",
+                    profiles.join("\n")
+                ),
+                tool_calls: None,
+                images: None,
+                tool_call_id: None,
+                tool_name: None,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SEMICOLONS: &str = include_str!("../../tests/fixtures/data/semicolons.csv");
+    const QUOTED: &str = include_str!("../../tests/fixtures/data/quoted_commas.csv");
+    const REPEATED_HEADER: &str = include_str!("../../tests/fixtures/data/repeated_header.csv");
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+    }
+
+    fn column<'a>(profile: &'a DatasetProfile, name: &str) -> &'a ColumnProfile {
+        profile.columns.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[test]
+    fn semicolons_and_decimal_commas() {
+        let profile = profile(SEMICOLONS, None, true).unwrap();
+        assert_eq!(
+            profile.dialect,
+            Dialect {
+                delimiter: ';',
+                quote: None
+            }
+        );
+        assert_eq!(profile.rows, 6);
+        let price = column(&profile, "price");
+        assert_eq!(price.ty, InferredType::Float);
+        assert_eq!(price.confidence, 1.0);
+        assert!(price.decimal_comma);
+        let date = column(&profile, "date");
+        assert_eq!(date.ty, InferredType::Date);
+        let country = column(&profile, "country");
+        assert_eq!(
+            (country.distinct, country.examples.clone()),
+            (1, vec!["PL".to_string()])
+        );
+        assert_eq!(column(&profile, "note").null_rate, 0.67);
+        assert!(
+            profile
+                .issues
+                .contains(&"column 'country' holds a single value ('PL')".to_string())
+        );
+        assert!(
+            profile
+                .issues
+                .contains(&"column 'note' is 67% empty".to_string())
+        );
+    }
+
+    #[test]
+    fn quoted_commas_stay_in_their_field() {
+        let profile = profile(QUOTED, None, true).unwrap();
+        assert_eq!(
+            profile.dialect,
+            Dialect {
+                delimiter: ',',
+                quote: Some('"')
+            }
+        );
+        assert_eq!(profile.columns.len(), 4);
+        let city = column(&profile, "city");
+        assert_eq!(city.ty, InferredType::String);
+        assert_eq!(city.examples[0], "Warsaw, Poland");
+        // A decimal comma is not a number when the comma separates fields.
+        assert_eq!(column(&profile, "amount").ty, InferredType::Float);
+        assert!(!column(&profile, "amount").decimal_comma);
+        let active = column(&profile, "active");
+        assert_eq!(active.ty, InferredType::Boolean);
+        assert_eq!(active.confidence, 0.75);
+        assert!(
+            profile
+                .issues
+                .iter()
+                .any(|i| i
+                    == "column 'active' mixes types: 1 of 4 values are not bool (e.g. 'maybe')")
+        );
+    }
+
+    #[test]
+    fn repeated_header_rows_are_flagged_and_skipped() {
+        let profile = profile(REPEATED_HEADER, None, true).unwrap();
+        assert_eq!(profile.rows, 4);
+        assert_eq!(column(&profile, "id").ty, InferredType::Integer);
+        assert_eq!(column(&profile, "id").confidence, 1.0);
+        assert_eq!(
+            profile.issues[0],
+            "header row repeated 2 time(s) in the data (lines 3, 6)"
+        );
+        assert!(
+            profile
+                .issues
+                .contains(&"1 row(s) do not have 3 fields".to_string())
+        );
+
+        let report = serde_json::to_value(&profile).unwrap();
+        assert_eq!(report["dialect"], json!({"delimiter": ","}));
+        assert_eq!(
+            report["columns"][0],
+            json!({"name": "id", "type": "integer", "confidence": 1.0, "null_rate": 0.0,
+                   "distinct": 4, "examples": ["1", "2", "3"]})
+        );
+        let prompt = profile.to_prompt("repeated_header.csv");
+        assert!(prompt.starts_with("repeated_header.csv: 4 rows, 3 columns, delimiter ','\n- id: integer (100%), 0% null, 4 distinct, e.g. 1, 2, 3\n"), "{prompt}");
+        assert!(prompt.len() < 1000);
+    }
+
+    #[test]
+    fn finds_data_file_mentions() {
+        assert_eq!(
+            mentioned_data_files(
+                "compare data/a.csv with `b.TSV` and (c.psv), not a.csvx or x.csv"
+            ),
+            ["data/a.csv", "b.TSV", "c.psv", "x.csv"]
+        );
+    }
+
+    #[tokio::test]
+    async fn profiler_adds_cached_profiles_of_mentioned_files() {
+        let profiler = DatasetProfiler::new().with_root(fixtures());
+        let message = |role: &str, content: &str| Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        };
+        let mut request = ChatRequest {
+            model: "m".to_string(),
+            messages: vec![
+                message("system", "You analyze data."),
+                message("user", "What is in data/semicolons.csv and missing.csv?"),
+            ],
+            stream: false,
+            temperature: 0.0,
+            max_tokens: 100,
+            tools: None,
+            format: None,
+            options: None,
+        };
+        profiler.before_llm_call(&mut request).await;
+        assert_eq!(request.messages.len(), 3);
+        assert_eq!(request.messages[1].role, "system");
+        assert!(
+            request.messages[1]
+                .content
+                .contains("data/semicolons.csv: 6 rows, 5 columns, delimiter ';'")
+        );
+        assert_eq!(profiler.cached(), 1);
+
+        // Later turns keep the profile without reading the file again.
+        request.messages.remove(1);
+        request
+            .messages
+            .push(message("assistant", "It has prices."));
+        request
+            .messages
+            .push(message("user", "What is the average price?"));
+        profiler.before_llm_call(&mut request).await;
+        assert_eq!(request.messages[3].role, "system");
+        assert!(request.messages[3].content.contains("semicolons.csv"));
+        assert_eq!(profiler.cached(), 1);
+    }
+
+    #[tokio::test]
+    async fn edited_files_are_profiled_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("sales.csv");
+        std::fs::write(&file, "id,price\n1,2.5\n").unwrap();
+        let profiler = DatasetProfiler::new().with_root(dir.path());
+        let first = profiler.profile_of("sales.csv").await.unwrap();
+        assert!(first.contains("sales.csv: 1 rows"), "{first}");
+
+        std::fs::write(&file, "id,price\n1,2.5\n2,3.5\n3,4.5\n").unwrap();
+        let second = profiler.profile_of("sales.csv").await.unwrap();
+        assert!(second.contains("sales.csv: 3 rows"), "{second}");
+        assert_eq!(profiler.cached(), 1);
+    }
+}
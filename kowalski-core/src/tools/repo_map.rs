@@ -0,0 +1,1070 @@
+//! Repository maps for code agents: a compact tree of the project's files with an outline of
+//! the symbols each defines (Rust `fn`/`struct`/`enum`/`trait`/`impl`/`mod`, Python
+//! `def`/`class`, best effort for JavaScript, Go, Java and similar), bounded by a token budget.
+//! The walk honours `.gitignore` files. [`RepoMapper`] caches outlines per file and only
+//! re-parses files whose modification time or size changed; as middleware it puts the map of
+//! every project directory mentioned in a conversation in front of the model, and its
+//! [`RepoMapTool`] (`repo_map`) lets the model ask for more detail on a subtree.
+
+use crate::agent::middleware::AgentMiddleware;
+use crate::agent::types::ChatRequest;
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::tools::fs::{relative, resolve_within};
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::SystemTime;
+
+/// Map size, in whitespace-separated words (the token estimate used by `text::chunk`), when
+/// no budget is given.
+pub const DEFAULT_MAP_TOKENS: usize = 1500;
+/// Files larger than this are listed without an outline.
+const MAX_PARSE_BYTES: u64 = 1024 * 1024;
+/// Words taken by the `… N more` line of a truncated directory.
+const OMISSION_WORDS: usize = 3;
+
+static RUST_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:async|const|unsafe|extern(?:\s+"[^"]*")?)\s+)*(fn|struct|enum|trait|mod|union)\s+([A-Za-z_]\w*)"#,
+    )
+    .unwrap()
+});
+static RUST_MACRO: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*macro_rules!\s*([A-Za-z_]\w*)").unwrap());
+static RUST_IMPL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:unsafe\s+)?impl\b").unwrap());
+static PYTHON_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:async\s+)?(def|class)\s+([A-Za-z_]\w*)").unwrap());
+/// `function f`, `class C`, `func (r *T) Name`, `public interface I`, …
+static GENERIC_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:(?:public|private|protected|internal|static|abstract|final|sealed|async|open|data)\s+)*(class|interface|struct|enum|trait|function|func|fun|type)\s+(?:\([^)]*\)\s*)?([A-Za-z_$][\w$]*)",
+    )
+    .unwrap()
+});
+
+/// Extensions outlined with [`GENERIC_ITEM`].
+const GENERIC_EXTENSIONS: &[&str] = &[
+    "js", "jsx", "mjs", "cjs", "ts", "tsx", "go", "java", "kt", "kts", "scala", "swift", "cs",
+    "php", "rb",
+];
+
+/// One definition in a source file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Symbol {
+    /// `fn`, `struct`, `enum`, `trait`, `impl`, `mod`, `macro`, `class`, `interface`, `type`, …
+    pub kind: String,
+    pub name: String,
+    /// 1-based line of the definition.
+    pub line: usize,
+}
+
+impl Symbol {
+    fn new(kind: &str, name: &str, line: usize) -> Self {
+        Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            line,
+        }
+    }
+}
+
+/// A file of the map and its outline (empty for files that are not source code).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileOutline {
+    /// Path relative to the mapped root, `/`-separated.
+    pub path: String,
+    pub symbols: Vec<Symbol>,
+}
+
+/// The outlines of every file under a root that `.gitignore` does not exclude.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RepoMap {
+    /// Sorted by path.
+    pub files: Vec<FileOutline>,
+}
+
+/// Definitions in `source`, by the language its file `extension` suggests; empty for languages
+/// without an outliner.
+pub fn extract_symbols(extension: &str, source: &str) -> Vec<Symbol> {
+    let extension = extension.to_ascii_lowercase();
+    let mut symbols = Vec::new();
+    let mut in_docstring = false;
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = line.trim_start();
+        match extension.as_str() {
+            "rs" => {
+                if trimmed.starts_with("//") {
+                    continue;
+                }
+                if let Some(c) = RUST_ITEM.captures(line) {
+                    symbols.push(Symbol::new(&c[1], &c[2], number));
+                } else if let Some(c) = RUST_MACRO.captures(line) {
+                    symbols.push(Symbol::new("macro", &c[1], number));
+                } else if let Some(m) = RUST_IMPL.find(line)
+                    && let Some(target) = impl_target(&line[m.end()..])
+                {
+                    symbols.push(Symbol::new("impl", &target, number));
+                }
+            }
+            "py" | "pyi" => {
+                // Triple-quoted strings often hold example code; skip their lines.
+                let quotes = trimmed.matches("\"\"\"").count() + trimmed.matches("'''").count();
+                if in_docstring {
+                    in_docstring = quotes % 2 == 0;
+                    continue;
+                }
+                if quotes % 2 == 1 {
+                    in_docstring = true;
+                    continue;
+                }
+                if let Some(c) = PYTHON_ITEM.captures(line) {
+                    symbols.push(Symbol::new(&c[1], &c[2], number));
+                }
+            }
+            ext if GENERIC_EXTENSIONS.contains(&ext) => {
+                if trimmed.starts_with("//") || trimmed.starts_with('*') || trimmed.starts_with('#')
+                {
+                    continue;
+                }
+                if let Some(c) = GENERIC_ITEM.captures(line) {
+                    let kind = match &c[1] {
+                        "function" | "func" | "fun" => "fn",
+                        other => other,
+                    };
+                    symbols.push(Symbol::new(kind, &c[2], number));
+                }
+            }
+            _ => return symbols,
+        }
+    }
+    symbols
+}
+
+/// `Display for Config<T>` from the rest of an `impl<T: Bound<U>> Display for Config<T> {`
+/// line: the generics after `impl` and any `where` clause dropped.
+fn impl_target(rest: &str) -> Option<String> {
+    let mut rest = rest.trim_start();
+    if rest.starts_with('<') {
+        let mut depth = 0usize;
+        let mut end = None;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        end = Some(i + 1);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        rest = &rest[end?..];
+    }
+    let rest = rest.split('{').next().unwrap_or_default();
+    let rest = rest.split(" where").next().unwrap_or_default();
+    let target = rest.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!target.is_empty()).then_some(target)
+}
+
+/// One pattern of a `.gitignore` file.
+#[derive(Debug)]
+struct IgnoreRule {
+    /// Directory of the `.gitignore`, relative to the walk root (`""` at the root).
+    base: String,
+    pattern: Regex,
+    negated: bool,
+    directories_only: bool,
+}
+
+/// Rules of the `.gitignore` text found in directory `base` (relative to the walk root).
+fn parse_gitignore(text: &str, base: &str) -> Vec<IgnoreRule> {
+    let mut rules = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (directories_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        // A slash anywhere but at the end ties the pattern to the .gitignore's directory;
+        // otherwise it matches a name at any depth.
+        let anchored = line.contains('/');
+        let glob = line.trim_start_matches('/');
+        if glob.is_empty() {
+            continue;
+        }
+        let prefix = if anchored { "^" } else { "^(?:.*/)?" };
+        let Ok(pattern) = Regex::new(&format!("{prefix}{}$", glob_to_regex(glob))) else {
+            continue;
+        };
+        rules.push(IgnoreRule {
+            base: base.to_string(),
+            pattern,
+            negated,
+            directories_only,
+        });
+    }
+    rules
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::new();
+    let chars: Vec<char> = glob.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                if chars.get(i + 2) == Some(&'/') {
+                    out.push_str("(?:.*/)?");
+                    i += 3;
+                } else {
+                    out.push_str(".*");
+                    i += 2;
+                }
+                continue;
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Whether the last rule matching `path` (relative to the walk root) ignores it.
+fn is_ignored(rules: &[IgnoreRule], path: &str, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for rule in rules {
+        if rule.directories_only && !is_dir {
+            continue;
+        }
+        let local = if rule.base.is_empty() {
+            path
+        } else {
+            match path
+                .strip_prefix(rule.base.as_str())
+                .and_then(|p| p.strip_prefix('/'))
+            {
+                Some(local) => local,
+                None => continue,
+            }
+        };
+        if rule.pattern.is_match(local) {
+            ignored = !rule.negated;
+        }
+    }
+    ignored
+}
+
+/// A file found by the walk: path relative to the root, absolute path, mtime and size.
+struct Entry {
+    path: String,
+    full: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+/// Files under `dir` (whose path relative to the walk root is `rel`) that no `.gitignore` on
+/// the way down excludes; `.git` is always skipped.
+fn walk(dir: &Path, rel: &str, rules: &mut Vec<IgnoreRule>, out: &mut Vec<Entry>) {
+    let inherited = rules.len();
+    if let Ok(text) = std::fs::read_to_string(dir.join(".gitignore")) {
+        rules.extend(parse_gitignore(&text, rel));
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        rules.truncate(inherited);
+        return;
+    };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" {
+            continue;
+        }
+        let path = if rel.is_empty() {
+            name
+        } else {
+            format!("{rel}/{name}")
+        };
+        // Symlinks are not followed, so the walk stays under the root.
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_symlink() {
+            continue;
+        }
+        if is_ignored(rules, &path, file_type.is_dir()) {
+            continue;
+        }
+        if file_type.is_dir() {
+            walk(&entry.path(), &path, rules, out);
+        } else if let Ok(metadata) = entry.metadata() {
+            out.push(Entry {
+                path,
+                full: entry.path(),
+                modified: metadata.modified().ok(),
+                len: metadata.len(),
+            });
+        }
+    }
+    rules.truncate(inherited);
+}
+
+/// Outline of a file as of `modified` / `len`.
+struct CachedOutline {
+    modified: Option<SystemTime>,
+    len: u64,
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Default)]
+struct MapperState {
+    outlines: HashMap<String, CachedOutline>,
+    /// The last map and the hash of the paths, mtimes and sizes it was built from.
+    last: Option<(u64, Arc<RepoMap>)>,
+}
+
+struct MapperInner {
+    root: PathBuf,
+    budget: usize,
+    state: Mutex<MapperState>,
+    parsed: AtomicUsize,
+}
+
+/// Builds and caches the [`RepoMap`] of a project. A rebuild walks the tree but only reads
+/// files whose mtime or size changed, and nothing at all when none did. Clones share the
+/// cache; as middleware for code agents, the map of each project directory mentioned in a user
+/// message (e.g. `src/agent/`) is added, as a system message for that request only, within the
+/// token budget.
+#[derive(Clone)]
+pub struct RepoMapper {
+    inner: Arc<MapperInner>,
+}
+
+impl Default for RepoMapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RepoMapper {
+    /// Maps the working directory with a [`DEFAULT_MAP_TOKENS`] budget.
+    pub fn new() -> Self {
+        Self::build(PathBuf::from("."), DEFAULT_MAP_TOKENS)
+    }
+
+    fn build(root: PathBuf, budget: usize) -> Self {
+        Self {
+            inner: Arc::new(MapperInner {
+                root,
+                budget,
+                state: Mutex::new(MapperState::default()),
+                parsed: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Maps `root`; mentioned paths outside it are ignored. Starts with an empty cache.
+    pub fn with_root(self, root: impl Into<PathBuf>) -> Self {
+        Self::build(root.into(), self.inner.budget)
+    }
+
+    /// Words the injected maps may take in total. Starts with an empty cache.
+    pub fn with_budget(self, budget: usize) -> Self {
+        Self::build(self.inner.root.clone(), budget.max(1))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.inner.root
+    }
+
+    /// Number of files read and outlined so far.
+    pub fn parsed(&self) -> usize {
+        self.inner.parsed.load(Ordering::Relaxed)
+    }
+
+    /// The `repo_map` tool over this mapper's root and cache.
+    pub fn tool(&self) -> RepoMapTool {
+        RepoMapTool {
+            mapper: self.clone(),
+        }
+    }
+
+    /// The current map of the whole root, rebuilt from changed files only.
+    pub fn map(&self) -> Arc<RepoMap> {
+        let mut entries = Vec::new();
+        walk(&self.inner.root, "", &mut Vec::new(), &mut entries);
+        let mut hasher = DefaultHasher::new();
+        for entry in &entries {
+            (&entry.path, entry.modified, entry.len).hash(&mut hasher);
+        }
+        let fingerprint = hasher.finish();
+
+        let mut state = self.inner.state.lock().unwrap();
+        if let Some((hash, map)) = &state.last
+            && *hash == fingerprint
+        {
+            return map.clone();
+        }
+        let mut outlines = HashMap::with_capacity(entries.len());
+        let mut files = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let outline = match state.outlines.remove(&entry.path) {
+                Some(cached) if cached.modified == entry.modified && cached.len == entry.len => {
+                    cached
+                }
+                _ => {
+                    self.inner.parsed.fetch_add(1, Ordering::Relaxed);
+                    CachedOutline {
+                        modified: entry.modified,
+                        len: entry.len,
+                        symbols: outline_file(&entry.full, entry.len),
+                    }
+                }
+            };
+            files.push(FileOutline {
+                path: entry.path.clone(),
+                symbols: outline.symbols.clone(),
+            });
+            outlines.insert(entry.path, outline);
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let map = Arc::new(RepoMap { files });
+        // Outlines of deleted files are dropped with the old cache.
+        state.outlines = outlines;
+        state.last = Some((fingerprint, map.clone()));
+        map
+    }
+
+    /// The map of `path` (a directory under the root) in at most `budget` words, and the path
+    /// relative to the root.
+    async fn render(&self, path: &str, budget: usize) -> Result<(String, String), KowalskiError> {
+        let (root, dir) = resolve_within(&self.inner.root, path, "repo_map")?;
+        if !dir.is_dir() {
+            return Err(KowalskiError::ToolInvalidInput(format!(
+                "'{path}' is not a directory"
+            )));
+        }
+        let label = relative(&root, &dir);
+        let mapper = self.clone();
+        let map = tokio::task::spawn_blocking(move || mapper.map())
+            .await
+            .map_err(|e| KowalskiError::tool_failed("repo_map", e))?;
+        let prefix = if label == "." { "" } else { label.as_str() };
+        Ok((map.render(prefix, budget), label))
+    }
+}
+
+fn outline_file(path: &Path, len: u64) -> Vec<Symbol> {
+    let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+        return Vec::new();
+    };
+    if len > MAX_PARSE_BYTES {
+        return Vec::new();
+    }
+    match std::fs::read(path) {
+        Ok(bytes) => extract_symbols(extension, &String::from_utf8_lossy(&bytes)),
+        Err(e) => {
+            log::debug!("repo_map {}: {e}", path.display());
+            Vec::new()
+        }
+    }
+}
+
+/// A directory of the rendered tree; costs are in words.
+#[derive(Default)]
+struct Node<'a> {
+    dirs: BTreeMap<&'a str, Node<'a>>,
+    files: Vec<(&'a str, &'a [Symbol])>,
+    cost: usize,
+}
+
+impl<'a> Node<'a> {
+    fn insert(&mut self, path: &'a str, symbols: &'a [Symbol]) {
+        match path.split_once('/') {
+            Some((dir, rest)) => self.dirs.entry(dir).or_default().insert(rest, symbols),
+            None => self.files.push((path, symbols)),
+        }
+    }
+
+    /// Sets each directory's cost: its own line plus everything below it.
+    fn measure(&mut self) -> usize {
+        let below: usize = self.dirs.values_mut().map(Node::measure).sum::<usize>()
+            + self.files.iter().map(|(_, s)| file_cost(s)).sum::<usize>();
+        self.cost = 1 + below;
+        self.cost
+    }
+}
+
+/// Words of `fn a,`; impl targets like `Display for Config` take more than one.
+fn symbol_cost(symbol: &Symbol) -> usize {
+    1 + symbol.name.split_whitespace().count()
+}
+
+/// `lib.rs: fn a, struct B` is one word for the name plus its symbols.
+fn file_cost(symbols: &[Symbol]) -> usize {
+    1 + symbols.iter().map(symbol_cost).sum::<usize>()
+}
+
+fn write_symbols(out: &mut String, symbols: &[Symbol]) {
+    for (i, symbol) in symbols.iter().enumerate() {
+        out.push_str(if i == 0 { ": " } else { ", " });
+        let _ = write!(out, "{} {}", symbol.kind, symbol.name);
+    }
+}
+
+/// Writes `name` and up to `budget` words of its outline; returns the words used (0 when not
+/// even the name fits).
+fn render_file(
+    out: &mut String,
+    depth: usize,
+    name: &str,
+    symbols: &[Symbol],
+    budget: usize,
+) -> usize {
+    if budget == 0 {
+        return 0;
+    }
+    let _ = write!(out, "{:indent$}{name}", "", indent = depth * 2);
+    let cost = file_cost(symbols);
+    let used = if cost <= budget {
+        write_symbols(out, symbols);
+        cost
+    } else {
+        // The name, the symbols that fit, then `… +n`.
+        let mut used = 1;
+        let mut shown = 0;
+        for symbol in symbols {
+            if used + symbol_cost(symbol) + 2 > budget {
+                break;
+            }
+            used += symbol_cost(symbol);
+            shown += 1;
+        }
+        if shown > 0 {
+            write_symbols(out, &symbols[..shown]);
+            let _ = write!(out, ", … +{}", symbols.len() - shown);
+            used += 2;
+        }
+        used
+    };
+    out.push('\n');
+    used
+}
+
+/// Writes directory `node` (named `name`, or nothing for the top of the map) in at most
+/// `budget` words. When it does not fit, the budget is shared between its entries in
+/// proportion to their full size, and the entries that get nothing are counted in a
+/// `… N more` line.
+fn render_dir(
+    out: &mut String,
+    depth: usize,
+    name: Option<&str>,
+    node: &Node,
+    budget: usize,
+) -> usize {
+    let header = usize::from(name.is_some());
+    let child_depth = depth + header;
+    let full = node.cost - 1 + header;
+    if budget == 0 {
+        return 0;
+    }
+    if let Some(name) = name {
+        let _ = writeln!(out, "{:indent$}{name}/", "", indent = depth * 2);
+    }
+    if full <= budget {
+        for (dir, child) in &node.dirs {
+            render_dir(out, child_depth, Some(dir), child, usize::MAX);
+        }
+        for (file, symbols) in &node.files {
+            render_file(out, child_depth, file, symbols, usize::MAX);
+        }
+        return full;
+    }
+    let Some(available) = budget.checked_sub(header + OMISSION_WORDS) else {
+        return header;
+    };
+    let total = full - header;
+    let share = |cost: usize| available * cost / total;
+    let mut used = header;
+    let mut omitted = 0;
+    for (dir, child) in &node.dirs {
+        match render_dir(out, child_depth, Some(dir), child, share(child.cost)) {
+            0 => omitted += 1,
+            words => used += words,
+        }
+    }
+    for (file, symbols) in &node.files {
+        match render_file(out, child_depth, file, symbols, share(file_cost(symbols))) {
+            0 => omitted += 1,
+            words => used += words,
+        }
+    }
+    if omitted > 0 {
+        let _ = writeln!(
+            out,
+            "{:indent$}… {omitted} more",
+            "",
+            indent = child_depth * 2
+        );
+        used += OMISSION_WORDS;
+    }
+    used
+}
+
+impl RepoMap {
+    /// Files under `prefix` (a `/`-separated directory relative to the root, `""` for all).
+    pub fn files_under<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a FileOutline> {
+        self.files.iter().filter(move |f| {
+            prefix.is_empty()
+                || f.path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// The tree under `prefix` as indented lines (`dir/`, then `file: kind name, …`) in at most
+    /// `budget` whitespace-separated words.
+    pub fn render(&self, prefix: &str, budget: usize) -> String {
+        let prefix = prefix.trim_matches('/');
+        let mut root = Node::default();
+        for file in self.files_under(prefix) {
+            let path = if prefix.is_empty() {
+                file.path.as_str()
+            } else {
+                &file.path[prefix.len() + 1..]
+            };
+            root.insert(path, &file.symbols);
+        }
+        root.measure();
+        let mut out = String::new();
+        render_dir(&mut out, 0, None, &root, budget);
+        out
+    }
+}
+
+/// Project directories (containing a `/`, like `src/` or `crates/core`) mentioned in `text`,
+/// in order, without duplicates.
+fn mentioned_dirs(text: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    for word in text.split_whitespace() {
+        let word = word
+            .trim_matches(|c: char| "`'\"()[]{}<>,;:!?".contains(c))
+            .trim_end_matches('.');
+        if word.contains('/')
+            && !word.contains("://")
+            && !word.starts_with('/')
+            && !word.starts_with('~')
+            && !seen.iter().any(|s| s == word)
+        {
+            seen.push(word.to_string());
+        }
+    }
+    seen
+}
+
+#[async_trait]
+impl AgentMiddleware for RepoMapper {
+    async fn before_llm_call(&self, request: &mut ChatRequest) {
+        let mut dirs = Vec::new();
+        for message in request.messages.iter().filter(|m| m.role == "user") {
+            for path in mentioned_dirs(&message.content) {
+                let Ok((_, dir)) = resolve_within(&self.inner.root, &path, "repo_map") else {
+                    continue;
+                };
+                if dir.is_dir() && !dirs.contains(&dir) {
+                    // Nested mentions are covered by their parent's map.
+                    if dirs.iter().any(|d| dir.starts_with(d)) {
+                        continue;
+                    }
+                    dirs.retain(|d| !d.starts_with(&dir));
+                    dirs.push(dir);
+                }
+            }
+        }
+        if dirs.is_empty() {
+            return;
+        }
+        let budget = self.inner.budget / dirs.len();
+        let mut maps = Vec::new();
+        for dir in dirs {
+            let path = dir.to_string_lossy().to_string();
+            match self.render(&path, budget).await {
+                Ok((map, label)) if !map.is_empty() => maps.push(format!("{label}/\n{map}")),
+                Ok(_) => {}
+                Err(e) => log::debug!("repo_map {path}: {e}"),
+            }
+        }
+        if maps.is_empty() {
+            return;
+        }
+        let insert_at = request.messages.len().saturating_sub(1);
+        request.messages.insert(
+            insert_at,
+            Message {
+                role: "system".to_string(),
+                content: format!(
+                    "Map of the project directories in this conversation (from repo_map; call \
+                     repo_map with a path for more detail):\n\n{}",
+                    maps.join("\n")
+                ),
+                tool_calls: None,
+                images: None,
+                tool_call_id: None,
+                tool_name: None,
+            },
+        );
+    }
+}
+
+/// Answers `repo_map` calls: the map of a directory under the project root, with a caller
+/// chosen budget. Shares the [`RepoMapper`] cache it was made from.
+#[derive(Clone)]
+pub struct RepoMapTool {
+    mapper: RepoMapper,
+}
+
+impl RepoMapTool {
+    /// A tool over its own mapper of `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        RepoMapper::new().with_root(root).tool()
+    }
+}
+
+#[async_trait]
+impl Tool for RepoMapTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        match input.task_type.as_str() {
+            "repo_map" | "default" => {}
+            other => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "Unknown task '{other}' for repo_map; use repo_map"
+                )));
+            }
+        }
+        let params = &input.parameters;
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .unwrap_or(".");
+        let budget = match params.get("max_tokens") {
+            None | Some(serde_json::Value::Null) => self.mapper.inner.budget,
+            Some(value) => value
+                .as_u64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .filter(|n| *n > 0)
+                .ok_or_else(|| {
+                    KowalskiError::ToolInvalidInput(format!(
+                        "'max_tokens' must be a positive integer, got {value}"
+                    ))
+                })? as usize,
+        };
+        let (map, label) = self.mapper.render(path, budget).await?;
+        let words = map.split_whitespace().count();
+        Ok(ToolOutput::new(
+            json!({"path": label, "map": map}),
+            Some(json!({"tokens": words, "max_tokens": budget})),
+        )
+        .with_source(label))
+    }
+
+    fn name(&self) -> &str {
+        "repo_map"
+    }
+
+    fn description(&self) -> &str {
+        "Shows the files under a project directory (gitignored files left out) with the \
+         functions, types and classes each defines. 'path' is the directory, 'max_tokens' bounds \
+         the size; large directories are summarized."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "path".to_string(),
+                description: "Directory relative to the project root".to_string(),
+                required: false,
+                default_value: Some(".".to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "max_tokens".to_string(),
+                description: "Upper bound on the size of the map".to_string(),
+                required: false,
+                default_value: Some(DEFAULT_MAP_TOKENS.to_string()),
+                parameter_type: ParameterType::Number,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/repo");
+
+    fn copy_dir(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap().flatten() {
+            let target = to.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &target);
+            } else {
+                std::fs::copy(entry.path(), target).unwrap();
+            }
+        }
+    }
+
+    /// The fixture repo plus the files its `.gitignore` keeps out of git.
+    fn fixture_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        copy_dir(Path::new(FIXTURE), dir.path());
+        for (path, content) in [
+            ("build/out.rs", "fn generated() {}\n"),
+            ("notes.tmp", "scratch\n"),
+            ("keep.tmp", "kept by a negated pattern\n"),
+            ("src/shapes/cache.tmp", "scratch\n"),
+            (".git/HEAD", "ref: refs/heads/main\n"),
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn outline(symbols: &[Symbol]) -> Vec<String> {
+        symbols
+            .iter()
+            .map(|s| format!("{} {}", s.kind, s.name))
+            .collect()
+    }
+
+    fn fixture_source(path: &str) -> String {
+        std::fs::read_to_string(Path::new(FIXTURE).join(path)).unwrap()
+    }
+
+    #[test]
+    fn extracts_rust_python_and_other_outlines() {
+        let rust = extract_symbols("rs", &fixture_source("src/lib.rs"));
+        assert_eq!(
+            outline(&rust),
+            [
+                "mod shapes",
+                "trait Area",
+                "fn area",
+                "enum Unit",
+                "struct Measured",
+                "impl Measured<T>",
+                "fn new",
+                "impl fmt::Display for Measured<T>",
+                "fn fmt",
+                "fn total_area",
+                "macro square",
+            ]
+        );
+        assert_eq!(rust[1].line, 8);
+
+        let python = extract_symbols("py", &fixture_source("scripts/report.py"));
+        assert_eq!(
+            outline(&python),
+            [
+                "class ShapeRow",
+                "def __init__",
+                "def area",
+                "def load",
+                "def main"
+            ]
+        );
+        let js = extract_symbols("js", &fixture_source("web/app.js"));
+        assert_eq!(outline(&js), ["class ShapeView", "fn renderShapes"]);
+        let go = extract_symbols("go", &fixture_source("web/server.go"));
+        assert_eq!(outline(&go), ["type Server", "fn Serve", "fn NewServer"]);
+        assert!(extract_symbols("md", &fixture_source("README.md")).is_empty());
+    }
+
+    #[test]
+    fn walk_honours_gitignore() {
+        let repo = fixture_repo();
+        let map = RepoMapper::new().with_root(repo.path()).map();
+        let paths: Vec<&str> = map.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                ".gitignore",
+                "README.md",
+                "keep.tmp",
+                "scripts/report.py",
+                "src/lib.rs",
+                "src/shapes/mod.rs",
+                "web/app.js",
+                "web/server.go",
+            ]
+        );
+
+        let rules = parse_gitignore("/docs/*.md\n**/gen/**\nlogs/\n", "sub");
+        assert!(is_ignored(&rules, "sub/docs/a.md", false));
+        assert!(!is_ignored(&rules, "sub/x/docs/a.md", false));
+        assert!(is_ignored(&rules, "sub/a/gen/b/c.rs", false));
+        assert!(is_ignored(&rules, "sub/deep/logs", true));
+        assert!(!is_ignored(&rules, "sub/deep/logs", false));
+        assert!(!is_ignored(&rules, "other/docs/a.md", false));
+    }
+
+    #[test]
+    fn rendered_maps_stay_within_the_budget() {
+        let repo = fixture_repo();
+        let map = RepoMapper::new().with_root(repo.path()).map();
+        let full = map.render("", usize::MAX);
+        assert!(
+            full.contains("src/\n  shapes/\n    mod.rs: struct Circle,"),
+            "{full}"
+        );
+        assert!(
+            full.contains("  lib.rs: mod shapes, trait Area, fn area,"),
+            "{full}"
+        );
+        assert!(
+            full.contains("web/\n  app.js: class ShapeView, fn renderShapes\n"),
+            "{full}"
+        );
+        let full_words = full.split_whitespace().count();
+
+        assert!(map.render("", 1).split_whitespace().count() <= 1);
+        for budget in [5, 12, 25, 40, full_words - 1] {
+            let text = map.render("", budget);
+            let words = text.split_whitespace().count();
+            assert!(words <= budget, "{words} > {budget}:\n{text}");
+            assert!(text.contains("more") || text.contains('+'), "{text}");
+        }
+        // The largest directory keeps the most of its outline.
+        let text = map.render("", 40);
+        assert!(text.contains("src/"), "{text}");
+        assert_eq!(map.render("", full_words), full);
+
+        let subtree = map.render("src/shapes", usize::MAX);
+        assert!(subtree.starts_with("mod.rs: struct Circle"), "{subtree}");
+        assert!(!subtree.contains("lib.rs"), "{subtree}");
+    }
+
+    #[test]
+    fn only_changed_files_are_parsed_again() {
+        let repo = fixture_repo();
+        let mapper = RepoMapper::new().with_root(repo.path());
+        let first = mapper.map();
+        assert_eq!(mapper.parsed(), 8);
+        assert!(Arc::ptr_eq(&first, &mapper.map()));
+        assert_eq!(mapper.parsed(), 8);
+
+        let app = repo.path().join("web/app.js");
+        let mut source = std::fs::read_to_string(&app).unwrap();
+        source.push_str("export function drawAll() {}\n");
+        std::fs::write(&app, source).unwrap();
+        std::fs::remove_file(repo.path().join("web/server.go")).unwrap();
+        let second = mapper.map();
+        assert_eq!(mapper.parsed(), 9);
+        let web: Vec<_> = second.files_under("web").collect();
+        assert_eq!(web.len(), 1);
+        assert_eq!(
+            outline(&web[0].symbols),
+            ["class ShapeView", "fn renderShapes", "fn drawAll"]
+        );
+    }
+
+    #[tokio::test]
+    async fn tool_and_middleware_map_mentioned_directories() {
+        let repo = fixture_repo();
+        let mapper = RepoMapper::new().with_root(repo.path()).with_budget(200);
+        let mut tool = mapper.tool();
+        let out = tool
+            .execute(ToolInput::from_parameters(
+                json!({"path": "src", "max_tokens": 30}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(out.result["path"], "src");
+        let map = out.result["map"].as_str().unwrap();
+        assert!(map.contains("shapes/"), "{map}");
+        assert!(map.split_whitespace().count() <= 30, "{map}");
+        assert!(
+            tool.execute(ToolInput::from_parameters(json!({"path": "../"})))
+                .await
+                .is_err()
+        );
+        assert!(
+            tool.execute(ToolInput::from_parameters(json!({"path": "src/lib.rs"})))
+                .await
+                .is_err()
+        );
+
+        let message = |role: &str, content: &str| Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            images: None,
+            tool_call_id: None,
+            tool_name: None,
+        };
+        let mut request = ChatRequest {
+            model: "m".to_string(),
+            messages: vec![
+                message("system", "You write code."),
+                message(
+                    "user",
+                    "Where is Circle defined in `src/` (see src/shapes/)? Not https://x.io/a or build/.",
+                ),
+            ],
+            stream: false,
+            temperature: 0.0,
+            max_tokens: 100,
+            tools: None,
+            format: None,
+            options: None,
+        };
+        mapper.before_llm_call(&mut request).await;
+        assert_eq!(request.messages.len(), 3);
+        let context = &request.messages[1];
+        assert_eq!(context.role, "system");
+        assert!(
+            context
+                .content
+                .contains("src/\nshapes/\n  mod.rs: struct Circle"),
+            "{}",
+            context.content
+        );
+        assert!(context.content.contains("lib.rs: mod shapes"));
+        assert!(!context.content.contains("web/"));
+
+        let mut unrelated = ChatRequest {
+            messages: vec![message("user", "What is 2/3 of 9?")],
+            ..request
+        };
+        mapper.before_llm_call(&mut unrelated).await;
+        assert_eq!(unrelated.messages.len(), 1);
+    }
+}
@@ -0,0 +1,542 @@
+use crate::error::KowalskiError;
+use crate::tools::fs::{relative, resolve_within};
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_TABLE: &str = "data";
+const DEFAULT_MAX_ROWS: usize = 1000;
+/// Text recognized as a date (or a date and time).
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d.%m.%Y", "%d/%m/%Y", "%m/%d/%Y", "%Y/%m/%d"];
+const DATETIME_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"];
+
+/// Inferred type of one column. Integer and float widen to float; any other disagreement widens
+/// to string and marks the column as mixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InferredType {
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    String,
+}
+
+impl InferredType {
+    /// Column type used in the generated `CREATE TABLE`.
+    pub fn sql(self) -> &'static str {
+        match self {
+            InferredType::Integer => "INTEGER",
+            InferredType::Float => "DOUBLE PRECISION",
+            InferredType::Boolean => "BOOLEAN",
+            InferredType::Date => "DATE",
+            InferredType::String => "TEXT",
+        }
+    }
+
+    fn json_schema(self) -> (&'static str, Option<&'static str>) {
+        match self {
+            InferredType::Integer => ("integer", None),
+            InferredType::Float => ("number", None),
+            InferredType::Boolean => ("boolean", None),
+            InferredType::Date => ("string", Some("date")),
+            InferredType::String => ("string", None),
+        }
+    }
+
+    /// Type of one non-null text cell. `NaN` and `inf` stay strings; `yes`/`no` are booleans.
+    pub(crate) fn of_text(v: &str) -> Self {
+        if v.parse::<i64>().is_ok() {
+            InferredType::Integer
+        } else if v.chars().any(|c| c.is_ascii_digit()) && v.parse::<f64>().is_ok() {
+            InferredType::Float
+        } else if ["true", "false", "yes", "no"]
+            .iter()
+            .any(|b| v.eq_ignore_ascii_case(b))
+        {
+            InferredType::Boolean
+        } else if is_date(v) {
+            InferredType::Date
+        } else {
+            InferredType::String
+        }
+    }
+
+    /// JSON keeps its own scalar types: `"42"` stays a string, only strings may become dates.
+    fn of_json(v: &Value) -> Self {
+        match v {
+            Value::Bool(_) => InferredType::Boolean,
+            Value::Number(n) if n.is_i64() || n.is_u64() => InferredType::Integer,
+            Value::Number(_) => InferredType::Float,
+            Value::String(s) if is_date(s.trim()) => InferredType::Date,
+            _ => InferredType::String,
+        }
+    }
+}
+
+fn is_date(v: &str) -> bool {
+    DATE_FORMATS
+        .iter()
+        .any(|f| NaiveDate::parse_from_str(v, f).is_ok())
+        || DATETIME_FORMATS
+            .iter()
+            .any(|f| NaiveDateTime::parse_from_str(v, f).is_ok())
+}
+
+/// One inferred column.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: InferredType,
+    pub nullable: bool,
+    /// Values of incompatible types were seen; the column was widened to string.
+    pub mixed: bool,
+}
+
+#[derive(Debug, Clone)]
+struct ColumnState {
+    name: String,
+    ty: Option<InferredType>,
+    nullable: bool,
+    mixed: bool,
+}
+
+impl ColumnState {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ty: None,
+            nullable: false,
+            mixed: false,
+        }
+    }
+
+    fn observe(&mut self, value: Option<InferredType>) {
+        let Some(seen) = value else {
+            self.nullable = true;
+            return;
+        };
+        self.ty = Some(match self.ty {
+            None => seen,
+            Some(current) if current == seen => current,
+            Some(InferredType::Integer | InferredType::Float)
+                if matches!(seen, InferredType::Integer | InferredType::Float) =>
+            {
+                InferredType::Float
+            }
+            Some(_) => {
+                self.mixed = true;
+                InferredType::String
+            }
+        });
+    }
+
+    fn finish(self) -> ColumnSchema {
+        ColumnSchema {
+            name: self.name,
+            // A column that only ever held nulls carries no type information.
+            ty: self.ty.unwrap_or(InferredType::String),
+            nullable: self.nullable || self.ty.is_none(),
+            mixed: self.mixed,
+        }
+    }
+}
+
+/// Infers column types (integer, float, boolean, date, string) and nullability from a CSV or
+/// JSON sample (inline or a file under the tool root) and renders them as a JSON Schema and a
+/// `CREATE TABLE` statement.
+#[derive(Debug, Clone)]
+pub struct SchemaInferenceTool {
+    root: PathBuf,
+}
+
+impl Default for SchemaInferenceTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchemaInferenceTool {
+    /// Sample files are read under the working directory.
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("."),
+        }
+    }
+
+    /// Reads `path` samples relative to `root`; paths that resolve outside it are refused, as
+    /// with [`FsTool`](crate::tools::FsTool).
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Columns of a CSV sample with a header row; empty cells count as null.
+    pub fn infer_csv(sample: &str, max_rows: usize) -> Result<Vec<ColumnSchema>, KowalskiError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(sample.as_bytes());
+        let mut columns: Vec<ColumnState> = reader
+            .headers()
+            .map_err(|e| KowalskiError::ContentProcessing(format!("CSV header: {e}")))?
+            .iter()
+            .map(|h| ColumnState::new(h.trim().to_string()))
+            .collect();
+        for record in reader.records().take(max_rows) {
+            let record =
+                record.map_err(|e| KowalskiError::ContentProcessing(format!("CSV row: {e}")))?;
+            for (i, column) in columns.iter_mut().enumerate() {
+                let cell = record.get(i).map(str::trim).filter(|v| !v.is_empty());
+                column.observe(cell.map(InferredType::of_text));
+            }
+        }
+        Ok(columns.into_iter().map(ColumnState::finish).collect())
+    }
+
+    /// Columns of a JSON sample: an array of flat objects, a single object, or one object per
+    /// line. Columns are the union of keys in first-seen order; a missing key counts as null.
+    pub fn infer_json(sample: &str, max_rows: usize) -> Result<Vec<ColumnSchema>, KowalskiError> {
+        let items: Vec<Map<String, Value>> = match serde_json::from_str::<Value>(sample) {
+            Ok(Value::Array(items)) => items
+                .into_iter()
+                .map(|item| match item {
+                    Value::Object(obj) => Ok(obj),
+                    other => Err(KowalskiError::ContentProcessing(format!(
+                        "JSON rows must be objects, got {other}"
+                    ))),
+                })
+                .collect::<Result<_, _>>()?,
+            Ok(Value::Object(obj)) => vec![obj],
+            _ => sample
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(serde_json::from_str::<Map<String, Value>>)
+                .collect::<Result<_, _>>()
+                .map_err(|e| {
+                    KowalskiError::ContentProcessing(format!(
+                        "JSON must be an array of objects or one object per line: {e}"
+                    ))
+                })?,
+        };
+        let items = &items[..items.len().min(max_rows)];
+        let mut columns: Vec<ColumnState> = Vec::new();
+        for item in items {
+            for key in item.keys() {
+                if !columns.iter().any(|c| &c.name == key) {
+                    columns.push(ColumnState::new(key.clone()));
+                }
+            }
+        }
+        for item in items {
+            for column in &mut columns {
+                let value = item.get(&column.name).filter(|v| !v.is_null());
+                column.observe(value.map(InferredType::of_json));
+            }
+        }
+        Ok(columns.into_iter().map(ColumnState::finish).collect())
+    }
+
+    /// JSON Schema (draft 2020-12) for one row; non-nullable columns are `required`.
+    pub fn json_schema(table: &str, columns: &[ColumnSchema]) -> Value {
+        let mut properties = Map::new();
+        for column in columns {
+            let (ty, format) = column.ty.json_schema();
+            let mut prop = Map::new();
+            prop.insert(
+                "type".to_string(),
+                if column.nullable {
+                    json!([ty, "null"])
+                } else {
+                    json!(ty)
+                },
+            );
+            if let Some(format) = format {
+                prop.insert("format".to_string(), json!(format));
+            }
+            properties.insert(column.name.clone(), Value::Object(prop));
+        }
+        let required: Vec<&str> = columns
+            .iter()
+            .filter(|c| !c.nullable)
+            .map(|c| c.name.as_str())
+            .collect();
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": table,
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+
+    /// `CREATE TABLE` statement with quoted identifiers and `NOT NULL` on non-nullable columns.
+    pub fn ddl(table: &str, columns: &[ColumnSchema]) -> String {
+        let body = columns
+            .iter()
+            .map(|c| {
+                format!(
+                    "  {} {}{}",
+                    quote_ident(&c.name),
+                    c.ty.sql(),
+                    if c.nullable { "" } else { " NOT NULL" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",\n");
+        format!("CREATE TABLE {} (\n{body}\n);", quote_ident(table))
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn looks_like_json(sample: &str) -> bool {
+    matches!(sample.trim_start().chars().next(), Some('[' | '{'))
+}
+
+#[async_trait]
+impl Tool for SchemaInferenceTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let param = |name: &str| input.parameters.get(name).and_then(|v| v.as_str());
+        let (sample, from_path) = match param("path") {
+            Some(path) => {
+                let (root, file) = resolve_within(&self.root, path, self.name())?;
+                (
+                    std::fs::read_to_string(&file)?,
+                    Some(relative(&root, &file)),
+                )
+            }
+            None => (
+                param("sample")
+                    .unwrap_or(input.content.as_str())
+                    .to_string(),
+                None,
+            ),
+        };
+        if sample.trim().is_empty() {
+            return Err(KowalskiError::ToolInvalidInput(
+                "Missing required parameter: sample (or path)".to_string(),
+            ));
+        }
+        let format = match param("format") {
+            Some(f) if f.eq_ignore_ascii_case("json") => "json",
+            Some(f) if f.eq_ignore_ascii_case("csv") => "csv",
+            Some(other) => {
+                return Err(KowalskiError::ToolInvalidInput(format!(
+                    "format must be 'csv' or 'json', got '{other}'"
+                )));
+            }
+            None => match from_path
+                .as_deref()
+                .map(Path::new)
+                .and_then(|p| p.extension())
+            {
+                Some(ext) if ext.eq_ignore_ascii_case("json") => "json",
+                Some(_) => "csv",
+                None if looks_like_json(&sample) => "json",
+                None => "csv",
+            },
+        };
+        let table = param("table").unwrap_or(DEFAULT_TABLE);
+        let max_rows = input
+            .parameters
+            .get("max_rows")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_ROWS);
+
+        let columns = if format == "json" {
+            Self::infer_json(&sample, max_rows)?
+        } else {
+            Self::infer_csv(&sample, max_rows)?
+        };
+        let mixed: Vec<&str> = columns
+            .iter()
+            .filter(|c| c.mixed)
+            .map(|c| c.name.as_str())
+            .collect();
+        Ok(ToolOutput::new(
+            json!({
+                "columns": columns,
+                "json_schema": Self::json_schema(table, &columns),
+                "ddl": Self::ddl(table, &columns),
+            }),
+            Some(json!({
+                "format": format,
+                "table": table,
+                "column_count": columns.len(),
+                "mixed_columns": mixed,
+            })),
+        )
+        .with_source(from_path.as_deref().unwrap_or(self.name())))
+    }
+
+    fn name(&self) -> &str {
+        "infer_schema"
+    }
+
+    fn description(&self) -> &str {
+        "Infers column types (integer, float, boolean, date, string) and nullability from a CSV or JSON sample. Returns a JSON Schema and a CREATE TABLE statement; mixed-type columns are widened to string and flagged."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "sample".to_string(),
+                description: "CSV text (with header row) or JSON rows; ignored when path is set"
+                    .to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "path".to_string(),
+                description:
+                    "Path (relative to the tool root) of a .csv or .json file to read the sample from"
+                        .to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "format".to_string(),
+                description: "'csv' or 'json'; detected from the file extension or content"
+                    .to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "table".to_string(),
+                description: "Table name used in the DDL and as the schema title".to_string(),
+                required: false,
+                default_value: Some(DEFAULT_TABLE.to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "max_rows".to_string(),
+                description: "Maximum sample rows to inspect".to_string(),
+                required: false,
+                default_value: Some(DEFAULT_MAX_ROWS.to_string()),
+                parameter_type: ParameterType::Number,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDERS: &str = "id,price,paid,placed_on,note,code\n\
+        1,9.5,true,2024-01-03,,A1\n\
+        2,10,false,2024-02-11,rush,42\n\
+        3,7.25,TRUE,2024-03-30,,2024-01-01\n";
+
+    fn input(params: Value) -> ToolInput {
+        ToolInput::new("infer_schema".to_string(), String::new(), params)
+    }
+
+    #[test]
+    fn text_cells_are_typed_like_the_dataset_profile() {
+        for (cell, ty) in [
+            ("42", InferredType::Integer),
+            ("-3.5", InferredType::Float),
+            ("NaN", InferredType::String),
+            ("inf", InferredType::String),
+            ("Yes", InferredType::Boolean),
+            ("03.04.2024", InferredType::Date),
+            ("2024-01-02 10:00:00", InferredType::Date),
+        ] {
+            assert_eq!(InferredType::of_text(cell), ty, "{cell}");
+        }
+    }
+
+    #[tokio::test]
+    async fn all_integer_column_infers_integer() {
+        let out = SchemaInferenceTool::new()
+            .execute(input(json!({ "sample": ORDERS, "table": "orders" })))
+            .await
+            .unwrap();
+        let columns = &out.result["columns"];
+        assert_eq!(
+            columns[0],
+            json!({"name": "id", "type": "integer", "nullable": false, "mixed": false})
+        );
+        assert_eq!(columns[1]["type"], "float");
+        assert_eq!(columns[2]["type"], "boolean");
+        assert_eq!(columns[3]["type"], "date");
+        assert_eq!(
+            columns[4],
+            json!({"name": "note", "type": "string", "nullable": true, "mixed": false})
+        );
+        assert_eq!(
+            columns[5],
+            json!({"name": "code", "type": "string", "nullable": false, "mixed": true})
+        );
+
+        let ddl = out.result["ddl"].as_str().unwrap();
+        assert!(ddl.starts_with("CREATE TABLE \"orders\" (\n"));
+        assert!(ddl.contains("  \"id\" INTEGER NOT NULL,\n"));
+        assert!(ddl.contains("  \"note\" TEXT,\n"));
+        assert_eq!(out.metadata.unwrap()["mixed_columns"], json!(["code"]));
+    }
+
+    #[tokio::test]
+    async fn json_sample_produces_json_schema() {
+        let out = SchemaInferenceTool::new()
+            .execute(input(json!({
+                "sample": r#"[{"id":1,"born":"1990-05-01","tags":["a"]},{"id":2,"score":0.5}]"#,
+            })))
+            .await
+            .unwrap();
+        let schema = &out.result["json_schema"];
+        assert_eq!(schema["properties"]["id"], json!({"type": "integer"}));
+        assert_eq!(
+            schema["properties"]["born"],
+            json!({"type": ["string", "null"], "format": "date"})
+        );
+        assert_eq!(
+            schema["properties"]["score"]["type"],
+            json!(["number", "null"])
+        );
+        assert_eq!(schema["required"], json!(["id"]));
+        assert_eq!(out.metadata.unwrap()["format"], "json");
+    }
+
+    #[tokio::test]
+    async fn sample_files_are_read_under_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("data");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("orders.csv"), ORDERS).unwrap();
+        std::fs::write(dir.path().join("secret.csv"), "key\nhunter2\n").unwrap();
+        let mut tool = SchemaInferenceTool::new().with_root(&root);
+
+        let out = tool
+            .execute(input(json!({ "path": "orders.csv" })))
+            .await
+            .unwrap();
+        assert_eq!(out.result["columns"][0]["name"], "id");
+        assert_eq!(out.metadata.unwrap()["format"], "csv");
+
+        for path in [
+            "../secret.csv".to_string(),
+            dir.path().join("secret.csv").to_string_lossy().to_string(),
+        ] {
+            let err = tool
+                .execute(input(json!({ "path": path })))
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(err, KowalskiError::PermissionDenied(_)),
+                "{path}: {err}"
+            );
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use crate::error::KowalskiError;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+fn default_root() -> PathBuf {
+    PathBuf::from(".")
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_output_bytes() -> usize {
+    64 * 1024
+}
+
+/// Settings for [`ShellTool`]. The default allowlist is empty, so nothing runs until binaries
+/// are listed explicitly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellToolConfig {
+    /// Binary names (or exact paths) that may be executed, e.g. `["cargo", "ls", "python3"]`.
+    #[serde(default)]
+    pub allowed_commands: Vec<String>,
+    /// Commands run in this directory or a subdirectory of it (`cwd` parameter).
+    #[serde(default = "default_root")]
+    pub root: PathBuf,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// stdout and stderr are each truncated to this many bytes.
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+impl Default for ShellToolConfig {
+    fn default() -> Self {
+        Self {
+            allowed_commands: Vec::new(),
+            root: default_root(),
+            timeout_secs: default_timeout_secs(),
+            max_output_bytes: default_max_output_bytes(),
+        }
+    }
+}
+
+/// Runs an allowlisted binary with an argument list (never through a shell), confined to a root
+/// directory, and returns stdout, stderr and the exit code.
+#[derive(Debug, Clone, Default)]
+pub struct ShellTool {
+    config: ShellToolConfig,
+}
+
+impl ShellTool {
+    pub fn new(config: ShellToolConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &ShellToolConfig {
+        &self.config
+    }
+
+    fn check_allowed(&self, command: &str) -> Result<(), KowalskiError> {
+        if self.config.allowed_commands.iter().any(|c| c == command) {
+            return Ok(());
+        }
+        let allowed = if self.config.allowed_commands.is_empty() {
+            "none configured".to_string()
+        } else {
+            self.config.allowed_commands.join(", ")
+        };
+        Err(KowalskiError::PermissionDenied(format!(
+            "command '{command}' is not on the shell allowlist (allowed: {allowed})"
+        )))
+    }
+
+    /// Resolves `cwd` against the root and rejects anything that escapes it.
+    fn resolve_cwd(&self, cwd: Option<&str>) -> Result<PathBuf, KowalskiError> {
+        let root = self.config.root.canonicalize().map_err(|e| {
+            KowalskiError::ToolConfig(format!("shell root {}: {e}", self.config.root.display()))
+        })?;
+        let Some(cwd) = cwd.filter(|c| !c.is_empty()) else {
+            return Ok(root);
+        };
+        let dir = root.join(Path::new(cwd)).canonicalize().map_err(|e| {
+            KowalskiError::ToolInvalidInput(format!("working directory '{cwd}': {e}"))
+        })?;
+        if !dir.starts_with(&root) {
+            return Err(KowalskiError::PermissionDenied(format!(
+                "working directory '{cwd}' is outside the shell root"
+            )));
+        }
+        Ok(dir)
+    }
+
+    fn truncate(&self, bytes: &[u8]) -> (String, bool) {
+        let limit = self.config.max_output_bytes;
+        let truncated = bytes.len() > limit;
+        let text = String::from_utf8_lossy(&bytes[..bytes.len().min(limit)]).into_owned();
+        (text, truncated)
+    }
+}
+
+#[async_trait]
+impl Tool for ShellTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let command = input
+            .parameters
+            .get("command")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| {
+                KowalskiError::ToolInvalidInput("Missing required parameter: command".to_string())
+            })?
+            .to_string();
+        self.check_allowed(&command)?;
+
+        let args = match input.parameters.get("args") {
+            None | Some(serde_json::Value::Null) => Vec::new(),
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .map(|v| {
+                    v.as_str().map(str::to_string).ok_or_else(|| {
+                        KowalskiError::ToolInvalidInput("args must be an array of strings".into())
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => {
+                return Err(KowalskiError::ToolInvalidInput(
+                    "args must be an array of strings".to_string(),
+                ));
+            }
+        };
+        let cwd = self.resolve_cwd(input.parameters.get("cwd").and_then(|v| v.as_str()))?;
+
+        let started = Instant::now();
+        let child = tokio::process::Command::new(&command)
+            .args(&args)
+            .current_dir(&cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                KowalskiError::ToolExecution(format!("failed to start '{command}': {e}"))
+            })?;
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| KowalskiError::Timeout {
+                operation: format!(
+                    "'{command}' did not finish within {}s",
+                    self.config.timeout_secs
+                ),
+            })??;
+
+        let (stdout, stdout_truncated) = self.truncate(&output.stdout);
+        let (stderr, stderr_truncated) = self.truncate(&output.stderr);
+        Ok(ToolOutput::new(
+            serde_json::json!({
+                "exit_code": output.status.code(),
+                "success": output.status.success(),
+                "stdout": stdout,
+                "stderr": stderr,
+            }),
+            Some(serde_json::json!({
+                "command": command,
+                "args": args,
+                "cwd": cwd.display().to_string(),
+                "duration_ms": started.elapsed().as_millis() as u64,
+                "stdout_truncated": stdout_truncated,
+                "stderr_truncated": stderr_truncated,
+            })),
+        )
+        .with_source(format!("shell:{command} {}", args.join(" ")).trim_end()))
+    }
+
+    fn name(&self) -> &str {
+        "shell"
+    }
+
+    fn description(&self) -> &str {
+        "Runs an allowlisted command with an argument list (no shell syntax) and returns stdout, stderr and exit_code."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "command".to_string(),
+                description: "Binary to run; must be on the allowlist".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "args".to_string(),
+                description: "Arguments, one string per argument".to_string(),
+                required: false,
+                default_value: Some("[]".to_string()),
+                parameter_type: ParameterType::Array,
+            },
+            ToolParameter {
+                name: "cwd".to_string(),
+                description: "Working directory relative to the tool root".to_string(),
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(dir: &tempfile::TempDir, allowed: &[&str]) -> ShellTool {
+        ShellTool::new(ShellToolConfig {
+            allowed_commands: allowed.iter().map(|s| s.to_string()).collect(),
+            root: dir.path().to_path_buf(),
+            ..ShellToolConfig::default()
+        })
+    }
+
+    fn input(params: serde_json::Value) -> ToolInput {
+        ToolInput::new("default".to_string(), String::new(), params)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runs_allowlisted_echo() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = tool(&dir, &["echo"])
+            .execute(input(
+                json!({"command": "echo", "args": ["hello", "world"]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(out.result["exit_code"], 0);
+        assert_eq!(out.result["stdout"], "hello world\n");
+        assert_eq!(out.result["stderr"], "");
+    }
+
+    #[tokio::test]
+    async fn rejects_commands_not_on_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = tool(&dir, &["echo"])
+            .execute(input(json!({"command": "rm", "args": ["-rf", "."]})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)));
+        assert!(err.to_string().contains("'rm'"));
+
+        let err = ShellTool::default()
+            .execute(input(json!({"command": "echo"})))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("none configured"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn confines_cwd_and_enforces_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let mut shell = tool(&dir, &["pwd", "sleep"]);
+
+        let out = shell
+            .execute(input(json!({"command": "pwd", "cwd": "sub"})))
+            .await
+            .unwrap();
+        assert!(
+            out.result["stdout"]
+                .as_str()
+                .unwrap()
+                .trim_end()
+                .ends_with("sub")
+        );
+
+        let err = shell
+            .execute(input(json!({"command": "pwd", "cwd": ".."})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::PermissionDenied(_)));
+
+        shell.config.timeout_secs = 0;
+        let err = shell
+            .execute(input(json!({"command": "sleep", "args": ["5"]})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KowalskiError::Timeout { .. }));
+    }
+}
@@ -0,0 +1,466 @@
+use crate::error::KowalskiError;
+use crate::tools::fs::{relative, resolve_within};
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde_json::{Map, Value, json};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
+use sqlx::{Column, Row, SqlitePool, TypeInfo, ValueRef};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const DEFAULT_TABLE: &str = "data";
+const DEFAULT_MAX_ROWS: usize = 100;
+
+/// Keywords that must not appear as statements in a query; `PRAGMA query_only` backs this up.
+const BLOCKED_KEYWORDS: &[&str] = &[
+    "insert", "update", "delete", "create", "drop", "alter", "attach", "detach", "pragma",
+    "vacuum", "reindex",
+];
+
+/// Loads a CSV or JSON file under the tool root into an in-memory SQLite table and runs a
+/// read-only SQL query over it, returning rows as JSON objects.
+#[derive(Debug, Clone)]
+pub struct SqlTool {
+    root: PathBuf,
+}
+
+impl Default for SqlTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl ColumnType {
+    fn sql(self) -> &'static str {
+        match self {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "REAL",
+            ColumnType::Text => "TEXT",
+        }
+    }
+
+    /// Narrowest type that fits every non-empty value.
+    fn infer<'a>(values: impl Iterator<Item = &'a str>) -> Self {
+        let mut ty = ColumnType::Integer;
+        for v in values.map(str::trim).filter(|v| !v.is_empty()) {
+            if ty == ColumnType::Integer && v.parse::<i64>().is_err() {
+                ty = ColumnType::Real;
+            }
+            if ty == ColumnType::Real && v.parse::<f64>().is_err() {
+                return ColumnType::Text;
+            }
+        }
+        ty
+    }
+}
+
+/// A loaded table: header names and string cells (empty string = NULL).
+struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<Option<String>>>,
+}
+
+impl SqlTool {
+    /// Files are read under the working directory.
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("."),
+        }
+    }
+
+    /// Reads files relative to `root`; paths that resolve outside it are refused, as with
+    /// [`FsTool`](crate::tools::FsTool).
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Rejects anything but a single `SELECT` / `WITH` statement.
+    pub fn check_read_only(query: &str) -> Result<(), KowalskiError> {
+        let trimmed = query.trim().trim_end_matches(';').trim();
+        if trimmed.contains(';') {
+            return Err(KowalskiError::ToolInvalidInput(
+                "only a single SQL statement is allowed".to_string(),
+            ));
+        }
+        let lower = trimmed.to_lowercase();
+        if !(lower.starts_with("select") || lower.starts_with("with")) {
+            return Err(KowalskiError::PermissionDenied(
+                "only read-only SELECT / WITH queries are allowed".to_string(),
+            ));
+        }
+        let words: Vec<&str> = lower
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .collect();
+        if let Some(kw) = BLOCKED_KEYWORDS.iter().find(|kw| words.contains(kw)) {
+            return Err(KowalskiError::PermissionDenied(format!(
+                "statement keyword '{}' is not allowed in a read-only query",
+                kw.to_uppercase()
+            )));
+        }
+        Ok(())
+    }
+
+    fn read_csv(path: &Path) -> Result<Table, KowalskiError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| KowalskiError::ContentProcessing(format!("read CSV: {e}")))?;
+        let columns = reader
+            .headers()
+            .map_err(|e| KowalskiError::ContentProcessing(format!("CSV header: {e}")))?
+            .iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record =
+                record.map_err(|e| KowalskiError::ContentProcessing(format!("CSV row: {e}")))?;
+            rows.push(
+                (0..columns.len())
+                    .map(|i| record.get(i).filter(|v| !v.is_empty()).map(str::to_string))
+                    .collect(),
+            );
+        }
+        Ok(Table { columns, rows })
+    }
+
+    /// JSON input: an array of flat objects; columns are the union of keys in first-seen order.
+    fn read_json(path: &Path) -> Result<Table, KowalskiError> {
+        let raw = std::fs::read_to_string(path)?;
+        let items: Vec<Map<String, Value>> = serde_json::from_str(&raw).map_err(|e| {
+            KowalskiError::ContentProcessing(format!("JSON must be an array of objects: {e}"))
+        })?;
+        let mut columns: Vec<String> = Vec::new();
+        for item in &items {
+            for key in item.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+        let rows = items
+            .iter()
+            .map(|item| {
+                columns
+                    .iter()
+                    .map(|c| match item.get(c) {
+                        None | Some(Value::Null) => None,
+                        Some(Value::String(s)) => Some(s.clone()),
+                        Some(other) => Some(other.to_string()),
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Table { columns, rows })
+    }
+
+    fn quote_ident(name: &str) -> String {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+
+    async fn load(pool: &SqlitePool, table_name: &str, table: &Table) -> Result<(), KowalskiError> {
+        let db_err = |e: sqlx::Error| KowalskiError::Database(format!("load table: {e}"));
+        let types: Vec<ColumnType> = (0..table.columns.len())
+            .map(|i| ColumnType::infer(table.rows.iter().filter_map(|r| r[i].as_deref())))
+            .collect();
+        let defs = table
+            .columns
+            .iter()
+            .zip(&types)
+            .map(|(c, t)| format!("{} {}", Self::quote_ident(c), t.sql()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query(&format!(
+            "CREATE TABLE {} ({defs})",
+            Self::quote_ident(table_name)
+        ))
+        .execute(pool)
+        .await
+        .map_err(db_err)?;
+
+        let placeholders = vec!["?"; table.columns.len()].join(", ");
+        let insert = format!(
+            "INSERT INTO {} VALUES ({placeholders})",
+            Self::quote_ident(table_name)
+        );
+        let mut tx = pool.begin().await.map_err(db_err)?;
+        for row in &table.rows {
+            let mut q = sqlx::query(&insert);
+            for (cell, ty) in row.iter().zip(&types) {
+                let cell = cell.as_deref().map(str::trim);
+                q = match (ty, cell) {
+                    (_, None) => q.bind(None::<String>),
+                    (ColumnType::Integer, Some(v)) => q.bind(v.parse::<i64>().ok()),
+                    (ColumnType::Real, Some(v)) => q.bind(v.parse::<f64>().ok()),
+                    (ColumnType::Text, Some(v)) => q.bind(v.to_string()),
+                };
+            }
+            q.execute(&mut *tx).await.map_err(db_err)?;
+        }
+        tx.commit().await.map_err(db_err)?;
+        Ok(())
+    }
+
+    fn row_to_json(row: &SqliteRow) -> Value {
+        let mut obj = Map::new();
+        for (i, column) in row.columns().iter().enumerate() {
+            let value = match row.try_get_raw(i) {
+                Ok(raw) if raw.is_null() => Value::Null,
+                Ok(raw) => match raw.type_info().name() {
+                    "INTEGER" => row
+                        .try_get::<i64, _>(i)
+                        .map(Value::from)
+                        .unwrap_or_default(),
+                    "REAL" => row
+                        .try_get::<f64, _>(i)
+                        .map(Value::from)
+                        .unwrap_or_default(),
+                    _ => row
+                        .try_get::<String, _>(i)
+                        .map(Value::from)
+                        .unwrap_or_default(),
+                },
+                Err(_) => Value::Null,
+            };
+            obj.insert(column.name().to_string(), value);
+        }
+        Value::Object(obj)
+    }
+
+    /// Loads `path` (`.csv` or `.json`) as `table_name` and runs `query`, returning at most
+    /// `max_rows` rows plus whether more were available.
+    pub async fn query_file(
+        path: &Path,
+        table_name: &str,
+        query: &str,
+        max_rows: usize,
+    ) -> Result<(Vec<Value>, bool), KowalskiError> {
+        Self::check_read_only(query)?;
+        let table = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::read_json(path)?,
+            _ => Self::read_csv(path)?,
+        };
+
+        // One connection: every `:memory:` connection is a separate database.
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .map_err(|e| KowalskiError::Database(e.to_string()))?;
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .map_err(|e| KowalskiError::Database(format!("open in-memory SQLite: {e}")))?;
+        Self::load(&pool, table_name, &table).await?;
+        sqlx::query("PRAGMA query_only = ON")
+            .execute(&pool)
+            .await
+            .map_err(|e| KowalskiError::Database(e.to_string()))?;
+
+        let rows = sqlx::query(query)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| KowalskiError::ToolExecution(format!("SQL error: {e}")))?;
+        pool.close().await;
+        let truncated = rows.len() > max_rows;
+        Ok((
+            rows.iter().take(max_rows).map(Self::row_to_json).collect(),
+            truncated,
+        ))
+    }
+}
+
+#[async_trait]
+impl Tool for SqlTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let param = |name: &str| input.parameters.get(name).and_then(|v| v.as_str());
+        let path = param("path").ok_or_else(|| {
+            KowalskiError::ToolInvalidInput("Missing required parameter: path".to_string())
+        })?;
+        let query = param("query").unwrap_or(input.content.as_str());
+        if query.trim().is_empty() {
+            return Err(KowalskiError::ToolInvalidInput(
+                "Missing required parameter: query".to_string(),
+            ));
+        }
+        let table = param("table").unwrap_or(DEFAULT_TABLE);
+        let max_rows = input
+            .parameters
+            .get("max_rows")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_ROWS);
+
+        let (root, file) = resolve_within(&self.root, path, self.name())?;
+        let (rows, truncated) = Self::query_file(&file, table, query, max_rows).await?;
+        Ok(ToolOutput::new(
+            json!({ "rows": rows }),
+            Some(json!({
+                "path": relative(&root, &file),
+                "table": table,
+                "row_count": rows.len(),
+                "truncated": truncated,
+            })),
+        )
+        .with_source(path))
+    }
+
+    fn name(&self) -> &str {
+        "sql_query"
+    }
+
+    fn description(&self) -> &str {
+        "Loads a CSV or JSON file into an in-memory SQLite table (default name `data`) and runs a read-only SELECT query over it. Returns rows as JSON objects."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "path".to_string(),
+                description: "Path (relative to the tool root) to a .csv file with a header row or a .json array of objects"
+                    .to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "query".to_string(),
+                description: "A single SELECT or WITH statement".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "table".to_string(),
+                description: "Table name to load the file as".to_string(),
+                required: false,
+                default_value: Some(DEFAULT_TABLE.to_string()),
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "max_rows".to_string(),
+                description: "Maximum rows to return".to_string(),
+                required: false,
+                default_value: Some(DEFAULT_MAX_ROWS.to_string()),
+                parameter_type: ParameterType::Number,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALES: &str = "product,quarter,revenue\n\
+        widget,Q1,100\n\
+        widget,Q2,150.5\n\
+        gadget,Q1,80\n\
+        gadget,Q1,40\n\
+        doohickey,Q2,\n";
+
+    fn input(params: Value) -> ToolInput {
+        ToolInput::new("query".to_string(), String::new(), params)
+    }
+
+    #[tokio::test]
+    async fn group_by_over_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sales.csv");
+        std::fs::write(&path, SALES).unwrap();
+
+        let out = SqlTool::new()
+            .with_root(dir.path())
+            .execute(input(json!({
+                "path": "sales.csv",
+                "query": "SELECT product, SUM(revenue) AS total, COUNT(*) AS n FROM data \
+                          GROUP BY product ORDER BY total DESC",
+            })))
+            .await
+            .unwrap();
+        let rows = out.result["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            json!({"product": "widget", "total": 250.5, "n": 2})
+        );
+        assert_eq!(
+            rows[1],
+            json!({"product": "gadget", "total": 120.0, "n": 2})
+        );
+        assert_eq!(rows[2]["total"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn json_input_and_row_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.json");
+        std::fs::write(
+            &path,
+            r#"[{"name":"Ann","age":31},{"name":"Bob","age":25,"city":"Oslo"},{"name":"Cid","age":40}]"#,
+        )
+        .unwrap();
+        let out = SqlTool::new()
+            .with_root(dir.path())
+            .execute(input(json!({
+                "path": "people.json",
+                "table": "people",
+                "query": "SELECT name, city FROM people WHERE age > 20 ORDER BY age",
+                "max_rows": 2,
+            })))
+            .await
+            .unwrap();
+        assert_eq!(
+            out.result["rows"],
+            json!([{"name": "Bob", "city": "Oslo"}, {"name": "Ann", "city": null}])
+        );
+        assert_eq!(out.metadata.unwrap()["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn files_outside_the_root_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("data");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(dir.path().join("secret.csv"), "key\nhunter2\n").unwrap();
+
+        let mut tool = SqlTool::new().with_root(&root);
+        for path in [
+            "../secret.csv".to_string(),
+            dir.path().join("secret.csv").to_string_lossy().to_string(),
+        ] {
+            let err = tool
+                .execute(input(json!({"path": path, "query": "SELECT * FROM data"})))
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(err, KowalskiError::PermissionDenied(_)),
+                "{path}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn blocks_writes_and_ddl() {
+        for q in [
+            "DELETE FROM data",
+            "DROP TABLE data",
+            "SELECT 1; DROP TABLE data",
+            "WITH x AS (SELECT 1) INSERT INTO data SELECT * FROM x",
+            "ATTACH DATABASE 'x.db' AS x",
+        ] {
+            assert!(
+                SqlTool::check_read_only(q).is_err(),
+                "{q} should be rejected"
+            );
+        }
+        assert!(SqlTool::check_read_only("select * from data;").is_ok());
+        assert!(SqlTool::check_read_only("SELECT created_at, updated FROM data").is_ok());
+    }
+}
@@ -0,0 +1,786 @@
+use crate::error::KowalskiError;
+use crate::tools::csv::{CsvFormat, invalid};
+use crate::tools::fs::resolve_within;
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde_json::{Map, Value, json};
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Cells that count as missing (compared case-insensitively after trimming). Non-finite numbers
+/// (`NaN`, `inf`) are missing too.
+pub(crate) const NULL_MARKERS: &[&str] = &["", "na", "n/a", "nan", "null", "none"];
+
+const DEFAULT_BINS: usize = 10;
+const MAX_BINS: usize = 1000;
+const DEFAULT_OUTLIER_ROWS: usize = 20;
+
+enum Cell {
+    Null,
+    Number(f64),
+    Text,
+}
+
+fn cell(raw: &str) -> Cell {
+    let raw = raw.trim();
+    if NULL_MARKERS.iter().any(|m| raw.eq_ignore_ascii_case(m)) {
+        return Cell::Null;
+    }
+    match raw.parse::<f64>() {
+        Ok(value) if value.is_finite() => Cell::Number(value),
+        Ok(_) => Cell::Null,
+        Err(_) => Cell::Text,
+    }
+}
+
+/// Running mean and second/third central moments (Welford), for mean, stddev and skew in one
+/// pass.
+#[derive(Debug, Clone, Copy, Default)]
+struct Moments {
+    n: f64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+}
+
+impl Moments {
+    fn add(&mut self, x: f64) {
+        let n1 = self.n;
+        self.n += 1.0;
+        let delta = x - self.mean;
+        let delta_n = delta / self.n;
+        let term = delta * delta_n * n1;
+        self.mean += delta_n;
+        self.m3 += term * delta_n * (self.n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term;
+    }
+
+    /// Sample standard deviation (n - 1).
+    fn std(&self) -> Option<f64> {
+        (self.n >= 2.0).then(|| (self.m2 / (self.n - 1.0)).sqrt())
+    }
+
+    /// Adjusted Fisher-Pearson skewness (what spreadsheets' `SKEW` and pandas report).
+    fn skew(&self) -> Option<f64> {
+        if self.n < 3.0 || self.m2 == 0.0 {
+            return None;
+        }
+        let n = self.n;
+        let g1 = (self.m3 / n) / (self.m2 / n).powf(1.5);
+        Some(g1 * (n * (n - 1.0)).sqrt() / (n - 2.0))
+    }
+}
+
+/// Running co-moment of two columns over rows where both are numbers.
+#[derive(Debug, Clone, Copy, Default)]
+struct CoMoments {
+    n: f64,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    c: f64,
+}
+
+impl CoMoments {
+    fn add(&mut self, x: f64, y: f64) {
+        self.n += 1.0;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / self.n;
+        self.mean_y += dy / self.n;
+        self.c += dx * (y - self.mean_y);
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+    }
+
+    fn pearson(&self) -> Option<f64> {
+        (self.n >= 2.0 && self.m2_x > 0.0 && self.m2_y > 0.0)
+            .then(|| (self.c / (self.m2_x * self.m2_y).sqrt()).clamp(-1.0, 1.0))
+    }
+}
+
+/// Quantile `p` of sorted `values` with linear interpolation between closest ranks.
+fn quantile(sorted: &[f64], p: f64) -> f64 {
+    let pos = (sorted.len() - 1) as f64 * p;
+    let (low, high) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (pos - low as f64)
+}
+
+/// 1-based ranks, ties sharing their average rank.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end) as f64 / 2.0 + 1.0;
+        for &i in &order[start..=end] {
+            ranks[i] = rank;
+        }
+        start = end + 1;
+    }
+    ranks
+}
+
+/// CSV text or a file under the tool root; files are re-opened for tasks that need two passes.
+enum Source {
+    Text(String),
+    File(PathBuf),
+}
+
+impl Source {
+    fn reader(&self, format: CsvFormat) -> Result<csv::Reader<Box<dyn Read + '_>>, KowalskiError> {
+        let input: Box<dyn Read + '_> = match self {
+            Source::Text(text) => Box::new(text.as_bytes()),
+            Source::File(path) => Box::new(std::io::BufReader::new(std::fs::File::open(path)?)),
+        };
+        Ok(format.reader(input))
+    }
+
+    /// Column names and an iterator over the records.
+    fn rows(
+        &self,
+        format: CsvFormat,
+    ) -> Result<
+        (
+            Vec<String>,
+            impl Iterator<Item = Result<csv::StringRecord, KowalskiError>> + '_,
+        ),
+        KowalskiError,
+    > {
+        let mut reader = self.reader(format)?;
+        let columns = format.columns(&mut reader)?;
+        Ok((columns, reader.into_records().map(|r| r.map_err(invalid))))
+    }
+}
+
+fn column_index(columns: &[String], name: &str) -> Result<usize, KowalskiError> {
+    columns.iter().position(|c| c == name).ok_or_else(|| {
+        KowalskiError::ToolInvalidInput(format!(
+            "Unknown column '{name}' (columns: {})",
+            columns.join(", ")
+        ))
+    })
+}
+
+fn not_numeric(name: &str) -> KowalskiError {
+    KowalskiError::ToolInvalidInput(format!("Column '{name}' is not numeric"))
+}
+
+#[derive(Default)]
+struct ColumnStats {
+    nulls: usize,
+    text: usize,
+    moments: Moments,
+    values: Vec<f64>,
+}
+
+impl ColumnStats {
+    fn add(&mut self, raw: &str) {
+        match cell(raw) {
+            Cell::Null => self.nulls += 1,
+            Cell::Text => self.text += 1,
+            Cell::Number(x) => {
+                self.moments.add(x);
+                self.values.push(x);
+            }
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        self.text == 0 && !self.values.is_empty()
+    }
+}
+
+/// Values of one numeric column, kept in memory for its quartiles.
+fn read_column(
+    source: &Source,
+    format: CsvFormat,
+    name: &str,
+) -> Result<ColumnStats, KowalskiError> {
+    let (columns, records) = source.rows(format)?;
+    let index = column_index(&columns, name)?;
+    let mut stats = ColumnStats::default();
+    for record in records {
+        stats.add(record?.get(index).unwrap_or_default());
+    }
+    if !stats.is_numeric() {
+        return Err(not_numeric(name));
+    }
+    Ok(stats)
+}
+
+fn describe(source: &Source, format: CsvFormat) -> Result<Value, KowalskiError> {
+    let (names, records) = source.rows(format)?;
+    let mut stats: Vec<ColumnStats> = names.iter().map(|_| ColumnStats::default()).collect();
+    let mut rows = 0usize;
+    for record in records {
+        let record = record?;
+        rows += 1;
+        for (i, column) in stats.iter_mut().enumerate() {
+            column.add(record.get(i).unwrap_or_default());
+        }
+    }
+    let columns: Vec<Value> = names
+        .iter()
+        .zip(stats)
+        .map(|(name, mut s)| {
+            let count = rows - s.nulls;
+            if !s.is_numeric() {
+                return json!({"column": name, "numeric": false, "count": count, "nulls": s.nulls});
+            }
+            s.values.sort_by(f64::total_cmp);
+            let v = &s.values;
+            json!({
+                "column": name,
+                "numeric": true,
+                "count": count,
+                "nulls": s.nulls,
+                "mean": s.moments.mean,
+                "std": s.moments.std(),
+                "min": v[0],
+                "q1": quantile(v, 0.25),
+                "median": quantile(v, 0.5),
+                "q3": quantile(v, 0.75),
+                "max": v[v.len() - 1],
+                "skew": s.moments.skew(),
+            })
+        })
+        .collect();
+    Ok(json!({"rows": rows, "columns": columns}))
+}
+
+fn correlation(
+    source: &Source,
+    format: CsvFormat,
+    method: &str,
+    requested: &[String],
+) -> Result<Value, KowalskiError> {
+    let spearman = match method {
+        "pearson" => false,
+        "spearman" => true,
+        other => {
+            return Err(KowalskiError::ToolInvalidInput(format!(
+                "Unknown correlation method '{other}' (expected pearson or spearman)"
+            )));
+        }
+    };
+    let (names, records) = source.rows(format)?;
+    let width = names.len();
+    // Per column: whether it held any number, and any text.
+    let mut numbers = vec![false; width];
+    let mut text = vec![false; width];
+    // Pearson is accumulated pair by pair while reading; Spearman ranks whole columns, so the
+    // cells are kept (`None` for missing) until the end.
+    let mut pairs = vec![CoMoments::default(); width * width];
+    let mut kept: Vec<Vec<Option<f64>>> = vec![Vec::new(); width];
+    for record in records {
+        let record = record?;
+        let cells: Vec<Option<f64>> = (0..width)
+            .map(|i| match cell(record.get(i).unwrap_or_default()) {
+                Cell::Number(x) => {
+                    numbers[i] = true;
+                    Some(x)
+                }
+                Cell::Null => None,
+                Cell::Text => {
+                    text[i] = true;
+                    None
+                }
+            })
+            .collect();
+        if spearman {
+            for (column, value) in kept.iter_mut().zip(&cells) {
+                column.push(*value);
+            }
+            continue;
+        }
+        for (i, x) in cells.iter().enumerate() {
+            for (j, y) in cells.iter().enumerate().skip(i) {
+                if let (Some(x), Some(y)) = (x, y) {
+                    pairs[i * width + j].add(*x, *y);
+                }
+            }
+        }
+    }
+    let is_numeric = |i: usize| numbers[i] && !text[i];
+    let selected: Vec<usize> = if requested.is_empty() {
+        (0..names.len()).filter(|&i| is_numeric(i)).collect()
+    } else {
+        requested
+            .iter()
+            .map(|name| {
+                let i = column_index(&names, name)?;
+                if is_numeric(i) {
+                    Ok(i)
+                } else {
+                    Err(not_numeric(name))
+                }
+            })
+            .collect::<Result<_, _>>()?
+    };
+    let coefficient = |i: usize, j: usize| -> Option<f64> {
+        let (i, j) = (i.min(j), i.max(j));
+        if !spearman {
+            return pairs[i * width + j].pearson();
+        }
+        let (xs, ys): (Vec<f64>, Vec<f64>) = kept[i]
+            .iter()
+            .zip(&kept[j])
+            .filter_map(|(x, y)| Some(((*x)?, (*y)?)))
+            .unzip();
+        let mut ranked = CoMoments::default();
+        for (x, y) in ranks(&xs).into_iter().zip(ranks(&ys)) {
+            ranked.add(x, y);
+        }
+        ranked.pearson()
+    };
+    let matrix: Vec<Vec<Option<f64>>> = selected
+        .iter()
+        .map(|&i| selected.iter().map(|&j| coefficient(i, j)).collect())
+        .collect();
+    let columns: Vec<&String> = selected.iter().map(|&i| &names[i]).collect();
+    Ok(json!({"method": method, "columns": columns, "matrix": matrix}))
+}
+
+fn histogram(
+    source: &Source,
+    format: CsvFormat,
+    column: &str,
+    bins: usize,
+) -> Result<Value, KowalskiError> {
+    // Two streaming passes: the range, then the counts.
+    let (columns, records) = source.rows(format)?;
+    let index = column_index(&columns, column)?;
+    let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+    let (mut count, mut nulls) = (0usize, 0usize);
+    for record in records {
+        match cell(record?.get(index).unwrap_or_default()) {
+            Cell::Number(x) => {
+                count += 1;
+                min = min.min(x);
+                max = max.max(x);
+            }
+            Cell::Null => nulls += 1,
+            Cell::Text => return Err(not_numeric(column)),
+        }
+    }
+    if count == 0 {
+        return Err(not_numeric(column));
+    }
+    let bins = if min == max {
+        1
+    } else {
+        bins.clamp(1, MAX_BINS)
+    };
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0usize; bins];
+    for record in source.rows(format)?.1 {
+        if let Cell::Number(x) = cell(record?.get(index).unwrap_or_default()) {
+            let bin = if width == 0.0 {
+                0
+            } else {
+                (((x - min) / width) as usize).min(bins - 1)
+            };
+            counts[bin] += 1;
+        }
+    }
+    let bins: Vec<Value> = counts
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let start = min + width * i as f64;
+            let end = if i + 1 == bins { max } else { start + width };
+            json!({"start": start, "end": end, "count": count})
+        })
+        .collect();
+    Ok(json!({
+        "column": column,
+        "count": count,
+        "nulls": nulls,
+        "bins": bins,
+    }))
+}
+
+fn outliers(
+    source: &Source,
+    format: CsvFormat,
+    column: &str,
+    method: &str,
+    threshold: Option<f64>,
+    limit: usize,
+) -> Result<Value, KowalskiError> {
+    let mut stats = read_column(source, format, column)?;
+    let (lower, upper, threshold) = match method {
+        "iqr" => {
+            let k = threshold.unwrap_or(1.5);
+            stats.values.sort_by(f64::total_cmp);
+            let (q1, q3) = (quantile(&stats.values, 0.25), quantile(&stats.values, 0.75));
+            (q1 - k * (q3 - q1), q3 + k * (q3 - q1), k)
+        }
+        "zscore" => {
+            let z = threshold.unwrap_or(3.0);
+            let std = stats.moments.std().unwrap_or(0.0);
+            (
+                stats.moments.mean - z * std,
+                stats.moments.mean + z * std,
+                z,
+            )
+        }
+        other => {
+            return Err(KowalskiError::ToolInvalidInput(format!(
+                "Unknown outlier method '{other}' (expected iqr or zscore)"
+            )));
+        }
+    };
+    // Second pass: the offending rows, with all their cells.
+    let (names, records) = source.rows(format)?;
+    let index = column_index(&names, column)?;
+    let mut count = 0usize;
+    let mut rows = Vec::new();
+    for (row, record) in records.enumerate() {
+        let record = record?;
+        let Cell::Number(x) = cell(record.get(index).unwrap_or_default()) else {
+            continue;
+        };
+        if x >= lower && x <= upper {
+            continue;
+        }
+        count += 1;
+        if rows.len() < limit {
+            let values: Map<String, Value> = names
+                .iter()
+                .zip(record.iter())
+                .map(|(name, cell)| (name.clone(), json!(cell)))
+                .collect();
+            rows.push(json!({"row": row + 1, "value": x, "values": values}));
+        }
+    }
+    Ok(json!({
+        "column": column,
+        "method": method,
+        "threshold": threshold,
+        "lower": lower,
+        "upper": upper,
+        "count": count,
+        "rows": rows,
+        "truncated": count > limit,
+    }))
+}
+
+fn run(input: &ToolInput, source: &Source, format: CsvFormat) -> Result<Value, KowalskiError> {
+    let params = &input.parameters;
+    let str_param = |name: &str| params.get(name).and_then(|v| v.as_str());
+    let count_param = |name: &str, default: usize| {
+        params
+            .get(name)
+            .and_then(|v| v.as_u64())
+            .map_or(default, |n| n as usize)
+    };
+    let column = || {
+        str_param("column").ok_or_else(|| {
+            KowalskiError::ToolInvalidInput(format!("{} needs a 'column'", input.task_type))
+        })
+    };
+    match input.task_type.as_str() {
+        "describe" | "default" => describe(source, format),
+        "correlation" => {
+            let columns: Vec<String> = params
+                .get("columns")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|c| c.as_str().map(str::to_string))
+                .collect();
+            let method = str_param("method").unwrap_or("pearson");
+            correlation(source, format, method, &columns)
+        }
+        "histogram" => histogram(source, format, column()?, count_param("bins", DEFAULT_BINS)),
+        "outliers" => outliers(
+            source,
+            format,
+            column()?,
+            str_param("method").unwrap_or("iqr"),
+            params.get("threshold").and_then(|v| v.as_f64()),
+            count_param("limit", DEFAULT_OUTLIER_ROWS),
+        ),
+        other => Err(KowalskiError::ToolInvalidInput(format!(
+            "Unknown stats task '{other}' (expected describe, correlation, histogram or outliers)"
+        ))),
+    }
+}
+
+/// Statistics over CSV data beyond [`CsvTool`](crate::tools::CsvTool)'s summary: `describe`,
+/// `correlation`, `histogram` and `outliers`. Data comes from `content` or from a `path` under
+/// the tool root. Missing cells (empty, `NA`, `NaN`, `null`, ...) are skipped and counted.
+#[derive(Debug, Clone)]
+pub struct StatsTool {
+    root: PathBuf,
+}
+
+impl Default for StatsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatsTool {
+    /// Files are read under the working directory.
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("."),
+        }
+    }
+
+    /// Reads `path` relative to `root`; paths that resolve outside it are refused.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    fn source(&self, input: &ToolInput) -> Result<Source, KowalskiError> {
+        let param = |name: &str| {
+            input
+                .parameters
+                .get(name)
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.trim().is_empty())
+        };
+        match (param("path"), param("content")) {
+            (Some(path), _) => Ok(Source::File(
+                resolve_within(&self.root, path, self.name())?.1,
+            )),
+            (None, Some(content)) => Ok(Source::Text(content.to_string())),
+            (None, None) => Err(KowalskiError::ToolInvalidInput(
+                "stats needs CSV text in 'content' or a file in 'path'".to_string(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for StatsTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let source = self.source(&input)?;
+        let format = CsvFormat::from_input(&input)?;
+        let origin = match &source {
+            Source::File(path) => path.display().to_string(),
+            Source::Text(_) => self.name().to_string(),
+        };
+        let result = tokio::task::spawn_blocking(move || run(&input, &source, format))
+            .await
+            .map_err(|e| KowalskiError::tool_failed("stats", e))??;
+        Ok(ToolOutput::new(result, None).with_source(origin))
+    }
+
+    fn name(&self) -> &str {
+        "stats"
+    }
+
+    fn description(&self) -> &str {
+        "Statistics over CSV data (text in content, or a file in path). Tasks: describe (count, nulls, mean, std, min, quartiles, max, skew per column), correlation (Pearson or Spearman matrix of numeric columns), histogram (binned counts of a column), outliers (rows outside the IQR fences or a z-score, capped at limit). Empty, NA and NaN cells count as missing."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        let param =
+            |name: &str, description: &str, parameter_type, default: Option<&str>| ToolParameter {
+                name: name.to_string(),
+                description: description.to_string(),
+                required: false,
+                default_value: default.map(str::to_string),
+                parameter_type,
+            };
+        vec![
+            ToolParameter {
+                required: true,
+                ..param(
+                    "task",
+                    "describe, correlation, histogram or outliers",
+                    ParameterType::String,
+                    None,
+                )
+            },
+            param("content", "CSV text", ParameterType::String, None),
+            param(
+                "path",
+                "CSV file relative to the tool root (instead of content)",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "delimiter",
+                "Field separator, one character (or \\t / tab)",
+                ParameterType::String,
+                Some(","),
+            ),
+            param(
+                "has_headers",
+                "Whether the first row names the columns; if false they are column_0, column_1, ...",
+                ParameterType::Boolean,
+                Some("true"),
+            ),
+            param(
+                "column",
+                "Column to bin (histogram) or check (outliers)",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "columns",
+                "Columns to correlate (default: all numeric columns)",
+                ParameterType::Array,
+                None,
+            ),
+            param(
+                "method",
+                "correlation: pearson or spearman; outliers: iqr or zscore",
+                ParameterType::String,
+                None,
+            ),
+            param(
+                "bins",
+                "Number of equal-width bins (histogram)",
+                ParameterType::Number,
+                Some("10"),
+            ),
+            param(
+                "threshold",
+                "outliers: IQR multiplier (default 1.5) or z-score (default 3)",
+                ParameterType::Number,
+                None,
+            ),
+            param(
+                "limit",
+                "Most offending rows to return (outliers)",
+                ParameterType::Number,
+                Some("20"),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str =
+        "id,x,y,label\n1,1,2,a\n2,2,4,b\n3,3,NaN,c\n4,4,5,d\n5,100,,e\n6,NA,4,f\n7,5,5,g\n";
+
+    async fn run(params: Value) -> Result<Value, KowalskiError> {
+        Ok(StatsTool::new()
+            .execute(ToolInput::from_parameters(params))
+            .await?
+            .result)
+    }
+
+    fn close(actual: &Value, expected: f64) -> bool {
+        (actual.as_f64().unwrap() - expected).abs() < 1e-9
+    }
+
+    #[tokio::test]
+    async fn describe_skips_missing_cells_and_matches_hand_computed_values() {
+        let out = run(json!({"task": "describe", "content": DATA}))
+            .await
+            .unwrap();
+        assert_eq!(out["rows"], 7);
+        let x = &out["columns"][1];
+        // x = 1, 2, 3, 4, 100, 5 (NA skipped)
+        assert_eq!(x["count"], 6);
+        assert_eq!(x["nulls"], 1);
+        assert!(close(&x["mean"], 115.0 / 6.0));
+        assert!(close(&x["median"], 3.5));
+        assert!(close(&x["q1"], 2.25));
+        assert!(close(&x["q3"], 4.75));
+        assert_eq!(x["min"], 1.0);
+        assert_eq!(x["max"], 100.0);
+        // Squared deviations sum to 7850.83.., over n - 1 = 5.
+        assert!(close(&x["std"], (9421.0 / 6.0f64).sqrt()));
+
+        let y = &out["columns"][2];
+        assert_eq!(y["count"], 5);
+        assert_eq!(y["nulls"], 2);
+        assert!(close(&y["mean"], 4.0));
+        assert_eq!(
+            out["columns"][3],
+            json!({"column": "label", "numeric": false, "count": 7, "nulls": 0})
+        );
+
+        let skewed = run(json!({"task": "describe", "content": "v\n1\n2\n3\n4\n100\n"}))
+            .await
+            .unwrap();
+        let v = &skewed["columns"][0];
+        assert!((v["skew"].as_f64().unwrap() - 2.232395911636458).abs() < 1e-9);
+        assert!((v["std"].as_f64().unwrap() - 43.617656975128774).abs() < 1e-9);
+        let flat = run(json!({"content": "v\n2\n2\n2\n"})).await.unwrap();
+        assert_eq!(flat["columns"][0]["skew"], Value::Null);
+        assert_eq!(flat["columns"][0]["std"], 0.0);
+    }
+
+    #[tokio::test]
+    async fn correlation_uses_pairwise_complete_rows() {
+        let content = "a,b,c\n1,2,5\n2,4,4\n3,5,3\n4,4,2\n5,5,1\n6,,0\n";
+        let out = run(json!({"task": "correlation", "content": content}))
+            .await
+            .unwrap();
+        assert_eq!(out["columns"], json!(["a", "b", "c"]));
+        let m = &out["matrix"];
+        assert!(close(&m[0][0], 1.0));
+        assert!(close(&m[0][1], 0.7745966692414834));
+        assert!(close(&m[1][0], 0.7745966692414834));
+        assert!(close(&m[0][2], -1.0));
+
+        let out = run(json!({"task": "correlation", "content": content, "method": "spearman", "columns": ["a", "b"]}))
+            .await
+            .unwrap();
+        // Ranks of b: 1, 2.5, 4.5, 2.5, 4.5
+        assert!(close(&out["matrix"][0][1], 0.7378647873726218));
+
+        let text = run(json!({"task": "correlation", "content": DATA, "columns": ["label"]})).await;
+        assert!(
+            text.unwrap_err()
+                .to_string()
+                .contains("'label' is not numeric")
+        );
+    }
+
+    #[tokio::test]
+    async fn histogram_and_outliers() {
+        let out = run(json!({"task": "histogram", "content": DATA, "column": "x", "bins": 4}))
+            .await
+            .unwrap();
+        assert_eq!(out["count"], 6);
+        assert_eq!(out["nulls"], 1);
+        let counts: Vec<u64> = out["bins"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b["count"].as_u64().unwrap())
+            .collect();
+        assert_eq!(counts, [5, 0, 0, 1]);
+        assert_eq!(out["bins"][0]["start"], 1.0);
+        assert_eq!(out["bins"][0]["end"], 25.75);
+        assert_eq!(out["bins"][3]["end"], 100.0);
+
+        let out = run(json!({"task": "outliers", "content": DATA, "column": "x"}))
+            .await
+            .unwrap();
+        // Fences 2.25 - 1.5 * 2.5 and 4.75 + 1.5 * 2.5.
+        assert_eq!(out["lower"], -1.5);
+        assert_eq!(out["upper"], 8.5);
+        assert_eq!(out["count"], 1);
+        assert_eq!(out["rows"][0]["row"], 5);
+        assert_eq!(out["rows"][0]["values"]["label"], "e");
+
+        let out = run(json!({"task": "outliers", "content": DATA, "column": "x", "method": "zscore", "threshold": 0.1, "limit": 2}))
+            .await
+            .unwrap();
+        assert_eq!(out["count"], 6);
+        assert_eq!(out["rows"].as_array().unwrap().len(), 2);
+        assert_eq!(out["truncated"], true);
+    }
+}
@@ -102,10 +102,44 @@ pub fn extract_tool_calls(input: &str) -> Vec<ToolCall> {
     results
 }
 
+/// Fields a JSON-mode reply may use for a final (non-tool) answer.
+const JSON_ANSWER_KEYS: [&str; 4] = ["answer", "response", "content", "text"];
+
+/// Unwraps the text of a JSON-mode final answer such as `{"answer": "..."}`.
+/// Returns `None` for tool calls and for replies that are not a JSON object with a string answer field.
+pub fn json_mode_answer(s: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(s.trim()).ok()?;
+    let obj = value.as_object()?;
+    if obj.contains_key("name") {
+        return None;
+    }
+    JSON_ANSWER_KEYS
+        .iter()
+        .find_map(|k| obj.get(*k).and_then(|v| v.as_str()))
+        .map(str::to_string)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn json_mode_answer_unwraps_final_answers_only() {
+        assert_eq!(
+            json_mode_answer(r#" {"answer": "42"} "#).as_deref(),
+            Some("42")
+        );
+        assert_eq!(
+            json_mode_answer(r#"{"response": "hi"}"#).as_deref(),
+            Some("hi")
+        );
+        assert_eq!(
+            json_mode_answer(r#"{"name": "fs_tool", "parameters": {}}"#),
+            None
+        );
+        assert_eq!(json_mode_answer("plain prose"), None);
+    }
+
     #[test]
     fn test_extract_tool_call() {
         let input = "Here is a call: {\"name\": \"fs_tool\", \"parameters\": {\"task\": \"list_dir\", \"path\": \"/\"}}";
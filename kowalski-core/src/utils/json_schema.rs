@@ -0,0 +1,207 @@
+//! A small JSON Schema checker for structured replies (see
+//! [`Agent::chat_structured`](crate::agent::Agent::chat_structured)). It covers the keywords
+//! extraction schemas use: `type`, `properties`, `required`, `additionalProperties: false`,
+//! `items`, `enum`, `const`, `minimum`/`maximum`, `minLength`/`maxLength` and
+//! `minItems`/`maxItems`. Other keywords are ignored.
+
+use serde_json::Value;
+
+/// Every way `value` breaks `schema`, as `"<path>: <problem>"` with `$` for the root
+/// (e.g. `$.contacts[0].email: missing required property`). Empty when it conforms.
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(value, schema, "$", &mut errors);
+    errors
+}
+
+fn check(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let names: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !names.is_empty() && !names.iter().any(|name| has_type(value, name)) {
+            errors.push(format!(
+                "{path}: expected {}, got {}",
+                names.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array)
+        && !options.contains(value)
+    {
+        errors.push(format!(
+            "{path}: {value} is not one of {}",
+            Value::from(options.clone())
+        ));
+    }
+    if let Some(constant) = schema.get("const")
+        && constant != value
+    {
+        errors.push(format!("{path}: expected {constant}, got {value}"));
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for name in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !object.contains_key(name) {
+                    errors.push(format!("{path}.{name}: missing required property"));
+                }
+            }
+            for (name, field) in object {
+                match properties.and_then(|p| p.get(name)) {
+                    Some(field_schema) => {
+                        check(field, field_schema, &format!("{path}.{name}"), errors)
+                    }
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        errors.push(format!("{path}.{name}: unexpected property"))
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            bound(
+                schema,
+                "minItems",
+                "maxItems",
+                items.len(),
+                "items",
+                path,
+                errors,
+            );
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item, item_schema, &format!("{path}[{i}]"), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count();
+            bound(
+                schema,
+                "minLength",
+                "maxLength",
+                len,
+                "characters",
+                path,
+                errors,
+            );
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+                && n < min
+            {
+                errors.push(format!("{path}: {n} is below the minimum {min}"));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+                && n > max
+            {
+                errors.push(format!("{path}: {n} is above the maximum {max}"));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn bound(
+    schema: &serde_json::Map<String, Value>,
+    min_key: &str,
+    max_key: &str,
+    len: usize,
+    unit: &str,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    if let Some(min) = schema.get(min_key).and_then(Value::as_u64)
+        && (len as u64) < min
+    {
+        errors.push(format!("{path}: {len} {unit}, at least {min} required"));
+    }
+    if let Some(max) = schema.get(max_key).and_then(Value::as_u64)
+        && (len as u64) > max
+    {
+        errors.push(format!("{path}: {len} {unit}, at most {max} allowed"));
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.as_i64().is_some() || value.as_u64().is_some(),
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate;
+    use serde_json::json;
+
+    fn contact_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "email": {"type": "string"},
+                "age": {"type": "integer", "minimum": 0},
+                "tags": {"type": "array", "items": {"enum": ["lead", "customer"]}}
+            },
+            "required": ["name", "email"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn conforming_value_has_no_errors() {
+        let value = json!({"name": "Ada", "email": "ada@example.com", "age": 36, "tags": ["lead"]});
+        assert!(validate(&value, &contact_schema()).is_empty());
+    }
+
+    #[test]
+    fn every_violation_is_reported_with_its_path() {
+        let value = json!({"name": "", "age": 1.5, "tags": ["vip"], "phone": "123"});
+        let mut errors = validate(&value, &contact_schema());
+        errors.sort();
+        assert_eq!(
+            errors,
+            [
+                "$.age: expected integer, got number",
+                "$.email: missing required property",
+                "$.name: 0 characters, at least 1 required",
+                "$.phone: unexpected property",
+                "$.tags[0]: \"vip\" is not one of [\"lead\",\"customer\"]",
+            ]
+        );
+        assert_eq!(
+            validate(&json!([1]), &contact_schema()),
+            ["$: expected object, got array"]
+        );
+    }
+}
@@ -1 +1,4 @@
 pub mod json;
+pub mod json_schema;
+pub mod ndjson;
+pub mod redact;
@@ -0,0 +1,79 @@
+/// Reassembles newline-delimited JSON (Ollama `/api/chat` streams) from arbitrarily split byte
+/// chunks: a chunk may end mid-object, mid-UTF-8 sequence, or carry several objects.
+#[derive(Debug, Clone, Default)]
+pub struct NdjsonBuffer {
+    pending: Vec<u8>,
+}
+
+impl NdjsonBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `chunk` and returns every line it completed (trimmed, blank lines skipped). Only
+    /// the new bytes are scanned, and consumed lines are removed in one step.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        let scanned = self.pending.len();
+        self.pending.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for (offset, _) in chunk.iter().enumerate().filter(|&(_, &b)| b == b'\n') {
+            let end = scanned + offset;
+            let line = String::from_utf8_lossy(&self.pending[start..end])
+                .trim()
+                .to_string();
+            if !line.is_empty() {
+                lines.push(line);
+            }
+            start = end + 1;
+        }
+        self.pending.drain(..start);
+        lines
+    }
+
+    /// Takes the unterminated remainder when it is already a complete JSON value (the last
+    /// object of a stream often arrives without a trailing newline).
+    pub fn take_complete(&mut self) -> Option<String> {
+        let text = std::str::from_utf8(&self.pending).ok()?.trim();
+        if text.is_empty() || serde_json::from_str::<serde::de::IgnoredAny>(text).is_err() {
+            return None;
+        }
+        let line = text.to_string();
+        self.pending.clear();
+        Some(line)
+    }
+
+    /// True when no partial line is waiting for more bytes.
+    pub fn is_empty(&self) -> bool {
+        self.pending.iter().all(u8::is_ascii_whitespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassembles_split_and_batched_lines() {
+        let mut buf = NdjsonBuffer::new();
+        assert!(buf.push(br#"{"a":1}"#).is_empty());
+        assert_eq!(buf.push(b"\n{\"b\":"), vec![r#"{"a":1}"#]);
+        assert!(buf.take_complete().is_none());
+        assert_eq!(
+            buf.push(b"2}\n\n{\"c\":3}\n{\"d\""),
+            vec![r#"{"b":2}"#, r#"{"c":3}"#]
+        );
+        assert!(!buf.is_empty());
+        buf.push(b":4}");
+        assert_eq!(buf.take_complete().as_deref(), Some(r#"{"d":4}"#));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn keeps_multibyte_characters_split_across_chunks() {
+        let line = "{\"content\":\"zażółć\"}\n".as_bytes();
+        let mut buf = NdjsonBuffer::new();
+        assert!(buf.push(&line[..15]).is_empty());
+        assert_eq!(buf.push(&line[15..]), vec!["{\"content\":\"zażółć\"}"]);
+    }
+}
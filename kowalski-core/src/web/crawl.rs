@@ -0,0 +1,289 @@
+//! Following links from a start page: a breadth-first crawl of one site that fetches every page
+//! at most once, however often (or in whatever spelling) it is linked.
+
+use super::{PageFetcher, truncate_chars};
+use crate::error::KowalskiError;
+use crate::progress::{NoProgress, Progress, ProgressReporter};
+use crate::tools::HtmlToMarkdownTool;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use url::Url;
+
+/// Upper bound on [`CrawlOptions::max_depth`].
+pub const MAX_CRAWL_DEPTH: usize = 5;
+
+/// Default cap on [`CrawlOptions::max_pages`].
+pub const DEFAULT_MAX_CRAWL_PAGES: usize = 20;
+
+static HREF: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?is)<a\s[^>]*?href\s*=\s*["']([^"']+)["']"#).expect("HREF regex"));
+
+/// Limits for [`crawl`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrawlOptions {
+    /// Link hops from the start page (depth 0). Applies to the whole crawl: a page is read at
+    /// the shortest distance it is found at, and nothing beyond this depth is fetched.
+    pub max_depth: usize,
+    /// Pages fetched in total, the start page included.
+    pub max_pages: usize,
+    /// Markdown kept per page, in characters.
+    pub max_chars: usize,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 1,
+            max_pages: DEFAULT_MAX_CRAWL_PAGES,
+            max_chars: super::DEFAULT_MAX_SOURCE_CHARS,
+        }
+    }
+}
+
+/// One page read by [`crawl`], with its distance from the start page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawledPage {
+    pub url: String,
+    pub depth: usize,
+    pub markdown: String,
+}
+
+/// Reads `start` and the same-host pages it links to, breadth first, up to
+/// [`max_depth`](CrawlOptions::max_depth) hops (at most [`MAX_CRAWL_DEPTH`]). URLs are compared
+/// in [normalized](normalize_url) form, so cycles and `#fragment` or trailing-slash variants
+/// never cause a second fetch. Returns the pages in crawl order; a linked page that cannot be
+/// fetched is skipped, while a failing start page is an error.
+pub async fn crawl(
+    fetcher: &dyn PageFetcher,
+    start: &str,
+    options: &CrawlOptions,
+) -> Result<Vec<CrawledPage>, KowalskiError> {
+    crawl_with_progress(fetcher, start, options, &NoProgress).await
+}
+
+/// [`crawl`], reporting each page read or skipped. The total counts the pages fetched plus those
+/// queued (within [`max_pages`](CrawlOptions::max_pages)), so it grows as links are found.
+pub async fn crawl_with_progress(
+    fetcher: &dyn PageFetcher,
+    start: &str,
+    options: &CrawlOptions,
+    progress: &dyn ProgressReporter,
+) -> Result<Vec<CrawledPage>, KowalskiError> {
+    let start = Url::parse(start)?;
+    let max_depth = options.max_depth.min(MAX_CRAWL_DEPTH);
+    let mut visited = HashSet::from([normalize_url(&start)]);
+    let mut queue = VecDeque::from([(start.clone(), 0)]);
+    let mut pages = Vec::new();
+    let mut state = Progress::default();
+
+    while let Some((url, depth)) = queue.pop_front() {
+        if pages.len() >= options.max_pages {
+            break;
+        }
+        state.current = Some(url.to_string());
+        let fetched = fetcher.fetch(url.as_str()).await;
+        state.done += 1;
+        let html = match fetched {
+            Ok(html) => html,
+            Err(e) if depth == 0 => return Err(e),
+            Err(e) => {
+                warn!("crawl: could not read {url}: {e}");
+                state.total = Some(state.done + queue.len().min(options.max_pages - pages.len()));
+                progress.report(&state);
+                continue;
+            }
+        };
+        if depth < max_depth {
+            for link in links(&url, &html) {
+                if link.host_str() == start.host_str() && visited.insert(normalize_url(&link)) {
+                    queue.push_back((link, depth + 1));
+                }
+            }
+        }
+        debug!("crawl: read {url} at depth {depth}");
+        let markdown = HtmlToMarkdownTool::convert(&html, true);
+        pages.push(CrawledPage {
+            url: url.to_string(),
+            depth,
+            markdown: truncate_chars(&markdown, options.max_chars),
+        });
+        state.bytes += html.len() as u64;
+        state.total = Some(state.done + queue.len().min(options.max_pages - pages.len()));
+        progress.report(&state);
+    }
+    Ok(pages)
+}
+
+/// The form URLs are deduplicated in: no fragment and no trailing slash on the path (the `url`
+/// crate already lowercases scheme and host and drops default ports).
+pub fn normalize_url(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    let path = url.path().trim_end_matches('/').to_string();
+    url.set_path(&path);
+    url.to_string().trim_end_matches('/').to_string()
+}
+
+/// `http`/`https` targets of the `<a href>` links in `html`, resolved against `base`.
+fn links(base: &Url, html: &str) -> Vec<Url> {
+    HREF.captures_iter(html)
+        .filter_map(|c| base.join(&c[1].replace("&amp;", "&")).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// `/` → `/a` → `/b` → `/c` → `/d`, with links back to `/` and to `/a` in other spellings.
+    #[derive(Default)]
+    struct CyclicSite {
+        fetched: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl PageFetcher for CyclicSite {
+        async fn fetch(&self, url: &str) -> Result<String, KowalskiError> {
+            self.fetched.lock().unwrap().push(url.to_string());
+            let links: &[&str] = match Url::parse(url)?.path() {
+                "/" => &[
+                    "/a",
+                    "/a/#intro",
+                    "HTTPS://Example.com/",
+                    "https://other.org/",
+                ],
+                "/a" => &["b", "/", "mailto:team@example.com"],
+                "/b" => &["/c", "/a#top", "/missing"],
+                "/c" => &["/d", "/b/"],
+                "/d" => &["/"],
+                _ => return Err(KowalskiError::Network("404".to_string())),
+            };
+            let anchors: String = links
+                .iter()
+                .map(|href| format!(r#"<a href="{href}">link</a>"#))
+                .collect();
+            Ok(format!("<h1>Page {url}</h1><p>{anchors}</p>"))
+        }
+    }
+
+    fn options(max_depth: usize) -> CrawlOptions {
+        CrawlOptions {
+            max_depth,
+            ..CrawlOptions::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn crawl_terminates_on_cycles_without_refetching() {
+        let site = CyclicSite::default();
+        let pages = crawl(&site, "https://example.com/", &options(MAX_CRAWL_DEPTH))
+            .await
+            .unwrap();
+
+        let fetched = site.fetched.lock().unwrap().clone();
+        let mut unique: HashMap<&str, usize> = HashMap::new();
+        for url in &fetched {
+            *unique.entry(url).or_default() += 1;
+        }
+        assert!(unique.values().all(|&n| n == 1), "{fetched:?}");
+        let depths: Vec<(&str, usize)> = pages.iter().map(|p| (p.url.as_str(), p.depth)).collect();
+        assert_eq!(
+            depths,
+            [
+                ("https://example.com/", 0),
+                ("https://example.com/a", 1),
+                ("https://example.com/b", 2),
+                ("https://example.com/c", 3),
+                ("https://example.com/d", 4),
+            ]
+        );
+        // The broken link was tried once and skipped; other hosts were never fetched.
+        assert_eq!(fetched.len(), 6);
+        assert!(!fetched.iter().any(|u| u.contains("other.org")));
+        assert!(pages[1].markdown.contains("Page https://example.com/a"));
+    }
+
+    #[tokio::test]
+    async fn crawl_depth_is_global_and_pages_are_capped() {
+        let site = CyclicSite::default();
+        let pages = crawl(&site, "https://example.com/", &options(2))
+            .await
+            .unwrap();
+        assert_eq!(pages.len(), 3);
+        assert!(pages.iter().all(|p| p.depth <= 2));
+        // `/c` is three hops away and is not even fetched.
+        assert_eq!(site.fetched.lock().unwrap().len(), 3);
+
+        let limited = CrawlOptions {
+            max_pages: 2,
+            ..options(MAX_CRAWL_DEPTH)
+        };
+        let pages = crawl(&CyclicSite::default(), "https://example.com/", &limited)
+            .await
+            .unwrap();
+        assert_eq!(pages.len(), 2);
+
+        let only_start = crawl(&CyclicSite::default(), "https://example.com/", &options(0))
+            .await
+            .unwrap();
+        assert_eq!(only_start.len(), 1);
+        assert!(
+            crawl(
+                &CyclicSite::default(),
+                "https://example.com/missing",
+                &options(1)
+            )
+            .await
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn crawl_reports_progress_per_page() {
+        let updates = Mutex::new(Vec::new());
+        let reporter = |p: &Progress| updates.lock().unwrap().push(p.clone());
+        let pages = crawl_with_progress(
+            &CyclicSite::default(),
+            "https://example.com/",
+            &options(MAX_CRAWL_DEPTH),
+            &reporter,
+        )
+        .await
+        .unwrap();
+
+        let updates = updates.into_inner().unwrap();
+        // One update per fetch, the broken link included.
+        assert_eq!(updates.len(), 6);
+        assert_eq!(
+            updates.iter().map(|p| p.done).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(updates[0].current.as_deref(), Some("https://example.com/"));
+        assert!(updates.windows(2).all(|w| w[0].bytes <= w[1].bytes));
+        let last = updates.last().unwrap();
+        assert_eq!(last.total, Some(6));
+        assert_eq!(pages.len(), 5);
+    }
+
+    #[test]
+    fn normalized_urls_ignore_fragments_slashes_and_case() {
+        let normalized = |s: &str| normalize_url(&Url::parse(s).unwrap());
+        assert_eq!(
+            normalized("HTTPS://Example.COM:443/a/#x"),
+            "https://example.com/a"
+        );
+        assert_eq!(normalized("https://example.com/"), "https://example.com");
+        assert_eq!(normalized("https://example.com"), "https://example.com");
+        assert_eq!(
+            normalized("https://example.com/a/?q=1#x"),
+            "https://example.com/a?q=1"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Keeps fetches of model-chosen URLs off the local network. Guarded clients resolve host names
+//! through [`PublicResolver`], which drops loopback, private, link-local and other non-public
+//! addresses, so a name that points inside the network fails like an unknown host. URLs and
+//! redirects that name such an address directly are refused before any request is sent.
+//! `[web] allow_private_addresses` turns the guard off, e.g. to scrape an intranet.
+
+use super::search::{DEFAULT_WEB_TIMEOUT, WEB_USER_AGENT};
+use crate::error::KowalskiError;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::{Client, ClientBuilder};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use url::{Host, Url};
+
+/// Redirects followed per request, as with reqwest's default policy.
+pub(crate) const MAX_REDIRECTS: usize = 10;
+
+/// Whether `ip` belongs to the public internet: not unspecified, loopback, private, link-local,
+/// carrier-grade NAT, benchmarking, documentation, multicast, broadcast, reserved or
+/// unique-local. IPv4-mapped and NAT64 IPv6 addresses are judged by their IPv4 address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => match embedded_v4(v6) {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(v6),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 198 && (b == 18 || b == 19))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let [first, second, ..] = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || (first == 0x2001 && second == 0x0db8))
+}
+
+/// The IPv4 address inside an IPv4-mapped (`::ffff:a.b.c.d`) or NAT64 (`64:ff9b::a.b.c.d`)
+/// address.
+fn embedded_v4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return Some(v4);
+    }
+    let octets = ip.octets();
+    (ip.segments()[..6] == [0x64, 0xff9b, 0, 0, 0, 0])
+        .then(|| Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]))
+}
+
+/// The host of `url` when it is an IP address outside the public internet.
+pub(crate) fn private_literal(url: &Url) -> Option<IpAddr> {
+    let ip = match url.host()? {
+        Host::Ipv4(ip) => IpAddr::V4(ip),
+        Host::Ipv6(ip) => IpAddr::V6(ip),
+        Host::Domain(_) => return None,
+    };
+    (!is_public_ip(ip)).then_some(ip)
+}
+
+/// Refuses `url` when its host is a non-public IP address; host names are checked as they
+/// resolve.
+pub(crate) fn check_url(url: &Url) -> Result<(), KowalskiError> {
+    match private_literal(url) {
+        Some(ip) => Err(KowalskiError::PermissionDenied(format!(
+            "refusing to fetch {url}: {ip} is not a public address \
+             (set [web] allow_private_addresses to allow it)"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The system resolver, keeping only public addresses.
+#[derive(Debug, Default)]
+pub(crate) struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let public: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if public.is_empty() {
+                return Err(format!(
+                    "{host} does not resolve to a public address \
+                     (set [web] allow_private_addresses to allow it)"
+                )
+                .into());
+            }
+            Ok(Box::new(public.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// `builder` resolving through [`PublicResolver`] unless `allow_private`. Clients that set their
+/// own redirect policy must check [`private_literal`] in it.
+pub(crate) fn resolve_public(builder: ClientBuilder, allow_private: bool) -> ClientBuilder {
+    if allow_private {
+        builder
+    } else {
+        builder.dns_resolver(Arc::new(PublicResolver))
+    }
+}
+
+/// A client for model-chosen URLs: public addresses only, redirects included, unless
+/// `allow_private`.
+pub(crate) fn fetch_client(allow_private: bool) -> Result<Client, KowalskiError> {
+    let redirect = Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if let Some(ip) = private_literal(attempt.url()).filter(|_| !allow_private) {
+            let target = attempt.url().to_string();
+            attempt.error(format!(
+                "redirect to {target}: {ip} is not a public address"
+            ))
+        } else {
+            attempt.follow()
+        }
+    });
+    let builder = Client::builder()
+        .user_agent(WEB_USER_AGENT)
+        .timeout(DEFAULT_WEB_TIMEOUT)
+        .redirect(redirect);
+    resolve_public(builder, allow_private)
+        .build()
+        .map_err(KowalskiError::Request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::{HttpFetcher, PageFetcher};
+
+    #[test]
+    fn only_public_addresses_pass() {
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+        ] {
+            assert!(!is_public_ip(private.parse().unwrap()), "{private}");
+        }
+        for public in [
+            "93.184.216.34",
+            "1.1.1.1",
+            "2606:4700::1111",
+            "::ffff:8.8.8.8",
+        ] {
+            assert!(is_public_ip(public.parse().unwrap()), "{public}");
+        }
+        let url = Url::parse("http://[::1]:8080/admin").unwrap();
+        assert_eq!(private_literal(&url), Some("::1".parse().unwrap()));
+        assert_eq!(
+            private_literal(&Url::parse("https://example.com/").unwrap()),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn local_addresses_are_fetched_only_when_allowed() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = axum::Router::new().route("/", axum::routing::get(|| async { "internal" }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let guarded = HttpFetcher::new().unwrap();
+        for url in [
+            format!("http://127.0.0.1:{port}/"),
+            format!("http://localhost:{port}/"),
+        ] {
+            let err = guarded.fetch(&url).await.unwrap_err();
+            // The resolver's refusal is the cause of reqwest's error.
+            let mut chain = err.to_string();
+            let mut source = std::error::Error::source(&err);
+            while let Some(cause) = source {
+                chain.push_str(&format!(": {cause}"));
+                source = cause.source();
+            }
+            assert!(chain.contains("public address"), "{url}: {chain}");
+        }
+
+        let config = crate::config::WebAgentConfig {
+            allow_private_addresses: true,
+            ..Default::default()
+        };
+        let open = HttpFetcher::new().unwrap().with_profiles(&config).unwrap();
+        let page = open
+            .fetch(&format!("http://localhost:{port}/"))
+            .await
+            .unwrap();
+        assert_eq!(page, "internal");
+    }
+}
@@ -0,0 +1,216 @@
+//! Web research: a [`WebAgent`] searches, reads and summarizes pages, follows up on what its
+//! notes miss, and has its LLM write a report citing them ([`WebAgent::research`]). [`crawl`]
+//! follows a page's links within its site.
+
+mod crawl;
+pub(crate) mod guard;
+mod profiles;
+mod research;
+mod search;
+mod tools;
+
+pub use crawl::{
+    CrawlOptions, CrawledPage, DEFAULT_MAX_CRAWL_PAGES, MAX_CRAWL_DEPTH, crawl,
+    crawl_with_progress, normalize_url,
+};
+pub use guard::is_public_ip;
+pub use research::{ReportSection, ResearchEvent, ResearchOptions, ResearchReport, ResearchSource};
+pub use search::{
+    DEFAULT_WEB_TIMEOUT, DuckDuckGoSearch, HttpFetcher, PageFetcher, SearchProvider, SearchResult,
+    WEB_USER_AGENT,
+};
+pub use tools::{WebScrapeTool, WebSearchTool};
+
+use crate::error::KowalskiError;
+use crate::llm::LLMProvider;
+use crate::tools::HtmlToMarkdownTool;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Default cap on extracted page text per source, in characters.
+pub const DEFAULT_MAX_SOURCE_CHARS: usize = 4000;
+
+/// Upper bound on [`ResearchOptions::max_depth`].
+pub const MAX_RESEARCH_DEPTH: usize = 10;
+
+/// Upper bound on [`ResearchOptions::max_sources`] and on the results of one `web_search` call.
+pub const MAX_RESEARCH_SOURCES: usize = 10;
+
+/// Agent for search → read → synthesize. Search and fetching default to DuckDuckGo and plain
+/// HTTP; swap them with [`with_search`](Self::with_search) / [`with_fetcher`](Self::with_fetcher).
+pub struct WebAgent {
+    llm: Arc<dyn LLMProvider>,
+    model: String,
+    search: Arc<dyn SearchProvider>,
+    fetcher: Arc<dyn PageFetcher>,
+    max_source_chars: usize,
+    events: broadcast::Sender<ResearchEvent>,
+}
+
+impl WebAgent {
+    pub fn new(llm: Arc<dyn LLMProvider>, model: impl Into<String>) -> Result<Self, KowalskiError> {
+        Ok(Self {
+            llm,
+            model: model.into(),
+            search: Arc::new(DuckDuckGoSearch::new()?),
+            fetcher: Arc::new(HttpFetcher::new()?),
+            max_source_chars: DEFAULT_MAX_SOURCE_CHARS,
+            events: broadcast::channel(64).0,
+        })
+    }
+
+    pub fn with_search(mut self, search: Arc<dyn SearchProvider>) -> Self {
+        self.search = search;
+        self
+    }
+
+    pub fn with_fetcher(mut self, fetcher: Arc<dyn PageFetcher>) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Caps the page text passed to the model per source (default [`DEFAULT_MAX_SOURCE_CHARS`]).
+    pub fn with_max_source_chars(mut self, chars: usize) -> Self {
+        self.max_source_chars = chars;
+        self
+    }
+
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, KowalskiError> {
+        self.search.search(query, limit).await
+    }
+
+    /// Fetches `url` and returns its main content as Markdown (boilerplate stripped, truncated
+    /// to the source cap).
+    pub async fn read_page(&self, url: &str) -> Result<String, KowalskiError> {
+        page_markdown(self.fetcher.as_ref(), url, self.max_source_chars).await
+    }
+}
+
+/// Fetches `url` and converts its main content to Markdown, capped at `max_chars`.
+async fn page_markdown(
+    fetcher: &dyn PageFetcher,
+    url: &str,
+    max_chars: usize,
+) -> Result<String, KowalskiError> {
+    let html = fetcher.fetch(url).await?;
+    let markdown = HtmlToMarkdownTool::convert(&html, true);
+    Ok(truncate_chars(&markdown, max_chars))
+}
+
+pub(crate) fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{Tool, ToolInput};
+    use async_trait::async_trait;
+
+    struct FixedSearch;
+
+    #[async_trait]
+    impl SearchProvider for FixedSearch {
+        async fn search(
+            &self,
+            _query: &str,
+            limit: usize,
+        ) -> Result<Vec<SearchResult>, KowalskiError> {
+            Ok((1..=3)
+                .map(|i| SearchResult {
+                    title: format!("Page {i}"),
+                    url: format!("https://example.com/{i}"),
+                    snippet: format!("snippet {i}"),
+                })
+                .take(limit)
+                .collect())
+        }
+    }
+
+    struct Pages;
+
+    #[async_trait]
+    impl PageFetcher for Pages {
+        async fn fetch(&self, url: &str) -> Result<String, KowalskiError> {
+            if url.ends_with("/2") {
+                return Err(KowalskiError::Network("connection reset".to_string()));
+            }
+            Ok(format!(
+                "<nav>menu</nav><h1>Body of {url}</h1><p>Rust 1.80 stabilised LazyLock.</p>"
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn web_tools_name_their_sources() {
+        let mut scrape = WebScrapeTool::with_fetcher(Arc::new(Pages));
+        let out = scrape
+            .execute(ToolInput::from_parameters(
+                serde_json::json!({"url": "https://example.com/1"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(out.source.as_deref(), Some("https://example.com/1"));
+        assert!(
+            out.result["markdown"]
+                .as_str()
+                .unwrap()
+                .contains("Rust 1.80 stabilised LazyLock.")
+        );
+        let err = scrape
+            .execute(ToolInput::from_parameters(
+                serde_json::json!({"url": "https://example.com/2"}),
+            ))
+            .await;
+        assert!(err.is_err());
+        let out = scrape
+            .execute(ToolInput::from_parameters(
+                serde_json::json!({"url": "https://example.com/1", "follow_links": true}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(out.source.as_deref(), Some("https://example.com/1"));
+        assert_eq!(out.result["pages"][0]["depth"], 0);
+
+        let mut search = WebSearchTool::with_search(Arc::new(FixedSearch));
+        let out = search
+            .execute(ToolInput::from_parameters(
+                serde_json::json!({"query": "lazylock", "limit": 2}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(out.source.as_deref(), Some("web search"));
+        assert_eq!(out.result["results"].as_array().unwrap().len(), 2);
+        assert_eq!(DuckDuckGoSearch::new().unwrap().name(), "duckduckgo");
+    }
+
+    #[test]
+    fn parses_duckduckgo_results() {
+        let html = r#"
+<div class="result"><h2><a rel="nofollow" class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fdoc.rust-lang.org%2Fstd%2Fsync%2Fstruct.LazyLock.html&amp;rut=abc">LazyLock in <b>std::sync</b></a></h2>
+<a class="result__snippet" href="//duckduckgo.com/l/?uddg=x">A value which is initialized on the first access &amp; shared.</a></div>
+<div class="result"><h2><a rel="nofollow" class="result__a" href="https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html">Announcing Rust 1.80.0</a></h2>
+<a class="result__snippet" href="x">LazyCell and LazyLock are now stable.</a></div>"#;
+        let results = DuckDuckGoSearch::parse_results(html, 5);
+        assert_eq!(
+            results[0],
+            SearchResult {
+                title: "LazyLock in std::sync".to_string(),
+                url: "https://doc.rust-lang.org/std/sync/struct.LazyLock.html".to_string(),
+                snippet: "A value which is initialized on the first access & shared.".to_string(),
+            }
+        );
+        assert_eq!(
+            results[1].url,
+            "https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html"
+        );
+        assert_eq!(DuckDuckGoSearch::parse_results(html, 1).len(), 1);
+    }
+}
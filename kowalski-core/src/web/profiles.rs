@@ -0,0 +1,243 @@
+//! Named HTTP profiles (`[[web.profiles]]`): per-profile headers, cookies and credentials, and a
+//! cookie jar that keeps what responses set (a login, for instance) for the session. A profile's
+//! credentials only go to its `hosts`: the model picks the URLs, so a page must not be able to
+//! send them anywhere else. Other URLs are fetched as if no profile had been named.
+
+use super::guard::{MAX_REDIRECTS, fetch_client, private_literal, resolve_public};
+use super::search::{DEFAULT_WEB_TIMEOUT, WEB_USER_AGENT};
+use crate::config::HttpProfile;
+use crate::error::KowalskiError;
+use base64::Engine;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use reqwest::{Client, RequestBuilder};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Clients for the configured profiles, built on first use so a profile whose secrets are not
+/// in the environment only fails when it is picked.
+#[derive(Debug, Default)]
+pub(crate) struct HttpProfiles {
+    profiles: HashMap<String, HttpProfile>,
+    clients: Mutex<HashMap<String, ProfileClient>>,
+    allow_private: bool,
+}
+
+/// A profile's client plus what it attaches to each request to one of its hosts.
+#[derive(Debug, Clone)]
+pub(crate) struct ProfileClient {
+    client: Client,
+    /// For URLs off the profile's hosts: no headers, no cookie jar.
+    plain: Client,
+    headers: HeaderMap,
+    hosts: Arc<Vec<String>>,
+}
+
+impl ProfileClient {
+    /// A GET for `url`: with the profile's headers and cookies when `url` is on its hosts,
+    /// without any of them otherwise.
+    pub(crate) fn get(&self, url: Url) -> RequestBuilder {
+        if host_allowed(&self.hosts, &url) {
+            self.client.get(url).headers(self.headers.clone())
+        } else {
+            self.plain.get(url)
+        }
+    }
+}
+
+impl HttpProfiles {
+    /// Profile clients reach only public addresses unless `allow_private`.
+    pub(crate) fn new(profiles: &[HttpProfile], allow_private: bool) -> Self {
+        Self {
+            profiles: profiles
+                .iter()
+                .map(|p| (p.name.clone(), p.clone()))
+                .collect(),
+            clients: Mutex::new(HashMap::new()),
+            allow_private,
+        }
+    }
+
+    /// The client for `name`; clones share its cookie jar.
+    pub(crate) fn client(&self, name: &str) -> Result<ProfileClient, KowalskiError> {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(client) = clients.get(name) {
+            return Ok(client.clone());
+        }
+        let profile = self.profiles.get(name).ok_or_else(|| {
+            let mut known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            known.sort_unstable();
+            KowalskiError::WebAgent(format!(
+                "unknown HTTP profile '{name}' (configured: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ))
+        })?;
+        let client = build_client(profile, self.allow_private)?;
+        clients.insert(name.to_string(), client.clone());
+        Ok(client)
+    }
+}
+
+/// A value from the config: `env:VAR` is read from the environment, anything else is literal.
+fn resolve(profile: &str, value: &str) -> Result<String, KowalskiError> {
+    match value.strip_prefix("env:") {
+        Some(var) => env_secret(profile, var),
+        None => Ok(value.to_string()),
+    }
+}
+
+fn env_secret(profile: &str, var: &str) -> Result<String, KowalskiError> {
+    std::env::var(var).map_err(|_| {
+        KowalskiError::Configuration(format!(
+            "HTTP profile '{profile}': environment variable {var} is not set"
+        ))
+    })
+}
+
+fn header_value(profile: &str, value: &str) -> Result<HeaderValue, KowalskiError> {
+    HeaderValue::from_str(value).map_err(|e| {
+        KowalskiError::Configuration(format!(
+            "HTTP profile '{profile}': invalid header value: {e}"
+        ))
+    })
+}
+
+/// Whether `url`'s host is one of `hosts`: an entry matches that host and its subdomains, on any
+/// port unless it names one.
+fn host_allowed(hosts: &[String], url: &Url) -> bool {
+    let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+        return false;
+    };
+    let port = url.port_or_known_default();
+    hosts.iter().any(|entry| {
+        let entry = entry.trim().to_ascii_lowercase();
+        let (name, entry_port) = match entry.rsplit_once(':') {
+            Some((name, p)) if p.parse::<u16>().is_ok() => (name, p.parse().ok()),
+            _ => (entry.as_str(), None),
+        };
+        (host == name || host.ends_with(&format!(".{name}")))
+            && entry_port.is_none_or(|p| Some(p) == port)
+    })
+}
+
+fn build_client(
+    profile: &HttpProfile,
+    allow_private: bool,
+) -> Result<ProfileClient, KowalskiError> {
+    let name = profile.name.as_str();
+    let hosts: Vec<String> = profile
+        .hosts
+        .iter()
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .collect();
+    if hosts.is_empty() {
+        return Err(KowalskiError::Configuration(format!(
+            "HTTP profile '{name}' needs `hosts`: the sites its credentials may be sent to"
+        )));
+    }
+    let hosts = Arc::new(hosts);
+    let mut headers = HeaderMap::new();
+    for (key, value) in &profile.headers {
+        let key = HeaderName::from_bytes(key.as_bytes()).map_err(|e| {
+            KowalskiError::Configuration(format!("HTTP profile '{name}': header '{key}': {e}"))
+        })?;
+        let mut value = header_value(name, &resolve(name, value)?)?;
+        value.set_sensitive(true);
+        headers.insert(key, value);
+    }
+    let authorization = match (&profile.bearer_token_env, &profile.basic_auth) {
+        (Some(var), _) => Some(format!("Bearer {}", env_secret(name, var)?)),
+        (None, Some(basic)) => {
+            let password = env_secret(name, &basic.password_env)?;
+            let encoded = base64::engine::general_purpose::STANDARD
+                .encode(format!("{}:{password}", basic.username));
+            Some(format!("Basic {encoded}"))
+        }
+        (None, None) => None,
+    };
+    if let Some(authorization) = authorization {
+        let mut value = header_value(name, &authorization)?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+
+    let mut fixed = Vec::new();
+    for (key, value) in &profile.cookies {
+        fixed.push(format!("{key}={}", resolve(name, value)?));
+    }
+    fixed.sort();
+    let cookies = ProfileCookies {
+        fixed: fixed.join("; "),
+        jar: Jar::default(),
+        hosts: hosts.clone(),
+    };
+
+    // Request headers are kept across redirects, so a redirect may not leave the hosts.
+    let redirect_hosts = hosts.clone();
+    let redirect = Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if let Some(ip) = private_literal(attempt.url()).filter(|_| !allow_private) {
+            let target = attempt.url().to_string();
+            attempt.error(format!(
+                "redirect to {target}: {ip} is not a public address"
+            ))
+        } else if host_allowed(&redirect_hosts, attempt.url()) {
+            attempt.follow()
+        } else {
+            let target = attempt.url().to_string();
+            attempt.error(format!(
+                "redirect to {target} is outside the profile's hosts"
+            ))
+        }
+    });
+    let builder = Client::builder()
+        .user_agent(WEB_USER_AGENT)
+        .timeout(DEFAULT_WEB_TIMEOUT)
+        .redirect(redirect)
+        .cookie_provider(Arc::new(cookies));
+    let client = resolve_public(builder, allow_private)
+        .build()
+        .map_err(KowalskiError::Request)?;
+    Ok(ProfileClient {
+        client,
+        plain: fetch_client(allow_private)?,
+        headers,
+        hosts,
+    })
+}
+
+/// The profile's configured cookies, sent to its hosts, followed by the ones responses set.
+struct ProfileCookies {
+    fixed: String,
+    jar: Jar,
+    hosts: Arc<Vec<String>>,
+}
+
+impl CookieStore for ProfileCookies {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &url::Url) {
+        self.jar.set_cookies(cookie_headers, url);
+    }
+
+    fn cookies(&self, url: &url::Url) -> Option<HeaderValue> {
+        let session = self
+            .jar
+            .cookies(url)
+            .and_then(|v| v.to_str().ok().map(str::to_string));
+        let no_fixed = self.fixed.is_empty() || !host_allowed(&self.hosts, url);
+        let all = match (no_fixed, session) {
+            (true, None) => return None,
+            (true, Some(session)) => session,
+            (false, None) => self.fixed.clone(),
+            (false, Some(session)) => format!("{}; {session}", self.fixed),
+        };
+        HeaderValue::from_str(&all).ok()
+    }
+}
@@ -0,0 +1,587 @@
+//! Deep research ([`WebAgent::research`]): search, read and summarize each source, let the
+//! model critique the notes for gaps, search again, and finally write a [`ResearchReport`] whose
+//! sections cite the sources by number.
+
+use super::{MAX_RESEARCH_DEPTH, MAX_RESEARCH_SOURCES, SearchResult, WebAgent};
+use crate::conversation::Message;
+use crate::error::KowalskiError;
+use crate::llm::ChatOptions;
+use crate::utils::json::strip_markdown_code_fences;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+const SUMMARY_PROMPT: &str = "You take research notes. Summarize what the source below says about the research topic in a few sentences, keeping figures, dates and names. If it says nothing about the topic, say so. Reply with only the summary.";
+
+const CRITIQUE_PROMPT: &str = "You review research notes for gaps. Given the topic and the numbered notes, list what is still missing, unclear or only supported by one source, as web search queries. Reply with only JSON: {\"queries\": [\"...\"]}, with an empty list when the notes cover the topic.";
+
+const REPORT_PROMPT: &str = "You are a web research assistant. Write a report on the topic using only the numbered source notes. Cite every claim with its source numbers in brackets, e.g. [1] or [2][3]. Reply with only JSON: {\"summary\": \"...\", \"sections\": [{\"heading\": \"...\", \"content\": \"...\"}]}.";
+
+/// Follow-up queries taken from one critique.
+const MAX_FOLLOW_UP_QUERIES: usize = 3;
+
+/// How far [`WebAgent::research`] digs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResearchOptions {
+    /// Sources read in total, across all rounds (1 to [`MAX_RESEARCH_SOURCES`]).
+    pub max_sources: usize,
+    /// Follow-up search rounds after the first, each driven by a critique of the notes so far
+    /// (at most [`MAX_RESEARCH_DEPTH`]; 0 searches once).
+    pub max_depth: usize,
+    /// Have the model summarize each page before the report; off, the report is written from
+    /// the extracted page text itself.
+    pub per_source_summary: bool,
+}
+
+impl Default for ResearchOptions {
+    fn default() -> Self {
+        Self {
+            max_sources: 6,
+            max_depth: 2,
+            per_source_summary: true,
+        }
+    }
+}
+
+/// A source the report may cite as `[number]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResearchSource {
+    /// 1-based, in the order the sources were read.
+    pub number: usize,
+    pub title: String,
+    pub url: String,
+    /// The model's notes on the page (or its extracted text, see
+    /// [`ResearchOptions::per_source_summary`]); the search snippet when the page was unreadable.
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportSection {
+    pub heading: String,
+    /// Markdown with `[n]` citation markers.
+    pub content: String,
+    /// Source numbers cited in `content`, ascending; markers naming no source are left out.
+    pub citations: Vec<usize>,
+}
+
+/// Outcome of [`WebAgent::research`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResearchReport {
+    pub summary: String,
+    pub sections: Vec<ReportSection>,
+    /// Everything that was read, numbered as cited.
+    pub sources: Vec<ResearchSource>,
+}
+
+impl ResearchReport {
+    pub fn source(&self, number: usize) -> Option<&ResearchSource> {
+        self.sources.iter().find(|s| s.number == number)
+    }
+
+    /// The summary, one `##` heading per section, and a numbered source list.
+    pub fn to_markdown(&self) -> String {
+        let mut out = self.summary.trim().to_string();
+        for section in &self.sections {
+            out.push_str(&format!(
+                "\n\n## {}\n\n{}",
+                section.heading,
+                section.content.trim()
+            ));
+        }
+        out.push_str("\n\n## Sources\n");
+        for source in &self.sources {
+            out.push_str(&format!(
+                "\n[{}] {}: {}",
+                source.number, source.title, source.url
+            ));
+        }
+        out
+    }
+}
+
+/// Progress of [`WebAgent::research`], broadcast to [`WebAgent::subscribe`] receivers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResearchEvent {
+    /// `round` 0 is the topic itself; later rounds run the critique's queries.
+    Searching {
+        round: usize,
+        query: String,
+    },
+    SourceRead {
+        number: usize,
+        url: String,
+    },
+    /// The page could not be fetched; its search snippet stands in for it. No `SourceRead` follows.
+    SourceUnreadable {
+        number: usize,
+        url: String,
+        error: String,
+    },
+    GapsFound {
+        queries: Vec<String>,
+    },
+    Reported {
+        sections: usize,
+        sources: usize,
+    },
+}
+
+impl WebAgent {
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<ResearchEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit(&self, event: ResearchEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Researches `topic` over several rounds: search, read and take notes on the new results,
+    /// ask the model what the notes still miss, search for that, and so on until
+    /// `options.max_sources` are read, `options.max_depth` follow-up rounds ran, or the critique
+    /// finds no gaps. The report cites the sources by number.
+    pub async fn research(
+        &self,
+        topic: &str,
+        options: ResearchOptions,
+    ) -> Result<ResearchReport, KowalskiError> {
+        let max_sources = options.max_sources.clamp(1, MAX_RESEARCH_SOURCES);
+        let max_depth = options.max_depth.min(MAX_RESEARCH_DEPTH);
+        let mut sources: Vec<ResearchSource> = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut asked: HashSet<String> = HashSet::new();
+        let mut queries = vec![topic.to_string()];
+
+        for round in 0..=max_depth {
+            for query in &queries {
+                if sources.len() >= max_sources {
+                    break;
+                }
+                asked.insert(query.to_lowercase());
+                self.emit(ResearchEvent::Searching {
+                    round,
+                    query: query.clone(),
+                });
+                let results = match self.search.search(query, max_sources).await {
+                    Ok(results) => results,
+                    Err(e) if round == 0 => return Err(e),
+                    Err(e) => {
+                        warn!("research: follow-up search '{query}' failed: {e}");
+                        continue;
+                    }
+                };
+                let mut fresh = Vec::new();
+                for result in results {
+                    if sources.len() + fresh.len() >= max_sources {
+                        break;
+                    }
+                    if seen.insert(result.url.clone()) {
+                        fresh.push(result);
+                    }
+                }
+                self.take_notes(topic, fresh, &options, &mut sources)
+                    .await?;
+            }
+            if round == max_depth || sources.len() >= max_sources || sources.is_empty() {
+                break;
+            }
+            queries = self
+                .find_gaps(topic, &sources)
+                .await?
+                .into_iter()
+                .filter(|q| !asked.contains(&q.to_lowercase()))
+                .take(MAX_FOLLOW_UP_QUERIES)
+                .collect();
+            if queries.is_empty() {
+                break;
+            }
+            self.emit(ResearchEvent::GapsFound {
+                queries: queries.clone(),
+            });
+        }
+
+        if sources.is_empty() {
+            return Err(KowalskiError::WebAgent(format!(
+                "no search results for '{topic}'"
+            )));
+        }
+        info!(
+            "research '{topic}': writing report from {} sources",
+            sources.len()
+        );
+        let report = self.write_report(topic, sources).await?;
+        self.emit(ResearchEvent::Reported {
+            sections: report.sections.len(),
+            sources: report.sources.len(),
+        });
+        Ok(report)
+    }
+
+    /// Reads `results` in parallel and appends a numbered note for each.
+    async fn take_notes(
+        &self,
+        topic: &str,
+        results: Vec<SearchResult>,
+        options: &ResearchOptions,
+        sources: &mut Vec<ResearchSource>,
+    ) -> Result<(), KowalskiError> {
+        let pages = futures::future::join_all(results.iter().map(|r| self.read_page(&r.url))).await;
+        for (result, page) in results.into_iter().zip(pages) {
+            let number = sources.len() + 1;
+            let page_read = page.is_ok();
+            let summary = match page {
+                Ok(text) if options.per_source_summary => {
+                    self.summarize_source(topic, &result, &text).await?
+                }
+                Ok(text) => text,
+                Err(e) => {
+                    warn!("research: could not read {}: {e}", result.url);
+                    self.emit(ResearchEvent::SourceUnreadable {
+                        number,
+                        url: result.url.clone(),
+                        error: e.to_string(),
+                    });
+                    result.snippet.clone()
+                }
+            };
+            if page_read {
+                self.emit(ResearchEvent::SourceRead {
+                    number,
+                    url: result.url.clone(),
+                });
+            }
+            sources.push(ResearchSource {
+                number,
+                title: result.title,
+                url: result.url,
+                summary,
+            });
+        }
+        Ok(())
+    }
+
+    async fn summarize_source(
+        &self,
+        topic: &str,
+        source: &SearchResult,
+        text: &str,
+    ) -> Result<String, KowalskiError> {
+        let messages = [
+            message("system", SUMMARY_PROMPT),
+            message(
+                "user",
+                format!(
+                    "Topic: {topic}\n\nSource: {}\nURL: {}\n\n{text}",
+                    source.title, source.url
+                ),
+            ),
+        ];
+        Ok(self
+            .llm
+            .chat(&self.model, &messages)
+            .await?
+            .trim()
+            .to_string())
+    }
+
+    /// The critique step: follow-up queries for what the notes do not cover yet. An unreadable
+    /// reply counts as no gaps.
+    async fn find_gaps(
+        &self,
+        topic: &str,
+        sources: &[ResearchSource],
+    ) -> Result<Vec<String>, KowalskiError> {
+        let messages = [
+            message("system", CRITIQUE_PROMPT),
+            message(
+                "user",
+                format!("Topic: {topic}\n\nNotes:\n\n{}", numbered_notes(sources)),
+            ),
+        ];
+        let reply = self
+            .llm
+            .chat_json(&self.model, &messages, &ChatOptions::default())
+            .await?;
+        let Some(value) = parse_json_object(&reply) else {
+            warn!("research: unreadable critique, stopping: {reply}");
+            return Ok(Vec::new());
+        };
+        Ok(value["queries"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|q| q.as_str())
+            .map(|q| q.trim().to_string())
+            .filter(|q| !q.is_empty())
+            .collect())
+    }
+
+    /// Synthesis: the model's report with each section's citations resolved against `sources`.
+    /// A reply that is not the requested JSON becomes the summary, without sections.
+    async fn write_report(
+        &self,
+        topic: &str,
+        sources: Vec<ResearchSource>,
+    ) -> Result<ResearchReport, KowalskiError> {
+        let messages = [
+            message("system", REPORT_PROMPT),
+            message(
+                "user",
+                format!("Topic: {topic}\n\nSources:\n\n{}", numbered_notes(&sources)),
+            ),
+        ];
+        let reply = self
+            .llm
+            .chat_json(&self.model, &messages, &ChatOptions::default())
+            .await?;
+        let Some(value) = parse_json_object(&reply) else {
+            warn!("research: report was not JSON, keeping it as the summary");
+            return Ok(ResearchReport {
+                summary: reply.trim().to_string(),
+                sections: Vec::new(),
+                sources,
+            });
+        };
+        let sections = value["sections"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|s| {
+                let content = s["content"].as_str()?.to_string();
+                Some(ReportSection {
+                    heading: s["heading"].as_str().unwrap_or_default().to_string(),
+                    citations: citations(&content, sources.len()),
+                    content,
+                })
+            })
+            .collect();
+        Ok(ResearchReport {
+            summary: value["summary"].as_str().unwrap_or_default().to_string(),
+            sections,
+            sources,
+        })
+    }
+}
+
+fn message(role: &str, content: impl Into<String>) -> Message {
+    Message {
+        role: role.to_string(),
+        content: content.into(),
+        tool_calls: None,
+        images: None,
+        tool_call_id: None,
+        tool_name: None,
+    }
+}
+
+fn numbered_notes(sources: &[ResearchSource]) -> String {
+    sources
+        .iter()
+        .map(|s| format!("[{}] {} ({})\n{}", s.number, s.title, s.url, s.summary))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The first JSON object in a model reply (fences and surrounding prose ignored).
+fn parse_json_object(reply: &str) -> Option<Value> {
+    let text = strip_markdown_code_fences(reply);
+    let raw = &text[text.find('{')?..];
+    let value: Value = serde_json::from_str(raw).ok().or_else(|| {
+        llm_json::repair_json(raw, &llm_json::RepairOptions::default())
+            .ok()
+            .and_then(|fixed| serde_json::from_str(&fixed).ok())
+    })?;
+    value.is_object().then_some(value)
+}
+
+/// Numbers in `[n]` markers of `text` that name one of `count` sources, ascending.
+fn citations(text: &str, count: usize) -> Vec<usize> {
+    let mut cited: Vec<usize> = text
+        .split('[')
+        .skip(1)
+        .filter_map(|rest| rest.split_once(']'))
+        .filter_map(|(inside, _)| inside.trim().parse().ok())
+        .filter(|n| (1..=count).contains(n))
+        .collect();
+    cited.sort_unstable();
+    cited.dedup();
+    cited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockBackend;
+    use crate::web::{PageFetcher, SearchProvider};
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    /// Results per query: the topic finds pages 1 and 2, the follow-up finds 2 again and 3.
+    struct Search;
+
+    #[async_trait]
+    impl SearchProvider for Search {
+        async fn search(
+            &self,
+            query: &str,
+            limit: usize,
+        ) -> Result<Vec<SearchResult>, KowalskiError> {
+            let pages: &[usize] = match query {
+                "LazyLock" => &[1, 2],
+                "LazyLock MSRV" => &[2, 3],
+                _ => &[],
+            };
+            Ok(pages
+                .iter()
+                .map(|i| SearchResult {
+                    title: format!("Page {i}"),
+                    url: format!("https://example.com/{i}"),
+                    snippet: format!("snippet {i}"),
+                })
+                .take(limit)
+                .collect())
+        }
+    }
+
+    struct Pages;
+
+    #[async_trait]
+    impl PageFetcher for Pages {
+        async fn fetch(&self, url: &str) -> Result<String, KowalskiError> {
+            if url.ends_with("/2") {
+                return Err(KowalskiError::Network("connection reset".to_string()));
+            }
+            Ok(format!("<nav>menu</nav><h1>Body of {url}</h1>"))
+        }
+    }
+
+    fn agent(backend: Arc<MockBackend>) -> WebAgent {
+        WebAgent::new(backend, "llama3.2")
+            .unwrap()
+            .with_search(Arc::new(Search))
+            .with_fetcher(Arc::new(Pages))
+    }
+
+    #[tokio::test]
+    async fn follows_up_on_gaps_and_cites_sources_by_number() {
+        let backend = Arc::new(
+            MockBackend::script()
+                .user_says("URL: https://example.com/1")
+                .responds_with_text("LazyLock is in std since 1.80.")
+                .user_says("Notes:\n\n[1] Page 1 (https://example.com/1)\nLazyLock is in std")
+                .responds_with_text(
+                    "```json\n{\"queries\": [\"LazyLock MSRV\", \"lazylock\"]}\n```",
+                )
+                .user_says("URL: https://example.com/3")
+                .responds_with_text("once_cell remains for older compilers.")
+                .user_says("[3] Page 3 (https://example.com/3)\nonce_cell remains")
+                .responds_with_text(
+                    r#"{"summary": "LazyLock replaces once_cell [1].", "sections": [
+                        {"heading": "Status", "content": "Stable since 1.80 [1], see also [2][9]."},
+                        {"heading": "Older compilers", "content": "Use once_cell [3] [1]."}]}"#,
+                )
+                .build(),
+        );
+        let agent = agent(backend.clone());
+        let mut events = agent.subscribe();
+
+        let options = ResearchOptions {
+            max_sources: 5,
+            max_depth: 1,
+            per_source_summary: true,
+        };
+        let report = agent.research("LazyLock", options).await.unwrap();
+        backend.assert_finished();
+
+        let urls: Vec<&str> = report.sources.iter().map(|s| s.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://example.com/1",
+                "https://example.com/2",
+                "https://example.com/3"
+            ]
+        );
+        // Page 2 was unreadable: its snippet is the note, and it is not read twice.
+        assert_eq!(report.source(2).unwrap().summary, "snippet 2");
+        assert_eq!(report.sections[0].citations, [1, 2]);
+        assert_eq!(report.sections[1].citations, [1, 3]);
+        assert_eq!(
+            report.source(3).unwrap().summary,
+            "once_cell remains for older compilers."
+        );
+
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("LazyLock replaces once_cell [1].\n\n## Status\n"));
+        assert!(markdown.ends_with(
+            "## Sources\n\n[1] Page 1: https://example.com/1\n[2] Page 2: https://example.com/2\n[3] Page 3: https://example.com/3"
+        ));
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            seen.push(event);
+        }
+        assert!(seen.contains(&ResearchEvent::GapsFound {
+            queries: vec!["LazyLock MSRV".to_string()]
+        }));
+        assert!(seen.contains(&ResearchEvent::Searching {
+            round: 1,
+            query: "LazyLock MSRV".to_string()
+        }));
+        assert!(matches!(
+            seen.iter()
+                .find(|e| matches!(e, ResearchEvent::SourceUnreadable { .. })),
+            Some(ResearchEvent::SourceUnreadable { number: 2, .. })
+        ));
+        let read: Vec<usize> = seen
+            .iter()
+            .filter_map(|e| match e {
+                ResearchEvent::SourceRead { number, .. } => Some(*number),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            read,
+            [1, 3],
+            "an unreadable source is only reported as unreadable"
+        );
+        assert_eq!(
+            seen.last(),
+            Some(&ResearchEvent::Reported {
+                sections: 2,
+                sources: 3
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn single_round_without_summaries_and_prose_report() {
+        let backend = Arc::new(
+            MockBackend::script()
+                .user_says("[1] Page 1 (https://example.com/1)\nBody of https://example.com/1")
+                .responds_with_text("LazyLock is stable [1].")
+                .build(),
+        );
+        let options = ResearchOptions {
+            max_sources: 1,
+            max_depth: 3,
+            per_source_summary: false,
+        };
+        let report = agent(backend.clone())
+            .research("LazyLock", options)
+            .await
+            .unwrap();
+        backend.assert_finished();
+        assert_eq!(report.summary, "LazyLock is stable [1].");
+        assert!(report.sections.is_empty());
+        assert_eq!(report.sources.len(), 1);
+        assert!(!report.sources[0].summary.contains("menu"));
+
+        let err = agent(Arc::new(MockBackend::script().build()))
+            .research("nothing", ResearchOptions::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no search results for 'nothing'"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Web search and page fetching behind small traits, so [`WebAgent`](super::WebAgent) can run
+//! against DuckDuckGo and plain HTTP by default and against stubs in tests.
+
+use super::guard::{check_url, fetch_client};
+use super::profiles::HttpProfiles;
+use crate::config::WebAgentConfig;
+use crate::error::KowalskiError;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// User agent sent with search and page requests.
+pub const WEB_USER_AGENT: &str = "Mozilla/5.0 (compatible; Kowalski Agent/1.0)";
+
+/// Default per-request timeout for search and page fetches.
+pub const DEFAULT_WEB_TIMEOUT: Duration = Duration::from_secs(20);
+
+const DUCKDUCKGO_HTML_URL: &str = "https://html.duckduckgo.com/html/";
+
+static RESULT_LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a[^>]*class="result__a"[^>]*href="([^"]+)"[^>]*>(.*?)</a>"#)
+        .expect("RESULT_LINK regex")
+});
+
+static RESULT_SNIPPET: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a[^>]*class="result__snippet"[^>]*>(.*?)</a>"#)
+        .expect("RESULT_SNIPPET regex")
+});
+
+static TAGS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").expect("TAGS regex"));
+
+/// One search hit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// Runs a web search.
+#[async_trait]
+pub trait SearchProvider: Send + Sync {
+    /// At most `limit` results, best first.
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>, KowalskiError>;
+
+    /// Names the engine where results are cited, e.g. `duckduckgo`.
+    fn name(&self) -> &str {
+        "web search"
+    }
+}
+
+/// Downloads a page's HTML.
+#[async_trait]
+pub trait PageFetcher: Send + Sync {
+    async fn fetch(&self, url: &str) -> Result<String, KowalskiError>;
+
+    /// Like [`Self::fetch`], with the headers, cookies and credentials of the named
+    /// [`HttpProfile`](crate::config::HttpProfile). Fetchers without profiles refuse.
+    async fn fetch_with_profile(&self, _url: &str, profile: &str) -> Result<String, KowalskiError> {
+        Err(KowalskiError::WebAgent(format!(
+            "unknown HTTP profile '{profile}'"
+        )))
+    }
+}
+
+/// A fetcher that always uses one profile, so crawls and page reads stay logged in.
+pub(crate) struct Profiled<'a> {
+    pub fetcher: &'a dyn PageFetcher,
+    pub profile: &'a str,
+}
+
+#[async_trait]
+impl PageFetcher for Profiled<'_> {
+    async fn fetch(&self, url: &str) -> Result<String, KowalskiError> {
+        self.fetcher.fetch_with_profile(url, self.profile).await
+    }
+}
+
+fn http_client() -> Result<reqwest::Client, KowalskiError> {
+    reqwest::Client::builder()
+        .user_agent(WEB_USER_AGENT)
+        .timeout(DEFAULT_WEB_TIMEOUT)
+        .build()
+        .map_err(KowalskiError::Request)
+}
+
+/// Search through DuckDuckGo's HTML endpoint (no API key).
+#[derive(Debug, Clone)]
+pub struct DuckDuckGoSearch {
+    client: reqwest::Client,
+    endpoint: String,
+}
+
+impl DuckDuckGoSearch {
+    pub fn new() -> Result<Self, KowalskiError> {
+        Ok(Self {
+            client: http_client()?,
+            endpoint: DUCKDUCKGO_HTML_URL.to_string(),
+        })
+    }
+
+    /// Points at another endpoint serving the same HTML (a proxy or a test server).
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Extracts results from a DuckDuckGo HTML results page, resolving its `/l/?uddg=` redirect
+    /// links to the target URL.
+    pub fn parse_results(html: &str, limit: usize) -> Vec<SearchResult> {
+        let snippets: Vec<String> = RESULT_SNIPPET
+            .captures_iter(html)
+            .map(|c| clean_text(&c[1]))
+            .collect();
+        RESULT_LINK
+            .captures_iter(html)
+            .enumerate()
+            .filter_map(|(i, c)| {
+                let url = resolve_redirect(&decode_entities(&c[1]))?;
+                Some(SearchResult {
+                    title: clean_text(&c[2]),
+                    url,
+                    snippet: snippets.get(i).cloned().unwrap_or_default(),
+                })
+            })
+            .take(limit)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for DuckDuckGoSearch {
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>, KowalskiError> {
+        let html = self
+            .client
+            .get(&self.endpoint)
+            .query(&[("q", query)])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(Self::parse_results(&html, limit))
+    }
+
+    fn name(&self) -> &str {
+        "duckduckgo"
+    }
+}
+
+/// Fetches pages with a plain GET; only `http` and `https` URLs on public addresses are
+/// allowed, unless `[web] allow_private_addresses` is set. Requests may go through a named
+/// [`HttpProfile`](crate::config::HttpProfile) (see [`Self::with_profiles`]).
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    client: reqwest::Client,
+    profiles: Arc<HttpProfiles>,
+    allow_private: bool,
+}
+
+impl HttpFetcher {
+    pub fn new() -> Result<Self, KowalskiError> {
+        Ok(Self {
+            client: fetch_client(false)?,
+            profiles: Arc::new(HttpProfiles::default()),
+            allow_private: false,
+        })
+    }
+
+    /// Applies the `[web]` settings: its profiles become available to
+    /// [`PageFetcher::fetch_with_profile`], each with its own client and cookie jar on first use,
+    /// kept for the fetcher's lifetime; `allow_private_addresses` lifts the public-address check.
+    pub fn with_profiles(mut self, config: &WebAgentConfig) -> Result<Self, KowalskiError> {
+        self.allow_private = config.allow_private_addresses;
+        self.client = fetch_client(self.allow_private)?;
+        self.profiles = Arc::new(HttpProfiles::new(&config.profiles, self.allow_private));
+        Ok(self)
+    }
+
+    fn parse(&self, url: &str) -> Result<url::Url, KowalskiError> {
+        let parsed = url::Url::parse(url)?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(KowalskiError::WebAgent(format!(
+                "refusing to fetch non-HTTP URL {url}"
+            )));
+        }
+        if !self.allow_private {
+            check_url(&parsed)?;
+        }
+        Ok(parsed)
+    }
+
+    async fn send(request: reqwest::RequestBuilder) -> Result<String, KowalskiError> {
+        Ok(request.send().await?.error_for_status()?.text().await?)
+    }
+}
+
+#[async_trait]
+impl PageFetcher for HttpFetcher {
+    async fn fetch(&self, url: &str) -> Result<String, KowalskiError> {
+        Self::send(self.client.get(self.parse(url)?)).await
+    }
+
+    async fn fetch_with_profile(&self, url: &str, profile: &str) -> Result<String, KowalskiError> {
+        let client = self.profiles.client(profile)?;
+        Self::send(client.get(self.parse(url)?)).await
+    }
+}
+
+/// DuckDuckGo wraps targets as `//duckduckgo.com/l/?uddg=<encoded>`; other links pass through.
+fn resolve_redirect(href: &str) -> Option<String> {
+    let absolute = if href.starts_with("//") {
+        format!("https:{href}")
+    } else {
+        href.to_string()
+    };
+    let parsed = url::Url::parse(&absolute).ok()?;
+    if parsed.path() == "/l/"
+        && let Some((_, target)) = parsed.query_pairs().find(|(k, _)| k == "uddg")
+    {
+        return Some(target.into_owned());
+    }
+    matches!(parsed.scheme(), "http" | "https").then_some(absolute)
+}
+
+fn clean_text(html: &str) -> String {
+    let text = decode_entities(&TAGS.replace_all(html, ""));
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,232 @@
+//! Web search and page reading as [`Tool`]s, so any agent can call them. Their output names its
+//! source (the search engine, the page URL) for the answer to cite.
+
+use super::search::Profiled;
+use super::{
+    CrawlOptions, DEFAULT_MAX_SOURCE_CHARS, DuckDuckGoSearch, HttpFetcher, MAX_CRAWL_DEPTH,
+    MAX_RESEARCH_SOURCES, PageFetcher, SearchProvider, crawl_with_progress, page_markdown,
+};
+use crate::config::WebAgentConfig;
+use crate::error::KowalskiError;
+use crate::progress::{NoProgress, ProgressReporter};
+use crate::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+
+const DEFAULT_SEARCH_RESULTS: usize = 5;
+
+fn required<'a>(input: &'a ToolInput, name: &str) -> Result<&'a str, KowalskiError> {
+    input
+        .parameters
+        .get(name)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| {
+            KowalskiError::ToolInvalidInput(format!("Missing required parameter: {name}"))
+        })
+}
+
+/// `web_search`: titles, URLs and snippets for a query (DuckDuckGo by default).
+#[derive(Clone)]
+pub struct WebSearchTool {
+    search: Arc<dyn SearchProvider>,
+}
+
+impl WebSearchTool {
+    pub fn new() -> Result<Self, KowalskiError> {
+        Ok(Self::with_search(Arc::new(DuckDuckGoSearch::new()?)))
+    }
+
+    pub fn with_search(search: Arc<dyn SearchProvider>) -> Self {
+        Self { search }
+    }
+}
+
+#[async_trait]
+impl Tool for WebSearchTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let query = required(&input, "query")?;
+        let limit = input
+            .parameters
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_SEARCH_RESULTS, |n| n as usize)
+            .clamp(1, MAX_RESEARCH_SOURCES);
+        let results = self.search.search(query, limit).await?;
+        Ok(
+            ToolOutput::new(json!({ "query": query, "results": results }), None)
+                .with_source(self.search.name()),
+        )
+    }
+
+    fn name(&self) -> &str {
+        "web_search"
+    }
+
+    fn description(&self) -> &str {
+        "Searches the web. Returns the title, URL and snippet of the top results."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "query".to_string(),
+                description: "What to search for".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "limit".to_string(),
+                description: format!("Number of results (1-{MAX_RESEARCH_SOURCES})"),
+                required: false,
+                default_value: Some(DEFAULT_SEARCH_RESULTS.to_string()),
+                parameter_type: ParameterType::Number,
+            },
+        ]
+    }
+}
+
+/// `web_scrape`: a page's main content as Markdown, capped like [`WebAgent`](super::WebAgent)
+/// sources. With `follow_links` it [crawls](super::crawl) the page's site up to `max_depth` hops
+/// and returns every page read, each once. With `profile` every request goes through that
+/// [`HttpProfile`](crate::config::HttpProfile).
+#[derive(Clone)]
+pub struct WebScrapeTool {
+    fetcher: Arc<dyn PageFetcher>,
+    max_chars: usize,
+    progress: Arc<dyn ProgressReporter>,
+    /// Offered to the model in the `profile` parameter description.
+    profile_names: Vec<String>,
+}
+
+impl WebScrapeTool {
+    pub fn new() -> Result<Self, KowalskiError> {
+        Ok(Self::with_fetcher(Arc::new(HttpFetcher::new()?)))
+    }
+
+    /// Plain HTTP fetching with the `[web]` profiles available by name.
+    pub fn with_profiles(config: &WebAgentConfig) -> Result<Self, KowalskiError> {
+        let fetcher = HttpFetcher::new()?.with_profiles(config)?;
+        Ok(Self {
+            profile_names: config.profiles.iter().map(|p| p.name.clone()).collect(),
+            ..Self::with_fetcher(Arc::new(fetcher))
+        })
+    }
+
+    pub fn with_fetcher(fetcher: Arc<dyn PageFetcher>) -> Self {
+        Self {
+            fetcher,
+            max_chars: DEFAULT_MAX_SOURCE_CHARS,
+            progress: Arc::new(NoProgress),
+            profile_names: Vec::new(),
+        }
+    }
+
+    /// Caps the returned Markdown (default [`DEFAULT_MAX_SOURCE_CHARS`]).
+    pub fn with_max_chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = max_chars;
+        self
+    }
+
+    /// Receives a progress update per page when following links.
+    pub fn with_progress(mut self, progress: Arc<dyn ProgressReporter>) -> Self {
+        self.progress = progress;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for WebScrapeTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let url = required(&input, "url")?;
+        let profile = input
+            .parameters
+            .get("profile")
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.is_empty());
+        let profiled;
+        let fetcher: &dyn PageFetcher = match profile {
+            Some(profile) => {
+                profiled = Profiled {
+                    fetcher: self.fetcher.as_ref(),
+                    profile,
+                };
+                &profiled
+            }
+            None => self.fetcher.as_ref(),
+        };
+        let follow_links = input
+            .parameters
+            .get("follow_links")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if follow_links {
+            let options = CrawlOptions {
+                max_depth: input
+                    .parameters
+                    .get("max_depth")
+                    .and_then(|v| v.as_u64())
+                    .map_or(1, |n| n as usize),
+                max_chars: self.max_chars,
+                ..CrawlOptions::default()
+            };
+            let pages = crawl_with_progress(fetcher, url, &options, self.progress.as_ref()).await?;
+            return Ok(
+                ToolOutput::new(json!({ "url": url, "pages": pages }), None).with_source(url)
+            );
+        }
+        let markdown = page_markdown(fetcher, url, self.max_chars).await?;
+        Ok(ToolOutput::new(json!({ "url": url, "markdown": markdown }), None).with_source(url))
+    }
+
+    fn name(&self) -> &str {
+        "web_scrape"
+    }
+
+    fn description(&self) -> &str {
+        "Reads a web page (http or https URL) and returns its main content as Markdown. Can also follow the page's links within the same site."
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![
+            ToolParameter {
+                name: "url".to_string(),
+                description: "Page to read".to_string(),
+                required: true,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+            ToolParameter {
+                name: "follow_links".to_string(),
+                description: "Also read the same-site pages it links to".to_string(),
+                required: false,
+                default_value: Some("false".to_string()),
+                parameter_type: ParameterType::Boolean,
+            },
+            ToolParameter {
+                name: "max_depth".to_string(),
+                description: format!("Link hops to follow (0-{MAX_CRAWL_DEPTH})"),
+                required: false,
+                default_value: Some("1".to_string()),
+                parameter_type: ParameterType::Number,
+            },
+            ToolParameter {
+                name: "profile".to_string(),
+                description: if self.profile_names.is_empty() {
+                    "Named HTTP profile (headers, cookies, login) to send the requests with"
+                        .to_string()
+                } else {
+                    format!(
+                        "Named HTTP profile (headers, cookies, login) to send the requests with; only when asked to. One of: {}",
+                        self.profile_names.join(", ")
+                    )
+                },
+                required: false,
+                default_value: None,
+                parameter_type: ParameterType::String,
+            },
+        ]
+    }
+}
@@ -0,0 +1,95 @@
+//! Integration test: the Ollama provider, `ModelManager` and `BaseAgent` share one pooled HTTP
+//! client, so consecutive requests to one endpoint go over a single kept-alive connection. A
+//! counting TCP proxy sits in front of a mock Ollama.
+
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use kowalski_core::agent::BaseAgent;
+use kowalski_core::config::Config;
+use kowalski_core::conversation::Message;
+use kowalski_core::llm::{LLMProvider, OllamaProvider};
+use kowalski_core::model::ModelManager;
+use kowalski_core::testing::memory;
+use kowalski_core::tools::manager::ToolManager;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Starts the mock Ollama behind a proxy; returns the proxy's port and its connection count.
+async fn spawn_counted_ollama() -> (u16, Arc<AtomicUsize>) {
+    let app = Router::new()
+        .route(
+            "/api/tags",
+            get(|| async {
+                Json(json!({"models": [{"name": "llama3.2:latest", "size": 1, "digest": "d", "modified_at": "now"}]}))
+            }),
+        )
+        .route(
+            "/api/chat",
+            post(|Json(_): Json<Value>| async {
+                Json(json!({"message": {"role": "assistant", "content": "hi"}, "done": true}))
+            }),
+        );
+    let backend = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let backend_addr = backend.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(backend, app).await.unwrap() });
+
+    let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = proxy.local_addr().unwrap().port();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counted = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((mut client, _)) = proxy.accept().await {
+            counted.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut server = tokio::net::TcpStream::connect(backend_addr).await.unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+            });
+        }
+    });
+    (port, connections)
+}
+
+#[tokio::test]
+async fn model_manager_provider_and_agent_reuse_one_connection() {
+    let (port, connections) = spawn_counted_ollama().await;
+    let base_url = format!("http://127.0.0.1:{port}");
+
+    let models = ModelManager::new(base_url.clone()).unwrap();
+    assert_eq!(models.list_models().await.unwrap().models.len(), 1);
+    assert_eq!(models.list_models().await.unwrap().models.len(), 1);
+
+    let provider = OllamaProvider::new("127.0.0.1", port);
+    let message = Message {
+        role: "user".to_string(),
+        content: "hello".to_string(),
+        tool_calls: None,
+        images: None,
+        tool_call_id: None,
+        tool_name: None,
+    };
+    assert_eq!(provider.chat("llama3.2", &[message]).await.unwrap(), "hi");
+
+    let agent = BaseAgent::new(
+        Config::default(),
+        "pooled",
+        "pooled",
+        Arc::new(provider),
+        memory(),
+        memory(),
+        memory(),
+        ToolManager::new(),
+    )
+    .await
+    .unwrap();
+    let tags = agent
+        .client
+        .get(format!("{base_url}/api/tags"))
+        .send()
+        .await
+        .unwrap();
+    assert!(tags.status().is_success());
+    tags.bytes().await.unwrap();
+
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
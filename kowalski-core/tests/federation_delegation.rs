@@ -0,0 +1,176 @@
+//! Integration test: two in-process `BaseAgent` workers behind a mock Ollama; the orchestrator
+//! delegates by capability, by name and by best match, gets correlated results with tool traces back, and
+//! marks a task that never answers as failed after its timeout.
+
+use axum::routing::post;
+use axum::{Json, Router};
+use kowalski_core::agent::{Agent, BaseAgent};
+use kowalski_core::config::Config;
+use kowalski_core::error::KowalskiError;
+use kowalski_core::federation::{
+    AgentRegistry, FederationOrchestrator, FederationWorker, MpscBroker, TaskSpec, TaskStatus,
+};
+use kowalski_core::tools::HtmlToMarkdownTool;
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Mock `/api/chat`: "convert" asks for a tool, a tool result gets a final answer,
+/// "stall" never replies in time, anything else gets a plain answer.
+async fn mock_ollama_chat(Json(body): Json<Value>) -> Json<Value> {
+    let last = body["messages"]
+        .as_array()
+        .and_then(|m| m.last())
+        .and_then(|m| m["content"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    let content = if last.starts_with("Based on the tool result") {
+        "Converted: # Hello".to_string()
+    } else if last.contains("convert") {
+        json!({"name": "html_to_markdown", "parameters": {"html": "<h1>Hello</h1>"}}).to_string()
+    } else if last.contains("stall") {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        "too late".to_string()
+    } else {
+        "Plain answer.".to_string()
+    };
+    Json(json!({"message": {"role": "assistant", "content": content}, "done": true}))
+}
+
+async fn spawn_mock_ollama() -> SocketAddr {
+    let app = Router::new().route("/api/chat", post(mock_ollama_chat));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr
+}
+
+async fn agent(ollama: SocketAddr, dir: &tempfile::TempDir) -> BaseAgent {
+    let mut config = Config::default();
+    config.ollama.host = ollama.ip().to_string();
+    config.ollama.port = ollama.port();
+    config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+    <BaseAgent as Agent>::new(config).await.unwrap()
+}
+
+struct Federation {
+    orchestrator: FederationOrchestrator,
+    registry: Arc<AgentRegistry>,
+    _dirs: Vec<tempfile::TempDir>,
+}
+
+/// `converter` (capability `markdown`, has the HTML tool) and `chatter` (capability `chat`).
+async fn federation() -> Federation {
+    let ollama = spawn_mock_ollama().await;
+    let broker = Arc::new(MpscBroker::new());
+    let registry = Arc::new(AgentRegistry::new());
+    let dirs = vec![tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap()];
+
+    let converter = agent(ollama, &dirs[0]).await;
+    converter.tool_manager.register(HtmlToMarkdownTool::new());
+    let chatter = agent(ollama, &dirs[1]).await;
+
+    for (id, cap, agent) in [
+        ("converter", "markdown", converter),
+        ("chatter", "chat", chatter),
+    ] {
+        registry.register_agent(id, &agent, &[cap]).await.unwrap();
+        FederationWorker::new(id, "llama3.2")
+            .with_registry(registry.clone())
+            .spawn(agent, broker.clone());
+    }
+
+    let orchestrator = FederationOrchestrator::new(registry.clone(), broker.clone());
+    orchestrator.listen_for_results(broker.subscribe("federation", 64));
+    Federation {
+        orchestrator,
+        registry,
+        _dirs: dirs,
+    }
+}
+
+#[tokio::test]
+async fn delegates_and_returns_results_with_tool_trace() {
+    let fed = federation().await;
+
+    let result = fed
+        .orchestrator
+        .delegate(
+            TaskSpec::for_capability("markdown", "Please convert the page")
+                .with_task_id("t-convert")
+                .with_timeout(Duration::from_secs(10)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.task_id, "t-convert");
+    assert_eq!(result.agent_id, "converter");
+    assert_eq!(result.answer, "Converted: # Hello");
+    assert_eq!(result.tool_trace.len(), 1);
+    assert_eq!(result.tool_trace[0].name, "html_to_markdown");
+    assert!(result.tool_trace[0].success);
+    assert!(result.tool_trace[0].result.contains("Hello"));
+    assert_eq!(result.usage.llm_calls, 2);
+    assert_eq!(result.usage.tool_calls, 1);
+    assert_eq!(
+        fed.registry.task_status("t-convert"),
+        Some(TaskStatus::Completed)
+    );
+
+    let result = fed
+        .orchestrator
+        .delegate(
+            TaskSpec::for_agent("chatter", "Say something").with_timeout(Duration::from_secs(10)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.agent_id, "chatter");
+    assert_eq!(result.answer, "Plain answer.");
+    assert!(result.tool_trace.is_empty());
+    assert_eq!(result.usage.llm_calls, 1);
+
+    // Tools registered on the agent become discoverable capabilities.
+    assert_eq!(
+        fed.registry.find_by_tool("html_to_markdown")[0].id,
+        "converter"
+    );
+    let result = fed
+        .orchestrator
+        .delegate(
+            TaskSpec::best_match("Please convert this html page to markdown")
+                .with_timeout(Duration::from_secs(10)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.agent_id, "converter");
+
+    let err = fed
+        .orchestrator
+        .delegate(TaskSpec::for_agent("nobody", "hello"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, KowalskiError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn times_out_and_marks_task_failed() {
+    let fed = federation().await;
+
+    let err = fed
+        .orchestrator
+        .delegate(
+            TaskSpec::for_agent("chatter", "Please stall")
+                .with_task_id("t-stall")
+                .with_timeout(Duration::from_millis(300)),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, KowalskiError::Timeout { .. }), "{err}");
+
+    let record = fed.registry.task("t-stall").unwrap();
+    assert_eq!(record.status, TaskStatus::Failed);
+    assert_eq!(record.agent_id, "chatter");
+    assert!(record.error.unwrap().contains("no result within"));
+}
@@ -0,0 +1,142 @@
+//! Integration test: a general agent hands a 10-message conversation to a code specialist.
+//! The receiver keeps the history intact, records where it came from, acks the handoff, and
+//! mirrors its answer back to the originator.
+
+use kowalski_core::agent::{Agent, BaseAgent};
+use kowalski_core::config::Config;
+use kowalski_core::conversation::{
+    Conversation, HANDOFF_CONVERSATION_ID, HANDOFF_FROM_AGENT, HANDOFF_REASON, Message,
+};
+use kowalski_core::federation::{
+    AgentRecord, AgentRegistry, FederationOrchestrator, FederationWorker, MpscBroker,
+};
+use kowalski_core::testing::{MockBackend, MockReply, memory};
+use kowalski_core::tools::manager::ToolManager;
+use std::sync::Arc;
+use std::time::Duration;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Answers every request with the same code.
+fn coder() -> Arc<MockBackend> {
+    Arc::new(
+        MockBackend::script()
+            .otherwise(|_| {
+                MockReply::text(
+                    "fn parse(input: &str) -> u32 { input.trim().parse().unwrap_or(0) }",
+                )
+            })
+            .build(),
+    )
+}
+
+async fn agent(name: &str, llm: Arc<MockBackend>) -> BaseAgent {
+    BaseAgent::new(
+        Config::default(),
+        name,
+        name,
+        llm,
+        memory(),
+        memory(),
+        memory(),
+        ToolManager::new(),
+    )
+    .await
+    .unwrap()
+}
+
+/// Ten alternating turns ending with an unanswered user question.
+async fn ten_message_conversation(general: &mut BaseAgent) -> Conversation {
+    let id = general.start_conversation("llama3.2");
+    for i in 0..9 {
+        let role = if i % 2 == 0 { "user" } else { "assistant" };
+        general
+            .add_message(&id, role, &format!("{role} turn {i}"))
+            .await;
+    }
+    general
+        .add_message(&id, "user", "Now write the parser in Rust.")
+        .await;
+    let conversation = general.get_conversation(&id).unwrap().clone();
+    assert_eq!(conversation.messages.len(), 10);
+    conversation
+}
+
+#[tokio::test]
+async fn accepted_conversation_keeps_history_and_origin() {
+    let mut general = agent("general", coder()).await;
+    let mut code = agent("code", coder()).await;
+    let conversation = ten_message_conversation(&mut general).await;
+
+    let id = code
+        .accept_handoff(&conversation, "general", "this turned into a code question")
+        .unwrap();
+    assert_ne!(id, conversation.id);
+    let local = code.get_conversation(&id).unwrap();
+    assert_eq!(local.messages, conversation.messages);
+    assert_eq!(local.model, conversation.model);
+    assert_eq!(local.metadata[HANDOFF_FROM_AGENT], "general");
+    assert_eq!(local.metadata[HANDOFF_CONVERSATION_ID], conversation.id);
+    assert_eq!(
+        local.metadata[HANDOFF_REASON],
+        "this turned into a code question"
+    );
+    // The sender's copy is untouched.
+    assert_eq!(
+        general.get_conversation(&conversation.id).unwrap().messages,
+        conversation.messages
+    );
+}
+
+#[tokio::test]
+async fn handoff_is_acked_answered_and_mirrored() {
+    let mut general = agent("general", coder()).await;
+    let conversation = ten_message_conversation(&mut general).await;
+    let code_llm = coder();
+    let code = agent("code", code_llm.clone()).await;
+
+    let registry = Arc::new(AgentRegistry::new());
+    registry
+        .register(AgentRecord::new("code", vec!["code".into()]))
+        .unwrap();
+    let broker = Arc::new(MpscBroker::new());
+    FederationWorker::new("code", "llama3.2")
+        .with_registry(registry.clone())
+        .spawn(code, broker.clone());
+    let orchestrator = FederationOrchestrator::new(registry, broker.clone());
+    orchestrator.listen_for_results(broker.subscribe("federation", 64));
+
+    let mut receipt = orchestrator
+        .handoff(
+            "general",
+            "code",
+            &conversation,
+            "this turned into a code question",
+            true,
+            WAIT,
+        )
+        .await
+        .unwrap();
+    assert_eq!(receipt.agent_id, "code");
+    assert_ne!(receipt.conversation_id, conversation.id);
+
+    let mirror = receipt.mirror.as_mut().unwrap();
+    let answer = tokio::time::timeout(WAIT, mirror.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(answer.role, "assistant");
+    assert!(answer.content.starts_with("fn parse"));
+
+    // The specialist answered with the full, ordered history in its context.
+    let requests = code_llm.requests();
+    let seen: Vec<Message> = requests
+        .last()
+        .unwrap()
+        .messages
+        .iter()
+        .filter(|m| m.role != "system")
+        .cloned()
+        .collect();
+    assert_eq!(seen, conversation.messages);
+}
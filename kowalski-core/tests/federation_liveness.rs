@@ -0,0 +1,106 @@
+//! Integration test: two heartbeating workers share a capability; one stops (its heartbeat with
+//! it). The registry marks it unresponsive, then removes it, emitting events, and a task queued
+//! on it is re-routed to the surviving worker.
+
+use kowalski_core::agent::BaseAgent;
+use kowalski_core::federation::{
+    AgentRecord, AgentRegistry, AgentStatus, FederationOrchestrator, FederationWorker,
+    LivenessPolicy, MpscBroker, RegistryEvent, TaskSpec, TaskStatus,
+};
+use kowalski_core::testing::{self, MockBackend};
+use kowalski_core::tools::manager::ToolManager;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const BEAT: Duration = Duration::from_millis(50);
+const WAIT: Duration = Duration::from_secs(5);
+
+async fn echo_agent() -> BaseAgent {
+    let backend = MockBackend::script().echoes("echo: ").build();
+    testing::agent(Arc::new(backend), ToolManager::new())
+        .await
+        .unwrap()
+}
+
+async fn next_event(events: &mut broadcast::Receiver<RegistryEvent>) -> RegistryEvent {
+    tokio::time::timeout(WAIT, events.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn silent_worker_is_dropped_and_its_queued_task_rerouted() {
+    let broker = Arc::new(MpscBroker::new());
+    let registry = Arc::new(AgentRegistry::new());
+    let mut events = registry.subscribe_events();
+    registry.track_heartbeats(broker.subscribe("federation", 64));
+    registry.spawn_liveness_monitor(LivenessPolicy {
+        heartbeat_interval: BEAT,
+        unresponsive_after_missed: 3,
+        remove_after_missed: 8,
+    });
+
+    // Build agents up front: construction blocks the runtime and would starve early heartbeats.
+    let agents = [echo_agent().await, echo_agent().await];
+    let mut workers = Vec::new();
+    for (id, agent) in ["w1", "w2"].into_iter().zip(agents) {
+        registry
+            .register(AgentRecord::new(id, vec!["echo".into()]))
+            .unwrap();
+        workers.push(
+            FederationWorker::new(id, "llama3.2")
+                .with_registry(registry.clone())
+                .with_heartbeat(BEAT)
+                .spawn(agent, broker.clone()),
+        );
+    }
+    let orchestrator = FederationOrchestrator::new(registry.clone(), broker.clone());
+    orchestrator.listen_for_results(broker.subscribe("federation", 64));
+
+    // Both alive: capability routing picks w1 (ties break by id).
+    tokio::time::sleep(BEAT * 4).await;
+    assert_eq!(registry.agent_status("w1"), Some(AgentStatus::Active));
+    let result = orchestrator
+        .delegate(TaskSpec::for_capability("echo", "first").with_timeout(WAIT))
+        .await
+        .unwrap();
+    assert_eq!(result.agent_id, "w1");
+
+    // w1 crashes: no more heartbeats, and a task routed to it stays queued until re-routed.
+    workers[0].abort();
+    let result = orchestrator
+        .delegate(
+            TaskSpec::for_capability("echo", "second")
+                .with_task_id("t-reroute")
+                .with_timeout(WAIT),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.agent_id, "w2");
+    assert_eq!(result.answer, "echo: second");
+    let record = registry.task("t-reroute").unwrap();
+    assert_eq!(record.agent_id, "w2");
+    assert_eq!(record.status, TaskStatus::Completed);
+
+    let joined = |id: &str| RegistryEvent::AgentJoined {
+        agent_id: id.into(),
+    };
+    assert_eq!(next_event(&mut events).await, joined("w1"));
+    assert_eq!(next_event(&mut events).await, joined("w2"));
+    assert_eq!(
+        next_event(&mut events).await,
+        RegistryEvent::AgentUnresponsive {
+            agent_id: "w1".into()
+        }
+    );
+    assert_eq!(
+        next_event(&mut events).await,
+        RegistryEvent::AgentLeft {
+            agent_id: "w1".into()
+        }
+    );
+    assert!(registry.get("w1").is_none());
+    assert_eq!(registry.agent_status("w2"), Some(AgentStatus::Active));
+}
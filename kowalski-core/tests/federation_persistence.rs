@@ -0,0 +1,124 @@
+//! Integration test: a registry backed by a `RegistryStore` is dropped mid-task and rebuilt from
+//! the same SQLite file. Registrations come back, stale ones expire, and the interrupted task
+//! resumes as Queued and completes through a new `Coordinator`.
+
+use kowalski_core::agent::BaseAgent;
+use kowalski_core::config::FederationConfig;
+use kowalski_core::federation::{
+    AgentRecord, AgentRegistry, Coordinator, FederationOrchestrator, FederationWorker, MpscBroker,
+    QueuedTask, RegistryStore, TaskPriority, TaskSpec, TaskStatus,
+};
+use kowalski_core::testing::{self, MockBackend};
+use kowalski_core::tools::manager::ToolManager;
+use std::sync::Arc;
+use std::time::Duration;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+async fn echo_agent() -> BaseAgent {
+    let backend = MockBackend::script().echoes("echo: ").build();
+    testing::agent(Arc::new(backend), ToolManager::new())
+        .await
+        .unwrap()
+}
+
+fn config(dir: &tempfile::TempDir) -> FederationConfig {
+    FederationConfig {
+        persistence_path: Some(dir.path().to_string_lossy().to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn interrupted_task_resumes_after_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let registry = AgentRegistry::from_config(&config(&dir)).await.unwrap();
+        registry
+            .register(AgentRecord::new("w1", vec!["echo".into()]))
+            .unwrap();
+        let spec = TaskSpec::for_capability("echo", "pick up where we left off")
+            .with_task_id("t-resume")
+            .with_priority(TaskPriority::High)
+            .with_timeout(WAIT);
+        registry.record_queued_task(&QueuedTask::new(spec));
+        registry
+            .set_task_status("t-resume", "w1", TaskStatus::Running, None)
+            .unwrap();
+        registry
+            .set_task_status("t-direct", "w1", TaskStatus::Running, None)
+            .unwrap();
+        registry
+            .set_task_status("t-done", "w1", TaskStatus::Completed, None)
+            .unwrap();
+        registry.flush().await;
+    }
+
+    let registry = Arc::new(AgentRegistry::from_config(&config(&dir)).await.unwrap());
+    let recovered = registry.recover().await.unwrap();
+    assert_eq!(recovered.agents, ["w1"]);
+    assert!(recovered.expired.is_empty());
+    assert_eq!(registry.get("w1").unwrap().capabilities, ["echo"]);
+
+    assert_eq!(registry.task_status("t-resume"), Some(TaskStatus::Queued));
+    assert_eq!(registry.task_status("t-done"), Some(TaskStatus::Completed));
+    // Delegated without a coordinator: no spec was kept, so it cannot be resumed.
+    assert_eq!(registry.task_status("t-direct"), Some(TaskStatus::Failed));
+    assert_eq!(recovered.pending.len(), 1);
+    let pending = &recovered.pending[0].spec;
+    assert_eq!(pending.task_id, "t-resume");
+    assert_eq!(pending.instruction, "pick up where we left off");
+    assert_eq!(pending.priority, TaskPriority::High);
+
+    let broker = Arc::new(MpscBroker::new());
+    FederationWorker::new("w1", "llama3.2")
+        .with_registry(registry.clone())
+        .spawn(echo_agent().await, broker.clone());
+    let orchestrator = FederationOrchestrator::new(registry.clone(), broker.clone());
+    orchestrator.listen_for_results(broker.subscribe("federation", 64));
+    let coordinator = Coordinator::new(Arc::new(orchestrator), 1);
+    let handle = coordinator.resume(recovered.pending).pop().unwrap();
+    let result = tokio::time::timeout(WAIT, handle).await.unwrap().unwrap();
+    assert_eq!(result.answer, "echo: pick up where we left off");
+    assert_eq!(
+        registry.task_status("t-resume"),
+        Some(TaskStatus::Completed)
+    );
+}
+
+#[tokio::test]
+async fn stale_registrations_expire_on_recover() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.sqlite");
+    let path = path.to_str().unwrap();
+    {
+        let store = RegistryStore::open(path, Duration::from_secs(60))
+            .await
+            .unwrap();
+        let registry = AgentRegistry::new().with_store(store);
+        registry
+            .register(AgentRecord::new("gone", vec!["chat".into()]))
+            .unwrap();
+        registry.flush().await;
+    }
+    tokio::time::sleep(Duration::from_millis(30)).await;
+
+    let store = RegistryStore::open(path, Duration::from_millis(10))
+        .await
+        .unwrap();
+    let registry = AgentRegistry::new().with_store(store);
+    let recovered = registry.recover().await.unwrap();
+    assert_eq!(recovered.expired, ["gone"]);
+    assert!(registry.get("gone").is_none());
+
+    // Expired rows are deleted, so even a lenient reload does not bring them back.
+    let lenient = RegistryStore::open(path, Duration::from_secs(60))
+        .await
+        .unwrap();
+    let recovered = AgentRegistry::new()
+        .with_store(lenient)
+        .recover()
+        .await
+        .unwrap();
+    assert!(recovered.agents.is_empty());
+}
@@ -0,0 +1,160 @@
+//! Integration test: a `Coordinator` over in-process workers runs queued tasks by priority,
+//! spreads load across workers, retries a failed attempt on another worker, and dead-letters a
+//! task that keeps failing.
+
+use kowalski_core::error::KowalskiError;
+use kowalski_core::federation::{
+    AgentRecord, AgentRegistry, Coordinator, FederationOrchestrator, FederationWorker, MpscBroker,
+    TaskPriority, TaskSpec, TaskStatus,
+};
+use kowalski_core::testing::{self, MockBackend, MockReply};
+use kowalski_core::tools::manager::ToolManager;
+use std::sync::Arc;
+use std::time::Duration;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Shared by the workers, which run on their id as model: "slow" instructions take a while, and
+/// the failing workers always error.
+fn worker_backend(failing: Vec<String>) -> Arc<MockBackend> {
+    Arc::new(
+        MockBackend::script()
+            .otherwise(move |request| {
+                let instruction = request.last_message();
+                let reply = if failing.contains(&request.model) {
+                    MockReply::Error(KowalskiError::Server("worker crashed".into()))
+                } else {
+                    MockReply::text(format!("done: {instruction}"))
+                };
+                if instruction.contains("slow") {
+                    reply.after(Duration::from_millis(200))
+                } else {
+                    reply
+                }
+            })
+            .build(),
+    )
+}
+
+struct Setup {
+    coordinator: Coordinator,
+    registry: Arc<AgentRegistry>,
+    workers: Arc<MockBackend>,
+}
+
+/// Workers `(id, fails)` all offering capability `work`.
+async fn setup(workers: &[(&str, bool)]) -> Setup {
+    let broker = Arc::new(MpscBroker::new());
+    let registry = Arc::new(AgentRegistry::new());
+    let failing = workers
+        .iter()
+        .filter(|(_, fails)| *fails)
+        .map(|(id, _)| id.to_string())
+        .collect();
+    let backend = worker_backend(failing);
+    for (id, _) in workers {
+        let agent = testing::agent(backend.clone(), ToolManager::new())
+            .await
+            .unwrap();
+        registry
+            .register(AgentRecord::new(*id, vec!["work".into()]))
+            .unwrap();
+        FederationWorker::new(*id, *id)
+            .with_registry(registry.clone())
+            .spawn(agent, broker.clone());
+    }
+    let orchestrator = FederationOrchestrator::new(registry.clone(), broker.clone());
+    orchestrator.listen_for_results(broker.subscribe("federation", 64));
+    Setup {
+        coordinator: Coordinator::new(Arc::new(orchestrator), 1),
+        registry,
+        workers: backend,
+    }
+}
+
+fn task(instruction: &str) -> TaskSpec {
+    TaskSpec::for_capability("work", instruction)
+        .with_task_id(instruction)
+        .with_timeout(WAIT)
+}
+
+#[tokio::test]
+async fn runs_queued_tasks_by_priority() {
+    let s = setup(&[("solo", false)]).await;
+    let busy = s.coordinator.submit(task("slow first"));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(s.coordinator.in_flight("solo"), 1);
+
+    let mut low = s
+        .coordinator
+        .submit(task("low").with_priority(TaskPriority::Low));
+    let normal = s.coordinator.submit(task("normal"));
+    let high = s
+        .coordinator
+        .submit(task("high").with_priority(TaskPriority::High));
+    assert!(low.try_result().is_none());
+    assert_eq!(low.status(), Some(TaskStatus::Queued));
+
+    for handle in [busy, high, normal, low] {
+        let result = tokio::time::timeout(WAIT, handle).await.unwrap().unwrap();
+        assert_eq!(result.agent_id, "solo");
+    }
+    let started: Vec<String> = s
+        .workers
+        .requests()
+        .iter()
+        .map(|r| r.last_message().to_string())
+        .collect();
+    assert_eq!(started, ["slow first", "high", "normal", "low"]);
+    assert_eq!(s.registry.task_status("low"), Some(TaskStatus::Completed));
+}
+
+#[tokio::test]
+async fn spreads_load_across_workers() {
+    let s = setup(&[("a", false), ("b", false)]).await;
+    let first = s.coordinator.submit(task("slow one"));
+    let second = s.coordinator.submit(task("slow two"));
+    let mut agents = vec![
+        first.await.unwrap().agent_id,
+        second.await.unwrap().agent_id,
+    ];
+    agents.sort();
+    assert_eq!(agents, ["a", "b"]);
+}
+
+#[tokio::test]
+async fn retries_failed_task_on_another_worker() {
+    let s = setup(&[("flaky", true), ("steady", false)]).await;
+    let result = s
+        .coordinator
+        .submit(task("retry me").with_max_retries(1, Duration::from_millis(10)))
+        .await
+        .unwrap();
+    assert_eq!(result.agent_id, "steady");
+    assert_eq!(result.answer, "done: retry me");
+    let record = s.registry.task("retry me").unwrap();
+    assert_eq!(record.status, TaskStatus::Completed);
+    assert_eq!(record.agent_id, "steady");
+    assert!(s.registry.dead_letters().is_empty());
+}
+
+#[tokio::test]
+async fn dead_letters_after_exhausting_retries() {
+    let s = setup(&[("flaky", true)]).await;
+    let err = s
+        .coordinator
+        .submit(task("doomed").with_max_retries(2, Duration::from_millis(10)))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("gave up after 3 attempt(s)"),
+        "{err}"
+    );
+
+    let letters = s.registry.dead_letters();
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].task_id, "doomed");
+    assert_eq!(letters[0].attempts.len(), 3);
+    assert!(letters[0].attempts.iter().all(|a| a.agent_id == "flaky"));
+    assert_eq!(s.registry.task_status("doomed"), Some(TaskStatus::Failed));
+}
@@ -0,0 +1,130 @@
+//! Integration test: a `SupervisorAgent` plans a request into four subtasks for web, academic and
+//! data workers, runs the independent ones in parallel and the dependent ones in order, and its
+//! synthesized answer carries every subtask output.
+
+use kowalski_core::federation::{
+    AgentRecord, AgentRegistry, FederationOrchestrator, FederationWorker, MpscBroker,
+    SupervisorAgent, SupervisorEvent,
+};
+use kowalski_core::testing::{self, MockBackend, MockReply};
+use kowalski_core::tools::manager::ToolManager;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PLAN: &str = r#"```json
+{"tasks": [
+  {"id": "web", "description": "Search the web for Rust async runtimes", "required_capability": "web_search", "depends_on": []},
+  {"id": "papers", "description": "Find papers on async runtimes", "required_capability": "academic", "depends_on": []},
+  {"id": "summary", "description": "Summarize the findings", "required_capability": "academic", "depends_on": ["web", "papers"]},
+  {"id": "table", "description": "Produce a comparison table", "required_capability": "data", "depends_on": ["summary"]}
+]}
+```"#;
+
+/// Shared by the workers, which run on the worker's id as model: each answers after 50ms with
+/// `<id did: first line of the instruction>`.
+fn worker_backend() -> Arc<MockBackend> {
+    Arc::new(
+        MockBackend::script()
+            .otherwise(|request| {
+                let step = request.last_message().lines().next().unwrap_or_default();
+                MockReply::text(format!("<{} did: {step}>", request.model))
+                    .after(Duration::from_millis(50))
+            })
+            .build(),
+    )
+}
+
+/// Returns the plan, then echoes the synthesis context back as the final answer.
+fn supervisor_backend() -> MockBackend {
+    MockBackend::script()
+        .otherwise(|request| {
+            let system = &request.messages[0].content;
+            let user = &request.messages[1].content;
+            if system.contains("JSON only") {
+                assert!(user.contains("web_search") && user.contains("data"));
+                return MockReply::text(PLAN);
+            }
+            MockReply::text(format!("FINAL\n{user}"))
+        })
+        .build()
+}
+
+#[tokio::test]
+async fn supervisor_runs_plan_in_dependency_order_and_synthesizes() {
+    let broker = Arc::new(MpscBroker::new());
+    let registry = Arc::new(AgentRegistry::new());
+    let workers = worker_backend();
+    for (id, capability) in [
+        ("web", "web_search"),
+        ("academic", "academic"),
+        ("data", "data"),
+    ] {
+        let agent = testing::agent(workers.clone(), ToolManager::new())
+            .await
+            .unwrap();
+        registry
+            .register(AgentRecord::new(id, vec![capability.into()]))
+            .unwrap();
+        FederationWorker::new(id, id)
+            .with_registry(registry.clone())
+            .spawn(agent, broker.clone());
+    }
+    let orchestrator = FederationOrchestrator::new(registry.clone(), broker.clone());
+    orchestrator.listen_for_results(broker.subscribe("federation", 64));
+    let supervisor = SupervisorAgent::new(
+        Arc::new(supervisor_backend()),
+        "llama3.2",
+        Arc::new(orchestrator),
+    )
+    .with_task_timeout(Duration::from_secs(5));
+    let mut events = supervisor.subscribe();
+
+    let run = supervisor
+        .run("Research Rust async runtimes, then summarize and tabulate")
+        .await
+        .unwrap();
+
+    // Independent steps overlapped; dependent ones waited and saw earlier outputs.
+    assert!(workers.peak_in_flight() >= 2);
+    let started: Vec<String> = workers
+        .requests()
+        .iter()
+        .map(|r| r.last_message().to_string())
+        .collect();
+    assert_eq!(started.len(), 4);
+    let position = |step: &str| started.iter().position(|s| s.starts_with(step)).unwrap();
+    assert!(position("Summarize") > position("Search the web"));
+    assert!(position("Summarize") > position("Find papers"));
+    assert!(position("Produce a comparison table") > position("Summarize"));
+    let summary_input = &started[position("Summarize")];
+    assert!(summary_input.contains("<web did: Search the web for Rust async runtimes>"));
+    assert!(summary_input.contains("<academic did: Find papers on async runtimes>"));
+
+    let order: Vec<&str> = run.results.iter().map(|r| r.task_id.as_str()).collect();
+    assert_eq!(&order[2..], ["summary", "table"]);
+    assert!(run.answer.starts_with("FINAL"));
+    for result in &run.results {
+        assert!(run.answer.contains(&result.output), "{}", result.output);
+    }
+    assert_eq!(
+        run.results.last().unwrap().output,
+        "<data did: Produce a comparison table>"
+    );
+
+    let mut seen = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        seen.push(event);
+    }
+    assert!(matches!(&seen[0], SupervisorEvent::PlanCreated { plan } if plan.tasks.len() == 4));
+    let completed = seen
+        .iter()
+        .filter(|e| matches!(e, SupervisorEvent::SubtaskCompleted { .. }))
+        .count();
+    assert_eq!(completed, 4);
+    assert_eq!(
+        seen.last(),
+        Some(&SupervisorEvent::Synthesized {
+            answer: run.answer.clone()
+        })
+    );
+}
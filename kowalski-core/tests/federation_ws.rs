@@ -0,0 +1,178 @@
+//! Integration test: a WebSocket registry node on localhost with two remote agents — a broadcast
+//! reaches everyone exactly once, a directed delegation runs on the remote worker and its result
+//! comes back, heartbeats are recorded, and a client started before the server connects once
+//! the server is up.
+
+use kowalski_core::agent::BaseAgent;
+use kowalski_core::federation::{
+    AclEnvelope, AclMessage, AgentRecord, AgentRegistry, FederationOrchestrator,
+    FederationTransport, FederationWorker, MessageBroker, MpscBroker, TaskSpec, TaskStatus,
+    WsClientOptions, WsFederationServer, WsTransport,
+};
+use kowalski_core::testing::{self, MockBackend};
+use kowalski_core::tools::manager::ToolManager;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::Receiver;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+async fn echo_agent() -> BaseAgent {
+    let backend = MockBackend::script().echoes("remote says: ").build();
+    testing::agent(Arc::new(backend), ToolManager::new())
+        .await
+        .unwrap()
+}
+
+fn options() -> WsClientOptions {
+    WsClientOptions {
+        heartbeat_interval: Duration::from_millis(50),
+        initial_backoff: Duration::from_millis(20),
+        max_backoff: Duration::from_millis(200),
+    }
+}
+
+fn client(url: &str, id: &str, capability: &str) -> WsTransport {
+    WsTransport::connect(
+        url,
+        AgentRecord::new(id, vec![capability.into()]),
+        vec!["federation".into()],
+        options(),
+    )
+}
+
+async fn recv(rx: &mut Receiver<AclEnvelope>) -> AclEnvelope {
+    tokio::time::timeout(WAIT, rx.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+async fn wait_for(mut condition: impl FnMut() -> bool) {
+    tokio::time::timeout(WAIT, async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn broadcast_and_directed_delegation_over_websocket() {
+    let registry = Arc::new(AgentRegistry::new());
+    let broker = Arc::new(MpscBroker::new());
+    let server = WsFederationServer::bind("127.0.0.1:0", registry.clone(), broker.clone())
+        .await
+        .unwrap();
+    let mut node_rx = broker.subscribe("federation", 64);
+
+    let alpha = client(&server.url(), "alpha", "search");
+    let beta = Arc::new(client(&server.url(), "beta", "echo"));
+    alpha.wait_connected(WAIT).await.unwrap();
+    beta.wait_connected(WAIT).await.unwrap();
+    wait_for(|| registry.list().len() == 2).await;
+    assert_eq!(registry.find_by_capability("echo")[0].id, "beta");
+
+    // Broadcast from alpha: the node and beta get it, alpha's own echo is deduplicated.
+    let mut alpha_rx = alpha.subscribe("federation", 64);
+    let mut beta_rx = beta.subscribe("federation", 64);
+    let ping = AclEnvelope::new(
+        "federation",
+        "alpha",
+        AclMessage::Ping {
+            text: "hello all".into(),
+        },
+    );
+    alpha.publish(&ping).await.unwrap();
+    assert_eq!(recv(&mut node_rx).await.id, ping.id);
+    assert_eq!(recv(&mut beta_rx).await.id, ping.id);
+    assert_eq!(recv(&mut alpha_rx).await.id, ping.id);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(alpha_rx.try_recv().is_err(), "echo must be deduplicated");
+    assert!(beta_rx.try_recv().is_err());
+
+    // Directed delegation from the node to the remote worker.
+    FederationWorker::new("beta", "llama3.2").spawn(echo_agent().await, beta.clone());
+    let orchestrator = FederationOrchestrator::new(registry.clone(), broker.clone());
+    orchestrator.listen_for_results(broker.subscribe("federation", 64));
+    let result = orchestrator
+        .delegate(
+            TaskSpec::for_capability("echo", "ping over the wire")
+                .with_task_id("remote-1")
+                .with_timeout(WAIT),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.agent_id, "beta");
+    assert_eq!(result.answer, "remote says: ping over the wire");
+    assert_eq!(
+        registry.task_status("remote-1"),
+        Some(TaskStatus::Completed)
+    );
+
+    // Heartbeats keep last_seen fresh.
+    let seen = server.last_seen("alpha").unwrap();
+    wait_for(|| server.last_seen("alpha").is_some_and(|t| t > seen)).await;
+
+    // Disconnecting deregisters the agent.
+    drop(alpha);
+    wait_for(|| registry.get("alpha").is_none()).await;
+    assert_eq!(server.connected_agents(), vec!["beta".to_string()]);
+}
+
+#[tokio::test]
+async fn client_connects_with_backoff_once_server_is_up() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let transport = client(&format!("ws://{addr}"), "late", "chat");
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(!transport.is_connected());
+
+    let registry = Arc::new(AgentRegistry::new());
+    let _server = WsFederationServer::bind(
+        &addr.to_string(),
+        registry.clone(),
+        Arc::new(MpscBroker::new()),
+    )
+    .await
+    .unwrap();
+    transport.wait_connected(WAIT).await.unwrap();
+    wait_for(|| registry.get("late").is_some()).await;
+    assert_eq!(transport.reconnect_count(), 0);
+}
+
+#[tokio::test]
+async fn envelopes_carry_the_connection_id_and_floods_do_not_stall() {
+    let registry = Arc::new(AgentRegistry::new());
+    let broker = Arc::new(MpscBroker::new());
+    let server = WsFederationServer::bind("127.0.0.1:0", registry.clone(), broker.clone())
+        .await
+        .unwrap();
+    let alpha = client(&server.url(), "alpha", "search");
+    let beta = client(&server.url(), "beta", "echo");
+    alpha.wait_connected(WAIT).await.unwrap();
+    beta.wait_connected(WAIT).await.unwrap();
+    wait_for(|| registry.list().len() == 2).await;
+    let mut beta_rx = beta.subscribe("federation", 1024);
+
+    // More envelopes than the server's per-connection buffers hold, all echoed back to alpha.
+    let count = 400;
+    for i in 0..count {
+        let claimed = if i == 0 { "beta" } else { "alpha" };
+        let env = AclEnvelope::new(
+            "federation",
+            claimed,
+            AclMessage::Ping {
+                text: format!("ping {i}"),
+            },
+        );
+        alpha.publish(&env).await.unwrap();
+    }
+    for i in 0..count {
+        let env = recv(&mut beta_rx).await;
+        assert_eq!(env.sender, "alpha", "envelope {i}");
+    }
+}
@@ -0,0 +1,32 @@
+#!/bin/sh
+# Tiny MCP server over stdio for `tests/mcp_client_stdio_mock.rs`.
+# Requests arrive as compact JSON with keys in alphabetical order, so `id` comes first.
+# Tools: `echo` (returns its `text`), `greet` (returns $MOCK_GREETING), `crash` (exits).
+
+echo "mock mcp server starting"
+while IFS= read -r line; do
+    id=$(printf '%s' "$line" | sed -n 's/^{"id":\([0-9]*\),.*/\1/p')
+    [ -z "$id" ] && continue
+    case "$line" in
+    *'"method":"initialize"'*)
+        echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"protocolVersion\":\"2025-03-26\",\"serverInfo\":{\"name\":\"mock-stdio\",\"version\":\"0.1.0\"},\"capabilities\":{\"tools\":{}}}}"
+        ;;
+    *'"method":"tools/list"'*)
+        echo "{\"jsonrpc\":\"2.0\",\"method\":\"notifications/message\",\"params\":{\"level\":\"info\",\"data\":\"listing\"}}"
+        echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"tools\":[{\"name\":\"echo\",\"description\":\"Echo text back\",\"inputSchema\":{\"type\":\"object\",\"properties\":{\"text\":{\"type\":\"string\",\"description\":\"Text to echo\"}},\"required\":[\"text\"]}},{\"name\":\"greet\",\"description\":\"Greeting from the environment\",\"inputSchema\":{\"type\":\"object\",\"properties\":{}}},{\"name\":\"crash\",\"description\":\"Exit the server\",\"inputSchema\":{\"type\":\"object\",\"properties\":{}}}]}}"
+        ;;
+    *'"name":"echo"'*)
+        text=$(printf '%s' "$line" | sed -n 's/.*"text":"\([^"]*\)".*/\1/p')
+        echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"$text\"}]}}"
+        ;;
+    *'"name":"greet"'*)
+        echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"$MOCK_GREETING\"}]}}"
+        ;;
+    *'"name":"crash"'*)
+        exit 1
+        ;;
+    *)
+        echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"error\":{\"code\":-32601,\"message\":\"method not found\"}}"
+        ;;
+    esac
+done
@@ -0,0 +1,26 @@
+"""Prints the area of every shape in a CSV file.
+
+def not_a_symbol():  (inside the docstring)
+"""
+
+import csv
+import sys
+
+
+class ShapeRow:
+    def __init__(self, kind, size):
+        self.kind = kind
+        self.size = float(size)
+
+    def area(self):
+        return self.size * self.size
+
+
+async def load(path):
+    with open(path) as f:
+        return [ShapeRow(*row) for row in csv.reader(f)]
+
+
+def main():
+    for row in sys.argv[1:]:
+        print(row)
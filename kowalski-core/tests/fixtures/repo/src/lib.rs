@@ -0,0 +1,47 @@
+//! Shapes and their areas.
+
+pub mod shapes;
+
+use std::fmt;
+
+/// Anything with an area.
+pub trait Area {
+    fn area(&self) -> f64;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Unit {
+    Metres,
+    Feet,
+}
+
+pub struct Measured<T> {
+    pub value: T,
+    pub unit: Unit,
+}
+
+impl<T: Area> Measured<T> {
+    pub fn new(value: T, unit: Unit) -> Self {
+        Self { value, unit }
+    }
+}
+
+impl<T> fmt::Display for Measured<T>
+where
+    T: Area,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // fn not_a_symbol() is only mentioned in a comment
+        write!(f, "{:.2} {:?}", self.value.area(), self.unit)
+    }
+}
+
+pub(crate) async fn total_area(items: &[&dyn Area]) -> f64 {
+    items.iter().map(|i| i.area()).sum()
+}
+
+macro_rules! square {
+    ($x:expr) => {
+        $x * $x
+    };
+}
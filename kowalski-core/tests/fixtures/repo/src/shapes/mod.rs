@@ -0,0 +1,44 @@
+use crate::Area;
+
+pub struct Circle {
+    pub radius: f64,
+}
+
+pub struct Rectangle {
+    pub width: f64,
+    pub height: f64,
+}
+
+pub struct Triangle {
+    pub base: f64,
+    pub height: f64,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f64 {
+        std::f64::consts::PI * self.radius * self.radius
+    }
+}
+
+impl Area for Rectangle {
+    fn area(&self) -> f64 {
+        self.width * self.height
+    }
+}
+
+impl Area for Triangle {
+    fn area(&self) -> f64 {
+        0.5 * self.base * self.height
+    }
+}
+
+pub fn unit_circle() -> Circle {
+    Circle { radius: 1.0 }
+}
+
+pub fn unit_square() -> Rectangle {
+    Rectangle {
+        width: 1.0,
+        height: 1.0,
+    }
+}
@@ -0,0 +1,12 @@
+// function notASymbol() is only in a comment
+export class ShapeView {
+  constructor(shape) {
+    this.shape = shape;
+  }
+}
+
+export function renderShapes(shapes) {
+  return shapes.map((s) => new ShapeView(s));
+}
+
+const helper = () => 1;
@@ -0,0 +1,13 @@
+package web
+
+type Server struct {
+	Addr string
+}
+
+func (s *Server) Serve() error {
+	return nil
+}
+
+func NewServer(addr string) *Server {
+	return &Server{Addr: addr}
+}
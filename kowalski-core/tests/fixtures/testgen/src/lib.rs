@@ -0,0 +1,6 @@
+//! Fixture crate for `CodeAgent::generate_tests`.
+
+/// Sum of `a` and `b`, wrapping on overflow.
+pub fn add(a: u32, b: u32) -> u32 {
+    a.wrapping_add(b)
+}
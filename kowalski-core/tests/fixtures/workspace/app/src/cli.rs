@@ -0,0 +1,6 @@
+use demo_core::parser::parse;
+use demo_util::fmt::show;
+
+pub fn run() {
+    println!("{}", show(&parse("demo")));
+}
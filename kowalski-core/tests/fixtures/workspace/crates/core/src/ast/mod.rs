@@ -0,0 +1,7 @@
+pub mod visit;
+
+use crate::parser::Token;
+
+pub struct Node {
+    pub token: Option<Token>,
+}
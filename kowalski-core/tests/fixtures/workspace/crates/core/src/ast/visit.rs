@@ -0,0 +1,5 @@
+use super::Node;
+
+pub trait Visitor {
+    fn visit(&mut self, node: &Node);
+}
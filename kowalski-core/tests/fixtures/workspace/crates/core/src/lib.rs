@@ -0,0 +1,7 @@
+//! A parser and the syntax tree it builds.
+
+pub mod ast;
+pub mod parser;
+mod span;
+
+pub use ast::Node;
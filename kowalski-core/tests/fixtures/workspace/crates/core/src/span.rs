@@ -0,0 +1,15 @@
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+
+    #[test]
+    fn spans_cover_the_source() {
+        let node = parse("abc");
+        assert_eq!(node.token.map(|t| t.span.end), Some(3));
+    }
+}
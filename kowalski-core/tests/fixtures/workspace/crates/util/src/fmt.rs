@@ -0,0 +1,6 @@
+pub fn show(node: &dcore::ast::Node) -> String {
+    match &node.token {
+        Some(token) => format!("{}..{}", token.span.start, token.span.end),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,3 @@
+pub mod fmt;
+
+pub use dcore::Node;
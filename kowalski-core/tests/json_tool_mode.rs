@@ -0,0 +1,118 @@
+//! Integration test: with `chat.json_tool_calls` enabled, a `BaseAgent` that has tools sends
+//! Ollama `format: "json"` and unwraps the `{"answer": ...}` reply; without tools (or with the
+//! option off) requests stay free-form. JSON-mode requests keep the configured sampling settings.
+
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use kowalski_core::agent::{Agent, BaseAgent};
+use kowalski_core::config::{Config, OllamaOptions};
+use kowalski_core::tools::HtmlToMarkdownTool;
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+/// Body of each `/api/chat` request.
+type SeenBodies = Arc<Mutex<Vec<Value>>>;
+
+async fn mock_ollama_chat(State(seen): State<SeenBodies>, Json(body): Json<Value>) -> Json<Value> {
+    let content = if body.get("format") == Some(&json!("json")) {
+        json!({"answer": "structured reply"}).to_string()
+    } else {
+        "free-form reply".to_string()
+    };
+    seen.lock().unwrap().push(body);
+    Json(json!({"message": {"role": "assistant", "content": content}, "done": true}))
+}
+
+/// `format` field of each request (`Value::Null` when absent).
+fn formats(seen: &SeenBodies) -> Vec<Value> {
+    seen.lock()
+        .unwrap()
+        .iter()
+        .map(|body| body.get("format").cloned().unwrap_or(Value::Null))
+        .collect()
+}
+
+async fn agent(json_tool_calls: bool, dir: &tempfile::TempDir) -> (BaseAgent, SeenBodies) {
+    agent_with(json_tool_calls, dir, Config::default()).await
+}
+
+async fn agent_with(
+    json_tool_calls: bool,
+    dir: &tempfile::TempDir,
+    mut config: Config,
+) -> (BaseAgent, SeenBodies) {
+    let seen = SeenBodies::default();
+    let app = Router::new()
+        .route("/api/chat", post(mock_ollama_chat))
+        .with_state(seen.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    config.ollama.host = addr.ip().to_string();
+    config.ollama.port = addr.port();
+    config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+    config.chat.json_tool_calls = json_tool_calls;
+    (<BaseAgent as Agent>::new(config).await.unwrap(), seen)
+}
+
+#[tokio::test]
+async fn json_format_only_when_tools_are_in_play() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut agent, seen) = agent(true, &dir).await;
+    let id = agent.start_conversation("llama3.2");
+
+    let reply = agent.chat_with_history(&id, "hello", None).await.unwrap();
+    assert_eq!(reply, "free-form reply");
+
+    agent.tool_manager.register(HtmlToMarkdownTool::new());
+    let reply = agent
+        .chat_with_history(&id, "hello again", None)
+        .await
+        .unwrap();
+    assert_eq!(reply, "structured reply");
+
+    assert_eq!(formats(&seen), vec![Value::Null, json!("json")]);
+}
+
+#[tokio::test]
+async fn json_format_is_opt_in() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut agent, seen) = agent(false, &dir).await;
+    agent.tool_manager.register(HtmlToMarkdownTool::new());
+    let id = agent.start_conversation("llama3.2");
+
+    let reply = agent.chat_with_history(&id, "hello", None).await.unwrap();
+    assert_eq!(reply, "free-form reply");
+    assert_eq!(formats(&seen), vec![Value::Null]);
+}
+
+#[tokio::test]
+async fn json_mode_keeps_the_configured_sampling_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.chat.temperature = 0.2;
+    config.chat.max_tokens = 256;
+    config.chat.options = Some(OllamaOptions {
+        num_ctx: Some(4096),
+        ..OllamaOptions::default()
+    });
+    let (mut agent, seen) = agent_with(true, &dir, config).await;
+    agent.tool_manager.register(HtmlToMarkdownTool::new());
+    let id = agent.start_conversation("llama3.2");
+
+    let reply = agent.chat_with_history(&id, "hello", None).await.unwrap();
+    assert_eq!(reply, "structured reply");
+    let body = seen.lock().unwrap()[0].clone();
+    assert_eq!(body["format"], "json");
+    let options = &body["options"];
+    assert!(
+        (options["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6,
+        "{body}"
+    );
+    assert_eq!(options["num_predict"], 256);
+    assert_eq!(options["num_ctx"], 4096);
+}
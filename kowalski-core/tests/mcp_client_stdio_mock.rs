@@ -0,0 +1,84 @@
+//! Integration test: MCP over stdio against `tests/fixtures/mock_mcp_stdio.sh`
+//! (tool discovery, env passing, proxy registration, relaunch after the server exits).
+#![cfg(unix)]
+
+use kowalski_core::agent::Agent;
+use kowalski_core::config::{Config, McpServerConfig, McpTransport};
+use kowalski_core::mcp::{McpHub, McpStdioClient};
+use kowalski_core::template::agent::TemplateAgent;
+use serde_json::json;
+use std::collections::HashMap;
+
+fn mock_server() -> McpServerConfig {
+    let script = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/mock_mcp_stdio.sh"
+    );
+    McpServerConfig {
+        name: "mock-stdio".to_string(),
+        url: String::new(),
+        transport: McpTransport::Stdio,
+        headers: HashMap::new(),
+        command: vec!["sh".to_string(), script.to_string()],
+        env: HashMap::from([("MOCK_GREETING".to_string(), "hello from env".to_string())]),
+    }
+}
+
+#[tokio::test]
+async fn stdio_client_lists_calls_and_relaunches() {
+    let client = McpStdioClient::connect(&mock_server())
+        .await
+        .expect("connect");
+
+    let tools = client.list_tools().await.expect("list_tools");
+    let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["echo", "greet", "crash"]);
+    assert_eq!(tools[0].input_schema["required"], json!(["text"]));
+
+    let echoed = client
+        .call_tool("echo", &json!({"text": "ping"}))
+        .await
+        .expect("echo");
+    assert_eq!(echoed.normalized_content(), json!("ping"));
+    let greeting = client.call_tool("greet", &json!({})).await.expect("greet");
+    assert_eq!(greeting.normalized_content(), json!("hello from env"));
+
+    assert!(client.call_tool("crash", &json!({})).await.is_err());
+    assert_eq!(client.restart_count(), 0);
+    let echoed = client
+        .call_tool("echo", &json!({"text": "again"}))
+        .await
+        .expect("echo after relaunch");
+    assert_eq!(echoed.normalized_content(), json!("again"));
+    assert_eq!(client.restart_count(), 1);
+}
+
+#[tokio::test]
+async fn template_agent_registers_stdio_tools_at_startup() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+    config.mcp.servers.push(mock_server());
+
+    let agent = TemplateAgent::new(config).await.expect("agent");
+    let tools = Agent::list_tools(&agent).await;
+    assert!(tools.iter().any(|(name, _)| name == "echo"));
+
+    let hub = McpHub::new(&[mock_server()]).await.unwrap().unwrap();
+    let mut proxies = hub.into_tool_proxies();
+    let echo = proxies.iter_mut().find(|t| t.name() == "echo").unwrap();
+    assert!(
+        echo.parameters()
+            .iter()
+            .any(|p| p.name == "text" && p.required)
+    );
+    let out = echo
+        .execute(kowalski_core::tools::ToolInput::new(
+            "default".to_string(),
+            String::new(),
+            json!({"text": "via proxy"}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(out.result, json!("via proxy"));
+}
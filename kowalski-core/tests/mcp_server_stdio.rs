@@ -0,0 +1,128 @@
+//! Conformance test: the MCP stdio server driven with scripted JSON-RPC lines
+//! (initialize, tools/list, successful call, invalid params, tool error, unknown method).
+
+use async_trait::async_trait;
+use kowalski_core::error::KowalskiError;
+use kowalski_core::mcp::server::serve_mcp_io;
+use kowalski_core::tools::manager::ToolManager;
+use kowalski_core::tools::{ParameterType, Tool, ToolInput, ToolOutput, ToolParameter};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+struct UpperTool;
+
+#[async_trait]
+impl Tool for UpperTool {
+    async fn execute(&mut self, input: ToolInput) -> Result<ToolOutput, KowalskiError> {
+        let text = input.parameters["text"].as_str().unwrap_or_default();
+        if text == "boom" {
+            return Err(KowalskiError::ToolExecution(
+                "refusing to shout boom".into(),
+            ));
+        }
+        Ok(ToolOutput::new(
+            json!({ "upper": text.to_uppercase() }),
+            None,
+        ))
+    }
+
+    fn name(&self) -> &str {
+        "upper"
+    }
+
+    fn description(&self) -> &str {
+        "Uppercases text"
+    }
+
+    fn parameters(&self) -> Vec<ToolParameter> {
+        vec![ToolParameter {
+            name: "text".to_string(),
+            description: "Text to uppercase".to_string(),
+            required: true,
+            default_value: None,
+            parameter_type: ParameterType::String,
+        }]
+    }
+}
+
+/// Writes `requests` one per line, closes the input, and returns every reply line.
+async fn run_script(requests: &[Value]) -> Vec<Value> {
+    let registry = ToolManager::new();
+    registry.register(UpperTool);
+
+    let (mut client_in, server_in) = tokio::io::duplex(64 * 1024);
+    let (server_out, client_out) = tokio::io::duplex(64 * 1024);
+    let server = tokio::spawn(serve_mcp_io(
+        registry,
+        BufReader::new(server_in),
+        server_out,
+    ));
+
+    for request in requests {
+        client_in
+            .write_all(format!("{request}\n").as_bytes())
+            .await
+            .unwrap();
+    }
+    client_in.write_all(b"not json\n").await.unwrap();
+    drop(client_in);
+    server.await.unwrap().unwrap();
+
+    let mut replies = Vec::new();
+    let mut lines = BufReader::new(client_out).lines();
+    while let Some(line) = lines.next_line().await.unwrap() {
+        replies.push(serde_json::from_str(&line).unwrap());
+    }
+    replies
+}
+
+#[tokio::test]
+async fn scripted_session_covers_list_call_and_errors() {
+    let replies = run_script(&[
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-03-26"}}),
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized", "params": {}}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "upper", "arguments": {"text": "hi"}}}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {"name": "upper", "arguments": {}}}),
+        json!({"jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": {"name": "upper", "arguments": {"text": "boom"}}}),
+        json!({"jsonrpc": "2.0", "id": 6, "method": "tools/call", "params": {"name": "missing"}}),
+        json!({"jsonrpc": "2.0", "id": 7, "method": "resources/list"}),
+    ])
+    .await;
+
+    // The notification gets no reply; the trailing garbage line gets a parse error.
+    assert_eq!(replies.len(), 8);
+    assert_eq!(replies[0]["id"], 1);
+    assert_eq!(replies[0]["result"]["serverInfo"]["name"], "kowalski");
+    assert!(replies[0]["result"]["capabilities"]["tools"].is_object());
+
+    let tools = replies[1]["result"]["tools"].as_array().unwrap();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0]["name"], "upper");
+    assert_eq!(tools[0]["inputSchema"]["type"], "object");
+    assert_eq!(
+        tools[0]["inputSchema"]["properties"]["text"]["type"],
+        "string"
+    );
+    assert_eq!(tools[0]["inputSchema"]["required"], json!(["text"]));
+
+    assert_eq!(replies[2]["result"]["isError"], false);
+    assert_eq!(replies[2]["result"]["structuredContent"]["upper"], "HI");
+    assert_eq!(replies[2]["result"]["content"][0]["type"], "text");
+
+    assert_eq!(replies[3]["id"], 4);
+    assert_eq!(replies[3]["error"]["code"], -32602);
+
+    assert_eq!(replies[4]["result"]["isError"], true);
+    assert!(
+        replies[4]["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("refusing to shout boom")
+    );
+
+    assert_eq!(replies[5]["error"]["code"], -32602);
+    assert_eq!(replies[6]["error"]["code"], -32601);
+    assert_eq!(replies[7]["id"], Value::Null);
+    assert_eq!(replies[7]["error"]["code"], -32700);
+}
@@ -0,0 +1,119 @@
+//! Integration test (feature `metrics`): a `chat_with_tools` turn moves the LLM, token, tool,
+//! memory and embedding metrics of the Prometheus recorder.
+
+#![cfg(feature = "metrics")]
+
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use kowalski_core::agent::Agent;
+use kowalski_core::config::Config;
+use kowalski_core::metrics::prometheus_handle;
+use kowalski_core::template::TemplateAgent;
+use kowalski_core::tools::CalculatorTool;
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+/// Asks for the calculator first, then answers; reports token counts like Ollama does.
+async fn mock_ollama_chat(State(calls): State<Arc<Mutex<usize>>>) -> Json<Value> {
+    let mut calls = calls.lock().unwrap();
+    let content = if *calls == 0 {
+        json!({"name": "calculator", "parameters": {"expression": "6 * 7"}}).to_string()
+    } else {
+        "The answer is 42.".to_string()
+    };
+    *calls += 1;
+    Json(json!({
+        "message": {"role": "assistant", "content": content},
+        "done": true,
+        "prompt_eval_count": 120,
+        "eval_count": 15,
+    }))
+}
+
+async fn mock_ollama_embeddings() -> Json<Value> {
+    Json(json!({"embedding": [0.1, 0.2, 0.3]}))
+}
+
+/// Value of the sample line starting with `series` (name and labels).
+fn sample(rendered: &str, series: &str) -> Option<f64> {
+    rendered
+        .lines()
+        .find_map(|line| line.strip_prefix(series)?.trim().parse().ok())
+}
+
+#[tokio::test]
+async fn chat_with_tools_moves_the_counters() {
+    let handle = prometheus_handle().unwrap();
+    assert!(prometheus_handle().is_ok(), "the handle is reused");
+
+    let app = Router::new()
+        .route("/api/chat", post(mock_ollama_chat))
+        .route("/api/embeddings", post(mock_ollama_embeddings))
+        .with_state(Arc::new(Mutex::new(0)));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let memory_dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.ollama.host = addr.ip().to_string();
+    config.ollama.port = addr.port();
+    config.memory.episodic_path = memory_dir.path().to_string_lossy().to_string();
+
+    let mut agent = TemplateAgent::new(config).await.unwrap();
+    agent
+        .register_tool(Box::new(CalculatorTool::new()))
+        .await
+        .unwrap();
+    let id = agent.start_conversation("llama3.2");
+    let answer = agent.chat_with_tools(&id, "what is 6 * 7?").await.unwrap();
+    assert_eq!(answer, "The answer is 42.");
+
+    let rendered = handle.render();
+    assert_eq!(
+        sample(&rendered, "kowalski_llm_requests_total "),
+        Some(2.0),
+        "{rendered}"
+    );
+    assert_eq!(
+        sample(&rendered, "kowalski_llm_latency_seconds_count "),
+        Some(2.0)
+    );
+    assert_eq!(
+        sample(&rendered, "kowalski_tokens_total{kind=\"prompt\"} "),
+        Some(240.0)
+    );
+    assert_eq!(
+        sample(&rendered, "kowalski_tokens_total{kind=\"completion\"} "),
+        Some(30.0)
+    );
+    assert_eq!(
+        sample(
+            &rendered,
+            "kowalski_tool_executions_total{tool=\"calculator\",status=\"ok\"} "
+        ),
+        Some(1.0),
+        "{rendered}"
+    );
+    assert_eq!(
+        sample(
+            &rendered,
+            "kowalski_tool_duration_seconds_count{tool=\"calculator\"} "
+        ),
+        Some(1.0)
+    );
+    for tier in ["working", "episodic", "semantic"] {
+        let series = format!("kowalski_memory_recall_duration_seconds_count{{tier=\"{tier}\"}} ");
+        assert!(
+            sample(&rendered, &series).is_some_and(|n| n >= 1.0),
+            "{rendered}"
+        );
+    }
+    assert!(
+        sample(&rendered, "kowalski_embedding_requests_total ").is_some_and(|n| n >= 1.0),
+        "{rendered}"
+    );
+}
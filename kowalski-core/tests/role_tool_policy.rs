@@ -0,0 +1,86 @@
+//! Integration test: a role denying `fs_tool` keeps it out of the tool schema sent to the model,
+//! and a call the model makes anyway is refused with a policy error fed back as the tool result.
+
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use kowalski_core::agent::Agent;
+use kowalski_core::config::Config;
+use kowalski_core::role::Role;
+use kowalski_core::template::TemplateAgent;
+use kowalski_core::tools::{CalculatorTool, FsTool};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+/// Body of each `/api/chat` request.
+type SeenBodies = Arc<Mutex<Vec<Value>>>;
+
+/// Asks for `fs_tool` first, then answers.
+async fn mock_ollama_chat(State(seen): State<SeenBodies>, Json(body): Json<Value>) -> Json<Value> {
+    let mut seen = seen.lock().unwrap();
+    let content = if seen.is_empty() {
+        json!({"name": "fs_tool", "parameters": {"task": "list_dir", "path": "."}}).to_string()
+    } else {
+        "I cannot read files in this role.".to_string()
+    };
+    seen.push(body);
+    Json(json!({"message": {"role": "assistant", "content": content}, "done": true}))
+}
+
+#[tokio::test]
+async fn denied_tools_are_neither_offered_nor_run() {
+    let seen = SeenBodies::default();
+    let app = Router::new()
+        .route("/api/chat", post(mock_ollama_chat))
+        .with_state(seen.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let memory_dir = tempfile::tempdir().unwrap();
+    let files = tempfile::tempdir().unwrap();
+    std::fs::write(files.path().join("secret.txt"), "launch codes").unwrap();
+    let mut config = Config::default();
+    config.ollama.host = addr.ip().to_string();
+    config.ollama.port = addr.port();
+    config.memory.episodic_path = memory_dir.path().to_string_lossy().to_string();
+
+    let mut agent = TemplateAgent::new(config).await.unwrap();
+    agent
+        .set_role(Role::new("reviewer", "Review, do not touch.").with_denied_tools(["fs_tool"]))
+        .await;
+    agent
+        .register_tool(Box::new(FsTool::new(files.path())))
+        .await
+        .unwrap();
+    agent
+        .register_tool(Box::new(CalculatorTool::new()))
+        .await
+        .unwrap();
+    let names: Vec<String> = agent.list_tools().await.into_iter().map(|t| t.0).collect();
+    assert_eq!(names, ["calculator"]);
+
+    let id = agent.start_conversation("llama3.2");
+    let answer = agent
+        .chat_with_tools(&id, "what is in the folder?")
+        .await
+        .unwrap();
+    assert_eq!(answer, "I cannot read files in this role.");
+
+    let bodies = seen.lock().unwrap().clone();
+    assert_eq!(bodies.len(), 2);
+    let first = bodies[0].to_string();
+    assert!(first.contains("calculator"), "{first}");
+    assert!(!first.contains("fs_tool"), "{first}");
+    let second = bodies[1].to_string();
+    assert!(
+        second.contains("fs_tool is not allowed by the role 'reviewer'"),
+        "{second}"
+    );
+    assert!(
+        bodies.iter().all(|b| !b.to_string().contains("secret.txt")),
+        "fs_tool must not have run"
+    );
+}
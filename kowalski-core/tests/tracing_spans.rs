@@ -0,0 +1,148 @@
+//! Integration test: a `chat_with_tools` run emits `chat_turn` spans (conversation, model and the
+//! token counts Ollama reports), a `tool_execution` span per tool call, and `memory_recall` and
+//! `embedding` spans while memories are looked up.
+
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use kowalski_core::agent::Agent;
+use kowalski_core::config::Config;
+use kowalski_core::template::TemplateAgent;
+use kowalski_core::tools::CalculatorTool;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Subscriber, subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Fields of one span, as text.
+#[derive(Debug, Default, Clone)]
+struct Fields(BTreeMap<String, String>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+/// Closed spans, by name, in the order they closed.
+type Closed = Arc<Mutex<Vec<(String, Fields)>>>;
+
+struct Capture(Closed);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        ctx.span(id).unwrap().extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        if let Some(fields) = span.extensions_mut().get_mut::<Fields>() {
+            values.record(fields);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).unwrap();
+        let fields = span.extensions_mut().remove::<Fields>().unwrap_or_default();
+        self.0
+            .lock()
+            .unwrap()
+            .push((span.name().to_string(), fields));
+    }
+}
+
+/// Asks for the calculator first, then answers; reports token counts like Ollama does.
+async fn mock_ollama_chat(State(calls): State<Arc<Mutex<usize>>>) -> Json<Value> {
+    let mut calls = calls.lock().unwrap();
+    let content = if *calls == 0 {
+        json!({"name": "calculator", "parameters": {"expression": "6 * 7"}}).to_string()
+    } else {
+        "The answer is 42.".to_string()
+    };
+    *calls += 1;
+    Json(json!({
+        "message": {"role": "assistant", "content": content},
+        "done": true,
+        "prompt_eval_count": 120,
+        "eval_count": 15,
+    }))
+}
+
+async fn mock_ollama_embeddings() -> Json<Value> {
+    Json(json!({"embedding": [0.1, 0.2, 0.3]}))
+}
+
+#[tokio::test]
+async fn chat_with_tools_emits_turn_tool_and_memory_spans() {
+    let closed = Closed::default();
+    let _subscriber =
+        subscriber::set_default(tracing_subscriber::registry().with(Capture(closed.clone())));
+
+    let app = Router::new()
+        .route("/api/chat", post(mock_ollama_chat))
+        .route("/api/embeddings", post(mock_ollama_embeddings))
+        .with_state(Arc::new(Mutex::new(0)));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let memory_dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.ollama.host = addr.ip().to_string();
+    config.ollama.port = addr.port();
+    config.memory.episodic_path = memory_dir.path().to_string_lossy().to_string();
+
+    let mut agent = TemplateAgent::new(config).await.unwrap();
+    agent
+        .register_tool(Box::new(CalculatorTool::new()))
+        .await
+        .unwrap();
+    let id = agent.start_conversation("llama3.2");
+    let answer = agent.chat_with_tools(&id, "what is 6 * 7?").await.unwrap();
+    assert_eq!(answer, "The answer is 42.");
+
+    let closed = closed.lock().unwrap().clone();
+    let named = |name: &str| -> Vec<&Fields> {
+        closed
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, f)| f)
+            .collect()
+    };
+
+    let turns = named("chat_turn");
+    assert_eq!(turns.len(), 2, "{closed:?}");
+    for turn in &turns {
+        assert_eq!(turn.0["conversation_id"], id);
+        assert_eq!(turn.0["model"], "llama3.2");
+        assert_eq!(turn.0["prompt_tokens"], "120");
+        assert_eq!(turn.0["completion_tokens"], "15");
+    }
+
+    let tools = named("tool_execution");
+    assert_eq!(tools.len(), 1, "{closed:?}");
+    assert_eq!(tools[0].0["tool"], "calculator");
+    assert_eq!(tools[0].0["status"], "ok");
+    assert!(tools[0].0["duration_ms"].parse::<u64>().is_ok());
+
+    let recalls = named("memory_recall");
+    assert!(!recalls.is_empty(), "{closed:?}");
+    assert!(recalls.iter().all(|r| r.0.contains_key("recalled")));
+    let embeddings = named("embedding");
+    assert!(!embeddings.is_empty(), "{closed:?}");
+    assert_eq!(embeddings[0].0["model"], "nomic-embed-text");
+}
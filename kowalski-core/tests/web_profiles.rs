@@ -0,0 +1,251 @@
+//! Integration test: `web_scrape` with named HTTP profiles against a local server that wants an
+//! API key header on one page and a cookie login on another, and a second server outside the
+//! profiles' hosts that must never see their credentials.
+
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::Redirect;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use kowalski_core::config::{HttpProfile, WebAgentConfig};
+use kowalski_core::tools::{Tool, ToolInput};
+use kowalski_core::web::WebScrapeTool;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const API_KEY_VAR: &str = "KOWALSKI_TEST_WEB_PROFILE_API_KEY";
+const TOKEN_VAR: &str = "KOWALSKI_TEST_WEB_PROFILE_TOKEN";
+
+async fn spawn(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://127.0.0.1:{}", addr.port())
+}
+
+fn cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split("; "))
+        .find_map(|pair| pair.strip_prefix(&format!("{name}=")).map(str::to_string))
+}
+
+async fn reports(headers: HeaderMap) -> Response {
+    match headers.get("x-api-key").and_then(|v| v.to_str().ok()) {
+        Some("s3cret") => "<h1>Quarterly report</h1><p>Revenue is up.</p>".into_response(),
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+async fn login(headers: HeaderMap) -> Response {
+    if cookie(&headers, "tenant").as_deref() != Some("acme") {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    (
+        [(header::SET_COOKIE, "session=abc123; Path=/; HttpOnly")],
+        "<p>Logged in</p>",
+    )
+        .into_response()
+}
+
+async fn account(headers: HeaderMap) -> Response {
+    match cookie(&headers, "session").as_deref() {
+        Some("abc123") => "<h1>Account</h1><p>Plan: research</p>".into_response(),
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+/// `host:port` of a server spawned by [`spawn`].
+fn authority(base: &str) -> String {
+    base.trim_start_matches("http://").to_string()
+}
+
+fn profiles(base: &str) -> WebAgentConfig {
+    let hosts = vec![authority(base)];
+    WebAgentConfig {
+        profiles: vec![
+            HttpProfile {
+                name: "reports".to_string(),
+                hosts: hosts.clone(),
+                headers: HashMap::from([("X-Api-Key".to_string(), format!("env:{API_KEY_VAR}"))]),
+                bearer_token_env: Some(TOKEN_VAR.to_string()),
+                ..HttpProfile::default()
+            },
+            HttpProfile {
+                name: "portal".to_string(),
+                hosts,
+                cookies: HashMap::from([("tenant".to_string(), "acme".to_string())]),
+                ..HttpProfile::default()
+            },
+        ],
+        // The test servers listen on loopback.
+        allow_private_addresses: true,
+    }
+}
+
+fn set_secrets() {
+    // SAFETY: every test sets the same values, and nothing reads the environment concurrently
+    // with a differing write.
+    unsafe {
+        std::env::set_var(API_KEY_VAR, "s3cret");
+        std::env::set_var(TOKEN_VAR, "t0ken");
+    }
+}
+
+type Seen = Arc<Mutex<Vec<String>>>;
+
+/// Records every credential-bearing header it receives.
+async fn capture(State(seen): State<Seen>, headers: HeaderMap) -> &'static str {
+    for name in ["x-api-key", "authorization", "cookie"] {
+        for value in headers.get_all(name) {
+            seen.lock()
+                .unwrap()
+                .push(format!("{name}: {}", value.to_str().unwrap_or_default()));
+        }
+    }
+    "<p>captured</p>"
+}
+
+async fn scrape(
+    tool: &mut WebScrapeTool,
+    params: serde_json::Value,
+) -> Result<String, kowalski_core::error::KowalskiError> {
+    let out = tool.execute(ToolInput::from_parameters(params)).await?;
+    Ok(out.result["markdown"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+#[tokio::test]
+async fn profiles_send_headers_and_keep_session_cookies() {
+    set_secrets();
+    let base = spawn(
+        Router::new()
+            .route("/reports", get(reports))
+            .route("/login", get(login))
+            .route("/account", get(account)),
+    )
+    .await;
+    let mut tool = WebScrapeTool::with_profiles(&profiles(&base)).unwrap();
+
+    let reports = format!("{base}/reports");
+    assert!(scrape(&mut tool, json!({"url": reports})).await.is_err());
+    let page = scrape(&mut tool, json!({"url": reports, "profile": "reports"}))
+        .await
+        .unwrap();
+    assert!(page.contains("Revenue is up."), "{page}");
+
+    // The login response's cookie is kept in the portal profile's jar, and only there.
+    let account = format!("{base}/account");
+    assert!(
+        scrape(&mut tool, json!({"url": account, "profile": "portal"}))
+            .await
+            .is_err()
+    );
+    scrape(
+        &mut tool,
+        json!({"url": format!("{base}/login"), "profile": "portal"}),
+    )
+    .await
+    .unwrap();
+    let page = scrape(&mut tool, json!({"url": account, "profile": "portal"}))
+        .await
+        .unwrap();
+    assert!(page.contains("Plan: research"), "{page}");
+    assert!(
+        scrape(&mut tool, json!({"url": account, "profile": "reports"}))
+            .await
+            .is_err()
+    );
+    assert!(scrape(&mut tool, json!({"url": account})).await.is_err());
+
+    let err = scrape(&mut tool, json!({"url": account, "profile": "admin"}))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("configured: portal, reports"),
+        "{err}"
+    );
+    let profile = tool
+        .parameters()
+        .into_iter()
+        .find(|p| p.name == "profile")
+        .unwrap();
+    assert!(profile.description.ends_with("One of: reports, portal"));
+}
+
+#[tokio::test]
+async fn credentials_stay_on_the_profile_hosts() {
+    set_secrets();
+    let seen = Seen::default();
+    let other = spawn(
+        Router::new()
+            .route("/capture", get(capture))
+            .with_state(seen.clone()),
+    )
+    .await;
+    let target = format!("{other}/capture");
+    let redirect_to = target.clone();
+    let base = spawn(Router::new().route(
+        "/moved",
+        get(move || async move { Redirect::temporary(&redirect_to) }),
+    ))
+    .await;
+    let mut tool = WebScrapeTool::with_profiles(&profiles(&base)).unwrap();
+
+    // A foreign host is fetched under either profile, and gets neither headers nor cookies.
+    for profile in ["reports", "portal"] {
+        let page = scrape(&mut tool, json!({"url": target, "profile": profile}))
+            .await
+            .unwrap();
+        assert!(page.contains("captured"), "{page}");
+        assert!(
+            scrape(
+                &mut tool,
+                json!({"url": format!("{base}/moved"), "profile": profile})
+            )
+            .await
+            .is_err()
+        );
+    }
+    assert!(
+        seen.lock().unwrap().is_empty(),
+        "{:?}",
+        seen.lock().unwrap()
+    );
+
+    // Without a profile the page is fetched, with no credentials.
+    let page = scrape(&mut tool, json!({"url": target})).await.unwrap();
+    assert!(page.contains("captured"), "{page}");
+    assert!(
+        seen.lock().unwrap().is_empty(),
+        "{:?}",
+        seen.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn profiles_without_hosts_are_refused() {
+    let config = WebAgentConfig {
+        profiles: vec![HttpProfile {
+            name: "open".to_string(),
+            ..HttpProfile::default()
+        }],
+        ..WebAgentConfig::default()
+    };
+    let mut tool = WebScrapeTool::with_profiles(&config).unwrap();
+    let err = scrape(
+        &mut tool,
+        json!({"url": "http://127.0.0.1:9/", "profile": "open"}),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("needs `hosts`"), "{err}");
+}
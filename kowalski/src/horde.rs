@@ -805,6 +805,7 @@ async fn handle_envelope(manager: &HordeManager, env: AclEnvelope) {
             from_agent,
             outcome,
             success,
+            ..
         } => {
             // Backward-compat path: legacy `kc.run` workers report only TaskResult. Synthesize
             // a TaskFinished if the task_id matches the canonical horde encoding.
@@ -83,7 +83,19 @@ pub async fn serve(
     let model = full_config.ollama.model.clone();
 
     let federation_broker = Arc::new(MpscBroker::new());
-    let federation_registry = Arc::new(AgentRegistry::new());
+    let federation_registry = Arc::new(
+        AgentRegistry::from_config(&full_config.federation)
+            .await
+            .map_err(|e| format!("federation registry: {e}"))?,
+    );
+    match federation_registry.recover().await {
+        Ok(recovered) if !recovered.pending.is_empty() => log::info!(
+            "federation: {} interrupted task(s) left queued after restart",
+            recovered.pending.len()
+        ),
+        Ok(_) => {}
+        Err(e) => log::warn!("federation registry recover: {}", e),
+    }
     #[cfg(feature = "postgres")]
     if let Some(ref url) = full_config.memory.database_url
         && kowalski_core::config::memory_uses_postgres(&full_config.memory)
@@ -91,10 +103,8 @@ pub async fn serve(
     {
         log::warn!("federation registry DB load: {}", e);
     }
-    let template_agent = AgentRecord {
-        id: "template".into(),
-        capabilities: vec!["chat".into(), "mcp".into(), "llm".into()],
-    };
+    let template_agent =
+        AgentRecord::new("template", vec!["chat".into(), "mcp".into(), "llm".into()]);
     federation_registry
         .register(template_agent.clone())
         .map_err(|e| format!("federation registry: {e}"))?;
@@ -267,6 +277,8 @@ pub async fn serve(
                 .on_response(DefaultOnResponse::new()),
         )
         .layer(CorsLayer::permissive());
+    #[cfg(feature = "metrics")]
+    let app = app.merge(kowalski::server::metrics_router(kowalski_core::metrics::prometheus_handle()?));
 
     if let Some((cert, key)) = tls {
         let rustls_config = axum_server::tls_rustls::RustlsConfig::from_pem_file(cert, key).await?;
@@ -364,11 +376,8 @@ async fn post_mcp_ping(
 async fn get_memory_status(
     State(state): State<ApiState>,
 ) -> Result<Json<MemoryStatus>, (StatusCode, String)> {
-    let llm_provider: Arc<dyn kowalski_core::llm::LLMProvider> =
-        Arc::new(kowalski_core::llm::OllamaProvider::new(
-            &state.full_config.ollama.host,
-            state.full_config.ollama.port,
-        ));
+    let llm_provider = kowalski_core::llm::create_llm_provider(&state.full_config)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     let episodic = kowalski_core::memory::episodic::EpisodicBuffer::open(
         &state.full_config.memory,
         llm_provider,
@@ -453,6 +462,9 @@ struct ChatBody {
     /// When true, `POST /api/chat/stream` runs the tool loop and streams **only** the first LLM turn after a tool result (final answer); earlier turns are non-streamed like `POST /api/chat`.
     #[serde(default)]
     tools_stream: bool,
+    /// When true (with `use_tools`), `POST /api/chat` stops at the model's first tool-call reply and returns the planned calls in `planned_tool_calls` without executing them. `POST /api/chat/stream` with `tools_stream` stops there too and returns that reply as the `assistant` event.
+    #[serde(default)]
+    dry_run: bool,
 }
 
 fn default_true() -> bool {
@@ -467,6 +479,8 @@ struct ChatResponse {
     memory_used: bool,
     memory_source: String,
     memory_items_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    planned_tool_calls: Vec<kowalski_core::tools::ToolCall>,
 }
 
 #[derive(Serialize)]
@@ -577,6 +591,14 @@ async fn post_chat_reset(
     State(state): State<ApiState>,
 ) -> Result<Json<ChatResetResponse>, (StatusCode, String)> {
     let mut guard = state.chat.lock().await;
+    let previous = guard.conv_id.clone();
+    if let Err(e) = guard.agent.close_conversation(&previous).await {
+        log::warn!(
+            "HTTP chat: could not close conversation {}: {}",
+            previous,
+            e
+        );
+    }
     let conversation_id = guard.agent.start_conversation(&state.model);
     guard.conv_id = conversation_id.clone();
     log::info!("HTTP chat: new conversation {}", conversation_id);
@@ -652,7 +674,23 @@ async fn post_chat(
         body.use_memory,
         conv_id
     );
-    let reply = if body.use_tools {
+    let mut planned_tool_calls = Vec::new();
+    let reply = if body.use_tools && body.dry_run {
+        let options = kowalski_core::agent::tool_loop::ToolLoopOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let outcome = kowalski_core::agent::tool_loop::run_tool_loop_with_options(
+            &mut guard.agent,
+            &conv_id,
+            body.message.trim(),
+            &options,
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        planned_tool_calls = outcome.planned_calls;
+        outcome.answer
+    } else if body.use_tools {
         guard
             .agent
             .chat_with_tools_with_options(&conv_id, body.message.trim(), body.use_memory)
@@ -673,6 +711,7 @@ async fn post_chat(
         memory_used: memory_debug.memory_used,
         memory_source: memory_debug.memory_source,
         memory_items_count: memory_debug.memory_items_count,
+        planned_tool_calls,
     }))
 }
 
@@ -707,6 +746,7 @@ async fn post_chat_stream(
     let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(256);
     let msg = body.message.trim().to_string();
     let tools_stream = body.tools_stream;
+    let dry_run = body.dry_run;
     let use_memory = body.use_memory;
     let requested_conv_id = body.conversation_id.clone();
     let api = state.clone();
@@ -774,12 +814,15 @@ async fn post_chat_stream(
             });
             let outcome = {
                 let mut guard = api.chat.lock().await;
-                guard
+                guard.agent.base_mut().dry_run = dry_run;
+                let outcome = guard
                     .agent
                     .chat_with_tools_stream_final_with_options(
                         &conv_id, &msg, &token_tx, use_memory,
                     )
-                    .await
+                    .await;
+                guard.agent.base_mut().dry_run = false;
+                outcome
             };
             drop(token_tx);
             let _ = forward.await;
@@ -803,14 +846,15 @@ async fn post_chat_stream(
             return;
         }
 
-        let prep = {
+        let (prep, hold_back) = {
             let mut guard = api.chat.lock().await;
-            guard
+            let prep = guard
                 .agent
                 .prepare_stream_turn_with_options(&conv_id, &msg, use_memory)
-                .await
+                .await;
+            (prep, guard.agent.base().has_middleware())
         };
-        let (model, messages, llm) = match prep {
+        let (model, messages, llm, options) = match prep {
             Ok(x) => x,
             Err(e) => {
                 let payload = json!({ "type": "error", "message": e.to_string() });
@@ -824,12 +868,15 @@ async fn post_chat_stream(
             }
         };
         let mut full = String::new();
-        let mut stream = llm.chat_stream(&model, messages);
+        let mut stream = llm.chat_stream_with_options(&model, messages, &options);
         while let Some(item) = stream.next().await {
             match item {
                 Ok(delta) => {
                     if !delta.is_empty() {
                         full.push_str(&delta);
+                        if hold_back {
+                            continue;
+                        }
                         let payload = json!({ "type": "token", "content": delta });
                         if tx
                             .send(Ok(Event::default().data(payload.to_string())))
@@ -852,9 +899,17 @@ async fn post_chat_stream(
                 }
             }
         }
-        {
+        let full = {
             let mut guard = api.chat.lock().await;
+            let full = guard.agent.base().finish_streamed_reply(full).await;
             guard.agent.add_message(&conv_id, "assistant", &full).await;
+            full
+        };
+        if hold_back && !full.is_empty() {
+            let payload = json!({ "type": "token", "content": full });
+            let _ = tx
+                .send(Ok(Event::default().data(payload.to_string())))
+                .await;
         }
         let summary = json!({ "type": "assistant", "content": full });
         let _ = tx
@@ -1204,6 +1259,8 @@ async fn post_federation_heartbeat(
     if id.is_empty() {
         return Err((StatusCode::BAD_REQUEST, "agent_id required".into()));
     }
+    // In-memory liveness (unknown ids are fine: the worker may register later).
+    let _ = state.federation.registry.heartbeat(id);
     #[cfg(feature = "postgres")]
     {
         if let Some(ref url) = state.full_config.memory.database_url
@@ -1421,6 +1478,8 @@ async fn post_federation_publish(
 struct FederationRegisterBody {
     id: String,
     capabilities: Vec<String>,
+    #[serde(default)]
+    tools: Vec<String>,
 }
 
 async fn post_federation_register(
@@ -1431,10 +1490,7 @@ async fn post_federation_register(
     if id.is_empty() {
         return Err((StatusCode::BAD_REQUEST, "id required".into()));
     }
-    let record = AgentRecord {
-        id: id.to_string(),
-        capabilities: body.capabilities,
-    };
+    let record = AgentRecord::new(id, body.capabilities).with_tools(body.tools);
     state
         .federation
         .registry
@@ -27,10 +27,8 @@ pub fn load_kowalski_config_for_serve(path: &Path) -> Result<Config, Box<dyn std
             "No config at {} — using defaults (Ollama localhost; add config.toml for MCP/tools)",
             path.display()
         );
-        return Ok(Config::default());
     }
-    let raw = fs::read_to_string(path)?;
-    Ok(toml::from_str(&raw)?)
+    Ok(Config::load(path)?)
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -67,7 +65,8 @@ pub struct DoctorOperatorJson {
 }
 
 pub fn config_divergence_lines(c: &Config) -> Vec<String> {
-    let d = Config::default();
+    let mut d = Config::default();
+    d.resolve_data_paths();
     let mut v = Vec::new();
     if c.ollama.model != d.ollama.model {
         v.push("ollama.model".into());
@@ -78,6 +77,9 @@ pub fn config_divergence_lines(c: &Config) -> Vec<String> {
     if c.memory.database_url.is_some() {
         v.push("memory.database_url set".into());
     }
+    if c.data_dir != d.data_dir {
+        v.push("data_dir".into());
+    }
     if c.memory.episodic_path != d.memory.episodic_path {
         v.push("memory.episodic_path".into());
     }
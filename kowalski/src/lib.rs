@@ -10,7 +10,11 @@
 //! ## Optional features
 //! - **`cli`**: `kowalski-cli` as `kowalski::cli`
 //! - **`postgres`**: Postgres / pgvector paths in `kowalski-core`
-//! - **`full`**: `cli` + `postgres`
+//! - **`server`** (default): `kowalski::server::serve` — any [`Agent`] as an HTTP API with SSE streaming
+//! - **`otel`**: export tracing spans over OTLP (`[observability]` in `config.toml`)
+//! - **`metrics`**: Prometheus metrics (`kowalski_core::metrics`) and `kowalski::server::metrics_router`
+//! - **`testing`**: `kowalski_core::testing::MockBackend`, a scripted LLM for offline agent tests
+//! - **`full`**: `cli` + `postgres` + `otel` + `metrics` + `server`
 //!
 //! ## Usage
 //!
@@ -44,11 +48,14 @@ pub use kowalski_core as core;
 #[cfg(feature = "cli")]
 pub use kowalski_cli as cli;
 
+#[cfg(feature = "server")]
+pub mod server;
+
 // Convenience re-exports for common types
 pub use crate::core::{
     agent::{Agent, BaseAgent},
     config::Config,
-    conversation::{Conversation, Message},
+    conversation::{Conversation, Message, MessageRole},
     memory::episodic::EpisodicBuffer,
     memory::semantic::SemanticStore,
     memory::{MemoryProvider, MemoryUnit, working::WorkingMemory},
@@ -31,8 +31,12 @@ struct Cli {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let cli = Cli::parse();
+    let observability =
+        kowalski_core::config::Config::load(&http_ops::mcp_config_path(cli.config.as_deref()))
+            .map(|config| config.observability)
+            .unwrap_or_default();
+    let _telemetry = kowalski_core::logging::init_with_config(&observability, "info")?;
 
     let addr: std::net::SocketAddr = cli
         .bind
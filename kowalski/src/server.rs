@@ -0,0 +1,691 @@
+//! Serve any [`Agent`] as a small JSON + SSE HTTP API (feature **`server`**).
+//!
+//! `Agent` methods take `&mut self`, so the agent is owned by a single actor task and HTTP
+//! handlers talk to it over an `mpsc` channel ([`AgentHandle`]). Requests are processed one at a
+//! time, in arrival order.
+//!
+//! Routes:
+//! - `POST /conversations` — start a conversation (`{"model": "..."}` optional) → `{"id": "..."}`
+//! - `POST /conversations/{id}/messages` — `{"content": "..."}`; replies with SSE events
+//!   `tool_call`, `tool_result`, `chunk`, then `done` (or `error`)
+//! - `GET /conversations/{id}` — the stored [`Conversation`]
+//! - `GET /tools` — registered tools (`name`, `description`)
+//! - `GET /healthz` — liveness (never requires auth)
+//!
+//! With the **`metrics`** feature, [`metrics_router`] adds `GET /metrics` (Prometheus text).
+
+use axum::extract::{Path, Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::Stream;
+use futures::StreamExt;
+use kowalski_core::agent::Agent;
+use kowalski_core::agent::tool_loop::{ToolLoopEvent, ToolLoopOptions, run_tool_loop_streaming};
+use kowalski_core::config::Config;
+use kowalski_core::conversation::Conversation;
+use kowalski_core::error::KowalskiError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Options for [`router`] / [`serve_with_options`].
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// When set, every route except `/healthz` requires `Authorization: Bearer <token>`.
+    pub bearer_token: Option<String>,
+    /// Model for `POST /conversations` when the body does not name one.
+    pub default_model: String,
+}
+
+impl Default for ServerOptions {
+    /// No auth; conversations default to the model of [`Config::default`].
+    fn default() -> Self {
+        Self {
+            bearer_token: None,
+            default_model: Config::default().ollama.model,
+        }
+    }
+}
+
+/// One SSE event emitted while the agent handles a message.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    Chunk {
+        content: String,
+    },
+    ToolCall {
+        name: String,
+        parameters: serde_json::Value,
+    },
+    ToolResult {
+        name: String,
+        result: String,
+    },
+    Done {
+        content: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl From<ToolLoopEvent> for ServerEvent {
+    fn from(event: ToolLoopEvent) -> Self {
+        match event {
+            ToolLoopEvent::Token(content) => ServerEvent::Chunk { content },
+            ToolLoopEvent::ToolCall { name, parameters } => {
+                ServerEvent::ToolCall { name, parameters }
+            }
+            ToolLoopEvent::ToolResult { name, result, .. } => {
+                ServerEvent::ToolResult { name, result }
+            }
+        }
+    }
+}
+
+impl ServerEvent {
+    fn event_name(&self) -> &'static str {
+        match self {
+            ServerEvent::Chunk { .. } => "chunk",
+            ServerEvent::ToolCall { .. } => "tool_call",
+            ServerEvent::ToolResult { .. } => "tool_result",
+            ServerEvent::Done { .. } => "done",
+            ServerEvent::Error { .. } => "error",
+        }
+    }
+}
+
+enum AgentRequest {
+    StartConversation {
+        model: String,
+        reply: oneshot::Sender<String>,
+    },
+    GetConversation {
+        id: String,
+        reply: oneshot::Sender<Option<Conversation>>,
+    },
+    ListTools {
+        reply: oneshot::Sender<Vec<(String, String)>>,
+    },
+    SendMessage {
+        id: String,
+        content: String,
+        events: mpsc::Sender<ServerEvent>,
+    },
+}
+
+/// Cloneable handle to the actor task that owns the agent.
+#[derive(Clone)]
+pub struct AgentHandle {
+    tx: mpsc::Sender<AgentRequest>,
+}
+
+impl AgentHandle {
+    /// Moves `agent` into a background task. The task ends when every handle is dropped.
+    pub fn spawn<A: Agent + 'static>(agent: A) -> Self {
+        let (tx, mut rx) = mpsc::channel::<AgentRequest>(64);
+        tokio::spawn(async move {
+            let mut agent = agent;
+            while let Some(request) = rx.recv().await {
+                handle_request(&mut agent, request).await;
+            }
+        });
+        Self { tx }
+    }
+
+    async fn call<T>(
+        &self,
+        build: impl FnOnce(oneshot::Sender<T>) -> AgentRequest,
+    ) -> Result<T, KowalskiError> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(build(reply))
+            .await
+            .map_err(|_| KowalskiError::Server("agent actor stopped".to_string()))?;
+        rx.await
+            .map_err(|_| KowalskiError::Server("agent actor dropped the request".to_string()))
+    }
+
+    pub async fn start_conversation(&self, model: &str) -> Result<String, KowalskiError> {
+        let model = model.to_string();
+        self.call(|reply| AgentRequest::StartConversation { model, reply })
+            .await
+    }
+
+    pub async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>, KowalskiError> {
+        let id = id.to_string();
+        self.call(|reply| AgentRequest::GetConversation { id, reply })
+            .await
+    }
+
+    pub async fn list_tools(&self) -> Result<Vec<(String, String)>, KowalskiError> {
+        self.call(|reply| AgentRequest::ListTools { reply }).await
+    }
+
+    /// Queues a user message; events arrive on the returned receiver, ending with `done` or `error`.
+    pub async fn send_message(
+        &self,
+        id: &str,
+        content: &str,
+    ) -> Result<mpsc::Receiver<ServerEvent>, KowalskiError> {
+        let (events, rx) = mpsc::channel(64);
+        self.tx
+            .send(AgentRequest::SendMessage {
+                id: id.to_string(),
+                content: content.to_string(),
+                events,
+            })
+            .await
+            .map_err(|_| KowalskiError::Server("agent actor stopped".to_string()))?;
+        Ok(rx)
+    }
+}
+
+async fn handle_request<A: Agent>(agent: &mut A, request: AgentRequest) {
+    match request {
+        AgentRequest::StartConversation { model, reply } => {
+            let _ = reply.send(agent.start_conversation(&model));
+        }
+        AgentRequest::GetConversation { id, reply } => {
+            let _ = reply.send(agent.get_conversation(&id).cloned());
+        }
+        AgentRequest::ListTools { reply } => {
+            let _ = reply.send(agent.list_tools().await);
+        }
+        AgentRequest::SendMessage {
+            id,
+            content,
+            events,
+        } => {
+            let last = match run_turn(agent, &id, &content, &events).await {
+                Ok(answer) => ServerEvent::Done { content: answer },
+                Err(e) => ServerEvent::Error {
+                    message: e.to_string(),
+                },
+            };
+            let _ = events.send(last).await;
+        }
+    }
+}
+
+/// One turn on the shared tool loop ([`run_tool_loop_streaming`]), its progress forwarded as
+/// events. The answer's tokens arrive as `chunk` events while the model writes it.
+async fn run_turn<A: Agent>(
+    agent: &mut A,
+    conversation_id: &str,
+    user_input: &str,
+    events: &mpsc::Sender<ServerEvent>,
+) -> Result<String, KowalskiError> {
+    if agent.get_conversation(conversation_id).is_none() {
+        return Err(KowalskiError::ConversationNotFound(
+            conversation_id.to_string(),
+        ));
+    }
+    let (tx, mut rx) = mpsc::channel(64);
+    let turn = async move {
+        run_tool_loop_streaming(
+            agent,
+            conversation_id,
+            user_input,
+            &ToolLoopOptions::default(),
+            &tx,
+        )
+        .await
+    };
+    let forward = async {
+        while let Some(event) = rx.recv().await {
+            let _ = events.send(ServerEvent::from(event)).await;
+        }
+    };
+    let (outcome, ()) = tokio::join!(turn, forward);
+    Ok(outcome?.answer)
+}
+
+#[derive(Clone)]
+struct ServerState {
+    agent: AgentHandle,
+    options: Arc<ServerOptions>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StartConversationBody {
+    model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendMessageBody {
+    content: String,
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+async fn require_bearer(
+    State(state): State<ServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(expected) = state.options.bearer_token.as_deref() {
+        let provided = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if provided != Some(expected) {
+            return error_response(StatusCode::UNAUTHORIZED, "missing or invalid bearer token");
+        }
+    }
+    next.run(request).await
+}
+
+async fn healthz() -> Json<serde_json::Value> {
+    Json(json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }))
+}
+
+async fn post_conversation(
+    State(state): State<ServerState>,
+    body: Option<Json<StartConversationBody>>,
+) -> Response {
+    let model = body
+        .and_then(|Json(b)| b.model)
+        .unwrap_or_else(|| state.options.default_model.clone());
+    match state.agent.start_conversation(&model).await {
+        Ok(id) => (
+            StatusCode::CREATED,
+            Json(json!({ "id": id, "model": model })),
+        )
+            .into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn get_conversation(State(state): State<ServerState>, Path(id): Path<String>) -> Response {
+    match state.agent.get_conversation(&id).await {
+        Ok(Some(conversation)) => Json(conversation).into_response(),
+        Ok(None) => error_response(
+            StatusCode::NOT_FOUND,
+            format!("conversation {id} not found"),
+        ),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn post_message(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    Json(body): Json<SendMessageBody>,
+) -> Response {
+    match state.agent.get_conversation(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return error_response(
+                StatusCode::NOT_FOUND,
+                format!("conversation {id} not found"),
+            );
+        }
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+    match state.agent.send_message(&id, &body.content).await {
+        Ok(rx) => sse_events(rx).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+fn sse_events(
+    rx: mpsc::Receiver<ServerEvent>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = ReceiverStream::new(rx).map(|ev| {
+        let data = serde_json::to_string(&ev).unwrap_or_else(|_| "{}".to_string());
+        Ok(Event::default().event(ev.event_name()).data(data))
+    });
+    Sse::new(stream)
+}
+
+async fn get_tools(State(state): State<ServerState>) -> Response {
+    match state.agent.list_tools().await {
+        Ok(tools) => Json(
+            tools
+                .into_iter()
+                .map(|(name, description)| json!({ "name": name, "description": description }))
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Builds the router for an already-spawned agent actor.
+pub fn router(agent: AgentHandle, options: ServerOptions) -> Router {
+    let state = ServerState {
+        agent,
+        options: Arc::new(options),
+    };
+    let protected = Router::new()
+        .route("/conversations", post(post_conversation))
+        .route("/conversations/{id}", get(get_conversation))
+        .route("/conversations/{id}/messages", post(post_message))
+        .route("/tools", get(get_tools))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_bearer,
+        ));
+    Router::new()
+        .route("/healthz", get(healthz))
+        .merge(protected)
+        .with_state(state)
+}
+
+/// `GET /metrics`: the Prometheus text of `handle` (see
+/// [`prometheus_handle`](kowalski_core::metrics::prometheus_handle)). Merge it into [`router`];
+/// like `/healthz` it does not require auth.
+#[cfg(feature = "metrics")]
+pub fn metrics_router(handle: kowalski_core::metrics::PrometheusHandle) -> Router {
+    Router::new().route(
+        "/metrics",
+        get(move || {
+            let body = handle.render();
+            async move { ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body) }
+        }),
+    )
+}
+
+/// Serves `agent` on `listener` until `shutdown` resolves, then drains in-flight requests.
+pub async fn serve_with_options<A, F>(
+    agent: A,
+    listener: tokio::net::TcpListener,
+    options: ServerOptions,
+    shutdown: F,
+) -> Result<(), KowalskiError>
+where
+    A: Agent + 'static,
+    F: Future<Output = ()> + Send + 'static,
+{
+    let app = router(AgentHandle::spawn(agent), options);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(|e| KowalskiError::Server(format!("agent server: {e}")))
+}
+
+/// Serves `agent` on `addr` without auth until Ctrl-C. Conversations default to the agent's own
+/// model ([`Agent::capabilities`]).
+pub async fn serve<A: Agent + 'static>(agent: A, addr: SocketAddr) -> Result<(), KowalskiError> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    log::info!("Serving agent '{}' at http://{}", agent.name(), addr);
+    let mut options = ServerOptions::default();
+    let model = agent.capabilities().await.default_model;
+    if !model.is_empty() {
+        options.default_model = model;
+    }
+    serve_with_options(agent, listener, options, async {
+        let _ = tokio::signal::ctrl_c().await;
+        kowalski_core::llm::shutdown_token().cancel();
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kowalski_core::template::agent::TemplateAgent;
+    use kowalski_core::tools::HtmlToMarkdownTool;
+    use serde_json::Value;
+
+    /// Mock Ollama `/api/chat`: asks for a tool first, then answers from the tool result.
+    /// Streamed requests get the reply eight characters per NDJSON line.
+    async fn mock_ollama_chat(Json(body): Json<Value>) -> Response {
+        let last = body["messages"]
+            .as_array()
+            .and_then(|m| m.last())
+            .and_then(|m| m["content"].as_str())
+            .unwrap_or_default()
+            .to_string();
+        let content = if last.starts_with("Based on the tool result") {
+            "The page title is Hello.".to_string()
+        } else {
+            json!({"name": "html_to_markdown", "parameters": {"html": "<h1>Hello</h1>"}})
+                .to_string()
+        };
+        if body["stream"] == true {
+            let chars: Vec<char> = content.chars().collect();
+            let mut lines = String::new();
+            for chunk in chars.chunks(8) {
+                let piece: String = chunk.iter().collect();
+                let line =
+                    json!({"message": {"role": "assistant", "content": piece}, "done": false});
+                lines.push_str(&format!("{line}\n"));
+            }
+            lines.push_str(
+                "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n",
+            );
+            return lines.into_response();
+        }
+        Json(json!({"message": {"role": "assistant", "content": content}, "done": true}))
+            .into_response()
+    }
+
+    async fn spawn_mock_ollama() -> SocketAddr {
+        let app = Router::new().route("/api/chat", post(mock_ollama_chat));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        addr
+    }
+
+    /// A [`TemplateAgent`] with `html_to_markdown`, talking to the mock Ollama.
+    async fn agent(episodic: &std::path::Path) -> TemplateAgent {
+        let ollama = spawn_mock_ollama().await;
+        let mut config = Config::default();
+        config.ollama.host = ollama.ip().to_string();
+        config.ollama.port = ollama.port();
+        config.memory.episodic_path = episodic.to_string_lossy().to_string();
+        let mut agent = TemplateAgent::new(config).await.unwrap();
+        agent
+            .register_tool(Box::new(HtmlToMarkdownTool::new()))
+            .await
+            .unwrap();
+        agent
+    }
+
+    /// SSE event names, in order.
+    fn event_names(sse: &str) -> Vec<&str> {
+        sse.lines()
+            .filter_map(|l| l.strip_prefix("event: "))
+            .collect()
+    }
+
+    #[test]
+    fn default_options_name_the_configured_model() {
+        let options = ServerOptions::default();
+        assert!(options.bearer_token.is_none());
+        assert_eq!(options.default_model, Config::default().ollama.model);
+        assert!(!options.default_model.is_empty());
+    }
+
+    #[tokio::test]
+    async fn streamed_chat_with_tool_events_and_auth() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = agent(dir.path()).await;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (stop_tx, stop_rx) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve_with_options(
+            agent,
+            listener,
+            ServerOptions {
+                bearer_token: Some("secret".to_string()),
+                default_model: "llama3.2".to_string(),
+            },
+            async {
+                let _ = stop_rx.await;
+            },
+        ));
+
+        let http = reqwest::Client::new();
+        let health = http.get(format!("{base}/healthz")).send().await.unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+        let denied = http
+            .post(format!("{base}/conversations"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(denied.status(), StatusCode::UNAUTHORIZED);
+
+        let created: Value = http
+            .post(format!("{base}/conversations"))
+            .bearer_auth("secret")
+            .json(&json!({}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+
+        let tools: Value = http
+            .get(format!("{base}/tools"))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(
+            tools
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|t| t["name"] == "html_to_markdown")
+        );
+
+        let sse = http
+            .post(format!("{base}/conversations/{id}/messages"))
+            .bearer_auth("secret")
+            .json(&json!({"content": "What is the page title?"}))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(
+            event_names(&sse),
+            vec![
+                "tool_call",
+                "tool_result",
+                "chunk",
+                "chunk",
+                "chunk",
+                "done"
+            ]
+        );
+        let streamed: String = sse
+            .lines()
+            .filter_map(|l| l.strip_prefix("data: "))
+            .filter_map(|data| serde_json::from_str::<ServerEvent>(data).ok())
+            .filter_map(|event| match event {
+                ServerEvent::Chunk { content } => Some(content),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(streamed, "The page title is Hello.");
+
+        let conversation: Value = http
+            .get(format!("{base}/conversations/{id}"))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let last = conversation["messages"].as_array().unwrap().last().unwrap();
+        assert_eq!(last["role"], "assistant");
+        assert_eq!(last["content"], "The page title is Hello.");
+
+        let missing = http
+            .get(format!("{base}/conversations/nope"))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        stop_tx.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn dry_run_agents_answer_with_the_plan_without_running_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut agent = agent(dir.path()).await;
+        agent.base_mut().dry_run = true;
+        let handle = AgentHandle::spawn(agent);
+        let id = handle.start_conversation("llama3.2").await.unwrap();
+
+        let mut rx = handle
+            .send_message(&id, "What is the page title?")
+            .await
+            .unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        let [ServerEvent::Done { content }] = events.as_slice() else {
+            panic!("expected only a done event, got {events:?}");
+        };
+        let planned = kowalski_core::utils::json::extract_tool_calls(content);
+        assert_eq!(planned[0].name, "html_to_markdown");
+
+        let conversation = handle.get_conversation(&id).await.unwrap().unwrap();
+        assert!(conversation.messages.iter().all(|m| m.role != "tool"));
+        assert_eq!(conversation.messages.last().unwrap().content, *content);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn metrics_route_renders_recorded_requests() {
+        let handle = kowalski_core::metrics::prometheus_handle().unwrap();
+        let ollama = spawn_mock_ollama().await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.ollama.host = ollama.ip().to_string();
+        config.ollama.port = ollama.port();
+        config.memory.episodic_path = dir.path().to_string_lossy().to_string();
+        let mut agent = TemplateAgent::new(config).await.unwrap();
+        let id = agent.start_conversation("llama3.2");
+        agent.chat_with_history(&id, "hello", None).await.unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, metrics_router(handle)).await.unwrap();
+        });
+        let response = reqwest::get(format!("{base}/metrics")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/plain")
+        );
+        let body = response.text().await.unwrap();
+        assert!(body.contains("kowalski_llm_requests_total"), "{body}");
+    }
+}
@@ -0,0 +1,10 @@
+-- Explicit key-value memory (`MemoryTool` / `KeyValueStore`), scoped by namespace.
+-- Same logical model as SQLite `agent_kv`.
+
+CREATE TABLE IF NOT EXISTS agent_kv (
+    namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    PRIMARY KEY (namespace, key)
+);
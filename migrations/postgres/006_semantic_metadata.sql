@@ -0,0 +1,3 @@
+-- `MemoryUnit.metadata` (e.g. `kind`, `conversation_id` of session summaries) as a JSON object.
+
+ALTER TABLE semantic_memory ADD COLUMN IF NOT EXISTS metadata TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,10 @@
+-- Explicit key-value memory (`MemoryTool` / `KeyValueStore`), scoped by namespace.
+-- Lives in the episodic SQLite file; kept separate from `episodic_kv` embeddings.
+
+CREATE TABLE IF NOT EXISTS agent_kv (
+    namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (namespace, key)
+);
@@ -0,0 +1,14 @@
+-- Federation registry and task state (`RegistryStore`), reloaded by `AgentRegistry::recover`.
+-- Lives in its own SQLite file under `federation.persistence_path`.
+
+CREATE TABLE IF NOT EXISTS federation_agents (
+    agent_id TEXT PRIMARY KEY NOT NULL,
+    record TEXT NOT NULL,
+    last_seen INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS federation_tasks (
+    task_id TEXT PRIMARY KEY NOT NULL,
+    payload TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);